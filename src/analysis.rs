@@ -4,13 +4,15 @@
 
 use std::collections::{HashMap, HashSet};
 use crate::function::Function;
-use crate::cfg::{CFG, Loop};
+use crate::cfg::{reverse_postorder_from, CFG, Loop};
+use crate::instruction::{Instruction, Opcode};
 use crate::passes::{Pass, AnalysisPass, PassResult};
+use crate::types::Type;
+use crate::value::{Value, ValueKind};
 
 /// Dominator tree analysis
-#[allow(dead_code)]
 pub struct DominatorTree {
-    /// Maps block index to its immediate dominator
+    /// Maps block index to its immediate dominator; `None` for unreachable blocks
     idoms: Vec<Option<usize>>,
     /// Maps block index to blocks it dominates
     dominated: Vec<HashSet<usize>>,
@@ -21,53 +23,22 @@ pub struct DominatorTree {
 impl DominatorTree {
     /// Compute dominator tree for a function
     pub fn new(function: &Function) -> Self {
-        let cfg = CFG::from_function(function);
+        Self::from_cfg(CFG::from_function(function))
+    }
+
+    /// Compute the dominator tree of a CFG
+    pub fn from_cfg(cfg: CFG) -> Self {
         let num_blocks = cfg.num_blocks();
 
         let mut idoms = vec![None; num_blocks];
-        let mut dominated = vec![HashSet::new(); num_blocks];
-
-        // Entry block dominates itself
         if num_blocks > 0 {
-            idoms[0] = Some(0);
-        }
-
-        // Compute dominators using iterative algorithm
-        let mut changed = true;
-        while changed {
-            changed = false;
-            for block in 1..num_blocks {
-                let preds = cfg.predecessors(block);
-                if preds.is_empty() {
-                    continue;
-                }
-
-                // Find first processed predecessor
-                let mut new_idom = None;
-                for &pred in preds {
-                    if idoms[pred].is_some() {
-                        new_idom = Some(pred);
-                        break;
-                    }
-                }
-
-                // Intersect with all other predecessors
-                if let Some(mut idom) = new_idom {
-                    for &pred in preds {
-                        if idoms[pred].is_some() {
-                            idom = Self::intersect(&idoms, pred, idom);
-                        }
-                    }
-
-                    if idoms[block] != Some(idom) {
-                        idoms[block] = Some(idom);
-                        changed = true;
-                    }
-                }
-            }
+            let successors: Vec<Vec<usize>> = (0..num_blocks).map(|b| cfg.successors(b).to_vec()).collect();
+            let predecessors: Vec<Vec<usize>> = (0..num_blocks).map(|b| cfg.predecessors(b).to_vec()).collect();
+            idoms = compute_idoms(0, &successors, &predecessors);
         }
 
         // Build dominated sets
+        let mut dominated = vec![HashSet::new(); num_blocks];
         for (block, idom) in idoms.iter().enumerate() {
             if let Some(dom) = idom {
                 if block != *dom {
@@ -83,24 +54,14 @@ impl DominatorTree {
         }
     }
 
-    fn intersect(idoms: &[Option<usize>], mut b1: usize, mut b2: usize) -> usize {
-        while b1 != b2 {
-            while b1 > b2 {
-                if let Some(idom) = idoms[b1] {
-                    b1 = idom;
-                } else {
-                    break;
-                }
-            }
-            while b2 > b1 {
-                if let Some(idom) = idoms[b2] {
-                    b2 = idom;
-                } else {
-                    break;
-                }
-            }
-        }
-        b1
+    /// The CFG the tree was computed from
+    pub fn cfg(&self) -> &CFG {
+        &self.cfg
+    }
+
+    /// Check if a block is reachable from the entry block
+    pub fn is_reachable(&self, block: usize) -> bool {
+        self.idoms[block].is_some()
     }
 
     /// Check if block1 dominates block2
@@ -155,28 +116,344 @@ impl AnalysisPass for DominatorTreePass {
     }
 }
 
+/// Immediate dominators of a graph by the Cooper-Harvey-Kennedy algorithm:
+/// iterate in reverse postorder from `root`, intersecting the predecessors
+/// by their position in that order. The root is its own immediate
+/// dominator; nodes unreachable from it have none.
+fn compute_idoms(root: usize, successors: &[Vec<usize>], predecessors: &[Vec<usize>]) -> Vec<Option<usize>> {
+    let num_nodes = successors.len();
+    let rpo = reverse_postorder_from(root, successors);
+    let mut order = vec![usize::MAX; num_nodes];
+    for (position, &node) in rpo.iter().enumerate() {
+        order[node] = position;
+    }
+
+    let mut idoms = vec![None; num_nodes];
+    idoms[root] = Some(root);
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for &node in rpo.iter().skip(1) {
+            let mut new_idom = None;
+            for &pred in &predecessors[node] {
+                if idoms[pred].is_none() {
+                    continue;
+                }
+                new_idom = Some(match new_idom {
+                    Some(idom) => intersect(&idoms, &order, pred, idom),
+                    None => pred,
+                });
+            }
+
+            if new_idom.is_some() && idoms[node] != new_idom {
+                idoms[node] = new_idom;
+                changed = true;
+            }
+        }
+    }
+    idoms
+}
+
+fn intersect(idoms: &[Option<usize>], order: &[usize], mut b1: usize, mut b2: usize) -> usize {
+    while b1 != b2 {
+        while order[b1] > order[b2] {
+            b1 = idoms[b1].expect("processed block has an immediate dominator");
+        }
+        while order[b2] > order[b1] {
+            b2 = idoms[b2].expect("processed block has an immediate dominator");
+        }
+    }
+    b1
+}
+
+/// Dominance frontier analysis
+///
+/// The frontier of a block holds the blocks where its dominance ends: those
+/// with a predecessor it dominates that it does not strictly dominate
+/// itself. This is where SSA construction places phi nodes.
+pub struct DominanceFrontier {
+    frontiers: Vec<HashSet<usize>>,
+}
+
+impl DominanceFrontier {
+    /// Compute the dominance frontiers of a function
+    pub fn new(function: &Function) -> Self {
+        Self::from_dominator_tree(&DominatorTree::new(function))
+    }
+
+    /// Compute dominance frontiers from a dominator tree, walking up from
+    /// each predecessor of a join point to its immediate dominator
+    pub fn from_dominator_tree(domtree: &DominatorTree) -> Self {
+        let cfg = domtree.cfg();
+        let mut frontiers = vec![HashSet::new(); cfg.num_blocks()];
+
+        for block in 0..cfg.num_blocks() {
+            let Some(idom) = domtree.idom(block) else { continue };
+            for &pred in cfg.predecessors(block) {
+                if !domtree.is_reachable(pred) {
+                    continue;
+                }
+                let mut runner = pred;
+                while runner != idom || runner == block {
+                    if !frontiers[runner].insert(block) {
+                        break;
+                    }
+                    match domtree.idom(runner) {
+                        Some(next) if next != runner => runner = next,
+                        _ => break,
+                    }
+                }
+            }
+        }
+
+        Self { frontiers }
+    }
+
+    /// Get the dominance frontier of a block
+    pub fn frontier(&self, block: usize) -> &HashSet<usize> {
+        &self.frontiers[block]
+    }
+
+    /// The iterated dominance frontier of a set of blocks: the closure of
+    /// the frontier under adding the frontier's own frontiers. For the
+    /// blocks defining a variable, these are the blocks that need a phi.
+    pub fn iterated_frontier(&self, blocks: &HashSet<usize>) -> HashSet<usize> {
+        let mut result = HashSet::new();
+        let mut worklist: Vec<usize> = blocks.iter().copied().collect();
+        let mut queued: HashSet<usize> = blocks.clone();
+
+        while let Some(block) = worklist.pop() {
+            for &frontier_block in &self.frontiers[block] {
+                result.insert(frontier_block);
+                if queued.insert(frontier_block) {
+                    worklist.push(frontier_block);
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Dominance frontier pass
+pub struct DominanceFrontierPass;
+
+impl Pass for DominanceFrontierPass {
+    fn name(&self) -> &str {
+        "domfrontier"
+    }
+
+    fn prerequisites(&self) -> Vec<String> {
+        vec!["domtree".to_string()]
+    }
+}
+
+impl AnalysisPass for DominanceFrontierPass {
+    type Result = DominanceFrontier;
+
+    fn run_analysis(&mut self, function: &Function) -> PassResult<Self::Result> {
+        Ok(DominanceFrontier::new(function))
+    }
+}
+
+/// Post-dominator tree analysis
+///
+/// Computed as the dominator tree of the reversed CFG below a virtual root
+/// that every exit block returns to, so functions with several exits have
+/// a single tree. Blocks that cannot reach an exit, as in an infinite loop,
+/// are attached to the virtual root too: for each such region, the block
+/// furthest from where it was entered becomes an additional root.
+pub struct PostDominatorTree {
+    /// Maps block index to its immediate post-dominator; the virtual root
+    /// has index `num_blocks`
+    ipdoms: Vec<Option<usize>>,
+    /// Blocks whose immediate post-dominator is the virtual root
+    roots: Vec<usize>,
+    cfg: CFG,
+}
+
+impl PostDominatorTree {
+    /// Compute the post-dominator tree of a function
+    pub fn new(function: &Function) -> Self {
+        let cfg = CFG::from_function(function);
+        let num_blocks = cfg.num_blocks();
+        let virtual_root = num_blocks;
+
+        // Edges of the reversed CFG, with the virtual root as the last node
+        let mut successors: Vec<Vec<usize>> = (0..num_blocks).map(|b| cfg.predecessors(b).to_vec()).collect();
+        let mut predecessors: Vec<Vec<usize>> = (0..num_blocks).map(|b| cfg.successors(b).to_vec()).collect();
+        successors.push(Vec::new());
+        predecessors.push(Vec::new());
+
+        let mut roots: Vec<usize> = (0..num_blocks).filter(|&b| cfg.successors(b).is_empty()).collect();
+        for &root in &roots {
+            successors[virtual_root].push(root);
+            predecessors[root].push(virtual_root);
+        }
+
+        // Attach regions that never reach an exit, until every block does
+        let mut reaches_exit = vec![false; num_blocks + 1];
+        for node in reverse_postorder_from(virtual_root, &successors) {
+            reaches_exit[node] = true;
+        }
+        let forward: Vec<Vec<usize>> = (0..num_blocks).map(|b| cfg.successors(b).to_vec()).collect();
+        while let Some(start) = (0..num_blocks).find(|&b| !reaches_exit[b]) {
+            let root = reverse_postorder_from(start, &forward).into_iter()
+                .rfind(|&b| !reaches_exit[b])
+                .unwrap_or(start);
+            roots.push(root);
+            successors[virtual_root].push(root);
+            predecessors[root].push(virtual_root);
+            for node in reverse_postorder_from(virtual_root, &successors) {
+                reaches_exit[node] = true;
+            }
+        }
+
+        let ipdoms = compute_idoms(virtual_root, &successors, &predecessors);
+        Self { ipdoms, roots, cfg }
+    }
+
+    /// The CFG the tree was computed from
+    pub fn cfg(&self) -> &CFG {
+        &self.cfg
+    }
+
+    /// Blocks attached to the virtual root: the exit blocks, then one
+    /// block of each region that cannot reach an exit
+    pub fn roots(&self) -> &[usize] {
+        &self.roots
+    }
+
+    /// Get the immediate post-dominator of a block; `None` when it is the
+    /// virtual root
+    pub fn ipdom(&self, block: usize) -> Option<usize> {
+        self.ipdoms[block].filter(|&ipdom| ipdom != self.cfg.num_blocks())
+    }
+
+    /// Check if block1 post-dominates block2: every path from block2 to an
+    /// exit passes through block1
+    pub fn post_dominates(&self, block1: usize, block2: usize) -> bool {
+        let mut current = block2;
+        loop {
+            if current == block1 {
+                return true;
+            }
+            match self.ipdom(current) {
+                Some(ipdom) => current = ipdom,
+                None => return false,
+            }
+        }
+    }
+
+    /// Check if block1 strictly post-dominates block2
+    pub fn strictly_post_dominates(&self, block1: usize, block2: usize) -> bool {
+        block1 != block2 && self.post_dominates(block1, block2)
+    }
+}
+
+/// Post-dominator tree pass
+pub struct PostDominatorTreePass;
+
+impl Pass for PostDominatorTreePass {
+    fn name(&self) -> &str {
+        "postdomtree"
+    }
+}
+
+impl AnalysisPass for PostDominatorTreePass {
+    type Result = PostDominatorTree;
+
+    fn run_analysis(&mut self, function: &Function) -> PassResult<Self::Result> {
+        Ok(PostDominatorTree::new(function))
+    }
+}
+
 /// Loop analysis
+///
+/// Builds the forest of natural loops: a loop is the set of blocks that
+/// reach a back edge to a header that dominates them, and loops sharing a
+/// header are merged. Cycles with more than one entry (irreducible control
+/// flow) have no such header and are not loops; their blocks only belong
+/// to the natural loops that enclose them.
 pub struct LoopInfo {
     loops: Vec<Loop>,
+    /// Maps block index to its innermost loop
     block_to_loop: HashMap<usize, usize>,
+    irreducible: bool,
+    cfg: CFG,
 }
 
 impl LoopInfo {
     /// Compute loop information for a function
     pub fn new(function: &Function) -> Self {
-        let cfg = CFG::from_function(function);
-        let loops = cfg.find_loops();
+        Self::from_cfg(CFG::from_function(function))
+    }
+
+    /// Compute loop information for a CFG
+    pub fn from_cfg(cfg: CFG) -> Self {
+        let domtree = DominatorTree::from_cfg(cfg);
+        let cfg = domtree.cfg();
+        let rpo = cfg.reverse_postorder();
+        let mut order = vec![usize::MAX; cfg.num_blocks()];
+        for (position, &block) in rpo.iter().enumerate() {
+            order[block] = position;
+        }
 
+        // Headers in reverse postorder come before the headers they dominate,
+        // so every loop follows the loops enclosing it
+        let mut loops: Vec<Loop> = Vec::new();
         let mut block_to_loop = HashMap::new();
-        for (i, loop_info) in loops.iter().enumerate() {
-            for &block in &loop_info.blocks {
-                block_to_loop.insert(block, i);
+        let mut irreducible = false;
+        for &header in &rpo {
+            let mut latches = Vec::new();
+            for &pred in cfg.predecessors(header) {
+                // Retreating edges in reverse postorder close a cycle
+                if !domtree.is_reachable(pred) || order[pred] < order[header] {
+                    continue;
+                }
+                if domtree.dominates(header, pred) {
+                    latches.push(pred);
+                } else {
+                    irreducible = true;
+                }
+            }
+            if latches.is_empty() {
+                continue;
+            }
+
+            // Walk back from the latches to the header
+            let mut blocks = HashSet::from([header]);
+            let mut worklist = latches.clone();
+            while let Some(block) = worklist.pop() {
+                if blocks.insert(block) {
+                    worklist.extend(cfg.predecessors(block).iter().filter(|&&pred| domtree.is_reachable(pred)));
+                }
             }
+
+            let index = loops.len();
+            let parent = block_to_loop.get(&header).copied();
+            let depth = parent.map_or(1, |parent: usize| loops[parent].depth + 1);
+            if let Some(parent) = parent {
+                loops[parent].children.push(index);
+            }
+            for &block in &blocks {
+                block_to_loop.insert(block, index);
+            }
+            loops.push(Loop {
+                header,
+                blocks,
+                latches,
+                parent,
+                children: Vec::new(),
+                depth,
+            });
         }
 
         Self {
             loops,
             block_to_loop,
+            irreducible,
+            cfg: domtree.cfg,
         }
     }
 
@@ -185,15 +462,98 @@ impl LoopInfo {
         &self.loops
     }
 
-    /// Get the loop containing a block, if any
+    /// Indices of the loops not nested in another
+    pub fn top_level_loops(&self) -> Vec<usize> {
+        (0..self.loops.len()).filter(|&i| self.loops[i].is_outermost()).collect()
+    }
+
+    /// Get the innermost loop containing a block, if any
     pub fn loop_for_block(&self, block: usize) -> Option<&Loop> {
         self.block_to_loop.get(&block).map(|&i| &self.loops[i])
     }
 
+    /// Index of the innermost loop containing a block, if any
+    pub fn loop_index_for_block(&self, block: usize) -> Option<usize> {
+        self.block_to_loop.get(&block).copied()
+    }
+
+    /// Number of loops containing a block, 0 outside loops
+    pub fn loop_depth(&self, block: usize) -> usize {
+        self.loop_for_block(block).map_or(0, |l| l.depth)
+    }
+
+    /// Check if a block is the header of a loop
+    pub fn is_loop_header(&self, block: usize) -> bool {
+        self.loop_for_block(block).is_some_and(|l| l.header == block)
+    }
+
     /// Get the number of loops
     pub fn num_loops(&self) -> usize {
         self.loops.len()
     }
+
+    /// Whether the function has a cycle that is not a natural loop
+    pub fn has_irreducible_control_flow(&self) -> bool {
+        self.irreducible
+    }
+
+    /// The CFG the loops were computed from
+    pub fn cfg(&self) -> &CFG {
+        &self.cfg
+    }
+
+    /// The only predecessor of the header outside the loop, if there is one
+    pub fn loop_predecessor(&self, l: &Loop) -> Option<usize> {
+        let mut outside = self.cfg.predecessors(l.header).iter().filter(|&&pred| !l.contains(pred));
+        match (outside.next(), outside.next()) {
+            (Some(&pred), None) => Some(pred),
+            _ => None,
+        }
+    }
+
+    /// The preheader: the loop predecessor, if its only successor is the
+    /// header
+    pub fn preheader(&self, l: &Loop) -> Option<usize> {
+        self.loop_predecessor(l).filter(|&pred| self.cfg.successors(pred) == [l.header])
+    }
+
+    /// Blocks of the loop with a successor outside it
+    pub fn exiting_blocks(&self, l: &Loop) -> Vec<usize> {
+        let mut exiting: Vec<usize> = l.blocks.iter().copied()
+            .filter(|&block| self.cfg.successors(block).iter().any(|&succ| !l.contains(succ)))
+            .collect();
+        exiting.sort_unstable();
+        exiting
+    }
+
+    /// Blocks outside the loop with a predecessor in it
+    pub fn exit_blocks(&self, l: &Loop) -> Vec<usize> {
+        let mut exits: Vec<usize> = l.blocks.iter()
+            .flat_map(|&block| self.cfg.successors(block).iter().copied())
+            .filter(|&succ| !l.contains(succ))
+            .collect();
+        exits.sort_unstable();
+        exits.dedup();
+        exits
+    }
+
+    /// Whether every exit block is entered only from inside the loop
+    pub fn has_dedicated_exits(&self, l: &Loop) -> bool {
+        self.exit_blocks(l).iter()
+            .all(|&exit| self.cfg.predecessors(exit).iter().all(|&pred| l.contains(pred)))
+    }
+
+    /// Whether the loop is in the canonical form of LLVM's LoopSimplify: a
+    /// preheader, a single latch and dedicated exits
+    pub fn is_loop_simplify_form(&self, l: &Loop) -> bool {
+        self.preheader(l).is_some() && l.latch().is_some() && self.has_dedicated_exits(l)
+    }
+
+    /// Whether the loop is rotated: the single latch is also an exiting
+    /// block, so the exit test runs at the bottom of the loop
+    pub fn is_rotated_form(&self, l: &Loop) -> bool {
+        l.latch().is_some_and(|latch| self.cfg.successors(latch).iter().any(|&succ| !l.contains(succ)))
+    }
 }
 
 /// Loop analysis pass
@@ -213,50 +573,341 @@ impl AnalysisPass for LoopAnalysisPass {
     }
 }
 
-/// Simple alias analysis
+/// Result of an alias query
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AliasResult {
+    /// The locations never overlap
+    NoAlias,
+    /// The locations may or may not overlap
+    MayAlias,
+    /// The locations overlap, but do not start at the same address or
+    /// have different sizes
+    PartialAlias,
+    /// The locations start at the same address and have the same size
+    MustAlias,
+}
+
+/// A memory location: a pointer and the number of bytes accessed through
+/// it, if known
+#[derive(Debug, Clone)]
+pub struct MemoryLocation {
+    pub pointer: Value,
+    pub size: Option<u64>,
+}
+
+impl MemoryLocation {
+    pub fn new(pointer: Value, size: Option<u64>) -> Self {
+        Self { pointer, size }
+    }
+
+    /// The location read or written by a load, store, atomicrmw or cmpxchg
+    pub fn of_instruction(inst: &Instruction) -> Option<Self> {
+        let operands = inst.operands();
+        let (pointer, accessed) = match inst.opcode() {
+            Opcode::Load => (operands.first()?, inst.result()?.get_type()),
+            Opcode::Store => (operands.get(1)?, operands.first()?.get_type()),
+            Opcode::AtomicRMW | Opcode::AtomicCmpXchg => (operands.first()?, operands.get(1)?.get_type()),
+            _ => return None,
+        };
+        Some(Self::new(pointer.clone(), type_layout(accessed).map(|(size, _)| size)))
+    }
+}
+
+/// Identity of a value within a function: locals by name, unnamed values
+/// by the data they share with their uses
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum ValueKey {
+    Global(String),
+    Local(String),
+    Unnamed(usize),
+}
+
+impl ValueKey {
+    pub(crate) fn of(value: &Value) -> Self {
+        match value.name() {
+            Some(name) if value.is_global() => ValueKey::Global(name.to_string()),
+            Some(name) => ValueKey::Local(name.to_string()),
+            None => ValueKey::Unnamed(value.data_ptr()),
+        }
+    }
+}
+
+/// A pointer as an underlying object plus a byte offset into it; the
+/// offset is `None` when an index is not constant
+struct DecomposedPointer {
+    base: Value,
+    offset: Option<i64>,
+}
+
+/// Alias analysis in the style of LLVM's BasicAA
+///
+/// Pointers are traced through casts and getelementptrs to their
+/// underlying objects. Distinct allocas, globals and `noalias` arguments
+/// never alias, constant getelementptr offsets are compared against the
+/// access sizes, and accesses larger than an alloca cannot point into it.
 pub struct AliasAnalysis {
-    /// Map of potentially aliasing pointers
-    may_alias: HashMap<String, HashSet<String>>,
+    /// Defining instruction of each local value
+    definitions: HashMap<ValueKey, Instruction>,
+    /// Indices of the arguments marked `noalias`
+    noalias_arguments: HashSet<usize>,
 }
 
 impl AliasAnalysis {
-    pub fn new(_function: &Function) -> Self {
-        // Simplified - a real implementation would do inter-procedural analysis
+    /// Number of casts and getelementptrs looked through per pointer
+    const MAX_LOOKUP: usize = 6;
+
+    pub fn new(function: &Function) -> Self {
+        let mut definitions = HashMap::new();
+        for bb in function.basic_blocks() {
+            for inst in bb.instructions() {
+                if let Some(result) = inst.result() {
+                    definitions.insert(ValueKey::of(result), inst.clone());
+                }
+            }
+        }
+
+        let noalias_arguments = function.attributes().parameter_attributes.iter()
+            .enumerate()
+            .filter(|(_, attributes)| attributes.noalias)
+            .map(|(index, _)| index)
+            .collect();
+
         Self {
-            may_alias: HashMap::new(),
+            definitions,
+            noalias_arguments,
         }
     }
 
-    /// Check if two values may alias
-    pub fn may_alias(&self, v1: &str, v2: &str) -> bool {
-        if v1 == v2 {
-            return true;
+    /// Whether two locations overlap
+    pub fn alias(&self, a: &MemoryLocation, b: &MemoryLocation) -> AliasResult {
+        // Accessing null or undef is undefined, so those accesses alias nothing
+        if Self::is_inaccessible(&a.pointer) || Self::is_inaccessible(&b.pointer) {
+            return AliasResult::NoAlias;
+        }
+        if ValueKey::of(&a.pointer) == ValueKey::of(&b.pointer) {
+            return AliasResult::MustAlias;
         }
 
-        if let Some(aliases) = self.may_alias.get(v1) {
-            if aliases.contains(v2) {
-                return true;
-            }
+        let da = self.decompose(&a.pointer);
+        let db = self.decompose(&b.pointer);
+        if ValueKey::of(&da.base) != ValueKey::of(&db.base) {
+            return self.alias_objects(&da.base, b.size, &db.base, a.size);
         }
 
-        if let Some(aliases) = self.may_alias.get(v2) {
-            if aliases.contains(v1) {
-                return true;
+        let (Some(offset_a), Some(offset_b)) = (da.offset, db.offset) else {
+            return AliasResult::MayAlias;
+        };
+        if offset_a == offset_b {
+            return match (a.size, b.size) {
+                (Some(size_a), Some(size_b)) if size_a == size_b => AliasResult::MustAlias,
+                (Some(_), Some(_)) => AliasResult::PartialAlias,
+                _ => AliasResult::MayAlias,
+            };
+        }
+
+        // Compare the lower location's extent with the start of the higher one
+        let (low_offset, low_size, high_offset) = if offset_a < offset_b {
+            (offset_a, a.size, offset_b)
+        } else {
+            (offset_b, b.size, offset_a)
+        };
+        match low_size {
+            Some(size) if low_offset.saturating_add(size as i64) <= high_offset => AliasResult::NoAlias,
+            Some(_) => AliasResult::PartialAlias,
+            None => AliasResult::MayAlias,
+        }
+    }
+
+    /// Check if two pointers may alias, for accesses of unknown size
+    pub fn may_alias(&self, a: &Value, b: &Value) -> bool {
+        !self.no_alias(a, b)
+    }
+
+    /// Check if two pointers must alias
+    pub fn must_alias(&self, a: &Value, b: &Value) -> bool {
+        self.alias(&MemoryLocation::new(a.clone(), None), &MemoryLocation::new(b.clone(), None))
+            == AliasResult::MustAlias
+    }
+
+    /// Check if two pointers never alias
+    pub fn no_alias(&self, a: &Value, b: &Value) -> bool {
+        self.alias(&MemoryLocation::new(a.clone(), None), &MemoryLocation::new(b.clone(), None))
+            == AliasResult::NoAlias
+    }
+
+    /// Alias result for pointers into different underlying objects, each
+    /// with the access size of the other location
+    fn alias_objects(&self, base_a: &Value, size_b: Option<u64>, base_b: &Value, size_a: Option<u64>) -> AliasResult {
+        if self.is_identified_object(base_a) && self.is_identified_object(base_b) {
+            return AliasResult::NoAlias;
+        }
+        // An argument was bound before any alloca of this call existed
+        if (self.is_alloca(base_a) && Self::is_argument(base_b)) || (self.is_alloca(base_b) && Self::is_argument(base_a)) {
+            return AliasResult::NoAlias;
+        }
+        // An access larger than an object cannot be within it
+        let too_large = |object: &Value, size: Option<u64>| match (self.object_size(object), size) {
+            (Some(object_size), Some(size)) => size > object_size,
+            _ => false,
+        };
+        if too_large(base_a, size_b) || too_large(base_b, size_a) {
+            return AliasResult::NoAlias;
+        }
+        AliasResult::MayAlias
+    }
+
+    fn is_inaccessible(pointer: &Value) -> bool {
+        (pointer.is_null() && pointer.get_type().address_space().unwrap_or(0) == 0)
+            || pointer.is_undef() || pointer.is_poison()
+    }
+
+    fn is_argument(value: &Value) -> bool {
+        matches!(value.kind(), ValueKind::Argument { .. })
+    }
+
+    fn is_alloca(&self, value: &Value) -> bool {
+        self.definition(value).is_some_and(|inst| inst.opcode() == Opcode::Alloca)
+    }
+
+    /// Allocas, globals and `noalias` arguments: objects no other
+    /// identified object can overlap
+    fn is_identified_object(&self, value: &Value) -> bool {
+        match value.kind() {
+            ValueKind::Argument { index } => self.noalias_arguments.contains(index),
+            _ => value.is_global() || self.is_alloca(value),
+        }
+    }
+
+    /// Size in bytes of an alloca with a constant element count
+    fn object_size(&self, value: &Value) -> Option<u64> {
+        let alloca = self.definition(value).filter(|inst| inst.opcode() == Opcode::Alloca)?;
+        let count = match alloca.operands().first() {
+            Some(count) => u64::try_from(count.as_const_int()?).ok()?,
+            None => 1,
+        };
+        let (size, _) = type_layout(alloca.result()?.get_type().pointee_type()?)?;
+        size.checked_mul(count)
+    }
+
+    fn definition(&self, value: &Value) -> Option<&Instruction> {
+        if value.is_global() || Self::is_argument(value) {
+            return None;
+        }
+        self.definitions.get(&ValueKey::of(value))
+    }
+
+    /// Trace a pointer through casts and getelementptrs
+    fn decompose(&self, pointer: &Value) -> DecomposedPointer {
+        let mut base = pointer.clone();
+        let mut offset = Some(0i64);
+        for _ in 0..Self::MAX_LOOKUP {
+            if let Some((opcode, operands)) = base.as_const_expr() {
+                match opcode {
+                    Opcode::BitCast | Opcode::AddrSpaceCast if !operands.is_empty() => {}
+                    // Constant expressions do not record the source element type
+                    Opcode::GetElementPtr if !operands.is_empty() => offset = None,
+                    _ => break,
+                }
+                base = operands[0].clone();
+                continue;
+            }
+
+            let Some(inst) = self.definition(&base) else {
+                break;
+            };
+            let Some(source) = inst.operands().first().cloned() else {
+                break;
+            };
+            match inst.opcode() {
+                Opcode::BitCast | Opcode::AddrSpaceCast => {}
+                Opcode::GetElementPtr => {
+                    let gep_offset = inst.gep_source_type()
+                        .and_then(|ty| gep_constant_offset(ty, &inst.operands()[1..]));
+                    offset = offset.zip(gep_offset).and_then(|(a, b)| a.checked_add(b));
+                }
+                _ => break,
             }
+            base = source;
         }
+        DecomposedPointer { base, offset }
+    }
+}
 
-        false
+/// Byte offset of a getelementptr with constant indices
+fn gep_constant_offset(source: &Type, indices: &[Value]) -> Option<i64> {
+    let (first, rest) = indices.split_first()?;
+    let (size, _) = type_layout(source)?;
+    let mut offset = first.as_const_int()?.checked_mul(size as i64)?;
+    let mut current = source.clone();
+    for index in rest {
+        let index = index.as_const_int()?;
+        if let Some(fields) = current.struct_fields() {
+            let field = usize::try_from(index).ok().filter(|i| *i < fields.len())?;
+            offset = offset.checked_add(struct_field_offset(&fields, current.is_packed_struct(), field)? as i64)?;
+            current = fields[field].clone();
+        } else {
+            let element = current.array_info().or_else(|| current.vector_info())?.0.clone();
+            let (element_size, _) = type_layout(&element)?;
+            offset = offset.checked_add(index.checked_mul(element_size as i64)?)?;
+            current = element;
+        }
     }
+    Some(offset)
+}
 
-    /// Check if two values must alias (are the same)
-    pub fn must_alias(&self, v1: &str, v2: &str) -> bool {
-        v1 == v2
+/// Allocation size and ABI alignment of a type in bytes, with natural
+/// alignment on a 64-bit target and struct padding; `None` for unsized
+/// and scalable types
+fn type_layout(ty: &Type) -> Option<(u64, u64)> {
+    if let Some(bits) = ty.int_width() {
+        let store_size = (bits as u64).div_ceil(8);
+        let align = store_size.next_power_of_two().min(8);
+        return Some((store_size.next_multiple_of(align), align));
+    }
+    if ty.is_float() || ty.is_pointer() {
+        let size = ty.size_in_bytes()?;
+        return Some((size, size));
+    }
+    if let Some((element, count)) = ty.array_info() {
+        let (size, align) = type_layout(element)?;
+        return Some((size * count as u64, align));
+    }
+    if let Some((element, count)) = ty.vector_info() {
+        if count == 0 {
+            return None;
+        }
+        let size = type_layout(element)?.0 * count as u64;
+        return Some((size, size.next_power_of_two()));
     }
+    if let Some(fields) = ty.struct_fields() {
+        let packed = ty.is_packed_struct();
+        let mut size: u64 = 0;
+        let mut struct_align: u64 = 1;
+        for field in &fields {
+            let (field_size, field_align) = type_layout(field)?;
+            let field_align = if packed { 1 } else { field_align };
+            size = size.next_multiple_of(field_align) + field_size;
+            struct_align = struct_align.max(field_align);
+        }
+        return Some((size.next_multiple_of(struct_align), struct_align));
+    }
+    None
+}
 
-    /// Check if two values definitely don't alias
-    pub fn no_alias(&self, v1: &str, v2: &str) -> bool {
-        !self.may_alias(v1, v2)
+/// Byte offset of field `index` of a struct
+fn struct_field_offset(fields: &[Type], packed: bool, index: usize) -> Option<u64> {
+    let mut offset: u64 = 0;
+    for (i, field) in fields.iter().enumerate() {
+        let (field_size, field_align) = type_layout(field)?;
+        if !packed {
+            offset = offset.next_multiple_of(field_align);
+        }
+        if i == index {
+            return Some(offset);
+        }
+        offset += field_size;
     }
+    None
 }
 
 /// Alias analysis pass
@@ -297,6 +948,37 @@ mod tests {
         assert!(domtree.dominates(0, 0));
     }
 
+    #[test]
+    fn test_dominator_tree_out_of_layout_order() {
+        use crate::value::{Value, ValueKind};
+
+        let ctx = Context::new();
+        let fn_type = ctx.function_type(ctx.void_type(), vec![], false);
+        let func = Function::new("test".to_string(), fn_type);
+        let label = |name: &str| Value::new(ctx.label_type(), ValueKind::BasicBlock, Some(name.to_string()));
+        let cond = Value::const_int(ctx.bool_type(), 1, None);
+
+        // entry -> tail and entry -> mid -> tail, laid out as entry, tail, dead, mid
+        let entry = BasicBlock::new(Some("entry".to_string()));
+        entry.add_instruction(Instruction::new(Opcode::CondBr, vec![cond, label("mid"), label("tail")], None));
+        let tail = BasicBlock::new(Some("tail".to_string()));
+        tail.add_instruction(Instruction::new(Opcode::Ret, vec![], None));
+        let dead = BasicBlock::new(Some("dead".to_string()));
+        dead.add_instruction(Instruction::new(Opcode::Br, vec![label("tail")], None));
+        let mid = BasicBlock::new(Some("mid".to_string()));
+        mid.add_instruction(Instruction::new(Opcode::Br, vec![label("tail")], None));
+        for bb in [entry, tail, dead, mid] {
+            func.add_basic_block(bb);
+        }
+
+        let domtree = DominatorTree::new(&func);
+        assert_eq!(domtree.idom(1), Some(0));
+        assert_eq!(domtree.idom(3), Some(0));
+        assert!(!domtree.dominates(3, 1));
+        assert!(!domtree.is_reachable(2));
+        assert_eq!(domtree.cfg().block_index("mid"), Some(3));
+    }
+
     #[test]
     fn test_loop_info() {
         let ctx = Context::new();
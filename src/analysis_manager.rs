@@ -0,0 +1,233 @@
+//! Analysis Manager
+//!
+//! Caches analysis results so passes share them instead of recomputing,
+//! in the manner of LLVM's new pass manager. Results are keyed by the
+//! analysis pass type and the IR unit, a function by name or the module.
+//! After a pass runs it reports the analyses it kept valid as
+//! `PreservedAnalyses`, and only the other results of the unit it changed
+//! are dropped.
+//!
+//! One manager serves one module at a time; `clear` it before moving on
+//! to another, as `PassManager::run` does.
+
+use std::any::{type_name, Any, TypeId};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use crate::analysis::{
+    AliasAnalysisPass, DominanceFrontierPass, DominatorTreePass, LoopAnalysisPass, PostDominatorTreePass,
+};
+use crate::block_frequency::BlockFrequencyPass;
+use crate::branch_probability::BranchProbabilityPass;
+use crate::call_graph::CallGraphAnalysisPass;
+use crate::function::Function;
+use crate::lazy_value_info::LazyValueInfoPass;
+use crate::liveness::LivenessPass;
+use crate::memory_ssa::MemorySSAPass;
+use crate::module::Module;
+use crate::passes::{AnalysisPass, ModuleAnalysisPass, PassError, PassResult};
+use crate::scalar_evolution::ScalarEvolutionPass;
+
+/// The analyses a pass kept valid, by analysis pass type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreservedAnalyses {
+    all: bool,
+    preserved: HashSet<TypeId>,
+}
+
+impl PreservedAnalyses {
+    /// Every analysis, as after a pass that changed nothing
+    pub fn all() -> Self {
+        Self { all: true, preserved: HashSet::new() }
+    }
+
+    /// No analysis
+    pub fn none() -> Self {
+        Self { all: false, preserved: HashSet::new() }
+    }
+
+    /// Mark the results of analysis pass `A` as still valid
+    pub fn preserve<A: 'static>(&mut self) -> &mut Self {
+        self.preserved.insert(TypeId::of::<A>());
+        self
+    }
+
+    pub fn is_preserved<A: 'static>(&self) -> bool {
+        self.preserves(TypeId::of::<A>())
+    }
+
+    pub fn are_all_preserved(&self) -> bool {
+        self.all
+    }
+
+    fn preserves(&self, analysis: TypeId) -> bool {
+        self.all || self.preserved.contains(&analysis)
+    }
+}
+
+type FunctionAnalysis = Box<dyn FnMut(&Function) -> PassResult<Rc<dyn Any>>>;
+type ModuleAnalysis = Box<dyn FnMut(&Module) -> PassResult<Rc<dyn Any>>>;
+
+/// Registered analyses and their cached results
+pub struct AnalysisManager {
+    function_analyses: HashMap<TypeId, FunctionAnalysis>,
+    module_analyses: HashMap<TypeId, ModuleAnalysis>,
+    /// Analysis pass types by pass name
+    names: HashMap<String, TypeId>,
+    /// Results by analysis and function name; `None` is the module
+    results: HashMap<(TypeId, Option<String>), Rc<dyn Any>>,
+}
+
+impl AnalysisManager {
+    /// A manager with the analyses of this crate registered
+    pub fn new() -> Self {
+        let mut manager = Self::empty();
+        manager.register_function_analysis(DominatorTreePass);
+        manager.register_function_analysis(PostDominatorTreePass);
+        manager.register_function_analysis(DominanceFrontierPass);
+        manager.register_function_analysis(LoopAnalysisPass);
+        manager.register_function_analysis(AliasAnalysisPass);
+        manager.register_function_analysis(ScalarEvolutionPass);
+        manager.register_function_analysis(MemorySSAPass);
+        manager.register_function_analysis(LivenessPass);
+        manager.register_function_analysis(LazyValueInfoPass);
+        manager.register_function_analysis(BranchProbabilityPass);
+        manager.register_function_analysis(BlockFrequencyPass);
+        manager.register_module_analysis(CallGraphAnalysisPass);
+        manager
+    }
+
+    /// A manager with no analyses registered
+    pub fn empty() -> Self {
+        Self {
+            function_analyses: HashMap::new(),
+            module_analyses: HashMap::new(),
+            names: HashMap::new(),
+            results: HashMap::new(),
+        }
+    }
+
+    /// Register an analysis of functions, replacing any of the same type
+    pub fn register_function_analysis<A>(&mut self, mut pass: A)
+    where
+        A: AnalysisPass + 'static,
+        A::Result: 'static,
+    {
+        self.names.insert(pass.name().to_string(), TypeId::of::<A>());
+        self.function_analyses.insert(TypeId::of::<A>(), Box::new(move |function: &Function| {
+            pass.run_analysis(function).map(|result| Rc::new(result) as Rc<dyn Any>)
+        }));
+    }
+
+    /// Register an analysis of the module, replacing any of the same type
+    pub fn register_module_analysis<A>(&mut self, mut pass: A)
+    where
+        A: ModuleAnalysisPass + 'static,
+        A::Result: 'static,
+    {
+        self.names.insert(pass.name().to_string(), TypeId::of::<A>());
+        self.module_analyses.insert(TypeId::of::<A>(), Box::new(move |module: &Module| {
+            pass.run_module_analysis(module).map(|result| Rc::new(result) as Rc<dyn Any>)
+        }));
+    }
+
+    /// Whether an analysis pass of this name is registered
+    pub fn is_registered(&self, name: &str) -> bool {
+        self.names.contains_key(name)
+    }
+
+    /// The result of analysis `A` for a function, computed unless cached
+    pub fn get_result<A>(&mut self, function: &Function) -> PassResult<Rc<A::Result>>
+    where
+        A: AnalysisPass + 'static,
+        A::Result: 'static,
+    {
+        let key = (TypeId::of::<A>(), Some(function.name()));
+        if let Some(result) = self.cached::<A::Result>(&key) {
+            return Ok(result);
+        }
+        let analysis = self.function_analyses.get_mut(&key.0).ok_or_else(|| not_registered::<A>())?;
+        let result = analysis(function)?;
+        self.results.insert(key, result.clone());
+        Ok(downcast(result))
+    }
+
+    /// The cached result of analysis `A` for a function, if any
+    pub fn get_cached_result<A>(&self, function: &str) -> Option<Rc<A::Result>>
+    where
+        A: AnalysisPass + 'static,
+        A::Result: 'static,
+    {
+        self.cached(&(TypeId::of::<A>(), Some(function.to_string())))
+    }
+
+    /// The result of module analysis `A`, computed unless cached
+    pub fn get_module_result<A>(&mut self, module: &Module) -> PassResult<Rc<A::Result>>
+    where
+        A: ModuleAnalysisPass + 'static,
+        A::Result: 'static,
+    {
+        let key = (TypeId::of::<A>(), None);
+        if let Some(result) = self.cached::<A::Result>(&key) {
+            return Ok(result);
+        }
+        let analysis = self.module_analyses.get_mut(&key.0).ok_or_else(|| not_registered::<A>())?;
+        let result = analysis(module)?;
+        self.results.insert(key, result.clone());
+        Ok(downcast(result))
+    }
+
+    /// The cached result of module analysis `A`, if any
+    pub fn get_cached_module_result<A>(&self) -> Option<Rc<A::Result>>
+    where
+        A: ModuleAnalysisPass + 'static,
+        A::Result: 'static,
+    {
+        self.cached(&(TypeId::of::<A>(), None))
+    }
+
+    fn cached<R: 'static>(&self, key: &(TypeId, Option<String>)) -> Option<Rc<R>> {
+        self.results.get(key).cloned().map(downcast)
+    }
+
+    /// Drop the results for a changed function that were not preserved,
+    /// along with the module results that were not, which may describe it
+    pub fn invalidate(&mut self, function: &str, preserved: &PreservedAnalyses) {
+        self.results.retain(|(analysis, unit), _| match unit {
+            Some(name) if name != function => true,
+            _ => preserved.preserves(*analysis),
+        });
+    }
+
+    /// Drop every result that was not preserved, after the module changed
+    pub fn invalidate_module(&mut self, preserved: &PreservedAnalyses) {
+        self.results.retain(|(analysis, _), _| preserved.preserves(*analysis));
+    }
+
+    /// The analyses preserved by their pass names; names of passes that
+    /// are not registered are ignored
+    pub fn preserved_by_name(&self, names: &[String]) -> PreservedAnalyses {
+        let mut preserved = PreservedAnalyses::none();
+        preserved.preserved.extend(names.iter().filter_map(|name| self.names.get(name)));
+        preserved
+    }
+
+    /// Drop every cached result
+    pub fn clear(&mut self) {
+        self.results.clear();
+    }
+}
+
+impl Default for AnalysisManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn not_registered<A>() -> PassError {
+    PassError::Failed(format!("analysis {} is not registered", type_name::<A>()))
+}
+
+/// Results are stored under the type of the analysis that produced them
+fn downcast<R: 'static>(result: Rc<dyn Any>) -> Rc<R> {
+    result.downcast().unwrap_or_else(|_| unreachable!("cached result of another type"))
+}
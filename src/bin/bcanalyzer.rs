@@ -0,0 +1,74 @@
+//! Bitstream analyzer
+//!
+//! Prints the container structure of a bitstream file in the manner of
+//! `llvm-bcanalyzer`: a summary with per-block sizes and record histograms,
+//! and with `--dump` every block, record, abbreviation definition and
+//! BLOCKINFO entry. Works on any bitstream (LLVM IR, Clang ASTs and
+//! diagnostics, remarks) since records are never interpreted.
+//!
+//! Usage: bcanalyzer [--dump] [--show-binary-blobs] [--no-stats] file
+//!
+//! Exits with status 1 if the file cannot be read or the stream is malformed;
+//! everything decoded before the error is still printed.
+
+use llvm_rust::bitcode::analyzer::{analyze, AnalyzerOptions};
+use std::env;
+use std::fs;
+use std::process;
+
+const USAGE: &str = "Usage: bcanalyzer [--dump] [--show-binary-blobs] [--no-stats] file";
+
+fn main() {
+    let mut options = AnalyzerOptions::default();
+    let mut stats = true;
+    let mut file = None;
+
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "-dump" | "--dump" => options.dump = true,
+            "-show-binary-blobs" | "--show-binary-blobs" => options.show_binary_blobs = true,
+            "--no-stats" => stats = false,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if file.is_none() => file = Some(arg),
+            _ => {
+                eprintln!("{}", USAGE);
+                process::exit(1);
+            }
+        }
+    }
+
+    let Some(path) = file else {
+        eprintln!("{}", USAGE);
+        process::exit(1);
+    };
+    let bytes = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            eprintln!("Error reading {}: {}", path, e);
+            process::exit(1);
+        }
+    };
+    let analysis = match analyze(&bytes, &options) {
+        Ok(analysis) => analysis,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            process::exit(1);
+        }
+    };
+
+    print!("{}", analysis.dump);
+    if stats {
+        if options.dump {
+            println!();
+        }
+        println!("Summary of {}:", path);
+        print!("{}", analysis.statistics());
+    }
+    if let Some(error) = analysis.error {
+        eprintln!("{}: {}", path, error);
+        process::exit(1);
+    }
+}
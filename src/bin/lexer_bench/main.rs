@@ -0,0 +1,167 @@
+//! Lexer throughput benchmark
+//!
+//! Measures MB/s of the byte lexer against the original `Vec<char>` lexer.
+//! Each input is lexed three ways:
+//!   - legacy:    original lexer, `tokenize()` into owned tokens
+//!   - tokenize:  byte lexer, `tokenize()` into owned tokens
+//!   - streaming: byte lexer, iterating borrowed tokens without collecting them (what the parser uses)
+//!
+//! Usage: lexer_bench [--iterations N] [--size-mb N] [file.ll ...]
+//!
+//! Without files a synthetic module of `--size-mb` megabytes is generated.
+//! Build with `--release` for meaningful numbers.
+
+mod legacy;
+
+use legacy::CharLexer;
+use llvm_rust::lexer::Lexer;
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::process;
+use std::time::{Duration, Instant};
+
+fn main() {
+    let mut iterations = 5;
+    let mut size_mb = 16;
+    let mut files = Vec::new();
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--iterations" => iterations = parse_count(args.next(), "--iterations"),
+            "--size-mb" => size_mb = parse_count(args.next(), "--size-mb"),
+            "-h" | "--help" => {
+                println!("Usage: lexer_bench [--iterations N] [--size-mb N] [file.ll ...]");
+                return;
+            }
+            _ => files.push(arg),
+        }
+    }
+
+    let inputs: Vec<(String, String)> = if files.is_empty() {
+        vec![(format!("<synthetic {} MB>", size_mb), synthetic_module(size_mb * 1024 * 1024))]
+    } else {
+        files
+            .iter()
+            .map(|path| match fs::read_to_string(path) {
+                Ok(content) => (path.clone(), content),
+                Err(e) => {
+                    eprintln!("Error reading {}: {}", path, e);
+                    process::exit(1);
+                }
+            })
+            .collect()
+    };
+
+    for (name, source) in &inputs {
+        bench_input(name, source, iterations);
+    }
+}
+
+fn parse_count(value: Option<String>, flag: &str) -> usize {
+    match value.as_deref().map(str::parse::<usize>) {
+        Some(Ok(n)) if n > 0 => n,
+        _ => {
+            eprintln!("{} expects a positive integer", flag);
+            process::exit(1);
+        }
+    }
+}
+
+fn bench_input(name: &str, source: &str, iterations: usize) {
+    let megabytes = source.len() as f64 / (1024.0 * 1024.0);
+    println!("{} ({:.2} MB, {} iterations)", name, megabytes, iterations);
+
+    // Both lexers must agree before their speeds are worth comparing
+    let legacy_tokens = CharLexer::new(source).tokenize();
+    let tokens = Lexer::new(source).tokenize();
+    match (&legacy_tokens, &tokens) {
+        (Ok(expected), Ok(actual)) if expected != actual => {
+            let index = expected.iter().zip(actual).position(|(a, b)| a != b).unwrap_or(expected.len().min(actual.len()));
+            println!("  warning: token streams differ at token {} ({:?} vs {:?})",
+                index, expected.get(index), actual.get(index));
+        }
+        (Err(e), _) | (_, Err(e)) => {
+            println!("  skipped: lexer error: {}", e);
+            return;
+        }
+        _ => {}
+    }
+    let token_count = tokens.map(|t| t.len()).unwrap_or(0);
+    println!("  {} tokens", token_count);
+
+    let legacy = measure(iterations, || CharLexer::new(source).tokenize().map(|t| t.len()).unwrap_or(0));
+    let owned = measure(iterations, || Lexer::new(source).tokenize().map(|t| t.len()).unwrap_or(0));
+    let streaming = measure(iterations, || Lexer::new(source).filter(|t| t.is_ok()).count());
+
+    report("legacy", megabytes, legacy, legacy);
+    report("tokenize", megabytes, owned, legacy);
+    report("streaming", megabytes, streaming, legacy);
+}
+
+/// Best-of-N wall time for `run`
+fn measure(iterations: usize, mut run: impl FnMut() -> usize) -> Duration {
+    let mut best = Duration::MAX;
+    for _ in 0..iterations {
+        let start = Instant::now();
+        std::hint::black_box(run());
+        best = best.min(start.elapsed());
+    }
+    best
+}
+
+fn report(label: &str, megabytes: f64, elapsed: Duration, baseline: Duration) {
+    let seconds = elapsed.as_secs_f64().max(f64::EPSILON);
+    println!("  {:<10} {:>9.2} ms {:>9.1} MB/s {:>7.2}x",
+        label, seconds * 1000.0, megabytes / seconds, baseline.as_secs_f64() / seconds);
+}
+
+/// Generate roughly `target_bytes` of IR resembling optimizer output
+fn synthetic_module(target_bytes: usize) -> String {
+    let mut out = String::with_capacity(target_bytes + 4096);
+    out.push_str("; ModuleID = 'synthetic'\n");
+    out.push_str("source_filename = \"synthetic.c\"\n");
+    out.push_str("target datalayout = \"e-m:e-p270:32:32-p271:32:32-p272:64:64-i64:64-f80:128-n8:16:32:64-S128\"\n");
+    out.push_str("target triple = \"x86_64-unknown-linux-gnu\"\n\n");
+    out.push_str("%struct.node = type { i32, ptr, [4 x i64] }\n");
+    out.push_str("@.str = private unnamed_addr constant [14 x i8] c\"hello world\\0A\\00\", align 1\n\n");
+    out.push_str("declare i32 @printf(ptr noundef, ...)\n\n");
+
+    let mut index = 0;
+    while out.len() < target_bytes {
+        let _ = writeln!(out, "; Function Attrs: noinline nounwind uwtable");
+        let _ = writeln!(out, "define dso_local i64 @function_{}(ptr noundef %list, i32 noundef %n) #0 !dbg !{} {{", index, index + 10);
+        let _ = writeln!(out, "entry:");
+        let _ = writeln!(out, "  %n.addr = alloca i32, align 4");
+        let _ = writeln!(out, "  store i32 %n, ptr %n.addr, align 4, !tbaa !5");
+        let _ = writeln!(out, "  %cmp = icmp sgt i32 %n, 0");
+        let _ = writeln!(out, "  br i1 %cmp, label %for.body, label %for.end");
+        let _ = writeln!(out, "\nfor.body:                                         ; preds = %entry, %for.body");
+        let _ = writeln!(out, "  %i.0 = phi i32 [ 0, %entry ], [ %inc, %for.body ]");
+        let _ = writeln!(out, "  %sum.0 = phi i64 [ 0, %entry ], [ %add, %for.body ]");
+        let _ = writeln!(out, "  %idx = sext i32 %i.0 to i64");
+        let _ = writeln!(out, "  %field = getelementptr inbounds %struct.node, ptr %list, i64 %idx, i32 2, i64 1");
+        let _ = writeln!(out, "  %val = load i64, ptr %field, align 8, !tbaa !7");
+        let _ = writeln!(out, "  %add = add nsw i64 %sum.0, %val");
+        let _ = writeln!(out, "  %call = call i32 (ptr, ...) @printf(ptr noundef @.str, i64 noundef %add)");
+        let _ = writeln!(out, "  %inc = add nuw nsw i32 %i.0, 1");
+        let _ = writeln!(out, "  %exitcond = icmp eq i32 %inc, %n");
+        let _ = writeln!(out, "  br i1 %exitcond, label %for.end, label %for.body, !llvm.loop !9");
+        let _ = writeln!(out, "\nfor.end:                                          ; preds = %for.body, %entry");
+        let _ = writeln!(out, "  %result = phi i64 [ 0, %entry ], [ %add, %for.body ]");
+        let _ = writeln!(out, "  %scaled = fmul fast double 1.500000e+00, 2.500000e-01");
+        let _ = writeln!(out, "  ret i64 %result");
+        let _ = writeln!(out, "}}\n");
+        index += 1;
+    }
+
+    out.push_str("attributes #0 = { noinline nounwind uwtable \"frame-pointer\"=\"all\" }\n\n");
+    out.push_str("!5 = !{!6, !6, i64 0}\n");
+    out.push_str("!6 = !{!\"int\", !8, i64 0}\n");
+    out.push_str("!7 = !{!\"long\", !8, i64 0}\n");
+    out.push_str("!8 = !{!\"omnipotent char\", !10, i64 0}\n");
+    out.push_str("!9 = distinct !{!9, !11}\n");
+    out.push_str("!11 = !{!\"llvm.loop.mustprogress\"}\n");
+    out
+}
@@ -0,0 +1,115 @@
+//! IR verifier
+//!
+//! Parses a textual IR file and runs the verifier and the extended validation
+//! rules over it. Rules are selected by category or rule ID (see
+//! `--list-rules`): `--disable` drops them, `--warn` reports them as
+//! warnings, which do not fail verification unless `--Werror` is given.
+//! `--json` prints a machine-readable report for CI. `--lint` also reports
+//! valid IR that is likely undefined behavior; lint findings never fail
+//! verification and go to stderr with `--json`.
+//!
+//! Usage: verify [--json] [--lint] [--disable NAME]... [--warn NAME]... [--Werror] [--list-rules] file
+//!
+//! Exits with status 1 if the file cannot be read or parsed, or if any error
+//! is reported.
+
+use llvm_rust::diagnostics::{diagnostics_to_json, Rule, RuleCategory, Severity, VerifierOptions};
+use llvm_rust::lint::lint_module;
+use llvm_rust::parser::Parser;
+use llvm_rust::verification::verify_module_with_options;
+use llvm_rust::Context;
+use std::env;
+use std::fs;
+use std::process;
+
+const USAGE: &str = "Usage: verify [--json] [--lint] [--disable NAME]... [--warn NAME]... [--Werror] [--list-rules] file";
+
+/// The rules selected by a category name or rule ID
+fn rules_named(name: &str) -> Option<Vec<Rule>> {
+    if let Some(category) = RuleCategory::from_name(name) {
+        return Some(Rule::all().filter(|rule| rule.category() == category).collect());
+    }
+    Rule::from_id(name).map(|rule| vec![rule])
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    eprintln!("{}", USAGE);
+    process::exit(1);
+}
+
+fn main() {
+    let mut options = VerifierOptions::default();
+    let mut json = false;
+    let mut lint = false;
+    let mut file = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--lint" => lint = true,
+            "--Werror" => options.warnings_as_errors = true,
+            "--disable" | "--warn" => {
+                let Some(name) = args.next() else { usage_error(&format!("{} expects a category or rule", arg)) };
+                let Some(rules) = rules_named(&name) else { usage_error(&format!("unknown category or rule '{}'", name)) };
+                for rule in rules {
+                    if arg == "--disable" {
+                        options.disabled_rules.insert(rule);
+                    } else {
+                        options.severities.insert(rule, Severity::Warning);
+                    }
+                }
+            }
+            "--list-rules" => {
+                for rule in Rule::all() {
+                    println!("{:<24} {:<20} {}", rule.id(), rule.category().name(), rule.description());
+                }
+                return;
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ if file.is_none() => file = Some(arg),
+            _ => usage_error(&format!("unexpected argument '{}'", arg)),
+        }
+    }
+
+    let Some(path) = file else { usage_error("no input file") };
+    let source = match fs::read_to_string(&path) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("Error reading {}: {}", path, e);
+            process::exit(1);
+        }
+    };
+    let module = match Parser::new(Context::new()).parse_module(&source) {
+        Ok(module) => module,
+        Err(e) => {
+            eprintln!("{}: {:?}", path, e);
+            process::exit(1);
+        }
+    };
+
+    let diagnostics = verify_module_with_options(&module, &options);
+    if json {
+        println!("{}", diagnostics_to_json(&diagnostics));
+    } else {
+        for diagnostic in &diagnostics {
+            println!("{}: {}", path, diagnostic);
+        }
+    }
+    if lint {
+        for warning in lint_module(&module) {
+            if json {
+                eprintln!("{}: {}", path, warning);
+            } else {
+                println!("{}: {}", path, warning);
+            }
+        }
+    }
+    if diagnostics.iter().any(|d| d.is_error()) {
+        process::exit(1);
+    }
+}
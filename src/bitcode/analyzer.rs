@@ -0,0 +1,509 @@
+//! Bitstream Analysis
+//!
+//! Inspects the container of any bitstream file (LLVM IR, Clang ASTs and
+//! diagnostics, optimization remarks) without interpreting its records, in
+//! the manner of `llvm-bcanalyzer`. The stream is walked directly rather than
+//! through [`BitstreamReader`](super::bitstream::BitstreamReader) so that
+//! abbreviation definitions and the size of every block and record can be
+//! reported, and so that a malformed file still yields everything read before
+//! the error.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use super::bitstream::{
+    Abbrev, AbbrevOp, BitstreamCursor, BlockInfo, Record, BLOCKINFO_BLOCK_ID, BLOCKINFO_CODE_BLOCKNAME,
+    BLOCKINFO_CODE_SETBID, BLOCKINFO_CODE_SETRECORDNAME, DEFINE_ABBREV, END_BLOCK, ENTER_SUBBLOCK,
+    FIRST_APPLICATION_ABBREV, UNABBREV_RECORD,
+};
+use super::{codes, strip_wrapper, BitcodeError, BitcodeResult};
+
+/// Kind of bitstream file, identified by its magic number
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamType {
+    LlvmIr,
+    ClangSerializedAst,
+    ClangSerializedDiagnostics,
+    Remarks,
+    Unknown,
+}
+
+impl StreamType {
+    fn from_magic(magic: &[u8]) -> Self {
+        match magic {
+            [b'B', b'C', 0xC0, 0xDE] => StreamType::LlvmIr,
+            b"CPCH" => StreamType::ClangSerializedAst,
+            b"DIAG" => StreamType::ClangSerializedDiagnostics,
+            b"RMRK" => StreamType::Remarks,
+            _ => StreamType::Unknown,
+        }
+    }
+
+    /// Human-readable name of the stream type
+    pub fn description(&self) -> &'static str {
+        match self {
+            StreamType::LlvmIr => "LLVM IR",
+            StreamType::ClangSerializedAst => "Clang Serialized AST",
+            StreamType::ClangSerializedDiagnostics => "Clang Serialized Diagnostics",
+            StreamType::Remarks => "LLVM Remarks",
+            StreamType::Unknown => "Unknown",
+        }
+    }
+}
+
+/// What to produce besides statistics
+#[derive(Debug, Clone, Default)]
+pub struct AnalyzerOptions {
+    /// Produce a textual dump of every block, record and abbreviation
+    pub dump: bool,
+    /// Dump blobs that are not printable text byte by byte instead of by length
+    pub show_binary_blobs: bool,
+}
+
+/// Statistics for one record code within a block ID
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecordStats {
+    pub count: usize,
+    /// Records encoded with a defined abbreviation
+    pub abbreviated: usize,
+    /// Total size in bits, including abbreviation IDs
+    pub bits: usize,
+}
+
+/// Statistics for all blocks with the same ID
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockStats {
+    pub instances: usize,
+    /// Total size in bits, from after the block ID through the aligned
+    /// END_BLOCK, less the nested blocks, as `llvm-bcanalyzer` counts it;
+    /// the sizes of all blocks do not overlap
+    pub bits: usize,
+    pub subblocks: usize,
+    /// DEFINE_ABBREV entries, including those in BLOCKINFO
+    pub abbrevs: usize,
+    pub records: usize,
+    pub abbreviated_records: usize,
+    pub codes: BTreeMap<u32, RecordStats>,
+}
+
+/// Result of analyzing a bitstream file
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    pub stream_type: StreamType,
+    /// Size of the bitstream in bits, including the magic number but not a wrapper
+    pub stream_bits: usize,
+    pub top_level_blocks: usize,
+    pub blocks: BTreeMap<u32, BlockStats>,
+    /// Abbreviations and names registered through BLOCKINFO
+    pub block_info: BlockInfo,
+    /// Textual dump, if requested
+    pub dump: String,
+    /// Error that stopped the walk; everything before it is still reported
+    pub error: Option<BitcodeError>,
+}
+
+impl Analysis {
+    /// Name of a block ID, from BLOCKINFO or the LLVM IR block names
+    pub fn block_name(&self, id: u32) -> Option<String> {
+        if let Some(name) = self.block_info.block_names.get(&id) {
+            return Some(name.clone());
+        }
+        if id == BLOCKINFO_BLOCK_ID || self.stream_type == StreamType::LlvmIr {
+            return codes::block_name(id).map(str::to_string);
+        }
+        None
+    }
+
+    /// Name of a record code in a block, from BLOCKINFO or the LLVM IR record names
+    pub fn record_name(&self, block_id: u32, code: u32) -> Option<String> {
+        if let Some(name) = self.block_info.record_names.get(&(block_id, code)) {
+            return Some(name.clone());
+        }
+        if block_id == BLOCKINFO_BLOCK_ID || self.stream_type == StreamType::LlvmIr {
+            return codes::record_name(block_id, code).map(str::to_string);
+        }
+        None
+    }
+
+    fn block_label(&self, id: u32) -> String {
+        self.block_name(id).unwrap_or_else(|| format!("UnknownBlock{}", id))
+    }
+
+    fn record_label(&self, block_id: u32, code: u32) -> String {
+        self.record_name(block_id, code).unwrap_or_else(|| format!("UnknownCode{}", code))
+    }
+
+    /// Stream size and per-block statistics with record histograms
+    pub fn statistics(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "         Total size: {}", format_size(self.stream_bits));
+        let _ = writeln!(out, "        Stream type: {}", self.stream_type.description());
+        let _ = writeln!(out, "  # Toplevel Blocks: {}", self.top_level_blocks);
+        let _ = writeln!(out);
+        let _ = writeln!(out, "Per-block Summary:");
+        for (&id, stats) in &self.blocks {
+            let _ = writeln!(out, "  Block ID #{} ({}):", id, self.block_label(id));
+            let _ = writeln!(out, "      Num Instances: {}", stats.instances);
+            let _ = writeln!(out, "         Total Size: {}", format_size(stats.bits));
+            let _ = writeln!(out, "    Percent of file: {:.4}%", percent(stats.bits, self.stream_bits));
+            if stats.instances > 1 {
+                let _ = writeln!(out, "       Average Size: {:.2}b", stats.bits as f64 / stats.instances as f64);
+            }
+            let _ = writeln!(out, "      Num SubBlocks: {}", stats.subblocks);
+            let _ = writeln!(out, "        Num Abbrevs: {}", stats.abbrevs);
+            let _ = writeln!(out, "        Num Records: {}", stats.records);
+            if stats.records > 0 {
+                let _ = writeln!(out, "    Percent Abbrevs: {:.4}%", percent(stats.abbreviated_records, stats.records));
+                let _ = writeln!(out);
+                let _ = writeln!(out, "    Record Histogram:");
+                let _ = writeln!(out, "        {:>7} {:>9} {:>9} {:>7}  Record Kind", "Count", "# Bits", "b/Rec", "% Abv");
+                let mut histogram: Vec<_> = stats.codes.iter().collect();
+                histogram.sort_by(|a, b| b.1.count.cmp(&a.1.count).then(a.0.cmp(b.0)));
+                for (&code, record) in histogram {
+                    let abbreviated = if record.abbreviated > 0 {
+                        format!("{:.2}", percent(record.abbreviated, record.count))
+                    } else {
+                        String::new()
+                    };
+                    let _ = writeln!(out, "        {:>7} {:>9} {:>9.1} {:>7}  {}", record.count, record.bits,
+                        record.bits as f64 / record.count as f64, abbreviated, self.record_label(id, code));
+                }
+            }
+            let _ = writeln!(out);
+        }
+        out
+    }
+}
+
+/// Walk a bitstream file, optionally dumping it, and collect statistics
+///
+/// Only a missing or truncated magic number is an error; a malformed stream
+/// is reported through [`Analysis::error`].
+pub fn analyze(bytes: &[u8], options: &AnalyzerOptions) -> BitcodeResult<Analysis> {
+    let bytes = strip_wrapper(bytes)?;
+    if bytes.len() < 4 {
+        return Err(BitcodeError::InvalidMagic);
+    }
+    let mut walker = Walker {
+        cursor: BitstreamCursor::new(&bytes[4..]),
+        options,
+        analysis: Analysis {
+            stream_type: StreamType::from_magic(&bytes[..4]),
+            stream_bits: bytes.len() * 8,
+            top_level_blocks: 0,
+            blocks: BTreeMap::new(),
+            block_info: BlockInfo::default(),
+            dump: String::new(),
+            error: None,
+        },
+    };
+    if let Err(error) = walker.walk() {
+        walker.analysis.error = Some(error);
+    }
+    Ok(walker.analysis)
+}
+
+struct Walker<'a, 'o> {
+    cursor: BitstreamCursor<'a>,
+    options: &'o AnalyzerOptions,
+    analysis: Analysis,
+}
+
+impl Walker<'_, '_> {
+    fn walk(&mut self) -> BitcodeResult<()> {
+        // Streams are padded to a multiple of four bytes
+        while self.cursor.bit_len() - self.cursor.bit_position() >= 32 {
+            let offset = self.cursor.bit_position();
+            match self.cursor.read(2)? as u32 {
+                ENTER_SUBBLOCK => {
+                    self.analysis.top_level_blocks += 1;
+                    self.walk_block(offset, 0)?;
+                }
+                END_BLOCK => break,
+                other => {
+                    return Err(BitcodeError::MalformedStream {
+                        message: format!("expected a top-level block, found abbreviation {}", other),
+                        bit_offset: offset,
+                    })
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Walk a block whose ENTER_SUBBLOCK abbreviation ID has just been
+    /// consumed, returning the bits it spans after its block ID
+    fn walk_block(&mut self, bit_offset: usize, depth: usize) -> BitcodeResult<usize> {
+        let id = self.cursor.read_vbr(8)? as u32;
+        let start = self.cursor.bit_position();
+        let abbrev_width = self.cursor.read_vbr(4)? as u32;
+        self.cursor.align32()?;
+        let length_words = self.cursor.read(32)? as usize;
+        let end = self.cursor.bit_position() + length_words * 32;
+        if end > self.cursor.bit_len() {
+            return Err(BitcodeError::MalformedStream {
+                message: format!("block {} extends past the end of the stream", id),
+                bit_offset,
+            });
+        }
+        if abbrev_width == 0 || abbrev_width > 32 {
+            return Err(BitcodeError::MalformedStream {
+                message: format!("invalid abbreviation width {}", abbrev_width),
+                bit_offset,
+            });
+        }
+
+        let label = self.analysis.block_label(id);
+        self.line(depth, format_args!("<{} NumWords={} BlockCodeSize={}>", label, length_words, abbrev_width));
+        self.analysis.blocks.entry(id).or_default().instances += 1;
+        let mut abbrevs = self.analysis.block_info.abbrevs.get(&id).cloned().unwrap_or_default();
+        let mut current_info_block = None;
+        // Bits of nested blocks, which count toward their own IDs only
+        let mut nested_bits = 0;
+
+        loop {
+            let entry_offset = self.cursor.bit_position();
+            match self.cursor.read(abbrev_width)? as u32 {
+                END_BLOCK => {
+                    self.cursor.align32()?;
+                    let span = self.cursor.bit_position() - start;
+                    self.analysis.blocks.entry(id).or_default().bits += span - nested_bits;
+                    self.line(depth, format_args!("</{}>", label));
+                    return Ok(span);
+                }
+                ENTER_SUBBLOCK => {
+                    self.analysis.blocks.entry(id).or_default().subblocks += 1;
+                    nested_bits += self.walk_block(entry_offset, depth + 1)?;
+                }
+                DEFINE_ABBREV => {
+                    let abbrev = self.cursor.read_abbrev()?;
+                    self.analysis.blocks.entry(id).or_default().abbrevs += 1;
+                    let spelling = abbrev_spelling(&abbrev);
+                    if id == BLOCKINFO_BLOCK_ID {
+                        let target = current_info_block.ok_or_else(|| BitcodeError::MalformedStream {
+                            message: "abbreviation in BLOCKINFO before SETBID".to_string(),
+                            bit_offset: entry_offset,
+                        })?;
+                        let target_abbrevs = self.analysis.block_info.abbrevs.entry(target).or_default();
+                        let abbrev_id = FIRST_APPLICATION_ABBREV as usize + target_abbrevs.len();
+                        target_abbrevs.push(abbrev);
+                        let target_label = self.analysis.block_label(target);
+                        self.line(depth + 1, format_args!("<DEFINE_ABBREV block={} id={}{}/>", target_label, abbrev_id, spelling));
+                    } else {
+                        let abbrev_id = FIRST_APPLICATION_ABBREV as usize + abbrevs.len();
+                        abbrevs.push(abbrev);
+                        self.line(depth + 1, format_args!("<DEFINE_ABBREV id={}{}/>", abbrev_id, spelling));
+                    }
+                }
+                abbrev_id => {
+                    let record = if abbrev_id == UNABBREV_RECORD {
+                        self.cursor.read_unabbrev_record()?
+                    } else {
+                        let abbrev = abbrevs.get((abbrev_id - FIRST_APPLICATION_ABBREV) as usize)
+                            .ok_or_else(|| BitcodeError::MalformedStream {
+                                message: format!("undefined abbreviation {} in block {}", abbrev_id, id),
+                                bit_offset: entry_offset,
+                            })?;
+                        self.cursor.read_abbrev_record(abbrev, abbrev_id)?
+                    };
+                    self.count_record(id, &record, self.cursor.bit_position() - entry_offset);
+                    if id == BLOCKINFO_BLOCK_ID {
+                        self.apply_block_info(&record, &mut current_info_block);
+                    }
+                    if self.options.dump {
+                        let text = self.record_spelling(id, &record);
+                        self.line(depth + 1, format_args!("{}", text));
+                    }
+                }
+            }
+            if self.cursor.bit_position() > end {
+                return Err(BitcodeError::MalformedStream {
+                    message: format!("block {} overruns its declared length", id),
+                    bit_offset: entry_offset,
+                });
+            }
+        }
+    }
+
+    fn count_record(&mut self, block_id: u32, record: &Record, bits: usize) {
+        let abbreviated = record.abbrev_id != UNABBREV_RECORD;
+        let stats = self.analysis.blocks.entry(block_id).or_default();
+        stats.records += 1;
+        stats.abbreviated_records += abbreviated as usize;
+        let code = stats.codes.entry(record.code).or_default();
+        code.count += 1;
+        code.abbreviated += abbreviated as usize;
+        code.bits += bits;
+    }
+
+    fn apply_block_info(&mut self, record: &Record, current_info_block: &mut Option<u32>) {
+        let info = &mut self.analysis.block_info;
+        match record.code {
+            BLOCKINFO_CODE_SETBID => *current_info_block = record.ops.first().map(|&b| b as u32),
+            BLOCKINFO_CODE_BLOCKNAME => {
+                if let Some(target) = *current_info_block {
+                    info.block_names.insert(target, record.string());
+                }
+            }
+            BLOCKINFO_CODE_SETRECORDNAME => {
+                if let (Some(target), Some(&code)) = (*current_info_block, record.ops.first()) {
+                    info.record_names.insert((target, code as u32), record.string_from(1));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn record_spelling(&self, block_id: u32, record: &Record) -> String {
+        let mut text = format!("<{}", self.analysis.record_label(block_id, record.code));
+        if record.abbrev_id != UNABBREV_RECORD {
+            let _ = write!(text, " abbrevid={}", record.abbrev_id);
+        }
+        for (index, op) in record.ops.iter().enumerate() {
+            let _ = write!(text, " op{}={}", index, op);
+        }
+        text.push_str("/>");
+        if !record.ops.is_empty() && record.ops.iter().all(|&op| is_printable(op)) {
+            let _ = write!(text, " record string = '{}'", record.string());
+        }
+        if let Some(blob) = &record.blob {
+            if blob.iter().all(|&b| is_printable(b as u64)) {
+                let _ = write!(text, " blob data = '{}'", String::from_utf8_lossy(blob));
+            } else if self.options.show_binary_blobs {
+                let escaped: String = blob.iter().map(|&b| match b {
+                    0x20..=0x7E if b != b'\\' => (b as char).to_string(),
+                    _ => format!("\\{:02X}", b),
+                }).collect();
+                let _ = write!(text, " blob data = '{}'", escaped);
+            } else {
+                let _ = write!(text, " blob data = unprintable, {} bytes", blob.len());
+            }
+        }
+        text
+    }
+
+    fn line(&mut self, depth: usize, args: std::fmt::Arguments) {
+        if self.options.dump {
+            let _ = writeln!(self.analysis.dump, "{:indent$}{}", "", args, indent = depth * 2);
+        }
+    }
+}
+
+fn abbrev_spelling(abbrev: &Abbrev) -> String {
+    abbrev.ops.iter().map(|op| match op {
+        AbbrevOp::Literal(value) => format!(" literal({})", value),
+        AbbrevOp::Fixed(width) => format!(" fixed({})", width),
+        AbbrevOp::Vbr(width) => format!(" vbr({})", width),
+        AbbrevOp::Array => " array".to_string(),
+        AbbrevOp::Char6 => " char6".to_string(),
+        AbbrevOp::Blob => " blob".to_string(),
+    }).collect()
+}
+
+fn is_printable(value: u64) -> bool {
+    (0x20..=0x7E).contains(&value)
+}
+
+fn format_size(bits: usize) -> String {
+    format!("{}b/{:.2}B/{}W", bits, bits as f64 / 8.0, bits / 32)
+}
+
+fn percent(part: usize, whole: usize) -> f64 {
+    if whole == 0 { 0.0 } else { part as f64 * 100.0 / whole as f64 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bitcode::bitstream::BitstreamWriter;
+    use crate::bitcode::BITCODE_MAGIC;
+
+    fn sample_stream() -> Vec<u8> {
+        let mut writer = BitstreamWriter::new();
+        writer.enter_block_info();
+        writer.set_block_info_target(8);
+        writer.define_abbrev(Abbrev { ops: vec![AbbrevOp::Literal(2), AbbrevOp::Array, AbbrevOp::Char6] });
+        writer.exit_block();
+        writer.enter_subblock(8, 3);
+        writer.emit_record(1, &[2]);
+        writer.emit_record_with_abbrev(4, 2, &[b'x' as u64, b'8' as u64], None);
+        let blob = writer.define_abbrev(Abbrev { ops: vec![AbbrevOp::Literal(16), AbbrevOp::Blob] });
+        writer.emit_record_with_abbrev(blob, 16, &[], Some(&[0, 1, 2]));
+        writer.enter_subblock(17, 4);
+        writer.emit_record(1, &[0]);
+        writer.exit_block();
+        writer.exit_block();
+        let mut bytes = BITCODE_MAGIC.to_vec();
+        bytes.extend(writer.finish());
+        bytes
+    }
+
+    #[test]
+    fn test_statistics() {
+        let bytes = sample_stream();
+        let analysis = analyze(&bytes, &AnalyzerOptions::default()).unwrap();
+        assert!(analysis.error.is_none());
+        assert_eq!(analysis.stream_type, StreamType::LlvmIr);
+        assert_eq!(analysis.stream_bits, bytes.len() * 8);
+        assert_eq!(analysis.top_level_blocks, 2);
+        assert!(analysis.dump.is_empty());
+
+        let info = &analysis.blocks[&BLOCKINFO_BLOCK_ID];
+        assert_eq!((info.instances, info.abbrevs, info.records), (1, 1, 1));
+        let module = &analysis.blocks[&8];
+        assert_eq!((module.subblocks, module.abbrevs, module.records, module.abbreviated_records), (1, 1, 3, 2));
+        assert_eq!(module.codes[&2].abbreviated, 1);
+        let types = &analysis.blocks[&17];
+        // Block sizes exclude nested blocks, so with the magic number and
+        // the abbreviation and block IDs that enter the two top-level
+        // blocks they add up to the stream
+        assert!(types.bits > 0 && module.bits > 0);
+        assert_eq!(info.bits + module.bits + types.bits + 32 + 2 * (2 + 8), analysis.stream_bits);
+        let percents: f64 = analysis.blocks.values().map(|stats| percent(stats.bits, analysis.stream_bits)).sum();
+        assert!(percents < 100.0);
+
+        let report = analysis.statistics();
+        assert!(report.contains("Stream type: LLVM IR"));
+        assert!(report.contains("Block ID #8 (MODULE_BLOCK):"));
+        assert!(report.contains("TRIPLE"));
+    }
+
+    #[test]
+    fn test_dump() {
+        let options = AnalyzerOptions { dump: true, show_binary_blobs: true };
+        let analysis = analyze(&sample_stream(), &options).unwrap();
+        let lines: Vec<&str> = analysis.dump.lines().collect();
+        assert_eq!(lines[0], "<BLOCKINFO_BLOCK NumWords=2 BlockCodeSize=2>");
+        assert_eq!(lines[1], "  <SETBID op0=8/>");
+        assert_eq!(lines[2], "  <DEFINE_ABBREV block=MODULE_BLOCK id=4 literal(2) array char6/>");
+        assert_eq!(lines[5], "  <VERSION op0=2/>");
+        assert_eq!(lines[6], "  <TRIPLE abbrevid=4 op0=120 op1=56/> record string = 'x8'");
+        assert_eq!(lines[7], "  <DEFINE_ABBREV id=5 literal(16) blob/>");
+        assert_eq!(lines[8], "  <SOURCE_FILENAME abbrevid=5/> blob data = '\\00\\01\\02'");
+        assert_eq!(lines[10], "    <NUMENTRY op0=0/>");
+        assert_eq!(lines.last(), Some(&"</MODULE_BLOCK>"));
+    }
+
+    #[test]
+    fn test_malformed_stream_keeps_partial_results() {
+        let mut bytes = sample_stream();
+        bytes.truncate(bytes.len() - 8);
+        let analysis = analyze(&bytes, &AnalyzerOptions { dump: true, ..Default::default() }).unwrap();
+        assert!(matches!(analysis.error, Some(BitcodeError::MalformedStream { .. })));
+        assert_eq!(analysis.blocks[&BLOCKINFO_BLOCK_ID].instances, 1);
+        assert!(analysis.dump.contains("</BLOCKINFO_BLOCK>"));
+
+        assert_eq!(analyze(b"BC", &AnalyzerOptions::default()).unwrap_err(), BitcodeError::InvalidMagic);
+    }
+
+    #[test]
+    fn test_other_stream_types() {
+        let mut bytes = b"DIAG".to_vec();
+        bytes.extend(&sample_stream()[4..]);
+        let analysis = analyze(&bytes, &AnalyzerOptions::default()).unwrap();
+        assert_eq!(analysis.stream_type, StreamType::ClangSerializedDiagnostics);
+        // LLVM IR names only apply to IR streams; BLOCKINFO is generic
+        assert_eq!(analysis.block_name(8), None);
+        assert_eq!(analysis.block_name(0).as_deref(), Some("BLOCKINFO_BLOCK"));
+        assert!(analysis.statistics().contains("Block ID #8 (UnknownBlock8):"));
+    }
+}
@@ -0,0 +1,825 @@
+//! Bitstream Container Format
+//!
+//! LLVM bitcode is stored in a generic bitstream: a sequence of nested
+//! blocks, each holding records whose operands are encoded either
+//! unabbreviated (as VBR6 values) or through abbreviations defined in the
+//! block or in the BLOCKINFO block. This module decodes that container into
+//! a tree of [`Block`]s without interpreting any record, and encodes it again
+//! with [`BitstreamWriter`].
+
+use std::collections::HashMap;
+use super::{BitcodeError, BitcodeResult};
+
+/// Abbreviation ID for the end of the current block
+pub const END_BLOCK: u32 = 0;
+/// Abbreviation ID for the start of a nested block
+pub const ENTER_SUBBLOCK: u32 = 1;
+/// Abbreviation ID for an abbreviation definition
+pub const DEFINE_ABBREV: u32 = 2;
+/// Abbreviation ID for a record with VBR6 operands
+pub const UNABBREV_RECORD: u32 = 3;
+/// First abbreviation ID available to defined abbreviations
+pub const FIRST_APPLICATION_ABBREV: u32 = 4;
+
+/// Block ID of the BLOCKINFO block
+pub const BLOCKINFO_BLOCK_ID: u32 = 0;
+/// BLOCKINFO record selecting the block that following abbreviations apply to
+pub const BLOCKINFO_CODE_SETBID: u32 = 1;
+/// BLOCKINFO record naming a block
+pub const BLOCKINFO_CODE_BLOCKNAME: u32 = 2;
+/// BLOCKINFO record naming a record code
+pub const BLOCKINFO_CODE_SETRECORDNAME: u32 = 3;
+
+/// Reads fixed-width and variable-width fields from a bitstream
+pub struct BitstreamCursor<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitstreamCursor<'a> {
+    /// Create a cursor at the start of `data`
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    /// Current position in bits
+    pub fn bit_position(&self) -> usize {
+        self.position
+    }
+
+    /// Move to an absolute bit position
+    pub fn jump_to_bit(&mut self, position: usize) -> BitcodeResult<()> {
+        if position > self.data.len() * 8 {
+            return Err(BitcodeError::UnexpectedEOF { bit_offset: position });
+        }
+        self.position = position;
+        Ok(())
+    }
+
+    /// Total size of the stream in bits
+    pub fn bit_len(&self) -> usize {
+        self.data.len() * 8
+    }
+
+    /// Whether the cursor has consumed the whole stream
+    pub fn at_end(&self) -> bool {
+        self.position >= self.bit_len()
+    }
+
+    /// Read a fixed-width field of up to 64 bits
+    pub fn read(&mut self, width: u32) -> BitcodeResult<u64> {
+        if width > 64 {
+            return Err(self.malformed(format!("fixed field width {} exceeds 64 bits", width)));
+        }
+        if self.position + width as usize > self.bit_len() {
+            return Err(BitcodeError::UnexpectedEOF { bit_offset: self.position });
+        }
+        let mut value = 0u64;
+        let mut read = 0;
+        while read < width {
+            let byte = self.data[self.position / 8] as u64;
+            let offset = (self.position % 8) as u32;
+            let take = (8 - offset).min(width - read);
+            value |= ((byte >> offset) & ((1 << take) - 1)) << read;
+            read += take;
+            self.position += take as usize;
+        }
+        Ok(value)
+    }
+
+    /// Read a variable-width field made of `width`-bit chunks
+    pub fn read_vbr(&mut self, width: u32) -> BitcodeResult<u64> {
+        if !(2..=32).contains(&width) {
+            return Err(self.malformed(format!("invalid VBR chunk width {}", width)));
+        }
+        let continuation = 1u64 << (width - 1);
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let chunk = self.read(width)?;
+            if shift >= 64 {
+                return Err(self.malformed("VBR value does not fit in 64 bits".to_string()));
+            }
+            value |= (chunk & (continuation - 1)) << shift;
+            if chunk & continuation == 0 {
+                return Ok(value);
+            }
+            shift += width - 1;
+        }
+    }
+
+    /// Skip to the next 32-bit boundary
+    pub fn align32(&mut self) -> BitcodeResult<()> {
+        let aligned = (self.position + 31) & !31;
+        self.jump_to_bit(aligned.min(self.bit_len()))
+    }
+
+    /// Read `count` whole bytes; the cursor must be byte aligned
+    pub fn read_bytes(&mut self, count: usize) -> BitcodeResult<&'a [u8]> {
+        let start = self.position / 8;
+        if !self.position.is_multiple_of(8) || start + count > self.data.len() {
+            return Err(BitcodeError::UnexpectedEOF { bit_offset: self.position });
+        }
+        self.position += count * 8;
+        Ok(&self.data[start..start + count])
+    }
+
+    /// Read the body of a DEFINE_ABBREV whose abbreviation ID has just been consumed
+    pub fn read_abbrev(&mut self) -> BitcodeResult<Abbrev> {
+        let count = self.read_vbr(5)? as usize;
+        let mut ops = Vec::with_capacity(count);
+        for _ in 0..count {
+            let is_literal = self.read(1)? == 1;
+            if is_literal {
+                ops.push(AbbrevOp::Literal(self.read_vbr(8)?));
+            } else {
+                let op = match self.read(3)? {
+                    1 => AbbrevOp::Fixed(self.read_vbr(5)? as u32),
+                    2 => AbbrevOp::Vbr(self.read_vbr(5)? as u32),
+                    3 => AbbrevOp::Array,
+                    4 => AbbrevOp::Char6,
+                    5 => AbbrevOp::Blob,
+                    other => {
+                        return Err(self.malformed(format!("unknown abbreviation encoding {}", other)))
+                    }
+                };
+                match op {
+                    AbbrevOp::Fixed(width) if width > 64 => {
+                        return Err(self.malformed(format!("fixed width {} too large", width)))
+                    }
+                    AbbrevOp::Vbr(width) if !(2..=32).contains(&width) => {
+                        return Err(self.malformed(format!("invalid VBR width {}", width)))
+                    }
+                    // A zero-width field always reads as zero
+                    AbbrevOp::Fixed(0) | AbbrevOp::Vbr(0) => ops.push(AbbrevOp::Literal(0)),
+                    op => ops.push(op),
+                }
+            }
+        }
+        for (index, op) in ops.iter().enumerate() {
+            let is_last = index + 1 == ops.len();
+            match op {
+                AbbrevOp::Array if index + 2 != ops.len() => {
+                    return Err(self.malformed("array must be followed by exactly one element encoding".to_string()))
+                }
+                AbbrevOp::Blob if !is_last => {
+                    return Err(self.malformed("blob must be the last abbreviation operand".to_string()))
+                }
+                _ => {}
+            }
+        }
+        Ok(Abbrev { ops })
+    }
+
+    /// Read the body of an UNABBREV_RECORD whose abbreviation ID has just been consumed
+    pub fn read_unabbrev_record(&mut self) -> BitcodeResult<Record> {
+        let code = self.read_vbr(6)? as u32;
+        let count = self.read_vbr(6)? as usize;
+        let mut ops = Vec::with_capacity(count.min(1 << 16));
+        for _ in 0..count {
+            ops.push(self.read_vbr(6)?);
+        }
+        Ok(Record { code, ops, blob: None, abbrev_id: UNABBREV_RECORD })
+    }
+
+    /// Read a record encoded with `abbrev`, whose ID `abbrev_id` has just been consumed
+    pub fn read_abbrev_record(&mut self, abbrev: &Abbrev, abbrev_id: u32) -> BitcodeResult<Record> {
+        let mut values = Vec::new();
+        let mut blob = None;
+        let mut index = 0;
+        while index < abbrev.ops.len() {
+            match &abbrev.ops[index] {
+                AbbrevOp::Array => {
+                    let length = self.read_vbr(6)? as usize;
+                    let element = &abbrev.ops[index + 1];
+                    for _ in 0..length {
+                        values.push(self.read_scalar(element)?);
+                    }
+                    index += 2;
+                    continue;
+                }
+                AbbrevOp::Blob => {
+                    let length = self.read_vbr(6)? as usize;
+                    self.align32()?;
+                    blob = Some(self.read_bytes(length)?.to_vec());
+                    self.align32()?;
+                }
+                scalar => values.push(self.read_scalar(scalar)?),
+            }
+            index += 1;
+        }
+        if values.is_empty() {
+            return Err(self.malformed("abbreviated record has no code".to_string()));
+        }
+        let code = values.remove(0) as u32;
+        Ok(Record { code, ops: values, blob, abbrev_id })
+    }
+
+    fn read_scalar(&mut self, op: &AbbrevOp) -> BitcodeResult<u64> {
+        match op {
+            AbbrevOp::Literal(value) => Ok(*value),
+            AbbrevOp::Fixed(width) => self.read(*width),
+            AbbrevOp::Vbr(width) => self.read_vbr(*width),
+            AbbrevOp::Char6 => Ok(decode_char6(self.read(6)? as u8) as u64),
+            AbbrevOp::Array | AbbrevOp::Blob => {
+                Err(self.malformed("array elements must be scalar".to_string()))
+            }
+        }
+    }
+    fn malformed(&self, message: String) -> BitcodeError {
+        BitcodeError::MalformedStream { message, bit_offset: self.position }
+    }
+}
+
+/// Operand encoding in an abbreviation definition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AbbrevOp {
+    /// A constant operand that takes no bits
+    Literal(u64),
+    /// A fixed-width operand
+    Fixed(u32),
+    /// A variable-width operand with the given chunk size
+    Vbr(u32),
+    /// A VBR6 length followed by that many elements of the next operand
+    Array,
+    /// A six-bit character from `[a-zA-Z0-9._]`
+    Char6,
+    /// A VBR6 length followed by 32-bit aligned bytes
+    Blob,
+}
+
+/// An abbreviation: the operand encodings of a record, including its code
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Abbrev {
+    pub ops: Vec<AbbrevOp>,
+}
+
+/// A decoded record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    /// Record code, meaningful relative to the enclosing block
+    pub code: u32,
+    /// Operands, excluding the code and any blob
+    pub ops: Vec<u64>,
+    /// Blob operand, if the abbreviation had one
+    pub blob: Option<Vec<u8>>,
+    /// Abbreviation ID used to encode the record (`UNABBREV_RECORD` if none)
+    pub abbrev_id: u32,
+}
+
+impl Record {
+    /// Interpret all operands as characters
+    pub fn string(&self) -> String {
+        self.string_from(0)
+    }
+
+    /// Interpret the operands from `start` on as characters
+    pub fn string_from(&self, start: usize) -> String {
+        self.ops.iter().skip(start).map(|&c| c as u8 as char).collect()
+    }
+}
+
+/// An entry in a block, in stream order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockEntry {
+    Record(Record),
+    Block(Block),
+}
+
+/// A decoded block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub id: u32,
+    /// Width of abbreviation IDs inside the block
+    pub abbrev_width: u32,
+    /// Bit offset of the block's ENTER_SUBBLOCK in the stream
+    pub bit_offset: usize,
+    pub entries: Vec<BlockEntry>,
+}
+
+impl Block {
+    /// Records directly inside this block
+    pub fn records(&self) -> impl Iterator<Item = &Record> {
+        self.entries.iter().filter_map(|entry| match entry {
+            BlockEntry::Record(record) => Some(record),
+            BlockEntry::Block(_) => None,
+        })
+    }
+
+    /// Sub-blocks directly inside this block
+    pub fn blocks(&self) -> impl Iterator<Item = &Block> {
+        self.entries.iter().filter_map(|entry| match entry {
+            BlockEntry::Block(block) => Some(block),
+            BlockEntry::Record(_) => None,
+        })
+    }
+
+    /// First sub-block with the given ID
+    pub fn block(&self, id: u32) -> Option<&Block> {
+        self.blocks().find(|block| block.id == id)
+    }
+}
+
+/// Abbreviations and names registered through BLOCKINFO blocks
+#[derive(Debug, Clone, Default)]
+pub struct BlockInfo {
+    pub abbrevs: HashMap<u32, Vec<Abbrev>>,
+    pub block_names: HashMap<u32, String>,
+    pub record_names: HashMap<(u32, u32), String>,
+}
+
+/// Decodes a bitstream into blocks
+pub struct BitstreamReader<'a> {
+    cursor: BitstreamCursor<'a>,
+    block_info: BlockInfo,
+}
+
+impl<'a> BitstreamReader<'a> {
+    /// Create a reader over a raw bitstream (after the magic number)
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { cursor: BitstreamCursor::new(data), block_info: BlockInfo::default() }
+    }
+
+    /// Abbreviations and names collected from BLOCKINFO so far
+    pub fn block_info(&self) -> &BlockInfo {
+        &self.block_info
+    }
+
+    /// Read every top-level block in the stream
+    pub fn read_top_level(&mut self) -> BitcodeResult<Vec<Block>> {
+        let mut blocks = Vec::new();
+        // Streams are padded to a multiple of four bytes
+        while self.cursor.bit_len() - self.cursor.bit_position() >= 32 {
+            let offset = self.cursor.bit_position();
+            match self.cursor.read(2)? as u32 {
+                ENTER_SUBBLOCK => blocks.push(self.read_block(offset)?),
+                // Zero padding after the last block
+                END_BLOCK => break,
+                other => {
+                    return Err(BitcodeError::MalformedStream {
+                        message: format!("expected a top-level block, found abbreviation {}", other),
+                        bit_offset: offset,
+                    })
+                }
+            }
+        }
+        Ok(blocks)
+    }
+
+    /// Read a block whose ENTER_SUBBLOCK abbreviation ID has just been consumed
+    fn read_block(&mut self, bit_offset: usize) -> BitcodeResult<Block> {
+        let id = self.cursor.read_vbr(8)? as u32;
+        let abbrev_width = self.cursor.read_vbr(4)? as u32;
+        self.cursor.align32()?;
+        let length_words = self.cursor.read(32)? as usize;
+        let end = self.cursor.bit_position() + length_words * 32;
+        if end > self.cursor.bit_len() {
+            return Err(BitcodeError::MalformedStream {
+                message: format!("block {} extends past the end of the stream", id),
+                bit_offset,
+            });
+        }
+        if abbrev_width == 0 || abbrev_width > 32 {
+            return Err(BitcodeError::MalformedStream {
+                message: format!("invalid abbreviation width {}", abbrev_width),
+                bit_offset,
+            });
+        }
+
+        let mut block = Block { id, abbrev_width, bit_offset, entries: Vec::new() };
+        let mut abbrevs = self.block_info.abbrevs.get(&id).cloned().unwrap_or_default();
+        let mut current_info_block = None;
+
+        loop {
+            let entry_offset = self.cursor.bit_position();
+            match self.cursor.read(abbrev_width)? as u32 {
+                END_BLOCK => {
+                    self.cursor.align32()?;
+                    return Ok(block);
+                }
+                ENTER_SUBBLOCK => {
+                    let sub = self.read_block(entry_offset)?;
+                    block.entries.push(BlockEntry::Block(sub));
+                }
+                DEFINE_ABBREV => {
+                    let abbrev = self.cursor.read_abbrev()?;
+                    if id == BLOCKINFO_BLOCK_ID {
+                        let target = current_info_block.ok_or_else(|| BitcodeError::MalformedStream {
+                            message: "abbreviation in BLOCKINFO before SETBID".to_string(),
+                            bit_offset: entry_offset,
+                        })?;
+                        self.block_info.abbrevs.entry(target).or_default().push(abbrev);
+                    } else {
+                        abbrevs.push(abbrev);
+                    }
+                }
+                abbrev_id => {
+                    let record = if abbrev_id == UNABBREV_RECORD {
+                        self.cursor.read_unabbrev_record()?
+                    } else {
+                        let abbrev = abbrevs.get((abbrev_id - FIRST_APPLICATION_ABBREV) as usize)
+                            .ok_or_else(|| BitcodeError::MalformedStream {
+                                message: format!("undefined abbreviation {} in block {}", abbrev_id, id),
+                                bit_offset: entry_offset,
+                            })?
+                            .clone();
+                        self.cursor.read_abbrev_record(&abbrev, abbrev_id)?
+                    };
+                    if id == BLOCKINFO_BLOCK_ID {
+                        match record.code {
+                            BLOCKINFO_CODE_SETBID => current_info_block = record.ops.first().map(|&b| b as u32),
+                            BLOCKINFO_CODE_BLOCKNAME => {
+                                if let Some(target) = current_info_block {
+                                    self.block_info.block_names.insert(target, record.string());
+                                }
+                            }
+                            BLOCKINFO_CODE_SETRECORDNAME => {
+                                if let (Some(target), Some(&code)) = (current_info_block, record.ops.first()) {
+                                    self.block_info.record_names.insert((target, code as u32), record.string_from(1));
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                    block.entries.push(BlockEntry::Record(record));
+                }
+            }
+            if self.cursor.bit_position() > end {
+                return Err(BitcodeError::MalformedStream {
+                    message: format!("block {} overruns its declared length", id),
+                    bit_offset: entry_offset,
+                });
+            }
+        }
+    }
+}
+
+/// A block opened by [`BitstreamWriter::enter_subblock`]
+struct OpenBlock {
+    id: u32,
+    /// Abbreviation width and abbreviations of the enclosing block
+    outer_width: u32,
+    outer_abbrevs: Vec<Abbrev>,
+    /// Bit position of the block's length word
+    length_position: usize,
+}
+
+/// Encodes records and blocks into a bitstream
+pub struct BitstreamWriter {
+    data: Vec<u8>,
+    position: usize,
+    abbrev_width: u32,
+    abbrevs: Vec<Abbrev>,
+    blocks: Vec<OpenBlock>,
+    block_info: HashMap<u32, Vec<Abbrev>>,
+    /// Block that abbreviations defined inside BLOCKINFO apply to
+    info_target: Option<u32>,
+}
+
+impl Default for BitstreamWriter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BitstreamWriter {
+    /// Create an empty writer at the top level (abbreviation width 2)
+    pub fn new() -> Self {
+        Self {
+            data: Vec::new(),
+            position: 0,
+            abbrev_width: 2,
+            abbrevs: Vec::new(),
+            blocks: Vec::new(),
+            block_info: HashMap::new(),
+            info_target: None,
+        }
+    }
+
+    /// Current position in bits
+    pub fn bit_position(&self) -> usize {
+        self.position
+    }
+
+    /// Write the low `width` bits of `value`
+    pub fn emit(&mut self, value: u64, width: u32) {
+        self.write_at(self.position, value, width);
+        self.position += width as usize;
+    }
+
+    /// Write `value` as a variable-width integer with `width`-bit chunks
+    pub fn emit_vbr(&mut self, mut value: u64, width: u32) {
+        let threshold = 1u64 << (width - 1);
+        while value >= threshold {
+            self.emit((value & (threshold - 1)) | threshold, width);
+            value >>= width - 1;
+        }
+        self.emit(value, width);
+    }
+
+    /// Pad with zero bits to a 32-bit boundary
+    pub fn align32(&mut self) {
+        self.position = self.position.div_ceil(32) * 32;
+        self.data.resize(self.position / 8, 0);
+    }
+
+    /// Append raw bytes; the stream must be byte aligned
+    pub fn emit_bytes(&mut self, bytes: &[u8]) {
+        debug_assert!(self.position.is_multiple_of(8));
+        self.data.truncate(self.position / 8);
+        self.data.extend_from_slice(bytes);
+        self.position += bytes.len() * 8;
+    }
+
+    /// Overwrite a 32-bit word at any earlier bit position
+    pub fn backpatch_word(&mut self, bit_position: usize, value: u32) {
+        self.write_at(bit_position, value as u64, 32);
+    }
+
+    /// Open a block; abbreviations registered for it in BLOCKINFO become available
+    pub fn enter_subblock(&mut self, id: u32, abbrev_width: u32) {
+        self.emit(ENTER_SUBBLOCK as u64, self.abbrev_width);
+        self.emit_vbr(id as u64, 8);
+        self.emit_vbr(abbrev_width as u64, 4);
+        self.align32();
+        let length_position = self.position;
+        self.emit(0, 32);
+        let abbrevs = self.block_info.get(&id).cloned().unwrap_or_default();
+        self.blocks.push(OpenBlock {
+            id,
+            outer_width: std::mem::replace(&mut self.abbrev_width, abbrev_width),
+            outer_abbrevs: std::mem::replace(&mut self.abbrevs, abbrevs),
+            length_position,
+        });
+    }
+
+    /// Close the innermost block and fill in its length
+    pub fn exit_block(&mut self) {
+        let block = self.blocks.pop().expect("exit_block without an open block");
+        self.emit(END_BLOCK as u64, self.abbrev_width);
+        self.align32();
+        let words = (self.position - block.length_position - 32) / 32;
+        self.backpatch_word(block.length_position, words as u32);
+        self.abbrev_width = block.outer_width;
+        self.abbrevs = block.outer_abbrevs;
+        if block.id == BLOCKINFO_BLOCK_ID {
+            self.info_target = None;
+        }
+    }
+
+    /// Open the BLOCKINFO block
+    pub fn enter_block_info(&mut self) {
+        self.enter_subblock(BLOCKINFO_BLOCK_ID, 2);
+    }
+
+    /// Select the block that following BLOCKINFO abbreviations apply to
+    pub fn set_block_info_target(&mut self, block_id: u32) {
+        if self.info_target != Some(block_id) {
+            self.emit_record(BLOCKINFO_CODE_SETBID, &[block_id as u64]);
+            self.info_target = Some(block_id);
+        }
+    }
+
+    /// Define an abbreviation and return its ID
+    ///
+    /// Inside BLOCKINFO the abbreviation is registered for the current target
+    /// block, and the ID is the one it will have in blocks of that kind.
+    pub fn define_abbrev(&mut self, abbrev: Abbrev) -> u32 {
+        self.emit(DEFINE_ABBREV as u64, self.abbrev_width);
+        self.emit_vbr(abbrev.ops.len() as u64, 5);
+        for op in &abbrev.ops {
+            match *op {
+                AbbrevOp::Literal(value) => {
+                    self.emit(1, 1);
+                    self.emit_vbr(value, 8);
+                }
+                AbbrevOp::Fixed(width) => {
+                    self.emit(0, 1);
+                    self.emit(1, 3);
+                    self.emit_vbr(width as u64, 5);
+                }
+                AbbrevOp::Vbr(width) => {
+                    self.emit(0, 1);
+                    self.emit(2, 3);
+                    self.emit_vbr(width as u64, 5);
+                }
+                AbbrevOp::Array => {
+                    self.emit(0, 1);
+                    self.emit(3, 3);
+                }
+                AbbrevOp::Char6 => {
+                    self.emit(0, 1);
+                    self.emit(4, 3);
+                }
+                AbbrevOp::Blob => {
+                    self.emit(0, 1);
+                    self.emit(5, 3);
+                }
+            }
+        }
+        let in_block_info = self.blocks.last().is_some_and(|b| b.id == BLOCKINFO_BLOCK_ID);
+        let list = match (in_block_info, self.info_target) {
+            (true, Some(target)) => self.block_info.entry(target).or_default(),
+            _ => &mut self.abbrevs,
+        };
+        list.push(abbrev);
+        FIRST_APPLICATION_ABBREV + list.len() as u32 - 1
+    }
+
+    /// Write a record with VBR6 operands
+    pub fn emit_record(&mut self, code: u32, ops: &[u64]) {
+        self.emit(UNABBREV_RECORD as u64, self.abbrev_width);
+        self.emit_vbr(code as u64, 6);
+        self.emit_vbr(ops.len() as u64, 6);
+        for &op in ops {
+            self.emit_vbr(op, 6);
+        }
+    }
+
+    /// Write a record through abbreviation `abbrev_id`
+    ///
+    /// The code and `ops` are matched against the abbreviation's operands in
+    /// order; an array takes all remaining operands and a blob takes `blob`.
+    pub fn emit_record_with_abbrev(&mut self, abbrev_id: u32, code: u32, ops: &[u64], blob: Option<&[u8]>) {
+        let abbrev = self.abbrevs[(abbrev_id - FIRST_APPLICATION_ABBREV) as usize].clone();
+        self.emit(abbrev_id as u64, self.abbrev_width);
+        let mut values = std::iter::once(code as u64).chain(ops.iter().copied());
+        let mut index = 0;
+        while index < abbrev.ops.len() {
+            match &abbrev.ops[index] {
+                AbbrevOp::Array => {
+                    let rest: Vec<u64> = values.by_ref().collect();
+                    self.emit_vbr(rest.len() as u64, 6);
+                    for value in rest {
+                        self.emit_scalar(&abbrev.ops[index + 1], value);
+                    }
+                    index += 1;
+                }
+                AbbrevOp::Blob => {
+                    let bytes = blob.unwrap_or(&[]);
+                    self.emit_vbr(bytes.len() as u64, 6);
+                    self.align32();
+                    self.emit_bytes(bytes);
+                    self.align32();
+                }
+                op => {
+                    let value = values.next().unwrap_or(0);
+                    self.emit_scalar(op, value);
+                }
+            }
+            index += 1;
+        }
+    }
+
+    /// Pad to a 32-bit boundary and return the encoded bytes
+    pub fn finish(mut self) -> Vec<u8> {
+        self.align32();
+        self.data
+    }
+
+    fn emit_scalar(&mut self, op: &AbbrevOp, value: u64) {
+        match *op {
+            AbbrevOp::Literal(literal) => debug_assert_eq!(literal, value),
+            AbbrevOp::Fixed(width) => self.emit(value, width),
+            AbbrevOp::Vbr(width) => self.emit_vbr(value, width),
+            AbbrevOp::Char6 => self.emit(encode_char6(value as u8).unwrap_or(0) as u64, 6),
+            AbbrevOp::Array | AbbrevOp::Blob => {}
+        }
+    }
+
+    fn write_at(&mut self, position: usize, value: u64, width: u32) {
+        let end = (position + width as usize).div_ceil(8);
+        if self.data.len() < end {
+            self.data.resize(end, 0);
+        }
+        let (mut position, mut value, mut width) = (position, value, width);
+        while width > 0 {
+            let shift = (position % 8) as u32;
+            let take = (8 - shift).min(width);
+            let mask = ((1u16 << take) - 1) as u8;
+            let byte = &mut self.data[position / 8];
+            *byte = (*byte & !(mask << shift)) | (((value as u8) & mask) << shift);
+            value = value.checked_shr(take).unwrap_or(0);
+            width -= take;
+            position += take as usize;
+        }
+    }
+}
+
+/// Decode a six-bit character
+pub fn decode_char6(value: u8) -> u8 {
+    match value {
+        0..=25 => b'a' + value,
+        26..=51 => b'A' + value - 26,
+        52..=61 => b'0' + value - 52,
+        62 => b'.',
+        _ => b'_',
+    }
+}
+
+/// Decode a sign-rotated VBR value (sign in the low bit)
+pub fn decode_signed(value: u64) -> i64 {
+    if value & 1 == 0 {
+        (value >> 1) as i64
+    } else if value != 1 {
+        -((value >> 1) as i64)
+    } else {
+        // -0 encodes i64::MIN
+        i64::MIN
+    }
+}
+
+/// Encode a character from `[a-zA-Z0-9._]` in six bits
+pub fn encode_char6(c: u8) -> Option<u8> {
+    match c {
+        b'a'..=b'z' => Some(c - b'a'),
+        b'A'..=b'Z' => Some(c - b'A' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'.' => Some(62),
+        b'_' => Some(63),
+        _ => None,
+    }
+}
+
+/// Encode a signed value as a sign-rotated VBR value (sign in the low bit)
+pub fn encode_signed(value: i64) -> u64 {
+    if value >= 0 {
+        (value as u64) << 1
+    } else {
+        // i64::MIN wraps to -0
+        (value.unsigned_abs() << 1) | 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_fixed_and_vbr() {
+        // 0b101 in 3 bits, then VBR4 value 9 as chunks 0b1001 (payload 1, continue) and 0b0001
+        let data = [0b1100_1101u8, 0b0000_0000, 0, 0];
+        let mut cursor = BitstreamCursor::new(&data);
+        assert_eq!(cursor.read(3).unwrap(), 0b101);
+        assert_eq!(cursor.read_vbr(4).unwrap(), 9);
+        assert_eq!(cursor.bit_position(), 11);
+        cursor.align32().unwrap();
+        assert!(cursor.at_end());
+        assert!(cursor.read(1).is_err());
+    }
+
+    #[test]
+    fn test_decode_helpers() {
+        assert_eq!(decode_char6(0), b'a');
+        assert_eq!(decode_char6(26), b'A');
+        assert_eq!(decode_char6(52), b'0');
+        assert_eq!(decode_char6(62), b'.');
+        assert_eq!(decode_char6(63), b'_');
+        assert_eq!(decode_signed(4), 2);
+        assert_eq!(decode_signed(5), -2);
+        assert_eq!(decode_signed(1), i64::MIN);
+    }
+
+    #[test]
+    fn test_writer_round_trip() {
+        let mut writer = BitstreamWriter::new();
+        writer.enter_block_info();
+        writer.set_block_info_target(8);
+        let shared = writer.define_abbrev(Abbrev { ops: vec![AbbrevOp::Literal(1), AbbrevOp::Array, AbbrevOp::Char6] });
+        writer.exit_block();
+        writer.enter_subblock(8, 3);
+        writer.emit_record(2, &[1, 1000]);
+        writer.emit_record_with_abbrev(shared, 1, &[b'a' as u64, b'_' as u64], None);
+        let local = writer.define_abbrev(Abbrev { ops: vec![AbbrevOp::Literal(4), AbbrevOp::Vbr(4), AbbrevOp::Blob] });
+        writer.emit_record_with_abbrev(local, 4, &[300], Some(b"xyz"));
+        writer.enter_subblock(9, 2);
+        writer.emit_record(7, &[]);
+        writer.exit_block();
+        writer.exit_block();
+        let data = writer.finish();
+        assert_eq!(data.len() % 4, 0);
+
+        let blocks = BitstreamReader::new(&data).read_top_level().unwrap();
+        assert_eq!(blocks.len(), 2);
+        let block = &blocks[1];
+        assert_eq!((block.id, block.abbrev_width), (8, 3));
+        let records: Vec<&Record> = block.records().collect();
+        assert_eq!((records[0].code, records[0].ops.clone()), (2, vec![1, 1000]));
+        assert_eq!(records[1].string(), "a_");
+        assert_eq!(records[1].abbrev_id, shared);
+        assert_eq!(records[2].ops, vec![300]);
+        assert_eq!(records[2].blob.as_deref(), Some(&b"xyz"[..]));
+        assert_eq!(block.block(9).unwrap().records().next().unwrap().code, 7);
+    }
+
+    #[test]
+    fn test_encode_helpers() {
+        for c in b"azAZ09._".iter().copied() {
+            assert_eq!(decode_char6(encode_char6(c).unwrap()), c);
+        }
+        assert_eq!(encode_char6(b'-'), None);
+        for value in [0, 2, -2, i64::MAX, i64::MIN + 1] {
+            assert_eq!(decode_signed(encode_signed(value)), value);
+        }
+    }
+}
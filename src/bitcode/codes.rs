@@ -0,0 +1,635 @@
+//! Bitcode Block IDs and Record Codes
+//!
+//! Numbering follows LLVM's `LLVMBitCodes.h`; the values are part of the
+//! on-disk format and must never change.
+
+// Block IDs
+pub const MODULE_BLOCK_ID: u32 = 8;
+pub const PARAMATTR_BLOCK_ID: u32 = 9;
+pub const PARAMATTR_GROUP_BLOCK_ID: u32 = 10;
+pub const CONSTANTS_BLOCK_ID: u32 = 11;
+pub const FUNCTION_BLOCK_ID: u32 = 12;
+pub const IDENTIFICATION_BLOCK_ID: u32 = 13;
+pub const VALUE_SYMTAB_BLOCK_ID: u32 = 14;
+pub const METADATA_BLOCK_ID: u32 = 15;
+pub const METADATA_ATTACHMENT_ID: u32 = 16;
+pub const TYPE_BLOCK_ID_NEW: u32 = 17;
+pub const USELIST_BLOCK_ID: u32 = 18;
+pub const MODULE_STRTAB_BLOCK_ID: u32 = 19;
+pub const GLOBALVAL_SUMMARY_BLOCK_ID: u32 = 20;
+pub const OPERAND_BUNDLE_TAGS_BLOCK_ID: u32 = 21;
+pub const METADATA_KIND_BLOCK_ID: u32 = 22;
+pub const STRTAB_BLOCK_ID: u32 = 23;
+pub const FULL_LTO_GLOBALVAL_SUMMARY_BLOCK_ID: u32 = 24;
+pub const SYMTAB_BLOCK_ID: u32 = 25;
+pub const SYNC_SCOPE_NAMES_BLOCK_ID: u32 = 26;
+
+// IDENTIFICATION_BLOCK
+pub const IDENTIFICATION_CODE_STRING: u32 = 1;
+pub const IDENTIFICATION_CODE_EPOCH: u32 = 2;
+
+/// The only bitcode epoch this reader understands
+pub const BITCODE_CURRENT_EPOCH: u64 = 0;
+
+// MODULE_BLOCK
+pub const MODULE_CODE_VERSION: u32 = 1;
+pub const MODULE_CODE_TRIPLE: u32 = 2;
+pub const MODULE_CODE_DATALAYOUT: u32 = 3;
+pub const MODULE_CODE_ASM: u32 = 4;
+pub const MODULE_CODE_SECTIONNAME: u32 = 5;
+pub const MODULE_CODE_DEPLIB: u32 = 6;
+pub const MODULE_CODE_GLOBALVAR: u32 = 7;
+pub const MODULE_CODE_FUNCTION: u32 = 8;
+pub const MODULE_CODE_ALIAS_OLD: u32 = 9;
+pub const MODULE_CODE_GCNAME: u32 = 11;
+pub const MODULE_CODE_COMDAT: u32 = 12;
+pub const MODULE_CODE_VSTOFFSET: u32 = 13;
+pub const MODULE_CODE_ALIAS: u32 = 14;
+pub const MODULE_CODE_METADATA_VALUES_UNUSED: u32 = 15;
+pub const MODULE_CODE_SOURCE_FILENAME: u32 = 16;
+pub const MODULE_CODE_HASH: u32 = 17;
+pub const MODULE_CODE_IFUNC: u32 = 18;
+
+// PARAMATTR_BLOCK and PARAMATTR_GROUP_BLOCK
+pub const PARAMATTR_CODE_ENTRY_OLD: u32 = 1;
+pub const PARAMATTR_CODE_ENTRY: u32 = 2;
+pub const PARAMATTR_GRP_CODE_ENTRY: u32 = 3;
+
+// TYPE_BLOCK_ID_NEW
+pub const TYPE_CODE_NUMENTRY: u32 = 1;
+pub const TYPE_CODE_VOID: u32 = 2;
+pub const TYPE_CODE_FLOAT: u32 = 3;
+pub const TYPE_CODE_DOUBLE: u32 = 4;
+pub const TYPE_CODE_LABEL: u32 = 5;
+pub const TYPE_CODE_OPAQUE: u32 = 6;
+pub const TYPE_CODE_INTEGER: u32 = 7;
+pub const TYPE_CODE_POINTER: u32 = 8;
+pub const TYPE_CODE_FUNCTION_OLD: u32 = 9;
+pub const TYPE_CODE_HALF: u32 = 10;
+pub const TYPE_CODE_ARRAY: u32 = 11;
+pub const TYPE_CODE_VECTOR: u32 = 12;
+pub const TYPE_CODE_X86_FP80: u32 = 13;
+pub const TYPE_CODE_FP128: u32 = 14;
+pub const TYPE_CODE_PPC_FP128: u32 = 15;
+pub const TYPE_CODE_METADATA: u32 = 16;
+pub const TYPE_CODE_X86_MMX: u32 = 17;
+pub const TYPE_CODE_STRUCT_ANON: u32 = 18;
+pub const TYPE_CODE_STRUCT_NAME: u32 = 19;
+pub const TYPE_CODE_STRUCT_NAMED: u32 = 20;
+pub const TYPE_CODE_FUNCTION: u32 = 21;
+pub const TYPE_CODE_TOKEN: u32 = 22;
+pub const TYPE_CODE_BFLOAT: u32 = 23;
+pub const TYPE_CODE_X86_AMX: u32 = 24;
+pub const TYPE_CODE_OPAQUE_POINTER: u32 = 25;
+pub const TYPE_CODE_TARGET_TYPE: u32 = 26;
+
+// OPERAND_BUNDLE_TAGS_BLOCK and SYNC_SCOPE_NAMES_BLOCK
+pub const OPERAND_BUNDLE_TAG: u32 = 1;
+pub const SYNC_SCOPE_NAME: u32 = 1;
+
+// VALUE_SYMTAB_BLOCK
+pub const VST_CODE_ENTRY: u32 = 1;
+pub const VST_CODE_BBENTRY: u32 = 2;
+pub const VST_CODE_FNENTRY: u32 = 3;
+pub const VST_CODE_COMBINED_ENTRY: u32 = 5;
+
+// STRTAB_BLOCK and SYMTAB_BLOCK
+pub const STRTAB_BLOB: u32 = 1;
+pub const SYMTAB_BLOB: u32 = 1;
+
+// CONSTANTS_BLOCK
+pub const CST_CODE_SETTYPE: u32 = 1;
+pub const CST_CODE_NULL: u32 = 2;
+pub const CST_CODE_UNDEF: u32 = 3;
+pub const CST_CODE_INTEGER: u32 = 4;
+pub const CST_CODE_WIDE_INTEGER: u32 = 5;
+pub const CST_CODE_FLOAT: u32 = 6;
+pub const CST_CODE_AGGREGATE: u32 = 7;
+pub const CST_CODE_STRING: u32 = 8;
+pub const CST_CODE_CSTRING: u32 = 9;
+pub const CST_CODE_CE_BINOP: u32 = 10;
+pub const CST_CODE_CE_CAST: u32 = 11;
+pub const CST_CODE_CE_GEP_OLD: u32 = 12;
+pub const CST_CODE_CE_SELECT: u32 = 13;
+pub const CST_CODE_CE_EXTRACTELT: u32 = 14;
+pub const CST_CODE_CE_INSERTELT: u32 = 15;
+pub const CST_CODE_CE_SHUFFLEVEC: u32 = 16;
+pub const CST_CODE_CE_CMP: u32 = 17;
+pub const CST_CODE_INLINEASM_OLD: u32 = 18;
+pub const CST_CODE_CE_SHUFVEC_EX: u32 = 19;
+pub const CST_CODE_CE_INBOUNDS_GEP: u32 = 20;
+pub const CST_CODE_BLOCKADDRESS: u32 = 21;
+pub const CST_CODE_DATA: u32 = 22;
+pub const CST_CODE_INLINEASM_OLD2: u32 = 23;
+pub const CST_CODE_CE_GEP_WITH_INRANGE_INDEX_OLD: u32 = 24;
+pub const CST_CODE_CE_UNOP: u32 = 25;
+pub const CST_CODE_POISON: u32 = 26;
+pub const CST_CODE_DSO_LOCAL_EQUIVALENT: u32 = 27;
+pub const CST_CODE_INLINEASM_OLD3: u32 = 28;
+pub const CST_CODE_NO_CFI_VALUE: u32 = 29;
+pub const CST_CODE_INLINEASM: u32 = 30;
+pub const CST_CODE_CE_GEP_WITH_INRANGE: u32 = 31;
+pub const CST_CODE_CE_GEP: u32 = 32;
+pub const CST_CODE_PTRAUTH: u32 = 33;
+
+// FUNCTION_BLOCK
+pub const FUNC_CODE_DECLAREBLOCKS: u32 = 1;
+pub const FUNC_CODE_INST_BINOP: u32 = 2;
+pub const FUNC_CODE_INST_CAST: u32 = 3;
+pub const FUNC_CODE_INST_GEP_OLD: u32 = 4;
+pub const FUNC_CODE_INST_SELECT: u32 = 5;
+pub const FUNC_CODE_INST_EXTRACTELT: u32 = 6;
+pub const FUNC_CODE_INST_INSERTELT: u32 = 7;
+pub const FUNC_CODE_INST_SHUFFLEVEC: u32 = 8;
+pub const FUNC_CODE_INST_CMP: u32 = 9;
+pub const FUNC_CODE_INST_RET: u32 = 10;
+pub const FUNC_CODE_INST_BR: u32 = 11;
+pub const FUNC_CODE_INST_SWITCH: u32 = 12;
+pub const FUNC_CODE_INST_INVOKE: u32 = 13;
+pub const FUNC_CODE_INST_UNREACHABLE: u32 = 15;
+pub const FUNC_CODE_INST_PHI: u32 = 16;
+pub const FUNC_CODE_INST_ALLOCA: u32 = 19;
+pub const FUNC_CODE_INST_LOAD: u32 = 20;
+pub const FUNC_CODE_INST_VAARG: u32 = 23;
+pub const FUNC_CODE_INST_STORE_OLD: u32 = 24;
+pub const FUNC_CODE_INST_EXTRACTVAL: u32 = 26;
+pub const FUNC_CODE_INST_INSERTVAL: u32 = 27;
+pub const FUNC_CODE_INST_CMP2: u32 = 28;
+pub const FUNC_CODE_INST_VSELECT: u32 = 29;
+pub const FUNC_CODE_INST_INBOUNDS_GEP_OLD: u32 = 30;
+pub const FUNC_CODE_INST_INDIRECTBR: u32 = 31;
+pub const FUNC_CODE_DEBUG_LOC_AGAIN: u32 = 33;
+pub const FUNC_CODE_INST_CALL: u32 = 34;
+pub const FUNC_CODE_DEBUG_LOC: u32 = 35;
+pub const FUNC_CODE_INST_FENCE: u32 = 36;
+pub const FUNC_CODE_INST_CMPXCHG_OLD: u32 = 37;
+pub const FUNC_CODE_INST_ATOMICRMW_OLD: u32 = 38;
+pub const FUNC_CODE_INST_RESUME: u32 = 39;
+pub const FUNC_CODE_INST_LANDINGPAD_OLD: u32 = 40;
+pub const FUNC_CODE_INST_LOADATOMIC: u32 = 41;
+pub const FUNC_CODE_INST_STOREATOMIC_OLD: u32 = 42;
+pub const FUNC_CODE_INST_GEP: u32 = 43;
+pub const FUNC_CODE_INST_STORE: u32 = 44;
+pub const FUNC_CODE_INST_STOREATOMIC: u32 = 45;
+pub const FUNC_CODE_INST_CMPXCHG: u32 = 46;
+pub const FUNC_CODE_INST_LANDINGPAD: u32 = 47;
+pub const FUNC_CODE_INST_CLEANUPRET: u32 = 48;
+pub const FUNC_CODE_INST_CATCHRET: u32 = 49;
+pub const FUNC_CODE_INST_CATCHPAD: u32 = 50;
+pub const FUNC_CODE_INST_CLEANUPPAD: u32 = 51;
+pub const FUNC_CODE_INST_CATCHSWITCH: u32 = 52;
+pub const FUNC_CODE_OPERAND_BUNDLE: u32 = 55;
+pub const FUNC_CODE_INST_UNOP: u32 = 56;
+pub const FUNC_CODE_INST_CALLBR: u32 = 57;
+pub const FUNC_CODE_INST_FREEZE: u32 = 58;
+pub const FUNC_CODE_INST_ATOMICRMW: u32 = 59;
+pub const FUNC_CODE_BLOCKADDR_USERS: u32 = 60;
+pub const FUNC_CODE_DEBUG_RECORD_VALUE: u32 = 61;
+pub const FUNC_CODE_DEBUG_RECORD_DECLARE: u32 = 62;
+pub const FUNC_CODE_DEBUG_RECORD_ASSIGN: u32 = 63;
+pub const FUNC_CODE_DEBUG_RECORD_VALUE_SIMPLE: u32 = 64;
+pub const FUNC_CODE_DEBUG_RECORD_LABEL: u32 = 65;
+
+// METADATA_BLOCK, METADATA_KIND_BLOCK and METADATA_ATTACHMENT
+pub const METADATA_STRING_OLD: u32 = 1;
+pub const METADATA_VALUE: u32 = 2;
+pub const METADATA_NODE: u32 = 3;
+pub const METADATA_NAME: u32 = 4;
+pub const METADATA_DISTINCT_NODE: u32 = 5;
+pub const METADATA_KIND: u32 = 6;
+pub const METADATA_LOCATION: u32 = 7;
+pub const METADATA_OLD_NODE: u32 = 8;
+pub const METADATA_OLD_FN_NODE: u32 = 9;
+pub const METADATA_NAMED_NODE: u32 = 10;
+pub const METADATA_ATTACHMENT: u32 = 11;
+pub const METADATA_GENERIC_DEBUG: u32 = 12;
+pub const METADATA_SUBRANGE: u32 = 13;
+pub const METADATA_ENUMERATOR: u32 = 14;
+pub const METADATA_BASIC_TYPE: u32 = 15;
+pub const METADATA_FILE: u32 = 16;
+pub const METADATA_DERIVED_TYPE: u32 = 17;
+pub const METADATA_COMPOSITE_TYPE: u32 = 18;
+pub const METADATA_SUBROUTINE_TYPE: u32 = 19;
+pub const METADATA_COMPILE_UNIT: u32 = 20;
+pub const METADATA_SUBPROGRAM: u32 = 21;
+pub const METADATA_LEXICAL_BLOCK: u32 = 22;
+pub const METADATA_LEXICAL_BLOCK_FILE: u32 = 23;
+pub const METADATA_NAMESPACE: u32 = 24;
+pub const METADATA_TEMPLATE_TYPE: u32 = 25;
+pub const METADATA_TEMPLATE_VALUE: u32 = 26;
+pub const METADATA_GLOBAL_VAR: u32 = 27;
+pub const METADATA_LOCAL_VAR: u32 = 28;
+pub const METADATA_EXPRESSION: u32 = 29;
+pub const METADATA_OBJC_PROPERTY: u32 = 30;
+pub const METADATA_IMPORTED_ENTITY: u32 = 31;
+pub const METADATA_MODULE: u32 = 32;
+pub const METADATA_MACRO: u32 = 33;
+pub const METADATA_MACRO_FILE: u32 = 34;
+pub const METADATA_STRINGS: u32 = 35;
+pub const METADATA_GLOBAL_DECL_ATTACHMENT: u32 = 36;
+pub const METADATA_GLOBAL_VAR_EXPR: u32 = 37;
+pub const METADATA_INDEX_OFFSET: u32 = 38;
+pub const METADATA_INDEX: u32 = 39;
+pub const METADATA_LABEL: u32 = 40;
+pub const METADATA_STRING_TYPE: u32 = 41;
+pub const METADATA_COMMON_BLOCK: u32 = 44;
+pub const METADATA_GENERIC_SUBRANGE: u32 = 45;
+pub const METADATA_ARG_LIST: u32 = 46;
+pub const METADATA_ASSIGN_ID: u32 = 47;
+
+// Attribute group entry kinds
+pub const ATTR_KIND_ENUM: u64 = 0;
+pub const ATTR_KIND_INT: u64 = 1;
+pub const ATTR_KIND_STRING: u64 = 3;
+pub const ATTR_KIND_STRING_VALUE: u64 = 4;
+pub const ATTR_KIND_TYPE: u64 = 5;
+pub const ATTR_KIND_TYPE_VALUE: u64 = 6;
+
+/// Attribute index of function-level attributes in a group
+pub const ATTR_FUNCTION_INDEX: u64 = 0xFFFF_FFFF;
+
+/// Attribute spellings, indexed by their `ATTR_KIND_*` code in LLVM
+const ATTRIBUTE_KIND_NAMES: [&str; 104] = [
+    "",
+    "align",
+    "alwaysinline",
+    "byval",
+    "inlinehint",
+    "inreg",
+    "minsize",
+    "naked",
+    "nest",
+    "noalias",
+    "nobuiltin",
+    "nocapture",
+    "noduplicate",
+    "noimplicitfloat",
+    "noinline",
+    "nonlazybind",
+    "noredzone",
+    "noreturn",
+    "nounwind",
+    "optsize",
+    "readnone",
+    "readonly",
+    "returned",
+    "returns_twice",
+    "signext",
+    "alignstack",
+    "ssp",
+    "sspreq",
+    "sspstrong",
+    "sret",
+    "sanitize_address",
+    "sanitize_thread",
+    "sanitize_memory",
+    "uwtable",
+    "zeroext",
+    "builtin",
+    "cold",
+    "optnone",
+    "inalloca",
+    "nonnull",
+    "jumptable",
+    "dereferenceable",
+    "dereferenceable_or_null",
+    "convergent",
+    "safestack",
+    "argmemonly",
+    "swiftself",
+    "swifterror",
+    "norecurse",
+    "inaccessiblememonly",
+    "inaccessiblemem_or_argmemonly",
+    "allocsize",
+    "writeonly",
+    "speculatable",
+    "strictfp",
+    "sanitize_hwaddress",
+    "nocf_check",
+    "optforfuzzing",
+    "shadowcallstack",
+    "speculative_load_hardening",
+    "immarg",
+    "willreturn",
+    "nofree",
+    "nosync",
+    "sanitize_memtag",
+    "preallocated",
+    "nomerge",
+    "null_pointer_is_valid",
+    "noundef",
+    "byref",
+    "mustprogress",
+    "nocallback",
+    "hot",
+    "noprofile",
+    "vscale_range",
+    "swiftasync",
+    "nosanitize_coverage",
+    "elementtype",
+    "disable_sanitizer_instrumentation",
+    "nosanitize_bounds",
+    "allocalign",
+    "allocptr",
+    "allockind",
+    "presplitcoroutine",
+    "fn_ret_thunk_extern",
+    "skipprofile",
+    "memory",
+    "nofpclass",
+    "optdebug",
+    "writable",
+    "coro_only_destroy_when_complete",
+    "dead_on_unwind",
+    "range",
+    "sanitize_numerical_stability",
+    "initializes",
+    "hybrid_patchable",
+    "sanitize_realtime",
+    "sanitize_realtime_blocking",
+    "coro_elide_safe",
+    "no_ext",
+    "no_divergence_source",
+    "sanitize_type",
+    "captures",
+    "dead_on_return",
+];
+
+/// Spelling of an attribute kind code, if known
+pub fn attribute_kind_name(kind: u64) -> Option<&'static str> {
+    ATTRIBUTE_KIND_NAMES.get(kind as usize).copied().filter(|name| !name.is_empty())
+}
+
+/// Attribute kind code of a spelling, if known
+pub fn attribute_kind_code(name: &str) -> Option<u64> {
+    ATTRIBUTE_KIND_NAMES.iter().position(|&n| !n.is_empty() && n == name).map(|i| i as u64)
+}
+
+/// Name of a block ID, as printed by bitstream dumps
+pub fn block_name(id: u32) -> Option<&'static str> {
+    Some(match id {
+        0 => "BLOCKINFO_BLOCK",
+        MODULE_BLOCK_ID => "MODULE_BLOCK",
+        PARAMATTR_BLOCK_ID => "PARAMATTR_BLOCK",
+        PARAMATTR_GROUP_BLOCK_ID => "PARAMATTR_GROUP_BLOCK_ID",
+        CONSTANTS_BLOCK_ID => "CONSTANTS_BLOCK",
+        FUNCTION_BLOCK_ID => "FUNCTION_BLOCK",
+        IDENTIFICATION_BLOCK_ID => "IDENTIFICATION_BLOCK_ID",
+        VALUE_SYMTAB_BLOCK_ID => "VALUE_SYMTAB",
+        METADATA_BLOCK_ID => "METADATA_BLOCK",
+        METADATA_ATTACHMENT_ID => "METADATA_ATTACHMENT",
+        TYPE_BLOCK_ID_NEW => "TYPE_BLOCK_ID",
+        USELIST_BLOCK_ID => "USELIST_BLOCK",
+        MODULE_STRTAB_BLOCK_ID => "MODULE_STRTAB_BLOCK",
+        GLOBALVAL_SUMMARY_BLOCK_ID => "GLOBALVAL_SUMMARY_BLOCK",
+        OPERAND_BUNDLE_TAGS_BLOCK_ID => "OPERAND_BUNDLE_TAGS_BLOCK",
+        METADATA_KIND_BLOCK_ID => "METADATA_KIND_BLOCK",
+        STRTAB_BLOCK_ID => "STRTAB_BLOCK",
+        FULL_LTO_GLOBALVAL_SUMMARY_BLOCK_ID => "FULL_LTO_GLOBALVAL_SUMMARY_BLOCK",
+        SYMTAB_BLOCK_ID => "SYMTAB_BLOCK",
+        SYNC_SCOPE_NAMES_BLOCK_ID => "SYNC_SCOPE_NAMES_BLOCK",
+        _ => return None,
+    })
+}
+
+/// Name of a record code within a block, as printed by bitstream dumps
+pub fn record_name(block_id: u32, code: u32) -> Option<&'static str> {
+    Some(match block_id {
+        0 => match code {
+            1 => "SETBID",
+            2 => "BLOCKNAME",
+            3 => "SETRECORDNAME",
+            _ => return None,
+        },
+        IDENTIFICATION_BLOCK_ID => match code {
+            IDENTIFICATION_CODE_STRING => "STRING",
+            IDENTIFICATION_CODE_EPOCH => "EPOCH",
+            _ => return None,
+        },
+        MODULE_BLOCK_ID => match code {
+            MODULE_CODE_VERSION => "VERSION",
+            MODULE_CODE_TRIPLE => "TRIPLE",
+            MODULE_CODE_DATALAYOUT => "DATALAYOUT",
+            MODULE_CODE_ASM => "ASM",
+            MODULE_CODE_SECTIONNAME => "SECTIONNAME",
+            MODULE_CODE_DEPLIB => "DEPLIB",
+            MODULE_CODE_GLOBALVAR => "GLOBALVAR",
+            MODULE_CODE_FUNCTION => "FUNCTION",
+            MODULE_CODE_ALIAS_OLD => "ALIAS_OLD",
+            MODULE_CODE_GCNAME => "GCNAME",
+            MODULE_CODE_COMDAT => "COMDAT",
+            MODULE_CODE_VSTOFFSET => "VSTOFFSET",
+            MODULE_CODE_ALIAS => "ALIAS",
+            MODULE_CODE_METADATA_VALUES_UNUSED => "METADATA_VALUES_UNUSED",
+            MODULE_CODE_SOURCE_FILENAME => "SOURCE_FILENAME",
+            MODULE_CODE_HASH => "HASH",
+            MODULE_CODE_IFUNC => "IFUNC",
+            _ => return None,
+        },
+        PARAMATTR_BLOCK_ID => match code {
+            PARAMATTR_CODE_ENTRY_OLD => "ENTRY_OLD",
+            PARAMATTR_CODE_ENTRY => "ENTRY",
+            _ => return None,
+        },
+        TYPE_BLOCK_ID_NEW => match code {
+            TYPE_CODE_NUMENTRY => "NUMENTRY",
+            TYPE_CODE_VOID => "VOID",
+            TYPE_CODE_FLOAT => "FLOAT",
+            TYPE_CODE_DOUBLE => "DOUBLE",
+            TYPE_CODE_LABEL => "LABEL",
+            TYPE_CODE_OPAQUE => "OPAQUE",
+            TYPE_CODE_INTEGER => "INTEGER",
+            TYPE_CODE_POINTER => "POINTER",
+            TYPE_CODE_FUNCTION_OLD => "FUNCTION_OLD",
+            TYPE_CODE_HALF => "HALF",
+            TYPE_CODE_ARRAY => "ARRAY",
+            TYPE_CODE_VECTOR => "VECTOR",
+            TYPE_CODE_X86_FP80 => "X86_FP80",
+            TYPE_CODE_FP128 => "FP128",
+            TYPE_CODE_PPC_FP128 => "PPC_FP128",
+            TYPE_CODE_METADATA => "METADATA",
+            TYPE_CODE_X86_MMX => "X86_MMX",
+            TYPE_CODE_STRUCT_ANON => "STRUCT_ANON",
+            TYPE_CODE_STRUCT_NAME => "STRUCT_NAME",
+            TYPE_CODE_STRUCT_NAMED => "STRUCT_NAMED",
+            TYPE_CODE_FUNCTION => "FUNCTION",
+            TYPE_CODE_TOKEN => "TOKEN",
+            TYPE_CODE_BFLOAT => "BFLOAT",
+            TYPE_CODE_X86_AMX => "X86_AMX",
+            TYPE_CODE_OPAQUE_POINTER => "OPAQUE_POINTER",
+            TYPE_CODE_TARGET_TYPE => "TARGET_TYPE",
+            _ => return None,
+        },
+        CONSTANTS_BLOCK_ID => match code {
+            CST_CODE_SETTYPE => "SETTYPE",
+            CST_CODE_NULL => "NULL",
+            CST_CODE_UNDEF => "UNDEF",
+            CST_CODE_INTEGER => "INTEGER",
+            CST_CODE_WIDE_INTEGER => "WIDE_INTEGER",
+            CST_CODE_FLOAT => "FLOAT",
+            CST_CODE_AGGREGATE => "AGGREGATE",
+            CST_CODE_STRING => "STRING",
+            CST_CODE_CSTRING => "CSTRING",
+            CST_CODE_CE_BINOP => "CE_BINOP",
+            CST_CODE_CE_CAST => "CE_CAST",
+            CST_CODE_CE_GEP_OLD => "CE_GEP_OLD",
+            CST_CODE_CE_SELECT => "CE_SELECT",
+            CST_CODE_CE_EXTRACTELT => "CE_EXTRACTELT",
+            CST_CODE_CE_INSERTELT => "CE_INSERTELT",
+            CST_CODE_CE_SHUFFLEVEC => "CE_SHUFFLEVEC",
+            CST_CODE_CE_CMP => "CE_CMP",
+            CST_CODE_INLINEASM_OLD => "INLINEASM_OLD",
+            CST_CODE_CE_SHUFVEC_EX => "CE_SHUFVEC_EX",
+            CST_CODE_CE_INBOUNDS_GEP => "CE_INBOUNDS_GEP",
+            CST_CODE_BLOCKADDRESS => "BLOCKADDRESS",
+            CST_CODE_DATA => "DATA",
+            CST_CODE_INLINEASM_OLD2 => "INLINEASM_OLD2",
+            CST_CODE_CE_GEP_WITH_INRANGE_INDEX_OLD => "CE_GEP_WITH_INRANGE_INDEX_OLD",
+            CST_CODE_CE_UNOP => "CE_UNOP",
+            CST_CODE_POISON => "POISON",
+            CST_CODE_DSO_LOCAL_EQUIVALENT => "DSO_LOCAL_EQUIVALENT",
+            CST_CODE_INLINEASM_OLD3 => "INLINEASM_OLD3",
+            CST_CODE_NO_CFI_VALUE => "NO_CFI_VALUE",
+            CST_CODE_INLINEASM => "INLINEASM",
+            CST_CODE_CE_GEP_WITH_INRANGE => "CE_GEP_WITH_INRANGE",
+            CST_CODE_CE_GEP => "CE_GEP",
+            CST_CODE_PTRAUTH => "PTRAUTH",
+            _ => return None,
+        },
+        FUNCTION_BLOCK_ID => match code {
+            FUNC_CODE_DECLAREBLOCKS => "DECLAREBLOCKS",
+            FUNC_CODE_INST_BINOP => "INST_BINOP",
+            FUNC_CODE_INST_CAST => "INST_CAST",
+            FUNC_CODE_INST_GEP_OLD => "INST_GEP_OLD",
+            FUNC_CODE_INST_SELECT => "INST_SELECT",
+            FUNC_CODE_INST_EXTRACTELT => "INST_EXTRACTELT",
+            FUNC_CODE_INST_INSERTELT => "INST_INSERTELT",
+            FUNC_CODE_INST_SHUFFLEVEC => "INST_SHUFFLEVEC",
+            FUNC_CODE_INST_CMP => "INST_CMP",
+            FUNC_CODE_INST_RET => "INST_RET",
+            FUNC_CODE_INST_BR => "INST_BR",
+            FUNC_CODE_INST_SWITCH => "INST_SWITCH",
+            FUNC_CODE_INST_INVOKE => "INST_INVOKE",
+            FUNC_CODE_INST_UNREACHABLE => "INST_UNREACHABLE",
+            FUNC_CODE_INST_PHI => "INST_PHI",
+            FUNC_CODE_INST_ALLOCA => "INST_ALLOCA",
+            FUNC_CODE_INST_LOAD => "INST_LOAD",
+            FUNC_CODE_INST_VAARG => "INST_VAARG",
+            FUNC_CODE_INST_STORE_OLD => "INST_STORE_OLD",
+            FUNC_CODE_INST_EXTRACTVAL => "INST_EXTRACTVAL",
+            FUNC_CODE_INST_INSERTVAL => "INST_INSERTVAL",
+            FUNC_CODE_INST_CMP2 => "INST_CMP2",
+            FUNC_CODE_INST_VSELECT => "INST_VSELECT",
+            FUNC_CODE_INST_INBOUNDS_GEP_OLD => "INST_INBOUNDS_GEP_OLD",
+            FUNC_CODE_INST_INDIRECTBR => "INST_INDIRECTBR",
+            FUNC_CODE_DEBUG_LOC_AGAIN => "DEBUG_LOC_AGAIN",
+            FUNC_CODE_INST_CALL => "INST_CALL",
+            FUNC_CODE_DEBUG_LOC => "DEBUG_LOC",
+            FUNC_CODE_INST_FENCE => "INST_FENCE",
+            FUNC_CODE_INST_CMPXCHG_OLD => "INST_CMPXCHG_OLD",
+            FUNC_CODE_INST_ATOMICRMW_OLD => "INST_ATOMICRMW_OLD",
+            FUNC_CODE_INST_RESUME => "INST_RESUME",
+            FUNC_CODE_INST_LANDINGPAD_OLD => "INST_LANDINGPAD_OLD",
+            FUNC_CODE_INST_LOADATOMIC => "INST_LOADATOMIC",
+            FUNC_CODE_INST_STOREATOMIC_OLD => "INST_STOREATOMIC_OLD",
+            FUNC_CODE_INST_GEP => "INST_GEP",
+            FUNC_CODE_INST_STORE => "INST_STORE",
+            FUNC_CODE_INST_STOREATOMIC => "INST_STOREATOMIC",
+            FUNC_CODE_INST_CMPXCHG => "INST_CMPXCHG",
+            FUNC_CODE_INST_LANDINGPAD => "INST_LANDINGPAD",
+            FUNC_CODE_INST_CLEANUPRET => "INST_CLEANUPRET",
+            FUNC_CODE_INST_CATCHRET => "INST_CATCHRET",
+            FUNC_CODE_INST_CATCHPAD => "INST_CATCHPAD",
+            FUNC_CODE_INST_CLEANUPPAD => "INST_CLEANUPPAD",
+            FUNC_CODE_INST_CATCHSWITCH => "INST_CATCHSWITCH",
+            FUNC_CODE_OPERAND_BUNDLE => "OPERAND_BUNDLE",
+            FUNC_CODE_INST_UNOP => "INST_UNOP",
+            FUNC_CODE_INST_CALLBR => "INST_CALLBR",
+            FUNC_CODE_INST_FREEZE => "INST_FREEZE",
+            FUNC_CODE_INST_ATOMICRMW => "INST_ATOMICRMW",
+            FUNC_CODE_BLOCKADDR_USERS => "BLOCKADDR_USERS",
+            FUNC_CODE_DEBUG_RECORD_VALUE => "DEBUG_RECORD_VALUE",
+            FUNC_CODE_DEBUG_RECORD_DECLARE => "DEBUG_RECORD_DECLARE",
+            FUNC_CODE_DEBUG_RECORD_ASSIGN => "DEBUG_RECORD_ASSIGN",
+            FUNC_CODE_DEBUG_RECORD_VALUE_SIMPLE => "DEBUG_RECORD_VALUE_SIMPLE",
+            FUNC_CODE_DEBUG_RECORD_LABEL => "DEBUG_RECORD_LABEL",
+            _ => return None,
+        },
+        VALUE_SYMTAB_BLOCK_ID => match code {
+            VST_CODE_ENTRY => "ENTRY",
+            VST_CODE_BBENTRY => "BBENTRY",
+            VST_CODE_FNENTRY => "FNENTRY",
+            VST_CODE_COMBINED_ENTRY => "COMBINED_ENTRY",
+            _ => return None,
+        },
+        PARAMATTR_GROUP_BLOCK_ID => match code {
+            PARAMATTR_GRP_CODE_ENTRY => "ENTRY",
+            _ => return None,
+        },
+        METADATA_BLOCK_ID | METADATA_KIND_BLOCK_ID | METADATA_ATTACHMENT_ID => match code {
+            METADATA_STRING_OLD => "STRING_OLD",
+            METADATA_VALUE => "VALUE",
+            METADATA_NODE => "NODE",
+            METADATA_NAME => "NAME",
+            METADATA_DISTINCT_NODE => "DISTINCT_NODE",
+            METADATA_KIND => "KIND",
+            METADATA_LOCATION => "LOCATION",
+            METADATA_OLD_NODE => "OLD_NODE",
+            METADATA_OLD_FN_NODE => "OLD_FN_NODE",
+            METADATA_NAMED_NODE => "NAMED_NODE",
+            METADATA_ATTACHMENT => "ATTACHMENT",
+            METADATA_GENERIC_DEBUG => "GENERIC_DEBUG",
+            METADATA_SUBRANGE => "SUBRANGE",
+            METADATA_ENUMERATOR => "ENUMERATOR",
+            METADATA_BASIC_TYPE => "BASIC_TYPE",
+            METADATA_FILE => "FILE",
+            METADATA_DERIVED_TYPE => "DERIVED_TYPE",
+            METADATA_COMPOSITE_TYPE => "COMPOSITE_TYPE",
+            METADATA_SUBROUTINE_TYPE => "SUBROUTINE_TYPE",
+            METADATA_COMPILE_UNIT => "COMPILE_UNIT",
+            METADATA_SUBPROGRAM => "SUBPROGRAM",
+            METADATA_LEXICAL_BLOCK => "LEXICAL_BLOCK",
+            METADATA_LEXICAL_BLOCK_FILE => "LEXICAL_BLOCK_FILE",
+            METADATA_NAMESPACE => "NAMESPACE",
+            METADATA_TEMPLATE_TYPE => "TEMPLATE_TYPE",
+            METADATA_TEMPLATE_VALUE => "TEMPLATE_VALUE",
+            METADATA_GLOBAL_VAR => "GLOBAL_VAR",
+            METADATA_LOCAL_VAR => "LOCAL_VAR",
+            METADATA_EXPRESSION => "EXPRESSION",
+            METADATA_OBJC_PROPERTY => "OBJC_PROPERTY",
+            METADATA_IMPORTED_ENTITY => "IMPORTED_ENTITY",
+            METADATA_MODULE => "MODULE",
+            METADATA_MACRO => "MACRO",
+            METADATA_MACRO_FILE => "MACRO_FILE",
+            METADATA_STRINGS => "STRINGS",
+            METADATA_GLOBAL_DECL_ATTACHMENT => "GLOBAL_DECL_ATTACHMENT",
+            METADATA_GLOBAL_VAR_EXPR => "GLOBAL_VAR_EXPR",
+            METADATA_INDEX_OFFSET => "INDEX_OFFSET",
+            METADATA_INDEX => "INDEX",
+            METADATA_LABEL => "LABEL",
+            METADATA_STRING_TYPE => "STRING_TYPE",
+            METADATA_COMMON_BLOCK => "COMMON_BLOCK",
+            METADATA_GENERIC_SUBRANGE => "GENERIC_SUBRANGE",
+            METADATA_ARG_LIST => "ARG_LIST",
+            _ => return None,
+        },
+        OPERAND_BUNDLE_TAGS_BLOCK_ID => match code {
+            OPERAND_BUNDLE_TAG => "OPERAND_BUNDLE_TAG",
+            _ => return None,
+        },
+        SYNC_SCOPE_NAMES_BLOCK_ID => match code {
+            SYNC_SCOPE_NAME => "SYNC_SCOPE_NAME",
+            _ => return None,
+        },
+        STRTAB_BLOCK_ID => match code {
+            STRTAB_BLOB => "BLOB",
+            _ => return None,
+        },
+        SYMTAB_BLOCK_ID => match code {
+            SYMTAB_BLOB => "BLOB",
+            _ => return None,
+        },
+        _ => return None,
+    })
+}
@@ -0,0 +1,110 @@
+//! LLVM Bitcode
+//!
+//! This module reads and writes the binary bitcode format (`.bc` files) produced
+//! by `llvm-as` and `clang -c -emit-llvm`. The [`bitstream`] layer handles the
+//! generic container; [`reader`] interprets its blocks as a [`Module`] and
+//! [`writer`] serializes a [`Module`] back into them. [`analyzer`] dumps the
+//! container of any bitstream file for inspection.
+
+pub mod analyzer;
+pub mod bitstream;
+pub mod codes;
+pub mod reader;
+pub mod writer;
+
+use std::fmt;
+use crate::context::Context;
+use crate::module::Module;
+use bitstream::{Block, BlockInfo, BitstreamReader};
+
+/// Magic number at the start of every raw bitcode file (`BC` 0xC0DE)
+pub const BITCODE_MAGIC: [u8; 4] = [b'B', b'C', 0xC0, 0xDE];
+/// Magic number of the Darwin bitcode wrapper header
+pub const WRAPPER_MAGIC: u32 = 0x0B17_C0DE;
+
+/// Bitcode error types
+#[derive(Debug, Clone, PartialEq)]
+pub enum BitcodeError {
+    /// The input is not bitcode
+    InvalidMagic,
+    /// The stream ended in the middle of a field, record or block
+    UnexpectedEOF { bit_offset: usize },
+    /// The bitstream container is malformed
+    MalformedStream { message: String, bit_offset: usize },
+    /// A record is missing operands or refers to something undefined
+    InvalidRecord { block: &'static str, message: String },
+    /// The bitcode uses a feature this reader does not handle
+    Unsupported(String),
+}
+
+/// Bitcode result
+pub type BitcodeResult<T> = Result<T, BitcodeError>;
+
+impl fmt::Display for BitcodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BitcodeError::InvalidMagic => write!(f, "Invalid bitcode signature"),
+            BitcodeError::UnexpectedEOF { bit_offset } => {
+                write!(f, "Unexpected end of bitcode at bit {}", bit_offset)
+            }
+            BitcodeError::MalformedStream { message, bit_offset } => {
+                write!(f, "Malformed bitstream at bit {}: {}", bit_offset, message)
+            }
+            BitcodeError::InvalidRecord { block, message } => {
+                write!(f, "Invalid record in {} block: {}", block, message)
+            }
+            BitcodeError::Unsupported(s) => write!(f, "Unsupported bitcode: {}", s),
+        }
+    }
+}
+
+impl std::error::Error for BitcodeError {}
+
+/// Check whether `bytes` starts with a bitcode or bitcode wrapper signature
+pub fn is_bitcode(bytes: &[u8]) -> bool {
+    bytes.starts_with(&BITCODE_MAGIC) || read_u32(bytes, 0) == Some(WRAPPER_MAGIC)
+}
+
+/// Strip the optional wrapper header, returning the bitstream with its magic number
+pub fn strip_wrapper(bytes: &[u8]) -> BitcodeResult<&[u8]> {
+    if read_u32(bytes, 0) != Some(WRAPPER_MAGIC) {
+        return Ok(bytes);
+    }
+    // Wrapper: magic, version, offset, size, cputype
+    let offset = read_u32(bytes, 8).ok_or(BitcodeError::InvalidMagic)? as usize;
+    let size = read_u32(bytes, 12).ok_or(BitcodeError::InvalidMagic)? as usize;
+    bytes.get(offset..offset.saturating_add(size)).ok_or(BitcodeError::UnexpectedEOF { bit_offset: bytes.len() * 8 })
+}
+
+/// Strip the optional wrapper header and magic number, returning the bitstream
+pub fn bitstream_contents(bytes: &[u8]) -> BitcodeResult<&[u8]> {
+    let bytes = strip_wrapper(bytes)?;
+    if !bytes.starts_with(&BITCODE_MAGIC) {
+        return Err(BitcodeError::InvalidMagic);
+    }
+    Ok(&bytes[BITCODE_MAGIC.len()..])
+}
+
+/// Decode the container of a bitcode file into its top-level blocks
+pub fn read_blocks(bytes: &[u8]) -> BitcodeResult<(Vec<Block>, BlockInfo)> {
+    let stream = bitstream_contents(bytes)?;
+    let mut reader = BitstreamReader::new(stream);
+    let blocks = reader.read_top_level()?;
+    Ok((blocks, reader.block_info().clone()))
+}
+
+/// Parse a bitcode file into a module
+pub fn parse_bitcode(bytes: &[u8], context: Context) -> BitcodeResult<Module> {
+    let (blocks, _) = read_blocks(bytes)?;
+    reader::BitcodeReader::new(context).read_module(&blocks)
+}
+
+/// Serialize a module as bitcode
+pub fn write_bitcode(module: &Module) -> BitcodeResult<Vec<u8>> {
+    writer::BitcodeWriter::new(module).write()
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let word = bytes.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+}
@@ -0,0 +1,2447 @@
+//! Bitcode Reader
+//!
+//! Interprets the blocks of a bitcode file as IR. Global values and module
+//! constants are numbered first; each function block then appends its
+//! arguments, constants and instruction results to that value list.
+//!
+//! Function bodies are decoded in two stages: records are first turned into
+//! pending instructions that refer to operands by value ID, so forward
+//! references (PHIs, values used before their block) need no placeholders.
+//! Once every result type and name is known the instructions are built.
+
+use std::collections::HashMap;
+use crate::basic_block::BasicBlock;
+use crate::context::Context;
+use crate::function::{CallingConvention, Function, FunctionAttributes, ParameterAttributes, ReturnAttributes};
+use crate::instruction::{AtomicOrdering, AtomicRMWBinOp, FloatPredicate, Instruction, IntPredicate, Opcode, OperandBundle};
+use crate::metadata::Metadata;
+use crate::module::{Alias, DLLStorageClass, GlobalVariable, Linkage, Module, ThreadLocalMode, UnnamedAddr, Visibility};
+use crate::types::Type;
+use crate::value::{Value, ValueKind};
+use super::bitstream::{decode_signed, BitstreamCursor, Block, BlockEntry, Record};
+use super::codes::*;
+use super::{BitcodeError, BitcodeResult};
+
+/// Reads a module out of decoded bitcode blocks
+pub struct BitcodeReader {
+    context: Context,
+    /// MODULE_CODE_VERSION: 1 enables relative value IDs, 2 moves names to the strtab
+    version: u64,
+    strtab: Vec<u8>,
+    types: Vec<TypeEntry>,
+    attribute_groups: HashMap<u64, AttributeGroup>,
+    attribute_lists: Vec<Vec<u64>>,
+    section_names: Vec<String>,
+    gc_names: Vec<String>,
+    comdats: Vec<String>,
+    values: Vec<ValueSlot>,
+    metadata: Vec<MetadataSlot>,
+    next_metadata_node: usize,
+    metadata_kinds: HashMap<u64, String>,
+    /// (value ID, kind, node) attached to global variables and function declarations
+    global_attachments: Vec<(usize, String, Metadata)>,
+    bundle_tags: Vec<String>,
+    /// Block names of each function with a body, keyed by value ID (for blockaddress)
+    function_block_names: HashMap<usize, Vec<String>>,
+}
+
+struct TypeEntry {
+    record: Record,
+    name: Option<String>,
+    resolved: Option<Type>,
+    resolving: bool,
+}
+
+enum ValueSlot {
+    Ready(Value),
+    /// A constant that is decoded on first use
+    Constant { type_id: u64, record: Record },
+    Resolving { type_id: u64 },
+}
+
+enum MetadataSlot {
+    String(String),
+    /// Metadata printed inline rather than numbered (values, DIExpression)
+    Inline(Metadata),
+    /// A numbered node, stored in the module under this name
+    Node(String),
+    /// A function-local value that is defined after the function's metadata
+    Local { id: usize, ty: Type },
+}
+
+enum Attribute {
+    Enum(u64),
+    Int(u64, u64),
+    String(String, String),
+    Type(u64, Option<Type>),
+}
+
+struct AttributeGroup {
+    index: u64,
+    attributes: Vec<Attribute>,
+}
+
+/// Global values waiting for their initializers, aliasees and personalities
+enum GlobalEntry {
+    Variable { global: GlobalVariable, initializer: Option<usize> },
+    Function { function: Function, personality: Option<usize>, has_body: bool },
+    Alias { alias: Alias, aliasee: usize },
+    IFunc,
+}
+
+/// An instruction operand, by value ID until the function is assembled
+enum Operand {
+    Value(usize),
+    Block(usize),
+    Constant(Value),
+    /// A local value passed as metadata (`metadata i32 %x`)
+    Metadata(usize),
+}
+
+struct PendingInst {
+    opcode: Opcode,
+    operands: Vec<Operand>,
+    result_type: Option<Type>,
+    value_id: Option<usize>,
+    block: usize,
+    alignment: Option<u64>,
+    is_volatile: bool,
+    /// `inalloca` and `swifterror` flags of an alloca
+    alloca_flags: (bool, bool),
+    ordering: Option<AtomicOrdering>,
+    rmw_operation: Option<AtomicRMWBinOp>,
+    gep_source_type: Option<Type>,
+    int_predicate: Option<IntPredicate>,
+    float_predicate: Option<FloatPredicate>,
+    bundles: Vec<(String, Vec<Operand>)>,
+    attachments: Vec<String>,
+    debug_loc: Option<Metadata>,
+    metadata: Vec<(String, Metadata)>,
+}
+
+impl PendingInst {
+    fn new(opcode: Opcode, operands: Vec<Operand>, result_type: Option<Type>) -> Self {
+        Self {
+            opcode,
+            operands,
+            result_type,
+            value_id: None,
+            block: 0,
+            alignment: None,
+            is_volatile: false,
+            alloca_flags: (false, false),
+            ordering: None,
+            rmw_operation: None,
+            gep_source_type: None,
+            int_predicate: None,
+            float_predicate: None,
+            bundles: Vec::new(),
+            attachments: Vec::new(),
+            debug_loc: None,
+            metadata: Vec::new(),
+        }
+    }
+}
+
+/// A function body after the first decoding stage
+struct FunctionBody {
+    function: Function,
+    /// Arguments and function-level constants, appended after the module values
+    locals: Vec<ValueSlot>,
+    arg_types: Vec<Type>,
+    insts: Vec<PendingInst>,
+    /// Value ID of the first instruction result
+    base: usize,
+    result_types: Vec<Type>,
+    block_names: Vec<String>,
+    value_names: HashMap<usize, String>,
+}
+
+/// Decoding state of the function block being read
+struct FunctionState {
+    base: usize,
+    result_types: Vec<Type>,
+}
+
+impl FunctionState {
+    fn next_value_id(&self) -> usize {
+        self.base + self.result_types.len()
+    }
+}
+
+fn invalid(block: &'static str, message: impl Into<String>) -> BitcodeError {
+    BitcodeError::InvalidRecord { block, message: message.into() }
+}
+
+fn operand(record: &Record, index: usize, block: &'static str) -> BitcodeResult<u64> {
+    record.ops.get(index).copied().ok_or_else(|| {
+        invalid(block, format!("record {} is missing operand {}", record.code, index))
+    })
+}
+
+/// Decode an alignment stored as log2 + 1 (0 means unspecified)
+fn decode_alignment(value: u64) -> Option<u64> {
+    if value == 0 || value > 64 {
+        None
+    } else {
+        Some(1u64 << (value - 1))
+    }
+}
+
+fn decode_linkage(value: u64) -> Linkage {
+    match value {
+        2 => Linkage::Appending,
+        3 => Linkage::Internal,
+        7 => Linkage::ExternWeak,
+        8 => Linkage::Common,
+        9 | 13 | 14 => Linkage::Private,
+        12 => Linkage::AvailableExternally,
+        1 | 16 => Linkage::Weak,
+        10 | 17 => Linkage::WeakOdr,
+        4 | 18 => Linkage::Linkonce,
+        11 | 15 | 19 => Linkage::LinkonceOdr,
+        _ => Linkage::External,
+    }
+}
+
+fn decode_visibility(value: u64) -> Visibility {
+    match value {
+        1 => Visibility::Hidden,
+        2 => Visibility::Protected,
+        _ => Visibility::Default,
+    }
+}
+
+fn decode_dll_storage_class(value: u64) -> DLLStorageClass {
+    match value {
+        1 => DLLStorageClass::DllImport,
+        2 => DLLStorageClass::DllExport,
+        _ => DLLStorageClass::Default,
+    }
+}
+
+fn decode_thread_local(value: u64) -> ThreadLocalMode {
+    match value {
+        1 => ThreadLocalMode::GeneralDynamic,
+        2 => ThreadLocalMode::LocalDynamic,
+        3 => ThreadLocalMode::InitialExec,
+        4 => ThreadLocalMode::LocalExec,
+        _ => ThreadLocalMode::NotThreadLocal,
+    }
+}
+
+fn decode_unnamed_addr(value: u64) -> UnnamedAddr {
+    match value {
+        1 => UnnamedAddr::Global,
+        2 => UnnamedAddr::Local,
+        _ => UnnamedAddr::None,
+    }
+}
+
+fn decode_calling_convention(value: u64) -> CallingConvention {
+    match value {
+        8 => CallingConvention::Fast,
+        9 => CallingConvention::Cold,
+        12 => CallingConvention::Webkit_JS,
+        13 => CallingConvention::AnyReg,
+        14 => CallingConvention::PreserveMost,
+        15 => CallingConvention::PreserveAll,
+        16 => CallingConvention::Swift,
+        17 => CallingConvention::CXX_FastTLS,
+        18 => CallingConvention::Tail,
+        19 => CallingConvention::CFunc,
+        20 => CallingConvention::SwiftTail,
+        64 => CallingConvention::X86_StdCall,
+        65 => CallingConvention::X86_FastCall,
+        66 => CallingConvention::ARM_APCS,
+        67 => CallingConvention::ARM_AAPCS,
+        68 => CallingConvention::ARM_AAPCS_VFP,
+        69 => CallingConvention::MSP430_INTR,
+        70 => CallingConvention::X86_ThisCall,
+        71 => CallingConvention::PTX_Kernel,
+        72 => CallingConvention::PTX_Device,
+        75 => CallingConvention::SPIR_Func,
+        76 => CallingConvention::SPIR_Kernel,
+        77 => CallingConvention::Intel_OCL_BI,
+        80 => CallingConvention::X86_VectorCall,
+        83 => CallingConvention::X86_INTR,
+        84 => CallingConvention::AVR_INTR,
+        85 => CallingConvention::AVR_SIGNAL,
+        87 => CallingConvention::AMDGPU_VS,
+        88 => CallingConvention::AMDGPU_GS,
+        89 => CallingConvention::AMDGPU_PS,
+        90 => CallingConvention::AMDGPU_CS,
+        91 => CallingConvention::AMDGPU_Kernel,
+        92 => CallingConvention::X86_RegCall,
+        93 => CallingConvention::AMDGPU_HS,
+        95 => CallingConvention::AMDGPU_LS,
+        96 => CallingConvention::AMDGPU_ES,
+        97 => CallingConvention::AArch64_VectorCall,
+        98 => CallingConvention::AArch64_SVE_VectorCall,
+        101 => CallingConvention::M68k_INTR,
+        104 => CallingConvention::AMDGPU_CS_Chain,
+        105 => CallingConvention::AMDGPU_CS_Chain_Preserve,
+        106 => CallingConvention::M68k_RTD,
+        110 => CallingConvention::RISCV_VectorCall,
+        _ => CallingConvention::C,
+    }
+}
+
+fn decode_ordering(value: u64) -> AtomicOrdering {
+    match value {
+        1 => AtomicOrdering::Unordered,
+        2 => AtomicOrdering::Monotonic,
+        3 => AtomicOrdering::Acquire,
+        4 => AtomicOrdering::Release,
+        5 => AtomicOrdering::AcquireRelease,
+        6 => AtomicOrdering::SequentiallyConsistent,
+        _ => AtomicOrdering::NotAtomic,
+    }
+}
+
+fn decode_rmw_operation(value: u64) -> Option<AtomicRMWBinOp> {
+    Some(match value {
+        0 => AtomicRMWBinOp::Xchg,
+        1 => AtomicRMWBinOp::Add,
+        2 => AtomicRMWBinOp::Sub,
+        3 => AtomicRMWBinOp::And,
+        4 => AtomicRMWBinOp::Nand,
+        5 => AtomicRMWBinOp::Or,
+        6 => AtomicRMWBinOp::Xor,
+        7 => AtomicRMWBinOp::Max,
+        8 => AtomicRMWBinOp::Min,
+        9 => AtomicRMWBinOp::UMax,
+        10 => AtomicRMWBinOp::UMin,
+        11 => AtomicRMWBinOp::FAdd,
+        12 => AtomicRMWBinOp::FSub,
+        13 => AtomicRMWBinOp::FMax,
+        14 => AtomicRMWBinOp::FMin,
+        15 => AtomicRMWBinOp::UIncWrap,
+        16 => AtomicRMWBinOp::UDecWrap,
+        17 => AtomicRMWBinOp::USubCond,
+        18 => AtomicRMWBinOp::USubSat,
+        19 => AtomicRMWBinOp::FMaximum,
+        20 => AtomicRMWBinOp::FMinimum,
+        _ => return None,
+    })
+}
+
+fn decode_int_predicate(value: u64) -> Option<IntPredicate> {
+    Some(match value {
+        32 => IntPredicate::EQ,
+        33 => IntPredicate::NE,
+        34 => IntPredicate::UGT,
+        35 => IntPredicate::UGE,
+        36 => IntPredicate::ULT,
+        37 => IntPredicate::ULE,
+        38 => IntPredicate::SGT,
+        39 => IntPredicate::SGE,
+        40 => IntPredicate::SLT,
+        41 => IntPredicate::SLE,
+        _ => return None,
+    })
+}
+
+fn decode_float_predicate(value: u64) -> Option<FloatPredicate> {
+    Some(match value {
+        0 => FloatPredicate::FALSE,
+        1 => FloatPredicate::OEQ,
+        2 => FloatPredicate::OGT,
+        3 => FloatPredicate::OGE,
+        4 => FloatPredicate::OLT,
+        5 => FloatPredicate::OLE,
+        6 => FloatPredicate::ONE,
+        7 => FloatPredicate::ORD,
+        8 => FloatPredicate::UNO,
+        9 => FloatPredicate::UEQ,
+        10 => FloatPredicate::UGT,
+        11 => FloatPredicate::UGE,
+        12 => FloatPredicate::ULT,
+        13 => FloatPredicate::ULE,
+        14 => FloatPredicate::UNE,
+        15 => FloatPredicate::TRUE,
+        _ => return None,
+    })
+}
+
+/// Whether `ty` is a floating point scalar or vector
+fn is_fp(ty: &Type) -> bool {
+    ty.is_float() || ty.vector_info().is_some_and(|(elem, _)| elem.is_float())
+}
+
+fn decode_binop(code: u64, ty: &Type) -> Option<Opcode> {
+    let fp = is_fp(ty);
+    Some(match code {
+        0 if fp => Opcode::FAdd,
+        0 => Opcode::Add,
+        1 if fp => Opcode::FSub,
+        1 => Opcode::Sub,
+        2 if fp => Opcode::FMul,
+        2 => Opcode::Mul,
+        3 => Opcode::UDiv,
+        4 if fp => Opcode::FDiv,
+        4 => Opcode::SDiv,
+        5 => Opcode::URem,
+        6 if fp => Opcode::FRem,
+        6 => Opcode::SRem,
+        7 => Opcode::Shl,
+        8 => Opcode::LShr,
+        9 => Opcode::AShr,
+        10 => Opcode::And,
+        11 => Opcode::Or,
+        12 => Opcode::Xor,
+        _ => return None,
+    })
+}
+
+fn decode_cast(code: u64) -> Option<Opcode> {
+    Some(match code {
+        0 => Opcode::Trunc,
+        1 => Opcode::ZExt,
+        2 => Opcode::SExt,
+        3 => Opcode::FPToUI,
+        4 => Opcode::FPToSI,
+        5 => Opcode::UIToFP,
+        6 => Opcode::SIToFP,
+        7 => Opcode::FPTrunc,
+        8 => Opcode::FPExt,
+        9 => Opcode::PtrToInt,
+        10 => Opcode::IntToPtr,
+        11 => Opcode::BitCast,
+        12 => Opcode::AddrSpaceCast,
+        13 => Opcode::PtrToAddr,
+        _ => return None,
+    })
+}
+
+/// Convert IEEE half precision bits to a double
+fn half_to_f64(bits: u16) -> f64 {
+    let sign = if bits & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((bits >> 10) & 0x1F) as i32;
+    let mantissa = (bits & 0x3FF) as f64;
+    sign * match exponent {
+        0 => mantissa * 2f64.powi(-24),
+        31 if mantissa == 0.0 => f64::INFINITY,
+        31 => f64::NAN,
+        _ => (1.0 + mantissa / 1024.0) * 2f64.powi(exponent - 15),
+    }
+}
+
+/// Convert an x87 80-bit float (64-bit mantissa with explicit integer bit) to a double
+fn x87_to_f64(mantissa: u64, sign_exponent: u64) -> f64 {
+    let sign = if sign_exponent & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = (sign_exponent & 0x7FFF) as i32;
+    if exponent == 0x7FFF {
+        return if mantissa << 1 == 0 { sign * f64::INFINITY } else { f64::NAN };
+    }
+    sign * (mantissa as f64) * 2f64.powi(exponent - 16383 - 63)
+}
+
+/// Convert IEEE quad precision words (low, high) to a double
+fn quad_to_f64(low: u64, high: u64) -> f64 {
+    let sign = if high >> 63 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((high >> 48) & 0x7FFF) as i32;
+    let fraction = ((high & 0xFFFF_FFFF_FFFF) as f64) * 2f64.powi(-48) + (low as f64) * 2f64.powi(-112);
+    sign * match exponent {
+        0 => fraction * 2f64.powi(-16382),
+        0x7FFF if fraction == 0.0 => f64::INFINITY,
+        0x7FFF => f64::NAN,
+        _ => (1.0 + fraction) * 2f64.powi(exponent - 16383),
+    }
+}
+
+/// How a debug info record operand is decoded
+#[derive(Clone, Copy)]
+pub(super) enum DIField {
+    /// Unsigned integer
+    Int(&'static str),
+    /// Sign-rotated integer
+    Signed(&'static str),
+    /// Metadata ID + 1, zero for null
+    Node(&'static str),
+    /// Metadata ID
+    NodeDirect(&'static str),
+    /// Operand that is not surfaced (format versions, unused slots)
+    Skip,
+}
+
+use DIField::{Int, Node, NodeDirect, Signed, Skip};
+
+/// Record layout of the specialized debug info nodes, after the distinct flag
+pub(super) fn di_layout(code: u32, record: &Record) -> Option<(&'static str, &'static [DIField])> {
+    let version = record.ops.first().copied().unwrap_or(0) >> 1;
+    Some(match code {
+        METADATA_LOCATION => ("DILocation", &[
+            Int("line"), Int("column"), NodeDirect("scope"), Node("inlinedAt"), Int("isImplicitCode"),
+        ]),
+        METADATA_GENERIC_DEBUG => ("GenericDINode", &[Int("tag"), Skip, Node("header")]),
+        METADATA_SUBRANGE => match version {
+            0 => ("DISubrange", &[Int("count"), Signed("lowerBound")]),
+            1 => ("DISubrange", &[Node("count"), Signed("lowerBound")]),
+            _ => ("DISubrange", &[Node("count"), Node("lowerBound"), Node("upperBound"), Node("stride")]),
+        },
+        METADATA_GENERIC_SUBRANGE => ("DIGenericSubrange", &[
+            Node("count"), Node("lowerBound"), Node("upperBound"), Node("stride"),
+        ]),
+        METADATA_BASIC_TYPE => ("DIBasicType", &[
+            Int("tag"), Node("name"), Int("size"), Int("align"), Int("encoding"), Int("flags"),
+        ]),
+        METADATA_STRING_TYPE if record.ops.len() > 8 => ("DIStringType", &[
+            Int("tag"), Node("name"), Node("stringLength"), Node("stringLengthExpression"),
+            Node("stringLocationExpression"), Int("size"), Int("align"), Int("encoding"),
+        ]),
+        METADATA_STRING_TYPE => ("DIStringType", &[
+            Int("tag"), Node("name"), Node("stringLength"), Node("stringLengthExpression"),
+            Int("size"), Int("align"), Int("encoding"),
+        ]),
+        METADATA_FILE => ("DIFile", &[
+            Node("filename"), Node("directory"), Int("checksumkind"), Node("checksum"), Node("source"),
+        ]),
+        METADATA_DERIVED_TYPE => ("DIDerivedType", &[
+            Int("tag"), Node("name"), Node("file"), Int("line"), Node("scope"), Node("baseType"),
+            Int("size"), Int("align"), Int("offset"), Int("flags"), Node("extraData"),
+            Skip, Node("annotations"),
+        ]),
+        METADATA_COMPOSITE_TYPE => ("DICompositeType", &[
+            Int("tag"), Node("name"), Node("file"), Int("line"), Node("scope"), Node("baseType"),
+            Int("size"), Int("align"), Int("offset"), Int("flags"), Node("elements"),
+            Int("runtimeLang"), Node("vtableHolder"), Node("templateParams"), Node("identifier"),
+            Node("discriminator"), Node("dataLocation"), Node("associated"), Node("allocated"),
+            Node("rank"), Node("annotations"),
+        ]),
+        METADATA_SUBROUTINE_TYPE => ("DISubroutineType", &[Int("flags"), Node("types"), Int("cc")]),
+        METADATA_COMPILE_UNIT => ("DICompileUnit", &[
+            Int("language"), Node("file"), Node("producer"), Int("isOptimized"), Node("flags"),
+            Int("runtimeVersion"), Node("splitDebugFilename"), Int("emissionKind"), Node("enums"),
+            Node("retainedTypes"), Skip, Node("globals"), Node("imports"), Int("dwoId"),
+            Node("macros"), Int("splitDebugInlining"), Int("debugInfoForProfiling"),
+            Int("nameTableKind"), Int("rangesBaseAddress"), Node("sysroot"), Node("sdk"),
+        ]),
+        METADATA_SUBPROGRAM => ("DISubprogram", &[
+            Node("scope"), Node("name"), Node("linkageName"), Node("file"), Int("line"), Node("type"),
+            Int("scopeLine"), Node("containingType"), Int("spFlags"), Int("virtualIndex"),
+            Int("flags"), Node("unit"), Node("templateParams"), Node("declaration"),
+            Node("retainedNodes"), Int("thisAdjustment"), Node("thrownTypes"), Node("annotations"),
+            Node("targetFuncName"),
+        ]),
+        METADATA_LEXICAL_BLOCK => ("DILexicalBlock", &[Node("scope"), Node("file"), Int("line"), Int("column")]),
+        METADATA_LEXICAL_BLOCK_FILE => ("DILexicalBlockFile", &[Node("scope"), Node("file"), Int("discriminator")]),
+        METADATA_NAMESPACE if record.ops.len() == 3 => ("DINamespace", &[Node("scope"), Node("name")]),
+        METADATA_NAMESPACE => ("DINamespace", &[Node("scope"), Node("file"), Node("name"), Int("line")]),
+        METADATA_TEMPLATE_TYPE => ("DITemplateTypeParameter", &[Node("name"), Node("type"), Int("defaulted")]),
+        METADATA_TEMPLATE_VALUE if record.ops.len() > 5 => ("DITemplateValueParameter", &[
+            Int("tag"), Node("name"), Node("type"), Int("defaulted"), Node("value"),
+        ]),
+        METADATA_TEMPLATE_VALUE => ("DITemplateValueParameter", &[
+            Int("tag"), Node("name"), Node("type"), Node("value"),
+        ]),
+        METADATA_GLOBAL_VAR => ("DIGlobalVariable", &[
+            Node("scope"), Node("name"), Node("linkageName"), Node("file"), Int("line"), Node("type"),
+            Int("isLocal"), Int("isDefinition"), Node("declaration"), Node("templateParams"),
+            Int("align"), Node("annotations"),
+        ]),
+        METADATA_LOCAL_VAR => ("DILocalVariable", &[
+            Node("scope"), Node("name"), Node("file"), Int("line"), Node("type"), Int("arg"),
+            Int("flags"), Int("align"), Node("annotations"),
+        ]),
+        METADATA_LABEL => ("DILabel", &[Node("scope"), Node("name"), Node("file"), Int("line")]),
+        METADATA_GLOBAL_VAR_EXPR => ("DIGlobalVariableExpression", &[Node("var"), Node("expr")]),
+        METADATA_OBJC_PROPERTY => ("DIObjCProperty", &[
+            Node("name"), Node("file"), Int("line"), Node("setter"), Node("getter"),
+            Int("attributes"), Node("type"),
+        ]),
+        METADATA_IMPORTED_ENTITY => ("DIImportedEntity", &[
+            Int("tag"), Node("scope"), Node("entity"), Int("line"), Node("name"), Node("file"),
+            Node("elements"),
+        ]),
+        METADATA_MODULE => ("DIModule", &[
+            Node("file"), Node("scope"), Node("name"), Node("configMacros"), Node("includePath"),
+            Node("apinotes"), Int("line"), Int("isDecl"),
+        ]),
+        METADATA_MACRO => ("DIMacro", &[Int("type"), Int("line"), Node("name"), Node("value")]),
+        METADATA_MACRO_FILE => ("DIMacroFile", &[Int("type"), Int("line"), Node("file"), Node("nodes")]),
+        METADATA_COMMON_BLOCK => ("DICommonBlock", &[
+            Node("scope"), Node("declaration"), Node("name"), Node("file"), Int("line"),
+        ]),
+        METADATA_ASSIGN_ID => ("DIAssignID", &[]),
+        _ => return None,
+    })
+}
+
+impl BitcodeReader {
+    /// Create a reader that builds types and values in `context`
+    pub fn new(context: Context) -> Self {
+        Self {
+            context,
+            version: 0,
+            strtab: Vec::new(),
+            types: Vec::new(),
+            attribute_groups: HashMap::new(),
+            attribute_lists: Vec::new(),
+            section_names: Vec::new(),
+            gc_names: Vec::new(),
+            comdats: Vec::new(),
+            values: Vec::new(),
+            metadata: Vec::new(),
+            next_metadata_node: 0,
+            metadata_kinds: HashMap::new(),
+            global_attachments: Vec::new(),
+            bundle_tags: Vec::new(),
+            function_block_names: HashMap::new(),
+        }
+    }
+
+    /// Build a module from the top-level blocks of a bitcode file
+    pub fn read_module(mut self, blocks: &[Block]) -> BitcodeResult<Module> {
+        let mut module_block = None;
+        for block in blocks {
+            match block.id {
+                IDENTIFICATION_BLOCK_ID => self.read_identification(block)?,
+                MODULE_BLOCK_ID if module_block.is_none() => module_block = Some(block),
+                STRTAB_BLOCK_ID if self.strtab.is_empty() => {
+                    if let Some(record) = block.records().find(|r| r.code == STRTAB_BLOB) {
+                        self.strtab = record.blob.clone().unwrap_or_default();
+                    }
+                }
+                _ => {}
+            }
+        }
+        let module_block = module_block.ok_or_else(|| invalid("module", "no module block"))?;
+
+        // Module-wide tables that everything else refers to
+        let mut triple = None;
+        let mut data_layout = None;
+        let mut source_filename = None;
+        for entry in &module_block.entries {
+            match entry {
+                BlockEntry::Record(record) => match record.code {
+                    MODULE_CODE_VERSION => self.version = operand(record, 0, "module")?,
+                    MODULE_CODE_TRIPLE => triple = Some(record.string()),
+                    MODULE_CODE_DATALAYOUT => data_layout = Some(record.string()),
+                    MODULE_CODE_SOURCE_FILENAME => source_filename = Some(record.string()),
+                    MODULE_CODE_SECTIONNAME => self.section_names.push(record.string()),
+                    MODULE_CODE_GCNAME => self.gc_names.push(record.string()),
+                    MODULE_CODE_COMDAT => {
+                        let name = if self.version >= 2 {
+                            self.strtab_name(record)?
+                        } else {
+                            // [selection_kind, name_size, name...]
+                            record.string_from(2)
+                        };
+                        self.comdats.push(name);
+                    }
+                    _ => {}
+                },
+                BlockEntry::Block(block) => match block.id {
+                    PARAMATTR_GROUP_BLOCK_ID => self.read_attribute_groups(block)?,
+                    PARAMATTR_BLOCK_ID => self.read_attribute_lists(block)?,
+                    TYPE_BLOCK_ID_NEW => self.read_type_table(block)?,
+                    METADATA_KIND_BLOCK_ID => self.read_metadata_kinds(block)?,
+                    OPERAND_BUNDLE_TAGS_BLOCK_ID => {
+                        for record in block.records() {
+                            if record.code == OPERAND_BUNDLE_TAG {
+                                self.bundle_tags.push(record.string());
+                            }
+                        }
+                    }
+                    _ => {}
+                },
+            }
+        }
+
+        let name = source_filename.clone().unwrap_or_else(|| "bitcode".to_string());
+        let module = Module::new(name, self.context.clone());
+        if let Some(source_filename) = source_filename {
+            module.set_source_filename(source_filename);
+        }
+        if let Some(triple) = triple {
+            module.set_target_triple(triple);
+        }
+        if let Some(layout) = data_layout {
+            module.set_data_layout(layout);
+        }
+        for id in 0..self.types.len() {
+            let ty = self.type_by_id(id as u64)?;
+            if let Some(name) = self.types[id].name.clone() {
+                module.add_named_type(name, ty);
+            }
+        }
+
+        // Before version 2 global names live in the module symbol table
+        let mut vst_names = HashMap::new();
+        if self.version < 2 {
+            if let Some(vst) = module_block.block(VALUE_SYMTAB_BLOCK_ID) {
+                for record in vst.records() {
+                    match record.code {
+                        VST_CODE_ENTRY => { vst_names.insert(operand(record, 0, "symbol table")?, record.string_from(1)); }
+                        VST_CODE_FNENTRY => { vst_names.insert(operand(record, 0, "symbol table")?, record.string_from(2)); }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        // Global values take the first value IDs, in record order
+        let mut globals = Vec::new();
+        for record in module_block.records() {
+            let name = || -> BitcodeResult<String> {
+                Ok(vst_names.get(&(globals.len() as u64)).cloned().unwrap_or_default())
+            };
+            let entry = match record.code {
+                MODULE_CODE_GLOBALVAR => {
+                    let name = if self.version >= 2 { self.strtab_name(record)? } else { name()? };
+                    self.read_global_var(record, name)?
+                }
+                MODULE_CODE_FUNCTION => {
+                    let name = if self.version >= 2 { self.strtab_name(record)? } else { name()? };
+                    self.read_function_record(record, name, &module)?
+                }
+                MODULE_CODE_ALIAS | MODULE_CODE_ALIAS_OLD => {
+                    let name = if self.version >= 2 { self.strtab_name(record)? } else { name()? };
+                    self.read_alias(record, name)?
+                }
+                MODULE_CODE_IFUNC => {
+                    let name = if self.version >= 2 { self.strtab_name(record)? } else { name()? };
+                    let fields = self.global_fields(record);
+                    let ty = self.type_by_id(operand(record, fields, "module")?)?;
+                    let ptr = self.global_pointer_type(&ty, 0);
+                    self.values.push(ValueSlot::Ready(Value::new(ptr, ValueKind::GlobalVariable { is_constant: false }, Some(name))));
+                    GlobalEntry::IFunc
+                }
+                _ => continue,
+            };
+            globals.push(entry);
+        }
+
+        // Module constants follow the global values
+        for block in module_block.blocks().filter(|b| b.id == CONSTANTS_BLOCK_ID) {
+            self.read_constants(block)?;
+        }
+
+        for block in module_block.blocks().filter(|b| b.id == METADATA_BLOCK_ID) {
+            self.read_metadata(block, &module)?;
+        }
+
+        // Function blocks appear in the order of the function records with bodies
+        let mut definitions = globals.iter().enumerate().filter_map(|(id, entry)| match entry {
+            GlobalEntry::Function { function, has_body: true, .. } => Some((id, function.clone())),
+            _ => None,
+        });
+        let mut bodies = Vec::new();
+        for block in module_block.blocks().filter(|b| b.id == FUNCTION_BLOCK_ID) {
+            let (id, function) = definitions.next()
+                .ok_or_else(|| invalid("function", "more function bodies than definitions"))?;
+            let body = self.decode_function(block, function, &module)?;
+            self.function_block_names.insert(id, body.block_names.clone());
+            bodies.push(body);
+        }
+        for body in bodies {
+            self.build_function(body)?;
+        }
+
+        let global_attachments = std::mem::take(&mut self.global_attachments);
+        for (id, entry) in globals.into_iter().enumerate() {
+            let mut attachments = global_attachments.iter().filter(|(global, ..)| *global == id);
+            match entry {
+                GlobalEntry::Variable { mut global, initializer } => {
+                    if let Some(id) = initializer {
+                        global.initializer = Some(self.value(id)?);
+                    }
+                    global.metadata = attachments.map(|(_, kind, node)| (kind.clone(), node.clone())).collect();
+                    module.add_global(global).map_err(|e| invalid("module", e))?;
+                }
+                GlobalEntry::Function { function, personality, .. } => {
+                    if let Some(id) = personality {
+                        function.set_personality(Some(self.value(id)?));
+                    }
+                    if let Some((.., subprogram)) = attachments.rfind(|(_, kind, _)| kind == "dbg") {
+                        function.set_subprogram(Some(subprogram.clone()));
+                    }
+                }
+                GlobalEntry::Alias { mut alias, aliasee } => {
+                    alias.aliasee = self.value(aliasee)?;
+                    module.add_alias(alias).map_err(|e| invalid("module", e))?;
+                }
+                GlobalEntry::IFunc => {}
+            }
+        }
+
+        Ok(module)
+    }
+
+    fn read_identification(&mut self, block: &Block) -> BitcodeResult<()> {
+        for record in block.records() {
+            if record.code == IDENTIFICATION_CODE_EPOCH {
+                let epoch = operand(record, 0, "identification")?;
+                if epoch != BITCODE_CURRENT_EPOCH {
+                    return Err(BitcodeError::Unsupported(format!("bitcode epoch {}", epoch)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Name of a global value or comdat whose first two operands index the strtab
+    fn strtab_name(&self, record: &Record) -> BitcodeResult<String> {
+        let offset = operand(record, 0, "module")? as usize;
+        let size = operand(record, 1, "module")? as usize;
+        let bytes = self.strtab.get(offset..offset.saturating_add(size))
+            .ok_or_else(|| invalid("module", "name is outside the string table"))?;
+        Ok(String::from_utf8_lossy(bytes).into_owned())
+    }
+
+    /// Index of the first field after the strtab name, if any
+    fn global_fields(&self, _record: &Record) -> usize {
+        if self.version >= 2 { 2 } else { 0 }
+    }
+
+    // Types
+
+    fn read_type_table(&mut self, block: &Block) -> BitcodeResult<()> {
+        let mut pending_name = None;
+        for record in block.records() {
+            match record.code {
+                TYPE_CODE_NUMENTRY => {}
+                TYPE_CODE_STRUCT_NAME => pending_name = Some(record.string()),
+                code => {
+                    let name = match code {
+                        TYPE_CODE_STRUCT_NAMED | TYPE_CODE_OPAQUE | TYPE_CODE_TARGET_TYPE => pending_name.take(),
+                        _ => None,
+                    };
+                    self.types.push(TypeEntry { record: record.clone(), name, resolved: None, resolving: false });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn type_by_id(&mut self, id: u64) -> BitcodeResult<Type> {
+        let index = id as usize;
+        let entry = self.types.get_mut(index)
+            .ok_or_else(|| invalid("type", format!("undefined type {}", id)))?;
+        if let Some(ty) = &entry.resolved {
+            return Ok(ty.clone());
+        }
+        if entry.resolving {
+            // Only named structs can refer to themselves
+            let name = entry.name.clone().unwrap_or_default();
+            return Ok(Type::opaque(&self.context, name));
+        }
+        entry.resolving = true;
+        let record = entry.record.clone();
+        let name = entry.name.clone();
+        let ty = self.build_type(&record, name)?;
+        let entry = &mut self.types[index];
+        entry.resolving = false;
+        entry.resolved = Some(ty.clone());
+        Ok(ty)
+    }
+
+    fn build_type(&mut self, record: &Record, name: Option<String>) -> BitcodeResult<Type> {
+        let ctx = self.context.clone();
+        let op = |i: usize| operand(record, i, "type");
+        Ok(match record.code {
+            TYPE_CODE_VOID => ctx.void_type(),
+            TYPE_CODE_HALF => ctx.half_type(),
+            TYPE_CODE_DOUBLE => ctx.double_type(),
+            // Formats without a dedicated type are represented as float, as in the parser
+            TYPE_CODE_FLOAT | TYPE_CODE_BFLOAT | TYPE_CODE_X86_FP80 | TYPE_CODE_FP128 | TYPE_CODE_PPC_FP128 => ctx.float_type(),
+            TYPE_CODE_X86_MMX => ctx.void_type(),
+            TYPE_CODE_LABEL => ctx.label_type(),
+            TYPE_CODE_METADATA => ctx.metadata_type(),
+            TYPE_CODE_TOKEN => ctx.token_type(),
+            TYPE_CODE_X86_AMX => ctx.x86_amx_type(),
+            TYPE_CODE_INTEGER => ctx.int_type(op(0)? as u32),
+            TYPE_CODE_POINTER => {
+                let pointee = self.type_by_id(op(0)?)?;
+                let address_space = record.ops.get(1).copied().unwrap_or(0) as u32;
+                Type::ptr_addrspace(&ctx, pointee, address_space)
+            }
+            TYPE_CODE_OPAQUE_POINTER => {
+                let address_space = record.ops.first().copied().unwrap_or(0) as u32;
+                Type::ptr_addrspace(&ctx, ctx.int8_type(), address_space)
+            }
+            TYPE_CODE_ARRAY => {
+                let element = self.type_by_id(op(1)?)?;
+                ctx.array_type(element, op(0)? as usize)
+            }
+            TYPE_CODE_VECTOR => {
+                let element = self.type_by_id(op(1)?)?;
+                ctx.vector_type(element, op(0)? as usize)
+            }
+            TYPE_CODE_FUNCTION | TYPE_CODE_FUNCTION_OLD => {
+                // FUNCTION: [vararg, retty, paramty...]; FUNCTION_OLD has an attribute ID first
+                let first = if record.code == TYPE_CODE_FUNCTION { 1 } else { 2 };
+                let return_type = self.type_by_id(op(first)?)?;
+                let mut params = Vec::new();
+                for &id in &record.ops[first + 1..] {
+                    params.push(self.type_by_id(id)?);
+                }
+                ctx.function_type(return_type, params, op(0)? != 0)
+            }
+            TYPE_CODE_STRUCT_ANON | TYPE_CODE_STRUCT_NAMED => {
+                let mut fields = Vec::new();
+                for &id in record.ops.iter().skip(1) {
+                    fields.push(self.type_by_id(id)?);
+                }
+                Type::struct_type_packed(&ctx, fields, name, op(0)? != 0)
+            }
+            TYPE_CODE_OPAQUE => Type::opaque(&ctx, name.unwrap_or_default()),
+            TYPE_CODE_TARGET_TYPE => Type::opaque(&ctx, format!("target(\"{}\")", name.unwrap_or_default())),
+            code => return Err(BitcodeError::Unsupported(format!("type code {}", code))),
+        })
+    }
+
+    /// Type of a reference to a global whose value type is `ty`
+    fn global_pointer_type(&self, ty: &Type, address_space: u32) -> Type {
+        if ty.is_function() {
+            return self.context.ptr_type(ty.clone());
+        }
+        let typed_pointers = self.types.iter().any(|t| t.record.code == TYPE_CODE_POINTER);
+        let pointee = if typed_pointers { ty.clone() } else { self.context.int8_type() };
+        Type::ptr_addrspace(&self.context, pointee, address_space)
+    }
+
+    /// Element type ID of an array or vector type ID
+    fn element_type_id(&self, id: u64) -> Option<u64> {
+        let record = &self.types.get(id as usize)?.record;
+        match record.code {
+            TYPE_CODE_ARRAY | TYPE_CODE_VECTOR => record.ops.get(1).copied(),
+            _ => None,
+        }
+    }
+
+    fn type_code(&self, id: u64) -> Option<u32> {
+        self.types.get(id as usize).map(|t| t.record.code)
+    }
+
+    // Attributes
+
+    fn read_attribute_groups(&mut self, block: &Block) -> BitcodeResult<()> {
+        for record in block.records().filter(|r| r.code == PARAMATTR_GRP_CODE_ENTRY) {
+            let group_id = operand(record, 0, "attribute group")?;
+            let index = operand(record, 1, "attribute group")?;
+            let mut attributes = Vec::new();
+            let mut i = 2;
+            let read_string = |i: &mut usize| {
+                let mut s = String::new();
+                while let Some(&c) = record.ops.get(*i) {
+                    *i += 1;
+                    if c == 0 {
+                        break;
+                    }
+                    s.push(c as u8 as char);
+                }
+                s
+            };
+            while i < record.ops.len() {
+                let kind = record.ops[i];
+                i += 1;
+                let attribute = match kind {
+                    ATTR_KIND_ENUM => {
+                        i += 1;
+                        Attribute::Enum(operand(record, i - 1, "attribute group")?)
+                    }
+                    ATTR_KIND_INT => {
+                        i += 2;
+                        Attribute::Int(operand(record, i - 2, "attribute group")?, operand(record, i - 1, "attribute group")?)
+                    }
+                    ATTR_KIND_STRING | ATTR_KIND_STRING_VALUE => {
+                        let key = read_string(&mut i);
+                        let value = if kind == ATTR_KIND_STRING_VALUE { read_string(&mut i) } else { String::new() };
+                        Attribute::String(key, value)
+                    }
+                    ATTR_KIND_TYPE | ATTR_KIND_TYPE_VALUE => {
+                        let attr = operand(record, i, "attribute group")?;
+                        i += 1;
+                        let ty = if kind == ATTR_KIND_TYPE_VALUE {
+                            i += 1;
+                            Some(self.type_by_id(operand(record, i - 1, "attribute group")?)?)
+                        } else {
+                            None
+                        };
+                        Attribute::Type(attr, ty)
+                    }
+                    // Constant range and integer list attributes: [kind, bitwidth/count, values...]
+                    _ => return Err(BitcodeError::Unsupported(format!("attribute encoding {}", kind))),
+                };
+                attributes.push(attribute);
+            }
+            self.attribute_groups.insert(group_id, AttributeGroup { index, attributes });
+        }
+        Ok(())
+    }
+
+    fn read_attribute_lists(&mut self, block: &Block) -> BitcodeResult<()> {
+        for record in block.records() {
+            match record.code {
+                PARAMATTR_CODE_ENTRY => self.attribute_lists.push(record.ops.clone()),
+                PARAMATTR_CODE_ENTRY_OLD => {
+                    return Err(BitcodeError::Unsupported("pre-3.3 attribute encoding".to_string()))
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Function, return and parameter attributes of attribute list `list` (1-based)
+    fn function_attributes(&self, list: u64, param_count: usize) -> FunctionAttributes {
+        let mut attrs = FunctionAttributes {
+            parameter_attributes: vec![ParameterAttributes::default(); param_count],
+            ..Default::default()
+        };
+        let groups = match list.checked_sub(1).and_then(|i| self.attribute_lists.get(i as usize)) {
+            Some(groups) => groups,
+            None => return attrs,
+        };
+        for group in groups.iter().filter_map(|id| self.attribute_groups.get(id)) {
+            for attribute in &group.attributes {
+                match group.index {
+                    ATTR_FUNCTION_INDEX => apply_function_attribute(&mut attrs, attribute),
+                    0 => apply_return_attribute(&mut attrs.return_attributes, attribute),
+                    index => {
+                        if let Some(param) = attrs.parameter_attributes.get_mut(index as usize - 1) {
+                            apply_parameter_attribute(param, attribute);
+                        }
+                    }
+                }
+            }
+        }
+        attrs
+    }
+
+    fn read_metadata_kinds(&mut self, block: &Block) -> BitcodeResult<()> {
+        for record in block.records().filter(|r| r.code == METADATA_KIND) {
+            self.metadata_kinds.insert(operand(record, 0, "metadata kind")?, record.string_from(1));
+        }
+        Ok(())
+    }
+
+    // Global values
+
+    fn read_global_var(&mut self, record: &Record, name: String) -> BitcodeResult<GlobalEntry> {
+        // [type, isconst|explicit<<1|addrspace<<2, initid+1, linkage, align, section,
+        //  visibility, threadlocal, unnamed_addr, externally_initialized, dllstorageclass, comdat, ...]
+        let f = self.global_fields(record);
+        let op = |i: usize| operand(record, f + i, "module");
+        let opt = |i: usize| record.ops.get(f + i).copied().unwrap_or(0);
+        let flags = op(1)?;
+        let mut ty = self.type_by_id(op(0)?)?;
+        let mut address_space = (flags >> 2) as u32;
+        if flags & 2 == 0 {
+            // The type is the pointer type of the global
+            if let Some((pointee, space)) = ty.pointer_info() {
+                address_space = space;
+                ty = pointee.clone();
+            }
+        }
+        let initializer = op(2)?.checked_sub(1).map(|id| id as usize);
+        let mut global = GlobalVariable::new(name.clone(), ty.clone(), flags & 1 != 0, None);
+        global.linkage = decode_linkage(op(3)?);
+        global.alignment = decode_alignment(opt(4)).map(|a| a as u32);
+        global.section = opt(5).checked_sub(1).and_then(|i| self.section_names.get(i as usize).cloned());
+        global.visibility = decode_visibility(opt(6));
+        global.thread_local_mode = decode_thread_local(opt(7));
+        global.unnamed_addr = decode_unnamed_addr(opt(8));
+        global.externally_initialized = opt(9) != 0;
+        global.dll_storage_class = decode_dll_storage_class(opt(10));
+        global.comdat = opt(11).checked_sub(1).and_then(|i| self.comdats.get(i as usize).cloned());
+        if address_space != 0 {
+            global.addrspace = Some(address_space);
+        }
+
+        let ptr = self.global_pointer_type(&ty, address_space);
+        let value = Value::new(ptr, ValueKind::GlobalVariable { is_constant: global.is_constant }, Some(name));
+        self.values.push(ValueSlot::Ready(value));
+        Ok(GlobalEntry::Variable { global, initializer })
+    }
+
+    fn read_function_record(&mut self, record: &Record, name: String, module: &Module) -> BitcodeResult<GlobalEntry> {
+        // [type, callingconv, isproto, linkage, paramattr, alignment, section, visibility, gc,
+        //  unnamed_addr, prologuedata, dllstorageclass, comdat, prefixdata, personalityfn, ...]
+        let f = self.global_fields(record);
+        let op = |i: usize| operand(record, f + i, "module");
+        let opt = |i: usize| record.ops.get(f + i).copied().unwrap_or(0);
+        let mut ty = self.type_by_id(op(0)?)?;
+        if let Some((pointee, _)) = ty.pointer_info() {
+            ty = pointee.clone();
+        }
+        let (_, param_types, _) = ty.function_info()
+            .ok_or_else(|| invalid("module", format!("function @{} does not have function type", name)))?;
+
+        let function = Function::new(name.clone(), ty.clone());
+        function.set_calling_convention(decode_calling_convention(op(1)?));
+        function.set_linkage(decode_linkage(op(3)?));
+        function.set_attributes(self.function_attributes(opt(4), param_types.len()));
+        function.set_visibility(decode_visibility(opt(7)));
+        function.set_dll_storage_class(decode_dll_storage_class(opt(11)));
+        module.add_function(function.clone());
+
+        let value = Value::new(self.context.ptr_type(ty), ValueKind::GlobalVariable { is_constant: false }, Some(name));
+        self.values.push(ValueSlot::Ready(value));
+        let personality = opt(14).checked_sub(1).map(|id| id as usize);
+        Ok(GlobalEntry::Function { function, personality, has_body: op(2)? == 0 })
+    }
+
+    fn read_alias(&mut self, record: &Record, name: String) -> BitcodeResult<GlobalEntry> {
+        // ALIAS: [alias value type, addrspace, aliasee, linkage, visibility, dllstorageclass,
+        //         threadlocal, unnamed_addr, ...]
+        // ALIAS_OLD: [alias pointer type, aliasee, linkage, visibility, ...]
+        let f = self.global_fields(record);
+        let op = |i: usize| operand(record, f + i, "module");
+        let mut ty = self.type_by_id(op(0)?)?;
+        let (mut address_space, rest) = if record.code == MODULE_CODE_ALIAS {
+            (op(1)? as u32, f + 2)
+        } else {
+            (0, f + 1)
+        };
+        if record.code == MODULE_CODE_ALIAS_OLD {
+            if let Some((pointee, space)) = ty.pointer_info() {
+                address_space = space;
+                ty = pointee.clone();
+            }
+        }
+        let opt = |i: usize| record.ops.get(rest + i).copied().unwrap_or(0);
+        let alias = Alias {
+            name: name.clone(),
+            ty: ty.clone(),
+            aliasee: Value::undef(ty.clone()),
+            linkage: decode_linkage(operand(record, rest + 1, "module")?),
+            visibility: decode_visibility(opt(2)),
+            dll_storage_class: decode_dll_storage_class(opt(3)),
+            thread_local_mode: decode_thread_local(opt(4)),
+            unnamed_addr: decode_unnamed_addr(opt(5)),
+        };
+        let ptr = self.global_pointer_type(&ty, address_space);
+        self.values.push(ValueSlot::Ready(Value::new(ptr, ValueKind::GlobalVariable { is_constant: false }, Some(name))));
+        Ok(GlobalEntry::Alias { alias, aliasee: operand(record, rest, "module")? as usize })
+    }
+
+    // Values and constants
+
+    fn read_constants(&mut self, block: &Block) -> BitcodeResult<()> {
+        let mut type_id = None;
+        for record in block.records() {
+            if record.code == CST_CODE_SETTYPE {
+                type_id = Some(operand(record, 0, "constants")?);
+                continue;
+            }
+            let type_id = type_id.ok_or_else(|| invalid("constants", "constant before its type"))?;
+            self.values.push(ValueSlot::Constant { type_id, record: record.clone() });
+        }
+        Ok(())
+    }
+
+    /// The value with ID `id`, decoding it if it is a constant
+    fn value(&mut self, id: usize) -> BitcodeResult<Value> {
+        let slot = self.values.get_mut(id)
+            .ok_or_else(|| invalid("constants", format!("undefined value {}", id)))?;
+        let (type_id, record) = match slot {
+            ValueSlot::Ready(value) => return Ok(value.clone()),
+            ValueSlot::Resolving { .. } => {
+                return Err(invalid("constants", format!("constant {} refers to itself", id)))
+            }
+            ValueSlot::Constant { type_id, record } => (*type_id, std::mem::replace(record, Record {
+                code: 0,
+                ops: Vec::new(),
+                blob: None,
+                abbrev_id: 0,
+            })),
+        };
+        *slot = ValueSlot::Resolving { type_id };
+        let result = self.decode_constant(type_id, &record);
+        match result {
+            Ok(value) => {
+                self.values[id] = ValueSlot::Ready(value.clone());
+                Ok(value)
+            }
+            Err(e) => {
+                self.values[id] = ValueSlot::Constant { type_id, record };
+                Err(e)
+            }
+        }
+    }
+
+    /// Type of the value with ID `id` without decoding it
+    fn value_type(&mut self, id: usize, state: Option<&FunctionState>) -> BitcodeResult<Type> {
+        match self.values.get(id) {
+            Some(ValueSlot::Ready(value)) => Ok(value.get_type().clone()),
+            Some(ValueSlot::Constant { type_id, .. }) | Some(ValueSlot::Resolving { type_id }) => {
+                let type_id = *type_id;
+                self.type_by_id(type_id)
+            }
+            None => state
+                .and_then(|s| id.checked_sub(s.base).and_then(|i| s.result_types.get(i)))
+                .cloned()
+                .ok_or_else(|| invalid("function", format!("undefined value {}", id))),
+        }
+    }
+
+    fn scalar_from_bits(&mut self, type_id: u64, ty: &Type, bits: &[u64]) -> BitcodeResult<Value> {
+        if ty.is_integer() {
+            let value = bits.first().copied().unwrap_or(0);
+            return Ok(Value::const_int(ty.clone(), value as i64, None));
+        }
+        let word = |i: usize| bits.get(i).copied().unwrap_or(0);
+        let value = match self.type_code(type_id) {
+            Some(TYPE_CODE_HALF) => half_to_f64(word(0) as u16),
+            Some(TYPE_CODE_BFLOAT) => f32::from_bits((word(0) as u32) << 16) as f64,
+            Some(TYPE_CODE_FLOAT) => f32::from_bits(word(0) as u32) as f64,
+            // [sign/exponent << 48 | mantissa >> 16, mantissa & 0xFFFF]
+            Some(TYPE_CODE_X86_FP80) => x87_to_f64(word(0) << 16 | (word(1) & 0xFFFF), word(0) >> 48),
+            Some(TYPE_CODE_FP128) => quad_to_f64(word(0), word(1)),
+            // ppc_fp128 is a pair of doubles; the high-order one dominates
+            Some(TYPE_CODE_PPC_FP128) => f64::from_bits(word(0)),
+            _ => f64::from_bits(word(0)),
+        };
+        Ok(Value::const_float(ty.clone(), value, None))
+    }
+
+    fn decode_constant(&mut self, type_id: u64, record: &Record) -> BitcodeResult<Value> {
+        let ty = self.type_by_id(type_id)?;
+        let op = |i: usize| operand(record, i, "constants");
+        Ok(match record.code {
+            CST_CODE_NULL => {
+                if ty.is_pointer() {
+                    Value::const_null(ty)
+                } else if ty.is_integer() {
+                    Value::const_int(ty, 0, None)
+                } else if ty.is_float() {
+                    Value::const_float(ty, 0.0, None)
+                } else if ty.is_token() {
+                    // `none`, represented as the parser does
+                    Value::undef(ty)
+                } else {
+                    Value::zero_initializer(ty)
+                }
+            }
+            CST_CODE_UNDEF => Value::undef(ty),
+            CST_CODE_POISON => Value::poison(ty),
+            // i1 is stored sign-extended; keep `true` as 1 like the parser
+            CST_CODE_INTEGER if ty.int_width() == Some(1) => Value::const_int(ty, decode_signed(op(0)?) & 1, None),
+            CST_CODE_INTEGER => Value::const_int(ty, decode_signed(op(0)?), None),
+            CST_CODE_WIDE_INTEGER => {
+                // Only the low word is representable
+                Value::const_int(ty, decode_signed(op(0)?), None)
+            }
+            CST_CODE_FLOAT => self.scalar_from_bits(type_id, &ty, &record.ops)?,
+            CST_CODE_AGGREGATE => {
+                let mut elements = Vec::new();
+                for &id in &record.ops {
+                    elements.push(self.value(id as usize)?);
+                }
+                if ty.is_struct() {
+                    Value::const_struct(ty, elements)
+                } else if ty.is_vector() {
+                    Value::const_vector(ty, elements)
+                } else {
+                    Value::const_array(ty, elements)
+                }
+            }
+            CST_CODE_STRING | CST_CODE_CSTRING | CST_CODE_DATA => {
+                let element_id = self.element_type_id(type_id)
+                    .ok_or_else(|| invalid("constants", "data constant without an element type"))?;
+                let element_ty = self.type_by_id(element_id)?;
+                let mut elements = Vec::new();
+                for &bits in &record.ops {
+                    elements.push(self.scalar_from_bits(element_id, &element_ty, &[bits])?);
+                }
+                if record.code == CST_CODE_CSTRING {
+                    elements.push(Value::const_int(element_ty, 0, None));
+                }
+                if ty.is_vector() {
+                    Value::const_vector(ty, elements)
+                } else {
+                    Value::const_array(ty, elements)
+                }
+            }
+            CST_CODE_CE_BINOP => {
+                let lhs = self.value(op(1)? as usize)?;
+                let rhs = self.value(op(2)? as usize)?;
+                let opcode = decode_binop(op(0)?, &ty)
+                    .ok_or_else(|| invalid("constants", "unknown binary opcode"))?;
+                Value::const_expr(ty, opcode, vec![lhs, rhs])
+            }
+            CST_CODE_CE_UNOP => {
+                let value = self.value(op(1)? as usize)?;
+                Value::const_expr(ty, Opcode::FNeg, vec![value])
+            }
+            CST_CODE_CE_CAST => {
+                let opcode = decode_cast(op(0)?).ok_or_else(|| invalid("constants", "unknown cast opcode"))?;
+                let value = self.value(op(2)? as usize)?;
+                Value::const_expr(ty, opcode, vec![value])
+            }
+            CST_CODE_CE_GEP_OLD | CST_CODE_CE_INBOUNDS_GEP | CST_CODE_CE_GEP
+            | CST_CODE_CE_GEP_WITH_INRANGE_INDEX_OLD | CST_CODE_CE_GEP_WITH_INRANGE => {
+                // [pointee type, flags, (inrange,) (type, value)...]; old forms omit parts of the header
+                let mut i = match record.code {
+                    CST_CODE_CE_GEP | CST_CODE_CE_GEP_WITH_INRANGE_INDEX_OLD => 2,
+                    CST_CODE_CE_GEP_WITH_INRANGE => {
+                        let width = op(2)?;
+                        if width > 64 {
+                            return Err(BitcodeError::Unsupported("wide GEP inrange bounds".to_string()));
+                        }
+                        5
+                    }
+                    _ => record.ops.len() % 2,
+                };
+                if record.code == CST_CODE_CE_GEP_WITH_INRANGE_INDEX_OLD {
+                    i += 1;
+                }
+                let mut operands = Vec::new();
+                while i + 1 < record.ops.len() {
+                    operands.push(self.value(record.ops[i + 1] as usize)?);
+                    i += 2;
+                }
+                Value::const_expr(ty, Opcode::GetElementPtr, operands)
+            }
+            CST_CODE_CE_SELECT => {
+                let operands = vec![self.value(op(0)? as usize)?, self.value(op(1)? as usize)?, self.value(op(2)? as usize)?];
+                Value::const_expr(ty, Opcode::Select, operands)
+            }
+            CST_CODE_CE_EXTRACTELT => {
+                // [opty, opval, (idxty,) idxval]
+                let vector = self.value(op(1)? as usize)?;
+                let index = self.value(*record.ops.last().unwrap_or(&0) as usize)?;
+                Value::const_expr(ty, Opcode::ExtractElement, vec![vector, index])
+            }
+            CST_CODE_CE_INSERTELT => {
+                // [opval, opval, (idxty,) idxval]
+                let vector = self.value(op(0)? as usize)?;
+                let element = self.value(op(1)? as usize)?;
+                let index = self.value(*record.ops.last().unwrap_or(&0) as usize)?;
+                Value::const_expr(ty, Opcode::InsertElement, vec![vector, element, index])
+            }
+            CST_CODE_CE_SHUFFLEVEC | CST_CODE_CE_SHUFVEC_EX => {
+                let first = if record.code == CST_CODE_CE_SHUFVEC_EX { 1 } else { 0 };
+                let mut operands = Vec::new();
+                for i in first..first + 3 {
+                    operands.push(self.value(op(i)? as usize)?);
+                }
+                Value::const_expr(ty, Opcode::ShuffleVector, operands)
+            }
+            CST_CODE_CE_CMP => {
+                // [opty, lhs, rhs, pred]
+                let operand_ty = self.type_by_id(op(0)?)?;
+                let lhs = self.value(op(1)? as usize)?;
+                let rhs = self.value(op(2)? as usize)?;
+                let opcode = if is_fp(&operand_ty) { Opcode::FCmp } else { Opcode::ICmp };
+                Value::const_expr(ty, opcode, vec![lhs, rhs])
+            }
+            CST_CODE_BLOCKADDRESS => {
+                // [fnty, fn, bb#]
+                let function_id = op(1)? as usize;
+                let function = self.value(function_id)?;
+                let index = op(2)? as usize;
+                let name = self.function_block_names.get(&function_id)
+                    .and_then(|names| names.get(index).cloned())
+                    .unwrap_or_else(|| index.to_string());
+                let block = Value::new(self.context.label_type(), ValueKind::BasicBlock, Some(name));
+                Value::block_address(ty, function, block)
+            }
+            // dso_local_equivalent and no_cfi wrap a global value
+            CST_CODE_DSO_LOCAL_EQUIVALENT | CST_CODE_NO_CFI_VALUE => self.value(op(1)? as usize)?,
+            // Inline asm and ptrauth have no value representation; callees become undef, as in the parser
+            CST_CODE_INLINEASM_OLD | CST_CODE_INLINEASM_OLD2 | CST_CODE_INLINEASM_OLD3
+            | CST_CODE_INLINEASM | CST_CODE_PTRAUTH => Value::undef(ty),
+            code => return Err(BitcodeError::Unsupported(format!("constant code {}", code))),
+        })
+    }
+
+    // Metadata
+
+    fn metadata_operand(&self, id: u64) -> Metadata {
+        match self.metadata.get(id as usize) {
+            Some(MetadataSlot::String(s)) => Metadata::string(s.clone()),
+            Some(MetadataSlot::Inline(md)) => md.clone(),
+            Some(MetadataSlot::Node(name)) => Metadata::reference(name.clone()),
+            // Only calls take the value itself
+            Some(MetadataSlot::Local { ty, .. }) => Metadata::value(Value::undef(ty.clone())),
+            // Null operands, as the parser represents them
+            None => Metadata::tuple(vec![]),
+        }
+    }
+
+    /// Decode a `getMDOrNull` operand (ID + 1, zero for null)
+    fn metadata_or_null(&self, value: u64) -> Option<Metadata> {
+        value.checked_sub(1).map(|id| self.metadata_operand(id))
+    }
+
+    fn read_metadata_strings(&mut self, record: &Record) -> BitcodeResult<()> {
+        // [count, offset to chars] with a blob of vbr6 lengths followed by the characters
+        let count = operand(record, 0, "metadata")? as usize;
+        let offset = operand(record, 1, "metadata")? as usize;
+        let blob = record.blob.as_deref().unwrap_or(&[]);
+        let (lengths, mut chars) = (blob.get(..offset), blob.get(offset..).unwrap_or(&[]));
+        let mut cursor = BitstreamCursor::new(lengths.ok_or_else(|| invalid("metadata", "string offsets out of range"))?);
+        for _ in 0..count {
+            let len = cursor.read_vbr(6)? as usize;
+            let s = chars.get(..len).ok_or_else(|| invalid("metadata", "string out of range"))?;
+            self.metadata.push(MetadataSlot::String(String::from_utf8_lossy(s).into_owned()));
+            chars = &chars[len..];
+        }
+        Ok(())
+    }
+
+    fn read_metadata(&mut self, block: &Block, module: &Module) -> BitcodeResult<()> {
+        let mut nodes = Vec::new();
+        let mut named = Vec::new();
+        let mut name = None;
+        // First number every entry so nodes can refer forward
+        for record in block.records() {
+            match record.code {
+                METADATA_STRINGS => self.read_metadata_strings(record)?,
+                METADATA_STRING_OLD => self.metadata.push(MetadataSlot::String(record.string())),
+                METADATA_VALUE => {
+                    // [ty, value]
+                    let ty = self.type_by_id(operand(record, 0, "metadata")?)?;
+                    let id = operand(record, 1, "metadata")? as usize;
+                    // Arguments are named once the function's symbol table is read
+                    let argument = matches!(self.values.get(id),
+                        Some(ValueSlot::Ready(value)) if matches!(value.kind(), ValueKind::Argument { .. }));
+                    let md = if id < self.values.len() && !argument {
+                        let value = self.value(id)?;
+                        match value.as_const_int() {
+                            Some(i) if value.get_type().is_integer() => Metadata::int(i),
+                            _ => Metadata::value(value),
+                        }
+                    } else {
+                        // A value of the function being read, resolved with its operands
+                        self.metadata.push(MetadataSlot::Local { id, ty });
+                        continue;
+                    };
+                    self.metadata.push(MetadataSlot::Inline(md));
+                }
+                METADATA_EXPRESSION => {
+                    let elements = record.ops.iter().skip(1).map(|&e| Metadata::int(e as i64)).collect();
+                    self.metadata.push(MetadataSlot::Inline(Metadata::named("DIExpression".to_string(), elements)));
+                }
+                METADATA_NAME => name = Some(record.string()),
+                METADATA_NAMED_NODE => {
+                    let name = name.take().ok_or_else(|| invalid("metadata", "named node without a name"))?;
+                    named.push((name, record));
+                }
+                METADATA_KIND => {
+                    self.metadata_kinds.insert(operand(record, 0, "metadata")?, record.string_from(1));
+                }
+                METADATA_INDEX_OFFSET | METADATA_INDEX | METADATA_GLOBAL_DECL_ATTACHMENT => {}
+                _ => {
+                    let id = self.next_metadata_node.to_string();
+                    self.next_metadata_node += 1;
+                    self.metadata.push(MetadataSlot::Node(id.clone()));
+                    nodes.push((id, record));
+                }
+            }
+        }
+
+        for (id, record) in nodes {
+            let node = self.metadata_node(record)?;
+            module.add_metadata(id, node);
+        }
+
+        for (name, record) in named {
+            // Named metadata holds the nodes themselves, as the parser resolves them
+            let operands: Vec<Metadata> = record.ops.iter().map(|&id| {
+                let md = self.metadata_operand(id);
+                match md.as_reference().and_then(|r| module.get_metadata(r)) {
+                    Some(node) => node,
+                    None => md,
+                }
+            }).collect();
+            if name == "llvm.module.flags" {
+                for flag in &operands {
+                    module.add_module_flag(flag.clone());
+                }
+            }
+            module.add_named_metadata(name, operands);
+        }
+
+        for record in block.records().filter(|r| r.code == METADATA_GLOBAL_DECL_ATTACHMENT) {
+            // [value ID, (kind, node)...]
+            let id = operand(record, 0, "metadata")? as usize;
+            for pair in record.ops[1..].chunks_exact(2) {
+                self.global_attachments.push((id, self.metadata_kind(pair[0]), self.metadata_operand(pair[1])));
+            }
+        }
+        Ok(())
+    }
+
+    fn metadata_node(&mut self, record: &Record) -> BitcodeResult<Metadata> {
+        Ok(match record.code {
+            METADATA_NODE | METADATA_DISTINCT_NODE => {
+                Metadata::tuple(record.ops.iter().map(|&op| {
+                    self.metadata_or_null(op).unwrap_or_else(|| Metadata::tuple(vec![]))
+                }).collect())
+            }
+            METADATA_ARG_LIST => {
+                let args = record.ops.iter().map(|&op| self.metadata_operand(op)).collect();
+                Metadata::named("DIArgList".to_string(), args)
+            }
+            METADATA_OLD_NODE | METADATA_OLD_FN_NODE => {
+                // [ty, value] pairs; metadata-typed entries name metadata IDs
+                let mut operands = Vec::new();
+                for pair in record.ops.chunks(2) {
+                    let ty = self.type_by_id(pair[0])?;
+                    let id = *pair.get(1).unwrap_or(&0);
+                    operands.push(if ty.is_metadata() {
+                        self.metadata_operand(id)
+                    } else if ty.is_void() {
+                        Metadata::tuple(vec![])
+                    } else {
+                        Metadata::value(self.value(id as usize)?)
+                    });
+                }
+                Metadata::tuple(operands)
+            }
+            METADATA_ENUMERATOR => {
+                // [flags (distinct|unsigned<<1|bigint<<2), value, name] or, for big integers,
+                // [flags, bitwidth, name, words...]
+                let flags = operand(record, 0, "metadata")?;
+                let (value, name) = if flags & 4 != 0 {
+                    (decode_signed(operand(record, 3, "metadata")?), operand(record, 2, "metadata")?)
+                } else {
+                    (decode_signed(operand(record, 1, "metadata")?), operand(record, 2, "metadata")?)
+                };
+                let mut fields = HashMap::new();
+                fields.insert("value".to_string(), Metadata::int(value));
+                if let Some(name) = self.metadata_or_null(name) {
+                    fields.insert("name".to_string(), name);
+                }
+                if flags & 2 != 0 {
+                    fields.insert("isUnsigned".to_string(), Metadata::int(1));
+                }
+                Metadata::named_with_fields("DIEnumerator".to_string(), fields)
+            }
+            code => {
+                let (name, layout) = di_layout(code, record)
+                    .ok_or_else(|| BitcodeError::Unsupported(format!("metadata code {}", code)))?;
+                let mut fields = HashMap::new();
+                for (field, &raw) in layout.iter().zip(record.ops.iter().skip(1)) {
+                    let (key, md) = match *field {
+                        Int(key) => (key, Some(Metadata::int(raw as i64))),
+                        Signed(key) => (key, Some(Metadata::int(decode_signed(raw)))),
+                        Node(key) => (key, self.metadata_or_null(raw)),
+                        NodeDirect(key) => (key, Some(self.metadata_operand(raw))),
+                        Skip => continue,
+                    };
+                    if let Some(md) = md {
+                        fields.insert(key.to_string(), md);
+                    }
+                }
+                Metadata::named_with_fields(name.to_string(), fields)
+            }
+        })
+    }
+
+    fn metadata_kind(&self, kind: u64) -> String {
+        self.metadata_kinds.get(&kind).cloned().unwrap_or_else(|| format!("kind{}", kind))
+    }
+
+    // Function bodies
+
+    /// ID of a relative operand (absolute before version 1)
+    fn relative_id(&self, state: &FunctionState, raw: u64) -> usize {
+        if self.version >= 1 {
+            (state.next_value_id() as u32).wrapping_sub(raw as u32) as usize
+        } else {
+            raw as usize
+        }
+    }
+
+    /// Read a value operand, followed by its type ID if it is a forward reference
+    fn value_type_pair(&mut self, state: &FunctionState, record: &Record, i: &mut usize) -> BitcodeResult<(usize, Type)> {
+        let id = self.relative_id(state, operand(record, *i, "function")?);
+        *i += 1;
+        if id < state.next_value_id() {
+            Ok((id, self.value_type(id, Some(state))?))
+        } else {
+            let ty = self.type_by_id(operand(record, *i, "function")?)?;
+            *i += 1;
+            Ok((id, ty))
+        }
+    }
+
+    /// Read a value operand whose type is implied by the instruction
+    fn value_operand(&self, state: &FunctionState, record: &Record, i: &mut usize) -> BitcodeResult<usize> {
+        let id = self.relative_id(state, operand(record, *i, "function")?);
+        *i += 1;
+        Ok(id)
+    }
+
+    /// Read a call argument of type `ty`
+    fn call_argument(&mut self, state: &FunctionState, record: &Record, i: &mut usize, ty: &Type) -> BitcodeResult<Operand> {
+        if ty.is_label() {
+            let block = operand(record, *i, "function")? as usize;
+            *i += 1;
+            Ok(Operand::Block(block))
+        } else if ty.is_metadata() {
+            // Metadata arguments name a metadata ID rather than a value
+            let id = self.relative_id(state, operand(record, *i, "function")?) as u64;
+            *i += 1;
+            Ok(match self.metadata.get(id as usize) {
+                Some(MetadataSlot::String(s)) => Operand::Constant(Value::metadata_string(ty.clone(), s.clone())),
+                Some(MetadataSlot::Local { id, .. }) => Operand::Metadata(*id),
+                _ => Operand::Constant(Value::metadata(ty.clone(), self.metadata_operand(id))),
+            })
+        } else {
+            Ok(Operand::Value(self.value_operand(state, record, i)?))
+        }
+    }
+
+    /// Read the fixed and variadic arguments of a call to `fn_ty`
+    fn call_arguments(&mut self, state: &FunctionState, record: &Record, i: &mut usize, fn_ty: &Type) -> BitcodeResult<Vec<Operand>> {
+        let (_, params, is_var_arg) = fn_ty.function_info()
+            .ok_or_else(|| invalid("function", "callee does not have function type"))?;
+        let mut args = Vec::new();
+        for param in &params {
+            args.push(self.call_argument(state, record, i, param)?);
+        }
+        if is_var_arg {
+            while *i < record.ops.len() {
+                args.push(Operand::Value(self.value_type_pair(state, record, i)?.0));
+            }
+        }
+        Ok(args)
+    }
+
+    /// Read a callee and its function type (explicit, or the pointee of the callee's type)
+    fn callee(&mut self, state: &FunctionState, record: &Record, i: &mut usize, explicit: bool) -> BitcodeResult<(usize, Type)> {
+        let explicit_ty = if explicit {
+            *i += 1;
+            Some(self.type_by_id(operand(record, *i - 1, "function")?)?)
+        } else {
+            None
+        };
+        let (callee, callee_ty) = self.value_type_pair(state, record, i)?;
+        let fn_ty = match explicit_ty {
+            Some(ty) => ty,
+            None => callee_ty.pointee_type().cloned()
+                .ok_or_else(|| invalid("function", "callee is not a function pointer"))?,
+        };
+        Ok((callee, fn_ty))
+    }
+
+    fn decode_function(&mut self, block: &Block, function: Function, module: &Module) -> BitcodeResult<FunctionBody> {
+        let module_values = self.values.len();
+        let module_metadata = self.metadata.len();
+        let fn_ty = function.get_type();
+        let (_, arg_types, _) = fn_ty.function_info()
+            .ok_or_else(|| invalid("function", "definition without function type"))?;
+        for (index, ty) in arg_types.iter().enumerate() {
+            // Named once the symbol table is read
+            self.values.push(ValueSlot::Ready(Value::argument(ty.clone(), index, None)));
+        }
+
+        let mut state = FunctionState { base: 0, result_types: Vec::new() };
+        let mut insts: Vec<PendingInst> = Vec::new();
+        let mut names = HashMap::new();
+        let mut block_labels = HashMap::new();
+        let mut block_count = 0;
+        let mut current_block = 0;
+        let mut bundles = Vec::new();
+        let mut started = false;
+        let mut last_location = None;
+
+        for entry in &block.entries {
+            let record = match entry {
+                BlockEntry::Block(sub) => {
+                    match sub.id {
+                        CONSTANTS_BLOCK_ID => self.read_constants(sub)?,
+                        // Nodes are numbered after the module's; the IDs are reused by the next function
+                        METADATA_BLOCK_ID => self.read_metadata(sub, module)?,
+                        VALUE_SYMTAB_BLOCK_ID => {
+                            for record in sub.records() {
+                                let id = operand(record, 0, "symbol table")?;
+                                match record.code {
+                                    VST_CODE_ENTRY => { names.insert(id as usize, record.string_from(1)); }
+                                    VST_CODE_BBENTRY => { block_labels.insert(id as usize, record.string_from(1)); }
+                                    _ => {}
+                                }
+                            }
+                        }
+                        METADATA_ATTACHMENT_ID => {
+                            for record in sub.records().filter(|r| r.code == METADATA_ATTACHMENT) {
+                                if record.ops.len() % 2 == 0 {
+                                    // The function's own: [(kind, node)...]
+                                    for pair in record.ops.chunks(2) {
+                                        if self.metadata_kind(pair[0]) == "dbg" {
+                                            function.set_subprogram(Some(self.metadata_operand(pair[1])));
+                                        }
+                                    }
+                                    continue;
+                                }
+                                let inst = insts.get_mut(record.ops[0] as usize)
+                                    .ok_or_else(|| invalid("metadata attachment", "no such instruction"))?;
+                                for pair in record.ops[1..].chunks(2) {
+                                    let kind = self.metadata_kind(pair[0]);
+                                    inst.attachments.push(kind.clone());
+                                    inst.metadata.push((kind, self.metadata_operand(pair[1])));
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                    continue;
+                }
+                BlockEntry::Record(record) => record,
+            };
+            if record.code == FUNC_CODE_DECLAREBLOCKS {
+                block_count = operand(record, 0, "function")? as usize;
+                continue;
+            }
+            // Instruction results are numbered after the function's constants
+            if !started {
+                started = true;
+                state.base = self.values.len();
+            }
+            if record.code == FUNC_CODE_DEBUG_LOC {
+                // [line, column, scope + 1, inlinedAt + 1, isImplicitCode]
+                let mut fields = HashMap::new();
+                fields.insert("line".to_string(), Metadata::int(operand(record, 0, "function")? as i64));
+                fields.insert("column".to_string(), Metadata::int(operand(record, 1, "function")? as i64));
+                for (key, &op) in ["scope", "inlinedAt"].into_iter().zip(record.ops.iter().skip(2)) {
+                    if let Some(md) = self.metadata_or_null(op) {
+                        fields.insert(key.to_string(), md);
+                    }
+                }
+                if record.ops.get(4).is_some_and(|&implicit| implicit != 0) {
+                    fields.insert("isImplicitCode".to_string(), Metadata::int(1));
+                }
+                last_location = Some(Metadata::named_with_fields("DILocation".to_string(), fields));
+            }
+            if record.code == FUNC_CODE_DEBUG_LOC || record.code == FUNC_CODE_DEBUG_LOC_AGAIN {
+                let last = insts.last_mut().ok_or_else(|| invalid("function", "debug location before any instruction"))?;
+                last.attachments.push("dbg".to_string());
+                last.debug_loc = last_location.clone();
+                continue;
+            }
+            if record.code == FUNC_CODE_OPERAND_BUNDLE {
+                let tag_id = operand(record, 0, "function")? as usize;
+                let tag = self.bundle_tags.get(tag_id).cloned().unwrap_or_else(|| tag_id.to_string());
+                let mut i = 1;
+                let mut inputs = Vec::new();
+                while i < record.ops.len() {
+                    inputs.push(Operand::Value(self.value_type_pair(&state, record, &mut i)?.0));
+                }
+                bundles.push((tag, inputs));
+                continue;
+            }
+            let mut inst = match self.decode_instruction(&state, record)? {
+                Some(inst) => inst,
+                None => continue,
+            };
+            if matches!(inst.opcode, Opcode::Call | Opcode::Invoke | Opcode::CallBr) {
+                inst.bundles = std::mem::take(&mut bundles);
+            }
+            inst.block = current_block;
+            if let Some(ty) = &inst.result_type {
+                inst.value_id = Some(state.next_value_id());
+                state.result_types.push(ty.clone());
+            }
+            let is_terminator = matches!(inst.opcode,
+                Opcode::Ret | Opcode::Br | Opcode::CondBr | Opcode::Switch | Opcode::IndirectBr |
+                Opcode::Invoke | Opcode::Resume | Opcode::Unreachable | Opcode::CleanupRet |
+                Opcode::CatchRet | Opcode::CatchSwitch | Opcode::CallBr);
+            insts.push(inst);
+            if is_terminator {
+                current_block += 1;
+            }
+        }
+        if !started {
+            state.base = self.values.len();
+        }
+        if block_count == 0 || current_block != block_count {
+            return Err(invalid("function", format!("@{} does not terminate every declared block", function.name())));
+        }
+
+        // Unnamed values are numbered in order: arguments, then each block label and its results
+        let mut slot = 0;
+        let mut next_slot = || {
+            slot += 1;
+            (slot - 1).to_string()
+        };
+        let mut value_names = HashMap::new();
+        for index in 0..arg_types.len() {
+            let id = module_values + index;
+            let name = names.get(&id).cloned().unwrap_or_else(&mut next_slot);
+            value_names.insert(id, name);
+        }
+        let mut block_names = Vec::new();
+        let mut inst_iter = insts.iter().peekable();
+        for index in 0..block_count {
+            block_names.push(block_labels.get(&index).cloned().unwrap_or_else(&mut next_slot));
+            while let Some(inst) = inst_iter.next_if(|inst| inst.block == index) {
+                if let Some(id) = inst.value_id {
+                    let name = names.get(&id).cloned().unwrap_or_else(&mut next_slot);
+                    value_names.insert(id, name);
+                }
+            }
+        }
+
+        let locals = self.values.split_off(module_values);
+        self.metadata.truncate(module_metadata);
+        Ok(FunctionBody {
+            function,
+            locals,
+            arg_types,
+            insts,
+            base: state.base,
+            result_types: state.result_types,
+            block_names,
+            value_names,
+        })
+    }
+
+    fn decode_instruction(&mut self, state: &FunctionState, record: &Record) -> BitcodeResult<Option<PendingInst>> {
+        let ctx = self.context.clone();
+        let op = |i: usize| operand(record, i, "function");
+        let mut i = 0;
+        let inst = match record.code {
+            FUNC_CODE_INST_BINOP => {
+                // [opval, ty, opval, opcode, (flags)]
+                let (lhs, ty) = self.value_type_pair(state, record, &mut i)?;
+                let rhs = self.value_operand(state, record, &mut i)?;
+                let opcode = decode_binop(op(i)?, &ty).ok_or_else(|| invalid("function", "unknown binary opcode"))?;
+                PendingInst::new(opcode, vec![Operand::Value(lhs), Operand::Value(rhs)], Some(ty))
+            }
+            FUNC_CODE_INST_UNOP => {
+                let (value, ty) = self.value_type_pair(state, record, &mut i)?;
+                PendingInst::new(Opcode::FNeg, vec![Operand::Value(value)], Some(ty))
+            }
+            FUNC_CODE_INST_FREEZE => {
+                let (value, ty) = self.value_type_pair(state, record, &mut i)?;
+                PendingInst::new(Opcode::Freeze, vec![Operand::Value(value)], Some(ty))
+            }
+            FUNC_CODE_INST_CAST => {
+                // [opval, opty, destty, castopc]
+                let (value, _) = self.value_type_pair(state, record, &mut i)?;
+                let dest = self.type_by_id(op(i)?)?;
+                let opcode = decode_cast(op(i + 1)?).ok_or_else(|| invalid("function", "unknown cast opcode"))?;
+                PendingInst::new(opcode, vec![Operand::Value(value)], Some(dest))
+            }
+            FUNC_CODE_INST_GEP | FUNC_CODE_INST_GEP_OLD | FUNC_CODE_INST_INBOUNDS_GEP_OLD => {
+                // GEP: [flags, srcty, (opval, opty)...]; the old forms start with the base pointer
+                let source = if record.code == FUNC_CODE_INST_GEP {
+                    i = 2;
+                    Some(self.type_by_id(op(1)?)?)
+                } else {
+                    None
+                };
+                let mut operands = Vec::new();
+                let mut vector_size = None;
+                let mut address_space = 0;
+                let mut base_ty = None;
+                while i < record.ops.len() {
+                    let (value, ty) = self.value_type_pair(state, record, &mut i)?;
+                    if let Some((_, size)) = ty.vector_info() {
+                        vector_size = Some(size);
+                    }
+                    if base_ty.is_none() {
+                        let scalar = ty.vector_info().map(|(e, _)| e.clone()).unwrap_or_else(|| ty.clone());
+                        address_space = scalar.address_space().unwrap_or(0);
+                        base_ty = Some(scalar);
+                    }
+                    operands.push(Operand::Value(value));
+                }
+                let source = match source {
+                    Some(source) => source,
+                    None => base_ty.as_ref().and_then(|t| t.pointee_type().cloned())
+                        .ok_or_else(|| invalid("function", "getelementptr base is not a pointer"))?,
+                };
+                // Result pointers are untyped, as in the parser
+                let ptr = Type::ptr_addrspace(&ctx, ctx.int8_type(), address_space);
+                let result = match vector_size {
+                    Some(size) => ctx.vector_type(ptr, size),
+                    None => ptr,
+                };
+                let mut inst = PendingInst::new(Opcode::GetElementPtr, operands, Some(result));
+                inst.gep_source_type = Some(source);
+                inst
+            }
+            FUNC_CODE_INST_SELECT | FUNC_CODE_INST_VSELECT => {
+                // SELECT: [tval, ty, fval, cond]; VSELECT: [tval, ty, fval, cond, condty]
+                let (t, ty) = self.value_type_pair(state, record, &mut i)?;
+                let f = self.value_operand(state, record, &mut i)?;
+                let cond = if record.code == FUNC_CODE_INST_VSELECT {
+                    self.value_type_pair(state, record, &mut i)?.0
+                } else {
+                    self.value_operand(state, record, &mut i)?
+                };
+                PendingInst::new(Opcode::Select, vec![Operand::Value(cond), Operand::Value(t), Operand::Value(f)], Some(ty))
+            }
+            FUNC_CODE_INST_EXTRACTELT => {
+                let (vector, ty) = self.value_type_pair(state, record, &mut i)?;
+                let (index, _) = self.value_type_pair(state, record, &mut i)?;
+                let element = ty.vector_info().map(|(e, _)| e.clone())
+                    .ok_or_else(|| invalid("function", "extractelement of a non-vector"))?;
+                PendingInst::new(Opcode::ExtractElement, vec![Operand::Value(vector), Operand::Value(index)], Some(element))
+            }
+            FUNC_CODE_INST_INSERTELT => {
+                let (vector, ty) = self.value_type_pair(state, record, &mut i)?;
+                let element = self.value_operand(state, record, &mut i)?;
+                let (index, _) = self.value_type_pair(state, record, &mut i)?;
+                PendingInst::new(Opcode::InsertElement,
+                    vec![Operand::Value(vector), Operand::Value(element), Operand::Value(index)], Some(ty))
+            }
+            FUNC_CODE_INST_SHUFFLEVEC => {
+                let (v1, ty) = self.value_type_pair(state, record, &mut i)?;
+                let v2 = self.value_operand(state, record, &mut i)?;
+                let (mask, mask_ty) = self.value_type_pair(state, record, &mut i)?;
+                let element = ty.vector_info().map(|(e, _)| e.clone())
+                    .ok_or_else(|| invalid("function", "shufflevector of a non-vector"))?;
+                let size = mask_ty.vector_info().map(|(_, n)| n).unwrap_or(0);
+                PendingInst::new(Opcode::ShuffleVector,
+                    vec![Operand::Value(v1), Operand::Value(v2), Operand::Value(mask)], Some(ctx.vector_type(element, size)))
+            }
+            FUNC_CODE_INST_CMP | FUNC_CODE_INST_CMP2 => {
+                // [opval, ty, opval, pred, (flags)]
+                let (lhs, ty) = self.value_type_pair(state, record, &mut i)?;
+                let rhs = self.value_operand(state, record, &mut i)?;
+                let predicate = op(i)?;
+                let result = match ty.vector_info() {
+                    Some((_, size)) => ctx.vector_type(ctx.bool_type(), size),
+                    None => ctx.bool_type(),
+                };
+                let operands = vec![Operand::Value(lhs), Operand::Value(rhs)];
+                if is_fp(&ty) {
+                    let mut inst = PendingInst::new(Opcode::FCmp, operands, Some(result));
+                    inst.float_predicate = decode_float_predicate(predicate);
+                    inst
+                } else {
+                    let mut inst = PendingInst::new(Opcode::ICmp, operands, Some(result));
+                    inst.int_predicate = decode_int_predicate(predicate);
+                    inst
+                }
+            }
+            FUNC_CODE_INST_RET => {
+                let operands = if record.ops.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![Operand::Value(self.value_type_pair(state, record, &mut i)?.0)]
+                };
+                PendingInst::new(Opcode::Ret, operands, None)
+            }
+            FUNC_CODE_INST_BR => {
+                // [bb] or [truebb, falsebb, cond]
+                if record.ops.len() == 1 {
+                    PendingInst::new(Opcode::Br, vec![Operand::Block(op(0)? as usize)], None)
+                } else {
+                    i = 2;
+                    let cond = self.value_operand(state, record, &mut i)?;
+                    PendingInst::new(Opcode::CondBr, vec![
+                        Operand::Value(cond), Operand::Block(op(0)? as usize), Operand::Block(op(1)? as usize),
+                    ], None)
+                }
+            }
+            FUNC_CODE_INST_SWITCH => {
+                // [opty, cond, defaultbb, (caseval, bb)...]; case values are absolute IDs
+                if op(0)? >> 16 == 0x4B5 {
+                    return Err(BitcodeError::Unsupported("case-range switch encoding".to_string()));
+                }
+                i = 1;
+                let cond = self.value_operand(state, record, &mut i)?;
+                let mut operands = vec![Operand::Value(cond), Operand::Block(op(2)? as usize)];
+                for case in record.ops[3..].chunks(2) {
+                    operands.push(Operand::Value(case[0] as usize));
+                    operands.push(Operand::Block(*case.get(1).unwrap_or(&0) as usize));
+                }
+                PendingInst::new(Opcode::Switch, operands, None)
+            }
+            FUNC_CODE_INST_INDIRECTBR => {
+                // [opty, addr, bb...]
+                i = 1;
+                let address = self.value_operand(state, record, &mut i)?;
+                let mut operands = vec![Operand::Value(address)];
+                operands.extend(record.ops[i..].iter().map(|&bb| Operand::Block(bb as usize)));
+                PendingInst::new(Opcode::IndirectBr, operands, None)
+            }
+            FUNC_CODE_INST_INVOKE => {
+                // [attrs, cc, normalbb, unwindbb, (fnty,) callee, args...]
+                let cc_info = op(1)?;
+                let (normal, unwind) = (op(2)? as usize, op(3)? as usize);
+                i = 4;
+                let (callee, fn_ty) = self.callee(state, record, &mut i, cc_info >> 13 & 1 != 0)?;
+                let mut operands = vec![Operand::Value(callee)];
+                operands.extend(self.call_arguments(state, record, &mut i, &fn_ty)?);
+                operands.push(Operand::Block(normal));
+                operands.push(Operand::Block(unwind));
+                let result = fn_ty.function_return_type().filter(|t| !t.is_void());
+                PendingInst::new(Opcode::Invoke, operands, result)
+            }
+            FUNC_CODE_INST_CALLBR => {
+                // [attrs, cc, defaultbb, numindirect, indirectbb..., (fnty,) callee, args...]
+                let cc_info = op(1)?;
+                let default = op(2)? as usize;
+                let indirect_count = op(3)? as usize;
+                i = 4 + indirect_count;
+                let (callee, fn_ty) = self.callee(state, record, &mut i, cc_info >> 15 & 1 != 0)?;
+                let mut operands = vec![Operand::Value(callee)];
+                operands.extend(self.call_arguments(state, record, &mut i, &fn_ty)?);
+                operands.push(Operand::Block(default));
+                let indirect = record.ops.get(4..4 + indirect_count)
+                    .ok_or_else(|| invalid("function", "callbr is missing indirect destinations"))?;
+                operands.extend(indirect.iter().map(|&bb| Operand::Block(bb as usize)));
+                let result = fn_ty.function_return_type().filter(|t| !t.is_void());
+                PendingInst::new(Opcode::CallBr, operands, result)
+            }
+            FUNC_CODE_INST_CALL => {
+                // [attrs, cc, (fmf,) (fnty,) callee, args...]
+                let cc_info = op(1)?;
+                i = 2;
+                if cc_info >> 17 & 1 != 0 {
+                    i += 1;
+                }
+                let (callee, fn_ty) = self.callee(state, record, &mut i, cc_info >> 15 & 1 != 0)?;
+                let mut operands = vec![Operand::Value(callee)];
+                operands.extend(self.call_arguments(state, record, &mut i, &fn_ty)?);
+                let result = fn_ty.function_return_type().filter(|t| !t.is_void());
+                PendingInst::new(Opcode::Call, operands, result)
+            }
+            FUNC_CODE_INST_RESUME => {
+                let (value, _) = self.value_type_pair(state, record, &mut i)?;
+                PendingInst::new(Opcode::Resume, vec![Operand::Value(value)], None)
+            }
+            FUNC_CODE_INST_UNREACHABLE => PendingInst::new(Opcode::Unreachable, Vec::new(), None),
+            FUNC_CODE_INST_PHI => {
+                // [ty, (val, bb)..., (flags)]; values are signed relative IDs
+                let ty = self.type_by_id(op(0)?)?;
+                let mut operands = Vec::new();
+                for pair in record.ops[1..].chunks_exact(2) {
+                    let relative = decode_signed(pair[0]);
+                    let id = if self.version >= 1 {
+                        (state.next_value_id() as i64 - relative) as usize
+                    } else {
+                        pair[0] as usize
+                    };
+                    operands.push(Operand::Value(id));
+                    operands.push(Operand::Block(pair[1] as usize));
+                }
+                PendingInst::new(Opcode::PHI, operands, Some(ty))
+            }
+            FUNC_CODE_INST_LANDINGPAD | FUNC_CODE_INST_LANDINGPAD_OLD => {
+                // [ty, (personality,) iscleanup, nclauses, (clausetype, val, ty)...]
+                let ty = self.type_by_id(op(0)?)?;
+                i = 1;
+                if record.code == FUNC_CODE_INST_LANDINGPAD_OLD {
+                    self.value_type_pair(state, record, &mut i)?;
+                }
+                let clauses = op(i + 1)? as usize;
+                i += 2;
+                let mut operands = Vec::new();
+                for _ in 0..clauses {
+                    i += 1;
+                    operands.push(Operand::Value(self.value_type_pair(state, record, &mut i)?.0));
+                }
+                PendingInst::new(Opcode::LandingPad, operands, Some(ty))
+            }
+            FUNC_CODE_INST_ALLOCA => {
+                // [instty, opty, op, align, (addrspace)]
+                let align = op(3)?;
+                let explicit = align & (1 << 6) != 0;
+                let mut ty = self.type_by_id(op(0)?)?;
+                if !explicit {
+                    ty = ty.pointee_type().cloned().ok_or_else(|| invalid("function", "alloca of a non-pointer type"))?;
+                }
+                let size_id = op(2)? as usize;
+                // The element count is only spelled out when it is not 1
+                let operands = if size_id < self.values.len() && self.value(size_id)?.is_const_int_value(1) {
+                    Vec::new()
+                } else {
+                    vec![Operand::Value(size_id)]
+                };
+                let mut inst = PendingInst::new(Opcode::Alloca, operands, Some(ctx.ptr_type(ty)));
+                inst.alignment = decode_alignment((align & 0x1F) | ((align >> 8) & 0x7) << 5);
+                inst.alloca_flags = (align & (1 << 5) != 0, align & (1 << 7) != 0);
+                inst
+            }
+            FUNC_CODE_INST_LOAD | FUNC_CODE_INST_LOADATOMIC => {
+                // [op, (ty,) align, vol, (ordering, ssid)]
+                let (ptr, ptr_ty) = self.value_type_pair(state, record, &mut i)?;
+                let tail = if record.code == FUNC_CODE_INST_LOAD { 2 } else { 4 };
+                let ty = if i + tail + 1 == record.ops.len() {
+                    i += 1;
+                    self.type_by_id(op(i - 1)?)?
+                } else {
+                    ptr_ty.pointee_type().cloned().ok_or_else(|| invalid("function", "load from a non-pointer"))?
+                };
+                let mut inst = PendingInst::new(Opcode::Load, vec![Operand::Value(ptr)], Some(ty));
+                inst.alignment = decode_alignment(op(i)?);
+                inst.is_volatile = op(i + 1)? != 0;
+                if record.code == FUNC_CODE_INST_LOADATOMIC {
+                    inst.ordering = Some(decode_ordering(op(i + 2)?));
+                }
+                inst
+            }
+            FUNC_CODE_INST_STORE | FUNC_CODE_INST_STOREATOMIC | FUNC_CODE_INST_STORE_OLD | FUNC_CODE_INST_STOREATOMIC_OLD => {
+                // [ptr, val, align, vol, (ordering, ssid)]
+                let (ptr, _) = self.value_type_pair(state, record, &mut i)?;
+                let value = if matches!(record.code, FUNC_CODE_INST_STORE | FUNC_CODE_INST_STOREATOMIC) {
+                    self.value_type_pair(state, record, &mut i)?.0
+                } else {
+                    self.value_operand(state, record, &mut i)?
+                };
+                let mut inst = PendingInst::new(Opcode::Store, vec![Operand::Value(value), Operand::Value(ptr)], None);
+                inst.alignment = decode_alignment(op(i)?);
+                inst.is_volatile = op(i + 1)? != 0;
+                if matches!(record.code, FUNC_CODE_INST_STOREATOMIC | FUNC_CODE_INST_STOREATOMIC_OLD) {
+                    inst.ordering = Some(decode_ordering(op(i + 2)?));
+                }
+                inst
+            }
+            FUNC_CODE_INST_CMPXCHG | FUNC_CODE_INST_CMPXCHG_OLD => {
+                // [ptr, cmp, new, vol, success, ssid, (failure, weak, align)]
+                let (ptr, ptr_ty) = self.value_type_pair(state, record, &mut i)?;
+                let (cmp, ty) = if record.code == FUNC_CODE_INST_CMPXCHG {
+                    self.value_type_pair(state, record, &mut i)?
+                } else {
+                    let ty = ptr_ty.pointee_type().cloned().ok_or_else(|| invalid("function", "cmpxchg on a non-pointer"))?;
+                    (self.value_operand(state, record, &mut i)?, ty)
+                };
+                let new = self.value_operand(state, record, &mut i)?;
+                let result = Type::struct_type(&ctx, vec![ty, ctx.bool_type()], None);
+                let mut inst = PendingInst::new(Opcode::AtomicCmpXchg,
+                    vec![Operand::Value(ptr), Operand::Value(cmp), Operand::Value(new)], Some(result));
+                inst.is_volatile = op(i)? != 0;
+                inst.ordering = Some(decode_ordering(op(i + 1)?));
+                inst.alignment = record.ops.get(i + 5).and_then(|&a| decode_alignment(a));
+                inst
+            }
+            FUNC_CODE_INST_ATOMICRMW | FUNC_CODE_INST_ATOMICRMW_OLD => {
+                // [ptr, val, op, vol, ordering, ssid, (align)]
+                let (ptr, ptr_ty) = self.value_type_pair(state, record, &mut i)?;
+                let (value, ty) = if record.code == FUNC_CODE_INST_ATOMICRMW {
+                    self.value_type_pair(state, record, &mut i)?
+                } else {
+                    let ty = ptr_ty.pointee_type().cloned().ok_or_else(|| invalid("function", "atomicrmw on a non-pointer"))?;
+                    (self.value_operand(state, record, &mut i)?, ty)
+                };
+                let mut inst = PendingInst::new(Opcode::AtomicRMW, vec![Operand::Value(ptr), Operand::Value(value)], Some(ty));
+                let operation = op(i)?;
+                inst.rmw_operation = Some(decode_rmw_operation(operation)
+                    .ok_or_else(|| invalid("function", format!("unknown atomicrmw operation {}", operation)))?);
+                inst.is_volatile = op(i + 1)? != 0;
+                inst.ordering = Some(decode_ordering(op(i + 2)?));
+                inst.alignment = record.ops.get(i + 4).and_then(|&a| decode_alignment(a));
+                inst
+            }
+            FUNC_CODE_INST_FENCE => {
+                let mut inst = PendingInst::new(Opcode::Fence, Vec::new(), None);
+                inst.ordering = Some(decode_ordering(op(0)?));
+                inst
+            }
+            FUNC_CODE_INST_VAARG => {
+                // [valistty, valist, resty]
+                i = 1;
+                let list = self.value_operand(state, record, &mut i)?;
+                let ty = self.type_by_id(op(i)?)?;
+                PendingInst::new(Opcode::VAArg, vec![Operand::Value(list)], Some(ty))
+            }
+            FUNC_CODE_INST_EXTRACTVAL => {
+                // [agg, idx...]
+                let (aggregate, mut ty) = self.value_type_pair(state, record, &mut i)?;
+                let mut operands = vec![Operand::Value(aggregate)];
+                for &index in &record.ops[i..] {
+                    ty = ty.struct_fields().and_then(|f| f.get(index as usize).cloned())
+                        .or_else(|| ty.array_info().map(|(e, _)| e.clone()))
+                        .ok_or_else(|| invalid("function", "extractvalue index out of range"))?;
+                    operands.push(Operand::Constant(Value::const_int(ctx.int32_type(), index as i64, None)));
+                }
+                PendingInst::new(Opcode::ExtractValue, operands, Some(ty))
+            }
+            FUNC_CODE_INST_INSERTVAL => {
+                // [agg, val, idx...]
+                let (aggregate, ty) = self.value_type_pair(state, record, &mut i)?;
+                let (element, _) = self.value_type_pair(state, record, &mut i)?;
+                let mut operands = vec![Operand::Value(aggregate), Operand::Value(element)];
+                operands.extend(record.ops[i..].iter()
+                    .map(|&index| Operand::Constant(Value::const_int(ctx.int32_type(), index as i64, None))));
+                PendingInst::new(Opcode::InsertValue, operands, Some(ty))
+            }
+            FUNC_CODE_INST_CLEANUPRET => {
+                // [cleanuppad, (unwindbb)]
+                let pad = self.value_operand(state, record, &mut i)?;
+                let mut operands = vec![Operand::Value(pad)];
+                operands.extend(record.ops.get(1).map(|&bb| Operand::Block(bb as usize)));
+                PendingInst::new(Opcode::CleanupRet, operands, None)
+            }
+            FUNC_CODE_INST_CATCHRET => {
+                // [catchpad, bb]
+                let pad = self.value_operand(state, record, &mut i)?;
+                PendingInst::new(Opcode::CatchRet, vec![Operand::Value(pad), Operand::Block(op(1)? as usize)], None)
+            }
+            FUNC_CODE_INST_CATCHSWITCH => {
+                // [parentpad, numhandlers, handlers..., (unwindbb)]
+                let parent = self.value_operand(state, record, &mut i)?;
+                let mut operands = vec![Operand::Value(parent)];
+                operands.extend(record.ops.iter().skip(2).map(|&bb| Operand::Block(bb as usize)));
+                PendingInst::new(Opcode::CatchSwitch, operands, Some(ctx.token_type()))
+            }
+            FUNC_CODE_INST_CATCHPAD | FUNC_CODE_INST_CLEANUPPAD => {
+                // [parentpad, numargs, (val, ty)...]
+                let parent = self.value_operand(state, record, &mut i)?;
+                let count = op(1)? as usize;
+                i = 2;
+                let mut operands = vec![Operand::Value(parent)];
+                for _ in 0..count {
+                    operands.push(Operand::Value(self.value_type_pair(state, record, &mut i)?.0));
+                }
+                let opcode = if record.code == FUNC_CODE_INST_CATCHPAD { Opcode::CatchPad } else { Opcode::CleanupPad };
+                PendingInst::new(opcode, operands, Some(ctx.token_type()))
+            }
+            // Use-list orders and debug records carry nothing the IR model keeps
+            FUNC_CODE_BLOCKADDR_USERS | FUNC_CODE_DEBUG_RECORD_VALUE | FUNC_CODE_DEBUG_RECORD_DECLARE
+            | FUNC_CODE_DEBUG_RECORD_ASSIGN | FUNC_CODE_DEBUG_RECORD_VALUE_SIMPLE | FUNC_CODE_DEBUG_RECORD_LABEL => {
+                return Ok(None)
+            }
+            code => return Err(BitcodeError::Unsupported(format!("instruction code {}", code))),
+        };
+        Ok(Some(inst))
+    }
+
+    /// Second stage: create values for the decoded instructions and add the blocks
+    fn build_function(&mut self, body: FunctionBody) -> BitcodeResult<()> {
+        let module_values = self.values.len();
+        let FunctionBody { function, locals, arg_types, insts, base, result_types, block_names, value_names } = body;
+        self.values.extend(locals);
+
+        let mut args = Vec::new();
+        for (index, ty) in arg_types.iter().enumerate() {
+            let id = module_values + index;
+            let arg = Value::argument(ty.clone(), index, value_names.get(&id).cloned());
+            self.values[id] = ValueSlot::Ready(arg.clone());
+            args.push(arg);
+        }
+        function.set_arguments(args);
+
+        let label = self.context.label_type();
+        let block_values: Vec<Value> = block_names.iter()
+            .map(|name| Value::new(label.clone(), ValueKind::BasicBlock, Some(name.clone())))
+            .collect();
+        let mut results = Vec::new();
+        for inst in &insts {
+            if let Some(id) = inst.value_id {
+                results.push(Value::instruction(result_types[id - base].clone(), inst.opcode, value_names.get(&id).cloned()));
+            }
+        }
+
+        let metadata_ty = self.context.metadata_type();
+        let resolve = |reader: &mut Self, operand: &Operand| -> BitcodeResult<Value> {
+            match operand {
+                Operand::Value(id) | Operand::Metadata(id) => {
+                    let value = if *id >= base {
+                        results.get(id - base).cloned()
+                            .ok_or_else(|| invalid("function", format!("@{} uses undefined value {}", function.name(), id)))?
+                    } else {
+                        reader.value(*id)?
+                    };
+                    Ok(match operand {
+                        Operand::Metadata(_) => Value::metadata(metadata_ty.clone(), Metadata::value(value)),
+                        _ => value,
+                    })
+                }
+                Operand::Block(index) => block_values.get(*index).cloned()
+                    .ok_or_else(|| invalid("function", format!("@{} branches to undefined block {}", function.name(), index))),
+                Operand::Constant(value) => Ok(value.clone()),
+            }
+        };
+
+        let blocks: Vec<BasicBlock> = block_names.iter().map(|name| BasicBlock::new(Some(name.clone()))).collect();
+        for pending in &insts {
+            let mut operands = Vec::new();
+            for operand in &pending.operands {
+                operands.push(resolve(self, operand)?);
+            }
+            let result = pending.value_id.map(|id| results[id - base].clone());
+            let mut inst = Instruction::new(pending.opcode, operands, result);
+            if let Some(ty) = &pending.gep_source_type {
+                inst.set_gep_source_type(ty.clone());
+            }
+            if let Some(alignment) = pending.alignment {
+                inst.set_alignment(alignment);
+            }
+            if pending.is_volatile {
+                inst.set_volatile(true);
+            }
+            inst.set_inalloca(pending.alloca_flags.0);
+            inst.set_swifterror(pending.alloca_flags.1);
+            if let Some(ordering) = pending.ordering {
+                inst.set_atomic(true);
+                inst.set_atomic_ordering(ordering);
+            }
+            if let Some(operation) = pending.rmw_operation {
+                inst.set_rmw_operation(operation);
+            }
+            if let Some(predicate) = pending.int_predicate {
+                inst.set_int_predicate(predicate);
+            }
+            if let Some(predicate) = pending.float_predicate {
+                inst.set_float_predicate(predicate);
+            }
+            for (tag, inputs) in &pending.bundles {
+                let mut values = Vec::new();
+                for input in inputs {
+                    values.push(resolve(self, input)?);
+                }
+                inst.add_operand_bundle(OperandBundle { tag: tag.clone(), inputs: values });
+            }
+            for attachment in &pending.attachments {
+                inst.add_metadata_attachment(attachment.clone());
+            }
+            inst.set_debug_loc(pending.debug_loc.clone());
+            for (kind, node) in &pending.metadata {
+                inst.set_metadata(kind.clone(), node.clone());
+            }
+            blocks[pending.block].add_instruction(inst);
+        }
+        for block in blocks {
+            function.add_basic_block(block);
+        }
+
+        self.values.truncate(module_values);
+        Ok(())
+    }
+}
+
+fn attribute_kind(attribute: &Attribute) -> Option<(&'static str, Option<u64>)> {
+    match attribute {
+        Attribute::Enum(kind) | Attribute::Type(kind, _) => attribute_kind_name(*kind).map(|n| (n, None)),
+        Attribute::Int(kind, value) => attribute_kind_name(*kind).map(|n| (n, Some(*value))),
+        Attribute::String(..) => None,
+    }
+}
+
+fn apply_function_attribute(attrs: &mut FunctionAttributes, attribute: &Attribute) {
+    if let Attribute::String(key, value) = attribute {
+        attrs.string_attributes.insert(key.clone(), value.clone());
+        return;
+    }
+    let (name, value) = match attribute_kind(attribute) {
+        Some(kind) => kind,
+        None => return,
+    };
+    let value = value.unwrap_or(0);
+    match name {
+        "noinline" => attrs.noinline = true,
+        "alwaysinline" => attrs.alwaysinline = true,
+        "inlinehint" => attrs.inlinehint = true,
+        "optsize" => attrs.optsize = true,
+        "optnone" => attrs.optnone = true,
+        "minsize" => attrs.minsize = true,
+        "noreturn" => attrs.noreturn = true,
+        "nounwind" => attrs.nounwind = true,
+        "norecurse" => attrs.norecurse = true,
+        "willreturn" => attrs.willreturn = true,
+        "nosync" => attrs.nosync = true,
+        "readnone" => attrs.readnone = true,
+        "readonly" => attrs.readonly = true,
+        "writeonly" => attrs.writeonly = true,
+        "argmemonly" => attrs.argmemonly = true,
+        "speculatable" => attrs.speculatable = true,
+        "returns_twice" => attrs.returns_twice = true,
+        "ssp" => attrs.ssp = true,
+        "sspreq" => attrs.sspreq = true,
+        "sspstrong" => attrs.sspstrong = true,
+        "uwtable" => attrs.uwtable = true,
+        "cold" => attrs.cold = true,
+        "hot" => attrs.hot = true,
+        "naked" => attrs.naked = true,
+        "builtin" => attrs.builtin = true,
+        "immarg" => attrs.has_immarg = true,
+        "allocsize" => {
+            // Element size argument in the high word, optional count argument in the low word
+            let mut args = vec![(value >> 32) as usize];
+            if value & 0xFFFF_FFFF != 0xFFFF_FFFF {
+                args.push((value & 0xFFFF_FFFF) as usize);
+            }
+            attrs.allocsize = Some(args);
+        }
+        "vscale_range" => attrs.vscale_range = Some(((value >> 32) as u32, value as u32)),
+        "allockind" => {
+            let kinds = ["alloc", "realloc", "free", "uninitialized", "zeroed", "aligned"];
+            attrs.allockind = Some(kinds.iter().enumerate()
+                .filter(|(bit, _)| value & (1 << bit) != 0)
+                .map(|(_, kind)| kind.to_string())
+                .collect());
+        }
+        other => attrs.other_attributes.push(other.to_string()),
+    }
+}
+
+fn apply_return_attribute(attrs: &mut ReturnAttributes, attribute: &Attribute) {
+    let (name, value) = match attribute_kind(attribute) {
+        Some(kind) => kind,
+        None => return,
+    };
+    match name {
+        "zeroext" => attrs.zeroext = true,
+        "signext" => attrs.signext = true,
+        "inreg" => attrs.inreg = true,
+        "noalias" => attrs.noalias = true,
+        "nonnull" => attrs.nonnull = true,
+        "noundef" => attrs.noundef = true,
+        "swifterror" => attrs.swifterror = true,
+        "immarg" => attrs.has_immarg = true,
+        "dereferenceable" => attrs.dereferenceable = value,
+        "align" => attrs.align = value.map(|v| v as u32),
+        _ => {}
+    }
+}
+
+fn apply_parameter_attribute(attrs: &mut ParameterAttributes, attribute: &Attribute) {
+    let (name, value) = match attribute_kind(attribute) {
+        Some(kind) => kind,
+        None => return,
+    };
+    let ty = match attribute {
+        Attribute::Type(_, ty) => ty.clone(),
+        _ => None,
+    };
+    match name {
+        "zeroext" => attrs.zeroext = true,
+        "signext" => attrs.signext = true,
+        "inreg" => attrs.inreg = true,
+        "byval" => attrs.byval = ty,
+        "inalloca" => attrs.inalloca = ty,
+        "byref" => attrs.byref = ty,
+        "sret" => attrs.sret = ty,
+        "noalias" => attrs.noalias = true,
+        "nocapture" => attrs.nocapture = true,
+        "nest" => attrs.nest = true,
+        "returned" => attrs.returned = true,
+        "nonnull" => attrs.nonnull = true,
+        "dereferenceable" => attrs.dereferenceable = value,
+        "swiftself" => attrs.swiftself = true,
+        "swifterror" => attrs.swifterror = true,
+        "swiftasync" => attrs.swiftasync = true,
+        "immarg" => attrs.immarg = true,
+        "align" => attrs.align = value.map(|v| v as u32),
+        "dead_on_return" => attrs.dead_on_return = true,
+        "dead_on_unwind" => attrs.dead_on_unwind = true,
+        "writable" => attrs.writable = true,
+        "readonly" => attrs.readonly = true,
+        "readnone" => attrs.readnone = true,
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_alignment() {
+        assert_eq!(decode_alignment(0), None);
+        assert_eq!(decode_alignment(1), Some(1));
+        assert_eq!(decode_alignment(4), Some(8));
+    }
+
+    #[test]
+    fn test_decode_float_formats() {
+        assert_eq!(half_to_f64(0x3C00), 1.0);
+        assert_eq!(half_to_f64(0xC000), -2.0);
+        // 1.5 as x87: explicit integer bit plus half fraction, biased exponent 16383
+        assert_eq!(x87_to_f64(0xC000_0000_0000_0000, 0x3FFF), 1.5);
+        assert_eq!(quad_to_f64(0, 0x4000_0000_0000_0000), 2.0);
+    }
+}
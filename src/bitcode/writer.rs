@@ -0,0 +1,2596 @@
+//! Bitcode writer
+//!
+//! Serializes a [`Module`] in the layout `llvm-as` produces: an identification
+//! block, then the module block with its type table, attributes, global value
+//! records, constants, metadata, function bodies and symbol table, and finally
+//! the string table holding global names (module version 2). Pointers are
+//! written opaque.
+//!
+//! Everything is numbered before anything is emitted, so the only offset that
+//! has to be patched afterwards is the forward declaration of the module
+//! symbol table.
+
+use std::collections::HashMap;
+
+use crate::context::Context;
+use crate::function::{CallingConvention, Function, FunctionAttributes, ParameterAttributes, ReturnAttributes};
+use crate::instruction::{AtomicOrdering, AtomicRMWBinOp, FloatPredicate, Instruction, IntPredicate, Opcode};
+use crate::metadata::{debug_info_constant, DwarfOpInfo, Metadata};
+use crate::module::{DLLStorageClass, Linkage, Module, ThreadLocalMode, UnnamedAddr, Visibility};
+use crate::types::{FloatKind, Type};
+use crate::value::{Value, ValueKind};
+use super::bitstream::{encode_char6, encode_signed, Abbrev, AbbrevOp, BitstreamWriter, Record};
+use super::codes::*;
+use super::reader::{di_layout, DIField};
+use super::{BitcodeError, BitcodeResult, BITCODE_MAGIC};
+
+// Abbreviations registered in BLOCKINFO, numbered per block kind
+const VST_ENTRY_8_ABBREV: u32 = 4;
+const VST_ENTRY_7_ABBREV: u32 = 5;
+const VST_ENTRY_6_ABBREV: u32 = 6;
+const VST_BBENTRY_6_ABBREV: u32 = 7;
+const CONSTANTS_SETTYPE_ABBREV: u32 = 4;
+const CONSTANTS_INTEGER_ABBREV: u32 = 5;
+const CONSTANTS_CE_CAST_ABBREV: u32 = 6;
+const CONSTANTS_NULL_ABBREV: u32 = 7;
+const FUNCTION_INST_LOAD_ABBREV: u32 = 4;
+const FUNCTION_INST_BINOP_ABBREV: u32 = 5;
+const FUNCTION_INST_CAST_ABBREV: u32 = 6;
+const FUNCTION_INST_RET_VOID_ABBREV: u32 = 7;
+const FUNCTION_INST_RET_VAL_ABBREV: u32 = 8;
+const FUNCTION_INST_UNREACHABLE_ABBREV: u32 = 9;
+const FUNCTION_INST_GEP_ABBREV: u32 = 10;
+
+/// Bitcode writer for one module
+pub struct BitcodeWriter<'a> {
+    module: &'a Module,
+    context: Context,
+    types: TypeTable,
+    globals: HashMap<String, GlobalInfo>,
+    global_count: u32,
+    constants: ConstantTable,
+    /// Constants of the function being planned
+    local_constants: ConstantTable,
+    /// Arguments, results and blocks of the function being planned
+    locals: LocalValues,
+    metadata: MetadataTable,
+    attribute_groups: Vec<(u64, Vec<u64>)>,
+    attribute_group_ids: HashMap<(u64, Vec<u64>), u64>,
+    attribute_lists: Vec<Vec<u64>>,
+    attribute_list_ids: HashMap<Vec<u64>, u64>,
+    sections: Vec<String>,
+    comdats: Vec<String>,
+    bundle_tags: Vec<String>,
+    metadata_kinds: Vec<String>,
+    strtab: Vec<u8>,
+}
+
+struct GlobalInfo {
+    id: u32,
+    value_type: Type,
+    address_space: u32,
+    function: Option<Function>,
+}
+
+/// Types by structural key, in ID order
+#[derive(Default)]
+struct TypeTable {
+    ids: HashMap<String, u32>,
+    /// (code, operands, struct name) of each type
+    records: Vec<(u32, Vec<u64>, Option<String>)>,
+    /// Named structs whose fields are being numbered
+    in_progress: Vec<String>,
+}
+
+/// Constants of one CONSTANTS block, numbered from `base`
+#[derive(Default)]
+struct ConstantTable {
+    base: u32,
+    ids: HashMap<String, u32>,
+    /// (type ID, code, operands) of each constant
+    records: Vec<(u32, u32, Vec<u64>)>,
+    /// Whether the instructions are being encoded, so no constant may be added
+    sealed: bool,
+}
+
+impl ConstantTable {
+    fn new(base: u32) -> Self {
+        Self { base, ..Default::default() }
+    }
+
+    fn next_id(&self) -> u32 {
+        self.base + self.records.len() as u32
+    }
+}
+
+#[derive(Default)]
+struct LocalValues {
+    by_ptr: HashMap<usize, u32>,
+    by_name: HashMap<String, u32>,
+    args: Vec<u32>,
+    types: HashMap<u32, Type>,
+    blocks: HashMap<String, u64>,
+    /// (code, operands) of the function's own metadata, numbered after the module's
+    metadata: Vec<(u32, Vec<u64>)>,
+    /// Metadata ID of each local value used as metadata, by value ID
+    metadata_values: HashMap<u32, u64>,
+}
+
+impl LocalValues {
+    fn bind(&mut self, value: &Value, id: u32, ty: Type) {
+        self.by_ptr.insert(value.data_ptr(), id);
+        if let Some(name) = value.name() {
+            self.by_name.insert(name.to_string(), id);
+        }
+        self.types.insert(id, ty);
+    }
+
+    /// ID of a function-local value; forward references are placeholders that share the name
+    fn lookup(&self, value: &Value) -> Option<u32> {
+        if let Some(&id) = self.by_ptr.get(&value.data_ptr()) {
+            return Some(id);
+        }
+        if let Some(id) = value.name().and_then(|name| self.by_name.get(name)) {
+            return Some(*id);
+        }
+        match value.kind() {
+            ValueKind::Argument { index } => self.args.get(*index).copied(),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum MdRef {
+    String(usize),
+    Entry(usize),
+}
+
+enum MdOp {
+    Raw(u64),
+    /// Metadata ID
+    Ref(MdRef),
+    /// Metadata ID + 1, zero for null
+    RefOrNull(Option<MdRef>),
+}
+
+struct MdEntry {
+    code: u32,
+    ops: Vec<MdOp>,
+}
+
+#[derive(Default)]
+struct MetadataTable {
+    strings: Vec<String>,
+    string_ids: HashMap<String, usize>,
+    entries: Vec<MdEntry>,
+    /// Entry of each node, by identity
+    nodes: HashMap<usize, usize>,
+    /// Entry of each numbered node, by number
+    numbered: HashMap<String, usize>,
+    /// VALUE entries by constant key
+    values: HashMap<String, usize>,
+    named: Vec<(String, Vec<MdRef>)>,
+    /// (value ID, [(kind, node)]) of global variables and function declarations
+    global_attachments: Vec<(u32, Vec<(u64, MdRef)>)>,
+    empty_node: Option<usize>,
+    /// Nodes whose identity is recorded in `nodes`, kept alive so it stays unique
+    retained: Vec<Metadata>,
+}
+
+impl MetadataTable {
+    /// Number of IDs taken, after which function-local metadata is numbered
+    fn len(&self) -> u64 {
+        (self.strings.len() + self.entries.len()) as u64
+    }
+
+    fn id(&self, md: MdRef) -> u64 {
+        match md {
+            MdRef::String(i) => i as u64,
+            MdRef::Entry(i) => (self.strings.len() + i) as u64,
+        }
+    }
+
+    fn string(&mut self, s: &str) -> MdRef {
+        if let Some(&i) = self.string_ids.get(s) {
+            return MdRef::String(i);
+        }
+        self.strings.push(s.to_string());
+        self.string_ids.insert(s.to_string(), self.strings.len() - 1);
+        MdRef::String(self.strings.len() - 1)
+    }
+
+    fn reserve(&mut self) -> usize {
+        self.entries.push(MdEntry { code: METADATA_NODE, ops: Vec::new() });
+        self.entries.len() - 1
+    }
+
+    /// `!{}`, which stands in where a null operand is not allowed
+    fn empty_node(&mut self) -> MdRef {
+        let index = match self.empty_node {
+            Some(index) => index,
+            None => {
+                let index = self.reserve();
+                self.empty_node = Some(index);
+                index
+            }
+        };
+        MdRef::Entry(index)
+    }
+}
+
+/// A planned function body
+struct FunctionBody {
+    value_id: u32,
+    block_count: usize,
+    constants: ConstantTable,
+    records: Vec<InstRecord>,
+    /// (VST code, value or block ID, name)
+    symbols: Vec<(u32, u64, String)>,
+    /// (code, operands) of the function-local metadata
+    metadata: Vec<(u32, Vec<u64>)>,
+    /// METADATA_ATTACHMENT records: the function's, then each instruction's
+    attachments: Vec<Vec<u64>>,
+}
+
+struct InstRecord {
+    code: u32,
+    ops: Vec<u64>,
+    abbrev: Option<u32>,
+}
+
+impl InstRecord {
+    fn new(code: u32, ops: Vec<u64>) -> Self {
+        Self { code, ops, abbrev: None }
+    }
+
+    fn abbreviated(code: u32, ops: Vec<u64>, abbrev: Option<u32>) -> Self {
+        Self { code, ops, abbrev }
+    }
+}
+
+fn invalid(block: &'static str, message: impl Into<String>) -> BitcodeError {
+    BitcodeError::InvalidRecord { block, message: message.into() }
+}
+
+/// Encode an alignment as log2 + 1 (0 means unspecified)
+fn encode_alignment(align: Option<u64>) -> u64 {
+    align.filter(|a| a.is_power_of_two()).map_or(0, |a| a.trailing_zeros() as u64 + 1)
+}
+
+fn encode_linkage(linkage: Linkage) -> u64 {
+    match linkage {
+        Linkage::External => 0,
+        Linkage::Appending => 2,
+        Linkage::Internal => 3,
+        Linkage::ExternWeak => 7,
+        Linkage::Common => 8,
+        Linkage::Private => 9,
+        Linkage::AvailableExternally => 12,
+        Linkage::Weak => 16,
+        Linkage::WeakOdr => 17,
+        Linkage::Linkonce => 18,
+        Linkage::LinkonceOdr => 19,
+    }
+}
+
+fn encode_visibility(visibility: Visibility) -> u64 {
+    match visibility {
+        Visibility::Default => 0,
+        Visibility::Hidden => 1,
+        Visibility::Protected => 2,
+    }
+}
+
+fn encode_dll_storage_class(class: DLLStorageClass) -> u64 {
+    match class {
+        DLLStorageClass::Default => 0,
+        DLLStorageClass::DllImport => 1,
+        DLLStorageClass::DllExport => 2,
+    }
+}
+
+fn encode_thread_local(mode: ThreadLocalMode) -> u64 {
+    match mode {
+        ThreadLocalMode::NotThreadLocal => 0,
+        ThreadLocalMode::GeneralDynamic => 1,
+        ThreadLocalMode::LocalDynamic => 2,
+        ThreadLocalMode::InitialExec => 3,
+        ThreadLocalMode::LocalExec => 4,
+    }
+}
+
+fn encode_unnamed_addr(unnamed: UnnamedAddr) -> u64 {
+    match unnamed {
+        UnnamedAddr::None => 0,
+        UnnamedAddr::Global => 1,
+        UnnamedAddr::Local => 2,
+    }
+}
+
+fn encode_calling_convention(cc: CallingConvention) -> BitcodeResult<u64> {
+    Ok(match cc {
+        CallingConvention::C => 0,
+        CallingConvention::Fast => 8,
+        CallingConvention::Cold => 9,
+        CallingConvention::Webkit_JS => 12,
+        CallingConvention::AnyReg => 13,
+        CallingConvention::PreserveMost => 14,
+        CallingConvention::PreserveAll => 15,
+        CallingConvention::Swift => 16,
+        CallingConvention::CXX_FastTLS => 17,
+        CallingConvention::Tail => 18,
+        CallingConvention::CFunc => 19,
+        CallingConvention::SwiftTail => 20,
+        CallingConvention::X86_StdCall => 64,
+        CallingConvention::X86_FastCall => 65,
+        CallingConvention::ARM_APCS => 66,
+        CallingConvention::ARM_AAPCS => 67,
+        CallingConvention::ARM_AAPCS_VFP => 68,
+        CallingConvention::MSP430_INTR => 69,
+        CallingConvention::X86_ThisCall => 70,
+        CallingConvention::PTX_Kernel => 71,
+        CallingConvention::PTX_Device => 72,
+        CallingConvention::SPIR_Func => 75,
+        CallingConvention::SPIR_Kernel => 76,
+        CallingConvention::Intel_OCL_BI => 77,
+        CallingConvention::X86_VectorCall => 80,
+        CallingConvention::X86_INTR => 83,
+        CallingConvention::AVR_INTR => 84,
+        CallingConvention::AVR_SIGNAL => 85,
+        CallingConvention::AMDGPU_VS => 87,
+        CallingConvention::AMDGPU_GS => 88,
+        CallingConvention::AMDGPU_PS => 89,
+        CallingConvention::AMDGPU_CS => 90,
+        CallingConvention::AMDGPU_Kernel => 91,
+        CallingConvention::X86_RegCall => 92,
+        CallingConvention::AMDGPU_HS => 93,
+        CallingConvention::AMDGPU_LS => 95,
+        CallingConvention::AMDGPU_ES => 96,
+        CallingConvention::AArch64_VectorCall => 97,
+        CallingConvention::AArch64_SVE_VectorCall => 98,
+        CallingConvention::M68k_INTR => 101,
+        CallingConvention::AMDGPU_CS_Chain => 104,
+        CallingConvention::AMDGPU_CS_Chain_Preserve => 105,
+        CallingConvention::M68k_RTD => 106,
+        CallingConvention::RISCV_VectorCall => 110,
+        other => return Err(BitcodeError::Unsupported(format!("calling convention {:?}", other))),
+    })
+}
+
+fn encode_rmw_operation(operation: AtomicRMWBinOp) -> u64 {
+    match operation {
+        AtomicRMWBinOp::Xchg => 0,
+        AtomicRMWBinOp::Add => 1,
+        AtomicRMWBinOp::Sub => 2,
+        AtomicRMWBinOp::And => 3,
+        AtomicRMWBinOp::Nand => 4,
+        AtomicRMWBinOp::Or => 5,
+        AtomicRMWBinOp::Xor => 6,
+        AtomicRMWBinOp::Max => 7,
+        AtomicRMWBinOp::Min => 8,
+        AtomicRMWBinOp::UMax => 9,
+        AtomicRMWBinOp::UMin => 10,
+        AtomicRMWBinOp::FAdd => 11,
+        AtomicRMWBinOp::FSub => 12,
+        AtomicRMWBinOp::FMax => 13,
+        AtomicRMWBinOp::FMin => 14,
+        AtomicRMWBinOp::UIncWrap => 15,
+        AtomicRMWBinOp::UDecWrap => 16,
+        AtomicRMWBinOp::USubCond => 17,
+        AtomicRMWBinOp::USubSat => 18,
+        AtomicRMWBinOp::FMaximum => 19,
+        AtomicRMWBinOp::FMinimum => 20,
+    }
+}
+
+fn encode_ordering(ordering: AtomicOrdering) -> u64 {
+    match ordering {
+        AtomicOrdering::NotAtomic => 0,
+        AtomicOrdering::Unordered => 1,
+        AtomicOrdering::Monotonic => 2,
+        AtomicOrdering::Acquire => 3,
+        AtomicOrdering::Release => 4,
+        AtomicOrdering::AcquireRelease => 5,
+        AtomicOrdering::SequentiallyConsistent => 6,
+    }
+}
+
+fn encode_int_predicate(predicate: IntPredicate) -> u64 {
+    match predicate {
+        IntPredicate::EQ => 32,
+        IntPredicate::NE => 33,
+        IntPredicate::UGT => 34,
+        IntPredicate::UGE => 35,
+        IntPredicate::ULT => 36,
+        IntPredicate::ULE => 37,
+        IntPredicate::SGT => 38,
+        IntPredicate::SGE => 39,
+        IntPredicate::SLT => 40,
+        IntPredicate::SLE => 41,
+    }
+}
+
+fn encode_float_predicate(predicate: FloatPredicate) -> u64 {
+    match predicate {
+        FloatPredicate::FALSE => 0,
+        FloatPredicate::OEQ => 1,
+        FloatPredicate::OGT => 2,
+        FloatPredicate::OGE => 3,
+        FloatPredicate::OLT => 4,
+        FloatPredicate::OLE => 5,
+        FloatPredicate::ONE => 6,
+        FloatPredicate::ORD => 7,
+        FloatPredicate::UNO => 8,
+        FloatPredicate::UEQ => 9,
+        FloatPredicate::UGT => 10,
+        FloatPredicate::UGE => 11,
+        FloatPredicate::ULT => 12,
+        FloatPredicate::ULE => 13,
+        FloatPredicate::UNE => 14,
+        FloatPredicate::TRUE => 15,
+    }
+}
+
+fn encode_binop(opcode: Opcode) -> Option<u64> {
+    Some(match opcode {
+        Opcode::Add | Opcode::FAdd => 0,
+        Opcode::Sub | Opcode::FSub => 1,
+        Opcode::Mul | Opcode::FMul => 2,
+        Opcode::UDiv => 3,
+        Opcode::SDiv | Opcode::FDiv => 4,
+        Opcode::URem => 5,
+        Opcode::SRem | Opcode::FRem => 6,
+        Opcode::Shl => 7,
+        Opcode::LShr => 8,
+        Opcode::AShr => 9,
+        Opcode::And => 10,
+        Opcode::Or => 11,
+        Opcode::Xor => 12,
+        _ => return None,
+    })
+}
+
+fn encode_cast(opcode: Opcode) -> Option<u64> {
+    Some(match opcode {
+        Opcode::Trunc => 0,
+        Opcode::ZExt => 1,
+        Opcode::SExt => 2,
+        Opcode::FPToUI => 3,
+        Opcode::FPToSI => 4,
+        Opcode::UIToFP => 5,
+        Opcode::SIToFP => 6,
+        Opcode::FPTrunc => 7,
+        Opcode::FPExt => 8,
+        Opcode::PtrToInt => 9,
+        Opcode::IntToPtr => 10,
+        Opcode::BitCast => 11,
+        Opcode::AddrSpaceCast => 12,
+        Opcode::PtrToAddr => 13,
+        _ => return None,
+    })
+}
+
+/// Convert a double to IEEE half precision bits, rounding to nearest even
+fn f64_to_half(value: f64) -> u16 {
+    let bits = (value as f32).to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xFF) as i32;
+    let mantissa = bits & 0x7F_FFFF;
+    if exponent == 0xFF {
+        return sign | 0x7C00 | if mantissa != 0 { 0x200 } else { 0 };
+    }
+    let exponent = exponent - 127 + 15;
+    if exponent >= 0x1F {
+        return sign | 0x7C00;
+    }
+    let (mantissa, shift) = if exponent <= 0 {
+        // Subnormal: make the implicit bit explicit and shift it into place
+        if exponent < -10 {
+            return sign;
+        }
+        (mantissa | 0x80_0000, (14 - exponent) as u32)
+    } else {
+        (mantissa, 13)
+    };
+    let half = mantissa >> shift;
+    let rest = mantissa & ((1 << shift) - 1);
+    let halfway = 1 << (shift - 1);
+    let mut result = if exponent <= 0 { half } else { ((exponent as u32) << 10) | half };
+    if rest > halfway || (rest == halfway && result & 1 != 0) {
+        // Carries into the exponent where needed
+        result += 1;
+    }
+    sign | result as u16
+}
+
+fn float_bits(kind: FloatKind, value: f64) -> u64 {
+    match kind {
+        FloatKind::Half => f64_to_half(value) as u64,
+        FloatKind::Float => (value as f32).to_bits() as u64,
+        FloatKind::Double => value.to_bits(),
+    }
+}
+
+/// Structural key of a type; named structs and opaque types are keyed by name
+fn type_key(ty: &Type) -> String {
+    if let Some(name) = ty.struct_name() {
+        return format!("%{}", name);
+    }
+    if let Some((_, space)) = ty.pointer_info() {
+        return format!("ptr{}", space);
+    }
+    if let Some((ret, params, is_var_arg)) = ty.function_info() {
+        let params: Vec<String> = params.iter().map(type_key).collect();
+        return format!("{} ({}{})", type_key(&ret), params.join(", "), if is_var_arg { ", ..." } else { "" });
+    }
+    if let Some((element, size)) = ty.array_info() {
+        return format!("[{} x {}]", size, type_key(element));
+    }
+    if let Some((element, size)) = ty.vector_info() {
+        return format!("<{} x {}>", size, type_key(element));
+    }
+    if let Some(fields) = ty.struct_fields() {
+        let fields: Vec<String> = fields.iter().map(type_key).collect();
+        let packed = if ty.is_packed_struct() { "packed " } else { "" };
+        return format!("{}{{{}}}", packed, fields.join(", "));
+    }
+    ty.to_string()
+}
+
+fn is_all_digits(name: &str) -> bool {
+    !name.is_empty() && name.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Characters of a string, one per operand
+fn string_ops(s: &str) -> Vec<u64> {
+    s.bytes().map(|b| b as u64).collect()
+}
+
+/// Whether a value is numbered in a CONSTANTS block
+fn is_constant(value: &Value) -> bool {
+    matches!(value.kind(),
+        ValueKind::ConstantInt { .. } | ValueKind::ConstantFloat { .. } | ValueKind::ConstantNull |
+        ValueKind::Undef | ValueKind::Poison | ValueKind::ConstantArray { .. } |
+        ValueKind::ConstantStruct { .. } | ValueKind::ConstantVector { .. } |
+        ValueKind::ZeroInitializer | ValueKind::ConstantExpr { .. } | ValueKind::BlockAddress { .. })
+}
+
+fn is_global(value: &Value) -> bool {
+    matches!(value.kind(), ValueKind::GlobalVariable { .. } | ValueKind::Function)
+}
+
+fn is_local(value: &Value) -> bool {
+    matches!(value.kind(), ValueKind::Argument { .. } | ValueKind::Instruction { .. })
+}
+
+/// Whether metadata refers to function-local values (`metadata i32 %x`, a
+/// DIArgList of them) and so belongs to the function's metadata block
+fn is_local_metadata(md: &Metadata) -> bool {
+    match md.as_value() {
+        Some(value) => is_local(value),
+        None => md.get_name() == Some("DIArgList")
+            && md.operands().is_some_and(|ops| ops.iter().any(is_local_metadata)),
+    }
+}
+
+/// Whether an instruction may call through its first operand
+fn is_call(opcode: Opcode) -> bool {
+    matches!(opcode, Opcode::Call | Opcode::Invoke | Opcode::CallBr)
+}
+
+/// Code of the specialized metadata node `name`
+fn di_code(name: &str) -> Option<u32> {
+    Some(match name {
+        "DILocation" => METADATA_LOCATION,
+        "GenericDINode" => METADATA_GENERIC_DEBUG,
+        "DISubrange" => METADATA_SUBRANGE,
+        "DIGenericSubrange" => METADATA_GENERIC_SUBRANGE,
+        "DIBasicType" => METADATA_BASIC_TYPE,
+        "DIStringType" => METADATA_STRING_TYPE,
+        "DIFile" => METADATA_FILE,
+        "DIDerivedType" => METADATA_DERIVED_TYPE,
+        "DICompositeType" => METADATA_COMPOSITE_TYPE,
+        "DISubroutineType" => METADATA_SUBROUTINE_TYPE,
+        "DICompileUnit" => METADATA_COMPILE_UNIT,
+        "DISubprogram" => METADATA_SUBPROGRAM,
+        "DILexicalBlock" => METADATA_LEXICAL_BLOCK,
+        "DILexicalBlockFile" => METADATA_LEXICAL_BLOCK_FILE,
+        "DINamespace" => METADATA_NAMESPACE,
+        "DITemplateTypeParameter" => METADATA_TEMPLATE_TYPE,
+        "DITemplateValueParameter" => METADATA_TEMPLATE_VALUE,
+        "DIGlobalVariable" => METADATA_GLOBAL_VAR,
+        "DILocalVariable" => METADATA_LOCAL_VAR,
+        "DILabel" => METADATA_LABEL,
+        "DIGlobalVariableExpression" => METADATA_GLOBAL_VAR_EXPR,
+        "DIObjCProperty" => METADATA_OBJC_PROPERTY,
+        "DIImportedEntity" => METADATA_IMPORTED_ENTITY,
+        "DIModule" => METADATA_MODULE,
+        "DIMacro" => METADATA_MACRO,
+        "DIMacroFile" => METADATA_MACRO_FILE,
+        "DICommonBlock" => METADATA_COMMON_BLOCK,
+        "DIAssignID" => METADATA_ASSIGN_ID,
+        _ => return None,
+    })
+}
+
+/// Leading operands that select the record layout `di_layout` decodes, and the
+/// flags the format version puts in the first operand
+fn di_format(code: u32) -> (Vec<u64>, u64) {
+    match code {
+        // Version 2: every bound is a node
+        METADATA_SUBRANGE => (vec![4], 4),
+        METADATA_NAMESPACE => (vec![0; 3], 0),
+        METADATA_STRING_TYPE => (vec![0; 8], 0),
+        METADATA_TEMPLATE_VALUE => (vec![0; 6], 0),
+        // Not used in old type refs
+        METADATA_COMPOSITE_TYPE | METADATA_SUBROUTINE_TYPE => (Vec::new(), 2),
+        // Has unit and spFlags
+        METADATA_SUBPROGRAM => (Vec::new(), 6),
+        // Version 2
+        METADATA_GLOBAL_VAR => (Vec::new(), 4),
+        // Has alignment
+        METADATA_LOCAL_VAR => (Vec::new(), 2),
+        _ => (Vec::new(), 0),
+    }
+}
+
+/// Integer field of a specialized debug info node, which the parser keeps
+/// as a string when it is written by name (`DW_TAG_member`, `DIFlagPublic`)
+fn di_int(key: &str, field: &Metadata) -> BitcodeResult<u64> {
+    if let Some(value) = field.as_int() {
+        return Ok(value as u64);
+    }
+    field.as_string()
+        .and_then(|spelling| debug_info_constant(key, spelling))
+        .ok_or_else(|| BitcodeError::Unsupported(format!("debug info field {}: {}", key, field)))
+}
+
+/// DWARF expression operations the parser keeps by name
+fn dwarf_op(name: &str) -> Option<u64> {
+    DwarfOpInfo::from_name(name).map(|op| op.code)
+}
+
+impl<'a> BitcodeWriter<'a> {
+    /// Create a writer for `module`
+    pub fn new(module: &'a Module) -> Self {
+        Self {
+            module,
+            context: module.context(),
+            types: TypeTable::default(),
+            globals: HashMap::new(),
+            global_count: 0,
+            constants: ConstantTable::default(),
+            local_constants: ConstantTable::default(),
+            locals: LocalValues::default(),
+            metadata: MetadataTable::default(),
+            attribute_groups: Vec::new(),
+            attribute_group_ids: HashMap::new(),
+            attribute_lists: Vec::new(),
+            attribute_list_ids: HashMap::new(),
+            sections: Vec::new(),
+            comdats: Vec::new(),
+            bundle_tags: Vec::new(),
+            metadata_kinds: Vec::new(),
+            strtab: Vec::new(),
+        }
+    }
+
+    /// Serialize the module, starting with the bitcode magic number
+    pub fn write(mut self) -> BitcodeResult<Vec<u8>> {
+        let module = self.module;
+        let functions = module.functions();
+        for function in &functions {
+            function.materialize().map_err(|e| invalid("function", format!("@{}: {}", function.name(), e)))?;
+        }
+        let globals = module.globals();
+        let aliases = module.aliases();
+
+        // Global values are numbered first: variables, functions, then aliases
+        for global in &globals {
+            let space = global.addrspace.unwrap_or(0);
+            self.add_global(&global.name, global.ty.clone(), space, None);
+        }
+        for function in &functions {
+            self.add_global(&function.name(), function.get_type(), 0, Some(function.clone()));
+        }
+        for alias in &aliases {
+            let space = alias.aliasee.get_type().address_space().unwrap_or(0);
+            self.add_global(&alias.name, alias.ty.clone(), space, None);
+        }
+
+        // Module-level constants: initializers, aliasees and personalities
+        self.constants = ConstantTable::new(self.global_count);
+        let mut initializers = Vec::new();
+        for global in &globals {
+            initializers.push(match &global.initializer {
+                Some(init) => Some(self.operand_id(init, false)?),
+                None => None,
+            });
+        }
+        let mut aliasees = Vec::new();
+        for alias in &aliases {
+            aliasees.push(self.operand_id(&alias.aliasee, false)?);
+        }
+        let mut personalities = Vec::new();
+        for function in &functions {
+            personalities.push(match function.personality() {
+                Some(personality) => Some(self.operand_id(&personality, false)?),
+                None => None,
+            });
+        }
+        self.plan_metadata(&functions)?;
+
+        let mut attribute_lists = Vec::new();
+        for function in &functions {
+            attribute_lists.push(self.attribute_list(&function.attributes())?);
+        }
+
+        let mut bodies = Vec::new();
+        for function in functions.iter().filter(|f| !f.basic_blocks().is_empty()) {
+            let id = self.globals[&function.name()].id;
+            bodies.push(self.plan_function(function, id)?);
+        }
+
+        // Every type is numbered once the bodies are planned
+        let mut global_records = Vec::new();
+        for (global, init) in globals.iter().zip(&initializers) {
+            global_records.push(self.global_var_record(global, *init)?);
+        }
+        let mut function_records = Vec::new();
+        for ((function, personality), attrs) in functions.iter().zip(&personalities).zip(&attribute_lists) {
+            function_records.push(self.function_record(function, *personality, *attrs)?);
+        }
+        let mut alias_records = Vec::new();
+        for (alias, aliasee) in aliases.iter().zip(&aliasees) {
+            alias_records.push(self.alias_record(alias, *aliasee)?);
+        }
+
+        let mut w = BitstreamWriter::new();
+        w.emit_bytes(&BITCODE_MAGIC);
+        self.write_identification(&mut w);
+
+        w.enter_subblock(MODULE_BLOCK_ID, 3);
+        w.emit_record(MODULE_CODE_VERSION, &[2]);
+        self.write_block_info(&mut w);
+        self.write_type_table(&mut w);
+        self.write_attributes(&mut w);
+        for comdat in self.comdats.clone() {
+            // [strtab offset, size, selection kind (any)]
+            let (offset, size) = self.add_to_strtab(&comdat);
+            w.emit_record(MODULE_CODE_COMDAT, &[offset, size, 1]);
+        }
+        if let Some(triple) = module.target_triple() {
+            w.emit_record(MODULE_CODE_TRIPLE, &string_ops(&triple));
+        }
+        if let Some(layout) = module.data_layout() {
+            w.emit_record(MODULE_CODE_DATALAYOUT, &string_ops(&layout));
+        }
+        for section in &self.sections {
+            w.emit_record(MODULE_CODE_SECTIONNAME, &string_ops(section));
+        }
+        if let Some(source) = module.source_filename() {
+            w.emit_record(MODULE_CODE_SOURCE_FILENAME, &string_ops(&source));
+        }
+        for (name, mut ops) in global_records {
+            let (offset, size) = self.add_to_strtab(&name);
+            ops.splice(0..0, [offset, size]);
+            w.emit_record(MODULE_CODE_GLOBALVAR, &ops);
+        }
+        for (name, mut ops) in function_records {
+            let (offset, size) = self.add_to_strtab(&name);
+            ops.splice(0..0, [offset, size]);
+            w.emit_record(MODULE_CODE_FUNCTION, &ops);
+        }
+        for (name, mut ops) in alias_records {
+            let (offset, size) = self.add_to_strtab(&name);
+            ops.splice(0..0, [offset, size]);
+            w.emit_record(MODULE_CODE_ALIAS, &ops);
+        }
+        let vst_offset = if bodies.is_empty() {
+            None
+        } else {
+            // Backpatched once the symbol table's position is known
+            let abbrev = w.define_abbrev(Abbrev { ops: vec![
+                AbbrevOp::Literal(MODULE_CODE_VSTOFFSET as u64), AbbrevOp::Fixed(32),
+            ] });
+            w.emit_record_with_abbrev(abbrev, MODULE_CODE_VSTOFFSET, &[0], None);
+            Some(w.bit_position() - 32)
+        };
+        self.write_constants(&mut w, &self.constants);
+        if !self.metadata_kinds.is_empty() {
+            w.enter_subblock(METADATA_KIND_BLOCK_ID, 3);
+            for (id, name) in self.metadata_kinds.iter().enumerate() {
+                let mut ops = vec![id as u64];
+                ops.extend(string_ops(name));
+                w.emit_record(METADATA_KIND, &ops);
+            }
+            w.exit_block();
+        }
+        self.write_metadata(&mut w);
+        if !self.bundle_tags.is_empty() {
+            w.enter_subblock(OPERAND_BUNDLE_TAGS_BLOCK_ID, 3);
+            for tag in &self.bundle_tags {
+                w.emit_record(OPERAND_BUNDLE_TAG, &string_ops(tag));
+            }
+            w.exit_block();
+        }
+        w.enter_subblock(SYNC_SCOPE_NAMES_BLOCK_ID, 2);
+        w.emit_record(SYNC_SCOPE_NAME, &string_ops("singlethread"));
+        w.emit_record(SYNC_SCOPE_NAME, &[]);
+        w.exit_block();
+
+        let mut function_offsets = Vec::new();
+        for body in &bodies {
+            function_offsets.push((body.value_id, w.bit_position() / 32));
+            self.write_function(&mut w, body);
+        }
+        if let Some(position) = vst_offset {
+            w.backpatch_word(position, (w.bit_position() / 32) as u32);
+            w.enter_subblock(VALUE_SYMTAB_BLOCK_ID, 4);
+            let abbrev = w.define_abbrev(Abbrev { ops: vec![
+                AbbrevOp::Literal(VST_CODE_FNENTRY as u64), AbbrevOp::Vbr(8), AbbrevOp::Vbr(8),
+            ] });
+            for (id, offset) in function_offsets {
+                w.emit_record_with_abbrev(abbrev, VST_CODE_FNENTRY, &[id as u64, offset as u64], None);
+            }
+            w.exit_block();
+        }
+        w.exit_block();
+
+        w.enter_subblock(STRTAB_BLOCK_ID, 3);
+        let abbrev = w.define_abbrev(Abbrev { ops: vec![AbbrevOp::Literal(STRTAB_BLOB as u64), AbbrevOp::Blob] });
+        w.emit_record_with_abbrev(abbrev, STRTAB_BLOB, &[], Some(&self.strtab));
+        w.exit_block();
+        Ok(w.finish())
+    }
+
+    fn add_global(&mut self, name: &str, value_type: Type, address_space: u32, function: Option<Function>) {
+        let id = self.global_count;
+        self.global_count += 1;
+        self.globals.insert(name.to_string(), GlobalInfo { id, value_type, address_space, function });
+    }
+
+    fn add_to_strtab(&mut self, name: &str) -> (u64, u64) {
+        let offset = self.strtab.len() as u64;
+        self.strtab.extend_from_slice(name.as_bytes());
+        (offset, name.len() as u64)
+    }
+
+    // Types
+
+    fn type_id(&mut self, ty: &Type) -> BitcodeResult<u32> {
+        let key = type_key(ty);
+        if let Some(&id) = self.types.ids.get(&key) {
+            return Ok(id);
+        }
+        let (code, ops, name) = if ty.is_void() {
+            (TYPE_CODE_VOID, Vec::new(), None)
+        } else if let Some(bits) = ty.int_width() {
+            (TYPE_CODE_INTEGER, vec![bits as u64], None)
+        } else if let Some(kind) = ty.float_kind() {
+            let code = match kind {
+                FloatKind::Half => TYPE_CODE_HALF,
+                FloatKind::Float => TYPE_CODE_FLOAT,
+                FloatKind::Double => TYPE_CODE_DOUBLE,
+            };
+            (code, Vec::new(), None)
+        } else if let Some((_, space)) = ty.pointer_info() {
+            (TYPE_CODE_OPAQUE_POINTER, vec![space as u64], None)
+        } else if let Some((element, size)) = ty.array_info() {
+            (TYPE_CODE_ARRAY, vec![size as u64, self.type_id(element)? as u64], None)
+        } else if let Some((element, size)) = ty.vector_info() {
+            (TYPE_CODE_VECTOR, vec![size as u64, self.type_id(element)? as u64], None)
+        } else if let Some((ret, params, is_var_arg)) = ty.function_info() {
+            let mut ops = vec![is_var_arg as u64, self.type_id(&ret)? as u64];
+            for param in &params {
+                ops.push(self.type_id(param)? as u64);
+            }
+            (TYPE_CODE_FUNCTION, ops, None)
+        } else if let Some(fields) = ty.struct_fields() {
+            let name = ty.struct_name().map(str::to_string);
+            if name.is_some() {
+                if self.types.in_progress.contains(&key) {
+                    // A named struct referring to itself: number it now, fill it in later
+                    let id = self.types.records.len() as u32;
+                    self.types.records.push((TYPE_CODE_OPAQUE, vec![0], name));
+                    self.types.ids.insert(key, id);
+                    return Ok(id);
+                }
+                self.types.in_progress.push(key.clone());
+            }
+            let mut ops = vec![ty.is_packed_struct() as u64];
+            for field in &fields {
+                ops.push(self.type_id(field)? as u64);
+            }
+            if name.is_some() {
+                self.types.in_progress.retain(|k| k != &key);
+                if let Some(&id) = self.types.ids.get(&key) {
+                    self.types.records[id as usize] = (TYPE_CODE_STRUCT_NAMED, ops, name);
+                    return Ok(id);
+                }
+                (TYPE_CODE_STRUCT_NAMED, ops, name)
+            } else {
+                (TYPE_CODE_STRUCT_ANON, ops, None)
+            }
+        } else if ty.is_opaque() {
+            let name = ty.struct_name().unwrap_or_default().to_string();
+            if let Some(defined) = self.module.get_named_type(&name).filter(|t| !t.is_opaque()) {
+                return self.type_id(&defined);
+            }
+            (TYPE_CODE_OPAQUE, vec![0], Some(name))
+        } else if ty.is_label() {
+            (TYPE_CODE_LABEL, Vec::new(), None)
+        } else if ty.is_token() {
+            (TYPE_CODE_TOKEN, Vec::new(), None)
+        } else if ty.is_metadata() {
+            (TYPE_CODE_METADATA, Vec::new(), None)
+        } else if ty.is_x86_amx() {
+            (TYPE_CODE_X86_AMX, Vec::new(), None)
+        } else {
+            return Err(BitcodeError::Unsupported(format!("type {}", ty)));
+        };
+        let id = self.types.records.len() as u32;
+        self.types.records.push((code, ops, name));
+        self.types.ids.insert(key, id);
+        Ok(id)
+    }
+
+    fn pointer_type_id(&mut self, address_space: u32) -> BitcodeResult<u32> {
+        let ptr = Type::ptr_addrspace(&self.context, self.context.int8_type(), address_space);
+        self.type_id(&ptr)
+    }
+
+    /// Type ID of a value as bitcode sees it: globals and callees are pointers
+    fn value_type_id(&mut self, value: &Value) -> BitcodeResult<u32> {
+        if is_global(value) {
+            let space = value.name().and_then(|n| self.globals.get(n)).map_or(0, |g| g.address_space);
+            return self.pointer_type_id(space);
+        }
+        if value.get_type().is_function() {
+            return self.pointer_type_id(0);
+        }
+        self.type_id(value.get_type())
+    }
+
+    fn write_type_table(&self, w: &mut BitstreamWriter) {
+        w.enter_subblock(TYPE_BLOCK_ID_NEW, 4);
+        w.emit_record(TYPE_CODE_NUMENTRY, &[self.types.records.len() as u64]);
+        for (code, ops, name) in &self.types.records {
+            if let Some(name) = name {
+                w.emit_record(TYPE_CODE_STRUCT_NAME, &string_ops(name));
+            }
+            w.emit_record(*code, ops);
+        }
+        w.exit_block();
+    }
+
+    // Global values
+
+    fn global_id(&self, value: &Value) -> BitcodeResult<u32> {
+        let name = value.name().unwrap_or_default();
+        self.globals.get(name).map(|g| g.id)
+            .ok_or_else(|| invalid("module", format!("reference to undefined global @{}", name)))
+    }
+
+    fn global_var_record(&mut self, global: &crate::module::GlobalVariable, init: Option<u32>) -> BitcodeResult<(String, Vec<u64>)> {
+        // [valty, isconst|explicit<<1|addrspace<<2, initid+1, linkage, align, section, visibility,
+        //  threadlocal, unnamed_addr, externally_initialized, dllstorageclass, comdat, attrs, preemption]
+        let section = match &global.section {
+            Some(section) => Self::index_of(&mut self.sections, section) + 1,
+            None => 0,
+        };
+        let comdat = match &global.comdat {
+            Some(comdat) => Self::index_of(&mut self.comdats, comdat) + 1,
+            None => 0,
+        };
+        let flags = global.is_constant as u64 | 2 | (global.addrspace.unwrap_or(0) as u64) << 2;
+        let ops = vec![
+            self.type_id(&global.ty)? as u64,
+            flags,
+            init.map_or(0, |id| id as u64 + 1),
+            encode_linkage(global.linkage),
+            encode_alignment(global.alignment.map(|a| a as u64)),
+            section,
+            encode_visibility(global.visibility),
+            encode_thread_local(global.thread_local_mode),
+            encode_unnamed_addr(global.unnamed_addr),
+            global.externally_initialized as u64,
+            encode_dll_storage_class(global.dll_storage_class),
+            comdat,
+            0,
+            0,
+        ];
+        Ok((global.name.clone(), ops))
+    }
+
+    fn function_record(&mut self, function: &Function, personality: Option<u32>, attrs: u64) -> BitcodeResult<(String, Vec<u64>)> {
+        // [type, callingconv, isproto, linkage, paramattr, alignment, section, visibility, gc,
+        //  unnamed_addr, prologuedata, dllstorageclass, comdat, prefixdata, personalityfn, preemption]
+        let ops = vec![
+            self.type_id(&function.get_type())? as u64,
+            encode_calling_convention(function.calling_convention())?,
+            function.basic_blocks().is_empty() as u64,
+            encode_linkage(function.linkage()),
+            attrs,
+            0,
+            0,
+            encode_visibility(function.visibility()),
+            0,
+            0,
+            0,
+            encode_dll_storage_class(function.dll_storage_class()),
+            0,
+            0,
+            personality.map_or(0, |id| id as u64 + 1),
+            0,
+        ];
+        Ok((function.name(), ops))
+    }
+
+    fn alias_record(&mut self, alias: &crate::module::Alias, aliasee: u32) -> BitcodeResult<(String, Vec<u64>)> {
+        // [alias value type, addrspace, aliasee, linkage, visibility, dllstorageclass,
+        //  threadlocal, unnamed_addr, preemption]
+        let space = self.globals[&alias.name].address_space;
+        let ops = vec![
+            self.type_id(&alias.ty)? as u64,
+            space as u64,
+            aliasee as u64,
+            encode_linkage(alias.linkage),
+            encode_visibility(alias.visibility),
+            encode_dll_storage_class(alias.dll_storage_class),
+            encode_thread_local(alias.thread_local_mode),
+            encode_unnamed_addr(alias.unnamed_addr),
+            0,
+        ];
+        Ok((alias.name.clone(), ops))
+    }
+
+    fn index_of(list: &mut Vec<String>, item: &str) -> u64 {
+        match list.iter().position(|s| s == item) {
+            Some(i) => i as u64,
+            None => {
+                list.push(item.to_string());
+                list.len() as u64 - 1
+            }
+        }
+    }
+
+    // Constants
+
+    /// ID of an operand: a global, a constant, or (in a function) a local value
+    fn operand_id(&mut self, value: &Value, local: bool) -> BitcodeResult<u32> {
+        match value.kind() {
+            ValueKind::GlobalVariable { .. } | ValueKind::Function => self.global_id(value),
+            ValueKind::Argument { .. } | ValueKind::Instruction { .. } if local => self.locals.lookup(value)
+                .ok_or_else(|| invalid("function", format!("use of undefined value {}", value))),
+            _ if is_constant(value) => self.constant_id(value, local),
+            _ => Err(invalid(if local { "function" } else { "constants" }, format!("{} is not a constant", value))),
+        }
+    }
+
+    fn constant_id(&mut self, value: &Value, local: bool) -> BitcodeResult<u32> {
+        let type_id = self.value_type_id(value)?;
+        let key = format!("{}|{}", type_id, value);
+        if let Some(&id) = self.constants.ids.get(&key) {
+            return Ok(id);
+        }
+        if local {
+            if let Some(&id) = self.local_constants.ids.get(&key) {
+                return Ok(id);
+            }
+            if self.local_constants.sealed {
+                return Err(invalid("function", format!("constant {} was not numbered", value)));
+            }
+        }
+        let (code, ops) = self.encode_constant(value, local)?;
+        let table = if local { &mut self.local_constants } else { &mut self.constants };
+        let id = table.next_id();
+        table.records.push((type_id, code, ops));
+        table.ids.insert(key, id);
+        Ok(id)
+    }
+
+    fn encode_constant(&mut self, value: &Value, local: bool) -> BitcodeResult<(u32, Vec<u64>)> {
+        let ty = value.get_type().clone();
+        Ok(match value.kind() {
+            ValueKind::ConstantInt { value: v } => {
+                if let Some(bits) = ty.int_width() {
+                    if bits <= 64 {
+                        // Sign-extend from the type's width
+                        let shift = 64 - bits;
+                        (CST_CODE_INTEGER, vec![encode_signed((v << shift) >> shift)])
+                    } else {
+                        let fill = encode_signed(if *v < 0 { -1 } else { 0 });
+                        let mut ops = vec![encode_signed(*v)];
+                        ops.resize(bits.div_ceil(64) as usize, fill);
+                        (CST_CODE_WIDE_INTEGER, ops)
+                    }
+                } else if let Some(kind) = ty.float_kind() {
+                    (CST_CODE_FLOAT, vec![float_bits(kind, *v as f64)])
+                } else if *v == 0 {
+                    (CST_CODE_NULL, Vec::new())
+                } else {
+                    return Err(BitcodeError::Unsupported(format!("integer constant of type {}", ty)));
+                }
+            }
+            ValueKind::ConstantFloat { value: v } => match ty.float_kind() {
+                Some(kind) => (CST_CODE_FLOAT, vec![float_bits(kind, *v)]),
+                None => return Err(BitcodeError::Unsupported(format!("float constant of type {}", ty))),
+            },
+            ValueKind::ConstantNull | ValueKind::ZeroInitializer => (CST_CODE_NULL, Vec::new()),
+            // `none`
+            ValueKind::Undef if ty.is_token() => (CST_CODE_NULL, Vec::new()),
+            ValueKind::Undef => (CST_CODE_UNDEF, Vec::new()),
+            ValueKind::Poison => (CST_CODE_POISON, Vec::new()),
+            ValueKind::ConstantArray { elements } | ValueKind::ConstantVector { elements } => {
+                self.encode_sequence(&ty, elements, local)?
+            }
+            ValueKind::ConstantStruct { fields } => {
+                let mut ops = Vec::new();
+                for field in fields {
+                    ops.push(self.operand_id(field, local)? as u64);
+                }
+                (CST_CODE_AGGREGATE, ops)
+            }
+            ValueKind::ConstantExpr { opcode, operands } => self.encode_constant_expr(&ty, *opcode, operands, local)?,
+            ValueKind::BlockAddress { function, block } => {
+                // [fnty, fn, bb#]
+                let name = function.name().unwrap_or_default();
+                let f = self.globals.get(name).and_then(|g| g.function.clone())
+                    .ok_or_else(|| invalid("constants", format!("blockaddress of undefined function @{}", name)))?;
+                let label = block.name().unwrap_or_default();
+                let index = f.basic_blocks().iter().position(|bb| bb.name().as_deref() == Some(label))
+                    .ok_or_else(|| invalid("constants", format!("blockaddress of undefined block %{}", label)))?;
+                let fn_ty = self.value_type_id(function)?;
+                (CST_CODE_BLOCKADDRESS, vec![fn_ty as u64, self.global_id(function)? as u64, index as u64])
+            }
+            _ => return Err(invalid("constants", format!("{} is not a constant", value))),
+        })
+    }
+
+    /// Arrays and vectors: packed data where the elements are simple scalars
+    fn encode_sequence(&mut self, ty: &Type, elements: &[Value], local: bool) -> BitcodeResult<(u32, Vec<u64>)> {
+        if elements.is_empty() {
+            return Ok((CST_CODE_NULL, Vec::new()));
+        }
+        let element_ty = ty.array_info().or_else(|| ty.vector_info()).map(|(e, _)| e.clone());
+        let width = element_ty.as_ref().and_then(|t| t.int_width()).filter(|w| matches!(w, 8 | 16 | 32 | 64));
+        let float = element_ty.as_ref().and_then(|t| t.float_kind());
+        let data: Option<Vec<u64>> = elements.iter().map(|e| match (e.kind(), width, float) {
+            (ValueKind::ConstantInt { value }, Some(bits), _) => {
+                Some(if bits == 64 { *value as u64 } else { *value as u64 & ((1u64 << bits) - 1) })
+            }
+            (ValueKind::ConstantFloat { value }, _, Some(kind)) => Some(float_bits(kind, *value)),
+            _ => None,
+        }).collect();
+        if let Some(mut data) = data {
+            if width == Some(8) && ty.is_array() {
+                // A C string is NUL terminated with no other NULs
+                if data.last() == Some(&0) && !data[..data.len() - 1].contains(&0) {
+                    data.pop();
+                    return Ok((CST_CODE_CSTRING, data));
+                }
+                return Ok((CST_CODE_STRING, data));
+            }
+            return Ok((CST_CODE_DATA, data));
+        }
+        let mut ops = Vec::new();
+        for element in elements {
+            ops.push(self.operand_id(element, local)? as u64);
+        }
+        Ok((CST_CODE_AGGREGATE, ops))
+    }
+
+    fn encode_constant_expr(&mut self, ty: &Type, opcode: Opcode, operands: &[Value], local: bool) -> BitcodeResult<(u32, Vec<u64>)> {
+        let operand = |i: usize| operands.get(i).cloned()
+            .ok_or_else(|| invalid("constants", format!("{:?} expression is missing operand {}", opcode, i)));
+        if let Some(code) = encode_binop(opcode) {
+            let (lhs, rhs) = (operand(0)?, operand(1)?);
+            return Ok((CST_CODE_CE_BINOP, vec![code, self.operand_id(&lhs, local)? as u64, self.operand_id(&rhs, local)? as u64]));
+        }
+        if let Some(code) = encode_cast(opcode) {
+            let value = operand(0)?;
+            return Ok((CST_CODE_CE_CAST, vec![code, self.value_type_id(&value)? as u64, self.operand_id(&value, local)? as u64]));
+        }
+        Ok(match opcode {
+            Opcode::FNeg => (CST_CODE_CE_UNOP, vec![0, self.operand_id(&operand(0)?, local)? as u64]),
+            Opcode::GetElementPtr => {
+                // [pointee type, (type, value)...]
+                let base = operand(0)?;
+                let source = if is_global(&base) {
+                    base.name().and_then(|n| self.globals.get(n)).map(|g| g.value_type.clone())
+                } else {
+                    base.get_type().pointee_type().cloned()
+                };
+                let source = source.unwrap_or_else(|| self.context.int8_type());
+                let mut ops = vec![self.type_id(&source)? as u64];
+                for op in operands {
+                    ops.push(self.value_type_id(op)? as u64);
+                    ops.push(self.operand_id(op, local)? as u64);
+                }
+                (CST_CODE_CE_GEP_OLD, ops)
+            }
+            Opcode::Select => {
+                let mut ops = Vec::new();
+                for i in 0..3 {
+                    ops.push(self.operand_id(&operand(i)?, local)? as u64);
+                }
+                (CST_CODE_CE_SELECT, ops)
+            }
+            Opcode::ExtractElement => {
+                let (vector, index) = (operand(0)?, operand(1)?);
+                (CST_CODE_CE_EXTRACTELT, vec![
+                    self.value_type_id(&vector)? as u64, self.operand_id(&vector, local)? as u64,
+                    self.value_type_id(&index)? as u64, self.operand_id(&index, local)? as u64,
+                ])
+            }
+            Opcode::InsertElement => {
+                let (vector, element, index) = (operand(0)?, operand(1)?, operand(2)?);
+                (CST_CODE_CE_INSERTELT, vec![
+                    self.operand_id(&vector, local)? as u64, self.operand_id(&element, local)? as u64,
+                    self.value_type_id(&index)? as u64, self.operand_id(&index, local)? as u64,
+                ])
+            }
+            Opcode::ShuffleVector => {
+                let (v1, v2, mask) = (operand(0)?, operand(1)?, operand(2)?);
+                let ops = vec![
+                    self.operand_id(&v1, local)? as u64, self.operand_id(&v2, local)? as u64,
+                    self.operand_id(&mask, local)? as u64,
+                ];
+                if v1.get_type() == ty {
+                    (CST_CODE_CE_SHUFFLEVEC, ops)
+                } else {
+                    let mut ex = vec![self.value_type_id(&v1)? as u64];
+                    ex.extend(ops);
+                    (CST_CODE_CE_SHUFVEC_EX, ex)
+                }
+            }
+            Opcode::ICmp | Opcode::FCmp => {
+                // The model does not keep the predicate of constant comparisons
+                let (lhs, rhs) = (operand(0)?, operand(1)?);
+                let predicate = if opcode == Opcode::ICmp { encode_int_predicate(IntPredicate::EQ) } else { 1 };
+                (CST_CODE_CE_CMP, vec![
+                    self.value_type_id(&lhs)? as u64, self.operand_id(&lhs, local)? as u64,
+                    self.operand_id(&rhs, local)? as u64, predicate,
+                ])
+            }
+            _ => return Err(BitcodeError::Unsupported(format!("{:?} constant expression", opcode))),
+        })
+    }
+
+    fn write_constants(&self, w: &mut BitstreamWriter, table: &ConstantTable) {
+        if table.records.is_empty() {
+            return;
+        }
+        w.enter_subblock(CONSTANTS_BLOCK_ID, 4);
+        let mut current = None;
+        for (type_id, code, ops) in &table.records {
+            if current != Some(*type_id) {
+                w.emit_record_with_abbrev(CONSTANTS_SETTYPE_ABBREV, CST_CODE_SETTYPE, &[*type_id as u64], None);
+                current = Some(*type_id);
+            }
+            match *code {
+                CST_CODE_INTEGER => w.emit_record_with_abbrev(CONSTANTS_INTEGER_ABBREV, *code, ops, None),
+                CST_CODE_NULL => w.emit_record_with_abbrev(CONSTANTS_NULL_ABBREV, *code, ops, None),
+                CST_CODE_CE_CAST => w.emit_record_with_abbrev(CONSTANTS_CE_CAST_ABBREV, *code, ops, None),
+                _ => w.emit_record(*code, ops),
+            }
+        }
+        w.exit_block();
+    }
+
+    // Metadata
+
+    fn plan_metadata(&mut self, functions: &[Function]) -> BitcodeResult<()> {
+        let module = self.module;
+        let mut numbered: Vec<(usize, String, Metadata)> = module.metadata_nodes().into_iter()
+            .filter_map(|(name, md)| name.parse::<usize>().ok().map(|n| (n, name, md)))
+            .collect();
+        numbered.sort_by_key(|(n, ..)| *n);
+        // Number every numbered node first so nodes can refer forward
+        for (_, name, md) in &numbered {
+            let index = self.metadata.reserve();
+            self.metadata.numbered.insert(name.clone(), index);
+            self.metadata.nodes.insert(md.node_id(), index);
+            self.metadata.retained.push(md.clone());
+        }
+        for (_, name, md) in &numbered {
+            let index = self.metadata.numbered[name];
+            self.metadata.entries[index] = self.metadata_record(md)?;
+            // `!{}` is uniqued, so a numbered one serves every empty operand
+            if self.metadata.empty_node.is_none() && md.as_tuple().is_some_and(|ops| ops.is_empty()) {
+                self.metadata.empty_node = Some(index);
+            }
+        }
+
+        let named = module.named_metadata();
+        let mut names: Vec<&String> = named.keys().collect();
+        names.sort();
+        for name in names {
+            // The module's own list refers to the numbered nodes rather than copies of them
+            let operands = module.get_metadata(name)
+                .and_then(|md| md.as_tuple().cloned())
+                .unwrap_or_else(|| named[name].clone());
+            let mut refs = Vec::new();
+            for op in &operands {
+                refs.push(self.metadata_node(op)?);
+            }
+            self.metadata.named.push((name.clone(), refs));
+        }
+
+        // Everything the bodies refer to is numbered with the module's
+        // metadata, so that function-local metadata can follow it
+        for function in functions {
+            let blocks = function.basic_blocks();
+            if blocks.is_empty() {
+                if let Some(subprogram) = function.subprogram() {
+                    let kind = self.metadata_kind("dbg");
+                    let md = self.metadata_node(&subprogram)?;
+                    let id = self.globals[&function.name()].id;
+                    self.metadata.global_attachments.push((id, vec![(kind, md)]));
+                }
+                continue;
+            }
+            if let Some(subprogram) = function.subprogram() {
+                self.metadata_kind("dbg");
+                self.metadata_node(&subprogram)?;
+            }
+            for inst in blocks.iter().flat_map(|b| b.instructions()) {
+                if let Some(location) = inst.debug_loc() {
+                    self.metadata_kind("dbg");
+                    self.debug_loc_ops(location)?;
+                }
+                for (kind, node) in inst.attached_metadata().iter().filter(|(kind, _)| kind != "dbg") {
+                    self.metadata_kind(kind);
+                    self.metadata_node(node)?;
+                }
+                if !is_call(inst.opcode()) {
+                    continue;
+                }
+                for arg in inst.operands().iter().filter(|v| v.get_type().is_metadata()) {
+                    match (arg.as_metadata_string(), arg.as_metadata()) {
+                        (Some(s), _) => { self.metadata.string(s); }
+                        (_, Some(node)) if is_local_metadata(node) => {
+                            // Only the operands that are not local stay at module level
+                            for op in node.operands().into_iter().flatten().filter(|op| !is_local_metadata(op)) {
+                                self.metadata_node(op)?;
+                            }
+                        }
+                        (_, Some(node)) => { self.metadata_node(node)?; }
+                        _ => { self.metadata.empty_node(); }
+                    }
+                }
+            }
+        }
+        for global in module.globals().iter().filter(|g| !g.metadata.is_empty()) {
+            let mut attachments = Vec::new();
+            for (kind, node) in &global.metadata {
+                attachments.push((self.metadata_kind(kind), self.metadata_node(node)?));
+            }
+            let id = self.globals[&global.name].id;
+            self.metadata.global_attachments.push((id, attachments));
+        }
+        Ok(())
+    }
+
+    fn metadata_kind(&mut self, name: &str) -> u64 {
+        Self::index_of(&mut self.metadata_kinds, name)
+    }
+
+    /// Reference to `md` where a null operand is not allowed
+    fn metadata_node(&mut self, md: &Metadata) -> BitcodeResult<MdRef> {
+        Ok(match self.metadata_ref(md, false)? {
+            Some(md) => md,
+            None => self.metadata.empty_node(),
+        })
+    }
+
+    /// [line, column, scope + 1, inlinedAt + 1, isImplicitCode] of a `!dbg` location,
+    /// or `None` if the attachment is not a `DILocation` and is dropped as upstream does
+    fn debug_loc_ops(&mut self, location: &Metadata) -> BitcodeResult<Option<Vec<u64>>> {
+        let location = match location.as_reference() {
+            Some(name) => self.module.get_metadata(name)
+                .ok_or_else(|| invalid("metadata", format!("undefined debug location !{}", name)))?,
+            None => location.clone(),
+        };
+        if location.get_name() != Some("DILocation") {
+            return Ok(None);
+        }
+        let int = |key: &str| location.get_field(key).and_then(|f| f.as_int()).unwrap_or(0) as u64;
+        let mut ops = vec![int("line"), int("column")];
+        for key in ["scope", "inlinedAt"] {
+            let md = match location.get_field(key) {
+                Some(field) => self.metadata_ref(field, false)?,
+                None => None,
+            };
+            ops.push(md.map_or(0, |md| self.metadata.id(md) + 1));
+        }
+        ops.push(int("isImplicitCode"));
+        Ok(Some(ops))
+    }
+
+    /// Reference to `md` as a node operand, or `None` for a null operand
+    fn metadata_ref(&mut self, md: &Metadata, wide: bool) -> BitcodeResult<Option<MdRef>> {
+        if let Some(s) = md.as_string() {
+            return Ok(Some(self.metadata.string(s)));
+        }
+        if let Some(name) = md.as_reference() {
+            return Ok(self.metadata.numbered.get(name).map(|&i| MdRef::Entry(i)));
+        }
+        if let Some(&index) = self.metadata.nodes.get(&md.node_id()) {
+            return Ok(Some(MdRef::Entry(index)));
+        }
+        if md.as_tuple().is_some_and(|ops| ops.is_empty()) {
+            return Ok(None);
+        }
+        let value = if let Some(i) = md.as_int() {
+            let ty = if wide || i32::try_from(i).is_err() { self.context.int64_type() } else { self.context.int32_type() };
+            Some(Value::const_int(ty, i, None))
+        } else if let Some(f) = md.as_float() {
+            Some(Value::const_float(self.context.double_type(), f, None))
+        } else {
+            md.as_value().cloned()
+        };
+        if let Some(value) = value {
+            return self.metadata_value(&value).map(Some);
+        }
+        // A node written inline
+        let index = self.metadata.reserve();
+        self.metadata.nodes.insert(md.node_id(), index);
+        self.metadata.retained.push(md.clone());
+        self.metadata.entries[index] = self.metadata_record(md)?;
+        Ok(Some(MdRef::Entry(index)))
+    }
+
+    fn metadata_value(&mut self, value: &Value) -> BitcodeResult<MdRef> {
+        // Function-local values are not tracked through metadata
+        let value = if is_global(value) || is_constant(value) { value.clone() } else { Value::undef(value.get_type().clone()) };
+        let type_id = self.value_type_id(&value)?;
+        let key = format!("{}|{}", type_id, value);
+        if let Some(&index) = self.metadata.values.get(&key) {
+            return Ok(MdRef::Entry(index));
+        }
+        let id = self.operand_id(&value, false)?;
+        let index = self.metadata.reserve();
+        self.metadata.entries[index] = MdEntry {
+            code: METADATA_VALUE,
+            ops: vec![MdOp::Raw(type_id as u64), MdOp::Raw(id as u64)],
+        };
+        self.metadata.values.insert(key, index);
+        Ok(MdRef::Entry(index))
+    }
+
+    fn metadata_record(&mut self, md: &Metadata) -> BitcodeResult<MdEntry> {
+        if let Some(operands) = md.as_tuple() {
+            let mut ops = Vec::new();
+            for op in operands {
+                ops.push(MdOp::RefOrNull(self.metadata_ref(op, false)?));
+            }
+            return Ok(MdEntry { code: METADATA_NODE, ops });
+        }
+        let name = md.get_name().ok_or_else(|| BitcodeError::Unsupported(format!("metadata {}", md)))?;
+        if let Some(operands) = md.operands() {
+            return Ok(match name {
+                "DIExpression" => {
+                    // [version 3 << 1, elements...]
+                    let mut ops = vec![MdOp::Raw(6)];
+                    for op in operands {
+                        let element = op.as_int().map(|i| i as u64)
+                            .or_else(|| op.as_string().and_then(dwarf_op))
+                            .ok_or_else(|| BitcodeError::Unsupported(format!("DIExpression element {}", op)))?;
+                        ops.push(MdOp::Raw(element));
+                    }
+                    MdEntry { code: METADATA_EXPRESSION, ops }
+                }
+                "DIArgList" => {
+                    let mut ops = Vec::new();
+                    for op in operands {
+                        ops.push(MdOp::Ref(self.metadata_node(op)?));
+                    }
+                    MdEntry { code: METADATA_ARG_LIST, ops }
+                }
+                _ if di_code(name).is_some() && operands.is_empty() => self.di_record(name, md)?,
+                // Other named nodes keep only their operands
+                _ => {
+                    let mut ops = Vec::new();
+                    for op in operands {
+                        ops.push(MdOp::RefOrNull(self.metadata_ref(op, false)?));
+                    }
+                    MdEntry { code: METADATA_NODE, ops }
+                }
+            });
+        }
+        self.di_record(name, md)
+    }
+
+    /// Specialized debug info node, in the field layout the reader decodes
+    fn di_record(&mut self, name: &str, md: &Metadata) -> BitcodeResult<MdEntry> {
+        let int = |key: &str| md.get_field(key).and_then(|f| f.as_int());
+        if name == "DIEnumerator" {
+            // [isUnsigned << 1, value, name]
+            let name = match md.get_field("name") {
+                Some(name) => self.metadata_ref(name, false)?,
+                None => None,
+            };
+            let unsigned = int("isUnsigned").unwrap_or(0) != 0;
+            return Ok(MdEntry { code: METADATA_ENUMERATOR, ops: vec![
+                MdOp::Raw((unsigned as u64) << 1),
+                MdOp::Raw(encode_signed(int("value").unwrap_or(0))),
+                MdOp::RefOrNull(name),
+            ] });
+        }
+        let code = di_code(name).ok_or_else(|| BitcodeError::Unsupported(format!("metadata node !{}", name)))?;
+        let (selector, flags) = di_format(code);
+        let stub = Record { code, ops: selector, blob: None, abbrev_id: 0 };
+        let (_, layout) = di_layout(code, &stub)
+            .ok_or_else(|| BitcodeError::Unsupported(format!("metadata node !{}", name)))?;
+        // Compile units are always distinct, as are subprogram definitions
+        let distinct = match name {
+            "DICompileUnit" | "DIAssignID" => true,
+            "DISubprogram" => md.has_field("unit"),
+            _ => false,
+        };
+        let mut ops = vec![MdOp::Raw(flags | distinct as u64)];
+        for field in layout {
+            ops.push(match *field {
+                DIField::Int(key) => MdOp::Raw(match (name, key, md.get_field(key)) {
+                    (_, _, Some(field)) => di_int(key, field)?,
+                    ("DIBasicType", "tag", None) => 0x24,
+                    ("DICompileUnit", "splitDebugInlining", None) => 1,
+                    // DISPFlagDefinition
+                    ("DISubprogram", "spFlags", None) if distinct => 8,
+                    _ => 0,
+                }),
+                DIField::Signed(key) => MdOp::Raw(encode_signed(int(key).unwrap_or(0))),
+                DIField::Node(key) => MdOp::RefOrNull(match md.get_field(key) {
+                    Some(field) => self.metadata_ref(field, true)?,
+                    None => None,
+                }),
+                DIField::NodeDirect(key) => {
+                    let field = match md.get_field(key) {
+                        Some(field) => self.metadata_ref(field, true)?,
+                        None => None,
+                    };
+                    MdOp::Ref(match field {
+                        Some(field) => field,
+                        None => self.metadata.empty_node(),
+                    })
+                }
+                DIField::Skip => MdOp::Raw(0),
+            });
+        }
+        if code == METADATA_FILE && matches!(ops.last(), Some(MdOp::RefOrNull(None))) {
+            // No source
+            ops.pop();
+        }
+        Ok(MdEntry { code, ops })
+    }
+
+    fn write_metadata(&self, w: &mut BitstreamWriter) {
+        let table = &self.metadata;
+        if table.strings.is_empty() && table.entries.is_empty() && table.named.is_empty() {
+            return;
+        }
+        w.enter_subblock(METADATA_BLOCK_ID, 3);
+        if !table.strings.is_empty() {
+            // [count, offset] with a blob of vbr6 lengths, then the characters
+            let mut lengths = BitstreamWriter::new();
+            for s in &table.strings {
+                lengths.emit_vbr(s.len() as u64, 6);
+            }
+            let mut blob = lengths.finish();
+            let offset = blob.len() as u64;
+            for s in &table.strings {
+                blob.extend_from_slice(s.as_bytes());
+            }
+            let abbrev = w.define_abbrev(Abbrev { ops: vec![
+                AbbrevOp::Literal(METADATA_STRINGS as u64), AbbrevOp::Vbr(6), AbbrevOp::Vbr(6), AbbrevOp::Blob,
+            ] });
+            w.emit_record_with_abbrev(abbrev, METADATA_STRINGS, &[table.strings.len() as u64, offset], Some(&blob));
+        }
+        for entry in &table.entries {
+            let ops: Vec<u64> = entry.ops.iter().map(|op| match op {
+                MdOp::Raw(value) => *value,
+                MdOp::Ref(md) => table.id(*md),
+                MdOp::RefOrNull(md) => md.map_or(0, |md| table.id(md) + 1),
+            }).collect();
+            w.emit_record(entry.code, &ops);
+        }
+        for (name, nodes) in &table.named {
+            w.emit_record(METADATA_NAME, &string_ops(name));
+            let ids: Vec<u64> = nodes.iter().map(|&md| table.id(md)).collect();
+            w.emit_record(METADATA_NAMED_NODE, &ids);
+        }
+        for (id, attachments) in &table.global_attachments {
+            // [value ID, (kind, node)...]
+            let mut ops = vec![*id as u64];
+            for &(kind, md) in attachments {
+                ops.extend([kind, table.id(md)]);
+            }
+            w.emit_record(METADATA_GLOBAL_DECL_ATTACHMENT, &ops);
+        }
+        w.exit_block();
+    }
+
+    // Attributes
+
+    fn attribute_group(&mut self, index: u64, attrs: Vec<u64>) -> u64 {
+        let key = (index, attrs);
+        if let Some(&id) = self.attribute_group_ids.get(&key) {
+            return id;
+        }
+        self.attribute_groups.push(key.clone());
+        let id = self.attribute_groups.len() as u64;
+        self.attribute_group_ids.insert(key, id);
+        id
+    }
+
+    /// Attribute list ID + 1 of `attrs`, zero if there are none
+    fn attribute_list(&mut self, attrs: &FunctionAttributes) -> BitcodeResult<u64> {
+        let mut groups = Vec::new();
+        let function = self.function_attribute_ops(attrs);
+        if !function.is_empty() {
+            groups.push(self.attribute_group(ATTR_FUNCTION_INDEX, function));
+        }
+        let ret = Self::return_attribute_ops(&attrs.return_attributes);
+        if !ret.is_empty() {
+            groups.push(self.attribute_group(0, ret));
+        }
+        for (i, param) in attrs.parameter_attributes.iter().enumerate() {
+            let ops = self.parameter_attribute_ops(param)?;
+            if !ops.is_empty() {
+                groups.push(self.attribute_group(i as u64 + 1, ops));
+            }
+        }
+        if groups.is_empty() {
+            return Ok(0);
+        }
+        if let Some(&id) = self.attribute_list_ids.get(&groups) {
+            return Ok(id);
+        }
+        self.attribute_lists.push(groups.clone());
+        let id = self.attribute_lists.len() as u64;
+        self.attribute_list_ids.insert(groups, id);
+        Ok(id)
+    }
+
+    fn enum_attribute(ops: &mut Vec<u64>, name: &str) {
+        if let Some(code) = attribute_kind_code(name) {
+            ops.extend([ATTR_KIND_ENUM, code]);
+        }
+    }
+
+    fn int_attribute(ops: &mut Vec<u64>, name: &str, value: u64) {
+        if let Some(code) = attribute_kind_code(name) {
+            ops.extend([ATTR_KIND_INT, code, value]);
+        }
+    }
+
+    fn function_attribute_ops(&self, attrs: &FunctionAttributes) -> Vec<u64> {
+        let flags = [
+            ("noinline", attrs.noinline), ("alwaysinline", attrs.alwaysinline),
+            ("inlinehint", attrs.inlinehint), ("optsize", attrs.optsize), ("optnone", attrs.optnone),
+            ("minsize", attrs.minsize), ("noreturn", attrs.noreturn), ("nounwind", attrs.nounwind),
+            ("norecurse", attrs.norecurse), ("willreturn", attrs.willreturn), ("nosync", attrs.nosync),
+            ("readnone", attrs.readnone), ("readonly", attrs.readonly), ("writeonly", attrs.writeonly),
+            ("argmemonly", attrs.argmemonly), ("speculatable", attrs.speculatable),
+            ("returns_twice", attrs.returns_twice), ("ssp", attrs.ssp), ("sspreq", attrs.sspreq),
+            ("sspstrong", attrs.sspstrong), ("uwtable", attrs.uwtable), ("cold", attrs.cold),
+            ("hot", attrs.hot), ("naked", attrs.naked), ("builtin", attrs.builtin),
+            ("immarg", attrs.has_immarg),
+        ];
+        let mut ops = Vec::new();
+        let mut written: Vec<&str> = Vec::new();
+        for (name, set) in flags {
+            if set {
+                Self::enum_attribute(&mut ops, name);
+                written.push(name);
+            }
+        }
+        if let Some(args) = &attrs.allocsize {
+            // Element size argument in the high word, optional count argument in the low word
+            let size = args.first().copied().unwrap_or(0) as u64;
+            let count = args.get(1).map_or(0xFFFF_FFFF, |&c| c as u64);
+            Self::int_attribute(&mut ops, "allocsize", size << 32 | count);
+        }
+        if let Some((min, max)) = attrs.vscale_range {
+            Self::int_attribute(&mut ops, "vscale_range", (min as u64) << 32 | max as u64);
+        }
+        if let Some(kinds) = &attrs.allockind {
+            let names = ["alloc", "realloc", "free", "uninitialized", "zeroed", "aligned"];
+            let bits = kinds.iter().filter_map(|k| names.iter().position(|n| n == k)).fold(0, |acc, bit| acc | 1 << bit);
+            Self::int_attribute(&mut ops, "allockind", bits);
+        }
+        for name in &attrs.other_attributes {
+            if !written.contains(&name.as_str()) {
+                Self::enum_attribute(&mut ops, name);
+                written.push(name);
+            }
+        }
+        let mut strings: Vec<(&String, &String)> = attrs.string_attributes.iter().collect();
+        strings.sort();
+        for (key, value) in strings {
+            if value.is_empty() && attribute_kind_code(key).is_some() {
+                // Attribute groups keep bare keywords as keys without values
+                if !written.contains(&key.as_str()) {
+                    Self::enum_attribute(&mut ops, key);
+                    written.push(key);
+                }
+            } else if value.is_empty() {
+                ops.push(ATTR_KIND_STRING);
+                ops.extend(string_ops(key));
+                ops.push(0);
+            } else {
+                ops.push(ATTR_KIND_STRING_VALUE);
+                ops.extend(string_ops(key));
+                ops.push(0);
+                ops.extend(string_ops(value));
+                ops.push(0);
+            }
+        }
+        ops
+    }
+
+    fn return_attribute_ops(attrs: &ReturnAttributes) -> Vec<u64> {
+        let flags = [
+            ("zeroext", attrs.zeroext), ("signext", attrs.signext), ("inreg", attrs.inreg),
+            ("noalias", attrs.noalias), ("nonnull", attrs.nonnull), ("noundef", attrs.noundef),
+            ("swifterror", attrs.swifterror), ("immarg", attrs.has_immarg),
+        ];
+        let mut ops = Vec::new();
+        for (name, set) in flags {
+            if set {
+                Self::enum_attribute(&mut ops, name);
+            }
+        }
+        if let Some(bytes) = attrs.dereferenceable {
+            Self::int_attribute(&mut ops, "dereferenceable", bytes);
+        }
+        if let Some(align) = attrs.align {
+            Self::int_attribute(&mut ops, "align", align as u64);
+        }
+        ops
+    }
+
+    fn parameter_attribute_ops(&mut self, attrs: &ParameterAttributes) -> BitcodeResult<Vec<u64>> {
+        let flags = [
+            ("zeroext", attrs.zeroext), ("signext", attrs.signext), ("inreg", attrs.inreg),
+            ("noalias", attrs.noalias), ("nocapture", attrs.nocapture), ("nest", attrs.nest),
+            ("returned", attrs.returned), ("nonnull", attrs.nonnull), ("swiftself", attrs.swiftself),
+            ("swifterror", attrs.swifterror), ("swiftasync", attrs.swiftasync), ("immarg", attrs.immarg),
+            ("dead_on_return", attrs.dead_on_return), ("dead_on_unwind", attrs.dead_on_unwind),
+            ("writable", attrs.writable), ("readonly", attrs.readonly), ("readnone", attrs.readnone),
+        ];
+        let mut ops = Vec::new();
+        for (name, set) in flags {
+            if set {
+                Self::enum_attribute(&mut ops, name);
+            }
+        }
+        if let Some(bytes) = attrs.dereferenceable {
+            Self::int_attribute(&mut ops, "dereferenceable", bytes);
+        }
+        if let Some(align) = attrs.align {
+            Self::int_attribute(&mut ops, "align", align as u64);
+        }
+        let typed = [("byval", &attrs.byval), ("inalloca", &attrs.inalloca), ("byref", &attrs.byref), ("sret", &attrs.sret)];
+        for (name, ty) in typed {
+            if let (Some(ty), Some(code)) = (ty, attribute_kind_code(name)) {
+                ops.extend([ATTR_KIND_TYPE_VALUE, code, self.type_id(ty)? as u64]);
+            }
+        }
+        Ok(ops)
+    }
+
+    fn write_attributes(&self, w: &mut BitstreamWriter) {
+        if self.attribute_lists.is_empty() {
+            return;
+        }
+        w.enter_subblock(PARAMATTR_GROUP_BLOCK_ID, 3);
+        for (i, (index, attrs)) in self.attribute_groups.iter().enumerate() {
+            let mut ops = vec![i as u64 + 1, *index];
+            ops.extend(attrs);
+            w.emit_record(PARAMATTR_GRP_CODE_ENTRY, &ops);
+        }
+        w.exit_block();
+        w.enter_subblock(PARAMATTR_BLOCK_ID, 3);
+        for groups in &self.attribute_lists {
+            w.emit_record(PARAMATTR_CODE_ENTRY, groups);
+        }
+        w.exit_block();
+    }
+
+    // Function bodies
+
+    /// Function type of the callee of a call-like instruction
+    fn callee_type(&self, inst: &Instruction, callee: &Value, args: &[Value]) -> Type {
+        if let Some(function) = callee.name().and_then(|n| self.globals.get(n)).and_then(|g| g.function.as_ref()) {
+            if is_global(callee) {
+                return function.get_type();
+            }
+        }
+        let ty = callee.get_type();
+        if ty.is_function() {
+            return ty.clone();
+        }
+        if let Some(pointee) = ty.pointee_type().filter(|p| p.is_function()) {
+            return pointee.clone();
+        }
+        let ret = inst.result().map_or_else(|| self.context.void_type(), |r| r.get_type().clone());
+        self.context.function_type(ret, args.iter().map(|a| a.get_type().clone()).collect(), false)
+    }
+
+    /// Operands of a call-like instruction: callee, arguments, trailing labels
+    fn call_parts(inst: &Instruction) -> (Option<&Value>, &[Value], &[Value]) {
+        let ops = inst.operands();
+        let labels = match inst.opcode() {
+            Opcode::Invoke => 2.min(ops.len().saturating_sub(1)),
+            Opcode::CallBr => ops.iter().rev().take_while(|v| matches!(v.kind(), ValueKind::BasicBlock)).count(),
+            _ => 0,
+        };
+        match ops.split_first() {
+            Some((callee, rest)) => {
+                let (args, labels) = rest.split_at(rest.len() - labels.min(rest.len()));
+                (Some(callee), args, labels)
+            }
+            None => (None, &[], &[]),
+        }
+    }
+
+    /// Type of the value `inst` defines, if it defines one
+    fn defined_type(&self, inst: &Instruction) -> Option<Type> {
+        match inst.opcode() {
+            Opcode::Call | Opcode::Invoke | Opcode::CallBr => {
+                let (callee, args, _) = Self::call_parts(inst);
+                let ret = callee.and_then(|c| self.callee_type(inst, c, args).function_return_type());
+                match (inst.result(), ret) {
+                    (_, Some(ret)) if ret.is_void() => None,
+                    (Some(result), _) => Some(result.get_type().clone()),
+                    (None, ret) => ret,
+                }
+            }
+            Opcode::Ret | Opcode::Br | Opcode::CondBr | Opcode::Switch | Opcode::IndirectBr | Opcode::Resume
+            | Opcode::Unreachable | Opcode::CleanupRet | Opcode::CatchRet | Opcode::Store | Opcode::Fence => None,
+            Opcode::CatchSwitch | Opcode::CatchPad | Opcode::CleanupPad => Some(self.context.token_type()),
+            _ => inst.result().map(|r| r.get_type().clone()),
+        }
+    }
+
+    fn plan_function(&mut self, function: &Function, value_id: u32) -> BitcodeResult<FunctionBody> {
+        let base = self.global_count + self.constants.records.len() as u32;
+        self.locals = LocalValues::default();
+        let blocks = function.basic_blocks();
+        for (index, block) in blocks.iter().enumerate() {
+            if let Some(name) = block.name() {
+                self.locals.blocks.insert(name, index as u64);
+            }
+        }
+        let mut symbols = Vec::new();
+        let (_, params, _) = function.get_type().function_info()
+            .ok_or_else(|| invalid("function", format!("@{} does not have function type", function.name())))?;
+        let args = function.arguments();
+        for (index, ty) in params.iter().enumerate() {
+            let id = base + index as u32;
+            self.locals.args.push(id);
+            self.locals.types.insert(id, ty.clone());
+            if let Some(arg) = args.get(index) {
+                self.locals.bind(arg, id, ty.clone());
+                if let Some(name) = arg.name().filter(|n| !is_all_digits(n)) {
+                    symbols.push((VST_CODE_ENTRY, id as u64, name.to_string()));
+                }
+            }
+        }
+
+        // Constants are numbered before any instruction result
+        self.local_constants = ConstantTable::new(base + params.len() as u32);
+        let insts: Vec<Instruction> = blocks.iter().flat_map(|b| b.instructions()).collect();
+        for inst in &insts {
+            self.number_local_constants(inst)?;
+        }
+        self.local_constants.sealed = true;
+
+        let mut next = self.local_constants.next_id();
+        let mut ids = Vec::new();
+        for inst in &insts {
+            match self.defined_type(inst) {
+                Some(ty) => {
+                    if let Some(result) = inst.result() {
+                        self.locals.bind(result, next, ty.clone());
+                        if let Some(name) = result.name().filter(|n| !is_all_digits(n)) {
+                            symbols.push((VST_CODE_ENTRY, next as u64, name.to_string()));
+                        }
+                    } else {
+                        self.locals.types.insert(next, ty);
+                    }
+                    ids.push(next);
+                    next += 1;
+                }
+                None => ids.push(next),
+            }
+        }
+        for (index, block) in blocks.iter().enumerate() {
+            if let Some(name) = block.name().filter(|n| !is_all_digits(n)) {
+                symbols.push((VST_CODE_BBENTRY, index as u64, name));
+            }
+        }
+
+        let module_metadata = self.metadata.len();
+        let mut attachments = Vec::new();
+        if let Some(subprogram) = function.subprogram() {
+            // [(kind, node)...]
+            let kind = self.metadata_kind("dbg");
+            let md = self.metadata_node(&subprogram)?;
+            attachments.push(vec![kind, self.metadata.id(md)]);
+        }
+        let mut records = Vec::new();
+        let mut last_location = None;
+        for (index, (inst, id)) in insts.iter().zip(ids).enumerate() {
+            self.encode_instruction(inst, id, &mut records)?;
+            let location = match inst.debug_loc() {
+                Some(location) => self.debug_loc_ops(location)?,
+                None => None,
+            };
+            if let Some(ops) = location {
+                if last_location.as_ref() == Some(&ops) {
+                    records.push(InstRecord::new(FUNC_CODE_DEBUG_LOC_AGAIN, Vec::new()));
+                } else {
+                    records.push(InstRecord::new(FUNC_CODE_DEBUG_LOC, ops.clone()));
+                    last_location = Some(ops);
+                }
+            }
+            // [instruction, (kind, node)...]
+            let mut ops = vec![index as u64];
+            for (kind, node) in inst.attached_metadata().iter().filter(|(kind, _)| kind != "dbg") {
+                let kind = self.metadata_kind(kind);
+                let md = self.metadata_node(node)?;
+                ops.extend([kind, self.metadata.id(md)]);
+            }
+            if ops.len() > 1 {
+                attachments.push(ops);
+            }
+        }
+        if self.metadata.len() != module_metadata {
+            return Err(invalid("metadata", format!("@{} refers to metadata the module did not number", function.name())));
+        }
+        Ok(FunctionBody {
+            value_id,
+            block_count: blocks.len(),
+            constants: std::mem::take(&mut self.local_constants),
+            records,
+            symbols,
+            metadata: std::mem::take(&mut self.locals.metadata),
+            attachments,
+        })
+    }
+
+    fn number_local_constants(&mut self, inst: &Instruction) -> BitcodeResult<()> {
+        let ops = inst.operands();
+        let ops = match inst.opcode() {
+            // Indices are written as literals
+            Opcode::ExtractValue => &ops[..ops.len().min(1)],
+            Opcode::InsertValue => &ops[..ops.len().min(2)],
+            _ => ops,
+        };
+        let bundle_inputs = inst.operand_bundles().iter().flat_map(|b| b.inputs.iter());
+        for op in ops.iter().chain(bundle_inputs) {
+            if is_constant(op) && !op.get_type().is_metadata() {
+                self.constant_id(op, true)?;
+            }
+        }
+        match inst.opcode() {
+            Opcode::Alloca if ops.is_empty() => {
+                self.constant_id(&Value::const_int(self.context.int32_type(), 1, None), true)?;
+            }
+            Opcode::CatchSwitch | Opcode::CatchPad | Opcode::CleanupPad
+                if ops.first().is_none_or(|op| matches!(op.kind(), ValueKind::BasicBlock)) =>
+            {
+                self.constant_id(&Value::undef(self.context.token_type()), true)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn block_index(&self, value: &Value) -> BitcodeResult<u64> {
+        let name = value.name().unwrap_or_default();
+        self.locals.blocks.get(name).copied()
+            .ok_or_else(|| invalid("function", format!("branch to undefined block %{}", name)))
+    }
+
+    fn local_type_id(&mut self, id: u32, value: &Value) -> BitcodeResult<u64> {
+        let ty = self.locals.types.get(&id).cloned();
+        Ok(match ty {
+            Some(ty) => self.type_id(&ty)?,
+            None => self.value_type_id(value)?,
+        } as u64)
+    }
+
+    /// Push a relative value ID, followed by its type for a forward reference
+    fn push_value_and_type(&mut self, ops: &mut Vec<u64>, value: &Value, inst_id: u32) -> BitcodeResult<bool> {
+        let id = self.operand_id(value, true)?;
+        ops.push(inst_id.wrapping_sub(id) as u64);
+        if id >= inst_id {
+            ops.push(self.local_type_id(id, value)?);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    fn push_value(&mut self, ops: &mut Vec<u64>, value: &Value, inst_id: u32) -> BitcodeResult<()> {
+        let id = self.operand_id(value, true)?;
+        ops.push(inst_id.wrapping_sub(id) as u64);
+        Ok(())
+    }
+
+    fn push_call_arguments(&mut self, ops: &mut Vec<u64>, fn_ty: &Type, args: &[Value], inst_id: u32) -> BitcodeResult<()> {
+        let (_, params, _) = fn_ty.function_info().unwrap_or((self.context.void_type(), Vec::new(), true));
+        if args.len() < params.len() {
+            return Err(invalid("function", format!("call passes {} arguments to {}", args.len(), fn_ty)));
+        }
+        for (arg, param) in args.iter().zip(&params) {
+            if param.is_label() {
+                ops.push(self.block_index(arg)?);
+            } else if param.is_metadata() {
+                // Metadata arguments name a metadata ID rather than a value
+                let md = self.metadata_argument(arg)?;
+                ops.push(inst_id.wrapping_sub(md as u32) as u64);
+            } else {
+                self.push_value(ops, arg, inst_id)?;
+            }
+        }
+        for arg in &args[params.len()..] {
+            self.push_value_and_type(ops, arg, inst_id)?;
+        }
+        Ok(())
+    }
+
+    /// Metadata ID of a metadata call argument
+    fn metadata_argument(&mut self, arg: &Value) -> BitcodeResult<u64> {
+        let md = match (arg.as_metadata_string(), arg.as_metadata()) {
+            (Some(s), _) => self.metadata.string(s),
+            (_, Some(node)) if is_local_metadata(node) => return self.local_metadata(node),
+            (_, Some(node)) => self.metadata_node(node)?,
+            _ => self.metadata.empty_node(),
+        };
+        Ok(self.metadata.id(md))
+    }
+
+    /// Metadata ID of a local value wrapped as metadata, or of a DIArgList of them
+    fn local_metadata(&mut self, md: &Metadata) -> BitcodeResult<u64> {
+        if let Some(value) = md.as_value() {
+            let id = self.operand_id(value, true)?;
+            if let Some(&md) = self.locals.metadata_values.get(&id) {
+                return Ok(md);
+            }
+            // [ty, value]
+            let ty = self.local_type_id(id, value)?;
+            let md = self.push_local_metadata(METADATA_VALUE, vec![ty, id as u64]);
+            self.locals.metadata_values.insert(id, md);
+            return Ok(md);
+        }
+        let mut ops = Vec::new();
+        for op in md.operands().into_iter().flatten() {
+            ops.push(if is_local_metadata(op) {
+                self.local_metadata(op)?
+            } else {
+                let md = self.metadata_node(op)?;
+                self.metadata.id(md)
+            });
+        }
+        Ok(self.push_local_metadata(METADATA_ARG_LIST, ops))
+    }
+
+    fn push_local_metadata(&mut self, code: u32, ops: Vec<u64>) -> u64 {
+        self.locals.metadata.push((code, ops));
+        self.metadata.len() + self.locals.metadata.len() as u64 - 1
+    }
+
+    fn bundle_tag(&mut self, tag: &str) -> u64 {
+        Self::index_of(&mut self.bundle_tags, tag)
+    }
+
+    fn encode_instruction(&mut self, inst: &Instruction, id: u32, records: &mut Vec<InstRecord>) -> BitcodeResult<()> {
+        let operands = inst.operands();
+        let operand = |i: usize| operands.get(i)
+            .ok_or_else(|| invalid("function", format!("{:?} is missing operand {}", inst.opcode(), i)));
+        let result_ty = || inst.result().map(|r| r.get_type().clone())
+            .ok_or_else(|| invalid("function", format!("{:?} has no result", inst.opcode())));
+        let mut ops = Vec::new();
+        let opcode = inst.opcode();
+
+        if let Some(code) = encode_binop(opcode) {
+            // [opval, ty, opval, opcode]
+            let forward = self.push_value_and_type(&mut ops, operand(0)?, id)?;
+            self.push_value(&mut ops, operand(1)?, id)?;
+            ops.push(code);
+            let abbrev = (!forward).then_some(FUNCTION_INST_BINOP_ABBREV);
+            records.push(InstRecord::abbreviated(FUNC_CODE_INST_BINOP, ops, abbrev));
+            return Ok(());
+        }
+        if let Some(code) = encode_cast(opcode) {
+            // [opval, opty, destty, castopc]
+            let forward = self.push_value_and_type(&mut ops, operand(0)?, id)?;
+            ops.push(self.type_id(&result_ty()?)? as u64);
+            ops.push(code);
+            let abbrev = (!forward).then_some(FUNCTION_INST_CAST_ABBREV);
+            records.push(InstRecord::abbreviated(FUNC_CODE_INST_CAST, ops, abbrev));
+            return Ok(());
+        }
+
+        let record = match opcode {
+            Opcode::FNeg => {
+                self.push_value_and_type(&mut ops, operand(0)?, id)?;
+                ops.push(0);
+                InstRecord::new(FUNC_CODE_INST_UNOP, ops)
+            }
+            Opcode::Freeze => {
+                self.push_value_and_type(&mut ops, operand(0)?, id)?;
+                InstRecord::new(FUNC_CODE_INST_FREEZE, ops)
+            }
+            Opcode::GetElementPtr => {
+                // [inbounds, srcty, (opval, ty)...]
+                let base = operand(0)?;
+                let source = match inst.gep_source_type() {
+                    Some(ty) => ty.clone(),
+                    None => base.get_type().pointee_type().cloned().unwrap_or_else(|| self.context.int8_type()),
+                };
+                ops.push(0);
+                ops.push(self.type_id(&source)? as u64);
+                for op in operands {
+                    self.push_value_and_type(&mut ops, op, id)?;
+                }
+                InstRecord::abbreviated(FUNC_CODE_INST_GEP, ops, Some(FUNCTION_INST_GEP_ABBREV))
+            }
+            Opcode::Select => {
+                // [tval, ty, fval, cond, condty]
+                self.push_value_and_type(&mut ops, operand(1)?, id)?;
+                self.push_value(&mut ops, operand(2)?, id)?;
+                self.push_value_and_type(&mut ops, operand(0)?, id)?;
+                InstRecord::new(FUNC_CODE_INST_VSELECT, ops)
+            }
+            Opcode::ExtractElement => {
+                self.push_value_and_type(&mut ops, operand(0)?, id)?;
+                self.push_value_and_type(&mut ops, operand(1)?, id)?;
+                InstRecord::new(FUNC_CODE_INST_EXTRACTELT, ops)
+            }
+            Opcode::InsertElement => {
+                self.push_value_and_type(&mut ops, operand(0)?, id)?;
+                self.push_value(&mut ops, operand(1)?, id)?;
+                self.push_value_and_type(&mut ops, operand(2)?, id)?;
+                InstRecord::new(FUNC_CODE_INST_INSERTELT, ops)
+            }
+            Opcode::ShuffleVector => {
+                self.push_value_and_type(&mut ops, operand(0)?, id)?;
+                self.push_value(&mut ops, operand(1)?, id)?;
+                self.push_value_and_type(&mut ops, operand(2)?, id)?;
+                InstRecord::new(FUNC_CODE_INST_SHUFFLEVEC, ops)
+            }
+            Opcode::ICmp | Opcode::FCmp => {
+                // [opval, ty, opval, pred]
+                let predicate = match opcode {
+                    Opcode::ICmp => inst.int_predicate().map(encode_int_predicate),
+                    _ => inst.float_predicate().map(encode_float_predicate),
+                };
+                let predicate = predicate.ok_or_else(|| invalid("function", "comparison without a predicate"))?;
+                self.push_value_and_type(&mut ops, operand(0)?, id)?;
+                self.push_value(&mut ops, operand(1)?, id)?;
+                ops.push(predicate);
+                InstRecord::new(FUNC_CODE_INST_CMP2, ops)
+            }
+            Opcode::Ret => match operands.first() {
+                None => InstRecord::abbreviated(FUNC_CODE_INST_RET, ops, Some(FUNCTION_INST_RET_VOID_ABBREV)),
+                Some(value) => {
+                    let forward = self.push_value_and_type(&mut ops, value, id)?;
+                    let abbrev = (!forward).then_some(FUNCTION_INST_RET_VAL_ABBREV);
+                    InstRecord::abbreviated(FUNC_CODE_INST_RET, ops, abbrev)
+                }
+            },
+            Opcode::Br => {
+                ops.push(self.block_index(operand(0)?)?);
+                InstRecord::new(FUNC_CODE_INST_BR, ops)
+            }
+            Opcode::CondBr => {
+                // [truebb, falsebb, cond]
+                ops.push(self.block_index(operand(1)?)?);
+                ops.push(self.block_index(operand(2)?)?);
+                self.push_value(&mut ops, operand(0)?, id)?;
+                InstRecord::new(FUNC_CODE_INST_BR, ops)
+            }
+            Opcode::Switch => {
+                // [opty, cond, defaultbb, (caseval, bb)...]; case values are absolute IDs
+                let cond = operand(0)?;
+                ops.push(self.value_type_id(cond)? as u64);
+                self.push_value(&mut ops, cond, id)?;
+                ops.push(self.block_index(operand(1)?)?);
+                for case in operands[2..].chunks(2) {
+                    ops.push(self.operand_id(&case[0], true)? as u64);
+                    let bb = case.get(1).ok_or_else(|| invalid("function", "switch case without a destination"))?;
+                    ops.push(self.block_index(bb)?);
+                }
+                InstRecord::new(FUNC_CODE_INST_SWITCH, ops)
+            }
+            Opcode::IndirectBr => {
+                // [opty, addr, bb...]
+                let address = operand(0)?;
+                ops.push(self.value_type_id(address)? as u64);
+                self.push_value(&mut ops, address, id)?;
+                for bb in &operands[1..] {
+                    ops.push(self.block_index(bb)?);
+                }
+                InstRecord::new(FUNC_CODE_INST_INDIRECTBR, ops)
+            }
+            Opcode::Call | Opcode::Invoke | Opcode::CallBr => {
+                self.encode_bundles(inst, id, records)?;
+                let (callee, args, labels) = Self::call_parts(inst);
+                let callee = callee.ok_or_else(|| invalid("function", format!("{:?} without a callee", opcode)))?;
+                let fn_ty = self.callee_type(inst, callee, args);
+                let cc = match callee.name().and_then(|n| self.globals.get(n)).and_then(|g| g.function.as_ref()) {
+                    Some(function) if is_global(callee) => encode_calling_convention(function.calling_convention())?,
+                    _ => 0,
+                };
+                ops.push(0);
+                let code = match opcode {
+                    Opcode::Call => {
+                        // [attrs, cc|explicit<<15, fnty, callee, args...]
+                        ops.push(cc << 1 | 1 << 15);
+                        FUNC_CODE_INST_CALL
+                    }
+                    Opcode::Invoke => {
+                        // [attrs, cc|explicit<<13, normalbb, unwindbb, fnty, callee, args...]
+                        ops.push(cc | 1 << 13);
+                        if labels.len() != 2 {
+                            return Err(invalid("function", "invoke without normal and unwind destinations"));
+                        }
+                        ops.push(self.block_index(&labels[0])?);
+                        ops.push(self.block_index(&labels[1])?);
+                        FUNC_CODE_INST_INVOKE
+                    }
+                    _ => {
+                        // [attrs, cc|explicit<<15, defaultbb, numindirect, indirectbb..., fnty, callee, args...]
+                        ops.push(cc | 1 << 15);
+                        let (default, indirect) = labels.split_first()
+                            .ok_or_else(|| invalid("function", "callbr without a default destination"))?;
+                        ops.push(self.block_index(default)?);
+                        ops.push(indirect.len() as u64);
+                        for bb in indirect {
+                            ops.push(self.block_index(bb)?);
+                        }
+                        FUNC_CODE_INST_CALLBR
+                    }
+                };
+                ops.push(self.type_id(&fn_ty)? as u64);
+                self.push_value_and_type(&mut ops, callee, id)?;
+                self.push_call_arguments(&mut ops, &fn_ty, args, id)?;
+                InstRecord::new(code, ops)
+            }
+            Opcode::Resume => {
+                self.push_value_and_type(&mut ops, operand(0)?, id)?;
+                InstRecord::new(FUNC_CODE_INST_RESUME, ops)
+            }
+            Opcode::Unreachable => {
+                InstRecord::abbreviated(FUNC_CODE_INST_UNREACHABLE, ops, Some(FUNCTION_INST_UNREACHABLE_ABBREV))
+            }
+            Opcode::CleanupRet => {
+                // [cleanuppad, (unwindbb)]
+                self.push_value(&mut ops, operand(0)?, id)?;
+                if let Some(bb) = operands.get(1) {
+                    ops.push(self.block_index(bb)?);
+                }
+                InstRecord::new(FUNC_CODE_INST_CLEANUPRET, ops)
+            }
+            Opcode::CatchRet => {
+                // [catchpad, bb]
+                self.push_value(&mut ops, operand(0)?, id)?;
+                ops.push(self.block_index(operand(1)?)?);
+                InstRecord::new(FUNC_CODE_INST_CATCHRET, ops)
+            }
+            Opcode::CatchSwitch => {
+                // [parentpad, numhandlers, handlers...]
+                let (parent, handlers) = self.pad_parent(operands);
+                self.push_value(&mut ops, &parent, id)?;
+                if handlers.is_empty() {
+                    return Err(invalid("function", "catchswitch without handlers"));
+                }
+                ops.push(handlers.len() as u64);
+                for bb in handlers {
+                    ops.push(self.block_index(bb)?);
+                }
+                InstRecord::new(FUNC_CODE_INST_CATCHSWITCH, ops)
+            }
+            Opcode::CatchPad | Opcode::CleanupPad => {
+                // [parentpad, numargs, (val, ty)...]
+                let (parent, args) = self.pad_parent(operands);
+                self.push_value(&mut ops, &parent, id)?;
+                ops.push(args.len() as u64);
+                for arg in args {
+                    self.push_value_and_type(&mut ops, arg, id)?;
+                }
+                let code = if opcode == Opcode::CatchPad { FUNC_CODE_INST_CATCHPAD } else { FUNC_CODE_INST_CLEANUPPAD };
+                InstRecord::new(code, ops)
+            }
+            Opcode::PHI => {
+                // [ty, (val, bb)...]; values are signed relative IDs
+                ops.push(self.type_id(&result_ty()?)? as u64);
+                for pair in operands.chunks(2) {
+                    let value = self.operand_id(&pair[0], true)?;
+                    ops.push(encode_signed(id as i64 - value as i64));
+                    let bb = pair.get(1).ok_or_else(|| invalid("function", "phi value without a block"))?;
+                    ops.push(self.block_index(bb)?);
+                }
+                InstRecord::new(FUNC_CODE_INST_PHI, ops)
+            }
+            Opcode::LandingPad => {
+                // [ty, iscleanup, nclauses, (clausetype, val, ty)...]; filters are arrays
+                ops.push(self.type_id(&result_ty()?)? as u64);
+                ops.push(operands.is_empty() as u64);
+                ops.push(operands.len() as u64);
+                for clause in operands {
+                    ops.push(clause.get_type().is_array() as u64);
+                    self.push_value_and_type(&mut ops, clause, id)?;
+                }
+                InstRecord::new(FUNC_CODE_INST_LANDINGPAD, ops)
+            }
+            Opcode::Alloca => {
+                // [instty, opty, op, align]; the size is an absolute ID
+                let allocated = result_ty()?.pointee_type().cloned()
+                    .ok_or_else(|| invalid("function", "alloca result is not a pointer"))?;
+                let size = match operands.first() {
+                    Some(size) => size.clone(),
+                    None => Value::const_int(self.context.int32_type(), 1, None),
+                };
+                ops.push(self.type_id(&allocated)? as u64);
+                ops.push(self.value_type_id(&size)? as u64);
+                ops.push(self.operand_id(&size, true)? as u64);
+                let align = encode_alignment(inst.alignment());
+                // Alignment split around the inalloca, explicit type and swifterror bits
+                ops.push((align & 0x1F) | (inst.is_inalloca() as u64) << 5 | 1 << 6
+                    | (inst.is_swifterror() as u64) << 7 | (align >> 5) << 8);
+                InstRecord::new(FUNC_CODE_INST_ALLOCA, ops)
+            }
+            Opcode::Load => {
+                // [op, ty, align, vol, (ordering, ssid)]
+                let ptr = operand(0)?;
+                let forward = self.push_value_and_type(&mut ops, ptr, id)?;
+                let ty = match inst.result() {
+                    Some(result) => result.get_type().clone(),
+                    None => ptr.get_type().pointee_type().cloned()
+                        .ok_or_else(|| invalid("function", "load from a non-pointer"))?,
+                };
+                ops.push(self.type_id(&ty)? as u64);
+                ops.push(encode_alignment(inst.alignment()));
+                ops.push(inst.is_volatile() as u64);
+                if inst.is_atomic() {
+                    ops.push(encode_ordering(inst.atomic_ordering().unwrap_or(AtomicOrdering::SequentiallyConsistent)));
+                    ops.push(1);
+                    InstRecord::new(FUNC_CODE_INST_LOADATOMIC, ops)
+                } else {
+                    let abbrev = (!forward).then_some(FUNCTION_INST_LOAD_ABBREV);
+                    InstRecord::abbreviated(FUNC_CODE_INST_LOAD, ops, abbrev)
+                }
+            }
+            Opcode::Store => {
+                // [ptr, val, align, vol, (ordering, ssid)]
+                self.push_value_and_type(&mut ops, operand(1)?, id)?;
+                self.push_value_and_type(&mut ops, operand(0)?, id)?;
+                ops.push(encode_alignment(inst.alignment()));
+                ops.push(inst.is_volatile() as u64);
+                if inst.is_atomic() {
+                    ops.push(encode_ordering(inst.atomic_ordering().unwrap_or(AtomicOrdering::SequentiallyConsistent)));
+                    ops.push(1);
+                    InstRecord::new(FUNC_CODE_INST_STOREATOMIC, ops)
+                } else {
+                    InstRecord::new(FUNC_CODE_INST_STORE, ops)
+                }
+            }
+            Opcode::AtomicCmpXchg => {
+                // [ptr, cmp, new, vol, success, ssid, failure, weak, align]
+                let success = inst.atomic_ordering().unwrap_or(AtomicOrdering::SequentiallyConsistent);
+                let failure = match success {
+                    AtomicOrdering::AcquireRelease => AtomicOrdering::Acquire,
+                    AtomicOrdering::Release => AtomicOrdering::Monotonic,
+                    other => other,
+                };
+                self.push_value_and_type(&mut ops, operand(0)?, id)?;
+                self.push_value_and_type(&mut ops, operand(1)?, id)?;
+                self.push_value(&mut ops, operand(2)?, id)?;
+                ops.extend([
+                    inst.is_volatile() as u64, encode_ordering(success), 1, encode_ordering(failure), 0,
+                    encode_alignment(inst.alignment()),
+                ]);
+                InstRecord::new(FUNC_CODE_INST_CMPXCHG, ops)
+            }
+            Opcode::AtomicRMW => {
+                // [ptr, val, op, vol, ordering, ssid, align]
+                let operation = inst.rmw_operation()
+                    .ok_or_else(|| invalid("function", "atomicrmw without an operation"))?;
+                self.push_value_and_type(&mut ops, operand(0)?, id)?;
+                self.push_value_and_type(&mut ops, operand(1)?, id)?;
+                let ordering = inst.atomic_ordering().unwrap_or(AtomicOrdering::SequentiallyConsistent);
+                ops.extend([encode_rmw_operation(operation), inst.is_volatile() as u64, encode_ordering(ordering), 1, encode_alignment(inst.alignment())]);
+                InstRecord::new(FUNC_CODE_INST_ATOMICRMW, ops)
+            }
+            Opcode::Fence => {
+                // [ordering, ssid]
+                let ordering = inst.atomic_ordering().unwrap_or(AtomicOrdering::SequentiallyConsistent);
+                InstRecord::new(FUNC_CODE_INST_FENCE, vec![encode_ordering(ordering), 1])
+            }
+            Opcode::VAArg => {
+                // [valistty, valist, resty]
+                let list = operand(0)?;
+                ops.push(self.value_type_id(list)? as u64);
+                self.push_value(&mut ops, list, id)?;
+                ops.push(self.type_id(&result_ty()?)? as u64);
+                InstRecord::new(FUNC_CODE_INST_VAARG, ops)
+            }
+            Opcode::ExtractValue | Opcode::InsertValue => {
+                // [agg, (val,) idx...]
+                let values = if opcode == Opcode::ExtractValue { 1 } else { 2 };
+                for i in 0..values {
+                    self.push_value_and_type(&mut ops, operand(i)?, id)?;
+                }
+                for index in &operands[values..] {
+                    let index = index.as_const_int()
+                        .ok_or_else(|| invalid("function", format!("{:?} index is not a constant", opcode)))?;
+                    ops.push(index as u64);
+                }
+                let code = if opcode == Opcode::ExtractValue { FUNC_CODE_INST_EXTRACTVAL } else { FUNC_CODE_INST_INSERTVAL };
+                InstRecord::new(code, ops)
+            }
+            _ => return Err(BitcodeError::Unsupported(format!("{:?} instruction", opcode))),
+        };
+        records.push(record);
+        Ok(())
+    }
+
+    /// Parent pad of an EH pad and its remaining operands; `none` when absent
+    fn pad_parent<'v>(&self, operands: &'v [Value]) -> (Value, &'v [Value]) {
+        match operands.split_first() {
+            Some((parent, rest)) if !matches!(parent.kind(), ValueKind::BasicBlock) => (parent.clone(), rest),
+            _ => (Value::undef(self.context.token_type()), operands),
+        }
+    }
+
+    fn encode_bundles(&mut self, inst: &Instruction, id: u32, records: &mut Vec<InstRecord>) -> BitcodeResult<()> {
+        for bundle in inst.operand_bundles() {
+            // [tag, (val, ty)...]
+            let mut ops = vec![self.bundle_tag(&bundle.tag)];
+            for input in &bundle.inputs {
+                self.push_value_and_type(&mut ops, input, id)?;
+            }
+            records.push(InstRecord::new(FUNC_CODE_OPERAND_BUNDLE, ops));
+        }
+        Ok(())
+    }
+
+    fn write_function(&self, w: &mut BitstreamWriter, body: &FunctionBody) {
+        w.enter_subblock(FUNCTION_BLOCK_ID, 4);
+        w.emit_record(FUNC_CODE_DECLAREBLOCKS, &[body.block_count as u64]);
+        self.write_constants(w, &body.constants);
+        if !body.metadata.is_empty() {
+            w.enter_subblock(METADATA_BLOCK_ID, 3);
+            for (code, ops) in &body.metadata {
+                w.emit_record(*code, ops);
+            }
+            w.exit_block();
+        }
+        for record in &body.records {
+            match record.abbrev {
+                Some(abbrev) => w.emit_record_with_abbrev(abbrev, record.code, &record.ops, None),
+                None => w.emit_record(record.code, &record.ops),
+            }
+        }
+        if !body.symbols.is_empty() {
+            w.enter_subblock(VALUE_SYMTAB_BLOCK_ID, 4);
+            for (code, id, name) in &body.symbols {
+                let mut ops = vec![*id];
+                ops.extend(string_ops(name));
+                let abbrev = if name.bytes().all(|c| encode_char6(c).is_some()) {
+                    if *code == VST_CODE_BBENTRY { VST_BBENTRY_6_ABBREV } else { VST_ENTRY_6_ABBREV }
+                } else if *code == VST_CODE_ENTRY && name.is_ascii() {
+                    VST_ENTRY_7_ABBREV
+                } else {
+                    VST_ENTRY_8_ABBREV
+                };
+                w.emit_record_with_abbrev(abbrev, *code, &ops, None);
+            }
+            w.exit_block();
+        }
+        if !body.attachments.is_empty() {
+            w.enter_subblock(METADATA_ATTACHMENT_ID, 3);
+            for ops in &body.attachments {
+                w.emit_record(METADATA_ATTACHMENT, ops);
+            }
+            w.exit_block();
+        }
+        w.exit_block();
+    }
+
+    // Container
+
+    fn write_identification(&self, w: &mut BitstreamWriter) {
+        w.enter_subblock(IDENTIFICATION_BLOCK_ID, 5);
+        let abbrev = w.define_abbrev(Abbrev { ops: vec![
+            AbbrevOp::Literal(IDENTIFICATION_CODE_STRING as u64), AbbrevOp::Array, AbbrevOp::Fixed(8),
+        ] });
+        w.emit_record_with_abbrev(abbrev, IDENTIFICATION_CODE_STRING, &string_ops("llvm-rust"), None);
+        let abbrev = w.define_abbrev(Abbrev { ops: vec![
+            AbbrevOp::Literal(IDENTIFICATION_CODE_EPOCH as u64), AbbrevOp::Vbr(6),
+        ] });
+        w.emit_record_with_abbrev(abbrev, IDENTIFICATION_CODE_EPOCH, &[BITCODE_CURRENT_EPOCH], None);
+        w.exit_block();
+    }
+
+    fn write_block_info(&self, w: &mut BitstreamWriter) {
+        // Wide enough for any type ID
+        let type_bits = (64 - (self.types.records.len() as u64).leading_zeros()).max(1);
+        let define = |w: &mut BitstreamWriter, expected: u32, ops: Vec<AbbrevOp>| {
+            let id = w.define_abbrev(Abbrev { ops });
+            debug_assert_eq!(id, expected);
+        };
+        w.enter_block_info();
+        w.set_block_info_target(VALUE_SYMTAB_BLOCK_ID);
+        define(w, VST_ENTRY_8_ABBREV, vec![AbbrevOp::Fixed(3), AbbrevOp::Vbr(8), AbbrevOp::Array, AbbrevOp::Fixed(8)]);
+        define(w, VST_ENTRY_7_ABBREV, vec![
+            AbbrevOp::Literal(VST_CODE_ENTRY as u64), AbbrevOp::Vbr(8), AbbrevOp::Array, AbbrevOp::Fixed(7),
+        ]);
+        define(w, VST_ENTRY_6_ABBREV, vec![
+            AbbrevOp::Literal(VST_CODE_ENTRY as u64), AbbrevOp::Vbr(8), AbbrevOp::Array, AbbrevOp::Char6,
+        ]);
+        define(w, VST_BBENTRY_6_ABBREV, vec![
+            AbbrevOp::Literal(VST_CODE_BBENTRY as u64), AbbrevOp::Vbr(8), AbbrevOp::Array, AbbrevOp::Char6,
+        ]);
+
+        w.set_block_info_target(CONSTANTS_BLOCK_ID);
+        define(w, CONSTANTS_SETTYPE_ABBREV, vec![AbbrevOp::Literal(CST_CODE_SETTYPE as u64), AbbrevOp::Fixed(type_bits)]);
+        define(w, CONSTANTS_INTEGER_ABBREV, vec![AbbrevOp::Literal(CST_CODE_INTEGER as u64), AbbrevOp::Vbr(8)]);
+        define(w, CONSTANTS_CE_CAST_ABBREV, vec![
+            AbbrevOp::Literal(CST_CODE_CE_CAST as u64), AbbrevOp::Fixed(4), AbbrevOp::Fixed(type_bits), AbbrevOp::Vbr(8),
+        ]);
+        define(w, CONSTANTS_NULL_ABBREV, vec![AbbrevOp::Literal(CST_CODE_NULL as u64)]);
+
+        w.set_block_info_target(FUNCTION_BLOCK_ID);
+        define(w, FUNCTION_INST_LOAD_ABBREV, vec![
+            AbbrevOp::Literal(FUNC_CODE_INST_LOAD as u64), AbbrevOp::Vbr(6), AbbrevOp::Fixed(type_bits),
+            AbbrevOp::Vbr(4), AbbrevOp::Fixed(1),
+        ]);
+        define(w, FUNCTION_INST_BINOP_ABBREV, vec![
+            AbbrevOp::Literal(FUNC_CODE_INST_BINOP as u64), AbbrevOp::Vbr(6), AbbrevOp::Vbr(6), AbbrevOp::Fixed(4),
+        ]);
+        define(w, FUNCTION_INST_CAST_ABBREV, vec![
+            AbbrevOp::Literal(FUNC_CODE_INST_CAST as u64), AbbrevOp::Vbr(6), AbbrevOp::Fixed(type_bits), AbbrevOp::Fixed(4),
+        ]);
+        define(w, FUNCTION_INST_RET_VOID_ABBREV, vec![AbbrevOp::Literal(FUNC_CODE_INST_RET as u64)]);
+        define(w, FUNCTION_INST_RET_VAL_ABBREV, vec![AbbrevOp::Literal(FUNC_CODE_INST_RET as u64), AbbrevOp::Vbr(6)]);
+        define(w, FUNCTION_INST_UNREACHABLE_ABBREV, vec![AbbrevOp::Literal(FUNC_CODE_INST_UNREACHABLE as u64)]);
+        define(w, FUNCTION_INST_GEP_ABBREV, vec![
+            AbbrevOp::Literal(FUNC_CODE_INST_GEP as u64), AbbrevOp::Fixed(1), AbbrevOp::Fixed(type_bits),
+            AbbrevOp::Array, AbbrevOp::Vbr(6),
+        ]);
+        w.exit_block();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_alignment() {
+        assert_eq!(encode_alignment(None), 0);
+        assert_eq!(encode_alignment(Some(1)), 1);
+        assert_eq!(encode_alignment(Some(16)), 5);
+    }
+
+    #[test]
+    fn test_f64_to_half() {
+        assert_eq!(f64_to_half(1.0), 0x3C00);
+        assert_eq!(f64_to_half(-2.0), 0xC000);
+        assert_eq!(f64_to_half(65504.0), 0x7BFF);
+        assert_eq!(f64_to_half(1e6), 0x7C00);
+        assert_eq!(f64_to_half(0.0), 0);
+        // Smallest subnormal
+        assert_eq!(f64_to_half(2f64.powi(-24)), 1);
+        assert_eq!(f64_to_half(f64::NAN) & 0x7C00, 0x7C00);
+    }
+}
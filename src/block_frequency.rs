@@ -0,0 +1,147 @@
+//! Block Frequency Analysis
+//!
+//! Turns branch probabilities into how often each block runs per entry to
+//! the function, like LLVM's BlockFrequencyInfo. Frequencies are found the
+//! way Wu and Larus do it, over the loops of `LoopInfo`: working from the
+//! innermost loop out, the flow from the header around the loop gives the
+//! probability of going around again, and a header runs `1 / (1 - p)`
+//! times for every time the loop is entered. The rest flows forward in
+//! reverse postorder.
+//!
+//! Cycles that are not natural loops have no header to scale; flow along
+//! their retreating edges is dropped.
+
+use crate::analysis::LoopInfo;
+use crate::branch_probability::BranchProbabilityInfo;
+use crate::cfg::CFG;
+use crate::function::Function;
+use crate::passes::{AnalysisPass, Pass, PassResult};
+
+/// Block frequencies of a function, relative to its entry
+pub struct BlockFrequencyInfo {
+    probabilities: BranchProbabilityInfo,
+    frequencies: Vec<f64>,
+}
+
+impl BlockFrequencyInfo {
+    /// Times a loop header may run per entry to the loop, so that a loop
+    /// that never exits still has a finite frequency
+    pub const MAX_LOOP_SCALE: f64 = (1 << 20) as f64;
+
+    /// Block frequencies from the static branch probabilities of a function
+    pub fn new(function: &Function) -> Self {
+        Self::from_branch_probabilities(BranchProbabilityInfo::new(function))
+    }
+
+    /// Block frequencies from branch probabilities and the loops they were
+    /// estimated with
+    pub fn from_branch_probabilities(probabilities: BranchProbabilityInfo) -> Self {
+        let loops = probabilities.loop_info();
+        let cfg = loops.cfg();
+        let rpo = cfg.reverse_postorder();
+        let mut order = vec![usize::MAX; cfg.num_blocks()];
+        for (position, &block) in rpo.iter().enumerate() {
+            order[block] = position;
+        }
+
+        // Inner loops follow the loops enclosing them, so walking the loops
+        // backwards scales every inner header before its outer loop is
+        // walked through
+        let mut scales = vec![1.0; cfg.num_blocks()];
+        for l in loops.loops().iter().rev() {
+            let flow = propagate(&probabilities, &rpo, &order, &scales, l.header, |block| l.contains(block));
+            let around: f64 = l.latches.iter()
+                .map(|&latch| flow[latch] * probabilities.edge_probability(latch, l.header).as_f64())
+                .sum();
+            scales[l.header] = if around >= 1.0 {
+                Self::MAX_LOOP_SCALE
+            } else {
+                (1.0 / (1.0 - around)).min(Self::MAX_LOOP_SCALE)
+            };
+        }
+
+        let frequencies = if rpo.is_empty() {
+            Vec::new()
+        } else {
+            propagate(&probabilities, &rpo, &order, &scales, 0, |_| true)
+        };
+        Self { probabilities, frequencies }
+    }
+
+    /// The branch probabilities the frequencies were derived from
+    pub fn branch_probabilities(&self) -> &BranchProbabilityInfo {
+        &self.probabilities
+    }
+
+    pub fn loop_info(&self) -> &LoopInfo {
+        self.probabilities.loop_info()
+    }
+
+    pub fn cfg(&self) -> &CFG {
+        self.probabilities.cfg()
+    }
+
+    /// Times a block runs per entry to the function; 0 if unreachable
+    pub fn frequency(&self, block: usize) -> f64 {
+        self.frequencies[block]
+    }
+
+    /// Times the edge from `from` to `to` is taken per entry to the
+    /// function
+    pub fn edge_frequency(&self, from: usize, to: usize) -> f64 {
+        self.frequencies[from] * self.probabilities.edge_probability(from, to).as_f64()
+    }
+
+    /// Frequency of the hottest block
+    pub fn max_frequency(&self) -> f64 {
+        self.frequencies.iter().copied().fold(0.0, f64::max)
+    }
+}
+
+/// Frequencies of the blocks of a region entered once at `head`, in
+/// reverse postorder. Edges into a block from later in the order close a
+/// cycle and are left out; the headers of inner loops make up for them
+/// with their scale.
+fn propagate(
+    probabilities: &BranchProbabilityInfo,
+    rpo: &[usize],
+    order: &[usize],
+    scales: &[f64],
+    head: usize,
+    in_region: impl Fn(usize) -> bool,
+) -> Vec<f64> {
+    let cfg = probabilities.cfg();
+    let mut frequencies = vec![0.0; cfg.num_blocks()];
+    for &block in &rpo[order[head]..] {
+        if !in_region(block) {
+            continue;
+        }
+        frequencies[block] = if block == head {
+            1.0
+        } else {
+            let incoming: f64 = cfg.predecessors(block).iter()
+                .filter(|&&pred| in_region(pred) && order[pred] < order[block])
+                .map(|&pred| frequencies[pred] * probabilities.edge_probability(pred, block).as_f64())
+                .sum();
+            incoming * scales[block]
+        };
+    }
+    frequencies
+}
+
+/// Block frequency analysis pass
+pub struct BlockFrequencyPass;
+
+impl Pass for BlockFrequencyPass {
+    fn name(&self) -> &str {
+        "block-freq"
+    }
+}
+
+impl AnalysisPass for BlockFrequencyPass {
+    type Result = BlockFrequencyInfo;
+
+    fn run_analysis(&mut self, function: &Function) -> PassResult<Self::Result> {
+        Ok(BlockFrequencyInfo::new(function))
+    }
+}
@@ -0,0 +1,360 @@
+//! Branch Probability Analysis
+//!
+//! Estimates how likely each edge of the CFG is to be taken, like LLVM's
+//! BranchProbabilityInfo. Probabilities come from `!prof branch_weights`
+//! metadata where a terminator has it; otherwise the first of these static
+//! heuristics that tells the successors of a block apart decides:
+//!
+//! - edges into blocks that can only end in `unreachable` are almost never
+//!   taken;
+//! - edges into blocks that can only reach a call to a `cold` function
+//!   are unlikely;
+//! - a loop is more likely to go around again than to exit;
+//! - pointers compared with `eq`, as in null checks, are unlikely to be
+//!   equal.
+//!
+//! Blocks left undecided split evenly between their successors.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Add;
+use crate::analysis::LoopInfo;
+use crate::cfg::CFG;
+use crate::function::Function;
+use crate::instruction::{Instruction, IntPredicate, Opcode};
+use crate::module::Module;
+use crate::passes::{AnalysisPass, Pass, PassResult};
+use crate::value_tracking::ValueTracking;
+
+/// A probability as a fraction of `DENOMINATOR`, as in LLVM
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BranchProbability(u32);
+
+impl BranchProbability {
+    pub const DENOMINATOR: u32 = 1 << 31;
+
+    /// `numerator / denominator`, rounded to the nearest representable
+    /// probability
+    pub fn new(numerator: u64, denominator: u64) -> Self {
+        assert!(denominator > 0 && numerator <= denominator, "probability out of range");
+        let scaled = (numerator as u128 * Self::DENOMINATOR as u128 + denominator as u128 / 2) / denominator as u128;
+        Self(scaled as u32)
+    }
+
+    pub fn zero() -> Self {
+        Self(0)
+    }
+
+    pub fn one() -> Self {
+        Self(Self::DENOMINATOR)
+    }
+
+    /// The numerator over `DENOMINATOR`
+    pub fn numerator(&self) -> u32 {
+        self.0
+    }
+
+    /// The probability of the other outcome
+    pub fn complement(&self) -> Self {
+        Self(Self::DENOMINATOR - self.0)
+    }
+
+    pub fn as_f64(&self) -> f64 {
+        self.0 as f64 / Self::DENOMINATOR as f64
+    }
+
+    /// `value` scaled by the probability, rounded down
+    pub fn scale(&self, value: u64) -> u64 {
+        (value as u128 * self.0 as u128 / Self::DENOMINATOR as u128) as u64
+    }
+}
+
+/// Sums saturate at one
+impl Add for BranchProbability {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self((self.0 + other.0).min(Self::DENOMINATOR))
+    }
+}
+
+/// Printed as LLVM prints probabilities: `0x40000000 / 0x80000000 = 50.00%`
+impl fmt::Display for BranchProbability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{:08x} / 0x{:08x} = {:.2}%", self.0, Self::DENOMINATOR, self.as_f64() * 100.0)
+    }
+}
+
+// Weights of the static heuristics, taken from LLVM
+const UNREACHABLE_TAKEN_WEIGHT: u64 = 1;
+const UNREACHABLE_NONTAKEN_WEIGHT: u64 = (1 << 20) - 1;
+const COLD_TAKEN_WEIGHT: u64 = 4;
+const COLD_NONTAKEN_WEIGHT: u64 = 64;
+const LOOP_TAKEN_WEIGHT: u64 = 124;
+const LOOP_NONTAKEN_WEIGHT: u64 = 4;
+const POINTER_TAKEN_WEIGHT: u64 = 20;
+const POINTER_NONTAKEN_WEIGHT: u64 = 12;
+
+/// Edge probabilities of a function
+///
+/// An edge is identified by its source and destination blocks; when a
+/// terminator names the same successor several times, as a switch may,
+/// the edge carries the sum of their probabilities.
+pub struct BranchProbabilityInfo {
+    loops: LoopInfo,
+    /// Probability of each successor of each block, in the order of
+    /// `CFG::successors`
+    probabilities: Vec<Vec<BranchProbability>>,
+}
+
+impl BranchProbabilityInfo {
+    /// Branch probabilities of a function on its own, where no call is
+    /// known to be cold
+    pub fn new(function: &Function) -> Self {
+        Self::build(function, &HashSet::new())
+    }
+
+    /// Branch probabilities of a function of a module, where calls to the
+    /// functions marked `cold` are cold
+    pub fn in_module(function: &Function, module: &Module) -> Self {
+        let cold_functions = module.functions().iter()
+            .filter(|callee| callee.attributes().cold)
+            .map(|callee| callee.name())
+            .collect();
+        Self::build(function, &cold_functions)
+    }
+
+    fn build(function: &Function, cold_functions: &HashSet<String>) -> Self {
+        let loops = LoopInfo::new(function);
+        let cfg = loops.cfg();
+        let blocks = function.basic_blocks();
+        let tracking = ValueTracking::new(function);
+
+        let unreachable = backward_closure(cfg, |block| {
+            blocks[block].terminator().is_some_and(|term| term.opcode() == Opcode::Unreachable)
+        });
+        let cold = backward_closure(cfg, |block| {
+            blocks[block].instructions().iter().any(|inst| calls_cold_function(inst, cold_functions))
+        });
+
+        let probabilities = (0..cfg.num_blocks())
+            .map(|block| {
+                let successors = cfg.successors(block);
+                let terminator = blocks[block].terminator();
+                let weights = terminator.as_ref().and_then(|term| metadata_weights(term, cfg, successors))
+                    .or_else(|| split_weights(successors, |succ| unreachable[succ], UNREACHABLE_TAKEN_WEIGHT, UNREACHABLE_NONTAKEN_WEIGHT))
+                    .or_else(|| split_weights(successors, |succ| cold[succ], COLD_TAKEN_WEIGHT, COLD_NONTAKEN_WEIGHT))
+                    .or_else(|| loop_weights(&loops, block))
+                    .or_else(|| pointer_weights(terminator.as_ref()?, &tracking))
+                    .unwrap_or_else(|| vec![1; successors.len()]);
+                normalize(&weights)
+            })
+            .collect();
+
+        Self { loops, probabilities }
+    }
+
+    /// The loops the probabilities were estimated with
+    pub fn loop_info(&self) -> &LoopInfo {
+        &self.loops
+    }
+
+    pub fn cfg(&self) -> &CFG {
+        self.loops.cfg()
+    }
+
+    /// Probabilities of the successors of a block, in the order of
+    /// `CFG::successors`
+    pub fn successor_probabilities(&self, block: usize) -> &[BranchProbability] {
+        &self.probabilities[block]
+    }
+
+    /// Probability of the edge from `from` to `to`; zero if there is none
+    pub fn edge_probability(&self, from: usize, to: usize) -> BranchProbability {
+        self.cfg().successors(from).iter()
+            .position(|&succ| succ == to)
+            .map_or(BranchProbability::zero(), |position| self.probabilities[from][position])
+    }
+
+    /// Whether the edge is taken at least four times in five, LLVM's
+    /// threshold for a hot edge
+    pub fn is_edge_hot(&self, from: usize, to: usize) -> bool {
+        self.edge_probability(from, to) >= BranchProbability::new(4, 5)
+    }
+}
+
+/// The blocks that have `seed` or whose successors all do, walking back
+/// from the exits; blocks without successors only have it if seeded
+fn backward_closure(cfg: &CFG, seed: impl Fn(usize) -> bool) -> Vec<bool> {
+    let mut marked: Vec<bool> = (0..cfg.num_blocks()).map(seed).collect();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for block in 0..cfg.num_blocks() {
+            let successors = cfg.successors(block);
+            if !marked[block] && !successors.is_empty() && successors.iter().all(|&succ| marked[succ]) {
+                marked[block] = true;
+                changed = true;
+            }
+        }
+    }
+    marked
+}
+
+fn calls_cold_function(inst: &Instruction, cold_functions: &HashSet<String>) -> bool {
+    matches!(inst.opcode(), Opcode::Call | Opcode::Invoke)
+        && inst.operands().first()
+            .filter(|callee| callee.is_global())
+            .and_then(|callee| callee.name())
+            .is_some_and(|name| cold_functions.contains(name))
+}
+
+/// Weights of the successors from `!prof branch_weights`, one weight per
+/// destination of the terminator in operand order
+fn metadata_weights(term: &Instruction, cfg: &CFG, successors: &[usize]) -> Option<Vec<u64>> {
+    let node = term.metadata("prof")?.as_tuple()?;
+    if node.first()?.as_string()? != "branch_weights" {
+        return None;
+    }
+    let weights: Vec<u64> = node[1..].iter()
+        .map(|weight| weight.as_int().or_else(|| weight.as_value()?.as_const_int()).map(|w| w as u32 as u64))
+        .collect::<Option<_>>()?;
+
+    let operands = term.operands();
+    let destinations: Vec<_> = match term.opcode() {
+        Opcode::CondBr => operands.iter().skip(1).take(2).collect(),
+        Opcode::Switch => operands.iter().skip(1).step_by(2).collect(),
+        _ => return None,
+    };
+    if destinations.len() != weights.len() || weights.iter().all(|&weight| weight == 0) {
+        return None;
+    }
+    let mut successor_weights = vec![0; successors.len()];
+    for (destination, weight) in destinations.into_iter().zip(weights) {
+        let block = cfg.block_index(destination.name()?)?;
+        successor_weights[successors.iter().position(|&succ| succ == block)?] += weight;
+    }
+    Some(successor_weights)
+}
+
+/// `taken` for the successors that satisfy `unlikely` and `nontaken` for
+/// the rest, each shared out within its group; `None` unless both groups
+/// have members
+fn split_weights(successors: &[usize], unlikely: impl Fn(usize) -> bool, taken: u64, nontaken: u64) -> Option<Vec<u64>> {
+    let count = successors.iter().filter(|&&succ| unlikely(succ)).count();
+    if count == 0 || count == successors.len() {
+        return None;
+    }
+    let likely = successors.len() - count;
+    // Scale so that every share stays a whole number
+    Some(successors.iter()
+        .map(|&succ| if unlikely(succ) { taken * likely as u64 } else { nontaken * count as u64 })
+        .collect())
+}
+
+/// Going around the innermost loop of the block is likely and leaving it
+/// is not; `None` outside loops and for blocks that neither exit nor
+/// branch back to the header
+fn loop_weights(loops: &LoopInfo, block: usize) -> Option<Vec<u64>> {
+    let l = loops.loop_for_block(block)?;
+    let successors = loops.cfg().successors(block);
+    let exits = successors.iter().filter(|&&succ| !l.contains(succ)).count();
+    let back_edges = successors.iter().filter(|&&succ| succ == l.header).count();
+    if exits == 0 && back_edges == 0 {
+        return None;
+    }
+    let inside = successors.len() - exits - back_edges;
+    // Back edges and the other edges that stay in the loop each share the
+    // taken weight; scale by every group size so the shares are whole
+    let scale = (exits.max(1) * back_edges.max(1) * inside.max(1)) as u64;
+    Some(successors.iter()
+        .map(|&succ| {
+            if !l.contains(succ) {
+                LOOP_NONTAKEN_WEIGHT * scale / exits as u64
+            } else if succ == l.header {
+                LOOP_TAKEN_WEIGHT * scale / back_edges as u64
+            } else {
+                LOOP_TAKEN_WEIGHT * scale / inside as u64
+            }
+        })
+        .collect())
+}
+
+/// A conditional branch on `icmp eq` or `icmp ne` of pointers more likely
+/// goes the way of the pointers being different
+fn pointer_weights(term: &Instruction, tracking: &ValueTracking) -> Option<Vec<u64>> {
+    if term.opcode() != Opcode::CondBr {
+        return None;
+    }
+    let operands = term.operands();
+    // Both destinations must be distinct successors
+    if operands.get(1)?.name()? == operands.get(2)?.name()? {
+        return None;
+    }
+    let compare = tracking.definition(operands.first()?)?;
+    if compare.opcode() != Opcode::ICmp || !compare.operands().first()?.get_type().is_pointer() {
+        return None;
+    }
+    match compare.int_predicate()? {
+        IntPredicate::EQ => Some(vec![POINTER_NONTAKEN_WEIGHT, POINTER_TAKEN_WEIGHT]),
+        IntPredicate::NE => Some(vec![POINTER_TAKEN_WEIGHT, POINTER_NONTAKEN_WEIGHT]),
+        _ => None,
+    }
+}
+
+/// Probabilities proportional to the weights, adding up to exactly one
+fn normalize(weights: &[u64]) -> Vec<BranchProbability> {
+    let total: u64 = weights.iter().sum();
+    if total == 0 {
+        return vec![BranchProbability::zero(); weights.len()];
+    }
+    let mut probabilities: Vec<BranchProbability> = weights.iter()
+        .map(|&weight| BranchProbability::new(weight, total))
+        .collect();
+    // Give the rounding error to the last edge with any weight
+    if let Some(last) = weights.iter().rposition(|&weight| weight > 0) {
+        let others = probabilities.iter().enumerate()
+            .filter(|&(i, _)| i != last)
+            .fold(BranchProbability::zero(), |sum, (_, &p)| sum + p);
+        probabilities[last] = others.complement();
+    }
+    probabilities
+}
+
+/// Branch probability analysis pass
+pub struct BranchProbabilityPass;
+
+impl Pass for BranchProbabilityPass {
+    fn name(&self) -> &str {
+        "branch-prob"
+    }
+}
+
+impl AnalysisPass for BranchProbabilityPass {
+    type Result = BranchProbabilityInfo;
+
+    fn run_analysis(&mut self, function: &Function) -> PassResult<Self::Result> {
+        Ok(BranchProbabilityInfo::new(function))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probability_arithmetic() {
+        let half = BranchProbability::new(1, 2);
+        assert_eq!(half.numerator(), 1 << 30);
+        assert_eq!(half + half, BranchProbability::one());
+        assert_eq!(BranchProbability::new(3, 4) + half, BranchProbability::one());
+        assert_eq!(BranchProbability::new(1, 4).complement(), BranchProbability::new(3, 4));
+        assert_eq!(BranchProbability::new(1, 3).scale(300), 100);
+        assert_eq!(BranchProbability::new(1, 4).scale(10), 2);
+        assert_eq!(half.to_string(), "0x40000000 / 0x80000000 = 50.00%");
+
+        let thirds = normalize(&[1, 1, 1]);
+        assert_eq!(thirds.into_iter().fold(BranchProbability::zero(), |sum, p| sum + p), BranchProbability::one());
+        assert_eq!(normalize(&[0, 5]), [BranchProbability::zero(), BranchProbability::one()]);
+    }
+}
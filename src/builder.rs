@@ -65,17 +65,23 @@ impl Builder {
     }
 
     /// Create an unconditional branch
-    pub fn build_br(&self, _dest: BasicBlock) {
-        let inst = Instruction::new(Opcode::Br, vec![], None);
+    pub fn build_br(&self, dest: BasicBlock) {
+        let inst = Instruction::new(Opcode::Br, vec![self.block_label(&dest)], None);
         self.insert(inst);
     }
 
     /// Create a conditional branch
-    pub fn build_cond_br(&self, cond: Value, _then_bb: BasicBlock, _else_bb: BasicBlock) {
-        let inst = Instruction::new(Opcode::CondBr, vec![cond], None);
+    pub fn build_cond_br(&self, cond: Value, then_bb: BasicBlock, else_bb: BasicBlock) {
+        let operands = vec![cond, self.block_label(&then_bb), self.block_label(&else_bb)];
+        let inst = Instruction::new(Opcode::CondBr, operands, None);
         self.insert(inst);
     }
 
+    /// A label operand referring to `bb`
+    fn block_label(&self, bb: &BasicBlock) -> Value {
+        Value::new(self.context.label_type(), crate::value::ValueKind::BasicBlock, bb.name())
+    }
+
     // Binary operations
 
     /// Create an integer addition
@@ -218,19 +224,21 @@ impl Builder {
     // Comparison operations
 
     /// Create an integer comparison
-    pub fn build_icmp(&self, _pred: IntPredicate, lhs: Value, rhs: Value, name: Option<String>) -> Value {
+    pub fn build_icmp(&self, pred: IntPredicate, lhs: Value, rhs: Value, name: Option<String>) -> Value {
         let result_type = self.context.bool_type();
         let result = Value::instruction(result_type, Opcode::ICmp, name);
-        let inst = Instruction::new(Opcode::ICmp, vec![lhs, rhs], Some(result.clone()));
+        let mut inst = Instruction::new(Opcode::ICmp, vec![lhs, rhs], Some(result.clone()));
+        inst.set_int_predicate(pred);
         self.insert(inst);
         result
     }
 
     /// Create a floating point comparison
-    pub fn build_fcmp(&self, _pred: FloatPredicate, lhs: Value, rhs: Value, name: Option<String>) -> Value {
+    pub fn build_fcmp(&self, pred: FloatPredicate, lhs: Value, rhs: Value, name: Option<String>) -> Value {
         let result_type = self.context.bool_type();
         let result = Value::instruction(result_type, Opcode::FCmp, name);
-        let inst = Instruction::new(Opcode::FCmp, vec![lhs, rhs], Some(result.clone()));
+        let mut inst = Instruction::new(Opcode::FCmp, vec![lhs, rhs], Some(result.clone()));
+        inst.set_float_predicate(pred);
         self.insert(inst);
         result
     }
@@ -0,0 +1,251 @@
+//! Call Graph
+//!
+//! The call graph of a module, like LLVM's CallGraph: a node per function
+//! with an edge per direct call or invoke. Two nodes stand for code outside
+//! the module. The external calling node calls every function that code
+//! outside the module can reach, and the calls-external node is the callee
+//! of indirect calls and of the declared functions, which may call
+//! anything. Strongly connected components are visited bottom-up, callees
+//! before their callers, which is the order inlining and attribute
+//! inference need.
+
+use std::collections::{HashMap, HashSet};
+use crate::instruction::Opcode;
+use crate::module::{Linkage, Module};
+use crate::passes::{ModuleAnalysisPass, Pass, PassResult};
+use crate::value::Value;
+
+/// A node of the call graph
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallGraphNode {
+    /// Name of the function; `None` for the two external nodes
+    pub function: Option<String>,
+    /// Nodes called from this one, once per call site
+    pub callees: Vec<usize>,
+}
+
+/// Call graph of a module
+#[derive(Debug, Clone)]
+pub struct CallGraph {
+    nodes: Vec<CallGraphNode>,
+    /// Maps function names to their nodes
+    node_indices: HashMap<String, usize>,
+}
+
+impl CallGraph {
+    /// Node that calls every function reachable from outside the module
+    pub const EXTERNAL_CALLING_NODE: usize = 0;
+    /// Node called by indirect calls and by declared functions
+    pub const CALLS_EXTERNAL_NODE: usize = 1;
+
+    /// Build the call graph of a module
+    pub fn new(module: &Module) -> Self {
+        let functions = module.functions();
+        let mut nodes = vec![
+            CallGraphNode { function: None, callees: Vec::new() },
+            CallGraphNode { function: None, callees: Vec::new() },
+        ];
+        let mut node_indices = HashMap::new();
+        for function in &functions {
+            node_indices.insert(function.name(), nodes.len());
+            nodes.push(CallGraphNode { function: Some(function.name()), callees: Vec::new() });
+        }
+
+        let mut address_taken = HashSet::new();
+        for global in module.globals() {
+            if let Some(initializer) = &global.initializer {
+                collect_referenced_functions(initializer, &mut address_taken);
+            }
+        }
+        for alias in module.aliases() {
+            collect_referenced_functions(&alias.aliasee, &mut address_taken);
+        }
+
+        for function in &functions {
+            let node = node_indices[&function.name()];
+            if !function.has_body() {
+                if !function.name().starts_with("llvm.") {
+                    nodes[node].callees.push(Self::CALLS_EXTERNAL_NODE);
+                }
+                continue;
+            }
+            for bb in function.basic_blocks() {
+                for inst in bb.instructions() {
+                    let operands = inst.operands();
+                    let is_call = matches!(inst.opcode(), Opcode::Call | Opcode::Invoke | Opcode::CallBr);
+                    // Operands other than the callee take the address of a function
+                    for operand in operands.iter().skip(is_call as usize) {
+                        collect_referenced_functions(operand, &mut address_taken);
+                    }
+                    let Some(callee) = operands.first().filter(|_| is_call) else {
+                        continue;
+                    };
+                    match called_function(callee) {
+                        // Intrinsics are not calls into code of the module
+                        Some(name) if name.starts_with("llvm.") => {}
+                        Some(name) if node_indices.contains_key(name) => nodes[node].callees.push(node_indices[name]),
+                        _ => nodes[node].callees.push(Self::CALLS_EXTERNAL_NODE),
+                    }
+                }
+            }
+        }
+
+        for function in &functions {
+            let local = matches!(function.linkage(), Linkage::Internal | Linkage::Private);
+            if !local || address_taken.contains(&function.name()) {
+                nodes[Self::EXTERNAL_CALLING_NODE].callees.push(node_indices[&function.name()]);
+            }
+        }
+
+        Self { nodes, node_indices }
+    }
+
+    pub fn nodes(&self) -> &[CallGraphNode] {
+        &self.nodes
+    }
+
+    pub fn node(&self, index: usize) -> &CallGraphNode {
+        &self.nodes[index]
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Get the node of a function by name
+    pub fn node_index(&self, function: &str) -> Option<usize> {
+        self.node_indices.get(function).copied()
+    }
+
+    /// Whether a node has a call site that calls another
+    pub fn calls(&self, caller: usize, callee: usize) -> bool {
+        self.nodes[caller].callees.contains(&callee)
+    }
+
+    /// Nodes with a call site that calls a node, in index order
+    pub fn callers(&self, callee: usize) -> Vec<usize> {
+        (0..self.nodes.len()).filter(|&caller| self.calls(caller, callee)).collect()
+    }
+
+    /// Strongly connected components by Tarjan's algorithm, in the order
+    /// it completes them: every component comes after the components it
+    /// calls into
+    pub fn bottom_up_sccs(&self) -> Vec<CallGraphSCC> {
+        let n = self.nodes.len();
+        let mut index = vec![usize::MAX; n];
+        let mut lowlink = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut stack = Vec::new();
+        let mut sccs = Vec::new();
+        let mut next_index = 0;
+
+        for root in 0..n {
+            if index[root] != usize::MAX {
+                continue;
+            }
+            // Nodes being visited with the position of the next callee to follow
+            let mut visiting = vec![(root, 0)];
+            index[root] = next_index;
+            lowlink[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+
+            while let Some(&mut (node, ref mut position)) = visiting.last_mut() {
+                if let Some(&callee) = self.nodes[node].callees.get(*position) {
+                    *position += 1;
+                    if index[callee] == usize::MAX {
+                        index[callee] = next_index;
+                        lowlink[callee] = next_index;
+                        next_index += 1;
+                        stack.push(callee);
+                        on_stack[callee] = true;
+                        visiting.push((callee, 0));
+                    } else if on_stack[callee] {
+                        lowlink[node] = lowlink[node].min(index[callee]);
+                    }
+                    continue;
+                }
+
+                visiting.pop();
+                if let Some(&(parent, _)) = visiting.last() {
+                    lowlink[parent] = lowlink[parent].min(lowlink[node]);
+                }
+                if lowlink[node] == index[node] {
+                    let mut members = Vec::new();
+                    while let Some(member) = stack.pop() {
+                        on_stack[member] = false;
+                        members.push(member);
+                        if member == node {
+                            break;
+                        }
+                    }
+                    members.sort_unstable();
+                    let has_cycle = members.len() > 1 || self.calls(node, node);
+                    sccs.push(CallGraphSCC { nodes: members, has_cycle });
+                }
+            }
+        }
+        sccs
+    }
+}
+
+/// A strongly connected component of the call graph
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallGraphSCC {
+    /// Nodes of the component, in index order
+    pub nodes: Vec<usize>,
+    /// Whether the component is recursive: more than one node, or a node
+    /// that calls itself
+    pub has_cycle: bool,
+}
+
+impl CallGraphSCC {
+    /// Names of the functions in the component
+    pub fn functions(&self, call_graph: &CallGraph) -> Vec<String> {
+        self.nodes.iter().filter_map(|&node| call_graph.node(node).function.clone()).collect()
+    }
+}
+
+/// Call graph analysis pass
+pub struct CallGraphAnalysisPass;
+
+impl Pass for CallGraphAnalysisPass {
+    fn name(&self) -> &str {
+        "callgraph"
+    }
+}
+
+impl ModuleAnalysisPass for CallGraphAnalysisPass {
+    type Result = CallGraph;
+
+    fn run_module_analysis(&mut self, module: &Module) -> PassResult<Self::Result> {
+        Ok(CallGraph::new(module))
+    }
+}
+
+/// The function a call calls directly, looking through pointer casts
+fn called_function(callee: &Value) -> Option<&str> {
+    if let Some((opcode, operands)) = callee.as_const_expr() {
+        return match opcode {
+            Opcode::BitCast | Opcode::AddrSpaceCast => called_function(operands.first()?),
+            _ => None,
+        };
+    }
+    callee.name().filter(|_| callee.is_global())
+}
+
+/// Collect the globals a constant refers to; the call graph only looks up
+/// the names of functions among them
+fn collect_referenced_functions(value: &Value, names: &mut HashSet<String>) {
+    if let Some(name) = value.name().filter(|_| value.is_global()) {
+        names.insert(name.to_string());
+        return;
+    }
+    let elements = value.array_elements().or(value.vector_elements()).or(value.struct_fields());
+    let operands = elements.map(|elements| elements.as_slice())
+        .or(value.as_const_expr().map(|(_, operands)| operands));
+    for operand in operands.into_iter().flatten() {
+        collect_referenced_functions(operand, names);
+    }
+}
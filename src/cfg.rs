@@ -6,6 +6,7 @@ use std::collections::{HashMap, HashSet, VecDeque};
 use crate::function::Function;
 use crate::basic_block::BasicBlock;
 use crate::instruction::Opcode;
+use crate::value::Value;
 
 /// Control Flow Graph
 #[allow(dead_code)]
@@ -19,15 +20,24 @@ pub struct CFG {
     predecessors: Vec<Vec<usize>>,
     /// Basic blocks
     blocks: Vec<BasicBlock>,
+    /// Branch targets that do not name a block
+    missing_targets: Vec<(usize, String)>,
+    /// Whether some terminator's destinations are unknown
+    has_opaque_terminators: bool,
 }
 
 impl CFG {
     /// Build a CFG from a function
+    ///
+    /// Edges are deduplicated, so a block appears once among another's
+    /// successors even if several operands of its terminator name it.
     pub fn from_function(function: &Function) -> Self {
         let blocks = function.basic_blocks();
         let mut block_indices = HashMap::new();
-        let successors = vec![Vec::new(); blocks.len()];
-        let predecessors = vec![Vec::new(); blocks.len()];
+        let mut successors = vec![Vec::new(); blocks.len()];
+        let mut predecessors = vec![Vec::new(); blocks.len()];
+        let mut missing_targets = Vec::new();
+        let mut has_opaque_terminators = false;
 
         // Build block index map
         for (i, bb) in blocks.iter().enumerate() {
@@ -38,86 +48,43 @@ impl CFG {
             }
         }
 
-        // Build mutable versions for edge building
-        let mut successors = successors;
-        let mut predecessors = predecessors;
-
         // Build successor/predecessor edges
         for (i, bb) in blocks.iter().enumerate() {
-            if let Some(term) = bb.terminator() {
-                match term.opcode() {
-                    Opcode::Br => {
-                        // Unconditional branch - one successor
-                        // Operand 0 is the target block
-                        let operands = term.operands();
-                        if !operands.is_empty() {
-                            if let Some(target_name) = operands[0].name() {
-                                if let Some(&target_idx) = block_indices.get(target_name) {
-                                    successors[i].push(target_idx);
-                                    predecessors[target_idx].push(i);
-                                }
-                            }
-                        }
+            let Some(term) = bb.terminator() else { continue };
+            let operands = term.operands();
+            let targets: Vec<&Value> = match term.opcode() {
+                // Operand 0 is the target block
+                Opcode::Br => operands.iter().take(1).collect(),
+                // Operand 0 is the condition, then the true and false targets
+                Opcode::CondBr => operands.iter().skip(1).take(2).collect(),
+                // Operand 0 is the condition, operand 1 the default target,
+                // then (value, target) pairs
+                Opcode::Switch => operands.iter().skip(1).step_by(2).collect(),
+                // The normal and unwind destinations are the last two operands
+                Opcode::Invoke => operands.iter().skip(operands.len().saturating_sub(2)).collect(),
+                Opcode::IndirectBr | Opcode::CallBr | Opcode::CatchSwitch |
+                Opcode::CatchRet | Opcode::CleanupRet => {
+                    let labels: Vec<&Value> = operands.iter().filter(|op| op.is_basic_block()).collect();
+                    // The text parser does not keep these destinations
+                    if labels.is_empty() {
+                        has_opaque_terminators = true;
                     }
-                    Opcode::CondBr => {
-                        // Conditional branch - two successors
-                        // Operand 1 is true target, operand 2 is false target
-                        let operands = term.operands();
-                        if operands.len() >= 3 {
-                            // True branch
-                            if let Some(target_name) = operands[1].name() {
-                                if let Some(&target_idx) = block_indices.get(target_name) {
-                                    successors[i].push(target_idx);
-                                    predecessors[target_idx].push(i);
-                                }
-                            }
-                            // False branch
-                            if let Some(target_name) = operands[2].name() {
-                                if let Some(&target_idx) = block_indices.get(target_name) {
-                                    successors[i].push(target_idx);
-                                    predecessors[target_idx].push(i);
-                                }
-                            }
-                        }
-                    }
-                    Opcode::Switch => {
-                        // Switch - multiple successors
-                        // Operand 1 is default target, then pairs of (value, target)
-                        let operands = term.operands();
-                        if operands.len() >= 2 {
-                            // Default target
-                            if let Some(target_name) = operands[1].name() {
-                                if let Some(&target_idx) = block_indices.get(target_name) {
-                                    successors[i].push(target_idx);
-                                    predecessors[target_idx].push(i);
-                                }
-                            }
-                            // Case targets (every odd index after 2)
-                            let mut idx = 3;
-                            while idx < operands.len() {
-                                if let Some(target_name) = operands[idx].name() {
-                                    if let Some(&target_idx) = block_indices.get(target_name) {
-                                        if !successors[i].contains(&target_idx) {
-                                            successors[i].push(target_idx);
-                                            predecessors[target_idx].push(i);
-                                        }
-                                    }
-                                }
-                                idx += 2; // Skip value, move to next target
-                            }
+                    labels
+                }
+                // Ret, Unreachable and Resume have no successors
+                _ => Vec::new(),
+            };
+
+            for target in targets {
+                let Some(target_name) = target.name() else { continue };
+                match block_indices.get(target_name) {
+                    Some(&target_idx) => {
+                        if !successors[i].contains(&target_idx) {
+                            successors[i].push(target_idx);
+                            predecessors[target_idx].push(i);
                         }
                     }
-                    Opcode::Invoke => {
-                        // Invoke - two successors (normal and unwind)
-                        let operands = term.operands();
-                        // Need to find "to label" and "unwind label" operands
-                        // Typically they're near the end of the operand list
-                        // For now, we'll skip this as it requires more sophisticated parsing
-                    }
-                    Opcode::Ret | Opcode::Unreachable => {
-                        // No successors
-                    }
-                    _ => {}
+                    None => missing_targets.push((i, target_name.to_string())),
                 }
             }
         }
@@ -127,9 +94,23 @@ impl CFG {
             successors,
             predecessors,
             blocks,
+            missing_targets,
+            has_opaque_terminators,
         }
     }
 
+    /// Branch targets that name no block in the function, as
+    /// (index of the branching block, target name)
+    pub fn missing_targets(&self) -> &[(usize, String)] {
+        &self.missing_targets
+    }
+
+    /// Whether every edge of the function is known: no terminator names a
+    /// missing block or lacks the destinations it must have
+    pub fn is_complete(&self) -> bool {
+        self.missing_targets.is_empty() && !self.has_opaque_terminators
+    }
+
     /// Get the successors of a block
     pub fn successors(&self, block_index: usize) -> &[usize] {
         &self.successors[block_index]
@@ -140,6 +121,16 @@ impl CFG {
         &self.predecessors[block_index]
     }
 
+    /// Get the name of a block by index (`bb<N>` for unnamed blocks)
+    pub fn block_name(&self, block_index: usize) -> String {
+        self.blocks[block_index].name().unwrap_or_else(|| format!("bb{}", block_index))
+    }
+
+    /// Get the index of a block by name (`bb<N>` for unnamed blocks)
+    pub fn block_index(&self, name: &str) -> Option<usize> {
+        self.block_indices.get(name).copied()
+    }
+
     /// Get the number of blocks
     pub fn num_blocks(&self) -> usize {
         self.blocks.len()
@@ -172,68 +163,46 @@ impl CFG {
 
     /// Compute reverse postorder traversal
     pub fn reverse_postorder(&self) -> Vec<usize> {
-        let mut visited: HashSet<usize> = HashSet::new();
-        let mut postorder = Vec::new();
-
-        self.dfs_postorder(0, &mut visited, &mut postorder);
-
-        postorder.reverse();
-        postorder
-    }
-
-    fn dfs_postorder(&self, block: usize, visited: &mut HashSet<usize>, postorder: &mut Vec<usize>) {
-        if !visited.insert(block) {
-            return;
+        if self.blocks.is_empty() {
+            return Vec::new();
         }
-
-        for &succ in self.successors(block) {
-            self.dfs_postorder(succ, visited, postorder);
-        }
-
-        postorder.push(block);
+        reverse_postorder_from(0, &self.successors)
     }
 
-    /// Find loops in the CFG using a simple backedge detection
+    /// Find the natural loops of the CFG, outer loops before the loops
+    /// nested in them (see `LoopInfo`)
     pub fn find_loops(&self) -> Vec<Loop> {
-        let mut loops = Vec::new();
-        let rpo = self.reverse_postorder();
-        let mut _visited: HashSet<usize> = HashSet::new();
-
-        for &block in &rpo {
-            for &succ in self.successors(block) {
-                // If successor appears before block in RPO, it's a backedge
-                if rpo.iter().position(|&b| b == succ) < rpo.iter().position(|&b| b == block) {
-                    // Found a loop with header at succ
-                    let loop_blocks = self.find_loop_blocks(succ, block);
-                    loops.push(Loop {
-                        header: succ,
-                        blocks: loop_blocks,
-                    });
-                }
-            }
-        }
-
-        loops
+        crate::analysis::LoopInfo::from_cfg(self.clone()).loops().to_vec()
     }
+}
 
-    fn find_loop_blocks(&self, header: usize, back_edge_source: usize) -> HashSet<usize> {
-        let mut loop_blocks = HashSet::new();
-        let mut queue = VecDeque::new();
-
-        loop_blocks.insert(header);
-        queue.push_back(back_edge_source);
-        loop_blocks.insert(back_edge_source);
-
-        while let Some(current) = queue.pop_front() {
-            for &pred in self.predecessors(current) {
-                if loop_blocks.insert(pred) {
-                    queue.push_back(pred);
-                }
+/// Reverse postorder of the nodes reachable from `root` in a graph given
+/// by successor lists
+///
+/// The depth-first search keeps an explicit stack, so deep graphs cannot
+/// overflow the call stack.
+pub(crate) fn reverse_postorder_from(root: usize, successors: &[Vec<usize>]) -> Vec<usize> {
+    let mut visited = vec![false; successors.len()];
+    let mut postorder = Vec::new();
+    // Each entry is a node and the index of its next successor to visit
+    let mut stack = vec![(root, 0)];
+    visited[root] = true;
+
+    while let Some((node, next)) = stack.last_mut() {
+        if let Some(&succ) = successors[*node].get(*next) {
+            *next += 1;
+            if !visited[succ] {
+                visited[succ] = true;
+                stack.push((succ, 0));
             }
+        } else {
+            postorder.push(*node);
+            stack.pop();
         }
-
-        loop_blocks
     }
+
+    postorder.reverse();
+    postorder
 }
 
 /// Represents a loop in the CFG
@@ -241,8 +210,16 @@ impl CFG {
 pub struct Loop {
     /// Loop header block
     pub header: usize,
-    /// All blocks in the loop
+    /// All blocks in the loop, including those of nested loops
     pub blocks: HashSet<usize>,
+    /// Blocks with a back edge to the header
+    pub latches: Vec<usize>,
+    /// Index of the innermost enclosing loop
+    pub parent: Option<usize>,
+    /// Indices of the loops directly nested in this one
+    pub children: Vec<usize>,
+    /// Nesting depth, 1 for outermost loops
+    pub depth: usize,
 }
 
 impl Loop {
@@ -255,6 +232,19 @@ impl Loop {
     pub fn size(&self) -> usize {
         self.blocks.len()
     }
+
+    /// The latch, if the loop has exactly one
+    pub fn latch(&self) -> Option<usize> {
+        match self.latches[..] {
+            [latch] => Some(latch),
+            _ => None,
+        }
+    }
+
+    /// Check if the loop is not nested in another
+    pub fn is_outermost(&self) -> bool {
+        self.parent.is_none()
+    }
 }
 
 #[cfg(test)]
@@ -4,6 +4,7 @@
 
 use std::collections::{HashMap, BinaryHeap};
 use std::cmp::Ordering;
+use crate::liveness::Liveness;
 
 /// Virtual register
 pub type VReg = usize;
@@ -140,6 +141,13 @@ impl RegisterAllocator {
         Ok(())
     }
 
+    /// Allocate the values of a function, with each value's index in
+    /// `liveness.values()` as its virtual register
+    pub fn allocate_function(&mut self, liveness: &Liveness) -> Result<(), String> {
+        self.next_vreg = self.next_vreg.max(liveness.values().len());
+        self.allocate(&liveness.live_ranges())
+    }
+
     /// Expire intervals that have ended
     fn expire_old_intervals(
         &mut self,
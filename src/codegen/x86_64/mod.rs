@@ -11,6 +11,8 @@ pub mod instruction_selection;
 use crate::module::Module;
 use crate::function::Function;
 use crate::basic_block::BasicBlock;
+use crate::block_frequency::BlockFrequencyInfo;
+use crate::branch_probability::BranchProbabilityInfo;
 use crate::instruction::{Instruction, Opcode};
 use super::{TargetMachine, CodegenError};
 use registers::*;
@@ -30,8 +32,9 @@ impl X86_64TargetMachine {
         }
     }
 
-    /// Select instructions for a function
-    fn select_instructions(&mut self, function: &Function) -> Result<Vec<MachineInstr>, CodegenError> {
+    /// Select instructions for a function, laying its blocks out by their
+    /// frequencies
+    fn select_instructions(&mut self, function: &Function, frequencies: &BlockFrequencyInfo) -> Result<Vec<MachineInstr>, CodegenError> {
         let mut machine_instrs = Vec::new();
 
         // Emit function prologue
@@ -42,8 +45,9 @@ impl X86_64TargetMachine {
         });
 
         // Process each basic block
-        for bb in function.basic_blocks() {
-            let bb_instrs = self.select_basic_block_instructions(&bb)?;
+        let blocks = function.basic_blocks();
+        for block in block_layout(frequencies) {
+            let bb_instrs = self.select_basic_block_instructions(&blocks[block])?;
             machine_instrs.extend(bb_instrs);
         }
 
@@ -355,7 +359,9 @@ impl TargetMachine for X86_64TargetMachine {
 
         // Process each function
         for function in module.functions() {
-            let function_asm = self.emit_function_assembly(&function)?;
+            let frequencies = BlockFrequencyInfo::from_branch_probabilities(
+                BranchProbabilityInfo::in_module(&function, module));
+            let function_asm = self.emit_function_assembly(&function, &frequencies)?;
             asm.push_str(&function_asm);
         }
 
@@ -364,13 +370,13 @@ impl TargetMachine for X86_64TargetMachine {
 
     fn emit_function(&mut self, function: &Function) -> Result<Vec<u8>, CodegenError> {
         // For now, just return empty - full implementation would generate machine code
-        let _ = self.select_instructions(function)?;
+        let _ = self.select_instructions(function, &BlockFrequencyInfo::new(function))?;
         Ok(Vec::new())
     }
 }
 
 impl X86_64TargetMachine {
-    fn emit_function_assembly(&mut self, function: &Function) -> Result<String, CodegenError> {
+    fn emit_function_assembly(&mut self, function: &Function, frequencies: &BlockFrequencyInfo) -> Result<String, CodegenError> {
         let mut asm = String::new();
 
         // Emit function label
@@ -379,7 +385,7 @@ impl X86_64TargetMachine {
         asm.push_str(&format!("{}:\n", function.name()));
 
         // Select and emit instructions
-        let machine_instrs = self.select_instructions(function)?;
+        let machine_instrs = self.select_instructions(function, frequencies)?;
         for instr in machine_instrs {
             asm.push_str(&format!("\t{}\n", instr));
         }
@@ -388,6 +394,35 @@ impl X86_64TargetMachine {
     }
 }
 
+/// Order of the blocks of a function in its code: the entry first, then
+/// each block followed by its likeliest successor not yet placed, so hot
+/// paths run straight through. When a chain ends, the hottest block left
+/// starts the next one, which leaves cold and unreachable blocks at the end.
+/// Every branch jumps explicitly, so any order is correct.
+pub fn block_layout(frequencies: &BlockFrequencyInfo) -> Vec<usize> {
+    let cfg = frequencies.cfg();
+    let num_blocks = cfg.num_blocks();
+    let mut placed = vec![false; num_blocks];
+    let mut layout = Vec::with_capacity(num_blocks);
+
+    let mut next = (num_blocks > 0).then_some(0);
+    while let Some(block) = next {
+        placed[block] = true;
+        layout.push(block);
+        next = hottest(cfg.successors(block).iter().copied().filter(|&succ| !placed[succ]),
+                       |succ| frequencies.edge_frequency(block, succ))
+            .or_else(|| hottest((0..num_blocks).filter(|&other| !placed[other]),
+                                |other| frequencies.frequency(other)));
+    }
+    layout
+}
+
+/// The candidate with the highest frequency; ties go to the block that
+/// comes first in the function
+fn hottest(candidates: impl Iterator<Item = usize>, frequency: impl Fn(usize) -> f64) -> Option<usize> {
+    candidates.max_by(|&a, &b| frequency(a).total_cmp(&frequency(b)).then(b.cmp(&a)))
+}
+
 /// Condition codes for conditional operations
 #[derive(Debug, Clone, Copy)]
 pub enum ConditionCode {
@@ -0,0 +1,516 @@
+//! Constant Ranges
+//!
+//! A `ConstantRange` is a set of integers of one bit width, stored as a
+//! half-open interval `[lower, upper)` that may wrap around, like LLVM's
+//! ConstantRange. `lower == upper` is the full set when both are the
+//! maximum value and the empty set when both are zero. The arithmetic is
+//! conservative: a result contains every value the operation can produce
+//! from members of the operands.
+
+use std::fmt;
+use crate::dataflow::Lattice;
+use crate::instruction::IntPredicate;
+
+/// A wrapping interval of integers of a fixed bit width (at most 128)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConstantRange {
+    width: u32,
+    lower: u128,
+    upper: u128,
+}
+
+/// All ones in the low `width` bits
+pub(crate) fn mask(width: u32) -> u128 {
+    if width >= 128 { u128::MAX } else { (1u128 << width) - 1 }
+}
+
+/// The signed value of the low `width` bits of `value`
+pub(crate) fn to_signed(value: u128, width: u32) -> i128 {
+    if width >= 128 {
+        return value as i128;
+    }
+    let shift = 128 - width;
+    ((value << shift) as i128) >> shift
+}
+
+impl ConstantRange {
+    /// Every value of the width
+    pub fn full(width: u32) -> Self {
+        Self { width, lower: mask(width), upper: mask(width) }
+    }
+
+    /// No value
+    pub fn empty(width: u32) -> Self {
+        Self { width, lower: 0, upper: 0 }
+    }
+
+    /// Exactly one value, given by its low `width` bits
+    pub fn single(width: u32, value: u128) -> Self {
+        let value = value & mask(width);
+        Self { width, lower: value, upper: value.wrapping_add(1) & mask(width) }
+    }
+
+    /// The values from `lower` up to but excluding `upper`, wrapping
+    /// around; equal bounds give the full set
+    pub fn new(width: u32, lower: u128, upper: u128) -> Self {
+        let (lower, upper) = (lower & mask(width), upper & mask(width));
+        if lower == upper {
+            return Self::full(width);
+        }
+        Self { width, lower, upper }
+    }
+
+    /// The values from `min` to `max` inclusive, in unsigned order
+    pub fn from_unsigned(width: u32, min: u128, max: u128) -> Self {
+        if min > max {
+            return Self::empty(width);
+        }
+        Self::new(width, min, max.wrapping_add(1))
+    }
+
+    /// The values from `min` to `max` inclusive, in signed order
+    pub fn from_signed(width: u32, min: i128, max: i128) -> Self {
+        if min > max {
+            return Self::empty(width);
+        }
+        Self::new(width, min as u128, (max as u128).wrapping_add(1))
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Inclusive lower bound of the interval
+    pub fn lower(&self) -> u128 {
+        self.lower
+    }
+
+    /// Exclusive upper bound of the interval
+    pub fn upper(&self) -> u128 {
+        self.upper
+    }
+
+    pub fn is_full_set(&self) -> bool {
+        self.lower == self.upper && self.lower == mask(self.width)
+    }
+
+    pub fn is_empty_set(&self) -> bool {
+        self.lower == self.upper && self.lower == 0
+    }
+
+    /// Whether the interval wraps past the maximum unsigned value
+    pub fn is_wrapped_set(&self) -> bool {
+        self.lower > self.upper && self.upper != 0
+    }
+
+    /// Whether the interval wraps past the maximum signed value
+    pub fn is_sign_wrapped_set(&self) -> bool {
+        let (lower, upper) = (to_signed(self.lower, self.width), to_signed(self.upper, self.width));
+        lower > upper && upper != to_signed(1 << (self.width - 1), self.width)
+    }
+
+    /// Number of values in the set
+    fn size(&self) -> u128 {
+        if self.is_full_set() {
+            return mask(self.width);
+        }
+        self.upper.wrapping_sub(self.lower) & mask(self.width)
+    }
+
+    pub fn contains(&self, value: u128) -> bool {
+        let value = value & mask(self.width);
+        if self.is_full_set() {
+            return true;
+        }
+        if self.lower <= self.upper {
+            self.lower <= value && value < self.upper
+        } else {
+            self.lower <= value || value < self.upper
+        }
+    }
+
+    /// The only member, if the set has exactly one
+    pub fn single_element(&self) -> Option<u128> {
+        (!self.is_full_set() && self.upper == self.lower.wrapping_add(1) & mask(self.width)).then_some(self.lower)
+    }
+
+    pub fn unsigned_min(&self) -> u128 {
+        if self.is_full_set() || self.is_wrapped_set() { 0 } else { self.lower }
+    }
+
+    pub fn unsigned_max(&self) -> u128 {
+        if self.is_full_set() || self.is_wrapped_set() {
+            mask(self.width)
+        } else {
+            self.upper.wrapping_sub(1) & mask(self.width)
+        }
+    }
+
+    pub fn signed_min(&self) -> i128 {
+        if self.is_full_set() || self.is_sign_wrapped_set() {
+            to_signed(1 << (self.width - 1), self.width)
+        } else {
+            to_signed(self.lower, self.width)
+        }
+    }
+
+    pub fn signed_max(&self) -> i128 {
+        if self.is_full_set() || self.is_sign_wrapped_set() {
+            to_signed(mask(self.width) >> 1, self.width)
+        } else {
+            to_signed(self.upper.wrapping_sub(1), self.width)
+        }
+    }
+
+    /// The smaller of the unsigned and signed hulls of two sets
+    pub fn union_with(&self, other: &Self) -> Self {
+        if self.is_empty_set() {
+            return *other;
+        }
+        if other.is_empty_set() {
+            return *self;
+        }
+        let unsigned = Self::from_unsigned(self.width,
+            self.unsigned_min().min(other.unsigned_min()), self.unsigned_max().max(other.unsigned_max()));
+        let signed = Self::from_signed(self.width,
+            self.signed_min().min(other.signed_min()), self.signed_max().max(other.signed_max()));
+        if signed.size() < unsigned.size() { signed } else { unsigned }
+    }
+
+    /// The values in both sets, or the smallest interval containing them
+    /// when they meet in two pieces
+    pub fn intersect_with(&self, other: &Self) -> Self {
+        let mut pieces = Vec::new();
+        for (a_min, a_max) in self.pieces() {
+            for &(b_min, b_max) in &other.pieces() {
+                let (min, max) = (a_min.max(b_min), a_max.min(b_max));
+                if min <= max {
+                    pieces.push((min, max));
+                }
+            }
+        }
+        pieces.sort_unstable();
+        let (Some(first), Some(last)) = (pieces.first(), pieces.last()) else {
+            return Self::empty(self.width);
+        };
+        // Cover the pieces, leaving out the largest gap between them or
+        // around the ends
+        let mut largest_gap = mask(self.width) - last.1 + first.0;
+        let mut covering = Self::from_unsigned(self.width, first.0, last.1);
+        for pair in pieces.windows(2) {
+            let gap = pair[1].0 - pair[0].1 - 1;
+            if gap > largest_gap {
+                largest_gap = gap;
+                covering = Self::new(self.width, pair[1].0, pair[0].1 + 1);
+            }
+        }
+        covering
+    }
+
+    /// The set as inclusive intervals in unsigned order, without wrapping
+    fn pieces(&self) -> Vec<(u128, u128)> {
+        let max = mask(self.width);
+        if self.is_empty_set() {
+            Vec::new()
+        } else if self.is_full_set() {
+            vec![(0, max)]
+        } else if self.is_wrapped_set() {
+            vec![(0, self.upper - 1), (self.lower, max)]
+        } else {
+            vec![(self.lower, self.upper.wrapping_sub(1) & max)]
+        }
+    }
+
+    /// The members zero-extended to `width` bits
+    pub fn zero_extend(&self, width: u32) -> Self {
+        if self.is_empty_set() {
+            return Self::empty(width);
+        }
+        Self::from_unsigned(width, self.unsigned_min(), self.unsigned_max())
+    }
+
+    /// The members sign-extended to `width` bits
+    pub fn sign_extend(&self, width: u32) -> Self {
+        if self.is_empty_set() {
+            return Self::empty(width);
+        }
+        Self::from_signed(width, self.signed_min(), self.signed_max())
+    }
+
+    /// The low `width` bits of the members
+    pub fn truncate(&self, width: u32) -> Self {
+        if self.is_empty_set() {
+            return Self::empty(width);
+        }
+        let (min, max) = (self.unsigned_min(), self.unsigned_max());
+        if max - min >= mask(width) {
+            return Self::full(width);
+        }
+        Self::new(width, min, max + 1)
+    }
+
+    /// Bitwise ands of members, which are no larger than either operand
+    pub fn binary_and(&self, other: &Self) -> Self {
+        if self.is_empty_set() || other.is_empty_set() {
+            return Self::empty(self.width);
+        }
+        Self::from_unsigned(self.width, 0, self.unsigned_max().min(other.unsigned_max()))
+    }
+
+    /// Bitwise ors of members, which are no smaller than either operand
+    /// and set no bit above the highest either may set
+    pub fn binary_or(&self, other: &Self) -> Self {
+        if self.is_empty_set() || other.is_empty_set() {
+            return Self::empty(self.width);
+        }
+        let highest = self.unsigned_max().max(other.unsigned_max());
+        Self::from_unsigned(self.width,
+            self.unsigned_min().max(other.unsigned_min()), mask(128 - highest.leading_zeros()))
+    }
+
+    /// Members shifted left by members of `amount`, when no set bit is
+    /// shifted out
+    pub fn shl(&self, amount: &Self) -> Self {
+        if self.is_empty_set() || amount.is_empty_set() {
+            return Self::empty(self.width);
+        }
+        let (max, shift) = (self.unsigned_max(), amount.unsigned_max());
+        if shift >= self.width as u128 || max.leading_zeros() < 128 - self.width + shift as u32 {
+            return Self::full(self.width);
+        }
+        Self::from_unsigned(self.width, self.unsigned_min() << amount.unsigned_min(), max << shift)
+    }
+
+    /// Members shifted right logically by members of `amount`
+    pub fn lshr(&self, amount: &Self) -> Self {
+        if self.is_empty_set() || amount.is_empty_set() {
+            return Self::empty(self.width);
+        }
+        if amount.unsigned_min() >= self.width as u128 {
+            return Self::full(self.width);
+        }
+        let longest = amount.unsigned_max().min(self.width as u128 - 1);
+        Self::from_unsigned(self.width,
+            self.unsigned_min() >> longest, self.unsigned_max() >> amount.unsigned_min())
+    }
+
+    /// The values that satisfy `predicate` against some member of `other`
+    pub fn make_allowed_icmp_region(predicate: IntPredicate, other: &Self) -> Self {
+        let width = other.width;
+        if other.is_empty_set() {
+            return Self::empty(width);
+        }
+        let signed_limit = to_signed(1 << (width - 1), width);
+        match predicate {
+            IntPredicate::EQ => *other,
+            IntPredicate::NE => match other.single_element() {
+                Some(value) => Self::new(width, value + 1, value),
+                None => Self::full(width),
+            },
+            IntPredicate::ULT if other.unsigned_max() == 0 => Self::empty(width),
+            IntPredicate::ULT => Self::from_unsigned(width, 0, other.unsigned_max() - 1),
+            IntPredicate::ULE => Self::from_unsigned(width, 0, other.unsigned_max()),
+            IntPredicate::UGT if other.unsigned_min() == mask(width) => Self::empty(width),
+            IntPredicate::UGT => Self::from_unsigned(width, other.unsigned_min() + 1, mask(width)),
+            IntPredicate::UGE => Self::from_unsigned(width, other.unsigned_min(), mask(width)),
+            IntPredicate::SLT if other.signed_max() == signed_limit => Self::empty(width),
+            IntPredicate::SLT => Self::from_signed(width, signed_limit, other.signed_max() - 1),
+            IntPredicate::SLE => Self::from_signed(width, signed_limit, other.signed_max()),
+            IntPredicate::SGT if other.signed_min() == -signed_limit - 1 => Self::empty(width),
+            IntPredicate::SGT => Self::from_signed(width, other.signed_min() + 1, -signed_limit - 1),
+            IntPredicate::SGE => Self::from_signed(width, other.signed_min(), -signed_limit - 1),
+        }
+    }
+
+    /// Whether `predicate` holds between every member of this set and
+    /// every member of `other` (`Some(true)`), for no pair
+    /// (`Some(false)`), or only for some
+    pub fn icmp(&self, predicate: IntPredicate, other: &Self) -> Option<bool> {
+        if self.is_empty_set() || other.is_empty_set() {
+            return None;
+        }
+        let less = |a: &Self, b: &Self| if predicate.is_signed() {
+            if a.signed_max() < b.signed_min() {
+                Some(true)
+            } else {
+                (a.signed_min() >= b.signed_max()).then_some(false)
+            }
+        } else if a.unsigned_max() < b.unsigned_min() {
+            Some(true)
+        } else {
+            (a.unsigned_min() >= b.unsigned_max()).then_some(false)
+        };
+        match predicate {
+            IntPredicate::EQ | IntPredicate::NE => {
+                let equal = match (self.single_element(), other.single_element()) {
+                    (Some(a), Some(b)) if a == b => Some(true),
+                    _ => self.intersect_with(other).is_empty_set().then_some(false),
+                };
+                if predicate == IntPredicate::EQ { equal } else { equal.map(|equal| !equal) }
+            }
+            IntPredicate::ULT | IntPredicate::SLT => less(self, other),
+            IntPredicate::UGT | IntPredicate::SGT => less(other, self),
+            IntPredicate::ULE | IntPredicate::SLE => less(other, self).map(|greater| !greater),
+            IntPredicate::UGE | IntPredicate::SGE => less(self, other).map(|less| !less),
+        }
+    }
+
+    /// Sums of members, wrapping
+    pub fn add(&self, other: &Self) -> Self {
+        if self.is_empty_set() || other.is_empty_set() {
+            return Self::empty(self.width);
+        }
+        if self.is_full_set() || other.is_full_set() {
+            return Self::full(self.width);
+        }
+        let lower = self.lower.wrapping_add(other.lower);
+        let upper = self.upper.wrapping_add(other.upper).wrapping_sub(1);
+        let sum = Self::new(self.width, lower, upper);
+        // A sum no larger than an operand has wrapped onto itself
+        if sum.size() < self.size() || sum.size() < other.size() {
+            return Self::full(self.width);
+        }
+        sum
+    }
+
+    /// Differences of members, wrapping
+    pub fn sub(&self, other: &Self) -> Self {
+        self.add(&other.negate())
+    }
+
+    /// Negations of members, wrapping
+    pub fn negate(&self) -> Self {
+        if self.is_empty_set() || self.is_full_set() {
+            return *self;
+        }
+        let negate = |v: u128| v.wrapping_neg() & mask(self.width);
+        Self::new(self.width, negate(self.upper.wrapping_sub(1)), negate(self.lower).wrapping_add(1))
+    }
+
+    /// Products of members, bounded by the unsigned extremes
+    pub fn multiply(&self, other: &Self) -> Self {
+        if self.is_empty_set() || other.is_empty_set() {
+            return Self::empty(self.width);
+        }
+        if self.width > 64 {
+            return Self::full(self.width);
+        }
+        match self.unsigned_max().checked_mul(other.unsigned_max()) {
+            Some(max) if max <= mask(self.width) => {
+                Self::from_unsigned(self.width, self.unsigned_min() * other.unsigned_min(), max)
+            }
+            _ => Self::full(self.width),
+        }
+    }
+
+    /// Unsigned quotients of members; division by zero contributes nothing
+    pub fn udiv(&self, other: &Self) -> Self {
+        if self.is_empty_set() || other.is_empty_set() || other.unsigned_max() == 0 {
+            return Self::empty(self.width);
+        }
+        let divisor_min = other.unsigned_min().max(1);
+        Self::from_unsigned(self.width,
+            self.unsigned_min() / other.unsigned_max(), self.unsigned_max() / divisor_min)
+    }
+
+    /// Unsigned maxima of pairs of members
+    pub fn umax(&self, other: &Self) -> Self {
+        if self.is_empty_set() || other.is_empty_set() {
+            return Self::empty(self.width);
+        }
+        Self::from_unsigned(self.width,
+            self.unsigned_min().max(other.unsigned_min()), self.unsigned_max().max(other.unsigned_max()))
+    }
+
+    /// Signed maxima of pairs of members
+    pub fn smax(&self, other: &Self) -> Self {
+        if self.is_empty_set() || other.is_empty_set() {
+            return Self::empty(self.width);
+        }
+        Self::from_signed(self.width,
+            self.signed_min().max(other.signed_min()), self.signed_max().max(other.signed_max()))
+    }
+}
+
+/// Ranges merge by union, from the empty set up to the full set
+impl Lattice for ConstantRange {
+    fn join(&mut self, other: &Self) -> bool {
+        let joined = self.union_with(other);
+        let changed = joined != *self;
+        *self = joined;
+        changed
+    }
+}
+
+impl fmt::Display for ConstantRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_full_set() {
+            write!(f, "full-set")
+        } else if self.is_empty_set() {
+            write!(f, "empty-set")
+        } else {
+            write!(f, "[{},{})", self.lower, self.upper)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounds() {
+        let range = ConstantRange::from_signed(8, -2, 3);
+        assert!(range.is_wrapped_set());
+        assert_eq!((range.signed_min(), range.signed_max()), (-2, 3));
+        assert_eq!((range.unsigned_min(), range.unsigned_max()), (0, 255));
+        assert!(range.contains(0xFE) && range.contains(3) && !range.contains(4));
+        assert_eq!(ConstantRange::single(8, 7).single_element(), Some(7));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let a = ConstantRange::from_unsigned(8, 1, 10);
+        let b = ConstantRange::from_unsigned(8, 2, 3);
+        assert_eq!(a.add(&b), ConstantRange::from_unsigned(8, 3, 13));
+        assert_eq!(a.multiply(&b), ConstantRange::from_unsigned(8, 2, 30));
+        assert_eq!(a.udiv(&b), ConstantRange::from_unsigned(8, 0, 5));
+        assert_eq!(a.sub(&b), ConstantRange::from_signed(8, -2, 8));
+        assert!(ConstantRange::from_unsigned(8, 0, 200).add(&ConstantRange::from_unsigned(8, 0, 100)).is_full_set());
+        assert!(ConstantRange::from_unsigned(8, 16, 20).multiply(&ConstantRange::from_unsigned(8, 16, 20)).is_full_set());
+    }
+
+    #[test]
+    fn test_intersect_and_casts() {
+        let wrapped = ConstantRange::from_signed(8, -10, 10);
+        assert_eq!(wrapped.intersect_with(&ConstantRange::from_unsigned(8, 5, 100)), ConstantRange::from_unsigned(8, 5, 10));
+        // The pieces [0,7] and [248,255] meet around the wrap
+        assert_eq!(wrapped.intersect_with(&ConstantRange::new(8, 248, 8)), ConstantRange::from_signed(8, -8, 7));
+        // [0,10] and [246,250] are covered by the wrapped range between them
+        assert_eq!(wrapped.intersect_with(&ConstantRange::from_unsigned(8, 0, 250)), wrapped);
+        assert!(ConstantRange::single(8, 3).intersect_with(&ConstantRange::single(8, 4)).is_empty_set());
+
+        assert_eq!(wrapped.zero_extend(16), ConstantRange::from_unsigned(16, 0, 255));
+        assert_eq!(wrapped.sign_extend(16), ConstantRange::from_signed(16, -10, 10));
+        assert_eq!(ConstantRange::from_unsigned(16, 250, 260).truncate(8), ConstantRange::new(8, 250, 5));
+        assert!(ConstantRange::from_unsigned(16, 0, 256).truncate(8).is_full_set());
+    }
+
+    #[test]
+    fn test_icmp() {
+        let small = ConstantRange::from_unsigned(8, 0, 9);
+        assert_eq!(ConstantRange::make_allowed_icmp_region(IntPredicate::ULT, &ConstantRange::single(8, 10)), small.union_with(&ConstantRange::single(8, 9)));
+        assert_eq!(ConstantRange::make_allowed_icmp_region(IntPredicate::SGE, &ConstantRange::single(8, 0)), ConstantRange::from_signed(8, 0, 127));
+        assert!(ConstantRange::make_allowed_icmp_region(IntPredicate::UGT, &ConstantRange::single(8, 255)).is_empty_set());
+
+        let ten = ConstantRange::single(8, 10);
+        assert_eq!(small.icmp(IntPredicate::ULT, &ten), Some(true));
+        assert_eq!(small.icmp(IntPredicate::UGE, &ten), Some(false));
+        assert_eq!(small.icmp(IntPredicate::NE, &ten), Some(true));
+        assert_eq!(small.icmp(IntPredicate::EQ, &ConstantRange::single(8, 3)), None);
+        // 0..=9 and -1 compare differently signed and unsigned
+        let minus_one = ConstantRange::single(8, 255);
+        assert_eq!(small.icmp(IntPredicate::SGT, &minus_one), Some(true));
+        assert_eq!(small.icmp(IntPredicate::UGT, &minus_one), Some(false));
+    }
+}
@@ -0,0 +1,183 @@
+//! Dataflow Framework
+//!
+//! A generic solver for dataflow problems over the blocks of a `CFG`. An
+//! analysis gives a lattice of facts, the facts at the boundary of the
+//! function, and a transfer function per block; the solver iterates a
+//! worklist until no fact changes. Facts can be adjusted along each edge,
+//! which is where SSA analyses account for phis.
+
+use std::collections::{BTreeSet, HashSet, VecDeque};
+use std::hash::Hash;
+use crate::cfg::CFG;
+
+/// Direction facts flow in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// From the entry block along the edges
+    Forward,
+    /// From the exits against the edges
+    Backward,
+}
+
+/// Facts that can be merged where control flow meets
+pub trait Lattice: Clone + PartialEq {
+    /// Merge `other` into this value, moving up the lattice; returns
+    /// whether the value changed
+    fn join(&mut self, other: &Self) -> bool;
+}
+
+impl<T: Clone + Eq + Hash> Lattice for HashSet<T> {
+    fn join(&mut self, other: &Self) -> bool {
+        let before = self.len();
+        self.extend(other.iter().cloned());
+        self.len() != before
+    }
+}
+
+impl<T: Clone + Ord> Lattice for BTreeSet<T> {
+    fn join(&mut self, other: &Self) -> bool {
+        let before = self.len();
+        self.extend(other.iter().cloned());
+        self.len() != before
+    }
+}
+
+/// A dataflow problem over the blocks of a CFG
+pub trait DataflowAnalysis {
+    type Domain: Lattice;
+
+    fn direction(&self) -> Direction;
+
+    /// Facts entering the entry block for a forward analysis, or leaving
+    /// the blocks without successors for a backward one
+    fn boundary(&self) -> Self::Domain;
+
+    /// Facts before anything is known, the least element of the lattice
+    fn bottom(&self) -> Self::Domain;
+
+    /// Facts on the other side of a block, from those at its start for a
+    /// forward analysis or at its end for a backward one
+    fn transfer(&self, block: usize, input: &Self::Domain) -> Self::Domain;
+
+    /// Facts passed along the edge from `from` to `to`; `facts` are those
+    /// leaving `from` for a forward analysis and entering `to` for a
+    /// backward one
+    fn transfer_edge(&self, from: usize, to: usize, facts: &Self::Domain) -> Self::Domain {
+        let _ = (from, to);
+        facts.clone()
+    }
+}
+
+/// Fixed point of a dataflow problem
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataflowResult<D> {
+    /// Facts at the start of each block
+    pub block_in: Vec<D>,
+    /// Facts at the end of each block
+    pub block_out: Vec<D>,
+}
+
+/// Solve a dataflow problem with a worklist, visiting blocks in reverse
+/// postorder for forward problems and postorder for backward ones.
+/// Unreachable blocks are solved too.
+pub fn solve<A: DataflowAnalysis>(analysis: &A, cfg: &CFG) -> DataflowResult<A::Domain> {
+    let n = cfg.num_blocks();
+    let mut block_in = vec![analysis.bottom(); n];
+    let mut block_out = vec![analysis.bottom(); n];
+    let forward = analysis.direction() == Direction::Forward;
+
+    let mut order = cfg.reverse_postorder();
+    let mut visited = vec![false; n];
+    for &block in &order {
+        visited[block] = true;
+    }
+    order.extend((0..n).filter(|&block| !visited[block]));
+    if !forward {
+        order.reverse();
+    }
+
+    let mut queued = vec![true; n];
+    let mut worklist: VecDeque<usize> = order.into_iter().collect();
+    while let Some(block) = worklist.pop_front() {
+        queued[block] = false;
+        if forward {
+            let mut input = if block == 0 { analysis.boundary() } else { analysis.bottom() };
+            for &pred in cfg.predecessors(block) {
+                input.join(&analysis.transfer_edge(pred, block, &block_out[pred]));
+            }
+            let output = analysis.transfer(block, &input);
+            block_in[block] = input;
+            if output != block_out[block] {
+                block_out[block] = output;
+                for &succ in cfg.successors(block) {
+                    if !queued[succ] {
+                        queued[succ] = true;
+                        worklist.push_back(succ);
+                    }
+                }
+            }
+        } else {
+            let successors = cfg.successors(block);
+            let mut output = if successors.is_empty() { analysis.boundary() } else { analysis.bottom() };
+            for &succ in successors {
+                output.join(&analysis.transfer_edge(block, succ, &block_in[succ]));
+            }
+            let input = analysis.transfer(block, &output);
+            block_out[block] = output;
+            if input != block_in[block] {
+                block_in[block] = input;
+                for &pred in cfg.predecessors(block) {
+                    if !queued[pred] {
+                        queued[pred] = true;
+                        worklist.push_back(pred);
+                    }
+                }
+            }
+        }
+    }
+
+    DataflowResult { block_in, block_out }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::Context;
+
+    /// Blocks on some path from the entry to each block, inclusive
+    struct Reaching;
+
+    impl DataflowAnalysis for Reaching {
+        type Domain = BTreeSet<usize>;
+
+        fn direction(&self) -> Direction {
+            Direction::Forward
+        }
+
+        fn boundary(&self) -> Self::Domain {
+            BTreeSet::new()
+        }
+
+        fn bottom(&self) -> Self::Domain {
+            BTreeSet::new()
+        }
+
+        fn transfer(&self, block: usize, input: &Self::Domain) -> Self::Domain {
+            let mut output = input.clone();
+            output.insert(block);
+            output
+        }
+    }
+
+    #[test]
+    fn test_forward_solve_with_loop() {
+        let ir = "define void @f(i1 %c) {\nentry:\n  br label %loop\nloop:\n  br i1 %c, label %loop, label %exit\n\
+            exit:\n  ret void\n}\n";
+        let module = Parser::new(Context::new()).parse_module(ir).unwrap();
+        let cfg = CFG::from_function(&module.get_function("f").unwrap());
+        let result = solve(&Reaching, &cfg);
+        assert_eq!(result.block_in[1], BTreeSet::from([0, 1]));
+        assert_eq!(result.block_out[2], BTreeSet::from([0, 1, 2]));
+    }
+}
@@ -0,0 +1,355 @@
+//! Verifier Diagnostics
+//!
+//! Every check of the verifier reports under a rule with a stable ID and a
+//! category. `VerifierOptions` decides which rules are reported and with
+//! what severity: rules are errors unless overridden, so a new or stricter
+//! rule can be rolled out as a warning first and promoted once the IR it
+//! runs on is clean. Diagnostics render as text or as JSON for CI gating.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use crate::verification::VerificationError;
+
+/// How a diagnostic affects the verification result
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Area of the IR a rule checks, for enabling or disabling rules in bulk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RuleCategory {
+    Structure,
+    ControlFlow,
+    Ssa,
+    Types,
+    Instructions,
+    ExceptionHandling,
+    Intrinsics,
+    Attributes,
+    CallingConvention,
+    Metadata,
+    DebugInfo,
+}
+
+impl RuleCategory {
+    pub const ALL: [RuleCategory; 11] = [
+        RuleCategory::Structure,
+        RuleCategory::ControlFlow,
+        RuleCategory::Ssa,
+        RuleCategory::Types,
+        RuleCategory::Instructions,
+        RuleCategory::ExceptionHandling,
+        RuleCategory::Intrinsics,
+        RuleCategory::Attributes,
+        RuleCategory::CallingConvention,
+        RuleCategory::Metadata,
+        RuleCategory::DebugInfo,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            RuleCategory::Structure => "structure",
+            RuleCategory::ControlFlow => "control-flow",
+            RuleCategory::Ssa => "ssa",
+            RuleCategory::Types => "types",
+            RuleCategory::Instructions => "instructions",
+            RuleCategory::ExceptionHandling => "exception-handling",
+            RuleCategory::Intrinsics => "intrinsics",
+            RuleCategory::Attributes => "attributes",
+            RuleCategory::CallingConvention => "calling-convention",
+            RuleCategory::Metadata => "metadata",
+            RuleCategory::DebugInfo => "debug-info",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|category| category.name() == name)
+    }
+}
+
+impl fmt::Display for RuleCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A verifier check. IDs are stable: rename a variant freely, never its ID.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Rule {
+    GlobalRedefinition,
+    GlobalVariable,
+    Alias,
+    LinkageVisibility,
+    EntryBlock,
+    Terminator,
+    ControlFlowGraph,
+    PhiPlacement,
+    SwitchCases,
+    SsaForm,
+    FunctionSignature,
+    ReturnType,
+    Cast,
+    OperandTypes,
+    GepIndexing,
+    Instruction,
+    PhiNode,
+    CallSignature,
+    Atomic,
+    Alignment,
+    ExceptionHandling,
+    LandingPad,
+    IntrinsicDefinition,
+    IntrinsicSignature,
+    IntrinsicImmArg,
+    IntrinsicOperands,
+    FunctionAttributes,
+    ReturnAttributes,
+    ParameterAttributes,
+    StringAttributes,
+    AllocAttributes,
+    AttributeExclusivity,
+    AttributeType,
+    CallingConvention,
+    ModuleFlags,
+    NamedMetadata,
+    InstructionMetadata,
+    MetadataReference,
+    MetadataCycle,
+    DebugInfoNamedMetadata,
+    DebugInfoNode,
+    DebugInfoSubrange,
+    DebugInfoCompositeType,
+    DebugInfoExpression,
+    DebugInfoAttachment,
+}
+
+/// ID, category and summary of each rule
+const RULES: &[(Rule, &str, RuleCategory, &str)] = &[
+    (Rule::GlobalRedefinition, "global-redefinition", RuleCategory::Structure, "global names are defined once"),
+    (Rule::GlobalVariable, "global-variable", RuleCategory::Structure, "global variable types, initializers, linkage and comdats"),
+    (Rule::Alias, "alias", RuleCategory::Structure, "alias targets and linkage"),
+    (Rule::LinkageVisibility, "linkage-visibility", RuleCategory::Structure, "linkage, visibility and DLL storage combinations"),
+    (Rule::EntryBlock, "entry-block", RuleCategory::Structure, "function definitions have an entry block"),
+    (Rule::Terminator, "terminator", RuleCategory::ControlFlow, "each block ends in exactly one terminator"),
+    (Rule::ControlFlowGraph, "cfg", RuleCategory::ControlFlow, "branch targets exist and the entry block has no predecessors"),
+    (Rule::PhiPlacement, "phi-placement", RuleCategory::ControlFlow, "phi nodes lead their block with one entry per predecessor"),
+    (Rule::SwitchCases, "switch-cases", RuleCategory::ControlFlow, "switch case values are unique"),
+    (Rule::SsaForm, "ssa-form", RuleCategory::Ssa, "values are defined once and dominate their uses"),
+    (Rule::FunctionSignature, "function-signature", RuleCategory::Types, "parameter and return types are valid for the function"),
+    (Rule::ReturnType, "return-type", RuleCategory::Types, "returned values match the function return type"),
+    (Rule::Cast, "cast", RuleCategory::Types, "cast source and destination types are compatible"),
+    (Rule::OperandTypes, "operand-types", RuleCategory::Types, "instruction operands have matching types"),
+    (Rule::GepIndexing, "gep-indexing", RuleCategory::Types, "getelementptr indices are valid for the source type"),
+    (Rule::Instruction, "instruction", RuleCategory::Instructions, "instruction operands and result are well-formed"),
+    (Rule::PhiNode, "phi-node", RuleCategory::Instructions, "phi operands pair values with blocks"),
+    (Rule::CallSignature, "call-signature", RuleCategory::Instructions, "calls pass the callee's argument count"),
+    (Rule::Atomic, "atomic", RuleCategory::Instructions, "atomic orderings and operand types"),
+    (Rule::Alignment, "alignment", RuleCategory::Instructions, "alignments are powers of two within range"),
+    (Rule::ExceptionHandling, "exception-handling", RuleCategory::ExceptionHandling, "invoke and resume are used correctly"),
+    (Rule::LandingPad, "landingpad", RuleCategory::ExceptionHandling, "landing pads are the first non-phi instruction of their block"),
+    (Rule::IntrinsicDefinition, "intrinsic-definition", RuleCategory::Intrinsics, "intrinsics are declared, never defined"),
+    (Rule::IntrinsicSignature, "intrinsic-signature", RuleCategory::Intrinsics, "intrinsic calls match the intrinsic's signature"),
+    (Rule::IntrinsicImmArg, "intrinsic-immarg", RuleCategory::Intrinsics, "immarg operands are constants in range"),
+    (Rule::IntrinsicOperands, "intrinsic-operands", RuleCategory::Intrinsics, "operand constraints of individual intrinsics"),
+    (Rule::FunctionAttributes, "function-attributes", RuleCategory::Attributes, "function attributes are compatible and in range"),
+    (Rule::ReturnAttributes, "return-attributes", RuleCategory::Attributes, "return attributes apply to the return type"),
+    (Rule::ParameterAttributes, "parameter-attributes", RuleCategory::Attributes, "parameter attributes apply to the parameter type"),
+    (Rule::StringAttributes, "string-attributes", RuleCategory::Attributes, "values of string attributes are well-formed"),
+    (Rule::AllocAttributes, "alloc-attributes", RuleCategory::Attributes, "allocsize, allockind and alloc-variant-zeroed"),
+    (Rule::AttributeExclusivity, "attribute-exclusivity", RuleCategory::Attributes, "mutually exclusive parameter attributes"),
+    (Rule::AttributeType, "attribute-type", RuleCategory::Attributes, "parameter attributes that require particular types"),
+    (Rule::CallingConvention, "calling-convention", RuleCategory::CallingConvention, "signature and call restrictions of calling conventions"),
+    (Rule::ModuleFlags, "module-flags", RuleCategory::Metadata, "llvm.module.flags entries are well-formed"),
+    (Rule::NamedMetadata, "named-metadata", RuleCategory::Metadata, "llvm.ident, llvm.commandline and other named metadata"),
+    (Rule::InstructionMetadata, "instruction-metadata", RuleCategory::Metadata, "metadata attached to instructions"),
+    (Rule::MetadataReference, "metadata-reference", RuleCategory::Metadata, "metadata references resolve"),
+    (Rule::MetadataCycle, "metadata-cycle", RuleCategory::Metadata, "scope and inlinedAt chains are acyclic"),
+    (Rule::DebugInfoNamedMetadata, "di-named-metadata", RuleCategory::DebugInfo, "llvm.dbg.* named metadata"),
+    (Rule::DebugInfoNode, "di-node", RuleCategory::DebugInfo, "units, scopes and files of debug info nodes"),
+    (Rule::DebugInfoSubrange, "di-subrange", RuleCategory::DebugInfo, "DISubrange and DIGenericSubrange bounds"),
+    (Rule::DebugInfoCompositeType, "di-composite-type", RuleCategory::DebugInfo, "DICompositeType fields"),
+    (Rule::DebugInfoExpression, "di-expression", RuleCategory::DebugInfo, "DIExpression operations"),
+    (Rule::DebugInfoAttachment, "di-attachment", RuleCategory::DebugInfo, "!dbg attachments of functions and instructions"),
+];
+
+impl Rule {
+    fn entry(self) -> &'static (Rule, &'static str, RuleCategory, &'static str) {
+        RULES.iter().find(|entry| entry.0 == self).expect("every rule is in RULES")
+    }
+
+    /// Every rule, in the order of the table
+    pub fn all() -> impl Iterator<Item = Rule> {
+        RULES.iter().map(|entry| entry.0)
+    }
+
+    pub fn id(self) -> &'static str {
+        self.entry().1
+    }
+
+    pub fn category(self) -> RuleCategory {
+        self.entry().2
+    }
+
+    pub fn description(self) -> &'static str {
+        self.entry().3
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        RULES.iter().find(|entry| entry.1 == id).map(|entry| entry.0)
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+/// Which rules the verifier reports, and how severely
+#[derive(Debug, Clone, Default)]
+pub struct VerifierOptions {
+    /// Categories whose rules are not reported
+    pub disabled_categories: HashSet<RuleCategory>,
+    /// Individual rules that are not reported
+    pub disabled_rules: HashSet<Rule>,
+    /// Severity overrides; rules not listed are errors
+    pub severities: HashMap<Rule, Severity>,
+    /// Report warnings as errors
+    pub warnings_as_errors: bool,
+}
+
+impl VerifierOptions {
+    pub fn is_enabled(&self, rule: Rule) -> bool {
+        !self.disabled_rules.contains(&rule) && !self.disabled_categories.contains(&rule.category())
+    }
+
+    pub fn severity(&self, rule: Rule) -> Severity {
+        match self.severities.get(&rule) {
+            Some(Severity::Warning) if !self.warnings_as_errors => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+
+    /// The diagnostic for `error` found by `rule`, or `None` if the rule is disabled
+    pub fn diagnostic(&self, rule: Rule, error: VerificationError) -> Option<Diagnostic> {
+        self.is_enabled(rule).then(|| Diagnostic { rule, severity: self.severity(rule), error })
+    }
+}
+
+/// A verifier finding and the rule that reported it
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub rule: Rule,
+    pub severity: Severity,
+    pub error: VerificationError,
+}
+
+impl Diagnostic {
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// One JSON object with the rule, category, severity, location and message
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"rule\":{},\"category\":{},\"severity\":{},\"location\":{},\"message\":{}}}",
+            json_string(self.rule.id()),
+            json_string(self.rule.category().name()),
+            json_string(self.severity.name()),
+            json_string(self.error.location()),
+            json_string(&self.error.to_string()),
+        )
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}[{}]: {}", self.severity, self.rule, self.error)
+    }
+}
+
+/// A JSON report with error and warning counts and every diagnostic
+pub fn diagnostics_to_json(diagnostics: &[Diagnostic]) -> String {
+    let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+    let entries: Vec<String> = diagnostics.iter().map(Diagnostic::to_json).collect();
+    format!(
+        "{{\"errors\":{},\"warnings\":{},\"diagnostics\":[{}]}}",
+        errors,
+        diagnostics.len() - errors,
+        entries.join(","),
+    )
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rule_ids_are_unique() {
+        let ids: HashSet<&str> = Rule::all().map(Rule::id).collect();
+        assert_eq!(ids.len(), RULES.len());
+        assert!(Rule::all().all(|rule| Rule::from_id(rule.id()) == Some(rule)));
+    }
+
+    #[test]
+    fn test_options_filter_and_downgrade() {
+        let error = || VerificationError::EntryBlockMissing { function: "f".to_string() };
+        let mut options = VerifierOptions::default();
+        assert_eq!(options.diagnostic(Rule::EntryBlock, error()).unwrap().severity, Severity::Error);
+
+        options.severities.insert(Rule::EntryBlock, Severity::Warning);
+        assert_eq!(options.diagnostic(Rule::EntryBlock, error()).unwrap().severity, Severity::Warning);
+        options.warnings_as_errors = true;
+        assert_eq!(options.diagnostic(Rule::EntryBlock, error()).unwrap().severity, Severity::Error);
+
+        options.disabled_categories.insert(RuleCategory::Structure);
+        assert!(options.diagnostic(Rule::EntryBlock, error()).is_none());
+    }
+
+    #[test]
+    fn test_json_escaping() {
+        let diagnostic = Diagnostic {
+            rule: Rule::NamedMetadata,
+            severity: Severity::Warning,
+            error: VerificationError::InvalidMetadata { reason: "bad \"name\"\n".to_string(), location: "!0".to_string() },
+        };
+        assert_eq!(diagnostic.to_json(), "{\"rule\":\"named-metadata\",\"category\":\"metadata\",\"severity\":\"warning\",\
+            \"location\":\"!0\",\"message\":\"Invalid metadata at !0: bad \\\"name\\\"\\n\"}");
+        assert_eq!(diagnostics_to_json(&[diagnostic]).find("\"errors\":0,\"warnings\":1"), Some(1));
+    }
+}
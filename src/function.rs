@@ -9,6 +9,7 @@ use std::fmt;
 use crate::types::Type;
 use crate::basic_block::BasicBlock;
 use crate::value::Value;
+use crate::metadata::Metadata;
 
 /// Calling convention
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -164,6 +165,15 @@ pub struct ParameterAttributes {
     pub memory: Option<String>,  // memory(...) attribute
 }
 
+/// Source of a function body that is parsed on first access
+///
+/// Lazily parsed modules attach one of these to each function definition
+/// instead of its basic blocks (see `Parser::parse_module_lazy`).
+pub trait Materializer: Send + Sync {
+    /// Parse and return the body of `function`
+    fn materialize(&self, function: &Function) -> Result<Vec<BasicBlock>, String>;
+}
+
 /// A function in LLVM IR
 #[derive(Clone)]
 pub struct Function {
@@ -181,6 +191,9 @@ struct FunctionData {
     visibility: crate::module::Visibility,
     dll_storage_class: crate::module::DLLStorageClass,
     personality: Option<Value>,
+    subprogram: Option<Metadata>,
+    materializer: Option<Arc<dyn Materializer>>,
+    materialize_error: Option<String>,
 }
 
 impl Function {
@@ -200,6 +213,9 @@ impl Function {
                 visibility: crate::module::Visibility::Default,
                 dll_storage_class: crate::module::DLLStorageClass::Default,
                 personality: None,
+                subprogram: None,
+                materializer: None,
+                materialize_error: None,
             })),
         }
     }
@@ -269,6 +285,16 @@ impl Function {
         self.data.write().unwrap().personality = personality;
     }
 
+    /// Get the DISubprogram attached with `!dbg`
+    pub fn subprogram(&self) -> Option<Metadata> {
+        self.data.read().unwrap().subprogram.clone()
+    }
+
+    /// Set the DISubprogram attached with `!dbg`
+    pub fn set_subprogram(&self, subprogram: Option<Metadata>) {
+        self.data.write().unwrap().subprogram = subprogram;
+    }
+
     /// Get the name of this function
     pub fn name(&self) -> String {
         self.data.read().unwrap().name.clone()
@@ -281,25 +307,77 @@ impl Function {
 
     /// Add a basic block to this function
     pub fn add_basic_block(&self, bb: BasicBlock) {
+        self.ensure_materialized();
         let mut data = self.data.write().unwrap();
         data.basic_blocks.push(bb);
     }
 
-    /// Get the basic blocks in this function
+    /// Get the basic blocks in this function, materializing a lazy body first
     pub fn basic_blocks(&self) -> Vec<BasicBlock> {
+        self.ensure_materialized();
         self.data.read().unwrap().basic_blocks.clone()
     }
 
     /// Get the entry basic block
     pub fn entry_block(&self) -> Option<BasicBlock> {
+        self.ensure_materialized();
         self.data.read().unwrap().basic_blocks.first().cloned()
     }
 
     /// Get the number of basic blocks in this function
     pub fn basic_block_count(&self) -> usize {
+        self.ensure_materialized();
         self.data.read().unwrap().basic_blocks.len()
     }
 
+    /// Defer this function's body to `materializer`, which runs on first access
+    pub fn set_materializer(&self, materializer: Arc<dyn Materializer>) {
+        let mut data = self.data.write().unwrap();
+        data.materializer = Some(materializer);
+        data.materialize_error = None;
+    }
+
+    /// Check if the body has not been parsed yet
+    pub fn is_materializable(&self) -> bool {
+        self.data.read().unwrap().materializer.is_some()
+    }
+
+    /// Parse a lazily loaded body now. Does nothing if the body is already present.
+    pub fn materialize(&self) -> Result<(), String> {
+        let materializer = self.data.read().unwrap().materializer.clone();
+        if let Some(materializer) = materializer {
+            // The lock is released while parsing so the materializer can
+            // inspect the function's signature and arguments
+            let result = materializer.materialize(self);
+            let mut data = self.data.write().unwrap();
+            if data.materializer.is_some() {
+                data.materializer = None;
+                match result {
+                    Ok(blocks) => data.basic_blocks = blocks,
+                    Err(e) => data.materialize_error = Some(e),
+                }
+            }
+        }
+
+        match &self.data.read().unwrap().materialize_error {
+            Some(e) => Err(e.clone()),
+            None => Ok(()),
+        }
+    }
+
+    /// Get the error from a failed materialization, if any
+    pub fn materialize_error(&self) -> Option<String> {
+        self.data.read().unwrap().materialize_error.clone()
+    }
+
+    /// Materialize on access. Failures leave the body empty and are reported
+    /// by `materialize_error`.
+    fn ensure_materialized(&self) {
+        if self.is_materializable() {
+            let _ = self.materialize();
+        }
+    }
+
     /// Set the function arguments
     pub fn set_arguments(&self, arguments: Vec<Value>) {
         let mut data = self.data.write().unwrap();
@@ -316,14 +394,16 @@ impl Function {
         self.data.read().unwrap().arguments.get(index).cloned()
     }
 
-    /// Check if this function has a body
+    /// Check if this function has a body (parsed or still pending materialization)
     pub fn has_body(&self) -> bool {
-        !self.data.read().unwrap().basic_blocks.is_empty()
+        let data = self.data.read().unwrap();
+        data.materializer.is_some() || !data.basic_blocks.is_empty()
     }
 }
 
 impl fmt::Display for Function {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.ensure_materialized();
         let data = self.data.read().unwrap();
 
         write!(f, "define {} @{}(", data.ty, data.name)?;
@@ -348,6 +428,9 @@ impl fmt::Display for Function {
 impl fmt::Debug for Function {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let data = self.data.read().unwrap();
+        if data.materializer.is_some() {
+            return write!(f, "Function(@{}, body not materialized)", data.name);
+        }
         write!(f, "Function(@{}, {} basic blocks)", data.name, data.basic_blocks.len())
     }
 }
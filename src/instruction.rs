@@ -6,6 +6,8 @@
 use std::fmt;
 use crate::value::Value;
 use crate::types::Type;
+use crate::metadata::Metadata;
+use crate::function::CallingConvention;
 
 /// Operand bundle for call/invoke instructions
 #[derive(Clone, Debug)]
@@ -21,6 +23,9 @@ pub struct Instruction {
     operands: Vec<Value>,
     result: Option<Value>,
     metadata_attachments: Vec<String>, // e.g., ["dbg", "llvm.access.group", "align"]
+    debug_loc: Option<Metadata>,
+    // Attached nodes other than `!dbg`, by kind
+    attached_metadata: Vec<(String, Metadata)>,
 
     // GEP-specific metadata
     gep_source_type: Option<Type>,
@@ -30,11 +35,25 @@ pub struct Instruction {
     is_volatile: bool,
     atomic_ordering: Option<AtomicOrdering>,
 
+    // Operation of an atomicrmw instruction
+    rmw_operation: Option<AtomicRMWBinOp>,
+
     // Alignment for load, store, alloca instructions
     alignment: Option<u64>,
 
+    // `inalloca` and `swifterror` flags of an alloca
+    is_inalloca: bool,
+    is_swifterror: bool,
+
     // Operand bundles for call/invoke instructions
     operand_bundles: Vec<OperandBundle>,
+
+    // Calling convention of call/invoke instructions
+    calling_convention: CallingConvention,
+
+    // Comparison predicate for icmp/fcmp instructions
+    int_predicate: Option<IntPredicate>,
+    float_predicate: Option<FloatPredicate>,
 }
 
 /// Instruction opcodes
@@ -137,12 +156,20 @@ impl Instruction {
             operands,
             result,
             metadata_attachments: Vec::new(),
+            debug_loc: None,
+            attached_metadata: Vec::new(),
             gep_source_type: None,
             is_atomic: false,
             is_volatile: false,
             atomic_ordering: None,
+            rmw_operation: None,
             alignment: None,
+            is_inalloca: false,
+            is_swifterror: false,
             operand_bundles: Vec::new(),
+            calling_convention: CallingConvention::C,
+            int_predicate: None,
+            float_predicate: None,
         }
     }
 
@@ -156,6 +183,36 @@ impl Instruction {
         &self.metadata_attachments
     }
 
+    /// Source location attached with `!dbg`
+    pub fn debug_loc(&self) -> Option<&Metadata> {
+        self.debug_loc.as_ref()
+    }
+
+    /// Set the `!dbg` source location
+    pub fn set_debug_loc(&mut self, location: Option<Metadata>) {
+        self.debug_loc = location;
+    }
+
+    /// Node attached with `!kind`, such as `range`; numbered nodes may be
+    /// references to resolve in the module
+    pub fn metadata(&self, kind: &str) -> Option<&Metadata> {
+        self.attached_metadata.iter().find(|(name, _)| name == kind).map(|(_, node)| node)
+    }
+
+    /// Attached nodes other than `!dbg`, by kind
+    pub fn attached_metadata(&self) -> &[(String, Metadata)] {
+        &self.attached_metadata
+    }
+
+    /// Attach a node with `!kind`, replacing any node of that kind
+    pub fn set_metadata(&mut self, kind: String, node: Metadata) {
+        self.attached_metadata.retain(|(name, _)| *name != kind);
+        if !self.has_metadata(&kind) {
+            self.metadata_attachments.push(kind.clone());
+        }
+        self.attached_metadata.push((kind, node));
+    }
+
     /// Check if instruction has a specific metadata attachment
     pub fn has_metadata(&self, name: &str) -> bool {
         self.metadata_attachments.iter().any(|m| m == name)
@@ -293,6 +350,26 @@ impl Instruction {
         self.is_volatile
     }
 
+    /// Mark an alloca as used by an `inalloca` argument
+    pub fn set_inalloca(&mut self, is_inalloca: bool) {
+        self.is_inalloca = is_inalloca;
+    }
+
+    /// Check if this alloca is used by an `inalloca` argument
+    pub fn is_inalloca(&self) -> bool {
+        self.is_inalloca
+    }
+
+    /// Mark an alloca as holding a Swift error value
+    pub fn set_swifterror(&mut self, is_swifterror: bool) {
+        self.is_swifterror = is_swifterror;
+    }
+
+    /// Check if this alloca holds a Swift error value
+    pub fn is_swifterror(&self) -> bool {
+        self.is_swifterror
+    }
+
     /// Set the atomic ordering
     pub fn set_atomic_ordering(&mut self, ordering: AtomicOrdering) {
         self.atomic_ordering = Some(ordering);
@@ -315,10 +392,52 @@ impl Instruction {
         self.operand_bundles.iter().find(|b| b.tag == tag)
     }
 
+    /// Set the calling convention of a call or invoke
+    pub fn set_calling_convention(&mut self, cc: CallingConvention) {
+        self.calling_convention = cc;
+    }
+
+    /// Get the calling convention of a call or invoke
+    pub fn calling_convention(&self) -> CallingConvention {
+        self.calling_convention
+    }
+
     /// Get the atomic ordering
     pub fn atomic_ordering(&self) -> Option<AtomicOrdering> {
         self.atomic_ordering
     }
+
+    /// Set the operation of an atomicrmw instruction
+    pub fn set_rmw_operation(&mut self, operation: AtomicRMWBinOp) {
+        self.rmw_operation = Some(operation);
+    }
+
+    /// Get the operation of an atomicrmw instruction
+    pub fn rmw_operation(&self) -> Option<AtomicRMWBinOp> {
+        self.rmw_operation
+    }
+
+    // Comparison predicate accessors
+
+    /// Set the predicate of an icmp instruction
+    pub fn set_int_predicate(&mut self, predicate: IntPredicate) {
+        self.int_predicate = Some(predicate);
+    }
+
+    /// Get the predicate of an icmp instruction
+    pub fn int_predicate(&self) -> Option<IntPredicate> {
+        self.int_predicate
+    }
+
+    /// Set the predicate of an fcmp instruction
+    pub fn set_float_predicate(&mut self, predicate: FloatPredicate) {
+        self.float_predicate = Some(predicate);
+    }
+
+    /// Get the predicate of an fcmp instruction
+    pub fn float_predicate(&self) -> Option<FloatPredicate> {
+        self.float_predicate
+    }
 }
 
 impl fmt::Display for Instruction {
@@ -364,9 +483,48 @@ pub enum IntPredicate {
     SLE, // signed less or equal
 }
 
+impl IntPredicate {
+    /// The predicate that holds exactly when this one does not
+    pub fn inverse(self) -> Self {
+        match self {
+            IntPredicate::EQ => IntPredicate::NE,
+            IntPredicate::NE => IntPredicate::EQ,
+            IntPredicate::UGT => IntPredicate::ULE,
+            IntPredicate::UGE => IntPredicate::ULT,
+            IntPredicate::ULT => IntPredicate::UGE,
+            IntPredicate::ULE => IntPredicate::UGT,
+            IntPredicate::SGT => IntPredicate::SLE,
+            IntPredicate::SGE => IntPredicate::SLT,
+            IntPredicate::SLT => IntPredicate::SGE,
+            IntPredicate::SLE => IntPredicate::SGT,
+        }
+    }
+
+    /// The predicate that holds for the operands in the other order
+    pub fn swapped(self) -> Self {
+        match self {
+            IntPredicate::UGT => IntPredicate::ULT,
+            IntPredicate::UGE => IntPredicate::ULE,
+            IntPredicate::ULT => IntPredicate::UGT,
+            IntPredicate::ULE => IntPredicate::UGE,
+            IntPredicate::SGT => IntPredicate::SLT,
+            IntPredicate::SGE => IntPredicate::SLE,
+            IntPredicate::SLT => IntPredicate::SGT,
+            IntPredicate::SLE => IntPredicate::SGE,
+            predicate => predicate,
+        }
+    }
+
+    /// Whether the predicate compares as signed integers
+    pub fn is_signed(self) -> bool {
+        matches!(self, IntPredicate::SGT | IntPredicate::SGE | IntPredicate::SLT | IntPredicate::SLE)
+    }
+}
+
 /// Floating point comparison predicates
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FloatPredicate {
+    FALSE, // always false
     OEQ, // ordered and equal
     OGT, // ordered and greater than
     OGE, // ordered and greater than or equal
@@ -381,6 +539,7 @@ pub enum FloatPredicate {
     ULT, // unordered or less than
     ULE, // unordered or less than or equal
     UNE, // unordered or not equal
+    TRUE, // always true
 }
 
 /// Atomic ordering constraints
@@ -411,6 +570,76 @@ pub enum AtomicRMWBinOp {
     UMin,
     FAdd,
     FSub,
+    FMax,
+    FMin,
+    FMaximum,
+    FMinimum,
+    UIncWrap,
+    UDecWrap,
+    USubCond,
+    USubSat,
+}
+
+impl AtomicRMWBinOp {
+    /// Look up an operation by its name in the textual IR
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "xchg" => Self::Xchg,
+            "add" => Self::Add,
+            "sub" => Self::Sub,
+            "and" => Self::And,
+            "nand" => Self::Nand,
+            "or" => Self::Or,
+            "xor" => Self::Xor,
+            "max" => Self::Max,
+            "min" => Self::Min,
+            "umax" => Self::UMax,
+            "umin" => Self::UMin,
+            "fadd" => Self::FAdd,
+            "fsub" => Self::FSub,
+            "fmax" => Self::FMax,
+            "fmin" => Self::FMin,
+            "fmaximum" => Self::FMaximum,
+            "fminimum" => Self::FMinimum,
+            "uinc_wrap" => Self::UIncWrap,
+            "udec_wrap" => Self::UDecWrap,
+            "usub_cond" => Self::USubCond,
+            "usub_sat" => Self::USubSat,
+            _ => return None,
+        })
+    }
+
+    /// Name of the operation in the textual IR
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Xchg => "xchg",
+            Self::Add => "add",
+            Self::Sub => "sub",
+            Self::And => "and",
+            Self::Nand => "nand",
+            Self::Or => "or",
+            Self::Xor => "xor",
+            Self::Max => "max",
+            Self::Min => "min",
+            Self::UMax => "umax",
+            Self::UMin => "umin",
+            Self::FAdd => "fadd",
+            Self::FSub => "fsub",
+            Self::FMax => "fmax",
+            Self::FMin => "fmin",
+            Self::FMaximum => "fmaximum",
+            Self::FMinimum => "fminimum",
+            Self::UIncWrap => "uinc_wrap",
+            Self::UDecWrap => "udec_wrap",
+            Self::USubCond => "usub_cond",
+            Self::USubSat => "usub_sat",
+        }
+    }
+
+    /// Check if this operation works on floating point values
+    pub fn is_floating_point(&self) -> bool {
+        matches!(self, Self::FAdd | Self::FSub | Self::FMax | Self::FMin | Self::FMaximum | Self::FMinimum)
+    }
 }
 
 /// Fast math flags for floating point operations
@@ -2,279 +2,531 @@
 //!
 //! Intrinsics are special built-in functions that the compiler can optimize
 //! specially. This includes memory operations, math functions, and more.
+//!
+//! Every intrinsic is one entry of the `intrinsics!` table below, which
+//! generates both the `Intrinsic` enum and its `IntrinsicInfo`: the name,
+//! the type constraints of the result and parameters, immarg positions,
+//! memory effects and attributes. Names, overloading, name mangling and the
+//! verifier's signature checks are all derived from that entry.
 
 use std::fmt;
+use crate::attributes::FunctionAttribute;
+use crate::types::{FloatKind, Type};
+use IntrinsicType::*;
 
-/// LLVM intrinsic functions
+/// Type constraint on an intrinsic result or parameter
+///
+/// The `Any*` constraints are overloaded: each one opens an overload slot,
+/// numbered in order of appearance from the result through the parameters,
+/// and its type becomes part of the mangled name. The remaining
+/// constraints either name a fixed type or are derived from a slot.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Intrinsic {
+pub enum IntrinsicType {
+    Void,
+    /// Integer of the given width
+    Int(u32),
+    /// Pointer in address space 0
+    Ptr,
+    Token,
+    Metadata,
+    /// Any integer or vector of integers
+    AnyInt,
+    /// Any floating point type or vector of them
+    AnyFloat,
+    /// Any vector
+    AnyVector,
+    /// Any vector of integers
+    AnyIntVector,
+    /// Any vector of floating point values
+    AnyFloatVector,
+    /// Any pointer
+    AnyPtr,
+    /// Any type
+    Any,
+    /// The type bound to the given slot
+    Match(usize),
+    /// The element type of the vector bound to the given slot
+    ElementOf(usize),
+    /// `i1`, or a vector of `i1` as wide as the vector bound to the given slot
+    MaskOf(usize),
+    /// `{ T, M }` where `T` is the type bound to the given slot and `M` its mask type
+    OverflowOf(usize),
+}
+
+impl IntrinsicType {
+    fn is_overloaded(self) -> bool {
+        matches!(self, AnyInt | AnyFloat | AnyVector | AnyIntVector | AnyFloatVector | AnyPtr | Any)
+    }
+
+    /// Check an overloaded constraint against `ty`
+    fn accepts(self, ty: &Type) -> bool {
+        let element_is = |f: fn(&Type) -> bool| ty.vector_info().is_some_and(|(element, _)| f(element));
+        match self {
+            AnyInt => ty.is_integer() || element_is(Type::is_integer),
+            AnyFloat => ty.is_float() || element_is(Type::is_float),
+            AnyVector => ty.is_vector(),
+            AnyIntVector => element_is(Type::is_integer),
+            AnyFloatVector => element_is(Type::is_float),
+            AnyPtr => ty.is_pointer(),
+            Any => true,
+            _ => false,
+        }
+    }
+
+    /// Check a fixed or derived constraint against `ty`, given the bound slots
+    fn matches(self, ty: &Type, slots: &[Type]) -> bool {
+        let is_mask_of = |ty: &Type, slot: &Type| match (ty.vector_info(), slot.vector_info()) {
+            (Some((element, size)), Some((_, slot_size))) => element.int_width() == Some(1) && size == slot_size,
+            (None, None) => ty.int_width() == Some(1),
+            _ => false,
+        };
+        match self {
+            Void => ty.is_void(),
+            Int(bits) => ty.int_width() == Some(bits),
+            Ptr => ty.address_space() == Some(0),
+            Token => ty.is_token(),
+            Metadata => ty.is_metadata(),
+            Match(slot) => slots.get(slot) == Some(ty),
+            ElementOf(slot) => slots.get(slot).and_then(|s| s.vector_info()).is_some_and(|(element, _)| element == ty),
+            MaskOf(slot) => slots.get(slot).is_some_and(|s| is_mask_of(ty, s)),
+            OverflowOf(slot) => match (slots.get(slot), ty.struct_fields()) {
+                (Some(s), Some(fields)) => fields.len() == 2 && fields[0] == *s && is_mask_of(&fields[1], s),
+                _ => false,
+            },
+            _ => self.accepts(ty),
+        }
+    }
+}
+
+/// Memory an intrinsic may access
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryEffects {
+    /// Does not access memory
+    None,
+    /// Reads only memory its pointer arguments point to
+    ArgMemReadOnly,
+    /// Reads and writes only memory its pointer arguments point to
+    ArgMem,
+    /// Reads and writes only memory not visible to the caller
+    InaccessibleMem,
+    /// Reads and writes argument memory and memory not visible to the caller
+    InaccessibleOrArgMem,
+    /// May read and write any memory
+    Any,
+}
+
+impl MemoryEffects {
+    pub fn may_write(self) -> bool {
+        !matches!(self, MemoryEffects::None | MemoryEffects::ArgMemReadOnly)
+    }
+}
+
+/// Why a signature does not match an intrinsic
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureMismatch {
+    ReturnType,
+    ArgumentType,
+    /// The intrinsic is variadic but the signature is not
+    MissingVarArgs,
+    /// The signature is variadic but the intrinsic is not
+    UnexpectedVarArgs,
+}
+
+/// Table entry describing one intrinsic
+#[derive(Debug, Clone, Copy)]
+pub struct IntrinsicInfo {
+    pub intrinsic: Intrinsic,
+    /// Name without the overload suffix
+    pub name: &'static str,
+    pub ret: IntrinsicType,
+    pub params: &'static [IntrinsicType],
+    pub varargs: bool,
+    /// Parameters that must be immediate constants
+    pub immargs: &'static [usize],
+    pub memory: MemoryEffects,
+    /// Attributes beyond the nounwind, nosync, nofree and willreturn that
+    /// intrinsics have by default
+    pub attributes: &'static [FunctionAttribute],
+}
+
+impl IntrinsicInfo {
+    const DEFAULT: IntrinsicInfo = IntrinsicInfo {
+        intrinsic: Intrinsic::Trap,
+        name: "",
+        ret: Void,
+        params: &[],
+        varargs: false,
+        immargs: &[],
+        memory: MemoryEffects::None,
+        attributes: &[],
+    };
+
+    pub fn is_overloaded(&self) -> bool {
+        self.ret.is_overloaded() || self.params.iter().any(|param| param.is_overloaded())
+    }
+
+    /// Match a function signature against the constraints, returning the
+    /// types bound to the overload slots
+    pub fn match_signature(&self, ret: &Type, params: &[Type], varargs: bool) -> Result<Vec<Type>, SignatureMismatch> {
+        if params.len() != self.params.len() {
+            return Err(SignatureMismatch::ArgumentType);
+        }
+        let constraints = std::iter::once(&self.ret).chain(self.params);
+        let types: Vec<&Type> = std::iter::once(ret).chain(params).collect();
+        let mismatch = |position: usize| if position == 0 {
+            SignatureMismatch::ReturnType
+        } else {
+            SignatureMismatch::ArgumentType
+        };
+
+        // Bind every slot first so constraints may refer to later slots
+        let mut slots = Vec::new();
+        for (position, (constraint, ty)) in constraints.clone().zip(&types).enumerate() {
+            if constraint.is_overloaded() {
+                if !constraint.accepts(ty) {
+                    return Err(mismatch(position));
+                }
+                slots.push((*ty).clone());
+            }
+        }
+        for (position, (constraint, ty)) in constraints.zip(&types).enumerate() {
+            if !constraint.is_overloaded() && !constraint.matches(ty, &slots) {
+                return Err(mismatch(position));
+            }
+        }
+
+        match (self.varargs, varargs) {
+            (true, false) => Err(SignatureMismatch::MissingVarArgs),
+            (false, true) => Err(SignatureMismatch::UnexpectedVarArgs),
+            _ => Ok(slots),
+        }
+    }
+
+    /// The name with the suffix for the given overload types, e.g.
+    /// `llvm.memcpy.p0.p0.i64`
+    pub fn mangled_name(&self, overload_types: &[Type]) -> String {
+        let mut name = self.name.to_string();
+        for ty in overload_types {
+            name.push('.');
+            name.push_str(&mangle_type(ty));
+        }
+        name
+    }
+}
+
+/// A type as it appears in the suffix of an overloaded intrinsic name
+pub fn mangle_type(ty: &Type) -> String {
+    if let Some(bits) = ty.int_width() {
+        format!("i{}", bits)
+    } else if let Some(kind) = ty.float_kind() {
+        match kind {
+            FloatKind::Half => "f16",
+            FloatKind::Float => "f32",
+            FloatKind::Double => "f64",
+        }.to_string()
+    } else if let Some((_, address_space)) = ty.pointer_info() {
+        format!("p{}", address_space)
+    } else if let Some((element, size)) = ty.vector_info() {
+        format!("v{}{}", size, mangle_type(element))
+    } else if let Some((element, size)) = ty.array_info() {
+        format!("a{}{}", size, mangle_type(element))
+    } else if let Some(name) = ty.struct_name() {
+        format!("s_{}", name)
+    } else if let Some(fields) = ty.struct_fields() {
+        let fields: String = fields.iter().map(mangle_type).collect();
+        format!("sl_{}s", fields)
+    } else if let Some((ret, params, varargs)) = ty.function_info() {
+        let params: String = params.iter().map(mangle_type).collect();
+        format!("f_{}{}{}f", mangle_type(&ret), params, if varargs { "vararg" } else { "" })
+    } else if ty.is_void() {
+        "isVoid".to_string()
+    } else if ty.is_metadata() {
+        "Metadata".to_string()
+    } else if ty.is_x86_amx() {
+        "x86amx".to_string()
+    } else {
+        ty.to_string()
+    }
+}
+
+macro_rules! intrinsics {
+    ($($(#[$meta:meta])* $variant:ident => $name:literal { $($field:ident: $value:expr),* $(,)? })*) => {
+        /// LLVM intrinsic functions
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum Intrinsic {
+            $($(#[$meta])* $variant,)*
+        }
+
+        /// Every intrinsic, in the order of the `Intrinsic` variants
+        pub static INTRINSICS: &[IntrinsicInfo] = &[$(
+            IntrinsicInfo {
+                intrinsic: Intrinsic::$variant,
+                name: $name,
+                $($field: $value,)*
+                ..IntrinsicInfo::DEFAULT
+            },
+        )*];
+    };
+}
+
+intrinsics! {
     // Memory operations
-    MemCpy,
-    MemMove,
-    MemSet,
+    MemCpy => "llvm.memcpy" { params: &[AnyPtr, AnyPtr, AnyInt, Int(1)], immargs: &[3], memory: MemoryEffects::ArgMem }
+    MemMove => "llvm.memmove" { params: &[AnyPtr, AnyPtr, AnyInt, Int(1)], immargs: &[3], memory: MemoryEffects::ArgMem }
+    MemSet => "llvm.memset" { params: &[AnyPtr, Int(8), AnyInt, Int(1)], immargs: &[3], memory: MemoryEffects::ArgMem }
+    MemCpyInline => "llvm.memcpy.inline" { params: &[AnyPtr, AnyPtr, AnyInt, Int(1)], immargs: &[3], memory: MemoryEffects::ArgMem }
+    MemSetInline => "llvm.memset.inline" { params: &[AnyPtr, Int(8), AnyInt, Int(1)], immargs: &[3], memory: MemoryEffects::ArgMem }
+    MemCpyElementUnorderedAtomic => "llvm.memcpy.element.unordered.atomic" {
+        params: &[AnyPtr, AnyPtr, AnyInt, Int(32)], immargs: &[3], memory: MemoryEffects::ArgMem,
+    }
+    MemMoveElementUnorderedAtomic => "llvm.memmove.element.unordered.atomic" {
+        params: &[AnyPtr, AnyPtr, AnyInt, Int(32)], immargs: &[3], memory: MemoryEffects::ArgMem,
+    }
+    MemSetElementUnorderedAtomic => "llvm.memset.element.unordered.atomic" {
+        params: &[AnyPtr, Int(8), AnyInt, Int(32)], immargs: &[3], memory: MemoryEffects::ArgMem,
+    }
+    ExperimentalMemSetPattern => "llvm.experimental.memset.pattern" {
+        params: &[AnyPtr, Any, AnyInt, Int(1)], immargs: &[3], memory: MemoryEffects::ArgMem,
+    }
 
     // Lifetime markers
-    LifetimeStart,
-    LifetimeEnd,
+    LifetimeStart => "llvm.lifetime.start" { params: &[Int(64), AnyPtr], immargs: &[0], memory: MemoryEffects::ArgMem }
+    LifetimeEnd => "llvm.lifetime.end" { params: &[Int(64), AnyPtr], immargs: &[0], memory: MemoryEffects::ArgMem }
+    InvariantStart => "llvm.invariant.start" { ret: Ptr, params: &[Int(64), AnyPtr], immargs: &[0], memory: MemoryEffects::ArgMem }
+    InvariantEnd => "llvm.invariant.end" { params: &[Ptr, Int(64), AnyPtr], immargs: &[1], memory: MemoryEffects::ArgMem }
 
     // Arithmetic with overflow
-    SAddWithOverflow,
-    UAddWithOverflow,
-    SSubWithOverflow,
-    USubWithOverflow,
-    SMulWithOverflow,
-    UMulWithOverflow,
+    SAddWithOverflow => "llvm.sadd.with.overflow" { ret: OverflowOf(0), params: &[AnyInt, Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    UAddWithOverflow => "llvm.uadd.with.overflow" { ret: OverflowOf(0), params: &[AnyInt, Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    SSubWithOverflow => "llvm.ssub.with.overflow" { ret: OverflowOf(0), params: &[AnyInt, Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    USubWithOverflow => "llvm.usub.with.overflow" { ret: OverflowOf(0), params: &[AnyInt, Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    SMulWithOverflow => "llvm.smul.with.overflow" { ret: OverflowOf(0), params: &[AnyInt, Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    UMulWithOverflow => "llvm.umul.with.overflow" { ret: OverflowOf(0), params: &[AnyInt, Match(0)], attributes: &[FunctionAttribute::Speculatable] }
 
     // Saturating arithmetic
-    SAddSat,
-    UAddSat,
-    SSubSat,
-    USubSat,
+    SAddSat => "llvm.sadd.sat" { ret: AnyInt, params: &[Match(0), Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    UAddSat => "llvm.uadd.sat" { ret: AnyInt, params: &[Match(0), Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    SSubSat => "llvm.ssub.sat" { ret: AnyInt, params: &[Match(0), Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    USubSat => "llvm.usub.sat" { ret: AnyInt, params: &[Match(0), Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    SShlSat => "llvm.sshl.sat" { ret: AnyInt, params: &[Match(0), Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    UShlSat => "llvm.ushl.sat" { ret: AnyInt, params: &[Match(0), Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+
+    // Fixed point arithmetic
+    SMulFix => "llvm.smul.fix" { ret: AnyInt, params: &[Match(0), Match(0), Int(32)], immargs: &[2] }
+    UMulFix => "llvm.umul.fix" { ret: AnyInt, params: &[Match(0), Match(0), Int(32)], immargs: &[2] }
+    SMulFixSat => "llvm.smul.fix.sat" { ret: AnyInt, params: &[Match(0), Match(0), Int(32)], immargs: &[2] }
+    UMulFixSat => "llvm.umul.fix.sat" { ret: AnyInt, params: &[Match(0), Match(0), Int(32)], immargs: &[2] }
+
+    // Integer operations
+    Abs => "llvm.abs" { ret: AnyInt, params: &[Match(0), Int(1)], immargs: &[1], attributes: &[FunctionAttribute::Speculatable] }
+    SMax => "llvm.smax" { ret: AnyInt, params: &[Match(0), Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    SMin => "llvm.smin" { ret: AnyInt, params: &[Match(0), Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    UMax => "llvm.umax" { ret: AnyInt, params: &[Match(0), Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    UMin => "llvm.umin" { ret: AnyInt, params: &[Match(0), Match(0)], attributes: &[FunctionAttribute::Speculatable] }
 
     // Bit manipulation
-    Bswap,
-    Ctpop,
-    Ctlz,
-    Cttz,
-    FshlRotate,
-    FshrRotate,
+    Bswap => "llvm.bswap" { ret: AnyInt, params: &[Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    Ctpop => "llvm.ctpop" { ret: AnyInt, params: &[Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    Ctlz => "llvm.ctlz" { ret: AnyInt, params: &[Match(0), Int(1)], immargs: &[1], attributes: &[FunctionAttribute::Speculatable] }
+    Cttz => "llvm.cttz" { ret: AnyInt, params: &[Match(0), Int(1)], immargs: &[1], attributes: &[FunctionAttribute::Speculatable] }
+    FshlRotate => "llvm.fshl" { ret: AnyInt, params: &[Match(0), Match(0), Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    FshrRotate => "llvm.fshr" { ret: AnyInt, params: &[Match(0), Match(0), Match(0)], attributes: &[FunctionAttribute::Speculatable] }
 
     // Math operations
-    Sqrt,
-    Sin,
-    Cos,
-    Pow,
-    Exp,
-    Exp2,
-    Log,
-    Log10,
-    Log2,
-    Fma,
-    Fabs,
-    Copysign,
-    Floor,
-    Ceil,
-    Trunc,
-    Rint,
-    Nearbyint,
-    Round,
+    Sqrt => "llvm.sqrt" { ret: AnyFloat, params: &[Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    Sin => "llvm.sin" { ret: AnyFloat, params: &[Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    Cos => "llvm.cos" { ret: AnyFloat, params: &[Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    Pow => "llvm.pow" { ret: AnyFloat, params: &[Match(0), Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    Exp => "llvm.exp" { ret: AnyFloat, params: &[Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    Exp2 => "llvm.exp2" { ret: AnyFloat, params: &[Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    Log => "llvm.log" { ret: AnyFloat, params: &[Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    Log10 => "llvm.log10" { ret: AnyFloat, params: &[Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    Log2 => "llvm.log2" { ret: AnyFloat, params: &[Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    Fma => "llvm.fma" { ret: AnyFloat, params: &[Match(0), Match(0), Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    Fabs => "llvm.fabs" { ret: AnyFloat, params: &[Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    Copysign => "llvm.copysign" { ret: AnyFloat, params: &[Match(0), Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    Floor => "llvm.floor" { ret: AnyFloat, params: &[Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    Ceil => "llvm.ceil" { ret: AnyFloat, params: &[Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    Trunc => "llvm.trunc" { ret: AnyFloat, params: &[Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    Rint => "llvm.rint" { ret: AnyFloat, params: &[Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    Nearbyint => "llvm.nearbyint" { ret: AnyFloat, params: &[Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    Round => "llvm.round" { ret: AnyFloat, params: &[Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    IsFpClass => "llvm.is.fpclass" { ret: MaskOf(0), params: &[AnyFloat, Int(32)], immargs: &[1] }
 
     // Min/Max
-    MinNum,
-    MaxNum,
-    Minimum,
-    Maximum,
+    MinNum => "llvm.minnum" { ret: AnyFloat, params: &[Match(0), Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    MaxNum => "llvm.maxnum" { ret: AnyFloat, params: &[Match(0), Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    Minimum => "llvm.minimum" { ret: AnyFloat, params: &[Match(0), Match(0)], attributes: &[FunctionAttribute::Speculatable] }
+    Maximum => "llvm.maximum" { ret: AnyFloat, params: &[Match(0), Match(0)], attributes: &[FunctionAttribute::Speculatable] }
 
     // Vector reductions
-    VectorReduceAdd,
-    VectorReduceMul,
-    VectorReduceAnd,
-    VectorReduceOr,
-    VectorReduceXor,
-    VectorReduceSMax,
-    VectorReduceSMin,
-    VectorReduceUMax,
-    VectorReduceUMin,
-    VectorReduceFAdd,
-    VectorReduceFMul,
-    VectorReduceFMax,
-    VectorReduceFMin,
-
-    // Saturating ops
-    SShlSat,
-    UShlSat,
+    VectorReduceAdd => "llvm.vector.reduce.add" { ret: ElementOf(0), params: &[AnyIntVector] }
+    VectorReduceMul => "llvm.vector.reduce.mul" { ret: ElementOf(0), params: &[AnyIntVector] }
+    VectorReduceAnd => "llvm.vector.reduce.and" { ret: ElementOf(0), params: &[AnyIntVector] }
+    VectorReduceOr => "llvm.vector.reduce.or" { ret: ElementOf(0), params: &[AnyIntVector] }
+    VectorReduceXor => "llvm.vector.reduce.xor" { ret: ElementOf(0), params: &[AnyIntVector] }
+    VectorReduceSMax => "llvm.vector.reduce.smax" { ret: ElementOf(0), params: &[AnyIntVector] }
+    VectorReduceSMin => "llvm.vector.reduce.smin" { ret: ElementOf(0), params: &[AnyIntVector] }
+    VectorReduceUMax => "llvm.vector.reduce.umax" { ret: ElementOf(0), params: &[AnyIntVector] }
+    VectorReduceUMin => "llvm.vector.reduce.umin" { ret: ElementOf(0), params: &[AnyIntVector] }
+    VectorReduceFAdd => "llvm.vector.reduce.fadd" { ret: ElementOf(0), params: &[ElementOf(0), AnyFloatVector] }
+    VectorReduceFMul => "llvm.vector.reduce.fmul" { ret: ElementOf(0), params: &[ElementOf(0), AnyFloatVector] }
+    VectorReduceFMax => "llvm.vector.reduce.fmax" { ret: ElementOf(0), params: &[AnyFloatVector] }
+    VectorReduceFMin => "llvm.vector.reduce.fmin" { ret: ElementOf(0), params: &[AnyFloatVector] }
+
+    // Vector manipulation
+    StepVector => "llvm.stepvector" { ret: AnyVector }
+    VectorExtract => "llvm.vector.extract" { ret: AnyVector, params: &[AnyVector, Int(64)], immargs: &[1] }
+    VectorInsert => "llvm.vector.insert" { ret: AnyVector, params: &[Match(0), AnyVector, Int(64)], immargs: &[2] }
+    VectorSplice => "llvm.vector.splice" { ret: AnyVector, params: &[Match(0), Match(0), Int(32)], immargs: &[2] }
+
+    // Vector predication
+    VpFpToSi => "llvm.vp.fptosi" { ret: AnyIntVector, params: &[AnyFloatVector, MaskOf(0), Int(32)] }
+    VpFpToUi => "llvm.vp.fptoui" { ret: AnyIntVector, params: &[AnyFloatVector, MaskOf(0), Int(32)] }
+    VpSiToFp => "llvm.vp.sitofp" { ret: AnyFloatVector, params: &[AnyIntVector, MaskOf(0), Int(32)] }
+    VpUiToFp => "llvm.vp.uitofp" { ret: AnyFloatVector, params: &[AnyIntVector, MaskOf(0), Int(32)] }
 
     // Trap and debugging
-    Trap,
-    Debugtrap,
+    Trap => "llvm.trap" {
+        memory: MemoryEffects::InaccessibleMem,
+        attributes: &[FunctionAttribute::NoReturn, FunctionAttribute::Cold],
+    }
+    Debugtrap => "llvm.debugtrap" { memory: MemoryEffects::InaccessibleMem }
+    DoNothing => "llvm.donothing" {}
+
+    // Stack and frame operations
+    StackSave => "llvm.stacksave" { ret: AnyPtr, memory: MemoryEffects::InaccessibleMem }
+    StackRestore => "llvm.stackrestore" { params: &[AnyPtr], memory: MemoryEffects::InaccessibleMem }
+    ReturnAddress => "llvm.returnaddress" { ret: Ptr, params: &[Int(32)], immargs: &[0] }
+    FrameAddress => "llvm.frameaddress" { ret: AnyPtr, params: &[Int(32)], immargs: &[0] }
+    LocalRecover => "llvm.localrecover" { ret: Ptr, params: &[Ptr, Ptr, Int(32)], immargs: &[2] }
 
-    // Stack operations
-    StackSave,
-    StackRestore,
+    // Variable arguments
+    VaStart => "llvm.va_start" { params: &[AnyPtr], memory: MemoryEffects::ArgMem }
+    VaEnd => "llvm.va_end" { params: &[AnyPtr], memory: MemoryEffects::ArgMem }
+    VaCopy => "llvm.va_copy" { params: &[AnyPtr, Match(0)], memory: MemoryEffects::ArgMem }
 
     // Prefetch
-    Prefetch,
+    Prefetch => "llvm.prefetch" { params: &[AnyPtr, Int(32), Int(32), Int(32)], immargs: &[1, 2, 3], memory: MemoryEffects::InaccessibleOrArgMem }
 
     // Assume
-    Assume,
+    Assume => "llvm.assume" { params: &[Int(1)], memory: MemoryEffects::InaccessibleMem }
 
     // Expect
-    Expect,
+    Expect => "llvm.expect" { ret: AnyInt, params: &[Match(0), Match(0)] }
 
     // Object size
-    ObjectSize,
+    ObjectSize => "llvm.objectsize" { ret: AnyInt, params: &[AnyPtr, Int(1), Int(1), Int(1)], immargs: &[1, 2, 3], attributes: &[FunctionAttribute::Speculatable] }
 
     // Overflow arithmetic
-    SAddO,
-    UAddO,
-    SSubO,
-    USubO,
-    SMulO,
-    UMulO,
+    SAddO => "llvm.sadd.o" { ret: OverflowOf(0), params: &[AnyInt, Match(0)] }
+    UAddO => "llvm.uadd.o" { ret: OverflowOf(0), params: &[AnyInt, Match(0)] }
+    SSubO => "llvm.ssub.o" { ret: OverflowOf(0), params: &[AnyInt, Match(0)] }
+    USubO => "llvm.usub.o" { ret: OverflowOf(0), params: &[AnyInt, Match(0)] }
+    SMulO => "llvm.smul.o" { ret: OverflowOf(0), params: &[AnyInt, Match(0)] }
+    UMulO => "llvm.umul.o" { ret: OverflowOf(0), params: &[AnyInt, Match(0)] }
 
     // Conversion
-    ConvertFromFp16,
-    ConvertToFp16,
+    ConvertFromFp16 => "llvm.convert.from.fp16" { ret: AnyFloat, params: &[Int(16)] }
+    ConvertToFp16 => "llvm.convert.to.fp16" { ret: Int(16), params: &[AnyFloat] }
 
     // Masked operations
-    MaskedLoad,
-    MaskedStore,
-    MaskedGather,
-    MaskedScatter,
+    MaskedLoad => "llvm.masked.load" {
+        ret: AnyVector, params: &[AnyPtr, Int(32), MaskOf(0), Match(0)], immargs: &[1], memory: MemoryEffects::ArgMemReadOnly,
+    }
+    MaskedStore => "llvm.masked.store" {
+        params: &[AnyVector, AnyPtr, Int(32), MaskOf(0)], immargs: &[2], memory: MemoryEffects::ArgMem,
+    }
+    MaskedGather => "llvm.masked.gather" {
+        ret: AnyVector, params: &[AnyVector, Int(32), MaskOf(0), Match(0)], immargs: &[1], memory: MemoryEffects::Any,
+    }
+    MaskedScatter => "llvm.masked.scatter" {
+        params: &[AnyVector, AnyVector, Int(32), MaskOf(0)], immargs: &[2], memory: MemoryEffects::Any,
+    }
 
     // Constrained FP
-    ExperimentalConstrainedFAdd,
-    ExperimentalConstrainedFSub,
-    ExperimentalConstrainedFMul,
-    ExperimentalConstrainedFDiv,
+    ExperimentalConstrainedFAdd => "llvm.experimental.constrained.fadd" {
+        ret: AnyFloat, params: &[Match(0), Match(0), Metadata, Metadata], memory: MemoryEffects::InaccessibleMem,
+    }
+    ExperimentalConstrainedFSub => "llvm.experimental.constrained.fsub" {
+        ret: AnyFloat, params: &[Match(0), Match(0), Metadata, Metadata], memory: MemoryEffects::InaccessibleMem,
+    }
+    ExperimentalConstrainedFMul => "llvm.experimental.constrained.fmul" {
+        ret: AnyFloat, params: &[Match(0), Match(0), Metadata, Metadata], memory: MemoryEffects::InaccessibleMem,
+    }
+    ExperimentalConstrainedFDiv => "llvm.experimental.constrained.fdiv" {
+        ret: AnyFloat, params: &[Match(0), Match(0), Metadata, Metadata], memory: MemoryEffects::InaccessibleMem,
+    }
 
     // Coroutines
-    CoroId,
-    CoroAlloc,
-    CoroBegin,
-    CoroEnd,
-    CoroSuspend,
-    CoroResume,
-    CoroDestroy,
-    CoroPromise,
-
-    // Experimental
-    ExperimentalGCStatepoint,
-    ExperimentalGCRelocate,
-    ExperimentalGCResult,
-
-    // Platform-specific
-    X86SSE,
-    X86AVX,
-    ARMV7NEON,
-    AArch64NEON,
+    CoroId => "llvm.coro.id" { ret: Token, params: &[Int(32), Ptr, Ptr, Ptr], memory: MemoryEffects::ArgMemReadOnly }
+    CoroAlloc => "llvm.coro.alloc" { ret: Int(1), params: &[Token], memory: MemoryEffects::Any }
+    CoroBegin => "llvm.coro.begin" { ret: Ptr, params: &[Token, Ptr], memory: MemoryEffects::Any }
+    CoroEnd => "llvm.coro.end" { ret: Int(1), params: &[Ptr, Int(1), Token], memory: MemoryEffects::Any }
+    CoroSuspend => "llvm.coro.suspend" { ret: Int(8), params: &[Token, Int(1)], memory: MemoryEffects::Any }
+    CoroResume => "llvm.coro.resume" { params: &[Ptr], memory: MemoryEffects::Any }
+    CoroDestroy => "llvm.coro.destroy" { params: &[Ptr], memory: MemoryEffects::Any }
+    CoroPromise => "llvm.coro.promise" { ret: Ptr, params: &[Ptr, Int(32), Int(1)] }
+
+    // Garbage collection and deoptimization
+    ExperimentalGCStatepoint => "llvm.experimental.gc.statepoint" {
+        ret: Token, params: &[Int(64), Int(32), AnyPtr, Int(32), Int(32)], varargs: true, immargs: &[0, 1, 3, 4],
+        memory: MemoryEffects::Any,
+    }
+    ExperimentalGCRelocate => "llvm.experimental.gc.relocate" { ret: Any, params: &[Token, Int(32), Int(32)], immargs: &[1, 2] }
+    ExperimentalGCResult => "llvm.experimental.gc.result" { ret: Any, params: &[Token] }
+    ExperimentalStackmap => "llvm.experimental.stackmap" { params: &[Int(64), Int(32)], varargs: true, immargs: &[0, 1], memory: MemoryEffects::Any }
+    ExperimentalPatchpoint => "llvm.experimental.patchpoint" {
+        ret: Any, params: &[Int(64), Int(32), Ptr, Int(32)], varargs: true, immargs: &[0, 1, 3], memory: MemoryEffects::Any,
+    }
+    ExperimentalDeoptimize => "llvm.experimental.deoptimize" { ret: Any, varargs: true, memory: MemoryEffects::Any }
+    ExperimentalGuard => "llvm.experimental.guard" { params: &[Int(1)], varargs: true, memory: MemoryEffects::Any }
+
+    // Exception handling and sanitizers
+    EhSjljCallsite => "llvm.eh.sjlj.callsite" { params: &[Int(32)], immargs: &[0] }
+    HwasanCheckMemaccess => "llvm.hwasan.check.memaccess" { params: &[Ptr, Ptr, Int(32)], immargs: &[2], memory: MemoryEffects::Any }
+
+    // Platform-specific namespaces; these only match their exact name
+    X86SSE => "llvm.x86.sse" {}
+    X86AVX => "llvm.x86.avx" {}
+    ARMV7NEON => "llvm.arm.neon" {}
+    AArch64NEON => "llvm.aarch64.neon" {}
 }
 
 impl Intrinsic {
+    /// The table entry of this intrinsic
+    pub fn info(&self) -> &'static IntrinsicInfo {
+        &INTRINSICS[*self as usize]
+    }
+
+    /// Find the intrinsic a function name refers to: its base name, or for
+    /// overloaded intrinsics the base name followed by a type suffix
+    pub fn lookup(name: &str) -> Option<Self> {
+        INTRINSICS.iter()
+            .filter(|info| name == info.name
+                || (info.is_overloaded() && name.strip_prefix(info.name).is_some_and(|suffix| suffix.starts_with('.'))))
+            .max_by_key(|info| info.name.len())
+            .map(|info| info.intrinsic)
+    }
+
     /// Get the name of this intrinsic as it appears in LLVM IR
     pub fn name(&self) -> &'static str {
-        match self {
-            Self::MemCpy => "llvm.memcpy",
-            Self::MemMove => "llvm.memmove",
-            Self::MemSet => "llvm.memset",
-            Self::LifetimeStart => "llvm.lifetime.start",
-            Self::LifetimeEnd => "llvm.lifetime.end",
-            Self::SAddWithOverflow => "llvm.sadd.with.overflow",
-            Self::UAddWithOverflow => "llvm.uadd.with.overflow",
-            Self::SSubWithOverflow => "llvm.ssub.with.overflow",
-            Self::USubWithOverflow => "llvm.usub.with.overflow",
-            Self::SMulWithOverflow => "llvm.smul.with.overflow",
-            Self::UMulWithOverflow => "llvm.umul.with.overflow",
-            Self::SAddSat => "llvm.sadd.sat",
-            Self::UAddSat => "llvm.uadd.sat",
-            Self::SSubSat => "llvm.ssub.sat",
-            Self::USubSat => "llvm.usub.sat",
-            Self::Bswap => "llvm.bswap",
-            Self::Ctpop => "llvm.ctpop",
-            Self::Ctlz => "llvm.ctlz",
-            Self::Cttz => "llvm.cttz",
-            Self::FshlRotate => "llvm.fshl",
-            Self::FshrRotate => "llvm.fshr",
-            Self::Sqrt => "llvm.sqrt",
-            Self::Sin => "llvm.sin",
-            Self::Cos => "llvm.cos",
-            Self::Pow => "llvm.pow",
-            Self::Exp => "llvm.exp",
-            Self::Exp2 => "llvm.exp2",
-            Self::Log => "llvm.log",
-            Self::Log10 => "llvm.log10",
-            Self::Log2 => "llvm.log2",
-            Self::Fma => "llvm.fma",
-            Self::Fabs => "llvm.fabs",
-            Self::Copysign => "llvm.copysign",
-            Self::Floor => "llvm.floor",
-            Self::Ceil => "llvm.ceil",
-            Self::Trunc => "llvm.trunc",
-            Self::Rint => "llvm.rint",
-            Self::Nearbyint => "llvm.nearbyint",
-            Self::Round => "llvm.round",
-            Self::MinNum => "llvm.minnum",
-            Self::MaxNum => "llvm.maxnum",
-            Self::Minimum => "llvm.minimum",
-            Self::Maximum => "llvm.maximum",
-            Self::VectorReduceAdd => "llvm.vector.reduce.add",
-            Self::VectorReduceMul => "llvm.vector.reduce.mul",
-            Self::VectorReduceAnd => "llvm.vector.reduce.and",
-            Self::VectorReduceOr => "llvm.vector.reduce.or",
-            Self::VectorReduceXor => "llvm.vector.reduce.xor",
-            Self::VectorReduceSMax => "llvm.vector.reduce.smax",
-            Self::VectorReduceSMin => "llvm.vector.reduce.smin",
-            Self::VectorReduceUMax => "llvm.vector.reduce.umax",
-            Self::VectorReduceUMin => "llvm.vector.reduce.umin",
-            Self::VectorReduceFAdd => "llvm.vector.reduce.fadd",
-            Self::VectorReduceFMul => "llvm.vector.reduce.fmul",
-            Self::VectorReduceFMax => "llvm.vector.reduce.fmax",
-            Self::VectorReduceFMin => "llvm.vector.reduce.fmin",
-            Self::SShlSat => "llvm.sshl.sat",
-            Self::UShlSat => "llvm.ushl.sat",
-            Self::Trap => "llvm.trap",
-            Self::Debugtrap => "llvm.debugtrap",
-            Self::StackSave => "llvm.stacksave",
-            Self::StackRestore => "llvm.stackrestore",
-            Self::Prefetch => "llvm.prefetch",
-            Self::Assume => "llvm.assume",
-            Self::Expect => "llvm.expect",
-            Self::ObjectSize => "llvm.objectsize",
-            Self::SAddO => "llvm.sadd.o",
-            Self::UAddO => "llvm.uadd.o",
-            Self::SSubO => "llvm.ssub.o",
-            Self::USubO => "llvm.usub.o",
-            Self::SMulO => "llvm.smul.o",
-            Self::UMulO => "llvm.umul.o",
-            Self::ConvertFromFp16 => "llvm.convert.from.fp16",
-            Self::ConvertToFp16 => "llvm.convert.to.fp16",
-            Self::MaskedLoad => "llvm.masked.load",
-            Self::MaskedStore => "llvm.masked.store",
-            Self::MaskedGather => "llvm.masked.gather",
-            Self::MaskedScatter => "llvm.masked.scatter",
-            Self::ExperimentalConstrainedFAdd => "llvm.experimental.constrained.fadd",
-            Self::ExperimentalConstrainedFSub => "llvm.experimental.constrained.fsub",
-            Self::ExperimentalConstrainedFMul => "llvm.experimental.constrained.fmul",
-            Self::ExperimentalConstrainedFDiv => "llvm.experimental.constrained.fdiv",
-            Self::CoroId => "llvm.coro.id",
-            Self::CoroAlloc => "llvm.coro.alloc",
-            Self::CoroBegin => "llvm.coro.begin",
-            Self::CoroEnd => "llvm.coro.end",
-            Self::CoroSuspend => "llvm.coro.suspend",
-            Self::CoroResume => "llvm.coro.resume",
-            Self::CoroDestroy => "llvm.coro.destroy",
-            Self::CoroPromise => "llvm.coro.promise",
-            Self::ExperimentalGCStatepoint => "llvm.experimental.gc.statepoint",
-            Self::ExperimentalGCRelocate => "llvm.experimental.gc.relocate",
-            Self::ExperimentalGCResult => "llvm.experimental.gc.result",
-            Self::X86SSE => "llvm.x86.sse",
-            Self::X86AVX => "llvm.x86.avx",
-            Self::ARMV7NEON => "llvm.arm.neon",
-            Self::AArch64NEON => "llvm.aarch64.neon",
-        }
+        self.info().name
     }
 
     /// Check if this intrinsic is overloaded (type-parametric)
     pub fn is_overloaded(&self) -> bool {
-        matches!(self,
-            Self::MemCpy | Self::MemMove | Self::MemSet |
-            Self::Bswap | Self::Ctpop | Self::Ctlz | Self::Cttz |
-            Self::Sqrt | Self::Sin | Self::Cos | Self::Pow |
-            Self::Exp | Self::Exp2 | Self::Log | Self::Log10 | Self::Log2 |
-            Self::Fma | Self::Fabs | Self::Copysign |
-            Self::Floor | Self::Ceil | Self::Trunc | Self::Rint |
-            Self::Nearbyint | Self::Round
-        )
+        self.info().is_overloaded()
     }
 
     /// Check if this intrinsic has side effects
     pub fn has_side_effects(&self) -> bool {
-        matches!(self,
-            Self::MemCpy | Self::MemMove | Self::MemSet |
-            Self::LifetimeStart | Self::LifetimeEnd |
-            Self::Trap | Self::Debugtrap |
-            Self::StackRestore | Self::Prefetch
-        )
+        self.info().memory.may_write()
+    }
+
+    /// The mangled name for the given overload types
+    pub fn mangled_name(&self, overload_types: &[Type]) -> String {
+        self.info().mangled_name(overload_types)
     }
 }
 
@@ -287,6 +539,7 @@ impl fmt::Display for Intrinsic {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Context;
 
     #[test]
     fn test_intrinsic_name() {
@@ -305,4 +558,51 @@ mod tests {
         assert!(Intrinsic::MemCpy.has_side_effects());
         assert!(!Intrinsic::Sqrt.has_side_effects());
     }
+
+    #[test]
+    fn test_table_order_matches_variants() {
+        assert!(INTRINSICS.iter().enumerate().all(|(index, info)| info.intrinsic as usize == index));
+        assert!(INTRINSICS.iter().all(|info| info.immargs.iter().all(|&index| index < info.params.len())));
+    }
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(Intrinsic::lookup("llvm.memcpy.p0.p0.i64"), Some(Intrinsic::MemCpy));
+        assert_eq!(Intrinsic::lookup("llvm.memcpy.inline.p0.p0.i64"), Some(Intrinsic::MemCpyInline));
+        assert_eq!(Intrinsic::lookup("llvm.smul.fix.sat.i32"), Some(Intrinsic::SMulFixSat));
+        assert_eq!(Intrinsic::lookup("llvm.trap"), Some(Intrinsic::Trap));
+        assert_eq!(Intrinsic::lookup("llvm.trap.i32"), None);
+        assert_eq!(Intrinsic::lookup("llvm.x86.sse.sqrt.ps"), None);
+        assert_eq!(Intrinsic::lookup("llvm.memcpyx"), None);
+    }
+
+    #[test]
+    fn test_match_signature_and_mangle() {
+        let ctx = Context::new();
+        let i1 = ctx.bool_type();
+        let i32 = ctx.int32_type();
+        let i64 = ctx.int64_type();
+        let ptr = ctx.ptr_type(ctx.int8_type());
+        let memcpy = Intrinsic::MemCpy.info();
+        let slots = memcpy.match_signature(&ctx.void_type(), &[ptr.clone(), ptr.clone(), i64.clone(), i1.clone()], false).unwrap();
+        assert_eq!(memcpy.mangled_name(&slots), "llvm.memcpy.p0.p0.i64");
+        assert_eq!(memcpy.match_signature(&ctx.void_type(), &[ptr.clone(), ptr.clone(), i64.clone()], false),
+            Err(SignatureMismatch::ArgumentType));
+
+        let overflow = Intrinsic::SAddWithOverflow.info();
+        let result = Type::struct_type(&ctx, vec![i32.clone(), i1.clone()], None);
+        let slots = overflow.match_signature(&result, &[i32.clone(), i32.clone()], false).unwrap();
+        assert_eq!(overflow.mangled_name(&slots), "llvm.sadd.with.overflow.i32");
+        assert_eq!(overflow.match_signature(&i32, &[i32.clone(), i32.clone()], false), Err(SignatureMismatch::ReturnType));
+
+        let v4f32 = ctx.vector_type(ctx.float_type(), 4);
+        let reduce = Intrinsic::VectorReduceFAdd.info();
+        let slots = reduce.match_signature(&ctx.float_type(), &[ctx.float_type(), v4f32.clone()], false).unwrap();
+        assert_eq!(reduce.mangled_name(&slots), "llvm.vector.reduce.fadd.v4f32");
+        assert_eq!(reduce.match_signature(&ctx.double_type(), &[ctx.float_type(), v4f32], false),
+            Err(SignatureMismatch::ReturnType));
+
+        let stackmap = Intrinsic::ExperimentalStackmap.info();
+        assert_eq!(stackmap.match_signature(&ctx.void_type(), &[i64, i32], false), Err(SignatureMismatch::MissingVarArgs));
+    }
 }
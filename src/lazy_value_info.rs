@@ -0,0 +1,315 @@
+//! Lazy Value Info
+//!
+//! Ranges of integer values at points of a function, computed on demand
+//! and cached per block like LLVM's LazyValueInfo. A value's range on
+//! entry to a block is the union of its ranges along the incoming edges,
+//! narrowed on each edge by the branch or switch that takes it, and
+//! within a block by earlier `llvm.assume` calls. Instructions combine
+//! the ranges of their operands, `!range` metadata and known bits.
+//!
+//! A query that depends on itself, around a loop, is answered with the
+//! range the value's definition gives it.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use crate::analysis::ValueKey;
+use crate::cfg::CFG;
+use crate::constant_range::ConstantRange;
+use crate::function::Function;
+use crate::instruction::{Instruction, IntPredicate, Opcode};
+use crate::passes::{AnalysisPass, Pass, PassResult};
+use crate::value::Value;
+use crate::value_tracking::{range_metadata, ValueTracking};
+
+/// Queries nested deeper than this give up with the full set
+const MAX_QUERY_DEPTH: usize = 256;
+
+/// How many `and`/`or` levels of a branch condition are looked through
+const MAX_CONDITION_DEPTH: u32 = 6;
+
+/// Lazily computed value ranges of one function
+pub struct LazyValueInfo {
+    cfg: CFG,
+    /// Instructions of each block
+    blocks: Vec<Vec<Instruction>>,
+    /// Block and defining instruction of each local value
+    definitions: HashMap<ValueKey, (usize, Instruction)>,
+    value_tracking: ValueTracking,
+    /// Ranges of values on entry to blocks
+    cache: RefCell<HashMap<(ValueKey, usize), ConstantRange>>,
+    /// Queries being answered
+    pending: RefCell<HashSet<(ValueKey, usize)>>,
+    depth: Cell<usize>,
+}
+
+impl LazyValueInfo {
+    pub fn new(function: &Function) -> Self {
+        let blocks: Vec<Vec<Instruction>> = function.basic_blocks().iter().map(|bb| bb.instructions()).collect();
+        let mut definitions = HashMap::new();
+        for (block, instructions) in blocks.iter().enumerate() {
+            for inst in instructions {
+                if let Some(result) = inst.result() {
+                    definitions.insert(ValueKey::of(result), (block, inst.clone()));
+                }
+            }
+        }
+        Self {
+            cfg: CFG::from_function(function),
+            blocks,
+            definitions,
+            value_tracking: ValueTracking::new(function),
+            cache: RefCell::new(HashMap::new()),
+            pending: RefCell::new(HashSet::new()),
+            depth: Cell::new(0),
+        }
+    }
+
+    pub fn cfg(&self) -> &CFG {
+        &self.cfg
+    }
+
+    pub fn value_tracking(&self) -> &ValueTracking {
+        &self.value_tracking
+    }
+
+    /// Range of an integer value on entry to a block; `None` for values
+    /// of other types
+    pub fn constant_range_on_entry(&self, value: &Value, block: usize) -> Option<ConstantRange> {
+        let width = value.get_type().int_width()?;
+        Some(self.range_on_entry(value, width, block))
+    }
+
+    /// Range of an integer value just before instruction `index` of a
+    /// block, narrowed by the assumes above it
+    pub fn constant_range(&self, value: &Value, block: usize, index: usize) -> Option<ConstantRange> {
+        let width = value.get_type().int_width()?;
+        let range = self.range_on_entry(value, width, block);
+        Some(self.apply_assumes(value, width, block, index, range))
+    }
+
+    /// Range of an integer value along the edge from one block to another
+    pub fn constant_range_on_edge(&self, value: &Value, from: usize, to: usize) -> Option<ConstantRange> {
+        let width = value.get_type().int_width()?;
+        Some(self.range_on_edge(value, width, from, to))
+    }
+
+    /// The value an integer has just before instruction `index` of a
+    /// block, if it can only have one
+    pub fn constant(&self, value: &Value, block: usize, index: usize) -> Option<u128> {
+        self.constant_range(value, block, index)?.single_element()
+    }
+
+    /// Whether `lhs predicate rhs` is always true or always false just
+    /// before instruction `index` of a block
+    pub fn predicate_at(&self, predicate: IntPredicate, lhs: &Value, rhs: &Value, block: usize, index: usize) -> Option<bool> {
+        let lhs = self.constant_range(lhs, block, index)?;
+        let rhs = self.constant_range(rhs, block, index)?;
+        if lhs.width() != rhs.width() {
+            return None;
+        }
+        lhs.icmp(predicate, &rhs)
+    }
+
+    fn range_on_entry(&self, value: &Value, width: u32, block: usize) -> ConstantRange {
+        if let Some(constant) = value.as_const_int() {
+            return ConstantRange::single(width, constant as u128);
+        }
+        let key = (ValueKey::of(value), block);
+        if let Some(range) = self.cache.borrow().get(&key) {
+            return *range;
+        }
+        let definition = self.definitions.get(&key.0);
+        if self.depth.get() >= MAX_QUERY_DEPTH || !self.pending.borrow_mut().insert(key.clone()) {
+            // Every use sees a subset of the range the definition gives, when
+            // that is not being answered itself
+            return match definition {
+                Some((defining_block, _)) if *defining_block != block && self.depth.get() < MAX_QUERY_DEPTH
+                    && !self.pending.borrow().contains(&(key.0.clone(), *defining_block)) => {
+                    self.range_on_entry(value, width, *defining_block)
+                }
+                _ => ConstantRange::full(width),
+            };
+        }
+        self.depth.set(self.depth.get() + 1);
+
+        let range = match definition {
+            Some((defining_block, inst)) if *defining_block == block => self.instruction_range(inst, width, block),
+            _ if block == 0 || !self.cfg.is_reachable(block) => match definition {
+                Some((defining_block, _)) => self.range_on_entry(value, width, *defining_block),
+                None => ConstantRange::full(width),
+            },
+            _ => {
+                let mut range = ConstantRange::empty(width);
+                for &pred in self.cfg.predecessors(block) {
+                    if self.cfg.is_reachable(pred) {
+                        range = range.union_with(&self.range_on_edge(value, width, pred, block));
+                    }
+                    if range.is_full_set() {
+                        break;
+                    }
+                }
+                range
+            }
+        };
+
+        self.depth.set(self.depth.get() - 1);
+        self.pending.borrow_mut().remove(&key);
+        self.cache.borrow_mut().insert(key, range);
+        range
+    }
+
+    fn range_on_edge(&self, value: &Value, width: u32, from: usize, to: usize) -> ConstantRange {
+        let at_end = self.range_on_entry(value, width, from);
+        let at_end = self.apply_assumes(value, width, from, self.blocks[from].len(), at_end);
+        if at_end.is_empty_set() {
+            return at_end;
+        }
+        let Some(terminator) = self.blocks[from].last() else {
+            return at_end;
+        };
+        let operands = terminator.operands();
+        let target = |index: usize| operands.get(index)
+            .and_then(|label| label.name())
+            .and_then(|name| self.cfg.block_index(name));
+        match terminator.opcode() {
+            Opcode::CondBr if target(1) != target(2) => {
+                let taken = target(1) == Some(to);
+                at_end.intersect_with(&self.condition_constraint(value, width, &operands[0], taken, 0))
+            }
+            Opcode::Switch if !operands.is_empty() && ValueKey::of(&operands[0]) == ValueKey::of(value) => {
+                if target(1) == Some(to) {
+                    return at_end;
+                }
+                let cases = (2..operands.len()).step_by(2)
+                    .filter(|&index| target(index + 1) == Some(to))
+                    .filter_map(|index| operands[index].as_const_int())
+                    .fold(ConstantRange::empty(width), |range, case| {
+                        range.union_with(&ConstantRange::single(width, case as u128))
+                    });
+                at_end.intersect_with(&cases)
+            }
+            _ => at_end,
+        }
+    }
+
+    /// Values `value` can have when `condition` is `taken`
+    fn condition_constraint(&self, value: &Value, width: u32, condition: &Value, taken: bool, depth: u32) -> ConstantRange {
+        let full = ConstantRange::full(width);
+        let key = ValueKey::of(value);
+        if ValueKey::of(condition) == key && width == 1 {
+            return ConstantRange::single(1, taken as u128);
+        }
+        let Some((_, inst)) = self.definitions.get(&ValueKey::of(condition)).filter(|_| depth < MAX_CONDITION_DEPTH) else {
+            return full;
+        };
+        let operands = inst.operands();
+        match inst.opcode() {
+            Opcode::ICmp if operands.len() == 2 => {
+                let Some(predicate) = inst.int_predicate() else { return full };
+                let predicate = if taken { predicate } else { predicate.inverse() };
+                let (predicate, other) = if ValueKey::of(&operands[0]) == key {
+                    (predicate, &operands[1])
+                } else if ValueKey::of(&operands[1]) == key {
+                    (predicate.swapped(), &operands[0])
+                } else {
+                    return full;
+                };
+                match other.as_const_int() {
+                    Some(constant) => ConstantRange::make_allowed_icmp_region(
+                        predicate, &ConstantRange::single(width, constant as u128)),
+                    None => full,
+                }
+            }
+            // Both conditions hold when an `and` does, neither when an `or` fails
+            Opcode::And | Opcode::Or if operands.len() == 2 && taken == (inst.opcode() == Opcode::And) => {
+                self.condition_constraint(value, width, &operands[0], taken, depth + 1)
+                    .intersect_with(&self.condition_constraint(value, width, &operands[1], taken, depth + 1))
+            }
+            _ => full,
+        }
+    }
+
+    /// Narrow a range by the assumes among the first `end` instructions
+    /// of a block
+    fn apply_assumes(&self, value: &Value, width: u32, block: usize, end: usize, range: ConstantRange) -> ConstantRange {
+        self.blocks[block].iter().take(end)
+            .filter(|inst| inst.opcode() == Opcode::Call && inst.operands().len() == 2)
+            .filter(|inst| inst.operands()[0].name() == Some("llvm.assume"))
+            .fold(range, |range, assume| {
+                range.intersect_with(&self.condition_constraint(value, width, &assume.operands()[1], true, 0))
+            })
+    }
+
+    /// Range of an instruction's result in the block that defines it
+    fn instruction_range(&self, inst: &Instruction, width: u32, block: usize) -> ConstantRange {
+        let operands = inst.operands();
+        let operand = |index: usize| operands.get(index).and_then(|operand| {
+            let width = operand.get_type().int_width()?;
+            Some(self.range_on_entry(operand, width, block))
+        });
+        let binary = |combine: fn(&ConstantRange, &ConstantRange) -> ConstantRange| {
+            operand(0).zip(operand(1))
+                .filter(|(a, b)| a.width() == width && b.width() == width)
+                .map(|(a, b)| combine(&a, &b))
+        };
+
+        let range = match inst.opcode() {
+            Opcode::Add => binary(ConstantRange::add),
+            Opcode::Sub => binary(ConstantRange::sub),
+            Opcode::Mul => binary(ConstantRange::multiply),
+            Opcode::UDiv => binary(ConstantRange::udiv),
+            Opcode::And => binary(ConstantRange::binary_and),
+            Opcode::Or => binary(ConstantRange::binary_or),
+            Opcode::Shl => binary(ConstantRange::shl),
+            Opcode::LShr => binary(ConstantRange::lshr),
+            Opcode::ZExt => operand(0).map(|a| a.zero_extend(width)),
+            Opcode::SExt => operand(0).map(|a| a.sign_extend(width)),
+            Opcode::Trunc => operand(0).map(|a| a.truncate(width)),
+            Opcode::Select => operand(1).zip(operand(2)).map(|(a, b)| a.union_with(&b)),
+            Opcode::PHI => Some(operands.chunks(2).fold(ConstantRange::empty(width), |range, pair| {
+                let pred = pair.get(1).and_then(|label| label.name()).and_then(|name| self.cfg.block_index(name));
+                match pred {
+                    Some(pred) if self.cfg.is_reachable(pred) => {
+                        range.union_with(&self.range_on_edge(&pair[0], width, pred, block))
+                    }
+                    Some(_) => range,
+                    None => ConstantRange::full(width),
+                }
+            })),
+            Opcode::ICmp => inst.int_predicate()
+                .zip(operand(0).zip(operand(1)))
+                .filter(|(_, (a, b))| a.width() == b.width())
+                .and_then(|(predicate, (a, b))| a.icmp(predicate, &b))
+                .map(|result| ConstantRange::single(1, result as u128)),
+            _ => None,
+        };
+        let mut range = range.filter(|range| range.width() == width).unwrap_or(ConstantRange::full(width));
+        if let Some(metadata) = range_metadata(inst, width) {
+            range = range.intersect_with(&metadata);
+        }
+        if let Some(result) = inst.result() {
+            let known = self.value_tracking.known_bits(result);
+            if known.width == width {
+                range = range.intersect_with(&known.to_range());
+            }
+        }
+        range
+    }
+}
+
+/// Lazy value info analysis pass
+pub struct LazyValueInfoPass;
+
+impl Pass for LazyValueInfoPass {
+    fn name(&self) -> &str {
+        "lazy-value-info"
+    }
+}
+
+impl AnalysisPass for LazyValueInfoPass {
+    type Result = LazyValueInfo;
+
+    fn run_analysis(&mut self, function: &Function) -> PassResult<Self::Result> {
+        Ok(LazyValueInfo::new(function))
+    }
+}
@@ -1,12 +1,21 @@
 //! LLVM IR Lexer
 //!
 //! Tokenizes LLVM IR text into a stream of tokens for parsing.
-
+//!
+//! The lexer scans the input as bytes and produces tokens on demand through
+//! [`Lexer::next_token`] or its `Iterator` implementation. Names and strings in
+//! those tokens borrow from the input whenever they contain no escapes (see
+//! [`BorrowedToken`]), so large modules can be scanned without copying the text
+//! or allocating per identifier; the parser pulls them this way through a small
+//! lookahead buffer. [`Lexer::tokenize`] still collects the whole stream as
+//! owned [`Token`]s for callers that want a `Vec`.
+
+use std::borrow::Cow;
 use std::fmt;
 
 #[allow(non_camel_case_types)]
 #[derive(Debug, Clone, PartialEq)]
-pub enum Token {
+pub enum Token<S = String> {
     // Keywords
     Define,
     Declare,
@@ -258,14 +267,15 @@ pub enum Token {
     X,                   // 'x' in array/vector types like [4 x i8]
 
     // Literals and identifiers
-    LocalIdent(String),  // %name or %0
-    GlobalIdent(String), // @name or @0
-    MetadataIdent(String), // !name or !0
+    LocalIdent(S),  // %name or %0
+    GlobalIdent(S), // @name or @0
+    MetadataIdent(S), // !name or !0
     AttrGroupId(u32),    // #0, #1, etc.
-    Identifier(String),  // Bare identifiers (for labels like BB1, then, etc.)
+    SummaryId(u32),      // ^0, ^1, etc.
+    Identifier(S),  // Bare identifiers (for labels like BB1, then, etc.)
     Integer(i128),
     Float64(f64),
-    StringLit(String),
+    StringLit(S),
     CString(Vec<u8>),
 
     // Symbols
@@ -290,7 +300,10 @@ pub enum Token {
     EOF,
 }
 
-impl fmt::Display for Token {
+/// A token whose names and strings borrow from the lexer input.
+pub type BorrowedToken<'a> = Token<Cow<'a, str>>;
+
+impl<S: fmt::Display + fmt::Debug> fmt::Display for Token<S> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Token::LocalIdent(s) => write!(f, "%{}", s),
@@ -305,358 +318,412 @@ impl fmt::Display for Token {
     }
 }
 
-pub struct Lexer {
-    input: Vec<char>,
+impl<'a> Token<Cow<'a, str>> {
+    /// Convert a borrowed token into an owned [`Token`]
+    pub fn into_owned(self) -> Token {
+        macro_rules! convert {
+            ($token:expr; $($unit:ident),* $(,)?) => {
+                match $token {
+                    Token::LocalIdent(s) => Token::LocalIdent(s.into_owned()),
+                    Token::GlobalIdent(s) => Token::GlobalIdent(s.into_owned()),
+                    Token::MetadataIdent(s) => Token::MetadataIdent(s.into_owned()),
+                    Token::Identifier(s) => Token::Identifier(s.into_owned()),
+                    Token::StringLit(s) => Token::StringLit(s.into_owned()),
+                    Token::CString(bytes) => Token::CString(bytes),
+                    Token::IntType(bits) => Token::IntType(bits),
+                    Token::AttrGroupId(id) => Token::AttrGroupId(id),
+                    Token::SummaryId(id) => Token::SummaryId(id),
+                    Token::Integer(n) => Token::Integer(n),
+                    Token::Float64(n) => Token::Float64(n),
+                    $(Token::$unit => Token::$unit,)*
+                }
+            };
+        }
+
+        convert!(self;
+            Define, Declare, Global, Constant, External, Private, Internal,
+            Available_externally, Linkonce, Weak, Common, Appending, Extern_weak, Linkonce_odr,
+            Weak_odr, Dllimport, Dllexport, Hidden, Protected, Default, Thread_local,
+            Unnamed_addr, Local_unnamed_addr, Type, Opaque, Null, None, True, False,
+            Zeroinitializer, Undef, Poison, To, Nuw, Nsw, Exact, Inbounds, Inrange, Volatile,
+            Atomic, Unordered, Monotonic, Acquire, Release, Acq_rel, Seq_cst, Singlethread,
+            Target, Datalayout, Triple, Source_filename, Attributes, Align, Addrspace, Section,
+            Comdat, Gc, Prefix, Prologue, Personality, Alias, Ifunc, Entry, Distinct, Nounwind,
+            Inreg, Byval, Inalloca, Sret, Preallocated, Noalias, Nocapture, Nest, Returned,
+            Nonnull, Dereferenceable, Dereferenceable_or_null, Swiftself, Swifterror,
+            Swiftasync, Immarg, Zeroext, Signext, Inlinehint, Alwaysinline, Noinline, Optsize,
+            Optnone, Minsize, Noreturn, Norecurse, Willreturn, Nosync, Sanitize_address,
+            Sanitize_thread, Sanitize_memory, Sanitize_hwaddress, Safestack, Uwtable,
+            Nocf_check, Shadowcallstack, Mustprogress, Vscale_range, Strictfp, Naked, Builtin,
+            Cold, Hot, Nobuiltin, Noduplicate, Noimplicitfloat, Nomerge, Nonlazybind, Noredzone,
+            Null_pointer_is_valid, Optforfuzzing, Readnone, Readonly, Writeonly, Argmemonly,
+            Inaccessiblememonly, Inaccessiblemem_or_argmemonly, Speculatable, Returns_twice,
+            Ssp, Sspreq, Sspstrong, Thunk, Amdgpu_kernel, Amdgpu_cs_chain, Amdgpu_ps, Syncscope,
+            Var, Dso_local, Dso_preemptable, Filename, Name, Ret, Br, Switch, IndirectBr,
+            Invoke, Resume, Unreachable, CleanupRet, CatchRet, CatchSwitch, CallBr, FNeg,
+            Freeze, Add, FAdd, Sub, FSub, Mul, FMul, UDiv, SDiv, FDiv, URem, SRem, FRem, Shl,
+            LShr, AShr, And, Or, Xor, ExtractElement, InsertElement, ShuffleVector,
+            ExtractValue, InsertValue, Alloca, Load, Store, GetElementPtr, Fence, AtomicCmpXchg,
+            AtomicRMW, Trunc, ZExt, SExt, FPToUI, FPToSI, UIToFP, SIToFP, FPTrunc, FPExt,
+            PtrToInt, IntToPtr, PtrToAddr, AddrToPtr, BitCast, AddrSpaceCast, ICmp, FCmp, Phi,
+            Call, Select, VAArg, LandingPad, Cleanup, Catch, Filter, Ptrauth, Eq, Ne, Ugt, Uge,
+            Ult, Ule, Sgt, Sge, Slt, Sle, Oeq, Ogt, Oge, Olt, Ole, One, Ord, Uno, Une, Ueq,
+            Void, Half, Bfloat, Float, Double, X86_fp80, Fp128, Ppc_fp128, X86_mmx, X86_amx,
+            Ptr, Label, Token, Metadata, X, Equal, Comma, LParen, RParen, LBrace, RBrace,
+            LBracket, RBracket, LAngle, RAngle, Star, Colon, Ellipsis, Pipe, Exclaim, Hash, EOF
+        )
+    }
+}
+
+/// Location of a token in the lexer input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    /// Byte offset of the first character of the token
+    pub start: usize,
+    /// Byte offset one past the last character of the token
+    pub end: usize,
+    /// 1-based line of the token start
+    pub line: usize,
+    /// 1-based column (in bytes) of the token start
+    pub column: usize,
+}
+
+pub struct Lexer<'a> {
+    source: &'a str,
+    input: &'a [u8],
     position: usize,
     line: usize,
-    column: usize,
+    line_start: usize,
+    finished: bool,
 }
 
-impl Lexer {
-    pub fn new(input: &str) -> Self {
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
         Self {
-            input: input.chars().collect(),
+            source: input,
+            input: input.as_bytes(),
             position: 0,
             line: 1,
-            column: 1,
+            line_start: 0,
+            finished: false,
         }
     }
 
+    /// Create a lexer that starts scanning at byte `offset` of `input`.
+    ///
+    /// Used to re-lex a range recorded from an earlier [`Span`].
+    pub fn with_offset(input: &'a str, offset: usize) -> Self {
+        let offset = offset.min(input.len());
+        assert!(input.is_char_boundary(offset), "lexer offset {} is not on a character boundary", offset);
+        let before = &input.as_bytes()[..offset];
+        let mut lexer = Self::new(input);
+        lexer.position = offset;
+        lexer.line = 1 + before.iter().filter(|&&b| b == b'\n').count();
+        lexer.line_start = before.iter().rposition(|&b| b == b'\n').map_or(0, |i| i + 1);
+        lexer
+    }
+
+    /// Create a lexer that resumes at a token found by an earlier scan of the
+    /// same `input`, keeping line and column numbers without rescanning.
+    pub fn resume(input: &'a str, span: Span) -> Self {
+        let mut lexer = Self::new(input);
+        lexer.position = span.start;
+        lexer.line = span.line;
+        lexer.line_start = span.start + 1 - span.column;
+        lexer
+    }
+
+    /// Current byte offset into the input
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Current 1-based line number
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    /// Current 1-based column number (in bytes)
+    pub fn column(&self) -> usize {
+        self.position - self.line_start + 1
+    }
+
+    /// Lex the remaining input into a vector of owned tokens terminated by `Token::EOF`
     pub fn tokenize(&mut self) -> Result<Vec<Token>, String> {
         let mut tokens = Vec::new();
 
         loop {
-            self.skip_whitespace_and_comments();
-
-            if self.is_at_end() {
-                tokens.push(Token::EOF);
+            let token = self.next_token()?;
+            let is_eof = token == Token::EOF;
+            tokens.push(token.into_owned());
+            if is_eof {
                 break;
             }
-
-            let token = self.next_token()?;
-            tokens.push(token);
         }
 
         Ok(tokens)
     }
 
-    fn next_token(&mut self) -> Result<Token, String> {
-        let ch = self.current_char();
+    /// Lex the next token, returning `Token::EOF` once the input is exhausted
+    pub fn next_token(&mut self) -> Result<BorrowedToken<'a>, String> {
+        self.next_spanned().map(|(token, _)| token)
+    }
+
+    /// Lex the next token together with its location in the input
+    pub fn next_spanned(&mut self) -> Result<(BorrowedToken<'a>, Span), String> {
+        self.skip_whitespace_and_comments();
+
+        let line = self.line;
+        let column = self.column();
+        let start = self.position;
+        let token = if self.is_at_end() {
+            Token::EOF
+        } else {
+            self.lex_token()?
+        };
+
+        Ok((token, Span { start, end: self.position, line, column }))
+    }
+
+    fn lex_token(&mut self) -> Result<BorrowedToken<'a>, String> {
+        let ch = self.current_byte();
 
         match ch {
-            '=' => { self.advance(); Ok(Token::Equal) }
-            ',' => { self.advance(); Ok(Token::Comma) }
-            '(' => { self.advance(); Ok(Token::LParen) }
-            ')' => { self.advance(); Ok(Token::RParen) }
-            '{' => { self.advance(); Ok(Token::LBrace) }
-            '}' => { self.advance(); Ok(Token::RBrace) }
-            '[' => { self.advance(); Ok(Token::LBracket) }
-            ']' => { self.advance(); Ok(Token::RBracket) }
-            '<' => { self.advance(); Ok(Token::LAngle) }
-            '>' => { self.advance(); Ok(Token::RAngle) }
-            '*' => { self.advance(); Ok(Token::Star) }
-            ':' => { self.advance(); Ok(Token::Colon) }
-            '|' => { self.advance(); Ok(Token::Pipe) }
-            '!' => {
+            b'=' => { self.advance(); Ok(Token::Equal) }
+            b',' => { self.advance(); Ok(Token::Comma) }
+            b'(' => { self.advance(); Ok(Token::LParen) }
+            b')' => { self.advance(); Ok(Token::RParen) }
+            b'{' => { self.advance(); Ok(Token::LBrace) }
+            b'}' => { self.advance(); Ok(Token::RBrace) }
+            b'[' => { self.advance(); Ok(Token::LBracket) }
+            b']' => { self.advance(); Ok(Token::RBracket) }
+            b'<' => { self.advance(); Ok(Token::LAngle) }
+            b'>' => { self.advance(); Ok(Token::RAngle) }
+            b'*' => { self.advance(); Ok(Token::Star) }
+            b':' => { self.advance(); Ok(Token::Colon) }
+            b'|' => { self.advance(); Ok(Token::Pipe) }
+            b'!' => {
                 self.advance();
-                if self.current_char().is_ascii_alphanumeric() || self.current_char() == '_' || self.current_char() == '\\' {
-                    self.read_metadata_ident()
+                let next = self.current_byte();
+                if next.is_ascii_alphanumeric() || next == b'_' || next == b'\\' {
+                    Ok(self.read_metadata_ident())
                 } else {
                     Ok(Token::Exclaim)
                 }
             }
-            '^' => {
-                // Summary/module metadata reference (e.g., ^0, ^1)
+            b'^' => {
+                // Summary entry (e.g., ^0, ^1)
                 self.advance();
-                if self.current_char().is_ascii_digit() {
-                    // Read numeric metadata reference
-                    self.read_metadata_ident()
+                if self.current_byte().is_ascii_digit() {
+                    match self.read_number_literal()? {
+                        Token::Integer(n) => Ok(Token::SummaryId(n as u32)),
+                        _ => Err("Expected integer after ^".to_string()),
+                    }
                 } else {
-                    // Just return caret as a token (for future use)
-                    Ok(Token::Exclaim) // Treat as metadata marker for now
+                    Err("Expected summary id after ^".to_string())
                 }
             }
-            '#' => {
+            b'#' => {
                 self.advance();
-                if self.current_char().is_ascii_digit() {
-                    let num = self.read_number_literal()?;
-                    if let Token::Integer(n) = num {
-                        Ok(Token::AttrGroupId(n as u32))
-                    } else {
-                        Err(format!("Expected integer after #"))
+                if self.current_byte().is_ascii_digit() {
+                    match self.read_number_literal()? {
+                        Token::Integer(n) => Ok(Token::AttrGroupId(n as u32)),
+                        _ => Err("Expected integer after #".to_string()),
                     }
                 } else {
                     Ok(Token::Hash)
                 }
             }
-            '%' => {
+            b'%' => {
                 self.advance();
-                self.read_local_ident()
+                Ok(Token::LocalIdent(self.read_name(false)))
             }
-            '@' => {
+            b'@' => {
                 self.advance();
-                self.read_global_ident()
+                Ok(Token::GlobalIdent(self.read_name(true)))
             }
-            '$' => {
-                // Comdat group name: $name
+            b'$' => {
+                // Comdat group name: $name (treated like a global ident)
                 self.advance();
-                self.read_global_ident() // Treat like global ident
+                Ok(Token::GlobalIdent(self.read_name(true)))
             }
-            '"' => self.read_string_literal(),
-            'c' if self.peek_char() == Some('"') => self.read_c_string(),
-            '-' | '0'..='9' => self.read_number_literal(),
-            'a'..='z' | 'A'..='Z' | '_' => self.read_keyword_or_ident(),
-            '.' => {
+            b'"' => Ok(self.read_string_literal()),
+            b'c' if self.peek_byte(1) == Some(b'"') => Ok(self.read_c_string()),
+            b'-' | b'0'..=b'9' => self.read_number_literal(),
+            b'a'..=b'z' | b'A'..=b'Z' | b'_' => self.read_keyword_or_ident(),
+            b'.' => {
                 // Check for ellipsis first
-                if self.peek_char() == Some('.') && self.peek_ahead(2) == Some('.') {
-                    self.advance();
-                    self.advance();
-                    self.advance();
+                if self.peek_byte(1) == Some(b'.') && self.peek_byte(2) == Some(b'.') {
+                    self.position += 3;
                     Ok(Token::Ellipsis)
-                } else if self.peek_char().map_or(false, |c| c.is_alphanumeric() || c == '_') {
+                } else if self.peek_byte(1).is_some_and(|c| c.is_ascii_alphanumeric() || c == b'_') {
                     // Dot followed by alphanumeric (e.g., .L.entry label)
                     self.read_keyword_or_ident()
                 } else {
-                    Err(format!("Unexpected character '{}' at line {}, column {}", ch, self.line, self.column))
+                    Err(self.unexpected_character())
                 }
             }
-            _ => Err(format!("Unexpected character '{}' at line {}, column {}", ch, self.line, self.column))
+            _ => Err(self.unexpected_character()),
         }
     }
 
-    fn read_local_ident(&mut self) -> Result<Token, String> {
-        let mut name = String::new();
-
-        // Handle numeric identifiers like %0, %1
-        if self.current_char().is_ascii_digit() {
-            while !self.is_at_end() && self.current_char().is_ascii_digit() {
-                name.push(self.current_char());
-                self.advance();
-            }
-            return Ok(Token::LocalIdent(name));
-        }
-
-        // Handle quoted identifiers
-        if self.current_char() == '"' {
-            self.advance(); // skip opening "
-            while !self.is_at_end() && self.current_char() != '"' {
-                if self.current_char() == '\\' {
-                    self.advance();
-                    if !self.is_at_end() {
-                        name.push(self.current_char());
-                        self.advance();
-                    }
-                } else {
-                    name.push(self.current_char());
-                    self.advance();
-                }
-            }
-            if self.current_char() == '"' {
-                self.advance(); // skip closing "
-            }
-            return Ok(Token::LocalIdent(name));
-        }
-
-        // Handle normal identifiers
-        while !self.is_at_end() {
-            let ch = self.current_char();
-            if ch.is_ascii_alphanumeric() || ch == '_' || ch == '.' || ch == '-' {
-                name.push(ch);
-                self.advance();
-            } else {
-                break;
-            }
-        }
-
-        Ok(Token::LocalIdent(name))
+    fn unexpected_character(&self) -> String {
+        let ch = self.source[self.position..].chars().next().unwrap_or('\0');
+        format!("Unexpected character '{}' at line {}, column {}", ch, self.line, self.column())
     }
 
-    fn read_global_ident(&mut self) -> Result<Token, String> {
-        let mut name = String::new();
-
-        // Handle numeric identifiers
-        if self.current_char().is_ascii_digit() {
-            while !self.is_at_end() && self.current_char().is_ascii_digit() {
-                name.push(self.current_char());
-                self.advance();
-            }
-            return Ok(Token::GlobalIdent(name));
+    /// Read the name following a `%`, `@` or `$` sigil: a number, a quoted
+    /// string or a bare identifier
+    fn read_name(&mut self, is_global: bool) -> Cow<'a, str> {
+        // Handle numeric identifiers like %0, @1
+        if self.current_byte().is_ascii_digit() {
+            return Cow::Borrowed(self.scan_while(|b| b.is_ascii_digit()));
         }
 
         // Handle quoted identifiers
-        if self.current_char() == '"' {
-            self.advance(); // skip opening "
-            while !self.is_at_end() && self.current_char() != '"' {
-                if self.current_char() == '\\' {
-                    self.advance();
-                    if !self.is_at_end() {
-                        name.push(self.current_char());
-                        self.advance();
-                    }
-                } else {
-                    name.push(self.current_char());
-                    self.advance();
-                }
-            }
-            if self.current_char() == '"' {
-                self.advance(); // skip closing "
-            }
-            return Ok(Token::GlobalIdent(name));
+        if self.current_byte() == b'"' {
+            let (raw, escaped) = self.scan_quoted();
+            return if escaped { Cow::Owned(unescape_name(raw)) } else { Cow::Borrowed(raw) };
         }
 
         // Handle normal identifiers
-        while !self.is_at_end() {
-            let ch = self.current_char();
-            if ch.is_ascii_alphanumeric() || ch == '_' || ch == '.' || ch == '-' || ch == '$' {
-                name.push(ch);
-                self.advance();
-            } else {
-                break;
-            }
+        if is_global {
+            Cow::Borrowed(self.scan_while(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'.' | b'-' | b'$')))
+        } else {
+            Cow::Borrowed(self.scan_while(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'.' | b'-')))
         }
-
-        Ok(Token::GlobalIdent(name))
     }
 
-    fn read_metadata_ident(&mut self) -> Result<Token, String> {
-        let mut name = String::new();
+    fn read_metadata_ident(&mut self) -> BorrowedToken<'a> {
+        let start = self.position;
 
         while !self.is_at_end() {
-            let ch = self.current_char();
-            if ch == '\\' {
-                // Handle backslash escape sequences (octal codes like \34)
-                self.advance(); // skip backslash
-                name.push('\\');
-                // Read up to 2 octal digits or any following characters
+            let ch = self.current_byte();
+            if ch == b'\\' {
+                // Keep backslash escape sequences (octal codes like \34) verbatim
+                self.advance();
                 for _ in 0..2 {
-                    if !self.is_at_end() && self.current_char().is_ascii_digit() {
-                        name.push(self.current_char());
+                    if !self.is_at_end() && self.current_byte().is_ascii_digit() {
                         self.advance();
                     } else {
                         break;
                     }
                 }
-            } else if ch.is_ascii_alphanumeric() || ch == '_' || ch == '.' || ch == '-' {
-                name.push(ch);
+            } else if ch.is_ascii_alphanumeric() || matches!(ch, b'_' | b'.' | b'-') {
                 self.advance();
             } else {
                 break;
             }
         }
 
-        Ok(Token::MetadataIdent(name))
+        Token::MetadataIdent(Cow::Borrowed(&self.source[start..self.position]))
     }
 
-    fn read_string_literal(&mut self) -> Result<Token, String> {
-        self.advance(); // skip opening "
-        let mut s = String::new();
+    fn read_string_literal(&mut self) -> BorrowedToken<'a> {
+        let (raw, escaped) = self.scan_quoted();
+        if escaped {
+            Token::StringLit(Cow::Owned(unescape_string(raw)))
+        } else {
+            Token::StringLit(Cow::Borrowed(raw))
+        }
+    }
 
-        while !self.is_at_end() && self.current_char() != '"' {
-            if self.current_char() == '\\' {
-                self.advance();
-                if !self.is_at_end() {
-                    let escape = self.current_char();
-                    match escape {
-                        'n' => s.push('\n'),
-                        'r' => s.push('\r'),
-                        't' => s.push('\t'),
-                        '\\' => s.push('\\'),
-                        '"' => s.push('"'),
-                        '0' => s.push('\0'),
-                        _ => s.push(escape),
-                    }
-                    self.advance();
+    fn read_c_string(&mut self) -> BorrowedToken<'a> {
+        self.advance(); // skip 'c'
+        let (raw, escaped) = self.scan_quoted();
+        let raw = raw.as_bytes();
+        if !escaped {
+            return Token::CString(raw.to_vec());
+        }
+
+        let mut bytes = Vec::with_capacity(raw.len());
+        let mut i = 0;
+        while i < raw.len() {
+            if raw[i] != b'\\' {
+                bytes.push(raw[i]);
+                i += 1;
+                continue;
+            }
+            i += 1;
+            let Some(&escape) = raw.get(i) else { break };
+            // Parse hex escape sequences like \FF
+            if escape.is_ascii_hexdigit() {
+                let len = if raw.get(i + 1).is_some_and(|b| b.is_ascii_hexdigit()) { 2 } else { 1 };
+                let hex = std::str::from_utf8(&raw[i..i + len]).unwrap_or("");
+                if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                    bytes.push(byte);
                 }
+                i += len;
             } else {
-                s.push(self.current_char());
-                self.advance();
+                bytes.push(match escape {
+                    b'n' => b'\n',
+                    b'r' => b'\r',
+                    b't' => b'\t',
+                    other => other,
+                });
+                i += 1;
             }
         }
 
-        if self.current_char() == '"' {
-            self.advance(); // skip closing "
-        }
-
-        Ok(Token::StringLit(s))
+        Token::CString(bytes)
     }
 
-    fn read_c_string(&mut self) -> Result<Token, String> {
-        self.advance(); // skip 'c'
-        self.advance(); // skip '"'
-        let mut bytes = Vec::new();
+    /// Scan a double-quoted string starting at the opening quote. Returns the
+    /// raw text between the quotes and whether it contains escapes.
+    fn scan_quoted(&mut self) -> (&'a str, bool) {
+        self.advance(); // skip opening "
+        let start = self.position;
+        let mut escaped = false;
 
-        while !self.is_at_end() && self.current_char() != '"' {
-            if self.current_char() == '\\' {
-                self.advance();
-                if !self.is_at_end() {
-                    let escape = self.current_char();
-                    // Parse hex escape sequences like \FF
-                    if escape.is_ascii_hexdigit() {
-                        let mut hex = String::new();
-                        hex.push(escape);
-                        self.advance();
-                        if !self.is_at_end() && self.current_char().is_ascii_hexdigit() {
-                            hex.push(self.current_char());
-                            self.advance();
-                        }
-                        if let Ok(byte) = u8::from_str_radix(&hex, 16) {
-                            bytes.push(byte);
-                        }
-                    } else {
-                        match escape {
-                            'n' => bytes.push(b'\n'),
-                            'r' => bytes.push(b'\r'),
-                            't' => bytes.push(b'\t'),
-                            '\\' => bytes.push(b'\\'),
-                            '"' => bytes.push(b'"'),
-                            '0' => bytes.push(0),
-                            _ => bytes.push(escape as u8),
-                        }
-                        self.advance();
-                    }
-                }
-            } else {
-                bytes.push(self.current_char() as u8);
+        while !self.is_at_end() && self.current_byte() != b'"' {
+            if self.current_byte() == b'\\' {
+                escaped = true;
                 self.advance();
             }
+            self.advance();
         }
 
-        if self.current_char() == '"' {
+        let end = self.position.min(self.input.len());
+        self.position = end;
+        let raw = &self.source[start..end];
+        if self.current_byte() == b'"' {
             self.advance(); // skip closing "
         }
-
-        Ok(Token::CString(bytes))
+        (raw, escaped)
     }
 
-    fn read_number_literal(&mut self) -> Result<Token, String> {
-        let mut num = String::new();
-        let is_negative = self.current_char() == '-';
+    fn read_number_literal(&mut self) -> Result<BorrowedToken<'a>, String> {
+        let start = self.position;
+
+        // Labels such as `-N-:` start like a number but are not one
+        let label_len = self.input[start..].iter()
+            .take_while(|&&b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'.' | b'-' | b'$'))
+            .count();
+        if self.input.get(start + label_len) == Some(&b':') {
+            let word = &self.source[start..start + label_len];
+            if !word.strip_prefix('-').unwrap_or(word).bytes().all(|b| b.is_ascii_digit()) {
+                self.position += label_len;
+                return Ok(Token::Identifier(Cow::Borrowed(word)));
+            }
+        }
+
+        let is_negative = self.current_byte() == b'-';
 
         if is_negative {
-            num.push('-');
             self.advance();
         }
 
         // Check for hex number (0x...)
-        if self.current_char() == '0' && self.peek_char() == Some('x') {
-            num.push('0');
-            self.advance();
-            num.push('x');
-            self.advance();
+        if self.current_byte() == b'0' && self.peek_byte(1) == Some(b'x') {
+            self.position += 2;
 
             // Check for special float prefixes: 0xH (half/f16), 0xR (bfloat), 0xK (f80), 0xM (fp128), 0xL (ppc_fp128)
-            let special_float = matches!(self.current_char(), 'H' | 'R' | 'K' | 'M' | 'L');
+            let special_float = matches!(self.current_byte(), b'H' | b'R' | b'K' | b'M' | b'L');
             if special_float {
-                num.push(self.current_char());
                 self.advance();
             }
 
-            while !self.is_at_end() && (self.current_char().is_ascii_hexdigit() || self.current_char() == '_') {
-                if self.current_char() != '_' {
-                    num.push(self.current_char());
-                }
-                self.advance();
-            }
+            let digits = self.scan_while(|b| b.is_ascii_hexdigit() || b == b'_');
 
             if special_float {
                 // For special float formats, just return as a float token
@@ -664,54 +731,37 @@ impl Lexer {
                 return Ok(Token::Float64(0.0)); // Placeholder value
             }
 
-            let value = i128::from_str_radix(&num[2..], 16)
+            let value = without_underscores(digits, |digits| i128::from_str_radix(digits, 16))
                 .map_err(|e| format!("Invalid hex number: {}", e))?;
             return Ok(Token::Integer(if is_negative { -value } else { value }));
         }
 
         // Read integer part
-        while !self.is_at_end() && (self.current_char().is_ascii_digit() || self.current_char() == '_') {
-            if self.current_char() != '_' {
-                num.push(self.current_char());
-            }
-            self.advance();
-        }
+        self.scan_while(|b| b.is_ascii_digit() || b == b'_');
 
         // Check for float (has decimal point or exponent)
-        if !self.is_at_end() && (self.current_char() == '.' || self.current_char() == 'e' || self.current_char() == 'E') {
-            if self.current_char() == '.' {
-                num.push('.');
+        if matches!(self.current_byte(), b'.' | b'e' | b'E') {
+            if self.current_byte() == b'.' {
                 self.advance();
-
-                while !self.is_at_end() && self.current_char().is_ascii_digit() {
-                    num.push(self.current_char());
-                    self.advance();
-                }
+                self.scan_while(|b| b.is_ascii_digit());
             }
 
-            if !self.is_at_end() && (self.current_char() == 'e' || self.current_char() == 'E') {
-                num.push(self.current_char());
+            if matches!(self.current_byte(), b'e' | b'E') {
                 self.advance();
-
-                if !self.is_at_end() && (self.current_char() == '+' || self.current_char() == '-') {
-                    num.push(self.current_char());
-                    self.advance();
-                }
-
-                while !self.is_at_end() && self.current_char().is_ascii_digit() {
-                    num.push(self.current_char());
+                if matches!(self.current_byte(), b'+' | b'-') {
                     self.advance();
                 }
+                self.scan_while(|b| b.is_ascii_digit());
             }
 
-            let value = num.parse::<f64>()
+            let value = without_underscores(&self.source[start..self.position], |text| text.parse::<f64>())
                 .map_err(|e| format!("Invalid float number: {}", e))?;
             return Ok(Token::Float64(value));
         }
 
         // It's an integer
         // Handle very large integers gracefully (they appear in metadata)
-        let value = match num.parse::<i128>() {
+        let value = match without_underscores(&self.source[start..self.position], |text| text.parse::<i128>()) {
             Ok(v) => v,
             Err(_) => {
                 // Integer too large for i128, use max/min as fallback
@@ -726,22 +776,12 @@ impl Lexer {
         Ok(Token::Integer(value))
     }
 
-    fn read_keyword_or_ident(&mut self) -> Result<Token, String> {
-        let mut word = String::new();
-
-        while !self.is_at_end() {
-            let ch = self.current_char();
-            // Allow dots and hyphens in identifiers (e.g., labels like "then.7", "no_exit.2")
-            if ch.is_ascii_alphanumeric() || ch == '_' || ch == '.' || ch == '-' {
-                word.push(ch);
-                self.advance();
-            } else {
-                break;
-            }
-        }
+    fn read_keyword_or_ident(&mut self) -> Result<BorrowedToken<'a>, String> {
+        // Allow dots and hyphens in identifiers (e.g., labels like "then.7", "no_exit.2")
+        let word = self.scan_while(|b| b.is_ascii_alphanumeric() || matches!(b, b'_' | b'.' | b'-'));
 
         // Check if it's an integer type
-        if word.starts_with('i') && word.len() > 1 {
+        if word.len() > 1 && word.as_bytes()[0] == b'i' {
             if let Ok(bits) = word[1..].parse::<u32>() {
                 // LLVM enforces a maximum bitwidth of 2^23 = 8388608
                 if bits > 8388608 {
@@ -751,308 +791,44 @@ impl Lexer {
             }
         }
 
-        // Check for keywords
-        let token = match word.as_str() {
-            "define" => Token::Define,
-            "declare" => Token::Declare,
-            "global" => Token::Global,
-            "constant" => Token::Constant,
-            "external" => Token::External,
-            "private" => Token::Private,
-            "internal" => Token::Internal,
-            "available_externally" => Token::Available_externally,
-            "linkonce" => Token::Linkonce,
-            "weak" => Token::Weak,
-            "common" => Token::Common,
-            "appending" => Token::Appending,
-            "extern_weak" => Token::Extern_weak,
-            "linkonce_odr" => Token::Linkonce_odr,
-            "weak_odr" => Token::Weak_odr,
-            "dllimport" => Token::Dllimport,
-            "dllexport" => Token::Dllexport,
-            "hidden" => Token::Hidden,
-            "protected" => Token::Protected,
-            "default" => Token::Default,
-            "thread_local" => Token::Thread_local,
-            "unnamed_addr" => Token::Unnamed_addr,
-            "local_unnamed_addr" => Token::Local_unnamed_addr,
-            "type" => Token::Type,
-            "opaque" => Token::Opaque,
-            "null" => Token::Null,
-            "none" => Token::None,
-            "true" => Token::True,
-            "false" => Token::False,
-            "zeroinitializer" => Token::Zeroinitializer,
-            "undef" => Token::Undef,
-            "poison" => Token::Poison,
-            "to" => Token::To,
-            "nuw" => Token::Nuw,
-            "nsw" => Token::Nsw,
-            "exact" => Token::Exact,
-            "inbounds" => Token::Inbounds,
-            "inrange" => Token::Inrange,
-            "volatile" => Token::Volatile,
-            "atomic" => Token::Atomic,
-            "unordered" => Token::Unordered,
-            "monotonic" => Token::Monotonic,
-            "acquire" => Token::Acquire,
-            "release" => Token::Release,
-            "acq_rel" => Token::Acq_rel,
-            "seq_cst" => Token::Seq_cst,
-            "singlethread" => Token::Singlethread,
-            "target" => Token::Target,
-            "datalayout" => Token::Datalayout,
-            "triple" => Token::Triple,
-            "source_filename" => Token::Source_filename,
-            "attributes" => Token::Attributes,
-            "align" => Token::Align,
-            "addrspace" => Token::Addrspace,
-            "section" => Token::Section,
-            "comdat" => Token::Comdat,
-            "gc" => Token::Gc,
-            "prefix" => Token::Prefix,
-            "prologue" => Token::Prologue,
-            "personality" => Token::Personality,
-
-            // Instructions
-            "ret" => Token::Ret,
-            "br" => Token::Br,
-            "switch" => Token::Switch,
-            "indirectbr" => Token::IndirectBr,
-            "invoke" => Token::Invoke,
-            "resume" => Token::Resume,
-            "unreachable" => Token::Unreachable,
-            "cleanupret" => Token::CleanupRet,
-            "catchret" => Token::CatchRet,
-            "catchswitch" => Token::CatchSwitch,
-            "callbr" => Token::CallBr,
-            "fneg" => Token::FNeg,
-            "freeze" => Token::Freeze,
-            "add" => Token::Add,
-            "fadd" => Token::FAdd,
-            "sub" => Token::Sub,
-            "fsub" => Token::FSub,
-            "mul" => Token::Mul,
-            "fmul" => Token::FMul,
-            "udiv" => Token::UDiv,
-            "sdiv" => Token::SDiv,
-            "fdiv" => Token::FDiv,
-            "urem" => Token::URem,
-            "srem" => Token::SRem,
-            "frem" => Token::FRem,
-            "shl" => Token::Shl,
-            "lshr" => Token::LShr,
-            "ashr" => Token::AShr,
-            "and" => Token::And,
-            "or" => Token::Or,
-            "xor" => Token::Xor,
-            "extractelement" => Token::ExtractElement,
-            "insertelement" => Token::InsertElement,
-            "shufflevector" => Token::ShuffleVector,
-            "extractvalue" => Token::ExtractValue,
-            "insertvalue" => Token::InsertValue,
-            "alloca" => Token::Alloca,
-            "load" => Token::Load,
-            "store" => Token::Store,
-            "getelementptr" => Token::GetElementPtr,
-            "fence" => Token::Fence,
-            "cmpxchg" => Token::AtomicCmpXchg,
-            "atomicrmw" => Token::AtomicRMW,
-            "trunc" => Token::Trunc,
-            "zext" => Token::ZExt,
-            "sext" => Token::SExt,
-            "fptoui" => Token::FPToUI,
-            "fptosi" => Token::FPToSI,
-            "uitofp" => Token::UIToFP,
-            "sitofp" => Token::SIToFP,
-            "fptrunc" => Token::FPTrunc,
-            "fpext" => Token::FPExt,
-            "ptrtoint" => Token::PtrToInt,
-            "inttoptr" => Token::IntToPtr,
-            "ptrtoaddr" => Token::PtrToAddr,
-            "addrtoptr" => Token::AddrToPtr,
-            "bitcast" => Token::BitCast,
-            "addrspacecast" => Token::AddrSpaceCast,
-            "icmp" => Token::ICmp,
-            "fcmp" => Token::FCmp,
-            "phi" => Token::Phi,
-            "call" => Token::Call,
-            "select" => Token::Select,
-            "va_arg" => Token::VAArg,
-            "landingpad" => Token::LandingPad,
-            "cleanup" => Token::Cleanup,
-            "catch" => Token::Catch,
-            "filter" => Token::Filter,
-            "ptrauth" => Token::Ptrauth,
-
-            // Comparison predicates
-            "eq" => Token::Eq,
-            "ne" => Token::Ne,
-            "ugt" => Token::Ugt,
-            "uge" => Token::Uge,
-            "ult" => Token::Ult,
-            "ule" => Token::Ule,
-            "sgt" => Token::Sgt,
-            "sge" => Token::Sge,
-            "slt" => Token::Slt,
-            "sle" => Token::Sle,
-            "oeq" => Token::Oeq,
-            "ogt" => Token::Ogt,
-            "oge" => Token::Oge,
-            "olt" => Token::Olt,
-            "ole" => Token::Ole,
-            "one" => Token::One,
-            "ord" => Token::Ord,
-            "uno" => Token::Uno,
-            "une" => Token::Une,
-            "ueq" => Token::Ueq,
-
-            // Types
-            "void" => Token::Void,
-            "half" => Token::Half,
-            "bfloat" => Token::Bfloat,
-            "float" => Token::Float,
-            "double" => Token::Double,
-            "x86_fp80" => Token::X86_fp80,
-            "fp128" => Token::Fp128,
-            "ppc_fp128" => Token::Ppc_fp128,
-            "x86_mmx" => Token::X86_mmx,
-            "x86_amx" => Token::X86_amx,
-            "ptr" => Token::Ptr,
-            "label" => Token::Label,
-            "token" => Token::Token,
-            "metadata" => Token::Metadata,
-            "x" => Token::X,
-            "alias" => Token::Alias,
-            "ifunc" => Token::Ifunc,
-            "entry" => Token::Entry,
-            "distinct" => Token::Distinct,
-            "nounwind" => Token::Nounwind,
-            "inreg" => Token::Inreg,
-            "byval" => Token::Byval,
-            "inalloca" => Token::Inalloca,
-            "sret" => Token::Sret,
-            "preallocated" => Token::Preallocated,
-            "noalias" => Token::Noalias,
-            "nocapture" => Token::Nocapture,
-            "nest" => Token::Nest,
-            "returned" => Token::Returned,
-            "nonnull" => Token::Nonnull,
-            "dereferenceable" => Token::Dereferenceable,
-            "dereferenceable_or_null" => Token::Dereferenceable_or_null,
-            "swiftself" => Token::Swiftself,
-            "swifterror" => Token::Swifterror,
-            "swiftasync" => Token::Swiftasync,
-            "immarg" => Token::Immarg,
-            "zeroext" => Token::Zeroext,
-            "signext" => Token::Signext,
-            "inlinehint" => Token::Inlinehint,
-            "alwaysinline" => Token::Alwaysinline,
-            "noinline" => Token::Noinline,
-            "optsize" => Token::Optsize,
-            "optnone" => Token::Optnone,
-            "minsize" => Token::Minsize,
-            "noreturn" => Token::Noreturn,
-            "norecurse" => Token::Norecurse,
-            "willreturn" => Token::Willreturn,
-            "nosync" => Token::Nosync,
-            "sanitize_address" => Token::Sanitize_address,
-            "sanitize_thread" => Token::Sanitize_thread,
-            "sanitize_memory" => Token::Sanitize_memory,
-            "sanitize_hwaddress" => Token::Sanitize_hwaddress,
-            "safestack" => Token::Safestack,
-            "uwtable" => Token::Uwtable,
-            "nocf_check" => Token::Nocf_check,
-            "shadowcallstack" => Token::Shadowcallstack,
-            "mustprogress" => Token::Mustprogress,
-            "vscale_range" => Token::Vscale_range,
-            "strictfp" => Token::Strictfp,
-            "naked" => Token::Naked,
-            "builtin" => Token::Builtin,
-            "cold" => Token::Cold,
-            "hot" => Token::Hot,
-            "nobuiltin" => Token::Nobuiltin,
-            "noduplicate" => Token::Noduplicate,
-            "noimplicitfloat" => Token::Noimplicitfloat,
-            "nomerge" => Token::Nomerge,
-            "nonlazybind" => Token::Nonlazybind,
-            "noredzone" => Token::Noredzone,
-            "null_pointer_is_valid" => Token::Null_pointer_is_valid,
-            "optforfuzzing" => Token::Optforfuzzing,
-            "readnone" => Token::Readnone,
-            "readonly" => Token::Readonly,
-            "writeonly" => Token::Writeonly,
-            "argmemonly" => Token::Argmemonly,
-            "inaccessiblememonly" => Token::Inaccessiblememonly,
-            "inaccessiblemem_or_argmemonly" => Token::Inaccessiblemem_or_argmemonly,
-            "speculatable" => Token::Speculatable,
-            "returns_twice" => Token::Returns_twice,
-            "ssp" => Token::Ssp,
-            "sspreq" => Token::Sspreq,
-            "sspstrong" => Token::Sspstrong,
-            "thunk" => Token::Thunk,
-            "amdgpu_kernel" => Token::Amdgpu_kernel,
-            "amdgpu_cs_chain" => Token::Amdgpu_cs_chain,
-            "amdgpu_ps" => Token::Amdgpu_ps,
-            "syncscope" => Token::Syncscope,
-            "var" => Token::Var,
-            "dso_local" => Token::Dso_local,
-            "dso_preemptable" => Token::Dso_preemptable,
-            "filename" => Token::Filename,
-            "name" => Token::Name,
-
-            _ => {
-                // Unknown keyword - return as bare identifier (used for labels like BB1, then, etc.)
-                Token::Identifier(word)
-            }
-        };
-
-        Ok(token)
+        // Unknown keywords are returned as bare identifiers (used for labels like BB1, then, etc.)
+        Ok(keyword(word).unwrap_or(Token::Identifier(Cow::Borrowed(word))))
     }
 
     fn skip_whitespace_and_comments(&mut self) {
-        while !self.is_at_end() {
-            match self.current_char() {
-                ' ' | '\t' | '\r' => {
+        while let Some(&ch) = self.input.get(self.position) {
+            match ch {
+                b' ' | b'\t' | b'\r' => {
                     self.advance();
                 }
-                '\n' => {
-                    self.advance();
-                    self.line += 1;
-                    self.column = 1;
+                b'\n' => {
+                    self.newline();
                 }
-                ';' => {
+                b';' => {
                     // Skip line comment until end of line
-                    while !self.is_at_end() && self.current_char() != '\n' {
-                        self.advance();
-                    }
+                    self.skip_to_end_of_line();
                 }
-                '/' => {
+                b'/' => {
                     // Check for C-style comment /* ... */ or C++ style // ...
-                    if self.peek_char() == Some('*') {
-                        self.advance(); // consume '/'
-                        self.advance(); // consume '*'
-                        // Skip until we find */
-                        while !self.is_at_end() {
-                            if self.current_char() == '*' && self.peek_char() == Some('/') {
-                                self.advance(); // consume '*'
-                                self.advance(); // consume '/'
-                                break;
-                            }
-                            if self.current_char() == '\n' {
-                                self.line += 1;
-                                self.column = 1;
+                    match self.peek_byte(1) {
+                        Some(b'*') => {
+                            self.position += 2;
+                            // Skip until we find */
+                            while !self.is_at_end() {
+                                if self.current_byte() == b'*' && self.peek_byte(1) == Some(b'/') {
+                                    self.position += 2;
+                                    break;
+                                }
+                                if self.current_byte() == b'\n' {
+                                    self.newline();
+                                } else {
+                                    self.advance();
+                                }
                             }
-                            self.advance();
                         }
-                    } else if self.peek_char() == Some('/') {
-                        // C++ style comment: // ... until end of line
-                        while !self.is_at_end() && self.current_char() != '\n' {
-                            self.advance();
-                        }
-                    } else {
+                        Some(b'/') => self.skip_to_end_of_line(),
                         // Not a comment, stop skipping
-                        break;
+                        _ => break,
                     }
                 }
                 _ => break,
@@ -1060,34 +836,44 @@ impl Lexer {
         }
     }
 
-    fn current_char(&self) -> char {
-        if self.position < self.input.len() {
-            self.input[self.position]
-        } else {
-            '\0'
-        }
+    fn skip_to_end_of_line(&mut self) {
+        self.position = self.input[self.position..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(self.input.len(), |offset| self.position + offset);
     }
 
-    fn peek_char(&self) -> Option<char> {
-        if self.position + 1 < self.input.len() {
-            Some(self.input[self.position + 1])
-        } else {
-            None
-        }
+    /// Consume a newline character and update line tracking
+    fn newline(&mut self) {
+        self.advance();
+        self.line += 1;
+        self.line_start = self.position;
     }
 
-    fn peek_ahead(&self, n: usize) -> Option<char> {
-        if self.position + n < self.input.len() {
-            Some(self.input[self.position + n])
-        } else {
-            None
+    /// Advance while `pred` holds and return the scanned text. `pred` must only
+    /// accept ASCII bytes so the slice stays on character boundaries.
+    fn scan_while(&mut self, pred: impl Fn(u8) -> bool) -> &'a str {
+        let start = self.position;
+        while let Some(&b) = self.input.get(self.position) {
+            if !pred(b) {
+                break;
+            }
+            self.position += 1;
         }
+        &self.source[start..self.position]
+    }
+
+    fn current_byte(&self) -> u8 {
+        self.input.get(self.position).copied().unwrap_or(0)
+    }
+
+    fn peek_byte(&self, n: usize) -> Option<u8> {
+        self.input.get(self.position + n).copied()
     }
 
     fn advance(&mut self) {
         if self.position < self.input.len() {
             self.position += 1;
-            self.column += 1;
         }
     }
 
@@ -1096,6 +882,349 @@ impl Lexer {
     }
 }
 
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<(BorrowedToken<'a>, Span), String>;
+
+    /// Yield tokens up to (but not including) `Token::EOF`; iteration stops
+    /// after the first lexer error.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        match self.next_spanned() {
+            Ok((Token::EOF, _)) => {
+                self.finished = true;
+                None
+            }
+            Ok(spanned) => Some(Ok(spanned)),
+            Err(e) => {
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Apply `parse` to `text`, stripping `_` digit separators first if present
+fn without_underscores<T>(text: &str, parse: impl Fn(&str) -> T) -> T {
+    if text.contains('_') {
+        parse(&text.replace('_', ""))
+    } else {
+        parse(text)
+    }
+}
+
+/// Resolve escapes in a quoted `%`/`@` name: a backslash keeps the next character
+fn unescape_name(raw: &str) -> String {
+    let mut name = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(ch) = chars.next() {
+        if ch == '\\' {
+            if let Some(next) = chars.next() {
+                name.push(next);
+            }
+        } else {
+            name.push(ch);
+        }
+    }
+    name
+}
+
+/// Resolve escapes in a string literal
+fn unescape_string(raw: &str) -> String {
+    let mut s = String::with_capacity(raw.len());
+    let mut chars = raw.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            s.push(ch);
+            continue;
+        }
+        if let Some(escape) = chars.next() {
+            match escape {
+                'n' => s.push('\n'),
+                'r' => s.push('\r'),
+                't' => s.push('\t'),
+                '0' => s.push('\0'),
+                _ => s.push(escape),
+            }
+        }
+    }
+    s
+}
+
+macro_rules! keywords {
+    ($($word:literal => Token::$token:ident,)*) => {
+        /// Look up the token for a reserved word
+        fn keyword<S>(word: &str) -> Option<Token<S>> {
+            Some(match word {
+                $($word => Token::$token,)*
+                _ => return None,
+            })
+        }
+
+        impl<S> Token<S> {
+            /// Source spelling of a reserved word token, e.g. `"external"` for
+            /// `Token::External`. Returns `None` for every other token.
+            pub fn keyword_spelling(&self) -> Option<&'static str> {
+                Some(match self {
+                    $(Token::$token => $word,)*
+                    _ => return None,
+                })
+            }
+        }
+    };
+}
+
+keywords! {
+    "define" => Token::Define,
+    "declare" => Token::Declare,
+    "global" => Token::Global,
+    "constant" => Token::Constant,
+    "external" => Token::External,
+    "private" => Token::Private,
+    "internal" => Token::Internal,
+    "available_externally" => Token::Available_externally,
+    "linkonce" => Token::Linkonce,
+    "weak" => Token::Weak,
+    "common" => Token::Common,
+    "appending" => Token::Appending,
+    "extern_weak" => Token::Extern_weak,
+    "linkonce_odr" => Token::Linkonce_odr,
+    "weak_odr" => Token::Weak_odr,
+    "dllimport" => Token::Dllimport,
+    "dllexport" => Token::Dllexport,
+    "hidden" => Token::Hidden,
+    "protected" => Token::Protected,
+    "default" => Token::Default,
+    "thread_local" => Token::Thread_local,
+    "unnamed_addr" => Token::Unnamed_addr,
+    "local_unnamed_addr" => Token::Local_unnamed_addr,
+    "type" => Token::Type,
+    "opaque" => Token::Opaque,
+    "null" => Token::Null,
+    "none" => Token::None,
+    "true" => Token::True,
+    "false" => Token::False,
+    "zeroinitializer" => Token::Zeroinitializer,
+    "undef" => Token::Undef,
+    "poison" => Token::Poison,
+    "to" => Token::To,
+    "nuw" => Token::Nuw,
+    "nsw" => Token::Nsw,
+    "exact" => Token::Exact,
+    "inbounds" => Token::Inbounds,
+    "inrange" => Token::Inrange,
+    "volatile" => Token::Volatile,
+    "atomic" => Token::Atomic,
+    "unordered" => Token::Unordered,
+    "monotonic" => Token::Monotonic,
+    "acquire" => Token::Acquire,
+    "release" => Token::Release,
+    "acq_rel" => Token::Acq_rel,
+    "seq_cst" => Token::Seq_cst,
+    "singlethread" => Token::Singlethread,
+    "target" => Token::Target,
+    "datalayout" => Token::Datalayout,
+    "triple" => Token::Triple,
+    "source_filename" => Token::Source_filename,
+    "attributes" => Token::Attributes,
+    "align" => Token::Align,
+    "addrspace" => Token::Addrspace,
+    "section" => Token::Section,
+    "comdat" => Token::Comdat,
+    "gc" => Token::Gc,
+    "prefix" => Token::Prefix,
+    "prologue" => Token::Prologue,
+    "personality" => Token::Personality,
+
+    // Instructions
+    "ret" => Token::Ret,
+    "br" => Token::Br,
+    "switch" => Token::Switch,
+    "indirectbr" => Token::IndirectBr,
+    "invoke" => Token::Invoke,
+    "resume" => Token::Resume,
+    "unreachable" => Token::Unreachable,
+    "cleanupret" => Token::CleanupRet,
+    "catchret" => Token::CatchRet,
+    "catchswitch" => Token::CatchSwitch,
+    "callbr" => Token::CallBr,
+    "fneg" => Token::FNeg,
+    "freeze" => Token::Freeze,
+    "add" => Token::Add,
+    "fadd" => Token::FAdd,
+    "sub" => Token::Sub,
+    "fsub" => Token::FSub,
+    "mul" => Token::Mul,
+    "fmul" => Token::FMul,
+    "udiv" => Token::UDiv,
+    "sdiv" => Token::SDiv,
+    "fdiv" => Token::FDiv,
+    "urem" => Token::URem,
+    "srem" => Token::SRem,
+    "frem" => Token::FRem,
+    "shl" => Token::Shl,
+    "lshr" => Token::LShr,
+    "ashr" => Token::AShr,
+    "and" => Token::And,
+    "or" => Token::Or,
+    "xor" => Token::Xor,
+    "extractelement" => Token::ExtractElement,
+    "insertelement" => Token::InsertElement,
+    "shufflevector" => Token::ShuffleVector,
+    "extractvalue" => Token::ExtractValue,
+    "insertvalue" => Token::InsertValue,
+    "alloca" => Token::Alloca,
+    "load" => Token::Load,
+    "store" => Token::Store,
+    "getelementptr" => Token::GetElementPtr,
+    "fence" => Token::Fence,
+    "cmpxchg" => Token::AtomicCmpXchg,
+    "atomicrmw" => Token::AtomicRMW,
+    "trunc" => Token::Trunc,
+    "zext" => Token::ZExt,
+    "sext" => Token::SExt,
+    "fptoui" => Token::FPToUI,
+    "fptosi" => Token::FPToSI,
+    "uitofp" => Token::UIToFP,
+    "sitofp" => Token::SIToFP,
+    "fptrunc" => Token::FPTrunc,
+    "fpext" => Token::FPExt,
+    "ptrtoint" => Token::PtrToInt,
+    "inttoptr" => Token::IntToPtr,
+    "ptrtoaddr" => Token::PtrToAddr,
+    "addrtoptr" => Token::AddrToPtr,
+    "bitcast" => Token::BitCast,
+    "addrspacecast" => Token::AddrSpaceCast,
+    "icmp" => Token::ICmp,
+    "fcmp" => Token::FCmp,
+    "phi" => Token::Phi,
+    "call" => Token::Call,
+    "select" => Token::Select,
+    "va_arg" => Token::VAArg,
+    "landingpad" => Token::LandingPad,
+    "cleanup" => Token::Cleanup,
+    "catch" => Token::Catch,
+    "filter" => Token::Filter,
+    "ptrauth" => Token::Ptrauth,
+
+    // Comparison predicates
+    "eq" => Token::Eq,
+    "ne" => Token::Ne,
+    "ugt" => Token::Ugt,
+    "uge" => Token::Uge,
+    "ult" => Token::Ult,
+    "ule" => Token::Ule,
+    "sgt" => Token::Sgt,
+    "sge" => Token::Sge,
+    "slt" => Token::Slt,
+    "sle" => Token::Sle,
+    "oeq" => Token::Oeq,
+    "ogt" => Token::Ogt,
+    "oge" => Token::Oge,
+    "olt" => Token::Olt,
+    "ole" => Token::Ole,
+    "one" => Token::One,
+    "ord" => Token::Ord,
+    "uno" => Token::Uno,
+    "une" => Token::Une,
+    "ueq" => Token::Ueq,
+
+    // Types
+    "void" => Token::Void,
+    "half" => Token::Half,
+    "bfloat" => Token::Bfloat,
+    "float" => Token::Float,
+    "double" => Token::Double,
+    "x86_fp80" => Token::X86_fp80,
+    "fp128" => Token::Fp128,
+    "ppc_fp128" => Token::Ppc_fp128,
+    "x86_mmx" => Token::X86_mmx,
+    "x86_amx" => Token::X86_amx,
+    "ptr" => Token::Ptr,
+    "label" => Token::Label,
+    "token" => Token::Token,
+    "metadata" => Token::Metadata,
+    "x" => Token::X,
+    "alias" => Token::Alias,
+    "ifunc" => Token::Ifunc,
+    "entry" => Token::Entry,
+    "distinct" => Token::Distinct,
+    "nounwind" => Token::Nounwind,
+    "inreg" => Token::Inreg,
+    "byval" => Token::Byval,
+    "inalloca" => Token::Inalloca,
+    "sret" => Token::Sret,
+    "preallocated" => Token::Preallocated,
+    "noalias" => Token::Noalias,
+    "nocapture" => Token::Nocapture,
+    "nest" => Token::Nest,
+    "returned" => Token::Returned,
+    "nonnull" => Token::Nonnull,
+    "dereferenceable" => Token::Dereferenceable,
+    "dereferenceable_or_null" => Token::Dereferenceable_or_null,
+    "swiftself" => Token::Swiftself,
+    "swifterror" => Token::Swifterror,
+    "swiftasync" => Token::Swiftasync,
+    "immarg" => Token::Immarg,
+    "zeroext" => Token::Zeroext,
+    "signext" => Token::Signext,
+    "inlinehint" => Token::Inlinehint,
+    "alwaysinline" => Token::Alwaysinline,
+    "noinline" => Token::Noinline,
+    "optsize" => Token::Optsize,
+    "optnone" => Token::Optnone,
+    "minsize" => Token::Minsize,
+    "noreturn" => Token::Noreturn,
+    "norecurse" => Token::Norecurse,
+    "willreturn" => Token::Willreturn,
+    "nosync" => Token::Nosync,
+    "sanitize_address" => Token::Sanitize_address,
+    "sanitize_thread" => Token::Sanitize_thread,
+    "sanitize_memory" => Token::Sanitize_memory,
+    "sanitize_hwaddress" => Token::Sanitize_hwaddress,
+    "safestack" => Token::Safestack,
+    "uwtable" => Token::Uwtable,
+    "nocf_check" => Token::Nocf_check,
+    "shadowcallstack" => Token::Shadowcallstack,
+    "mustprogress" => Token::Mustprogress,
+    "vscale_range" => Token::Vscale_range,
+    "strictfp" => Token::Strictfp,
+    "naked" => Token::Naked,
+    "builtin" => Token::Builtin,
+    "cold" => Token::Cold,
+    "hot" => Token::Hot,
+    "nobuiltin" => Token::Nobuiltin,
+    "noduplicate" => Token::Noduplicate,
+    "noimplicitfloat" => Token::Noimplicitfloat,
+    "nomerge" => Token::Nomerge,
+    "nonlazybind" => Token::Nonlazybind,
+    "noredzone" => Token::Noredzone,
+    "null_pointer_is_valid" => Token::Null_pointer_is_valid,
+    "optforfuzzing" => Token::Optforfuzzing,
+    "readnone" => Token::Readnone,
+    "readonly" => Token::Readonly,
+    "writeonly" => Token::Writeonly,
+    "argmemonly" => Token::Argmemonly,
+    "inaccessiblememonly" => Token::Inaccessiblememonly,
+    "inaccessiblemem_or_argmemonly" => Token::Inaccessiblemem_or_argmemonly,
+    "speculatable" => Token::Speculatable,
+    "returns_twice" => Token::Returns_twice,
+    "ssp" => Token::Ssp,
+    "sspreq" => Token::Sspreq,
+    "sspstrong" => Token::Sspstrong,
+    "thunk" => Token::Thunk,
+    "amdgpu_kernel" => Token::Amdgpu_kernel,
+    "amdgpu_cs_chain" => Token::Amdgpu_cs_chain,
+    "amdgpu_ps" => Token::Amdgpu_ps,
+    "syncscope" => Token::Syncscope,
+    "var" => Token::Var,
+    "dso_local" => Token::Dso_local,
+    "dso_preemptable" => Token::Dso_preemptable,
+    "filename" => Token::Filename,
+    "name" => Token::Name,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1135,4 +1264,67 @@ mod tests {
         assert!(matches!(tokens[2], Token::Float64(_)));
         assert!(matches!(tokens[3], Token::Float64(_)));
     }
+
+    #[test]
+    fn test_labels_starting_like_numbers() {
+        let mut lexer = Lexer::new("-N-: 3: 1.x:");
+        let tokens = lexer.tokenize().unwrap();
+
+        assert_eq!(tokens[0], Token::Identifier("-N-".into()));
+        assert_eq!(tokens[2], Token::Integer(3));
+        assert_eq!(tokens[4], Token::Identifier("1.x".into()));
+    }
+
+    #[test]
+    fn test_names_borrow_from_input() {
+        let source = "%x = load i32, ptr @\"quoted name\" !dbg !7";
+        let tokens: Vec<_> = Lexer::new(source).map(|t| t.unwrap().0).collect();
+
+        assert!(matches!(&tokens[0], Token::LocalIdent(Cow::Borrowed("x"))));
+        assert!(matches!(&tokens[6], Token::GlobalIdent(Cow::Borrowed("quoted name"))));
+        assert!(matches!(&tokens[7], Token::MetadataIdent(Cow::Borrowed("dbg"))));
+        assert!(matches!(&tokens[8], Token::MetadataIdent(Cow::Borrowed("7"))));
+    }
+
+    #[test]
+    fn test_escapes_are_resolved() {
+        let mut lexer = Lexer::new(r#""a\"b\n" @"x\"y" c"hi\0A\00""#);
+
+        assert_eq!(lexer.next_token().unwrap().into_owned(), Token::StringLit("a\"b\n".to_string()));
+        assert_eq!(lexer.next_token().unwrap().into_owned(), Token::GlobalIdent("x\"y".to_string()));
+        assert_eq!(lexer.next_token().unwrap().into_owned(), Token::CString(b"hi\n\0".to_vec()));
+        assert_eq!(lexer.next_token().unwrap().into_owned(), Token::EOF);
+    }
+
+    #[test]
+    fn test_spans_and_offsets() {
+        let source = "; comment\ndefine void @f() {\n  ret void\n}\n";
+        let spanned: Vec<_> = Lexer::new(source).map(|t| t.unwrap()).collect();
+
+        let (ret, span) = &spanned[6];
+        assert_eq!(*ret, Token::Ret);
+        assert_eq!(&source[span.start..span.end], "ret");
+        assert_eq!((span.line, span.column), (3, 3));
+
+        // Resuming at a recorded offset continues with the same tokens and lines
+        let mut resumed = Lexer::with_offset(source, span.start);
+        assert_eq!(resumed.next_token().unwrap(), Token::Ret);
+        assert_eq!(resumed.line(), 3);
+
+        let mut resumed = Lexer::resume(source, *span);
+        assert_eq!(resumed.next_spanned().unwrap(), (Token::Ret, *span));
+    }
+
+    #[test]
+    fn test_summary_ids_and_keyword_spelling() {
+        let tokens = Lexer::new("^12 = gv: (linkage: linkonce_odr)").tokenize().unwrap();
+        assert_eq!(tokens[0], Token::SummaryId(12));
+        assert_eq!(tokens[7].keyword_spelling(), Some("linkonce_odr"));
+        assert_eq!(tokens[2].keyword_spelling(), None);
+        // Spellings that are not the lowercased variant name
+        assert_eq!(Token::<String>::VAArg.keyword_spelling(), Some("va_arg"));
+        assert_eq!(Token::<String>::AtomicCmpXchg.keyword_spelling(), Some("cmpxchg"));
+        assert_eq!(Token::<String>::GetElementPtr.keyword_spelling(), Some("getelementptr"));
+        assert!(Lexer::new("^ 1").tokenize().is_err());
+    }
 }
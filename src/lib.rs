@@ -22,13 +22,28 @@ pub mod metadata;
 pub mod attributes;
 pub mod intrinsics;
 pub mod verification;
+pub mod diagnostics;
 pub mod validation_rules;
 pub mod printer;
 pub mod lexer;
 pub mod parser;
+pub mod summary;
+pub mod bitcode;
 pub mod cfg;
+pub mod dataflow;
 pub mod passes;
+pub mod analysis_manager;
 pub mod analysis;
+pub mod liveness;
+pub mod call_graph;
+pub mod lint;
+pub mod constant_range;
+pub mod value_tracking;
+pub mod lazy_value_info;
+pub mod scalar_evolution;
+pub mod branch_probability;
+pub mod block_frequency;
+pub mod memory_ssa;
 pub mod transforms;
 pub mod codegen;
 
@@ -45,4 +60,5 @@ pub use attributes::{FunctionAttribute, ParameterAttribute, CallingConvention};
 pub use intrinsics::Intrinsic;
 pub use verification::{verify_module, verify_function};
 pub use printer::{print_module, print_function};
-pub use parser::parse;
+pub use parser::{parse, parse_lazy, parse_type, parse_constant};
+pub use bitcode::{parse_bitcode, write_bitcode};
@@ -0,0 +1,297 @@
+//! Lint Analysis
+//!
+//! Like `opt -lint`, this pass reports IR that is valid but very likely to
+//! have undefined behavior when it runs: division by a constant zero,
+//! accesses through null or undef pointers, oversized shifts, misaligned
+//! constant addresses, calling convention mismatches, `noreturn` functions
+//! that return, and writes to constant globals. Findings are warnings; the
+//! module still verifies.
+
+use std::fmt;
+use crate::analysis::DominatorTree;
+use crate::basic_block::BasicBlock;
+use crate::function::Function;
+use crate::instruction::{Instruction, Opcode};
+use crate::module::Module;
+use crate::passes::{ModulePass, Pass, PassResult};
+use crate::value::Value;
+
+/// Kinds of suspicious IR the lint pass reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LintKind {
+    /// Integer division or remainder by a constant zero or undef
+    DivisionByZero,
+    /// Load, store or call through a null or undef pointer
+    InvalidPointer,
+    /// Shift by at least the bit width of the shifted value
+    OversizedShift,
+    /// Access whose alignment the constant address does not have
+    MisalignedAddress,
+    /// Direct call with a calling convention other than the callee's
+    CallingConventionMismatch,
+    /// `noreturn` function with a reachable return
+    NoReturnReturns,
+    /// Write to a constant global
+    WriteToConstant,
+}
+
+impl LintKind {
+    /// Name of the kind, as printed in findings
+    pub fn name(&self) -> &'static str {
+        match self {
+            LintKind::DivisionByZero => "division-by-zero",
+            LintKind::InvalidPointer => "invalid-pointer",
+            LintKind::OversizedShift => "oversized-shift",
+            LintKind::MisalignedAddress => "misaligned-address",
+            LintKind::CallingConventionMismatch => "calling-convention-mismatch",
+            LintKind::NoReturnReturns => "noreturn-returns",
+            LintKind::WriteToConstant => "write-to-constant",
+        }
+    }
+}
+
+impl fmt::Display for LintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A finding of the lint pass
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintWarning {
+    pub kind: LintKind,
+    pub message: String,
+    pub location: String,
+}
+
+impl fmt::Display for LintWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "warning[{}]: {} at {}", self.kind, self.message, self.location)
+    }
+}
+
+/// Lint all function bodies of a module
+pub fn lint_module(module: &Module) -> Vec<LintWarning> {
+    let mut linter = Linter::new(Some(module));
+    for function in module.functions() {
+        linter.lint_function(&function);
+    }
+    linter.warnings
+}
+
+/// Lint a function on its own, without the globals and callees of its
+/// module
+pub fn lint_function(function: &Function) -> Vec<LintWarning> {
+    let mut linter = Linter::new(None);
+    linter.lint_function(function);
+    linter.warnings
+}
+
+/// Walks function bodies and collects findings
+struct Linter<'a> {
+    module: Option<&'a Module>,
+    warnings: Vec<LintWarning>,
+}
+
+impl<'a> Linter<'a> {
+    fn new(module: Option<&'a Module>) -> Self {
+        Self { module, warnings: Vec::new() }
+    }
+
+    fn warn(&mut self, kind: LintKind, message: String, location: &str) {
+        self.warnings.push(LintWarning { kind, message, location: location.to_string() });
+    }
+
+    fn lint_function(&mut self, function: &Function) {
+        let fn_name = function.name();
+        // Returns only matter where control can get to them
+        let domtree = function.attributes().noreturn.then(|| DominatorTree::new(function));
+        for (index, bb) in function.basic_blocks().iter().enumerate() {
+            let noreturn = domtree.as_ref().is_some_and(|domtree| domtree.is_reachable(index));
+            self.lint_block(&fn_name, noreturn, bb);
+        }
+    }
+
+    /// Lint the instructions of a block; `noreturn` if the function is and
+    /// the block is reachable
+    fn lint_block(&mut self, fn_name: &str, noreturn: bool, bb: &BasicBlock) {
+        let block_name = bb.name().unwrap_or_default();
+        for (index, inst) in bb.instructions().iter().enumerate() {
+            let location = format!("function {} block %{} instruction {} ({:?})", fn_name, block_name, index, inst.opcode());
+            self.lint_instruction(inst, &location);
+            if noreturn && inst.opcode() == Opcode::Ret {
+                self.warn(LintKind::NoReturnReturns,
+                    format!("noreturn function @{} returns", fn_name), &location);
+            }
+        }
+    }
+
+    fn lint_instruction(&mut self, inst: &Instruction, location: &str) {
+        let operands = inst.operands();
+        match inst.opcode() {
+            Opcode::UDiv | Opcode::SDiv | Opcode::URem | Opcode::SRem if operands.len() >= 2 => {
+                if scalar_constants(&operands[1]).contains(&Some(0)) {
+                    self.warn(LintKind::DivisionByZero, "division by zero".to_string(), location);
+                } else if is_undef(&operands[1]) {
+                    self.warn(LintKind::DivisionByZero, "division by undef".to_string(), location);
+                }
+            }
+            Opcode::Shl | Opcode::LShr | Opcode::AShr if operands.len() >= 2 => {
+                let width = operands[0].get_type().vector_info()
+                    .map_or(operands[0].get_type(), |(elem, _)| elem)
+                    .int_width();
+                if let Some(width) = width {
+                    let oversized = scalar_constants(&operands[1]).into_iter().flatten()
+                        .find(|amount| *amount < 0 || *amount as u64 >= width as u64);
+                    if let Some(amount) = oversized {
+                        self.warn(LintKind::OversizedShift,
+                            format!("shift amount {} is not less than the bit width {}", amount, width), location);
+                    }
+                }
+            }
+            Opcode::Load => {
+                if let Some(pointer) = operands.first() {
+                    self.lint_access(inst, pointer, "load", location);
+                }
+            }
+            Opcode::Store | Opcode::AtomicRMW | Opcode::AtomicCmpXchg => {
+                let pointer_index = if inst.opcode() == Opcode::Store { 1 } else { 0 };
+                if let Some(pointer) = operands.get(pointer_index) {
+                    self.lint_access(inst, pointer, "store", location);
+                    self.lint_write(pointer, location);
+                }
+            }
+            Opcode::Call | Opcode::Invoke => self.lint_call(inst, location),
+            _ => {}
+        }
+    }
+
+    /// Check the pointer of a memory access: not null or undef, and
+    /// aligned as the access claims when its address is known
+    fn lint_access(&mut self, inst: &Instruction, pointer: &Value, access: &str, location: &str) {
+        // Null is a valid address outside address space 0
+        if pointer.is_null() && pointer.get_type().address_space().unwrap_or(0) == 0 {
+            self.warn(LintKind::InvalidPointer, format!("{} through a null pointer", access), location);
+            return;
+        }
+        if is_undef(pointer) {
+            self.warn(LintKind::InvalidPointer, format!("{} through an undef pointer", access), location);
+            return;
+        }
+
+        let Some(align) = inst.alignment().filter(|align| *align > 1) else {
+            return;
+        };
+        if let Some(address) = constant_address(pointer) {
+            if !(address as u64).is_multiple_of(align) {
+                self.warn(LintKind::MisalignedAddress,
+                    format!("{} with align {} from address {:#x}", access, align, address), location);
+            }
+        } else if let Some(global) = pointer.name().filter(|_| pointer.is_global()).and_then(|name| self.global(name)) {
+            if let Some(global_align) = global.alignment.filter(|global_align| (*global_align as u64) < align) {
+                self.warn(LintKind::MisalignedAddress,
+                    format!("{} with align {} from @{}, which is aligned to {}", access, align, global.name, global_align),
+                    location);
+            }
+        }
+    }
+
+    /// Check that the pointer of a write is not a constant global
+    fn lint_write(&mut self, pointer: &Value, location: &str) {
+        let Some(name) = pointer.name().filter(|_| pointer.is_global()) else {
+            return;
+        };
+        if self.global(name).is_some_and(|global| global.is_constant) {
+            self.warn(LintKind::WriteToConstant, format!("write to constant global @{}", name), location);
+        }
+    }
+
+    fn lint_call(&mut self, inst: &Instruction, location: &str) {
+        let Some(callee) = inst.operands().first() else {
+            return;
+        };
+        if callee.is_null() || is_undef(callee) {
+            self.warn(LintKind::InvalidPointer, "call through a null or undef pointer".to_string(), location);
+            return;
+        }
+        let Some(name) = callee.name().filter(|_| callee.is_global()) else {
+            return;
+        };
+
+        // Memory intrinsics write through their first argument
+        if ["llvm.memcpy.", "llvm.memmove.", "llvm.memset."].iter().any(|prefix| name.starts_with(prefix)) {
+            if let Some(dest) = inst.operands().get(1) {
+                self.lint_write(dest, location);
+            }
+        }
+
+        let Some(function) = self.module.and_then(|module| module.get_function(name)) else {
+            return;
+        };
+        if function.calling_convention() != inst.calling_convention() {
+            self.warn(LintKind::CallingConventionMismatch,
+                format!("call with calling convention {:?} to @{}, which uses {:?}",
+                    inst.calling_convention(), name, function.calling_convention()),
+                location);
+        }
+    }
+
+    fn global(&self, name: &str) -> Option<crate::module::GlobalVariable> {
+        self.module.and_then(|module| module.get_global(name))
+    }
+}
+
+/// Whether a value is undef or poison
+fn is_undef(value: &Value) -> bool {
+    value.is_undef() || value.is_poison()
+}
+
+/// The integer constants of a scalar or vector constant, one per element;
+/// `None` for elements that are not integer constants
+fn scalar_constants(value: &Value) -> Vec<Option<i64>> {
+    if value.is_zero_initializer() {
+        return vec![Some(0)];
+    }
+    match value.vector_elements() {
+        Some(elements) => elements.iter().map(|e| e.as_const_int()).collect(),
+        None => vec![value.as_const_int()],
+    }
+}
+
+/// The address of a pointer built from a constant integer
+fn constant_address(pointer: &Value) -> Option<i64> {
+    let (opcode, operands) = pointer.as_const_expr()?;
+    match opcode {
+        Opcode::IntToPtr => operands.first()?.as_const_int(),
+        _ => None,
+    }
+}
+
+/// Module pass that collects lint findings without changing the module
+#[derive(Default)]
+pub struct LintPass {
+    pub warnings: Vec<LintWarning>,
+}
+
+impl LintPass {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Pass for LintPass {
+    fn name(&self) -> &str {
+        "lint"
+    }
+
+    fn preserves_all(&self) -> bool {
+        true
+    }
+}
+
+impl ModulePass for LintPass {
+    fn run_on_module(&mut self, module: &mut Module) -> PassResult<bool> {
+        self.warnings = lint_module(module);
+        Ok(false)
+    }
+}
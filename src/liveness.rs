@@ -0,0 +1,232 @@
+//! Liveness Analysis
+//!
+//! Which SSA values are live at the start and end of each block, solved
+//! backward with the dataflow framework. A phi reads its incoming value
+//! at the end of the incoming block, so phi operands are live out of that
+//! predecessor only and not live into the phi's block.
+//!
+//! Numbering the instructions in layout order turns the block sets into
+//! one interval per value, the live ranges the linear scan register
+//! allocator takes.
+
+use std::collections::{BTreeSet, HashMap};
+use crate::analysis::ValueKey;
+use crate::cfg::CFG;
+use crate::codegen::register_allocator::LiveRange;
+use crate::dataflow::{self, DataflowAnalysis, Direction};
+use crate::function::Function;
+use crate::instruction::Opcode;
+use crate::passes::{AnalysisPass, Pass, PassResult};
+use crate::value::Value;
+
+/// Live values of a function per block
+pub struct Liveness {
+    function: Function,
+    cfg: CFG,
+    /// Arguments, then instruction results in layout order; the position
+    /// of a value is its index everywhere else
+    values: Vec<Value>,
+    indices: HashMap<ValueKey, usize>,
+    live_in: Vec<BTreeSet<usize>>,
+    live_out: Vec<BTreeSet<usize>>,
+}
+
+/// Uses and defs of each block, for the solver
+struct LivenessProblem {
+    /// Values read by the block's non-phi instructions and defined elsewhere
+    uses: Vec<BTreeSet<usize>>,
+    /// Values defined in the block, phis included
+    defs: Vec<BTreeSet<usize>>,
+    /// Values the phis of a block read along the edge from a predecessor,
+    /// keyed by (predecessor, block)
+    phi_uses: HashMap<(usize, usize), BTreeSet<usize>>,
+}
+
+impl DataflowAnalysis for LivenessProblem {
+    type Domain = BTreeSet<usize>;
+
+    fn direction(&self) -> Direction {
+        Direction::Backward
+    }
+
+    fn boundary(&self) -> Self::Domain {
+        BTreeSet::new()
+    }
+
+    fn bottom(&self) -> Self::Domain {
+        BTreeSet::new()
+    }
+
+    fn transfer(&self, block: usize, live_out: &Self::Domain) -> Self::Domain {
+        let mut live_in = self.uses[block].clone();
+        live_in.extend(live_out.difference(&self.defs[block]).copied());
+        live_in
+    }
+
+    fn transfer_edge(&self, from: usize, to: usize, live_in: &Self::Domain) -> Self::Domain {
+        match self.phi_uses.get(&(from, to)) {
+            Some(phi_uses) => live_in.union(phi_uses).copied().collect(),
+            None => live_in.clone(),
+        }
+    }
+}
+
+impl Liveness {
+    /// Compute liveness for a function
+    pub fn new(function: &Function) -> Self {
+        let cfg = CFG::from_function(function);
+        let blocks = function.basic_blocks();
+
+        let mut values = function.arguments();
+        for bb in &blocks {
+            values.extend(bb.instructions().iter().filter_map(|inst| inst.result().cloned()));
+        }
+        let indices: HashMap<ValueKey, usize> = values.iter()
+            .enumerate()
+            .map(|(index, value)| (ValueKey::of(value), index))
+            .collect();
+
+        let mut problem = LivenessProblem {
+            uses: vec![BTreeSet::new(); blocks.len()],
+            defs: vec![BTreeSet::new(); blocks.len()],
+            phi_uses: HashMap::new(),
+        };
+        for (block, bb) in blocks.iter().enumerate() {
+            for inst in bb.instructions() {
+                let operands = inst.operands();
+                if inst.opcode() == Opcode::PHI {
+                    for pair in operands.chunks(2) {
+                        let (Some(&value), Some(pred)) = (pair.first().and_then(|v| indices.get(&ValueKey::of(v))),
+                            pair.get(1).and_then(|label| label.name()).and_then(|name| cfg.block_index(name))) else {
+                            continue;
+                        };
+                        problem.phi_uses.entry((pred, block)).or_default().insert(value);
+                    }
+                } else {
+                    for operand in operands {
+                        if let Some(&value) = indices.get(&ValueKey::of(operand)) {
+                            if !problem.defs[block].contains(&value) {
+                                problem.uses[block].insert(value);
+                            }
+                        }
+                    }
+                }
+                if let Some(result) = inst.result() {
+                    problem.defs[block].insert(indices[&ValueKey::of(result)]);
+                }
+            }
+        }
+
+        let solution = dataflow::solve(&problem, &cfg);
+        Self {
+            function: function.clone(),
+            cfg,
+            values,
+            indices,
+            live_in: solution.block_in,
+            live_out: solution.block_out,
+        }
+    }
+
+    pub fn function(&self) -> &Function {
+        &self.function
+    }
+
+    pub fn cfg(&self) -> &CFG {
+        &self.cfg
+    }
+
+    /// Arguments, then instruction results in layout order
+    pub fn values(&self) -> &[Value] {
+        &self.values
+    }
+
+    /// Position of an argument or instruction result among `values`
+    pub fn value_index(&self, value: &Value) -> Option<usize> {
+        self.indices.get(&ValueKey::of(value)).copied()
+    }
+
+    /// Values live at the start of a block, in the order of `values`
+    pub fn live_in(&self, block: usize) -> Vec<Value> {
+        self.live_in[block].iter().map(|&index| self.values[index].clone()).collect()
+    }
+
+    /// Values live at the end of a block, in the order of `values`
+    pub fn live_out(&self, block: usize) -> Vec<Value> {
+        self.live_out[block].iter().map(|&index| self.values[index].clone()).collect()
+    }
+
+    pub fn is_live_in(&self, block: usize, value: &Value) -> bool {
+        self.value_index(value).is_some_and(|index| self.live_in[block].contains(&index))
+    }
+
+    pub fn is_live_out(&self, block: usize, value: &Value) -> bool {
+        self.value_index(value).is_some_and(|index| self.live_out[block].contains(&index))
+    }
+
+    /// One live range per value, with the value's index as its virtual
+    /// register. Instructions are numbered in layout order and arguments
+    /// are defined at 0. A range spans the definition, every use, and
+    /// every block the value is live into or out of, so it can cover
+    /// holes where the value is dead.
+    pub fn live_ranges(&self) -> Vec<LiveRange> {
+        let mut ranges: Vec<LiveRange> = (0..self.values.len())
+            .map(|vreg| LiveRange { vreg, start: usize::MAX, end: 0 })
+            .collect();
+        let mut extend = |index: usize, position: usize| {
+            let range = &mut ranges[index];
+            range.start = range.start.min(position);
+            range.end = range.end.max(position);
+        };
+        for index in 0..self.function.arguments().len() {
+            extend(index, 0);
+        }
+
+        let mut position = 0;
+        for (block, bb) in self.function.basic_blocks().iter().enumerate() {
+            let instructions = bb.instructions();
+            let first = position;
+            let last = (position + instructions.len()).saturating_sub(1).max(first);
+            for &index in &self.live_in[block] {
+                extend(index, first);
+            }
+            for &index in &self.live_out[block] {
+                extend(index, last);
+            }
+            for inst in &instructions {
+                // Phi operands are read at the end of the predecessor,
+                // which its live-out set covers
+                if inst.opcode() != Opcode::PHI {
+                    for operand in inst.operands() {
+                        if let Some(index) = self.value_index(operand) {
+                            extend(index, position);
+                        }
+                    }
+                }
+                if let Some(index) = inst.result().and_then(|result| self.value_index(result)) {
+                    extend(index, position);
+                }
+                position += 1;
+            }
+            position = position.max(first + 1);
+        }
+        ranges
+    }
+}
+
+/// Liveness analysis pass
+pub struct LivenessPass;
+
+impl Pass for LivenessPass {
+    fn name(&self) -> &str {
+        "liveness"
+    }
+}
+
+impl AnalysisPass for LivenessPass {
+    type Result = Liveness;
+
+    fn run_analysis(&mut self, function: &Function) -> PassResult<Self::Result> {
+        Ok(Liveness::new(function))
+    }
+}
@@ -0,0 +1,490 @@
+//! Memory SSA
+//!
+//! Puts the memory effects of a function in SSA form, like LLVM's
+//! MemorySSA. Every instruction that may write memory (stores, fences,
+//! atomics, volatile or ordered loads and calls) is a `MemoryDef` of a
+//! new memory state, every plain read is a `MemoryUse` of the state it
+//! sees, and a `MemoryPhi` merges the states reaching a block where
+//! several do. The state before any access is `liveOnEntry`.
+//!
+//! The defining access of a use is only the last def above it; the
+//! walker asks alias analysis which defs actually write the memory an
+//! access reads, skipping over the others.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use crate::analysis::{AliasAnalysis, AliasResult, DominanceFrontier, DominatorTree, MemoryLocation};
+use crate::function::Function;
+use crate::instruction::{AtomicOrdering, Instruction, Opcode};
+use crate::module::Module;
+use crate::passes::{AnalysisPass, Pass, PassResult};
+use crate::printer::IRPrinter;
+
+/// Id of the memory state on entry to the function
+pub const LIVE_ON_ENTRY: usize = 0;
+
+/// A node of memory SSA
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MemoryAccess {
+    /// The memory state on entry to the function
+    LiveOnEntry,
+    /// An instruction that may change memory, and the state it changes
+    Def { block: usize, defining: usize },
+    /// An instruction that reads memory, and the state it reads
+    Use { block: usize, defining: usize },
+    /// The states reaching a block, as (predecessor, access) pairs in the
+    /// order of the predecessors
+    Phi { block: usize, incoming: Vec<(usize, usize)> },
+}
+
+impl MemoryAccess {
+    /// The block of the access; `None` for `liveOnEntry`
+    pub fn block(&self) -> Option<usize> {
+        match self {
+            MemoryAccess::LiveOnEntry => None,
+            MemoryAccess::Def { block, .. } | MemoryAccess::Use { block, .. } | MemoryAccess::Phi { block, .. } => {
+                Some(*block)
+            }
+        }
+    }
+
+    /// The state a def or use follows
+    pub fn defining(&self) -> Option<usize> {
+        match self {
+            MemoryAccess::Def { defining, .. } | MemoryAccess::Use { defining, .. } => Some(*defining),
+            _ => None,
+        }
+    }
+}
+
+/// How an instruction touches memory
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Effect {
+    None,
+    Read,
+    Write,
+}
+
+/// Memory SSA of a function
+///
+/// Accesses are numbered in the order they are created, starting with
+/// `liveOnEntry` as 0; the ids of removed accesses are not reused. The
+/// defs and uses of a block are kept in the order of their instructions,
+/// which is how they are matched to the IR when printing.
+pub struct MemorySSA {
+    function: Function,
+    domtree: DominatorTree,
+    frontier: DominanceFrontier,
+    alias_analysis: AliasAnalysis,
+    /// Memory effects of the callees defined or declared in the module
+    callee_effects: HashMap<String, Effect>,
+    /// Accesses by id; `None` once removed
+    accesses: Vec<Option<MemoryAccess>>,
+    /// Instruction of each def and use, by id
+    instructions: Vec<Option<Instruction>>,
+    /// Defs and uses of each block in instruction order
+    block_accesses: Vec<Vec<usize>>,
+    /// Phi of each block that has one
+    phis: HashMap<usize, usize>,
+}
+
+impl MemorySSA {
+    /// Defs the walker looks at per query before it gives up and returns
+    /// the access it reached
+    const WALK_LIMIT: usize = 100;
+
+    /// Build memory SSA for a function on its own; every call may write
+    /// memory
+    pub fn new(function: &Function) -> Self {
+        Self::build(function, HashMap::new())
+    }
+
+    /// Build memory SSA for a function of a module, where calls to
+    /// `readnone` and `readonly` functions do not write memory
+    pub fn in_module(function: &Function, module: &Module) -> Self {
+        let callee_effects = module.functions().iter()
+            .map(|callee| {
+                let attributes = callee.attributes();
+                let effect = if attributes.readnone {
+                    Effect::None
+                } else if attributes.readonly {
+                    Effect::Read
+                } else {
+                    Effect::Write
+                };
+                (callee.name(), effect)
+            })
+            .collect();
+        Self::build(function, callee_effects)
+    }
+
+    fn build(function: &Function, callee_effects: HashMap<String, Effect>) -> Self {
+        let domtree = DominatorTree::new(function);
+        let frontier = DominanceFrontier::from_dominator_tree(&domtree);
+        let mut memory_ssa = Self {
+            function: function.clone(),
+            block_accesses: vec![Vec::new(); domtree.cfg().num_blocks()],
+            domtree,
+            frontier,
+            alias_analysis: AliasAnalysis::new(function),
+            callee_effects,
+            accesses: vec![Some(MemoryAccess::LiveOnEntry)],
+            instructions: vec![None],
+            phis: HashMap::new(),
+        };
+
+        for (block, bb) in function.basic_blocks().iter().enumerate() {
+            for inst in bb.instructions() {
+                memory_ssa.create_access(inst, block);
+            }
+        }
+        memory_ssa.link();
+        memory_ssa
+    }
+
+    /// Add an unlinked def or use for an instruction that touches memory
+    /// to the end of its block
+    fn create_access(&mut self, inst: Instruction, block: usize) -> Option<usize> {
+        let access = match self.effect(&inst) {
+            Effect::None => return None,
+            Effect::Read => MemoryAccess::Use { block, defining: LIVE_ON_ENTRY },
+            Effect::Write => MemoryAccess::Def { block, defining: LIVE_ON_ENTRY },
+        };
+        let id = self.accesses.len();
+        self.accesses.push(Some(access));
+        self.instructions.push(Some(inst));
+        self.block_accesses[block].push(id);
+        Some(id)
+    }
+
+    fn effect(&self, inst: &Instruction) -> Effect {
+        match inst.opcode() {
+            Opcode::Load => {
+                let ordered = !matches!(inst.atomic_ordering(),
+                    None | Some(AtomicOrdering::NotAtomic) | Some(AtomicOrdering::Unordered));
+                if inst.is_volatile() || ordered { Effect::Write } else { Effect::Read }
+            }
+            Opcode::Store | Opcode::Fence | Opcode::AtomicRMW | Opcode::AtomicCmpXchg | Opcode::VAArg => Effect::Write,
+            Opcode::Call | Opcode::Invoke | Opcode::CallBr => {
+                let callee = inst.operands().first().and_then(|callee| callee.name().filter(|_| callee.is_global()));
+                match callee {
+                    Some(name) if name.starts_with("llvm.dbg.") => Effect::None,
+                    Some(name) => self.callee_effects.get(name).copied().unwrap_or(Effect::Write),
+                    None => Effect::Write,
+                }
+            }
+            _ => Effect::None,
+        }
+    }
+
+    /// Place phis at the iterated dominance frontier of the blocks with
+    /// defs and point every access at the state reaching it, walking the
+    /// dominator tree. Existing phis keep their ids.
+    fn link(&mut self) {
+        let cfg = self.domtree.cfg();
+        let def_blocks: HashSet<usize> = (0..self.block_accesses.len())
+            .filter(|&block| self.block_accesses[block].iter()
+                .any(|&id| matches!(self.accesses[id], Some(MemoryAccess::Def { .. }))))
+            .collect();
+        let phi_blocks: HashSet<usize> = self.frontier.iterated_frontier(&def_blocks).into_iter()
+            .filter(|&block| self.domtree.is_reachable(block))
+            .collect();
+
+        self.phis.retain(|block, id| {
+            let keep = phi_blocks.contains(block);
+            if !keep {
+                self.accesses[*id] = None;
+            }
+            keep
+        });
+        for &block in &phi_blocks {
+            if !self.phis.contains_key(&block) {
+                self.phis.insert(block, self.accesses.len());
+                self.accesses.push(Some(MemoryAccess::Phi { block, incoming: Vec::new() }));
+                self.instructions.push(None);
+            }
+        }
+
+        // The state leaving each block, for the phis of its successors
+        let mut outgoing: HashMap<usize, usize> = HashMap::new();
+        let mut worklist = Vec::new();
+        if !self.block_accesses.is_empty() {
+            worklist.push((0, LIVE_ON_ENTRY));
+        }
+        while let Some((block, incoming)) = worklist.pop() {
+            let mut current = self.phis.get(&block).copied().unwrap_or(incoming);
+            for &id in &self.block_accesses[block] {
+                match &mut self.accesses[id] {
+                    Some(MemoryAccess::Def { defining, .. }) => {
+                        *defining = current;
+                        current = id;
+                    }
+                    Some(MemoryAccess::Use { defining, .. }) => *defining = current,
+                    _ => {}
+                }
+            }
+            outgoing.insert(block, current);
+            worklist.extend(self.domtree.dominated_by(block).iter().map(|&child| (child, current)));
+        }
+
+        // Accesses in unreachable blocks see the entry state
+        for block in (0..self.block_accesses.len()).filter(|&block| !self.domtree.is_reachable(block)) {
+            for &id in &self.block_accesses[block] {
+                if let Some(MemoryAccess::Def { defining, .. } | MemoryAccess::Use { defining, .. }) = &mut self.accesses[id] {
+                    *defining = LIVE_ON_ENTRY;
+                }
+            }
+        }
+
+        for (&block, &id) in &self.phis {
+            let incoming = cfg.predecessors(block).iter()
+                .map(|&pred| (pred, outgoing.get(&pred).copied().unwrap_or(LIVE_ON_ENTRY)))
+                .collect();
+            self.accesses[id] = Some(MemoryAccess::Phi { block, incoming });
+        }
+    }
+
+    /// The function the accesses belong to
+    pub fn function(&self) -> &Function {
+        &self.function
+    }
+
+    /// Get an access by id; `None` once removed
+    pub fn access(&self, id: usize) -> Option<&MemoryAccess> {
+        self.accesses.get(id).and_then(Option::as_ref)
+    }
+
+    /// The instruction of a def or use
+    pub fn instruction(&self, id: usize) -> Option<&Instruction> {
+        self.instructions.get(id).and_then(Option::as_ref)
+    }
+
+    /// Number of ids handed out, including those of removed accesses
+    pub fn num_ids(&self) -> usize {
+        self.accesses.len()
+    }
+
+    /// Defs and uses of a block in instruction order
+    pub fn block_accesses(&self, block: usize) -> &[usize] {
+        &self.block_accesses[block]
+    }
+
+    /// The phi of a block, if it has one
+    pub fn phi(&self, block: usize) -> Option<usize> {
+        self.phis.get(&block).copied()
+    }
+
+    /// The access of the instruction at `index` in `block`, if it touches
+    /// memory
+    pub fn access_for_instruction(&self, block: usize, index: usize) -> Option<usize> {
+        let instructions = self.function.basic_blocks().get(block)?.instructions();
+        let inst = instructions.get(index)?;
+        if self.effect(inst) == Effect::None {
+            return None;
+        }
+        let position = instructions[..index].iter().filter(|inst| self.effect(inst) != Effect::None).count();
+        self.block_accesses[block].get(position).copied()
+    }
+
+    /// Accesses that follow the state of `id`, including phis
+    pub fn users(&self, id: usize) -> Vec<usize> {
+        (0..self.accesses.len())
+            .filter(|&user| match &self.accesses[user] {
+                Some(MemoryAccess::Def { defining, .. } | MemoryAccess::Use { defining, .. }) => *defining == id,
+                Some(MemoryAccess::Phi { incoming, .. }) => incoming.iter().any(|(_, access)| *access == id),
+                _ => false,
+            })
+            .collect()
+    }
+
+    /// The nearest def or phi above a def or use that may write the
+    /// memory its instruction accesses, or `liveOnEntry`. Defs that alias
+    /// analysis proves disjoint are skipped; a phi is looked through when
+    /// all its incoming paths lead to the same clobber. Phis and
+    /// `liveOnEntry` are their own clobbers.
+    pub fn clobbering_access(&self, id: usize) -> usize {
+        let (Some(defining), Some(inst)) = (self.access(id).and_then(MemoryAccess::defining), self.instruction(id)) else {
+            return id;
+        };
+        let location = MemoryLocation::of_instruction(inst);
+        self.clobbering_access_for_location(defining, location.as_ref())
+    }
+
+    /// The nearest access at or above `start` that may write `location`;
+    /// `None` stands for a location that may be anything
+    pub fn clobbering_access_for_location(&self, start: usize, location: Option<&MemoryLocation>) -> usize {
+        let mut budget = Self::WALK_LIMIT;
+        self.walk(start, location, &mut Vec::new(), &mut budget).unwrap_or(start)
+    }
+
+    /// Walk up from `start`; `None` when every path leads back to a phi
+    /// being looked through
+    fn walk(&self, start: usize, location: Option<&MemoryLocation>, phis: &mut Vec<usize>, budget: &mut usize) -> Option<usize> {
+        let mut current = start;
+        loop {
+            if *budget == 0 {
+                return Some(current);
+            }
+            *budget -= 1;
+            match self.access(current) {
+                Some(MemoryAccess::Def { defining, .. }) => {
+                    if self.may_clobber(current, location) {
+                        return Some(current);
+                    }
+                    current = *defining;
+                }
+                Some(MemoryAccess::Phi { incoming, .. }) => {
+                    if phis.contains(&current) {
+                        return None;
+                    }
+                    phis.push(current);
+                    let mut clobbers = incoming.iter().filter_map(|(_, access)| self.walk(*access, location, phis, budget));
+                    let first = clobbers.next();
+                    let agree = clobbers.all(|clobber| Some(clobber) == first);
+                    phis.pop();
+                    return if agree { first } else { Some(current) };
+                }
+                _ => return Some(current),
+            }
+        }
+    }
+
+    /// Whether a def may write a location
+    fn may_clobber(&self, def: usize, location: Option<&MemoryLocation>) -> bool {
+        let (Some(inst), Some(location)) = (self.instruction(def), location) else {
+            return true;
+        };
+        if inst.opcode() != Opcode::Store {
+            return true;
+        }
+        MemoryLocation::of_instruction(inst)
+            .is_none_or(|written| self.alias_analysis.alias(&written, location) != AliasResult::NoAlias)
+    }
+
+    /// Describe an access as in the annotations of the printed IR
+    pub fn describe(&self, id: usize) -> String {
+        let name = |id: usize| if id == LIVE_ON_ENTRY { "liveOnEntry".to_string() } else { id.to_string() };
+        match self.access(id) {
+            Some(MemoryAccess::LiveOnEntry) => "liveOnEntry".to_string(),
+            Some(MemoryAccess::Def { defining, .. }) => format!("{} = MemoryDef({})", id, name(*defining)),
+            Some(MemoryAccess::Use { defining, .. }) => format!("MemoryUse({})", name(*defining)),
+            Some(MemoryAccess::Phi { incoming, .. }) => {
+                let incoming: Vec<String> = incoming.iter()
+                    .map(|(pred, access)| format!("{{{},{}}}", self.domtree.cfg().block_name(*pred), name(*access)))
+                    .collect();
+                format!("{} = MemoryPhi({})", id, incoming.join(","))
+            }
+            None => "removed".to_string(),
+        }
+    }
+}
+
+/// Prints the function with each access as a comment above its
+/// instruction and the phis at the top of their blocks, like
+/// `opt -print-memoryssa`
+impl fmt::Display for MemorySSA {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "define @{} {{", self.function.name())?;
+        for (block, bb) in self.function.basic_blocks().iter().enumerate() {
+            writeln!(f, "{}:", self.domtree.cfg().block_name(block))?;
+            if let Some(phi) = self.phi(block) {
+                writeln!(f, "; {}", self.describe(phi))?;
+            }
+            let mut accesses = self.block_accesses[block].iter();
+            for inst in bb.instructions() {
+                if self.effect(&inst) != Effect::None {
+                    if let Some(&id) = accesses.next() {
+                        writeln!(f, "; {}", self.describe(id))?;
+                    }
+                }
+                write!(f, "  {}", IRPrinter::new().print_instruction(&inst))?;
+            }
+        }
+        writeln!(f, "}}")
+    }
+}
+
+/// Keeps memory SSA up to date as instructions that touch memory are
+/// added, removed or moved within an unchanged CFG
+///
+/// Each update first changes the IR, then tells the updater, which
+/// re-links the accesses in one walk of the dominator tree. Ids of
+/// existing accesses stay the same.
+pub struct MemorySSAUpdater<'a> {
+    memory_ssa: &'a mut MemorySSA,
+}
+
+impl<'a> MemorySSAUpdater<'a> {
+    pub fn new(memory_ssa: &'a mut MemorySSA) -> Self {
+        Self { memory_ssa }
+    }
+
+    /// Add an access for an instruction inserted into `block` after the
+    /// instruction of the access `after`, or before the block's other
+    /// accesses for `None`. Returns `None` if the instruction does not
+    /// touch memory.
+    pub fn insert_access(&mut self, inst: Instruction, block: usize, after: Option<usize>) -> Option<usize> {
+        let id = self.memory_ssa.create_access(inst, block)?;
+        self.memory_ssa.block_accesses[block].pop();
+        self.place(id, block, after);
+        self.memory_ssa.link();
+        Some(id)
+    }
+
+    /// Remove the access of an instruction removed from the IR; its users
+    /// follow the state it followed
+    pub fn remove_access(&mut self, id: usize) {
+        let Some(block) = self.memory_ssa.access(id).and_then(MemoryAccess::block) else {
+            return;
+        };
+        if matches!(self.memory_ssa.accesses[id], Some(MemoryAccess::Phi { .. })) {
+            return;
+        }
+        self.memory_ssa.block_accesses[block].retain(|&access| access != id);
+        self.memory_ssa.accesses[id] = None;
+        self.memory_ssa.instructions[id] = None;
+        self.memory_ssa.link();
+    }
+
+    /// Move the access of an instruction moved in the IR, as for
+    /// `insert_access`
+    pub fn move_access(&mut self, id: usize, block: usize, after: Option<usize>) {
+        let Some(from) = self.memory_ssa.access(id).and_then(MemoryAccess::block) else {
+            return;
+        };
+        self.memory_ssa.block_accesses[from].retain(|&access| access != id);
+        match &mut self.memory_ssa.accesses[id] {
+            Some(MemoryAccess::Def { block: access_block, .. } | MemoryAccess::Use { block: access_block, .. }) => {
+                *access_block = block;
+            }
+            _ => return,
+        }
+        self.place(id, block, after);
+        self.memory_ssa.link();
+    }
+
+    fn place(&mut self, id: usize, block: usize, after: Option<usize>) {
+        let accesses = &mut self.memory_ssa.block_accesses[block];
+        let position = after
+            .and_then(|after| accesses.iter().position(|&access| access == after))
+            .map_or(0, |position| position + 1);
+        accesses.insert(position, id);
+    }
+}
+
+/// Memory SSA analysis pass
+pub struct MemorySSAPass;
+
+impl Pass for MemorySSAPass {
+    fn name(&self) -> &str {
+        "memoryssa"
+    }
+}
+
+impl AnalysisPass for MemorySSAPass {
+    type Result = MemorySSA;
+
+    fn run_analysis(&mut self, function: &Function) -> PassResult<Self::Result> {
+        Ok(MemorySSA::new(function))
+    }
+}
@@ -157,6 +157,359 @@ pub enum DwarfOp {
     Generic(String),
 }
 
+/// Name, encoding and argument count of a DWARF expression operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DwarfOpInfo {
+    pub name: &'static str,
+    pub code: u64,
+    pub args: usize,
+}
+
+/// Operations accepted in a DIExpression
+const DWARF_OPERATIONS: &[(&str, u64, usize)] = &[
+    ("DW_OP_deref", 0x06, 0),
+    ("DW_OP_constu", 0x10, 1),
+    ("DW_OP_consts", 0x11, 1),
+    ("DW_OP_dup", 0x12, 0),
+    ("DW_OP_over", 0x14, 0),
+    ("DW_OP_swap", 0x16, 0),
+    ("DW_OP_xderef", 0x18, 0),
+    ("DW_OP_and", 0x1a, 0),
+    ("DW_OP_div", 0x1b, 0),
+    ("DW_OP_minus", 0x1c, 0),
+    ("DW_OP_mod", 0x1d, 0),
+    ("DW_OP_mul", 0x1e, 0),
+    ("DW_OP_not", 0x20, 0),
+    ("DW_OP_or", 0x21, 0),
+    ("DW_OP_plus", 0x22, 0),
+    ("DW_OP_plus_uconst", 0x23, 1),
+    ("DW_OP_shl", 0x24, 0),
+    ("DW_OP_shr", 0x25, 0),
+    ("DW_OP_shra", 0x26, 0),
+    ("DW_OP_xor", 0x27, 0),
+    ("DW_OP_eq", 0x29, 0),
+    ("DW_OP_ge", 0x2a, 0),
+    ("DW_OP_gt", 0x2b, 0),
+    ("DW_OP_le", 0x2c, 0),
+    ("DW_OP_lt", 0x2d, 0),
+    ("DW_OP_ne", 0x2e, 0),
+    ("DW_OP_lit0", 0x30, 0),
+    ("DW_OP_regx", 0x90, 1),
+    ("DW_OP_bregx", 0x92, 2),
+    ("DW_OP_deref_size", 0x94, 1),
+    ("DW_OP_xderef_size", 0x95, 1),
+    ("DW_OP_push_object_address", 0x97, 0),
+    ("DW_OP_stack_value", 0x9f, 0),
+    ("DW_OP_LLVM_fragment", 0x1000, 2),
+    ("DW_OP_LLVM_convert", 0x1001, 2),
+    ("DW_OP_LLVM_tag_offset", 0x1002, 1),
+    ("DW_OP_LLVM_entry_value", 0x1003, 1),
+    ("DW_OP_LLVM_implicit_pointer", 0x1004, 0),
+    ("DW_OP_LLVM_arg", 0x1005, 1),
+    ("DW_OP_LLVM_extract_bits_sext", 0x1006, 2),
+    ("DW_OP_LLVM_extract_bits_zext", 0x1007, 2),
+];
+
+impl DwarfOpInfo {
+    /// Look up an operation by its textual name (`DW_OP_deref`)
+    pub fn from_name(name: &str) -> Option<Self> {
+        DWARF_OPERATIONS.iter().find(|op| op.0 == name).map(Self::from_entry)
+    }
+
+    /// Look up an operation by its encoding, as stored in bitcode
+    pub fn from_code(code: u64) -> Option<Self> {
+        DWARF_OPERATIONS.iter().find(|op| op.1 == code).map(Self::from_entry)
+    }
+
+    fn from_entry(&(name, code, args): &(&'static str, u64, usize)) -> Self {
+        Self { name, code, args }
+    }
+}
+
+/// Encodings of the constants that specialized debug info fields are
+/// written with, from `Dwarf.def` and `DebugInfoFlags.def`
+const DEBUG_INFO_CONSTANTS: &[(&str, u64)] = &[
+    ("DW_TAG_null", 0x0),
+    ("DW_TAG_array_type", 0x1),
+    ("DW_TAG_class_type", 0x2),
+    ("DW_TAG_entry_point", 0x3),
+    ("DW_TAG_enumeration_type", 0x4),
+    ("DW_TAG_formal_parameter", 0x5),
+    ("DW_TAG_imported_declaration", 0x8),
+    ("DW_TAG_label", 0xa),
+    ("DW_TAG_lexical_block", 0xb),
+    ("DW_TAG_member", 0xd),
+    ("DW_TAG_pointer_type", 0xf),
+    ("DW_TAG_reference_type", 0x10),
+    ("DW_TAG_compile_unit", 0x11),
+    ("DW_TAG_string_type", 0x12),
+    ("DW_TAG_structure_type", 0x13),
+    ("DW_TAG_subroutine_type", 0x15),
+    ("DW_TAG_typedef", 0x16),
+    ("DW_TAG_union_type", 0x17),
+    ("DW_TAG_unspecified_parameters", 0x18),
+    ("DW_TAG_variant", 0x19),
+    ("DW_TAG_common_block", 0x1a),
+    ("DW_TAG_common_inclusion", 0x1b),
+    ("DW_TAG_inheritance", 0x1c),
+    ("DW_TAG_inlined_subroutine", 0x1d),
+    ("DW_TAG_module", 0x1e),
+    ("DW_TAG_ptr_to_member_type", 0x1f),
+    ("DW_TAG_set_type", 0x20),
+    ("DW_TAG_subrange_type", 0x21),
+    ("DW_TAG_with_stmt", 0x22),
+    ("DW_TAG_access_declaration", 0x23),
+    ("DW_TAG_base_type", 0x24),
+    ("DW_TAG_catch_block", 0x25),
+    ("DW_TAG_const_type", 0x26),
+    ("DW_TAG_constant", 0x27),
+    ("DW_TAG_enumerator", 0x28),
+    ("DW_TAG_file_type", 0x29),
+    ("DW_TAG_friend", 0x2a),
+    ("DW_TAG_namelist", 0x2b),
+    ("DW_TAG_namelist_item", 0x2c),
+    ("DW_TAG_packed_type", 0x2d),
+    ("DW_TAG_subprogram", 0x2e),
+    ("DW_TAG_template_type_parameter", 0x2f),
+    ("DW_TAG_template_value_parameter", 0x30),
+    ("DW_TAG_thrown_type", 0x31),
+    ("DW_TAG_try_block", 0x32),
+    ("DW_TAG_variant_part", 0x33),
+    ("DW_TAG_variable", 0x34),
+    ("DW_TAG_volatile_type", 0x35),
+    ("DW_TAG_dwarf_procedure", 0x36),
+    ("DW_TAG_restrict_type", 0x37),
+    ("DW_TAG_interface_type", 0x38),
+    ("DW_TAG_namespace", 0x39),
+    ("DW_TAG_imported_module", 0x3a),
+    ("DW_TAG_unspecified_type", 0x3b),
+    ("DW_TAG_partial_unit", 0x3c),
+    ("DW_TAG_imported_unit", 0x3d),
+    ("DW_TAG_condition", 0x3f),
+    ("DW_TAG_shared_type", 0x40),
+    ("DW_TAG_type_unit", 0x41),
+    ("DW_TAG_rvalue_reference_type", 0x42),
+    ("DW_TAG_template_alias", 0x43),
+    ("DW_TAG_coarray_type", 0x44),
+    ("DW_TAG_generic_subrange", 0x45),
+    ("DW_TAG_dynamic_type", 0x46),
+    ("DW_TAG_atomic_type", 0x47),
+    ("DW_TAG_call_site", 0x48),
+    ("DW_TAG_call_site_parameter", 0x49),
+    ("DW_TAG_skeleton_unit", 0x4a),
+    ("DW_TAG_immutable_type", 0x4b),
+    ("DW_TAG_MIPS_loop", 0x4081),
+    ("DW_TAG_format_label", 0x4101),
+    ("DW_TAG_function_template", 0x4102),
+    ("DW_TAG_class_template", 0x4103),
+    ("DW_TAG_GNU_BINCL", 0x4104),
+    ("DW_TAG_GNU_EINCL", 0x4105),
+    ("DW_TAG_GNU_template_template_param", 0x4106),
+    ("DW_TAG_GNU_template_parameter_pack", 0x4107),
+    ("DW_TAG_GNU_formal_parameter_pack", 0x4108),
+    ("DW_TAG_GNU_call_site", 0x4109),
+    ("DW_TAG_GNU_call_site_parameter", 0x410a),
+    ("DW_TAG_APPLE_property", 0x4200),
+    ("DW_TAG_SUN_function_template", 0x4201),
+    ("DW_TAG_SUN_class_template", 0x4202),
+    ("DW_TAG_SUN_struct_template", 0x4203),
+    ("DW_TAG_SUN_union_template", 0x4204),
+    ("DW_TAG_SUN_indirect_inheritance", 0x4205),
+    ("DW_TAG_SUN_codeflags", 0x4206),
+    ("DW_TAG_SUN_memop_info", 0x4207),
+    ("DW_TAG_SUN_omp_child_func", 0x4208),
+    ("DW_TAG_SUN_rtti_descriptor", 0x4209),
+    ("DW_TAG_SUN_dtor_info", 0x420a),
+    ("DW_TAG_SUN_dtor", 0x420b),
+    ("DW_TAG_SUN_f90_interface", 0x420c),
+    ("DW_TAG_SUN_fortran_vax_structure", 0x420d),
+    ("DW_TAG_SUN_hi", 0x42ff),
+    ("DW_TAG_ALTIUM_circ_type", 0x5101),
+    ("DW_TAG_ALTIUM_mwa_circ_type", 0x5102),
+    ("DW_TAG_ALTIUM_rev_carry_type", 0x5103),
+    ("DW_TAG_ALTIUM_rom", 0x5111),
+    ("DW_TAG_LLVM_annotation", 0x6000),
+    ("DW_TAG_GHS_namespace", 0x8004),
+    ("DW_TAG_GHS_using_namespace", 0x8005),
+    ("DW_TAG_GHS_using_declaration", 0x8006),
+    ("DW_TAG_GHS_template_templ_param", 0x8007),
+    ("DW_TAG_UPC_shared_type", 0x8765),
+    ("DW_TAG_UPC_strict_type", 0x8766),
+    ("DW_TAG_UPC_relaxed", 0x8767),
+    ("DW_TAG_PGI_kanji_type", 0xa000),
+    ("DW_TAG_PGI_interface_block", 0xa020),
+    ("DW_TAG_BORLAND_property", 0xb000),
+    ("DW_TAG_BORLAND_Delphi_string", 0xb001),
+    ("DW_TAG_BORLAND_Delphi_dynamic_array", 0xb002),
+    ("DW_TAG_BORLAND_Delphi_set", 0xb003),
+    ("DW_TAG_BORLAND_Delphi_variant", 0xb004),
+    ("DW_LANG_C89", 0x1),
+    ("DW_LANG_C", 0x2),
+    ("DW_LANG_Ada83", 0x3),
+    ("DW_LANG_C_plus_plus", 0x4),
+    ("DW_LANG_Cobol74", 0x5),
+    ("DW_LANG_Cobol85", 0x6),
+    ("DW_LANG_Fortran77", 0x7),
+    ("DW_LANG_Fortran90", 0x8),
+    ("DW_LANG_Pascal83", 0x9),
+    ("DW_LANG_Modula2", 0xa),
+    ("DW_LANG_Java", 0xb),
+    ("DW_LANG_C99", 0xc),
+    ("DW_LANG_Ada95", 0xd),
+    ("DW_LANG_Fortran95", 0xe),
+    ("DW_LANG_PLI", 0xf),
+    ("DW_LANG_ObjC", 0x10),
+    ("DW_LANG_ObjC_plus_plus", 0x11),
+    ("DW_LANG_UPC", 0x12),
+    ("DW_LANG_D", 0x13),
+    ("DW_LANG_Python", 0x14),
+    ("DW_LANG_OpenCL", 0x15),
+    ("DW_LANG_Go", 0x16),
+    ("DW_LANG_Modula3", 0x17),
+    ("DW_LANG_Haskell", 0x18),
+    ("DW_LANG_C_plus_plus_03", 0x19),
+    ("DW_LANG_C_plus_plus_11", 0x1a),
+    ("DW_LANG_OCaml", 0x1b),
+    ("DW_LANG_Rust", 0x1c),
+    ("DW_LANG_C11", 0x1d),
+    ("DW_LANG_Swift", 0x1e),
+    ("DW_LANG_Julia", 0x1f),
+    ("DW_LANG_Dylan", 0x20),
+    ("DW_LANG_C_plus_plus_14", 0x21),
+    ("DW_LANG_Fortran03", 0x22),
+    ("DW_LANG_Fortran08", 0x23),
+    ("DW_LANG_RenderScript", 0x24),
+    ("DW_LANG_BLISS", 0x25),
+    ("DW_LANG_Mips_Assembler", 0x8001),
+    ("DW_LANG_GOOGLE_RenderScript", 0x8e57),
+    ("DW_LANG_BORLAND_Delphi", 0xb000),
+    ("DW_ATE_address", 0x1),
+    ("DW_ATE_boolean", 0x2),
+    ("DW_ATE_complex_float", 0x3),
+    ("DW_ATE_float", 0x4),
+    ("DW_ATE_signed", 0x5),
+    ("DW_ATE_signed_char", 0x6),
+    ("DW_ATE_unsigned", 0x7),
+    ("DW_ATE_unsigned_char", 0x8),
+    ("DW_ATE_imaginary_float", 0x9),
+    ("DW_ATE_packed_decimal", 0xa),
+    ("DW_ATE_numeric_string", 0xb),
+    ("DW_ATE_edited", 0xc),
+    ("DW_ATE_signed_fixed", 0xd),
+    ("DW_ATE_unsigned_fixed", 0xe),
+    ("DW_ATE_decimal_float", 0xf),
+    ("DW_ATE_UTF", 0x10),
+    ("DW_ATE_UCS", 0x11),
+    ("DW_ATE_ASCII", 0x12),
+    ("DW_ATE_HP_complex_float", 0x81),
+    ("DW_ATE_HP_float128", 0x82),
+    ("DW_ATE_HP_complex_float128", 0x83),
+    ("DW_ATE_HP_floathpintel", 0x84),
+    ("DW_ATE_HP_imaginary_float90", 0x85),
+    ("DW_ATE_HP_imaginary_float128", 0x86),
+    ("DW_CC_normal", 0x1),
+    ("DW_CC_program", 0x2),
+    ("DW_CC_nocall", 0x3),
+    ("DW_CC_pass_by_reference", 0x4),
+    ("DW_CC_pass_by_value", 0x5),
+    ("DW_CC_GNU_renesas_sh", 0x40),
+    ("DW_CC_GNU_borland_fastcall_i386", 0x41),
+    ("DW_CC_BORLAND_safecall", 0xb0),
+    ("DW_CC_BORLAND_stdcall", 0xb1),
+    ("DW_CC_BORLAND_pascal", 0xb2),
+    ("DW_CC_BORLAND_msfastcall", 0xb3),
+    ("DW_CC_BORLAND_msreturn", 0xb4),
+    ("DW_CC_BORLAND_thiscall", 0xb5),
+    ("DW_CC_BORLAND_fastcall", 0xb6),
+    ("DW_CC_LLVM_vectorcall", 0xc0),
+    ("DW_CC_LLVM_Win64", 0xc1),
+    ("DW_CC_LLVM_X86_64SysV", 0xc2),
+    ("DW_CC_LLVM_AAPCS", 0xc3),
+    ("DW_CC_LLVM_AAPCS_VFP", 0xc4),
+    ("DW_CC_LLVM_IntelOclBicc", 0xc5),
+    ("DW_CC_LLVM_SpirFunction", 0xc6),
+    ("DW_CC_LLVM_OpenCLKernel", 0xc7),
+    ("DW_CC_LLVM_Swift", 0xc8),
+    ("DW_CC_LLVM_PreserveMost", 0xc9),
+    ("DW_CC_LLVM_PreserveAll", 0xca),
+    ("DW_CC_LLVM_X86RegCall", 0xcb),
+    ("DW_CC_GDB_IBM_OpenCL", 0xff),
+    ("DW_MACINFO_define", 1),
+    ("DW_MACINFO_undef", 2),
+    ("DW_MACINFO_start_file", 3),
+    ("DW_MACINFO_end_file", 4),
+    ("DIFlagZero", 0),
+    ("DIFlagPrivate", 1),
+    ("DIFlagProtected", 2),
+    ("DIFlagPublic", 3),
+    ("DIFlagFwdDecl", 1 << 2),
+    ("DIFlagAppleBlock", 1 << 3),
+    ("DIFlagReservedBit4", 1 << 4),
+    ("DIFlagVirtual", 1 << 5),
+    ("DIFlagArtificial", 1 << 6),
+    ("DIFlagExplicit", 1 << 7),
+    ("DIFlagPrototyped", 1 << 8),
+    ("DIFlagObjcClassComplete", 1 << 9),
+    ("DIFlagObjectPointer", 1 << 10),
+    ("DIFlagVector", 1 << 11),
+    ("DIFlagStaticMember", 1 << 12),
+    ("DIFlagLValueReference", 1 << 13),
+    ("DIFlagRValueReference", 1 << 14),
+    ("DIFlagExportSymbols", 1 << 15),
+    ("DIFlagSingleInheritance", 1 << 16),
+    ("DIFlagMultipleInheritance", 2 << 16),
+    ("DIFlagVirtualInheritance", 3 << 16),
+    ("DIFlagIntroducedVirtual", 1 << 18),
+    ("DIFlagBitField", 1 << 19),
+    ("DIFlagNoReturn", 1 << 20),
+    ("DIFlagTypePassByValue", 1 << 22),
+    ("DIFlagTypePassByReference", 1 << 23),
+    ("DIFlagEnumClass", 1 << 24),
+    ("DIFlagThunk", 1 << 25),
+    ("DIFlagNonTrivial", 1 << 26),
+    ("DIFlagBigEndian", 1 << 27),
+    ("DIFlagLittleEndian", 1 << 28),
+    ("DIFlagAllCallsDescribed", 1 << 29),
+    ("DIFlagIndirectVirtualBase", (1 << 2) | (1 << 5)),
+    ("DISPFlagZero", 0),
+    ("DISPFlagVirtual", 1),
+    ("DISPFlagPureVirtual", 2),
+    ("DISPFlagLocalToUnit", 1 << 2),
+    ("DISPFlagDefinition", 1 << 3),
+    ("DISPFlagOptimized", 1 << 4),
+    ("DISPFlagPure", 1 << 5),
+    ("DISPFlagElemental", 1 << 6),
+    ("DISPFlagRecursive", 1 << 7),
+    ("DISPFlagMainSubprogram", 1 << 8),
+    ("DISPFlagDeleted", 1 << 9),
+    ("DISPFlagObjCDirect", 1 << 11),
+    ("CSK_MD5", 1),
+    ("CSK_SHA1", 2),
+    ("CSK_SHA256", 3),
+];
+
+/// Value of a specialized debug info field written by name, such as
+/// `DW_TAG_member`, `FullDebug` or `DIFlagPublic | DIFlagVector`
+pub fn debug_info_constant(field: &str, spelling: &str) -> Option<u64> {
+    spelling.split('|').map(str::trim).try_fold(0, |value, name| {
+        let constant = match (field, name) {
+            ("emissionKind", "NoDebug") => 0,
+            ("emissionKind", "FullDebug") => 1,
+            ("emissionKind", "LineTablesOnly") => 2,
+            ("emissionKind", "DebugDirectivesOnly") => 3,
+            ("nameTableKind", "Default") => 0,
+            ("nameTableKind", "GNU") => 1,
+            ("nameTableKind", "None") => 2,
+            ("nameTableKind", "Apple") => 3,
+            _ => match name.parse() {
+                Ok(constant) => constant,
+                Err(_) => DEBUG_INFO_CONSTANTS.iter().find(|c| c.0 == name)?.1,
+            },
+        };
+        Some(value | constant)
+    })
+}
+
 /// DISubprogram - represents a function
 #[derive(Clone)]
 pub struct DISubprogram {
@@ -475,6 +828,27 @@ impl Metadata {
         }
     }
 
+    /// Get float value if this is a float metadata node
+    pub fn as_float(&self) -> Option<f64> {
+        match &*self.data {
+            MetadataData::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    /// Get the wrapped value if this is a value metadata node
+    pub fn as_value(&self) -> Option<&Value> {
+        match &*self.data {
+            MetadataData::Value(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Identity of the underlying node, shared by all clones
+    pub(crate) fn node_id(&self) -> usize {
+        Arc::as_ptr(&self.data) as usize
+    }
+
     /// Get tuple operands if this is a tuple metadata node
     pub fn as_tuple(&self) -> Option<&Vec<Metadata>> {
         match &*self.data {
@@ -563,6 +937,14 @@ impl Metadata {
         }
     }
 
+    /// Get all fields of named metadata with fields
+    pub fn fields(&self) -> Option<&std::collections::HashMap<String, Metadata>> {
+        match &*self.data {
+            MetadataData::NamedWithFields { fields, .. } => Some(fields),
+            _ => None,
+        }
+    }
+
     /// Check if a field exists in named metadata with fields
     pub fn has_field(&self, field_name: &str) -> bool {
         match &*self.data {
@@ -679,4 +1061,22 @@ mod tests {
         let tuple = Metadata::tuple(vec![md1, md2]);
         assert_eq!(format!("{}", tuple), "!{!1, !2}");
     }
+
+    #[test]
+    fn test_dwarf_op_lookup() {
+        let fragment = DwarfOpInfo::from_name("DW_OP_LLVM_fragment").unwrap();
+        assert_eq!((fragment.code, fragment.args), (0x1000, 2));
+        assert_eq!(DwarfOpInfo::from_code(0x23).unwrap().name, "DW_OP_plus_uconst");
+        assert!(DwarfOpInfo::from_name("DW_OP_bogus").is_none());
+    }
+
+    #[test]
+    fn test_debug_info_constants() {
+        assert_eq!(debug_info_constant("tag", "DW_TAG_base_type"), Some(0x24));
+        assert_eq!(debug_info_constant("language", "DW_LANG_C99"), Some(0xc));
+        assert_eq!(debug_info_constant("flags", "DIFlagPublic | DIFlagStaticMember"), Some(3 | 1 << 12));
+        assert_eq!(debug_info_constant("emissionKind", "FullDebug"), Some(1));
+        assert_eq!(debug_info_constant("nameTableKind", "None"), Some(2));
+        assert_eq!(debug_info_constant("flags", "DIFlagBogus"), None);
+    }
 }
@@ -11,6 +11,7 @@ use crate::function::Function;
 use crate::types::Type;
 use crate::value::Value;
 use crate::metadata::Metadata;
+use crate::summary::ModuleSummaryIndex;
 
 /// Linkage types for global values
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -107,6 +108,11 @@ struct ModuleData {
     named_metadata: HashMap<String, Vec<Metadata>>,
     module_flags: Vec<Metadata>,
     all_metadata: HashMap<String, Metadata>,  // All metadata nodes including numbered (!0, !1, etc.)
+    named_types: HashMap<String, Type>,  // Identified struct types (%name = type ...)
+    source_filename: Option<String>,
+    target_triple: Option<String>,
+    data_layout: Option<String>,
+    summary_index: Option<ModuleSummaryIndex>,  // ThinLTO summary entries (^0 = ...)
 }
 
 /// A global variable in a module
@@ -126,6 +132,8 @@ pub struct GlobalVariable {
     pub section: Option<String>,
     pub alignment: Option<u32>,
     pub comdat: Option<String>,
+    /// Attached metadata, such as the `!dbg` DIGlobalVariableExpression
+    pub metadata: Vec<(String, Metadata)>,
 }
 
 /// An alias in a module
@@ -154,6 +162,11 @@ impl Module {
                 named_metadata: HashMap::new(),
                 module_flags: Vec::new(),
                 all_metadata: HashMap::new(),
+                named_types: HashMap::new(),
+                source_filename: None,
+                target_triple: None,
+                data_layout: None,
+                summary_index: None,
             })),
         }
     }
@@ -163,6 +176,36 @@ impl Module {
         self.data.read().unwrap().name.clone()
     }
 
+    /// Get the source file name (`source_filename = "..."`), if set
+    pub fn source_filename(&self) -> Option<String> {
+        self.data.read().unwrap().source_filename.clone()
+    }
+
+    /// Set the source file name
+    pub fn set_source_filename(&self, source_filename: String) {
+        self.data.write().unwrap().source_filename = Some(source_filename);
+    }
+
+    /// Get the target triple (`target triple = "..."`), if set
+    pub fn target_triple(&self) -> Option<String> {
+        self.data.read().unwrap().target_triple.clone()
+    }
+
+    /// Set the target triple
+    pub fn set_target_triple(&self, triple: String) {
+        self.data.write().unwrap().target_triple = Some(triple);
+    }
+
+    /// Get the data layout string (`target datalayout = "..."`), if set
+    pub fn data_layout(&self) -> Option<String> {
+        self.data.read().unwrap().data_layout.clone()
+    }
+
+    /// Set the data layout string
+    pub fn set_data_layout(&self, layout: String) {
+        self.data.write().unwrap().data_layout = Some(layout);
+    }
+
     /// Get the context associated with this module
     pub fn context(&self) -> Context {
         self.data.read().unwrap().context.clone()
@@ -183,6 +226,13 @@ impl Module {
             .cloned()
     }
 
+    /// Remove a function by name, returning it if it was present
+    pub fn remove_function(&self, name: &str) -> Option<Function> {
+        let mut data = self.data.write().unwrap();
+        let index = data.functions.iter().position(|f| f.name() == name)?;
+        Some(data.functions.remove(index))
+    }
+
     /// Get all functions in this module
     pub fn functions(&self) -> Vec<Function> {
         self.data.read().unwrap().functions.clone()
@@ -311,6 +361,42 @@ impl Module {
         let mut data = self.data.write().unwrap();
         data.all_metadata.insert(name, metadata);
     }
+
+    /// Get a metadata node by name ("0" for !0, "llvm.ident" for !llvm.ident)
+    pub fn get_metadata(&self, name: &str) -> Option<Metadata> {
+        self.data.read().unwrap().all_metadata.get(name).cloned()
+    }
+
+    /// Get all metadata nodes keyed by name
+    pub fn metadata_nodes(&self) -> HashMap<String, Metadata> {
+        self.data.read().unwrap().all_metadata.clone()
+    }
+
+    /// Get the summary index attached to this module, if any
+    pub fn summary_index(&self) -> Option<ModuleSummaryIndex> {
+        self.data.read().unwrap().summary_index.clone()
+    }
+
+    /// Attach a summary index, printed after the module's IR
+    pub fn set_summary_index(&self, index: ModuleSummaryIndex) {
+        self.data.write().unwrap().summary_index = Some(index);
+    }
+
+    /// Register a named type (%name = type ...)
+    pub fn add_named_type(&self, name: String, ty: Type) {
+        let mut data = self.data.write().unwrap();
+        data.named_types.insert(name, ty);
+    }
+
+    /// Get a named type by name, without the leading '%'
+    pub fn get_named_type(&self, name: &str) -> Option<Type> {
+        self.data.read().unwrap().named_types.get(name).cloned()
+    }
+
+    /// Get all named types
+    pub fn named_types(&self) -> HashMap<String, Type> {
+        self.data.read().unwrap().named_types.clone()
+    }
 }
 
 impl GlobalVariable {
@@ -331,6 +417,7 @@ impl GlobalVariable {
             section: None,
             alignment: None,
             comdat: None,
+            metadata: Vec::new(),
         }
     }
 
@@ -366,6 +453,7 @@ impl GlobalVariable {
             section,
             alignment,
             comdat,
+            metadata: Vec::new(),
         }
     }
 
@@ -424,6 +512,11 @@ impl fmt::Display for Module {
             write!(f, "{}", func)?;
         }
 
+        if let Some(index) = &data.summary_index {
+            writeln!(f)?;
+            write!(f, "{}", index)?;
+        }
+
         Ok(())
     }
 }
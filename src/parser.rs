@@ -2,14 +2,17 @@
 //!
 //! This module provides functionality to parse LLVM IR from text format (.ll files).
 
-use crate::lexer::{Lexer, Token};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use crate::lexer::{BorrowedToken, Lexer, Span, Token};
 use crate::module::{Module, GlobalVariable};
-use crate::function::{Function, CallingConvention};
+use crate::function::{Function, CallingConvention, Materializer};
 use crate::basic_block::BasicBlock;
-use crate::instruction::{Instruction, Opcode};
+use crate::instruction::{AtomicRMWBinOp, FloatPredicate, Instruction, IntPredicate, Opcode};
 use crate::value::Value;
 use crate::types::Type;
 use crate::context::Context;
+use crate::summary::{self, GlobalValueId, ModuleSummaryIndex};
 
 /// Parse errors
 #[derive(Debug, Clone)]
@@ -33,10 +36,120 @@ pub enum ParseError {
 /// Parse result
 pub type ParseResult<T> = Result<T, ParseError>;
 
+/// Tokens the parser may look at past the current one
+const LOOKAHEAD: usize = 16;
+
+/// Tokens pulled from the lexer as the parser reaches them
+///
+/// Positions are absolute indices into the token sequence, so the parser can
+/// remember one and back up to it. `LOOKAHEAD` tokens past the current one are
+/// always buffered; `release` drops those before a position the parser will
+/// not return to, which bounds the buffer by one top-level entity or
+/// instruction.
+struct TokenStream<'a> {
+    lexer: Lexer<'a>,
+    buffer: VecDeque<(BorrowedToken<'a>, Span)>,
+    /// Position of the first buffered token
+    first: usize,
+    /// First lexer error; the stream ends with `Token::EOF` where it occurred
+    error: Option<String>,
+}
+
+impl<'a> TokenStream<'a> {
+    fn new(lexer: Lexer<'a>) -> Self {
+        let mut stream = Self { lexer, buffer: VecDeque::new(), first: 0, error: None };
+        stream.fill(LOOKAHEAD);
+        stream
+    }
+
+    /// Lex until the token at `position` is buffered or the input ends
+    fn fill(&mut self, position: usize) {
+        while self.first + self.buffer.len() <= position {
+            if matches!(self.buffer.back(), Some((Token::EOF, _))) {
+                return;
+            }
+            let next = self.lexer.next_spanned().unwrap_or_else(|e| {
+                self.error = Some(e);
+                let at = self.lexer.position();
+                (Token::EOF, Span { start: at, end: at, line: self.lexer.line(), column: self.lexer.column() })
+            });
+            self.buffer.push_back(next);
+        }
+    }
+
+    fn get(&self, position: usize) -> Option<&(BorrowedToken<'a>, Span)> {
+        self.buffer.get(position.checked_sub(self.first)?)
+    }
+
+    /// Drop the tokens before `position`
+    fn release(&mut self, position: usize) {
+        while self.first < position && self.buffer.len() > 1 {
+            self.buffer.pop_front();
+            self.first += 1;
+        }
+    }
+}
+
+/// Finds `%T = type ...` definitions ahead of the parser, so a named type can
+/// be used before the line that defines it
+struct TypeScan<'a> {
+    source: &'a str,
+    /// Started at the parser's position by the first forward reference
+    lexer: Option<Lexer<'a>>,
+    /// A name followed by `=` if the last tokens were one, waiting for `type`
+    pending: Option<(BorrowedToken<'a>, Span, bool)>,
+    /// Definitions passed so far, at the span of their name
+    found: std::collections::HashMap<String, Span>,
+}
+
+impl<'a> TypeScan<'a> {
+    fn new(source: &'a str) -> Self {
+        Self { source, lexer: None, pending: None, found: std::collections::HashMap::new() }
+    }
+
+    /// Span of the definition of `name`, scanning on from `from` the first time
+    fn find(&mut self, name: &str, from: Span) -> Option<Span> {
+        if let Some(span) = self.found.get(name) {
+            return Some(*span);
+        }
+        let source = self.source;
+        let lexer = self.lexer.get_or_insert_with(|| Lexer::resume(source, from));
+        loop {
+            // A lexer error is reported by the parser once it gets there
+            let (token, span) = lexer.next_spanned().ok()?;
+            match (token, self.pending.take()) {
+                (Token::EOF, _) => return None,
+                (Token::Type, Some((Token::LocalIdent(n) | Token::GlobalIdent(n), at, true))) => {
+                    self.found.insert(n.to_string(), at);
+                    if n == name {
+                        return Some(at);
+                    }
+                }
+                (Token::Equal, Some((ident, at, false))) => self.pending = Some((ident, at, true)),
+                (ident @ (Token::LocalIdent(_) | Token::GlobalIdent(_)), _) => self.pending = Some((ident, span, false)),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Module-level tables that function bodies are parsed against
+#[derive(Default)]
+struct ModuleTables {
+    function_decls: std::collections::HashMap<String, Type>,
+    type_table: std::collections::HashMap<String, Type>,
+    metadata_registry: std::collections::HashMap<String, crate::metadata::Metadata>,
+    attribute_groups: std::collections::HashMap<String, std::collections::HashMap<String, String>>,
+    comdat_definitions: std::collections::HashMap<String, String>,
+}
+
 /// LLVM IR Parser
-pub struct Parser {
+///
+/// Tokens borrow from the source being parsed, so a parser lives no longer
+/// than the sources passed to it.
+pub struct Parser<'a> {
     context: Context,
-    tokens: Vec<Token>,
+    tokens: TokenStream<'a>,
     current: usize,
     /// Symbol table for tracking local values within a function
     symbol_table: std::collections::HashMap<String, Value>,
@@ -50,13 +163,71 @@ pub struct Parser {
     attribute_groups: std::collections::HashMap<String, std::collections::HashMap<String, String>>,
     /// Comdat definitions registry for $name
     comdat_definitions: std::collections::HashMap<String, String>,
+    /// Source of the module being parsed lazily, shared with the materializers
+    /// of its deferred function bodies
+    lazy_source: Option<Arc<str>>,
+    /// Function bodies deferred during the current lazy parse, as spans from `{` to `}`
+    deferred_bodies: Vec<(Function, Span)>,
+    /// Spans of summary entries (`^N = ...`), parsed after the module
+    summary_entries: Vec<Span>,
+    /// Named type definitions ahead of the module parse
+    type_scan: Option<TypeScan<'a>>,
+    /// Named types whose definitions are being parsed
+    defining_types: Vec<String>,
+    /// Set when a type definition refers to a type still being defined
+    type_cycle: bool,
+    /// Whether an unknown `%name` type is an error rather than a placeholder
+    require_named_types: bool,
+    /// GUIDs of summary slots (`^N`) naming global values and type ids
+    summary_slots: std::collections::HashMap<u32, GlobalValueId>,
+    /// Module indices of `^N = module:` slots
+    summary_module_slots: std::collections::HashMap<u32, usize>,
+    /// Predicate of the icmp/fcmp being parsed
+    comparison_predicate: Option<BorrowedToken<'a>>,
+    /// Operation of the atomicrmw being parsed
+    rmw_operation: Option<AtomicRMWBinOp>,
+    /// `inalloca` and `swifterror` flags of the alloca being parsed
+    alloca_flags: (bool, bool),
+    /// Calling convention of the call or invoke being parsed
+    call_calling_convention: Option<CallingConvention>,
+    /// `!dbg` attachment of the function header being parsed
+    function_subprogram: Option<crate::metadata::Metadata>,
+    /// Module a constant fragment is parsed against; names resolve in it alone
+    constant_scope: Option<Module>,
+}
+
+/// Materializer for a function body deferred by a lazy parse
+///
+/// Bodies of one module share a parser holding the module's type table,
+/// function declarations and metadata, so they resolve names exactly as an
+/// eager parse of the whole module would.
+struct LazyFunctionBody {
+    context: Context,
+    source: Arc<str>,
+    /// From the opening to the closing brace
+    span: Span,
+    tables: Arc<Mutex<ModuleTables>>,
+}
+
+impl Materializer for LazyFunctionBody {
+    fn materialize(&self, function: &Function) -> Result<Vec<BasicBlock>, String> {
+        let mut tables = self.tables.lock().unwrap();
+        let mut parser = Parser::new(self.context.clone());
+        parser.set_module_tables(std::mem::take(&mut *tables));
+        parser.tokens = TokenStream::new(Lexer::resume(&self.source[..self.span.end], self.span));
+
+        let result = parser.parse_function_body(function);
+        let result = parser.finish(result);
+        *tables = parser.take_module_tables();
+        result.map_err(|e| format!("failed to materialize @{}: {:?}", function.name(), e))
+    }
 }
 
-impl Parser {
+impl<'a> Parser<'a> {
     pub fn new(context: Context) -> Self {
         Self {
             context,
-            tokens: Vec::new(),
+            tokens: TokenStream::new(Lexer::new("")),
             current: 0,
             symbol_table: std::collections::HashMap::new(),
             function_decls: std::collections::HashMap::new(),
@@ -64,20 +235,51 @@ impl Parser {
             metadata_registry: std::collections::HashMap::new(),
             attribute_groups: std::collections::HashMap::new(),
             comdat_definitions: std::collections::HashMap::new(),
+            lazy_source: None,
+            deferred_bodies: Vec::new(),
+            summary_entries: Vec::new(),
+            type_scan: None,
+            defining_types: Vec::new(),
+            type_cycle: false,
+            require_named_types: false,
+            summary_slots: std::collections::HashMap::new(),
+            summary_module_slots: std::collections::HashMap::new(),
+            comparison_predicate: None,
+            rmw_operation: None,
+            alloca_flags: (false, false),
+            call_calling_convention: None,
+            function_subprogram: None,
+            constant_scope: None,
         }
     }
 
+    /// Parse a module, deferring function bodies until they are accessed.
+    ///
+    /// Each function definition's signature is parsed and its body skipped;
+    /// the body is parsed on first access through `Function::basic_blocks()`
+    /// or `Function::materialize()`. The materializers share `source` rather
+    /// than copying it.
+    pub fn parse_module_lazy(&mut self, source: &'a Arc<str>) -> ParseResult<Module> {
+        self.lazy_source = Some(source.clone());
+        let result = self.parse_module(source);
+        self.lazy_source = None;
+        result
+    }
+
     /// Parse a module from source code
-    pub fn parse_module(&mut self, source: &str) -> ParseResult<Module> {
-        // Tokenize
-        let mut lexer = Lexer::new(source);
-        self.tokens = lexer.tokenize().map_err(ParseError::LexerError)?;
+    pub fn parse_module(&mut self, source: &'a str) -> ParseResult<Module> {
+        self.tokens = TokenStream::new(Lexer::new(source));
         self.current = 0;
+        self.type_scan = Some(TypeScan::new(source));
+        self.deferred_bodies.clear();
+        self.summary_entries.clear();
 
-        // Pre-scan and parse type declarations in multiple passes to handle forward references
-        self.parse_all_type_declarations()?;
-        self.current = 0; // Reset to beginning
+        let result = self.parse_module_contents(source);
+        self.type_scan = None;
+        self.finish(result)
+    }
 
+    fn parse_module_contents(&mut self, source: &'a str) -> ParseResult<Module> {
         let module = Module::new("parsed_module".to_string(), self.context.clone());
 
         // Parse module contents with safety limit to prevent infinite loops
@@ -86,18 +288,29 @@ impl Parser {
 
         'main_loop: while !self.is_at_end() && iterations < MAX_MODULE_ITERATIONS {
             iterations += 1;
+            // Nothing before a top-level entity is looked at again
+            self.tokens.release(self.current);
             // Note: Attribute group parsing moved to a dedicated section below
             // (not skipping attributes anymore)
 
             // Parse target datalayout/triple
             if self.match_token(&Token::Target) {
-                self.parse_target_directive()?;
+                self.parse_target_directive(&module)?;
                 continue;
             }
 
             // Parse source_filename
             if self.match_token(&Token::Source_filename) {
-                self.parse_source_filename()?;
+                if let Some(name) = self.parse_source_filename()? {
+                    module.set_source_filename(name);
+                }
+                continue;
+            }
+
+            // Summary entries may refer to later entries, so they are parsed once all are known
+            if let Some(Token::SummaryId(_)) = self.peek() {
+                self.summary_entries.push(self.span());
+                self.skip_summary_entry()?;
                 continue;
             }
 
@@ -116,7 +329,7 @@ impl Parser {
                 self.advance(); // consume =
                 self.advance(); // consume comdat
                 let comdat_type = if let Some(Token::Identifier(ty)) = self.peek() {
-                    let t = ty.clone();
+                    let t = ty.to_string();
                     self.advance(); // consume comdat type
                     t
                 } else {
@@ -139,8 +352,8 @@ impl Parser {
             if self.peek_global_ident().is_some() && self.peek_ahead(1) == Some(&Token::Equal) {
                 // Look ahead to find if this is a global variable (has 'global' or 'constant' keyword)
                 let mut is_global_var = false;
-                for offset in 2..10 {  // Check up to 10 tokens ahead for global/constant
-                    if let Some(tok) = self.peek_ahead(offset) {
+                for offset in 2.. {
+                    if let Some(tok) = self.scan_ahead(offset) {
                         if matches!(tok, Token::Global | Token::Constant) {
                             is_global_var = true;
                             break;
@@ -169,7 +382,7 @@ impl Parser {
                 // Check if it's an alias by looking ahead
                 let mut idx = 2;
                 // Skip linkage/visibility keywords
-                while let Some(tok) = self.peek_ahead(idx) {
+                while let Some(tok) = self.scan_ahead(idx) {
                     // Skip linkage keywords
                     if matches!(tok, Token::Private | Token::Internal | Token::External |
                                      Token::Weak | Token::Linkonce | Token::Linkonce_odr | Token::Weak_odr |
@@ -264,15 +477,15 @@ impl Parser {
                             self.advance();
                             if self.match_token(&Token::Equal) {
                                 if let Some(Token::StringLit(value)) = self.peek().cloned() {
-                                    attrs.insert(key, value);
+                                    attrs.insert(key.into_owned(), value.into_owned());
                                     self.advance();
                                 } else {
                                     // No value, store empty string
-                                    attrs.insert(key, String::new());
+                                    attrs.insert(key.into_owned(), String::new());
                                 }
                             } else {
                                 // Key without value
-                                attrs.insert(key, String::new());
+                                attrs.insert(key.into_owned(), String::new());
                             }
                         } else {
                             // Not a string attribute, skip it
@@ -288,7 +501,7 @@ impl Parser {
             // Parse metadata definitions: !name = !{ ... } or !0 = !{...}
             let metadata_def_name = if let Some(Token::MetadataIdent(name)) = self.peek().cloned() {
                 // Named metadata: !llvm.module.flags = ...
-                let n = name.clone();
+                let n = name.into_owned();
                 self.advance();
                 Some(n)
             } else if self.check(&Token::Exclaim) {
@@ -363,22 +576,245 @@ impl Parser {
             });
         }
 
+        if !self.summary_entries.is_empty() {
+            let index = self.parse_summary_index(&module, source)?;
+            module.set_summary_index(index);
+        }
+
         // Second pass: resolve metadata references and apply attribute groups
         self.resolve_metadata_references(&module);
         self.apply_attribute_groups(&module);
 
+        // Keep named types with the module so fragments can be parsed against it later
+        for (name, ty) in &self.type_table {
+            module.add_named_type(name.clone(), ty.clone());
+        }
+
+        if !self.deferred_bodies.is_empty() {
+            self.attach_materializers();
+        }
+
         Ok(module)
     }
 
+    /// Parse a single function definition or declaration against an existing
+    /// module and add it to that module.
+    ///
+    /// The module's named types, functions and metadata are in scope, along
+    /// with anything this parser has already seen, so the fragment may use
+    /// `%struct.T`, call `@f` or attach `!dbg !3` without restating them.
+    /// A definition replaces a matching declaration of the same name. The
+    /// function is not verified.
+    pub fn parse_function_into(&mut self, module: &Module, source: &'a str) -> ParseResult<Function> {
+        self.enter_module_scope(module);
+        self.load_fragment(source);
+
+        let result = if self.match_token(&Token::Define) {
+            self.parse_function_definition()
+        } else if self.match_token(&Token::Declare) {
+            self.parse_function_declaration()
+        } else {
+            Err(ParseError::UnexpectedToken {
+                expected: "define or declare".to_string(),
+                found: format!("{:?}", self.peek()),
+                position: self.current,
+            })
+        };
+        let function = self.finish(result)?;
+        self.expect_fragment_end("function")?;
+
+        let name = function.name();
+        if let Some(existing) = module.get_function(&name) {
+            if existing.has_body() {
+                return Err(ParseError::InvalidSyntax {
+                    message: format!("invalid redefinition of function '@{}'", name),
+                    position: 0,
+                });
+            }
+            if existing.get_type() != function.get_type() {
+                return Err(ParseError::InvalidSyntax {
+                    message: format!("invalid forward reference to function '@{}' with wrong type", name),
+                    position: 0,
+                });
+            }
+            module.remove_function(&name);
+        }
+
+        self.apply_function_attribute_groups(&function);
+        self.function_decls.insert(name, function.get_type());
+        module.add_function(function.clone());
+        Ok(function)
+    }
+
+    /// Parse a single global variable definition against an existing module
+    /// and add it to that module.
+    pub fn parse_global_into(&mut self, module: &Module, source: &'a str) -> ParseResult<GlobalVariable> {
+        self.enter_module_scope(module);
+        self.load_fragment(source);
+        let global = self.parse_global_variable();
+        let global = self.finish(global)?;
+        self.expect_fragment_end("global variable")?;
+        module.add_global(global.clone()).map_err(|e| ParseError::InvalidSyntax {
+            message: e,
+            position: 0,
+        })?;
+        Ok(global)
+    }
+
+    /// Parse a typed constant such as `i32 42`, `ptr @g` or
+    /// `ptr getelementptr (i8, ptr @g, i64 4)` against an existing module.
+    ///
+    /// Every global the constant names must exist in the module.
+    pub fn parse_constant_in(&mut self, module: &Module, source: &'a str) -> ParseResult<Value> {
+        self.enter_module_scope(module);
+        self.load_fragment(source);
+
+        self.constant_scope = Some(module.clone());
+        let result = self.parse_type()
+            .and_then(|ty| self.parse_value_with_type(Some(&ty)));
+        self.constant_scope = None;
+        let value = self.finish(result)?;
+        self.expect_fragment_end("constant")?;
+        Ok(value)
+    }
+
+    /// Parse a single type using this parser's named types.
+    ///
+    /// Named types come from an earlier `parse_module` or from the modules
+    /// passed to `parse_function_into` and friends. Unlike types inside a
+    /// module, an unknown `%name` is an error rather than a placeholder.
+    pub fn parse_type_fragment(&mut self, source: &'a str) -> ParseResult<Type> {
+        self.load_fragment(source);
+
+        self.require_named_types = true;
+        let ty = self.parse_type();
+        self.require_named_types = false;
+        let ty = self.finish(ty)?;
+        self.expect_fragment_end("type")?;
+        Ok(ty)
+    }
+
+    /// Bring a module's named types, function signatures and metadata into scope
+    fn enter_module_scope(&mut self, module: &Module) {
+        for (name, ty) in module.named_types() {
+            self.type_table.entry(name).or_insert(ty);
+        }
+        for function in module.functions() {
+            self.function_decls.insert(function.name(), function.get_type());
+        }
+        for (name, metadata) in module.metadata_nodes() {
+            self.metadata_registry.entry(name).or_insert(metadata);
+        }
+    }
+
+    /// Start on a fragment, replacing whatever this parser was working on
+    fn load_fragment(&mut self, source: &'a str) {
+        self.tokens = TokenStream::new(Lexer::new(source));
+        self.current = 0;
+        self.symbol_table.clear();
+    }
+
+    /// Report a lexer error in place of whatever the parser made of the
+    /// tokens before it
+    fn finish<T>(&mut self, result: ParseResult<T>) -> ParseResult<T> {
+        match self.tokens.error.take() {
+            Some(error) => Err(ParseError::LexerError(error)),
+            None => result,
+        }
+    }
+
+    /// Parse the `%block` of `blockaddress(@f, %block)` in a constant fragment,
+    /// which must name a block of a function defined in the module
+    fn parse_block_address_target(&mut self, func: &Value) -> ParseResult<Value> {
+        let position = self.current;
+        let name = match self.peek() {
+            Some(Token::LocalIdent(name)) => name.clone(),
+            other => return Err(ParseError::UnexpectedToken {
+                expected: "basic block name".to_string(),
+                found: format!("{:?}", other),
+                position,
+            }),
+        };
+        self.advance();
+
+        let function = func.name().and_then(|f| self.constant_scope.as_ref()?.get_function(f));
+        let error = |message: String| ParseError::InvalidSyntax { message, position };
+        let function = function.ok_or_else(|| error("expected a function in blockaddress".to_string()))?;
+        if !function.has_body() {
+            return Err(error(format!("cannot take blockaddress inside a declaration of '@{}'", function.name())));
+        }
+        if !function.basic_blocks().iter().any(|bb| bb.name().as_deref() == Some(&*name)) {
+            return Err(error(format!("referenced value '%{}' is not a basic block of '@{}'", name, function.name())));
+        }
+        Ok(Value::new(self.context.label_type(), crate::value::ValueKind::BasicBlock, Some(name.into_owned())))
+    }
+
+    /// Fail unless the whole fragment was consumed
+    fn expect_fragment_end(&self, what: &str) -> ParseResult<()> {
+        if self.is_at_end() {
+            Ok(())
+        } else {
+            Err(ParseError::InvalidSyntax {
+                message: format!("unexpected {:?} after {}", self.peek(), what),
+                position: self.current,
+            })
+        }
+    }
+
+    /// Hand deferred function bodies to materializers that share a snapshot
+    /// of this parser's module-level tables
+    fn attach_materializers(&mut self) {
+        let Some(source) = self.lazy_source.clone() else { return };
+        let tables = Arc::new(Mutex::new(ModuleTables {
+            function_decls: self.function_decls.clone(),
+            type_table: self.type_table.clone(),
+            metadata_registry: self.metadata_registry.clone(),
+            attribute_groups: self.attribute_groups.clone(),
+            comdat_definitions: self.comdat_definitions.clone(),
+        }));
+
+        for (function, span) in self.deferred_bodies.drain(..) {
+            function.set_materializer(Arc::new(LazyFunctionBody {
+                context: self.context.clone(),
+                source: source.clone(),
+                span,
+                tables: tables.clone(),
+            }));
+        }
+    }
+
+    fn set_module_tables(&mut self, tables: ModuleTables) {
+        self.function_decls = tables.function_decls;
+        self.type_table = tables.type_table;
+        self.metadata_registry = tables.metadata_registry;
+        self.attribute_groups = tables.attribute_groups;
+        self.comdat_definitions = tables.comdat_definitions;
+    }
+
+    fn take_module_tables(&mut self) -> ModuleTables {
+        ModuleTables {
+            function_decls: std::mem::take(&mut self.function_decls),
+            type_table: std::mem::take(&mut self.type_table),
+            metadata_registry: std::mem::take(&mut self.metadata_registry),
+            attribute_groups: std::mem::take(&mut self.attribute_groups),
+            comdat_definitions: std::mem::take(&mut self.comdat_definitions),
+        }
+    }
+
     /// Apply attribute groups to functions
     fn apply_attribute_groups(&self, module: &Module) {
         for func in module.functions() {
-            let attrs = func.attributes();
-            for group_ref in &attrs.attribute_groups {
-                if let Some(group_attrs) = self.attribute_groups.get(group_ref) {
-                    for (key, value) in group_attrs {
-                        func.add_string_attribute(key.clone(), value.clone());
-                    }
+            self.apply_function_attribute_groups(&func);
+        }
+    }
+
+    /// Apply the attribute groups referenced by one function
+    fn apply_function_attribute_groups(&self, func: &Function) {
+        let attrs = func.attributes();
+        for group_ref in &attrs.attribute_groups {
+            if let Some(group_attrs) = self.attribute_groups.get(group_ref) {
+                for (key, value) in group_attrs {
+                    func.add_string_attribute(key.clone(), value.clone());
                 }
             }
         }
@@ -408,6 +844,29 @@ impl Parser {
             }
         }
 
+        // Instruction attachments may name nodes defined after the function;
+        // lazily parsed bodies see every node and resolve them as they parse
+        if self.lazy_source.is_none() {
+            for function in module.functions() {
+                for bb in function.basic_blocks() {
+                    let unresolved = bb.instructions().iter()
+                        .any(|inst| inst.attached_metadata().iter().any(|(_, node)| node.is_reference()));
+                    if !unresolved {
+                        continue;
+                    }
+                    bb.transform_instructions(|instructions| {
+                        for inst in instructions.iter_mut() {
+                            for (kind, node) in inst.attached_metadata().to_vec() {
+                                if node.is_reference() {
+                                    inst.set_metadata(kind, self.resolve_metadata_node(&node));
+                                }
+                            }
+                        }
+                    });
+                }
+            }
+        }
+
         // Handle !llvm.module.flags specifically for the module flags structure
         if let Some(module_flags_md) = self.metadata_registry.get("llvm.module.flags") {
             if let Some(flag_list) = module_flags_md.operands() {
@@ -421,104 +880,639 @@ impl Parser {
         }
     }
 
-    /// Recursively resolve a metadata node, replacing references with actual content
-    fn resolve_metadata_node(&self, node: &crate::metadata::Metadata) -> crate::metadata::Metadata {
-        use crate::metadata::Metadata;
-
-        // Check if this is a Reference that needs resolution
-        if let Some(ref_name) = self.get_metadata_ref_name(node) {
-            // Look up the referenced metadata in registry
-            if let Some(target) = self.metadata_registry.get(&ref_name) {
-                // Recursively resolve the target in case it contains more references
-                return self.resolve_metadata_node(target);
-            } else {
-                // Couldn't resolve - return as-is
-                return node.clone();
+    /// Recursively resolve a metadata node, replacing references with actual content
+    fn resolve_metadata_node(&self, node: &crate::metadata::Metadata) -> crate::metadata::Metadata {
+        use crate::metadata::Metadata;
+
+        // Check if this is a Reference that needs resolution
+        if let Some(ref_name) = self.get_metadata_ref_name(node) {
+            // Look up the referenced metadata in registry
+            if let Some(target) = self.metadata_registry.get(&ref_name) {
+                // Recursively resolve the target in case it contains more references
+                return self.resolve_metadata_node(target);
+            } else {
+                // Couldn't resolve - return as-is
+                return node.clone();
+            }
+        }
+
+        // If it's a tuple, resolve all operands recursively
+        if let Some(operands) = node.operands() {
+            let resolved_operands: Vec<Metadata> = operands
+                .iter()
+                .map(|op| self.resolve_metadata_node(op))
+                .collect();
+            return Metadata::tuple(resolved_operands);
+        }
+
+        // For other types (String, Int, etc.), return as-is
+        node.clone()
+    }
+
+    /// Helper to check if a metadata node is a Reference and get its name
+    fn get_metadata_ref_name(&self, node: &crate::metadata::Metadata) -> Option<String> {
+        node.as_reference().map(|s| s.to_string())
+    }
+
+    fn parse_target_directive(&mut self, module: &Module) -> ParseResult<()> {
+        // target datalayout = "..."
+        // target triple = "..."
+        let is_layout = self.match_token(&Token::Datalayout);
+        if is_layout || self.match_token(&Token::Triple) {
+            self.consume(&Token::Equal)?;
+            if let Some(Token::StringLit(value)) = self.peek().cloned() {
+                self.advance();
+                if is_layout {
+                    module.set_data_layout(value.into_owned());
+                } else {
+                    module.set_target_triple(value.into_owned());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_source_filename(&mut self) -> ParseResult<Option<String>> {
+        // source_filename = "..."
+        self.consume(&Token::Equal)?;
+        if let Some(Token::StringLit(name)) = self.peek().cloned() {
+            self.advance();
+            return Ok(Some(name.into_owned()));
+        }
+        Ok(None)
+    }
+
+    /// Skip a summary entry (`^N = kind: ...`) during the main module pass
+    fn skip_summary_entry(&mut self) -> ParseResult<()> {
+        self.expect_summary_id()?;
+        self.consume(&Token::Equal)?;
+        self.expect_summary_word()?;
+        self.consume(&Token::Colon)?;
+        self.skip_summary_value()
+    }
+
+    /// Skip one summary value: a parenthesized group or a single token
+    fn skip_summary_value(&mut self) -> ParseResult<()> {
+        let mut depth = 0;
+        loop {
+            if self.is_at_end() {
+                return Err(ParseError::UnexpectedEOF);
+            }
+            if self.check(&Token::LParen) {
+                depth += 1;
+            } else if self.check(&Token::RParen) {
+                depth -= 1;
+            }
+            self.advance();
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Parse the summary entries collected by `parse_module` into an index.
+    ///
+    /// Entries may refer to slots defined later, so a first pass assigns each
+    /// slot its module index or GUID and a second pass parses the entries.
+    fn parse_summary_index(&mut self, module: &Module, source: &'a str) -> ParseResult<ModuleSummaryIndex> {
+        let source_filename = module.source_filename().unwrap_or_else(|| module.name());
+        let entries = std::mem::take(&mut self.summary_entries);
+        self.summary_slots.clear();
+        self.summary_module_slots.clear();
+
+        for &span in &entries {
+            self.resume_at(source, span);
+            self.assign_summary_slot(&source_filename)?;
+        }
+
+        let mut index = ModuleSummaryIndex::new();
+        for &span in &entries {
+            self.resume_at(source, span);
+            self.parse_summary_entry(&mut index)?;
+        }
+        Ok(index)
+    }
+
+    fn assign_summary_slot(&mut self, source_filename: &str) -> ParseResult<()> {
+        let slot = self.expect_summary_id()?;
+        self.consume(&Token::Equal)?;
+        let kind = self.expect_summary_word()?;
+        self.consume(&Token::Colon)?;
+        match kind.as_str() {
+            "module" => {
+                let id = self.summary_module_slots.len();
+                self.summary_module_slots.insert(slot, id);
+            }
+            "gv" => {
+                self.consume(&Token::LParen)?;
+                let guid = match self.expect_summary_word()?.as_str() {
+                    "guid" => {
+                        self.consume(&Token::Colon)?;
+                        self.parse_summary_int()?
+                    }
+                    "name" => {
+                        self.consume(&Token::Colon)?;
+                        let name = self.expect_summary_string()?;
+                        // Locals are hashed together with the source file name
+                        let linkage = self.find_summary_linkage();
+                        summary::global_value_guid(&summary::global_identifier(&name, linkage, source_filename))
+                    }
+                    other => return Err(self.summary_error(format!("expected 'name' or 'guid', found '{}'", other))),
+                };
+                self.summary_slots.insert(slot, guid);
+            }
+            "typeid" | "typeidCompatibleVTable" => {
+                self.consume(&Token::LParen)?;
+                self.expect_summary_field("name")?;
+                let name = self.expect_summary_string()?;
+                self.summary_slots.insert(slot, summary::global_value_guid(&name));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Linkage of the first summary in the rest of the current gv entry
+    fn find_summary_linkage(&mut self) -> crate::module::Linkage {
+        let start = self.current;
+        let mut depth = 0;
+        let mut linkage = crate::module::Linkage::External;
+        while !self.is_at_end() {
+            match self.peek() {
+                Some(Token::LParen) => depth += 1,
+                Some(Token::RParen) if depth == 0 => break,
+                Some(Token::RParen) => depth -= 1,
+                Some(Token::Identifier(word)) if word == "linkage" => {
+                    if let Some(found) = self.peek_ahead(2)
+                        .and_then(Token::keyword_spelling)
+                        .and_then(summary::linkage_from_name) {
+                        linkage = found;
+                        break;
+                    }
+                }
+                _ => {}
+            }
+            self.advance();
+        }
+        self.current = start;
+        linkage
+    }
+
+    fn parse_summary_entry(&mut self, index: &mut ModuleSummaryIndex) -> ParseResult<()> {
+        use crate::summary::{TypeIdOffsetVtable, TypeIdSummary};
+
+        let slot = self.expect_summary_id()?;
+        self.consume(&Token::Equal)?;
+        let kind = self.expect_summary_word()?;
+        self.consume(&Token::Colon)?;
+        match kind.as_str() {
+            "module" => {
+                // ^0 = module: (path: "a.o", hash: (0, 0, 0, 0, 0))
+                self.consume(&Token::LParen)?;
+                self.expect_summary_field("path")?;
+                let path = self.expect_summary_string()?;
+                self.consume(&Token::Comma)?;
+                self.expect_summary_field("hash")?;
+                self.consume(&Token::LParen)?;
+                let mut hash = [0u32; 5];
+                for (i, word) in hash.iter_mut().enumerate() {
+                    if i > 0 {
+                        self.consume(&Token::Comma)?;
+                    }
+                    *word = self.parse_summary_int()? as u32;
+                }
+                self.consume(&Token::RParen)?;
+                self.consume(&Token::RParen)?;
+                index.add_module(path, hash);
+            }
+            "gv" => {
+                // ^1 = gv: (name: "f", summaries: (function: (...), ...))
+                let guid = self.summary_slots[&slot];
+                self.consume(&Token::LParen)?;
+                let name = if self.expect_summary_word()? == "name" {
+                    self.consume(&Token::Colon)?;
+                    Some(self.expect_summary_string()?)
+                } else {
+                    self.consume(&Token::Colon)?;
+                    self.parse_summary_int()?;
+                    None
+                };
+                let mut summaries = Vec::new();
+                if self.match_token(&Token::Comma) {
+                    self.expect_summary_field("summaries")?;
+                    self.consume(&Token::LParen)?;
+                    loop {
+                        summaries.push(self.parse_global_value_summary()?);
+                        if !self.match_token(&Token::Comma) {
+                            break;
+                        }
+                    }
+                    self.consume(&Token::RParen)?;
+                }
+                self.consume(&Token::RParen)?;
+                index.get_or_insert_value(guid, name.as_deref()).summaries.extend(summaries);
+            }
+            "typeid" => {
+                // ^2 = typeid: (name: "_ZTS1A", summary: (typeTestRes: (kind: single, sizeM1BitWidth: 0)))
+                self.consume(&Token::LParen)?;
+                self.expect_summary_field("name")?;
+                let name = self.expect_summary_string()?;
+                self.consume(&Token::Comma)?;
+                self.expect_summary_field("summary")?;
+                self.consume(&Token::LParen)?;
+                self.expect_summary_field("typeTestRes")?;
+                let resolution = self.parse_type_test_resolution()?;
+                // Whole-program devirtualization resolutions are not modelled
+                while self.match_token(&Token::Comma) {
+                    self.expect_summary_word()?;
+                    self.consume(&Token::Colon)?;
+                    self.skip_summary_value()?;
+                }
+                self.consume(&Token::RParen)?;
+                self.consume(&Token::RParen)?;
+                index.add_type_id(TypeIdSummary { name, resolution });
+            }
+            "typeidCompatibleVTable" => {
+                // ^3 = typeidCompatibleVTable: (name: "_ZTS1A", summary: ((offset: 16, ^4)))
+                self.consume(&Token::LParen)?;
+                self.expect_summary_field("name")?;
+                let name = self.expect_summary_string()?;
+                self.consume(&Token::Comma)?;
+                self.expect_summary_field("summary")?;
+                self.consume(&Token::LParen)?;
+                let mut vtables = Vec::new();
+                loop {
+                    self.consume(&Token::LParen)?;
+                    self.expect_summary_field("offset")?;
+                    let offset = self.parse_summary_int()?;
+                    self.consume(&Token::Comma)?;
+                    let vtable = self.parse_summary_ref()?;
+                    self.consume(&Token::RParen)?;
+                    vtables.push(TypeIdOffsetVtable { offset, vtable });
+                    if !self.match_token(&Token::Comma) {
+                        break;
+                    }
+                }
+                self.consume(&Token::RParen)?;
+                self.consume(&Token::RParen)?;
+                index.add_compatible_vtables(name, vtables);
+            }
+            "flags" => index.set_flags(self.parse_summary_int()?),
+            "blockcount" => index.set_block_count(self.parse_summary_int()?),
+            _ => return Err(self.summary_error(format!("unknown summary entry '{}'", kind))),
+        }
+        Ok(())
+    }
+
+    fn parse_global_value_summary(&mut self) -> ParseResult<summary::GlobalValueSummary> {
+        use crate::summary::{AliasSummary, FunctionSummary, GlobalValueSummary, VariableSummary};
+
+        let kind = self.expect_summary_word()?;
+        self.consume(&Token::Colon)?;
+        self.consume(&Token::LParen)?;
+        self.expect_summary_field("module")?;
+        let module = self.parse_summary_module_ref()?;
+        self.consume(&Token::Comma)?;
+        self.expect_summary_field("flags")?;
+        let flags = self.parse_summary_gv_flags()?;
+
+        let summary = match kind.as_str() {
+            "function" => {
+                self.consume(&Token::Comma)?;
+                self.expect_summary_field("insts")?;
+                let mut function = FunctionSummary {
+                    module,
+                    flags,
+                    inst_count: self.parse_summary_int()? as u32,
+                    function_flags: Default::default(),
+                    calls: Vec::new(),
+                    refs: Vec::new(),
+                    type_tests: Vec::new(),
+                };
+                while self.match_token(&Token::Comma) {
+                    let field = self.expect_summary_word()?;
+                    self.consume(&Token::Colon)?;
+                    match field.as_str() {
+                        "funcFlags" => function.function_flags = self.parse_summary_function_flags()?,
+                        "calls" => function.calls = self.parse_summary_calls()?,
+                        "typeIdInfo" => function.type_tests = self.parse_summary_type_tests()?,
+                        "refs" => function.refs = self.parse_summary_refs()?,
+                        // Parameter access and virtual call details are not modelled
+                        _ => self.skip_summary_value()?,
+                    }
+                }
+                GlobalValueSummary::Function(function)
+            }
+            "variable" => {
+                let mut variable = VariableSummary {
+                    module,
+                    flags,
+                    read_only: false,
+                    write_only: false,
+                    constant: false,
+                    refs: Vec::new(),
+                };
+                while self.match_token(&Token::Comma) {
+                    let field = self.expect_summary_word()?;
+                    self.consume(&Token::Colon)?;
+                    match field.as_str() {
+                        "varFlags" => {
+                            for (key, value) in self.parse_summary_record()? {
+                                match key.as_str() {
+                                    "readonly" => variable.read_only = value != "0",
+                                    "writeonly" => variable.write_only = value != "0",
+                                    "constant" => variable.constant = value != "0",
+                                    _ => {}
+                                }
+                            }
+                        }
+                        "refs" => variable.refs = self.parse_summary_refs()?,
+                        _ => self.skip_summary_value()?,
+                    }
+                }
+                GlobalValueSummary::Variable(variable)
+            }
+            "alias" => {
+                self.consume(&Token::Comma)?;
+                self.expect_summary_field("aliasee")?;
+                let aliasee = if self.match_token(&Token::Null) {
+                    None
+                } else {
+                    Some(self.parse_summary_ref()?)
+                };
+                GlobalValueSummary::Alias(AliasSummary { module, flags, aliasee })
+            }
+            _ => return Err(self.summary_error(format!("unknown summary kind '{}'", kind))),
+        };
+        self.consume(&Token::RParen)?;
+        Ok(summary)
+    }
+
+    fn parse_summary_gv_flags(&mut self) -> ParseResult<summary::GlobalValueFlags> {
+        let mut flags = summary::GlobalValueFlags::default();
+        for (key, value) in self.parse_summary_record()? {
+            match key.as_str() {
+                "linkage" => flags.linkage = summary::linkage_from_name(&value)
+                    .ok_or_else(|| self.summary_error(format!("unknown linkage '{}'", value)))?,
+                "visibility" => flags.visibility = summary::visibility_from_name(&value)
+                    .ok_or_else(|| self.summary_error(format!("unknown visibility '{}'", value)))?,
+                "notEligibleToImport" => flags.not_eligible_to_import = value != "0",
+                "live" => flags.live = value != "0",
+                "dsoLocal" => flags.dso_local = value != "0",
+                "canAutoHide" => flags.can_auto_hide = value != "0",
+                _ => {}
+            }
+        }
+        Ok(flags)
+    }
+
+    fn parse_summary_function_flags(&mut self) -> ParseResult<summary::FunctionFlags> {
+        let mut flags = summary::FunctionFlags::default();
+        for (key, value) in self.parse_summary_record()? {
+            let set = value != "0";
+            match key.as_str() {
+                "readNone" => flags.read_none = set,
+                "readOnly" => flags.read_only = set,
+                "noRecurse" => flags.no_recurse = set,
+                "returnDoesNotAlias" => flags.return_does_not_alias = set,
+                "noInline" => flags.no_inline = set,
+                "alwaysInline" => flags.always_inline = set,
+                "noUnwind" => flags.no_unwind = set,
+                "mayThrow" => flags.may_throw = set,
+                "hasUnknownCall" => flags.has_unknown_call = set,
+                "mustBeUnreachable" => flags.must_be_unreachable = set,
+                _ => {}
+            }
+        }
+        Ok(flags)
+    }
+
+    fn parse_type_test_resolution(&mut self) -> ParseResult<summary::TypeTestResolution> {
+        let mut resolution = summary::TypeTestResolution::default();
+        for (key, value) in self.parse_summary_record()? {
+            let number = || value.parse::<u64>()
+                .map_err(|_| self.summary_error(format!("expected integer for '{}'", key)));
+            match key.as_str() {
+                "kind" => resolution.kind = summary::TypeTestResolutionKind::from_name(&value)
+                    .ok_or_else(|| self.summary_error(format!("unknown type test resolution '{}'", value)))?,
+                "sizeM1BitWidth" => resolution.size_m1_bit_width = number()? as u32,
+                "alignLog2" => resolution.align_log2 = number()?,
+                "sizeM1" => resolution.size_m1 = number()?,
+                "bitMask" => resolution.bit_mask = number()? as u8,
+                "inlineBits" => resolution.inline_bits = number()?,
+                _ => {}
+            }
+        }
+        Ok(resolution)
+    }
+
+    /// Parse `((callee: ^N [, hotness: h] [, relbf: n]), ...)`
+    fn parse_summary_calls(&mut self) -> ParseResult<Vec<summary::CallEdge>> {
+        let mut calls = Vec::new();
+        self.consume(&Token::LParen)?;
+        loop {
+            self.consume(&Token::LParen)?;
+            self.expect_summary_field("callee")?;
+            let mut call = summary::CallEdge {
+                callee: self.parse_summary_ref()?,
+                hotness: summary::Hotness::Unknown,
+                rel_block_freq: None,
+            };
+            while self.match_token(&Token::Comma) {
+                let field = self.expect_summary_word()?;
+                self.consume(&Token::Colon)?;
+                match field.as_str() {
+                    "hotness" => {
+                        let name = self.expect_summary_word()?;
+                        call.hotness = summary::Hotness::from_name(&name)
+                            .ok_or_else(|| self.summary_error(format!("unknown hotness '{}'", name)))?;
+                    }
+                    "relbf" => call.rel_block_freq = Some(self.parse_summary_int()?),
+                    _ => self.skip_summary_value()?,
+                }
+            }
+            self.consume(&Token::RParen)?;
+            calls.push(call);
+            if !self.match_token(&Token::Comma) {
+                break;
+            }
+        }
+        self.consume(&Token::RParen)?;
+        Ok(calls)
+    }
+
+    /// Parse `([readonly|writeonly] ^N, ...)`
+    fn parse_summary_refs(&mut self) -> ParseResult<Vec<summary::ValueRef>> {
+        let mut refs = Vec::new();
+        self.consume(&Token::LParen)?;
+        loop {
+            let access = if self.match_token(&Token::Readonly) {
+                summary::RefAccess::ReadOnly
+            } else if self.match_token(&Token::Writeonly) {
+                summary::RefAccess::WriteOnly
+            } else {
+                summary::RefAccess::ReadWrite
+            };
+            refs.push(summary::ValueRef { guid: self.parse_summary_ref()?, access });
+            if !self.match_token(&Token::Comma) {
+                break;
+            }
+        }
+        self.consume(&Token::RParen)?;
+        Ok(refs)
+    }
+
+    /// Parse `typeIdInfo: (typeTests: (^N, guid, ...), ...)`, keeping the type tests
+    fn parse_summary_type_tests(&mut self) -> ParseResult<Vec<GlobalValueId>> {
+        let mut tests = Vec::new();
+        self.consume(&Token::LParen)?;
+        loop {
+            let field = self.expect_summary_word()?;
+            self.consume(&Token::Colon)?;
+            if field == "typeTests" {
+                self.consume(&Token::LParen)?;
+                loop {
+                    tests.push(self.parse_summary_ref()?);
+                    if !self.match_token(&Token::Comma) {
+                        break;
+                    }
+                }
+                self.consume(&Token::RParen)?;
+            } else {
+                self.skip_summary_value()?;
+            }
+            if !self.match_token(&Token::Comma) {
+                break;
+            }
+        }
+        self.consume(&Token::RParen)?;
+        Ok(tests)
+    }
+
+    /// Parse a flat `(key: value, ...)` record whose values are words, numbers or strings
+    fn parse_summary_record(&mut self) -> ParseResult<Vec<(String, String)>> {
+        let mut fields = Vec::new();
+        self.consume(&Token::LParen)?;
+        while !self.check(&Token::RParen) {
+            let key = self.expect_summary_word()?;
+            self.consume(&Token::Colon)?;
+            let value = match self.peek().cloned() {
+                Some(Token::Integer(n)) => n.to_string(),
+                Some(Token::StringLit(s)) => s.into_owned(),
+                _ => self.summary_word().ok_or_else(|| self.summary_error(format!("expected a value for '{}'", key)))?,
+            };
+            self.advance();
+            fields.push((key, value));
+            if !self.match_token(&Token::Comma) {
+                break;
             }
         }
+        self.consume(&Token::RParen)?;
+        Ok(fields)
+    }
 
-        // If it's a tuple, resolve all operands recursively
-        if let Some(operands) = node.operands() {
-            let resolved_operands: Vec<Metadata> = operands
-                .iter()
-                .map(|op| self.resolve_metadata_node(op))
-                .collect();
-            return Metadata::tuple(resolved_operands);
+    /// A bare word in a summary entry; reserved words count as words here
+    fn summary_word(&self) -> Option<String> {
+        match self.peek()? {
+            Token::Identifier(word) => Some(word.to_string()),
+            token => token.keyword_spelling().map(str::to_string),
         }
+    }
 
-        // For other types (String, Int, etc.), return as-is
-        node.clone()
+    fn expect_summary_word(&mut self) -> ParseResult<String> {
+        let word = self.summary_word().ok_or_else(|| ParseError::UnexpectedToken {
+            expected: "summary field name".to_string(),
+            found: format!("{:?}", self.peek()),
+            position: self.current,
+        })?;
+        self.advance();
+        Ok(word)
     }
 
-    /// Helper to check if a metadata node is a Reference and get its name
-    fn get_metadata_ref_name(&self, node: &crate::metadata::Metadata) -> Option<String> {
-        node.as_reference().map(|s| s.to_string())
+    /// Consume `name:`
+    fn expect_summary_field(&mut self, name: &str) -> ParseResult<()> {
+        let word = self.expect_summary_word()?;
+        if word != name {
+            return Err(ParseError::UnexpectedToken {
+                expected: format!("'{}'", name),
+                found: word,
+                position: self.current - 1,
+            });
+        }
+        self.consume(&Token::Colon)
     }
 
-    fn parse_target_directive(&mut self) -> ParseResult<()> {
-        // target datalayout = "..."
-        // target triple = "..."
-        if self.match_token(&Token::Datalayout) || self.match_token(&Token::Triple) {
-            self.consume(&Token::Equal)?;
-            if let Some(Token::StringLit(_)) = self.peek() {
+    fn expect_summary_string(&mut self) -> ParseResult<String> {
+        match self.peek().cloned() {
+            Some(Token::StringLit(s)) => {
                 self.advance();
+                Ok(s.into_owned())
             }
+            other => Err(ParseError::UnexpectedToken {
+                expected: "string".to_string(),
+                found: format!("{:?}", other),
+                position: self.current,
+            }),
         }
-        Ok(())
     }
 
-    fn parse_source_filename(&mut self) -> ParseResult<()> {
-        // source_filename = "..."
-        self.consume(&Token::Equal)?;
-        if let Some(Token::StringLit(_)) = self.peek() {
-            self.advance();
+    fn expect_summary_id(&mut self) -> ParseResult<u32> {
+        match self.peek() {
+            Some(Token::SummaryId(id)) => {
+                let id = *id;
+                self.advance();
+                Ok(id)
+            }
+            other => Err(ParseError::UnexpectedToken {
+                expected: "summary id".to_string(),
+                found: format!("{:?}", other),
+                position: self.current,
+            }),
         }
-        Ok(())
     }
 
-    fn parse_all_type_declarations(&mut self) -> ParseResult<()> {
-        // Multi-pass approach to handle forward references
-        // Keep parsing until no more type declarations are found or max iterations reached
-        const MAX_PASSES: usize = 10;
-
-        for _pass in 0..MAX_PASSES {
-            self.current = 0;
-            let mut found_any = false;
-
-            while !self.is_at_end() {
-                // Look for type declarations
-                if (self.peek_global_ident().is_some() || self.check_local_ident())
-                    && self.peek_ahead(1) == Some(&Token::Equal)
-                    && self.peek_ahead(2) == Some(&Token::Type) {
-
-                    // Try to parse this type declaration
-                    let saved_pos = self.current;
-                    if self.parse_type_declaration().is_ok() {
-                        found_any = true;
-                    } else {
-                        // Parsing failed (probably forward reference), restore position
-                        self.current = saved_pos;
-                        self.advance();
-                    }
-                } else {
-                    self.advance();
-                }
+    fn parse_summary_int(&mut self) -> ParseResult<u64> {
+        match self.peek() {
+            Some(Token::Integer(n)) if (0..=u64::MAX as i128).contains(n) => {
+                let n = *n as u64;
+                self.advance();
+                Ok(n)
             }
+            other => Err(ParseError::UnexpectedToken {
+                expected: "unsigned integer".to_string(),
+                found: format!("{:?}", other),
+                position: self.current,
+            }),
+        }
+    }
 
-            // If no declarations were successfully parsed this pass, we're done
-            if !found_any {
-                break;
-            }
+    /// A reference to a global value or type id: `^N` or a raw GUID
+    fn parse_summary_ref(&mut self) -> ParseResult<GlobalValueId> {
+        if let Some(Token::SummaryId(id)) = self.peek() {
+            let id = *id;
+            let guid = self.summary_slots.get(&id).copied()
+                .ok_or_else(|| self.summary_error(format!("use of undefined summary entry ^{}", id)))?;
+            self.advance();
+            return Ok(guid);
         }
+        self.parse_summary_int()
+    }
 
-        Ok(())
+    fn parse_summary_module_ref(&mut self) -> ParseResult<usize> {
+        let id = self.expect_summary_id()?;
+        self.summary_module_slots.get(&id).copied()
+            .ok_or_else(|| self.summary_error(format!("^{} is not a module entry", id)))
+    }
+
+    fn summary_error(&self, message: String) -> ParseError {
+        ParseError::InvalidSyntax { message, position: self.current }
     }
 
     fn parse_type_declaration(&mut self) -> ParseResult<()> {
         // %TypeName = type { ... } or %0 = type { ... } or %TypeName = type opaque
         let type_name = match self.peek() {
-            Some(Token::GlobalIdent(name)) => name.clone(),
-            Some(Token::LocalIdent(name)) => name.clone(),
+            Some(Token::GlobalIdent(name)) => name.to_string(),
+            Some(Token::LocalIdent(name)) => name.to_string(),
             _ => return Err(ParseError::InvalidSyntax {
                 message: "Expected type name".to_string(),
                 position: self.current,
@@ -528,19 +1522,69 @@ impl Parser {
         self.consume(&Token::Equal)?;
         self.consume(&Token::Type)?;
 
+        // A type that refers to itself sees a placeholder the first time and
+        // the stored first version the second
+        let body = self.current;
+        let outer_cycle = std::mem::replace(&mut self.type_cycle, false);
+        self.defining_types.push(type_name.clone());
+        let mut result = self.parse_type_body(&type_name);
+        if result.is_ok() && self.type_cycle {
+            self.current = body;
+            result = self.parse_type_body(&type_name);
+        }
+        self.defining_types.pop();
+        self.type_cycle = outer_cycle;
+        result
+    }
+
+    fn parse_type_body(&mut self, type_name: &str) -> ParseResult<()> {
         // Check if it's an opaque type
         let ty = if self.match_token(&Token::Opaque) {
             // Opaque type - create proper opaque type
-            Type::opaque(&self.context, type_name.clone())
+            Type::opaque(&self.context, type_name.to_string())
         } else {
             self.parse_type()?
         };
 
         // Store in type table
-        self.type_table.insert(type_name, ty);
+        self.type_table.insert(type_name.to_string(), ty);
         Ok(())
     }
 
+    /// Look up a named type, parsing its definition further down the module
+    /// if the parser has not reached it yet
+    fn named_type(&mut self, name: &str) -> ParseResult<Type> {
+        if let Some(ty) = self.type_table.get(name) {
+            return Ok(ty.clone());
+        }
+        if self.require_named_types {
+            return Err(ParseError::UnknownType { type_name: format!("%{}", name), position: self.current });
+        }
+        if self.defining_types.iter().any(|defining| defining == name) {
+            self.type_cycle = true;
+        } else if let Some(ty) = self.parse_forward_type(name) {
+            return Ok(ty);
+        }
+        // Type not found - treat as opaque type placeholder (use i8 to ensure it's sized for alloca)
+        Ok(self.context.int8_type())
+    }
+
+    /// Parse the definition of a named type that comes later in the module
+    fn parse_forward_type(&mut self, name: &str) -> Option<Type> {
+        let from = self.span();
+        let scan = self.type_scan.as_mut()?;
+        let span = scan.find(name, from)?;
+        let source = scan.source;
+
+        let tokens = std::mem::replace(&mut self.tokens, TokenStream::new(Lexer::resume(source, span)));
+        let current = std::mem::replace(&mut self.current, 0);
+        let result = self.parse_type_declaration();
+        self.tokens = tokens;
+        self.current = current;
+        result.ok()?;
+        self.type_table.get(name).cloned()
+    }
+
     fn parse_global_variable(&mut self) -> ParseResult<GlobalVariable> {
         use crate::module::{Linkage, Visibility, DLLStorageClass, ThreadLocalMode, UnnamedAddr};
 
@@ -587,7 +1631,7 @@ impl Parser {
                     // Check for thread local mode: thread_local(localdynamic)
                     if self.match_token(&Token::LParen) {
                         if let Some(Token::Identifier(mode)) = self.peek() {
-                            match mode.as_str() {
+                            match &**mode {
                                 "generaldynamic" => thread_local_mode = ThreadLocalMode::GeneralDynamic,
                                 "localdynamic" => thread_local_mode = ThreadLocalMode::LocalDynamic,
                                 "initialexec" => thread_local_mode = ThreadLocalMode::InitialExec,
@@ -623,7 +1667,7 @@ impl Parser {
                         } else if let Some(Token::StringLit(s)) = self.peek() {
                             // Symbolic address space: map to number
                             // Default mapping: A=1 (alloca), G=2 (global), P=3 (program)
-                            let addr_num = match s.as_str() {
+                            let addr_num = match &**s {
                                 "A" => 1,
                                 "G" => 2,
                                 "P" => 3,
@@ -674,29 +1718,31 @@ impl Parser {
         // Parse initializer if present
         // Don't parse initializer if next token is:
         // - End of file
-        // - A global ident (start of next global)
+        // - A global ident starting the next global (@g = ...)
         // - Define/Declare (start of function)
         // - Comma (trailing attributes)
         // - A local ident that starts a type declaration (%T = type ...)
         let is_type_decl = self.check_local_ident()
             && self.peek_ahead(1) == Some(&Token::Equal)
             && self.peek_ahead(2) == Some(&Token::Type);
+        let is_next_global = self.check_global_ident() && self.peek_ahead(1) == Some(&Token::Equal);
 
-        let initializer = if !self.is_at_end() && !self.check_global_ident() && !self.check(&Token::Define) && !self.check(&Token::Declare) && !self.check(&Token::Comma) && !is_type_decl {
+        let initializer = if !self.is_at_end() && !is_next_global && !self.check(&Token::Define) && !self.check(&Token::Declare) && !self.check(&Token::Comma) && !is_type_decl {
             // Parse the initializer and propagate errors
             Some(self.parse_global_initializer(&ty)?)
         } else {
             None
         };
 
-        // Parse trailing attributes (section, align, comdat, etc.)
+        // Parse trailing attributes (section, align, comdat, metadata)
+        let mut metadata = Vec::new();
         loop {
             match self.peek() {
                 Some(Token::Comma) => { self.advance(); },
                 Some(Token::Section) => {
                     self.advance();
                     if let Some(Token::StringLit(s)) = self.peek() {
-                        section = Some(s.clone());
+                        section = Some(s.to_string());
                         self.advance();
                     }
                 },
@@ -713,12 +1759,12 @@ impl Parser {
                     let comdat_name = if self.match_token(&Token::LParen) {
                         // Handle $name format (GlobalIdent)
                         let cname = if let Some(Token::GlobalIdent(name)) = self.peek() {
-                            let n = name.clone();
+                            let n = name.to_string();
                             self.advance();
                             n
                         } else if let Some(Token::Identifier(name)) = self.peek() {
                             // Handle plain identifier format
-                            let n = name.clone();
+                            let n = name.to_string();
                             self.advance();
                             n
                         } else {
@@ -741,11 +1787,17 @@ impl Parser {
 
                     comdat = Some(comdat_name);
                 },
+                Some(Token::MetadataIdent(kind)) if !self.at_metadata_definition() => {
+                    // Attachment such as `!dbg !5`
+                    let kind = kind.to_string();
+                    self.advance();
+                    metadata.push((kind, self.parse_metadata_node()?));
+                },
                 _ => break,
             }
         }
 
-        Ok(crate::module::GlobalVariable::new_with_attributes(
+        let mut global = crate::module::GlobalVariable::new_with_attributes(
             name,
             ty,
             is_constant,
@@ -760,7 +1812,9 @@ impl Parser {
             section,
             alignment,
             comdat,
-        ))
+        );
+        global.metadata = metadata;
+        Ok(global)
     }
 
     fn parse_alias(&mut self) -> ParseResult<crate::module::Alias> {
@@ -803,7 +1857,7 @@ impl Parser {
                     self.advance();
                     if self.match_token(&Token::LParen) {
                         if let Some(Token::Identifier(mode)) = self.peek() {
-                            match mode.as_str() {
+                            match &**mode {
                                 "generaldynamic" => thread_local_mode = ThreadLocalMode::GeneralDynamic,
                                 "localdynamic" => thread_local_mode = ThreadLocalMode::LocalDynamic,
                                 "initialexec" => thread_local_mode = ThreadLocalMode::InitialExec,
@@ -931,6 +1985,10 @@ impl Parser {
                 // Complex aggregate constant - parse with expected type for validation
                 self.parse_value_with_type(Some(ty))
             },
+            Some(Token::GlobalIdent(_)) => {
+                // Address of another global or function
+                self.parse_value_with_type(Some(ty))
+            },
             Some(Token::Identifier(_)) => {
                 // Could be splat, asm, or other special identifiers
                 self.parse_value_with_type(Some(ty))
@@ -1000,12 +2058,14 @@ impl Parser {
         self.consume(&Token::RParen)?;
 
         // Parse function attributes
+        self.function_subprogram = None;
         let mut attrs = self.parse_function_attributes()?;
         attrs.return_attributes = ret_attrs;
         attrs.parameter_attributes = param_attrs;
 
         let fn_type = self.context.function_type(return_type, param_types, is_vararg);
         let function = Function::new(name, fn_type);
+        function.set_subprogram(self.function_subprogram.take());
         function.set_visibility(visibility);
         function.set_dll_storage_class(dll_storage_class);
         function.set_calling_convention(cc);
@@ -1034,6 +2094,7 @@ impl Parser {
         self.consume(&Token::RParen)?;
 
         // Parse function attributes
+        self.function_subprogram = None;
         let mut attrs = self.parse_function_attributes()?;
         attrs.return_attributes = ret_attrs;
         attrs.parameter_attributes = param_attrs;
@@ -1042,6 +2103,7 @@ impl Parser {
         let param_types: Vec<Type> = params.iter().map(|(ty, _)| ty.clone()).collect();
         let fn_type = self.context.function_type(return_type, param_types, is_vararg);
         let function = Function::new(name, fn_type);
+        function.set_subprogram(self.function_subprogram.take());
         function.set_linkage(linkage);
         function.set_visibility(visibility);
         function.set_dll_storage_class(dll_storage_class);
@@ -1054,39 +2116,85 @@ impl Parser {
         }).collect();
         function.set_arguments(args.clone());
 
+        // Parse body if present
+        if self.check(&Token::LBrace) {
+            if self.lazy_source.is_some() {
+                self.defer_function_body(&function)?;
+            } else {
+                for bb in self.parse_function_body(&function)? {
+                    function.add_basic_block(bb);
+                }
+            }
+        }
+
+        Ok(function)
+    }
+
+    /// Parse a function body `{ ... }` starting at the opening brace
+    fn parse_function_body(&mut self, function: &Function) -> ParseResult<Vec<BasicBlock>> {
         // Clear symbol table for this function and populate with parameters
         self.symbol_table.clear();
-        for arg in &args {
+        for arg in function.arguments() {
             if let Some(name) = arg.name() {
                 self.symbol_table.insert(name.to_string(), arg.clone());
             }
         }
 
-        // Parse body if present with safety limit to prevent infinite loops
-        if self.match_token(&Token::LBrace) {
-            let mut bb_count = 0;
-            const MAX_BASIC_BLOCKS: usize = 10000;
+        self.consume(&Token::LBrace)?;
 
-            while !self.check(&Token::RBrace) && !self.is_at_end() && bb_count < MAX_BASIC_BLOCKS {
-                bb_count += 1;
-                if let Some(bb) = self.parse_basic_block()? {
-                    function.add_basic_block(bb);
-                } else {
-                    break;
-                }
+        // Parse basic blocks with safety limit to prevent infinite loops
+        let mut blocks = Vec::new();
+        let mut bb_count = 0;
+        const MAX_BASIC_BLOCKS: usize = 10000;
+
+        while !self.check(&Token::RBrace) && !self.is_at_end() && bb_count < MAX_BASIC_BLOCKS {
+            bb_count += 1;
+            if let Some(bb) = self.parse_basic_block()? {
+                blocks.push(bb);
+            } else {
+                break;
             }
+        }
 
-            if bb_count >= MAX_BASIC_BLOCKS {
-                return Err(ParseError::InvalidSyntax {
-                    message: format!("Function exceeded maximum basic block count ({}), possible infinite loop", MAX_BASIC_BLOCKS),
-                    position: self.current,
-                });
+        if bb_count >= MAX_BASIC_BLOCKS {
+            return Err(ParseError::InvalidSyntax {
+                message: format!("Function exceeded maximum basic block count ({}), possible infinite loop", MAX_BASIC_BLOCKS),
+                position: self.current,
+            });
+        }
+
+        self.consume(&Token::RBrace)?;
+        Ok(blocks)
+    }
+
+    /// Skip a function body `{ ... }` and record its byte range for later materialization
+    fn defer_function_body(&mut self, function: &Function) -> ParseResult<()> {
+        let open = self.span();
+        let mut depth = 0usize;
+        while !self.is_at_end() {
+            match self.peek() {
+                Some(Token::LBrace) => depth += 1,
+                Some(Token::RBrace) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                _ => {}
             }
+            self.advance();
+            // The body is only scanned, so none of it needs to stay buffered
+            self.tokens.release(self.current);
+        }
 
-            self.consume(&Token::RBrace)?;
+        if !self.check(&Token::RBrace) {
+            return Err(ParseError::UnexpectedEOF);
         }
+        let close = self.span();
+        self.advance(); // consume closing brace
 
-        Ok(function)
+        self.deferred_bodies.push((function.clone(), Span { end: close.end, ..open }));
+        Ok(())
     }
 
     fn parse_basic_block(&mut self) -> ParseResult<Option<BasicBlock>> {
@@ -1095,10 +2203,10 @@ impl Parser {
             if self.peek_ahead(1) == Some(&Token::Colon) {
                 // Extract label name from various token types
                 let label_name = match token {
-                    Token::LocalIdent(n) => Some(n),
-                    Token::Identifier(n) => Some(n), // Bare identifiers like BB1, then, etc.
+                    Token::LocalIdent(n) => Some(n.into_owned()),
+                    Token::Identifier(n) => Some(n.into_owned()), // Bare identifiers like BB1, then, etc.
                     Token::Integer(n) => Some(n.to_string()), // Numeric labels like 1:, 2:
-                    Token::StringLit(s) => Some(s), // String labels like "2":
+                    Token::StringLit(s) => Some(s.into_owned()), // String labels like "2":
                     // Common keywords that can be used as labels
                     Token::Entry => Some("entry".to_string()),
                     Token::Cleanup => Some("cleanup".to_string()),
@@ -1117,8 +2225,8 @@ impl Parser {
                     Token::Load => Some("load".to_string()),
                     Token::Store => Some("store".to_string()),
                     Token::Alloca => Some("alloca".to_string()),
-                    // Any other token followed by colon is not a valid label
-                    _ => None,
+                    // Any other reserved word spelled as a label, e.g. `one:`
+                    other => other.keyword_spelling().map(str::to_string),
                 };
 
                 if let Some(name) = label_name {
@@ -1209,6 +2317,8 @@ impl Parser {
             }
 
             inst_count += 1;
+            // Nothing backtracks past the start of an instruction
+            self.tokens.release(self.current);
 
             // Stop if we hit next label, closing brace, or EOF
             if self.check(&Token::RBrace) || self.is_at_end() {
@@ -1286,7 +2396,7 @@ impl Parser {
             if self.peek_ahead(1) == Some(&Token::Equal) {
                 self.advance(); // consume ident
                 self.advance(); // consume =
-                Some(n)
+                Some(n.into_owned())
             } else {
                 None
             }
@@ -1363,6 +2473,11 @@ impl Parser {
 
         // Parse operands and get result type if instruction produces one
         let (operands, result_type, gep_source_type, alignment, is_atomic, operand_bundles) = self.parse_instruction_operands(opcode)?;
+        let opcode = if opcode == Opcode::Br && operands.len() == 3 { Opcode::CondBr } else { opcode };
+        let predicate = self.comparison_predicate.take();
+        let rmw_operation = self.rmw_operation.take();
+        let (inalloca, swifterror) = std::mem::take(&mut self.alloca_flags);
+        let call_calling_convention = self.call_calling_convention.take();
 
         // Skip instruction-level attributes that come after operands (nounwind, readonly, etc.)
         self.skip_instruction_level_attributes();
@@ -1371,33 +2486,35 @@ impl Parser {
         // Some instructions (like extractvalue) may consume a comma but leave metadata
         // Handle both: ", !foo !0" and "!foo !0" (comma already consumed)
         let mut metadata_attachments = Vec::new();
+        let mut debug_loc = None;
+        let mut attached_metadata = Vec::new();
         loop {
             if self.match_token(&Token::Comma) {
                 // Comma-prefixed metadata: , !dbg !0
-                if self.is_metadata_token() {
-                    if let Some(name) = self.skip_metadata() {
-                        metadata_attachments.push(name);
-                    }
-                    if self.is_metadata_token() {
-                        self.skip_metadata(); // Skip the metadata value (!0, !{}, etc.)
-                    }
-                } else {
+                if !self.is_metadata_token() {
                     // Not metadata, put comma back and stop
                     self.current -= 1;
                     break;
                 }
-            } else if self.is_metadata_token() {
-                // Direct metadata (comma was consumed by operand parsing)
-                if let Some(name) = self.skip_metadata() {
-                    metadata_attachments.push(name);
-                }
-                if self.is_metadata_token() {
-                    self.skip_metadata(); // Skip the metadata value (!0, !{}, etc.)
-                }
-            } else {
+            } else if !self.is_metadata_token() {
                 // No more metadata
                 break;
             }
+            // Direct metadata (comma was consumed by operand parsing)
+            if let Some(name) = self.skip_metadata() {
+                if self.is_metadata_token() {
+                    let start = self.current;
+                    match self.parse_metadata_node() {
+                        Ok(node) if name == "dbg" => debug_loc = Some(node),
+                        Ok(node) => attached_metadata.push((name.clone(), node)),
+                        Err(_) => self.current = start,
+                    }
+                }
+                metadata_attachments.push(name);
+            }
+            if self.is_metadata_token() {
+                self.skip_metadata(); // Skip the metadata value (!0, !{}, etc.)
+            }
         }
 
         // Create result value if there's a result name OR if instruction produces a non-void result
@@ -1443,6 +2560,23 @@ impl Parser {
             inst.set_atomic(true);
         }
 
+        if let Some(predicate) = predicate {
+            match opcode {
+                Opcode::ICmp => if let Some(p) = int_predicate(&predicate) { inst.set_int_predicate(p) },
+                Opcode::FCmp => if let Some(p) = float_predicate(&predicate) { inst.set_float_predicate(p) },
+                _ => {}
+            }
+        }
+
+        if let Some(operation) = rmw_operation {
+            inst.set_rmw_operation(operation);
+        }
+        inst.set_inalloca(inalloca);
+        inst.set_swifterror(swifterror);
+        if let Some(cc) = call_calling_convention {
+            inst.set_calling_convention(cc);
+        }
+
         // Attach operand bundles if present (for Call/Invoke instructions)
         for bundle in operand_bundles {
             inst.add_operand_bundle(bundle);
@@ -1452,6 +2586,10 @@ impl Parser {
         for md_name in metadata_attachments {
             inst.add_metadata_attachment(md_name);
         }
+        inst.set_debug_loc(debug_loc);
+        for (kind, node) in attached_metadata {
+            inst.set_metadata(kind, node);
+        }
         Ok(Some(inst))
     }
 
@@ -1564,17 +2702,27 @@ impl Parser {
             }
             Opcode::Br => {
                 // br label %dest or br i1 %cond, label %iftrue, label %iffalse
+                // The conditional form becomes a CondBr once the operands are known
                 if self.match_token(&Token::Label) {
-                    let _dest = self.expect_local_ident()?;
+                    let dest = self.expect_local_ident()?;
+                    operands.push(Value::new(
+                        self.context.label_type(),
+                        crate::value::ValueKind::BasicBlock,
+                        Some(dest)
+                    ));
                 } else {
-                    let _cond_ty = self.parse_type()?;
-                    let _cond = self.parse_value()?;
-                    self.consume(&Token::Comma)?;
-                    self.consume(&Token::Label)?;
-                    let _true_dest = self.expect_local_ident()?;
-                    self.consume(&Token::Comma)?;
-                    self.consume(&Token::Label)?;
-                    let _false_dest = self.expect_local_ident()?;
+                    let cond_ty = self.parse_type()?;
+                    operands.push(self.parse_value_with_type(Some(&cond_ty))?);
+                    for _ in 0..2 {
+                        self.consume(&Token::Comma)?;
+                        self.consume(&Token::Label)?;
+                        let dest = self.expect_local_ident()?;
+                        operands.push(Value::new(
+                            self.context.label_type(),
+                            crate::value::ValueKind::BasicBlock,
+                            Some(dest)
+                        ));
+                    }
                 }
             }
             Opcode::CallBr => {
@@ -1614,7 +2762,7 @@ impl Parser {
                 // 1. Fast-math flags (nnan, ninf, etc.)
                 self.skip_instruction_flags();
                 // 2. Calling convention (fastcc, coldcc, etc.)
-                self.skip_linkage_and_visibility();
+                self.call_calling_convention = Some(self.parse_calling_convention());
                 // 3. Return attributes (inreg, zeroext, etc.)
                 self.skip_attributes();
 
@@ -1682,9 +2830,8 @@ impl Parser {
             }
             Opcode::Alloca => {
                 // alloca [inalloca] [swifterror] type [, type NumElements] [, align N] [, addrspace(N)]
-                // Skip optional inalloca and swifterror keywords
-                self.match_token(&Token::Inalloca);
-                self.match_token(&Token::Swifterror);
+                let inalloca = self.match_token(&Token::Inalloca);
+                self.alloca_flags = (inalloca, self.match_token(&Token::Swifterror));
 
                 let alloca_ty = self.parse_type()?;
 
@@ -1737,9 +2884,12 @@ impl Parser {
                         // Skip unknown attributes
                         self.advance();
                     } else {
-                        // This is array size: type value
-                        let _size_ty = self.parse_type()?;
-                        let _size_val = self.parse_value()?;
+                        // Element count: type value, kept as an operand unless it is 1
+                        let size_ty = self.parse_type()?;
+                        let size_val = self.parse_value_with_type(Some(&size_ty))?;
+                        if !size_val.is_const_int_value(1) {
+                            operands.push(size_val);
+                        }
                     }
                 }
 
@@ -1765,8 +2915,8 @@ impl Parser {
                 // Check if using old syntax (no comma) or new syntax (comma)
                 if self.match_token(&Token::Comma) {
                     // New syntax: comma separates type from pointer
-                    let _ptr_ty = self.parse_type()?;
-                    let ptr = self.parse_value()?;
+                    let ptr_ty = self.parse_type()?;
+                    let ptr = self.parse_value_with_type(Some(&ptr_ty))?;
                     operands.push(ptr);
                 } else {
                     // Old syntax: pointer value directly follows (type already includes *)
@@ -1790,8 +2940,8 @@ impl Parser {
                 self.consume(&Token::Comma)?;
 
                 // Parse pointer - could be "ptr" keyword (new) or typed pointer (old)
-                let _ptr_ty = self.parse_type()?;
-                let ptr = self.parse_value()?;
+                let ptr_ty = self.parse_type()?;
+                let ptr = self.parse_value_with_type(Some(&ptr_ty))?;
                 operands.push(ptr);
 
                 // Parse alignment and other attributes
@@ -1838,20 +2988,23 @@ impl Parser {
                     operands.push(idx);
                 }
 
-                // Set result type based on whether we found a vector
+                // Set result type based on whether we found a vector, keeping
+                // the address space of the base
+                let address_space = ptr_ty.vector_info()
+                    .map_or(&ptr_ty, |(elem_ty, _)| elem_ty)
+                    .address_space()
+                    .unwrap_or(0);
+                let result_ptr_ty = Type::ptr_addrspace(&self.context, self.context.int8_type(), address_space);
                 result_type = if let Some(size) = vector_size {
-                    Some(self.context.vector_type(
-                        self.context.ptr_type(self.context.int8_type()),
-                        size
-                    ))
+                    Some(self.context.vector_type(result_ptr_ty, size))
                 } else {
-                    Some(self.context.ptr_type(self.context.int8_type()))
+                    Some(result_ptr_ty)
                 };
             }
             Opcode::ICmp | Opcode::FCmp => {
                 // icmp/fcmp [samesign] predicate type op1, op2
                 self.skip_instruction_flags(); // Skip flags like samesign
-                self.parse_comparison_predicate()?;
+                self.comparison_predicate = Some(self.parse_comparison_predicate()?);
                 let ty = self.parse_type()?;
                 // Comparison result is i1 for scalars, <N x i1> for vectors
                 let cmp_result_ty = if ty.is_vector() {
@@ -2036,18 +3189,18 @@ impl Parser {
                 self.match_token(&Token::Volatile);
 
                 // Parse pointer type and value
-                let _ptr_ty = self.parse_type()?;
-                let _ptr = self.parse_value()?;
+                let ptr_ty = self.parse_type()?;
+                operands.push(self.parse_value_with_type(Some(&ptr_ty))?);
                 self.consume(&Token::Comma)?;
 
                 // Parse compare type and value
                 let cmp_ty = self.parse_type()?;
-                let _cmp = self.parse_value()?;
+                operands.push(self.parse_value_with_type(Some(&cmp_ty))?);
                 self.consume(&Token::Comma)?;
 
                 // Parse new type and value
                 let new_ty = self.parse_type()?;
-                let _new = self.parse_value()?;
+                operands.push(self.parse_value_with_type(Some(&new_ty))?);
 
                 // Validate that compare value and new value types match
                 if cmp_ty != new_ty {
@@ -2128,7 +3281,7 @@ impl Parser {
                         Token::Xor => "xor",
                         Token::FAdd => "fadd",
                         Token::FSub => "fsub",
-                        Token::Identifier(s) => s.as_str(),
+                        Token::Identifier(s) => &**s,
                         _ => "",
                     };
                     self.advance();
@@ -2136,15 +3289,16 @@ impl Parser {
                 } else {
                     return Err(ParseError::UnexpectedEOF);
                 };
+                self.rmw_operation = AtomicRMWBinOp::from_name(&operation);
 
                 // Parse pointer type and value
-                let _ptr_ty = self.parse_type()?;
-                let _ptr = self.parse_value()?;
+                let ptr_ty = self.parse_type()?;
+                operands.push(self.parse_value_with_type(Some(&ptr_ty))?);
                 self.consume(&Token::Comma)?;
 
                 // Parse value type and value
                 let val_ty = self.parse_type()?;
-                let _val = self.parse_value()?;
+                operands.push(self.parse_value_with_type(Some(&val_ty))?);
 
                 // Validate operand type for atomic RMW operation
                 match operation.as_str() {
@@ -2219,15 +3373,15 @@ impl Parser {
             }
             Opcode::VAArg => {
                 // va_arg ptr_type ptr_val, result_type
-                let _ptr_ty = self.parse_type()?;
-                let _ptr_val = self.parse_value()?;
+                let ptr_ty = self.parse_type()?;
+                operands.push(self.parse_value_with_type(Some(&ptr_ty))?);
                 self.consume(&Token::Comma)?;
                 let result_ty = self.parse_type()?;
                 result_type = Some(result_ty);  // va_arg result is the specified type
             }
             Opcode::Invoke => {
                 // invoke [cc] [attrs] type @func(args...) to label %normal unwind label %exception
-                self.skip_linkage_and_visibility();
+                self.call_calling_convention = Some(self.parse_calling_convention());
                 self.skip_attributes();
 
                 let ret_ty = self.parse_type()?;
@@ -2317,8 +3471,11 @@ impl Parser {
                 let mask = self.parse_value_with_type(Some(&mask_ty))?;
                 operands.push(mask);
 
-                // Result type is same as first vector type
-                result_type = Some(vec1_ty);
+                // Result has the element type of the inputs and the length of the mask
+                result_type = match (vec1_ty.vector_info(), mask_ty.vector_info()) {
+                    (Some((elem_ty, _)), Some((_, size))) => Some(self.context.vector_type(elem_ty.clone(), size)),
+                    _ => Some(vec1_ty),
+                };
             }
             Opcode::ExtractValue => {
                 // extractvalue <aggregate type> %agg, <idx>...
@@ -2718,8 +3875,9 @@ impl Parser {
         Ok((operands, result_type, gep_source_type_field, alignment, is_atomic, operand_bundles))
     }
 
-    fn parse_comparison_predicate(&mut self) -> ParseResult<()> {
+    fn parse_comparison_predicate(&mut self) -> ParseResult<BorrowedToken<'a>> {
         // Parse comparison predicate (eq, ne, ugt, etc.)
+        let token = self.peek().cloned().ok_or(ParseError::UnexpectedEOF)?;
         if self.match_token(&Token::Eq) || self.match_token(&Token::Ne) ||
            self.match_token(&Token::Ugt) || self.match_token(&Token::Uge) ||
            self.match_token(&Token::Ult) || self.match_token(&Token::Ule) ||
@@ -2732,7 +3890,7 @@ impl Parser {
            self.match_token(&Token::Une) || self.match_token(&Token::Ueq) ||
            self.match_token(&Token::True) ||
            self.match_token(&Token::False) {
-            Ok(())
+            Ok(token)
         } else {
             Err(ParseError::InvalidSyntax {
                 message: "Expected comparison predicate".to_string(),
@@ -2750,7 +3908,7 @@ impl Parser {
 
         // Case 1: Token::MetadataIdent - lexer already combined ! with identifier/number
         if let Some(Token::MetadataIdent(ref name)) = self.peek() {
-            metadata_name = Some(name.clone());
+            metadata_name = Some(name.to_string());
             self.advance();
             // Check if followed by parentheses like !DIExpression(...)
             if self.check(&Token::LParen) {
@@ -2784,7 +3942,7 @@ impl Parser {
                 return None;
             } else if let Some(Token::Identifier(ref name)) = self.peek() {
                 // !DIExpression() - when lexer didn't combine them
-                metadata_name = Some(name.clone());
+                metadata_name = Some(name.to_string());
                 self.advance();
                 if self.check(&Token::LParen) {
                     self.advance();
@@ -2817,9 +3975,12 @@ impl Parser {
     fn parse_metadata_node(&mut self) -> ParseResult<crate::metadata::Metadata> {
         use crate::metadata::Metadata;
 
+        // `distinct` only affects uniquing
+        self.match_token(&Token::Distinct);
+
         // Case 1: MetadataIdent - reference to numbered or named metadata
         if let Some(Token::MetadataIdent(ref name)) = self.peek() {
-            let md_name = name.clone();
+            let md_name = name.to_string();
             self.advance();
 
             // Check if it's a reference to existing metadata (!0, !1, etc.)
@@ -2843,9 +4004,16 @@ impl Parser {
                 let mut has_named_fields = false;
 
                 while !self.check(&Token::RParen) && !self.is_at_end() {
+                    // Field names that are also reserved words (`name:`, `file:`, ...) lex as keywords
+                    let keyword_field = match self.peek() {
+                        Some(token) if self.peek_ahead(1) == Some(&Token::Colon) => token.keyword_spelling().map(str::to_string),
+                        _ => None,
+                    };
                     // Check for key: value pairs or standalone identifiers
-                    if let Some(Token::Identifier(field_name)) = self.peek() {
-                        let field_name = field_name.clone();
+                    if let Some(field_name) = keyword_field.or_else(|| match self.peek() {
+                        Some(Token::Identifier(name)) => Some(name.to_string()),
+                        _ => None,
+                    }) {
                         self.advance(); // consume field name
 
                         if self.match_token(&Token::Colon) {
@@ -2861,10 +4029,27 @@ impl Parser {
                                 fields.insert(field_name, inner.clone());
                                 operands.push(inner);
                             } else if let Some(Token::StringLit(s)) = self.peek() {
-                                let s = s.clone();
+                                let s = s.to_string();
                                 fields.insert(field_name, Metadata::string(s.clone()));
                                 operands.push(Metadata::string(s));
                                 self.advance();
+                            } else if let Some(flag @ (Token::True | Token::False)) = self.peek() {
+                                let value = Metadata::int((*flag == Token::True) as i64);
+                                fields.insert(field_name, value.clone());
+                                operands.push(value);
+                                self.advance();
+                            } else if let Some(Token::Identifier(first)) = self.peek() {
+                                // Enumerators and flag sets: DW_TAG_array_type, DIFlagPublic | DIFlagStaticMember
+                                let mut spelling = first.to_string();
+                                self.advance();
+                                while self.check(&Token::Pipe) {
+                                    let Some(Token::Identifier(flag)) = self.peek_ahead(1).cloned() else { break };
+                                    spelling = format!("{} | {}", spelling, flag);
+                                    self.advance();
+                                    self.advance();
+                                }
+                                fields.insert(field_name, Metadata::string(spelling.clone()));
+                                operands.push(Metadata::string(spelling));
                             } else {
                                 self.advance(); // skip unknown value
                             }
@@ -2876,6 +4061,10 @@ impl Parser {
                         // Positional metadata argument (no field name)
                         let inner = self.parse_metadata_node()?;
                         operands.push(inner);
+                    } else if let Some(Token::Integer(n)) = self.peek() {
+                        // Positional integer, e.g. the arguments of DW_OP_plus_uconst
+                        operands.push(Metadata::int(*n as i64));
+                        self.advance();
                     } else if !self.check(&Token::Comma) && !self.check(&Token::RParen) {
                         // Unknown token - skip to avoid infinite loop
                         self.advance();
@@ -2921,7 +4110,7 @@ impl Parser {
                         elements.push(Metadata::int(*n as i64));
                         self.advance();
                     } else if let Some(Token::StringLit(s)) = self.peek() {
-                        elements.push(Metadata::string(s.clone()));
+                        elements.push(Metadata::string(s.to_string()));
                         self.advance();
                     } else if self.match_token(&Token::Null) {
                         // null in metadata
@@ -2942,7 +4131,7 @@ impl Parser {
 
             // Case 2b: !"string" - string metadata
             if let Some(Token::StringLit(s)) = self.peek() {
-                let string = s.clone();
+                let string = s.to_string();
                 self.advance();
                 return Ok(Metadata::string(string));
             }
@@ -2962,7 +4151,7 @@ impl Parser {
 
             // Case 2d: !DILocation(...) when lexer didn't combine
             if let Some(Token::Identifier(name)) = self.peek() {
-                let md_name = name.clone();
+                let md_name = name.to_string();
                 self.advance();
                 if self.check(&Token::LParen) {
                     // Put the identifier back and re-parse as MetadataIdent
@@ -2986,41 +4175,35 @@ impl Parser {
                 break; // End of arguments
             }
 
-            // Handle metadata arguments specially: metadata i32 0 or metadata !{}
+            // Metadata arguments: metadata !0, metadata !DIExpression(), metadata i32 %x
             if self.match_token(&Token::Metadata) {
-                // Check for various metadata forms
-                let metadata_ty;
-                let metadata_val;
-                if let Some(Token::MetadataIdent(_)) = self.peek() {
-                    // metadata !DIExpression() or !0
-                    self.advance(); // consume the metadata ident
-                    // If followed by parens, skip them
-                    if self.check(&Token::LParen) {
-                        self.advance();
-                        let mut depth = 1;
-                        while depth > 0 && !self.is_at_end() {
-                            if self.check(&Token::LParen) {
-                                depth += 1;
-                            } else if self.check(&Token::RParen) {
-                                depth -= 1;
-                            }
-                            self.advance();
+                let metadata_ty = self.context.metadata_type();
+                let metadata_val = if let (Some(Token::Exclaim), Some(Token::StringLit(s))) = (self.peek(), self.peek_ahead(1)) {
+                    // Kept as a string, intrinsics such as llvm.type.test need it
+                    let s = s.to_string();
+                    self.advance();
+                    self.advance();
+                    Value::metadata_string(metadata_ty.clone(), s)
+                } else if matches!(self.peek(), Some(Token::MetadataIdent(name)) if name == "DIArgList") {
+                    self.advance();
+                    self.consume(&Token::LParen)?;
+                    let mut values = Vec::new();
+                    while !self.check(&Token::RParen) && !self.is_at_end() {
+                        let ty = self.parse_type()?;
+                        values.push(crate::metadata::Metadata::value(self.parse_value_with_type(Some(&ty))?));
+                        if !self.match_token(&Token::Comma) {
+                            break;
                         }
                     }
-                    metadata_ty = self.context.metadata_type();
-                    metadata_val = Value::undef(metadata_ty.clone());
-                } else if self.check(&Token::Exclaim) {
-                    // metadata !{} - literal metadata
-                    self.skip_metadata();
-                    metadata_ty = self.context.metadata_type();
-                    metadata_val = Value::undef(metadata_ty.clone());
+                    self.consume(&Token::RParen)?;
+                    Value::metadata(metadata_ty.clone(), crate::metadata::Metadata::named("DIArgList".to_string(), values))
+                } else if self.is_metadata_token() {
+                    Value::metadata(metadata_ty.clone(), self.parse_metadata_node()?)
                 } else {
-                    // metadata i32 0 - parse type and value
-                    metadata_ty = self.context.metadata_type();
-                    let _ty = self.parse_type()?;
-                    let _val = self.parse_value()?;
-                    metadata_val = Value::undef(metadata_ty.clone());
-                }
+                    let ty = self.parse_type()?;
+                    let value = self.parse_value_with_type(Some(&ty))?;
+                    Value::metadata(metadata_ty.clone(), crate::metadata::Metadata::value(value))
+                };
                 // Add metadata as an argument so verifier sees correct arg count
                 args.push((metadata_ty, metadata_val));
                 if !self.match_token(&Token::Comma) {
@@ -3152,7 +4335,7 @@ impl Parser {
 
                 // Handle identifier-based attributes with type parameters: byref(type), elementtype(type), nofpclass(...), preallocated(type), range(type low, high)
                 if let Some(Token::Identifier(attr)) = self.peek() {
-                    if matches!(attr.as_str(), "byref" | "elementtype" | "nofpclass" | "preallocated" | "range") {
+                    if matches!(attr.as_ref(), "byref" | "elementtype" | "nofpclass" | "preallocated" | "range") {
                         self.advance();
                         if self.check(&Token::LParen) {
                             self.advance();
@@ -3176,7 +4359,7 @@ impl Parser {
 
                 // Handle identifier-based attributes
                 if let Some(Token::Identifier(attr)) = self.peek() {
-                    if matches!(attr.as_str(), "noundef" | "nonnull" | "readonly" | "writeonly" |
+                    if matches!(attr.as_ref(), "noundef" | "nonnull" | "readonly" | "writeonly" |
                                               "allocalign" | "allocsize" | "returned") {
                         self.advance();
                         continue;
@@ -3252,7 +4435,7 @@ impl Parser {
         while !self.check(&Token::RBracket) && !self.is_at_end() {
             // Parse bundle tag (string literal)
             let tag = if let Some(Token::StringLit(s)) = self.peek() {
-                let tag_str = s.clone();
+                let tag_str = s.to_string();
                 self.advance();
                 tag_str
             } else {
@@ -3358,7 +4541,7 @@ impl Parser {
                             val
                         } else if let Some(Token::StringLit(s)) = self.peek() {
                             // Symbolic address space: map to number
-                            let val = match s.as_str() {
+                            let val = match &**s {
                                 "A" => 1,
                                 "G" => 2,
                                 "P" => 3,
@@ -3404,7 +4587,7 @@ impl Parser {
                 self.advance(); // consume 'target'
                 self.consume(&Token::LParen)?;
                 let type_name = if let Some(Token::StringLit(name)) = self.peek() {
-                    let name = name.clone();
+                    let name = name.to_string();
                     self.advance(); // consume type name string
                     name
                 } else {
@@ -3515,15 +4698,9 @@ impl Parser {
             }
             Token::LocalIdent(name) => {
                 // Type reference like %TypeName or %0
-                let name = name.clone();
+                let name = name.to_string();
                 self.advance();
-                // Look up in type table
-                if let Some(ty) = self.type_table.get(&name) {
-                    Ok(ty.clone())
-                } else {
-                    // Type not found - treat as opaque type placeholder (use i8 to ensure it's sized for alloca)
-                    Ok(self.context.int8_type())
-                }
+                self.named_type(&name)
             }
             Token::Ellipsis => {
                 // Ellipsis for varargs - should be caught before parse_type() is called
@@ -3593,7 +4770,7 @@ impl Parser {
                     val
                 } else if let Some(Token::StringLit(s)) = self.peek() {
                     // Symbolic address space: map to number
-                    let val = match s.as_str() {
+                    let val = match &**s {
                         "A" => 1,
                         "G" => 2,
                         "P" => 3,
@@ -3641,7 +4818,7 @@ impl Parser {
 
                 // Skip optional keywords
                 while let Some(Token::Identifier(kw)) = self.peek() {
-                    if matches!(kw.as_str(), "volatile" | "sideeffect" | "alignstack" | "inteldialect") {
+                    if matches!(kw.as_ref(), "volatile" | "sideeffect" | "alignstack" | "inteldialect") {
                         self.advance();
                     } else {
                         break;
@@ -3708,13 +4885,23 @@ impl Parser {
                 self.consume(&Token::LParen)?;
                 let func = self.parse_value()?; // @func
                 self.consume(&Token::Comma)?;
-                let block = self.parse_value()?; // %block
+                let block = if self.constant_scope.is_some() {
+                    self.parse_block_address_target(&func)?
+                } else {
+                    self.parse_value()? // %block
+                };
                 self.consume(&Token::RParen)?;
                 let ty = expected_type.cloned().unwrap_or_else(|| self.context.ptr_type(self.context.int8_type()));
                 Ok(Value::block_address(ty, func, block))
             }
             Token::LocalIdent(name) => {
-                let name = name.clone();
+                let name = name.to_string();
+                if self.constant_scope.is_some() {
+                    return Err(ParseError::InvalidSyntax {
+                        message: format!("constant cannot reference local value '%{}'", name),
+                        position: self.current,
+                    });
+                }
                 self.advance();
                 // Look up in symbol table first
                 if let Some(value) = self.symbol_table.get(&name) {
@@ -3728,7 +4915,17 @@ impl Parser {
                 }
             }
             Token::GlobalIdent(name) => {
-                let name = name.clone();
+                let name = name.to_string();
+                if let Some(module) = &self.constant_scope {
+                    if module.get_global(&name).is_none()
+                        && module.get_function(&name).is_none()
+                        && module.get_alias(&name).is_none() {
+                        return Err(ParseError::InvalidSyntax {
+                            message: format!("use of undefined value '@{}'", name),
+                            position: self.current,
+                        });
+                    }
+                }
                 self.advance();
                 // Use expected type if provided, otherwise look up function declaration, otherwise default to ptr
                 let ty = if let Some(expected) = expected_type {
@@ -3796,7 +4993,9 @@ impl Parser {
             }
             Token::Null => {
                 self.advance();
-                Ok(Value::const_null(self.context.ptr_type(self.context.int8_type())))
+                let ty = expected_type.filter(|ty| ty.is_pointer()).cloned()
+                    .unwrap_or_else(|| self.context.ptr_type(self.context.int8_type()));
+                Ok(Value::const_null(ty))
             }
             Token::CString(bytes) => {
                 let bytes = bytes.clone();
@@ -3853,18 +5052,21 @@ impl Parser {
                     Ok(Value::zero_initializer(ty))
                 } else {
                     // Vector constant
+                    let mut elements = Vec::new();
                     while !self.check(&Token::RAngle) && !self.is_at_end() {
                         // Parse element type and value
-                        let _elem_ty = self.parse_type()?;
-                        let _elem_val = self.parse_value()?;
+                        let elem_ty = self.parse_type()?;
+                        elements.push(self.parse_value_with_type(Some(&elem_ty))?);
                         if !self.match_token(&Token::Comma) {
                             break;
                         }
                     }
                     self.consume(&Token::RAngle)?;
-                    // Return placeholder vector constant with expected type
-                    let ty = expected_type.cloned().unwrap_or_else(|| self.context.void_type());
-                    Ok(Value::zero_initializer(ty))
+                    match expected_type {
+                        Some(ty) if ty.is_vector() => Ok(Value::const_vector(ty.clone(), elements)),
+                        // Placeholder when the vector type is unknown
+                        _ => Ok(Value::zero_initializer(self.context.void_type())),
+                    }
                 }
             }
             Token::LBracket => {
@@ -4077,13 +5279,14 @@ impl Parser {
         // For select: select (type cond, type val1, type val2)
 
         let mut result_type: Option<Type> = None;
+        let mut operands = Vec::new();
 
         if matches!(opcode, Opcode::GetElementPtr) {
             // GEP is special: getelementptr (basetype, ptrtype ptrvalue, indextype indexvalue, ...)
             let _base_ty = self.parse_type()?;
             self.consume(&Token::Comma)?;
             let ptr_ty = self.parse_type()?;
-            let _ptr_val = self.parse_value()?;
+            operands.push(self.parse_value_with_type(Some(&ptr_ty))?);
 
             // Determine result type: ptr or <N x ptr> if any index is a vector
             let mut vector_size = None;
@@ -4103,7 +5306,7 @@ impl Parser {
                     vector_size = idx_ty.vector_info().map(|(_, size)| size);
                 }
 
-                let _idx_val = self.parse_value()?;
+                operands.push(self.parse_value_with_type(Some(&idx_ty))?);
             }
 
             // Set result type based on whether we found a vector
@@ -4119,7 +5322,7 @@ impl Parser {
             // Simplified parsing - just parse type and value, skip to closing paren
             // This allows the constant expression to be recognized without full semantic support
             let src_ty = self.parse_type()?;
-            let _src_val = self.parse_value()?;
+            operands.push(self.parse_value_with_type(Some(&src_ty))?);
 
             // Handle 'to' keyword for casts - destination type is the result type
             if matches!(opcode, Opcode::PtrToInt | Opcode::IntToPtr | Opcode::PtrToAddr | Opcode::AddrToPtr |
@@ -4200,11 +5403,11 @@ impl Parser {
                 // Result type is the value type (second argument)
                 if self.match_token(&Token::Comma) {
                     let val_ty = self.parse_type()?;
+                    operands.push(self.parse_value_with_type(Some(&val_ty))?);
                     result_type = Some(val_ty);  // Select result is value type
-                    let _val2 = self.parse_value()?;
                     if self.match_token(&Token::Comma) {
-                        let _ty3 = self.parse_type()?;
-                        let _val3 = self.parse_value()?;
+                        let ty3 = self.parse_type()?;
+                        operands.push(self.parse_value_with_type(Some(&ty3))?);
                     }
                 }
             } else if matches!(opcode, Opcode::ShuffleVector) {
@@ -4212,11 +5415,11 @@ impl Parser {
                 // Result type is the first vector type
                 result_type = Some(src_ty.clone());
                 if self.match_token(&Token::Comma) {
-                    let _ty2 = self.parse_type()?;
-                    let _val2 = self.parse_value()?;
+                    let ty2 = self.parse_type()?;
+                    operands.push(self.parse_value_with_type(Some(&ty2))?);
                     if self.match_token(&Token::Comma) {
-                        let _ty3 = self.parse_type()?;
-                        let _val3 = self.parse_value()?;
+                        let ty3 = self.parse_type()?;
+                        operands.push(self.parse_value_with_type(Some(&ty3))?);
                     }
                 }
             } else if matches!(opcode, Opcode::InsertElement) {
@@ -4224,11 +5427,11 @@ impl Parser {
                 // Result type is the vector type
                 result_type = Some(src_ty.clone());
                 if self.match_token(&Token::Comma) {
-                    let _ty2 = self.parse_type()?;
-                    let _val2 = self.parse_value()?;
+                    let ty2 = self.parse_type()?;
+                    operands.push(self.parse_value_with_type(Some(&ty2))?);
                     if self.match_token(&Token::Comma) {
-                        let _ty3 = self.parse_type()?;
-                        let _val3 = self.parse_value()?;
+                        let ty3 = self.parse_type()?;
+                        operands.push(self.parse_value_with_type(Some(&ty3))?);
                     }
                 }
             } else {
@@ -4236,8 +5439,8 @@ impl Parser {
                 result_type = Some(src_ty.clone());
                 // Parse second operand if comma present
                 if self.match_token(&Token::Comma) {
-                    let _ty2 = self.parse_type()?;
-                    let _val2 = self.parse_value()?;
+                    let ty2 = self.parse_type()?;
+                    operands.push(self.parse_value_with_type(Some(&ty2))?);
                 }
             }
         }
@@ -4246,7 +5449,7 @@ impl Parser {
 
         // Return a constant expression value with the correct result type
         let ty = result_type.unwrap_or_else(|| self.context.void_type());
-        Ok(Value::instruction(ty, opcode, Some("constexpr".to_string())))
+        Ok(Value::const_expr(ty, opcode, operands))
     }
 
     fn parse_parameters(&mut self) -> ParseResult<(Vec<(Type, String)>, Vec<crate::function::ParameterAttributes>, bool)> {
@@ -4269,7 +5472,7 @@ impl Parser {
 
             let name = if let Some(Token::LocalIdent(n)) = self.peek().cloned() {
                 self.advance();
-                n
+                n.into_owned()
             } else {
                 format!("arg{}", params.len())
             };
@@ -4341,7 +5544,7 @@ impl Parser {
             // Check identifier-based calling conventions
             if let Some(Token::Identifier(id)) = self.peek() {
                 let cc_name = id.clone();
-                let cc_opt = match cc_name.as_str() {
+                let cc_opt = match cc_name.as_ref() {
                     "ccc" => Some(CallingConvention::C),
                     "fastcc" => Some(CallingConvention::Fast),
                     "coldcc" => Some(CallingConvention::Cold),
@@ -4665,13 +5868,13 @@ impl Parser {
 
             // Handle identifier-based attributes (noundef, etc.)
             if let Some(Token::Identifier(attr)) = self.peek() {
-                if matches!(attr.as_str(), "noundef") {
+                if matches!(attr.as_ref(), "noundef") {
                     self.advance();
                     attrs.noundef = true;
                     continue;
                 }
                 // Handle identifier-based attributes with parameters: nofpclass(...), range(...), etc.
-                if matches!(attr.as_str(), "nofpclass" | "range") {
+                if matches!(attr.as_ref(), "nofpclass" | "range") {
                     self.advance();
                     if self.check(&Token::LParen) {
                         self.advance(); // consume (
@@ -4760,7 +5963,7 @@ impl Parser {
 
             // Handle identifier-based attributes (noundef, nonnull, nofpclass, range, etc.)
             if let Some(Token::Identifier(attr)) = self.peek() {
-                if matches!(attr.as_str(), "noundef" | "nonnull" | "readonly" | "writeonly" |
+                if matches!(attr.as_ref(), "noundef" | "nonnull" | "readonly" | "writeonly" |
                                           "readnone" | "returned" | "noreturn" | "nounwind" |
                                           "allocalign" | "allocsize" | "initializes" | "nofpclass" | "range") {
                     self.advance();
@@ -4840,7 +6043,7 @@ impl Parser {
 
             // Handle identifier-based attributes with type parameters: byref(type), elementtype(type), preallocated(type), range(type val, val), nofpclass(...), captures(...)
             if let Some(Token::Identifier(attr)) = self.peek() {
-                if matches!(attr.as_str(), "byref" | "elementtype" | "preallocated" | "range" | "nofpclass" | "captures") {
+                if matches!(attr.as_ref(), "byref" | "elementtype" | "preallocated" | "range" | "nofpclass" | "captures") {
                     self.advance();
                     if self.check(&Token::LParen) {
                         self.advance(); // consume (
@@ -5220,7 +6423,7 @@ impl Parser {
 
             // Handle identifier-based attributes with parameters
             if let Some(Token::Identifier(attr)) = self.peek() {
-                if matches!(attr.as_str(), "byref" | "elementtype" | "preallocated" | "range" | "nofpclass" | "captures") {
+                if matches!(attr.as_ref(), "byref" | "elementtype" | "preallocated" | "range" | "nofpclass" | "captures") {
                     self.advance();
                     if self.check(&Token::LParen) {
                         self.advance(); // consume (
@@ -5305,7 +6508,7 @@ impl Parser {
                     if self.check(&Token::LParen) {
                         self.advance(); // consume (
                         if let Some(Token::Identifier(kind)) = self.peek() {
-                            let kind_str = kind.as_str();
+                            let kind_str = kind.as_ref();
                             if kind_str != "sync" && kind_str != "async" {
                                 return Err(ParseError::InvalidSyntax {
                                     message: "expected unwind table kind".to_string(),
@@ -5352,9 +6555,12 @@ impl Parser {
                         continue;
                     }
 
-                    // Handle metadata
-                    if self.is_metadata_token() {
-                        self.skip_metadata();
+                    // Metadata attachments, keeping `!dbg` as the subprogram. A declaration
+                    // may be followed directly by the module's `!0 = ...` definitions.
+                    if self.is_metadata_token() && !self.at_metadata_definition() {
+                        if self.skip_metadata().as_deref() == Some("dbg") && self.is_metadata_token() {
+                            self.function_subprogram = self.parse_metadata_node().ok();
+                        }
                         continue;
                     }
 
@@ -5373,7 +6579,7 @@ impl Parser {
                     // Handle identifier-based attributes
                     if let Some(Token::Identifier(attr)) = self.peek().cloned() {
                         // Check for parameter-only attributes that cannot be used on functions
-                        if matches!(attr.as_str(), "byref" | "byval" | "inalloca" | "sret" | "nest" | "nocapture" | "returned" | "swiftself" | "swifterror" | "swiftasync") {
+                        if matches!(attr.as_ref(), "byref" | "byval" | "inalloca" | "sret" | "nest" | "nocapture" | "returned" | "swiftself" | "swifterror" | "swiftasync") {
                             // byref specifically requires (type) syntax
                             if attr == "byref" {
                                 self.advance(); // consume 'byref'
@@ -5480,11 +6686,11 @@ impl Parser {
                             continue;
                         }
 
-                        if matches!(attr.as_str(), "memory" | "convergent" | "inaccessiblememonly" |
+                        if matches!(attr.as_ref(), "memory" | "convergent" | "inaccessiblememonly" |
                                                   "null_pointer_is_valid" | "optforfuzzing" | "presplitcoroutine" |
                                                   "sanitize_address_dyninit" | "allocptr" |
                                                   "alloc-family" | "fn_ret_thunk_extern") {
-                            attrs.other_attributes.push(attr.clone());
+                            attrs.other_attributes.push(attr.to_string());
                             self.advance();
                             // Some have parameters
                             if self.check(&Token::LParen) {
@@ -5503,15 +6709,15 @@ impl Parser {
                         self.advance();
                         if self.match_token(&Token::Equal) {
                             if let Some(Token::StringLit(value)) = self.peek().cloned() {
-                                attrs.string_attributes.insert(key, value);
+                                attrs.string_attributes.insert(key.into_owned(), value.into_owned());
                                 self.advance();
                             } else {
                                 // No value, store empty string
-                                attrs.string_attributes.insert(key, String::new());
+                                attrs.string_attributes.insert(key.into_owned(), String::new());
                             }
                         } else {
                             // Key without value
-                            attrs.string_attributes.insert(key, String::new());
+                            attrs.string_attributes.insert(key.into_owned(), String::new());
                         }
                         continue;
                     }
@@ -5605,7 +6811,7 @@ impl Parser {
 
             // Skip identifier-based attributes (memory(...), vscale_range(...), etc.)
             if let Some(Token::Identifier(attr)) = self.peek() {
-                if matches!(attr.as_str(), "memory" | "convergent" | "inaccessiblememonly" |
+                if matches!(attr.as_ref(), "memory" | "convergent" | "inaccessiblememonly" |
                                           "null_pointer_is_valid" | "optforfuzzing" | "presplitcoroutine" |
                                           "sanitize_address_dyninit" | "allockind" | "allocptr" |
                                           "alloc-family" | "fn_ret_thunk_extern") {
@@ -5663,7 +6869,7 @@ impl Parser {
 
             // Fast-math flags and other identifier-based flags (identifiers)
             if let Some(Token::Identifier(id)) = self.peek() {
-                if matches!(id.as_str(), "fast" | "nnan" | "ninf" | "nsz" | "arcp" |
+                if matches!(id.as_ref(), "fast" | "nnan" | "ninf" | "nsz" | "arcp" |
                                          "contract" | "afn" | "reassoc" | "nneg" | "disjoint" | "samesign" | "nusw") {
                     self.advance();
                     continue;
@@ -5692,7 +6898,7 @@ impl Parser {
 
             // Identifier-based attributes
             if let Some(Token::Identifier(attr)) = self.peek() {
-                if matches!(attr.as_str(), "readonly" | "writeonly" | "readnone" |
+                if matches!(attr.as_ref(), "readonly" | "writeonly" | "readnone" |
                                            "nocapture" | "noinline" | "alwaysinline" |
                                            "cold" | "hot" | "convergent" | "speculatable") {
                     self.advance();
@@ -5862,7 +7068,7 @@ impl Parser {
 
     fn peek_global_ident(&self) -> Option<String> {
         if let Some(Token::GlobalIdent(name)) = self.peek() {
-            Some(name.clone())
+            Some(name.to_string())
         } else {
             None
         }
@@ -5871,7 +7077,7 @@ impl Parser {
     fn expect_global_ident(&mut self) -> ParseResult<String> {
         if let Some(Token::GlobalIdent(name)) = self.peek().cloned() {
             self.advance();
-            Ok(name)
+            Ok(name.into_owned())
         } else {
             Err(ParseError::InvalidSyntax {
                 message: "Expected global identifier".to_string(),
@@ -5883,7 +7089,7 @@ impl Parser {
     fn expect_local_ident(&mut self) -> ParseResult<String> {
         if let Some(Token::LocalIdent(name)) = self.peek().cloned() {
             self.advance();
-            Ok(name)
+            Ok(name.into_owned())
         } else {
             Err(ParseError::InvalidSyntax {
                 message: "Expected local identifier".to_string(),
@@ -5894,26 +7100,47 @@ impl Parser {
 
     // Token manipulation helpers
 
-    fn peek(&self) -> Option<&Token> {
-        self.tokens.get(self.current)
+    fn peek(&self) -> Option<&BorrowedToken<'a>> {
+        self.tokens.get(self.current).map(|(token, _)| token)
+    }
+
+    /// Look `n` tokens past the current one, up to `LOOKAHEAD`
+    fn peek_ahead(&self, n: usize) -> Option<&BorrowedToken<'a>> {
+        debug_assert!(n <= LOOKAHEAD, "peeking {} tokens ahead", n);
+        self.tokens.get(self.current + n).map(|(token, _)| token)
     }
 
-    fn peek_ahead(&self, n: usize) -> Option<&Token> {
-        self.tokens.get(self.current + n)
+    /// Look `n` tokens past the current one, lexing beyond `LOOKAHEAD` for
+    /// scans whose length depends on the input
+    fn scan_ahead(&mut self, n: usize) -> Option<&BorrowedToken<'a>> {
+        self.tokens.fill(self.current + n);
+        self.tokens.get(self.current + n).map(|(token, _)| token)
     }
 
-    fn advance(&mut self) -> Option<&Token> {
+    /// Location of the current token
+    fn span(&self) -> Span {
+        self.tokens.get(self.current).map(|(_, span)| *span).unwrap_or_default()
+    }
+
+    fn advance(&mut self) -> Option<&BorrowedToken<'a>> {
         if !self.is_at_end() {
             self.current += 1;
+            self.tokens.fill(self.current + LOOKAHEAD);
         }
-        self.tokens.get(self.current - 1)
+        self.tokens.get(self.current.checked_sub(1)?).map(|(token, _)| token)
+    }
+
+    /// Continue with the tokens of `source` from `span` on
+    fn resume_at(&mut self, source: &'a str, span: Span) {
+        self.tokens = TokenStream::new(Lexer::resume(source, span));
+        self.current = 0;
     }
 
     fn is_at_end(&self) -> bool {
         matches!(self.peek(), Some(Token::EOF) | None)
     }
 
-    fn match_token(&mut self, token: &Token) -> bool {
+    fn match_token(&mut self, token: &BorrowedToken) -> bool {
         if self.check(token) {
             self.advance();
             true
@@ -5922,13 +7149,23 @@ impl Parser {
         }
     }
 
-    fn check(&self, token: &Token) -> bool {
+    fn check(&self, token: &BorrowedToken) -> bool {
         if self.is_at_end() {
             return false;
         }
         std::mem::discriminant(self.peek().unwrap()) == std::mem::discriminant(token)
     }
 
+    /// Whether the current tokens start a metadata definition (`!0 = ...`)
+    fn at_metadata_definition(&self) -> bool {
+        match self.peek() {
+            Some(Token::MetadataIdent(_)) => self.peek_ahead(1) == Some(&Token::Equal),
+            Some(Token::Exclaim) => matches!(self.peek_ahead(1), Some(Token::Integer(_)))
+                && self.peek_ahead(2) == Some(&Token::Equal),
+            _ => false,
+        }
+    }
+
     fn is_metadata_token(&self) -> bool {
         // Check if current token is a metadata token
         // Either Token::Exclaim or Token::MetadataIdent
@@ -5938,7 +7175,7 @@ impl Parser {
         matches!(self.peek(), Some(Token::Exclaim) | Some(Token::MetadataIdent(_)))
     }
 
-    fn consume(&mut self, token: &Token) -> ParseResult<()> {
+    fn consume(&mut self, token: &BorrowedToken) -> ParseResult<()> {
         if self.check(token) {
             self.advance();
             Ok(())
@@ -5952,6 +7189,67 @@ impl Parser {
     }
 }
 
+fn int_predicate(token: &BorrowedToken) -> Option<IntPredicate> {
+    Some(match token {
+        Token::Eq => IntPredicate::EQ,
+        Token::Ne => IntPredicate::NE,
+        Token::Ugt => IntPredicate::UGT,
+        Token::Uge => IntPredicate::UGE,
+        Token::Ult => IntPredicate::ULT,
+        Token::Ule => IntPredicate::ULE,
+        Token::Sgt => IntPredicate::SGT,
+        Token::Sge => IntPredicate::SGE,
+        Token::Slt => IntPredicate::SLT,
+        Token::Sle => IntPredicate::SLE,
+        _ => return None,
+    })
+}
+
+fn float_predicate(token: &BorrowedToken) -> Option<FloatPredicate> {
+    Some(match token {
+        Token::False => FloatPredicate::FALSE,
+        Token::Oeq => FloatPredicate::OEQ,
+        Token::Ogt => FloatPredicate::OGT,
+        Token::Oge => FloatPredicate::OGE,
+        Token::Olt => FloatPredicate::OLT,
+        Token::Ole => FloatPredicate::OLE,
+        Token::One => FloatPredicate::ONE,
+        Token::Ord => FloatPredicate::ORD,
+        Token::Uno => FloatPredicate::UNO,
+        Token::Ueq => FloatPredicate::UEQ,
+        Token::Ugt => FloatPredicate::UGT,
+        Token::Uge => FloatPredicate::UGE,
+        Token::Ult => FloatPredicate::ULT,
+        Token::Ule => FloatPredicate::ULE,
+        Token::Une => FloatPredicate::UNE,
+        Token::True => FloatPredicate::TRUE,
+        _ => return None,
+    })
+}
+
+/// Parse a module from a string, deferring function bodies until they are accessed.
+///
+/// Unlike [`parse`], the module is not verified, since that would materialize
+/// every body. The module keeps `source` alive and re-lexes each body from
+/// it when the body is first accessed.
+pub fn parse_lazy(source: Arc<str>, context: Context) -> ParseResult<Module> {
+    let mut parser = Parser::new(context);
+    parser.parse_module_lazy(&source)
+}
+
+/// Parse a single type such as `{ i32, ptr }` or `<4 x float>`.
+///
+/// No named types are in scope; use [`Parser::parse_type_fragment`] on a
+/// parser that has seen the module to resolve `%name` types.
+pub fn parse_type(context: &Context, source: &str) -> ParseResult<Type> {
+    Parser::new(context.clone()).parse_type_fragment(source)
+}
+
+/// Parse a typed constant against an existing module; see [`Parser::parse_constant_in`]
+pub fn parse_constant(module: &Module, source: &str) -> ParseResult<Value> {
+    Parser::new(module.context()).parse_constant_in(module, source)
+}
+
 /// Parse a module from a string
 pub fn parse(source: &str, context: Context) -> ParseResult<Module> {
     let mut parser = Parser::new(context);
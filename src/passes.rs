@@ -5,8 +5,11 @@
 
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex, Once};
+use crate::analysis_manager::{AnalysisManager, PreservedAnalyses};
+use crate::call_graph::{CallGraph, CallGraphSCC};
 use crate::module::Module;
 use crate::function::Function;
+use crate::verification::{verify_function, verify_module, VerificationError, Verifier};
 
 /// Result of a pass execution
 pub type PassResult<T> = Result<T, PassError>;
@@ -20,6 +23,14 @@ pub enum PassError {
     PrerequisitesNotMet(Vec<String>),
     /// Invalid IR
     InvalidIR(String),
+    /// The IR no longer verifies after a pass changed it
+    VerificationFailed {
+        /// The pass that changed the IR
+        pass: String,
+        /// The function the pass changed, or `None` for a module pass
+        function: Option<String>,
+        errors: Vec<VerificationError>,
+    },
 }
 
 /// Base trait for all passes
@@ -47,12 +58,41 @@ pub trait Pass {
 pub trait ModulePass: Pass {
     /// Run this pass on a module
     fn run_on_module(&mut self, module: &mut Module) -> PassResult<bool>;
+
+    /// Run this pass with access to cached analyses, returning the ones it
+    /// kept valid. By default this runs `run_on_module` and preserves
+    /// everything if nothing changed, or what `preserved_analyses` names.
+    fn run_with_analyses(&mut self, module: &mut Module, analyses: &mut AnalysisManager) -> PassResult<PreservedAnalyses> {
+        let changed = self.run_on_module(module)?;
+        if !changed || self.preserves_all() {
+            return Ok(PreservedAnalyses::all());
+        }
+        Ok(analyses.preserved_by_name(&self.preserved_analyses()))
+    }
 }
 
 /// A function pass operates on a single function
 pub trait FunctionPass: Pass {
     /// Run this pass on a function
     fn run_on_function(&mut self, function: &mut Function) -> PassResult<bool>;
+
+    /// Run this pass with access to cached analyses, returning the ones it
+    /// kept valid. By default this runs `run_on_function` and preserves
+    /// everything if nothing changed, or what `preserved_analyses` names.
+    fn run_with_analyses(&mut self, function: &mut Function, analyses: &mut AnalysisManager) -> PassResult<PreservedAnalyses> {
+        let changed = self.run_on_function(function)?;
+        if !changed || self.preserves_all() {
+            return Ok(PreservedAnalyses::all());
+        }
+        Ok(analyses.preserved_by_name(&self.preserved_analyses()))
+    }
+}
+
+/// A call graph SCC pass operates on the strongly connected components of
+/// the call graph, callees before callers
+pub trait CallGraphSCCPass: Pass {
+    /// Run this pass on the functions of one component of `call_graph`
+    fn run_on_scc(&mut self, scc: &CallGraphSCC, call_graph: &CallGraph, module: &mut Module) -> PassResult<bool>;
 }
 
 /// An analysis pass produces analysis results
@@ -63,6 +103,14 @@ pub trait AnalysisPass: Pass {
     fn run_analysis(&mut self, function: &Function) -> PassResult<Self::Result>;
 }
 
+/// An analysis pass over a whole module
+pub trait ModuleAnalysisPass: Pass {
+    type Result;
+
+    /// Run the analysis
+    fn run_module_analysis(&mut self, module: &Module) -> PassResult<Self::Result>;
+}
+
 /// Type for pass constructor functions
 type PassConstructor = Arc<dyn Fn() -> Box<dyn FunctionPass> + Send + Sync>;
 
@@ -113,24 +161,52 @@ impl PassRegistry {
 /// Pass manager for running passes
 pub struct PassManager {
     module_passes: Vec<Box<dyn ModulePass>>,
+    scc_passes: Vec<Box<dyn CallGraphSCCPass>>,
     function_passes: Vec<Box<dyn FunctionPass>>,
-    analysis_cache: HashMap<String, Box<dyn std::any::Any>>,
+    analysis_manager: AnalysisManager,
+    verify_each: bool,
 }
 
 impl PassManager {
     pub fn new() -> Self {
         Self {
             module_passes: Vec::new(),
+            scc_passes: Vec::new(),
             function_passes: Vec::new(),
-            analysis_cache: HashMap::new(),
+            analysis_manager: AnalysisManager::new(),
+            verify_each: false,
         }
     }
 
+    /// Re-verify the IR after every pass that reports a change, like
+    /// `opt -verify-each`. A module pass triggers a full verification; a
+    /// function pass only re-verifies the function it changed, plus the
+    /// cheap module-level invariants. The first failure is returned as
+    /// `PassError::VerificationFailed`, naming the pass. The input module
+    /// is assumed to verify.
+    pub fn set_verify_each(&mut self, verify_each: bool) {
+        self.verify_each = verify_each;
+    }
+
+    /// Analyses the passes share, with the results cached so far
+    pub fn analysis_manager(&self) -> &AnalysisManager {
+        &self.analysis_manager
+    }
+
+    pub fn analysis_manager_mut(&mut self) -> &mut AnalysisManager {
+        &mut self.analysis_manager
+    }
+
     /// Add a module pass to the manager
     pub fn add_module_pass(&mut self, pass: Box<dyn ModulePass>) {
         self.module_passes.push(pass);
     }
 
+    /// Add a call graph SCC pass to the manager
+    pub fn add_scc_pass(&mut self, pass: Box<dyn CallGraphSCCPass>) {
+        self.scc_passes.push(pass);
+    }
+
     /// Add a function pass to the manager
     pub fn add_function_pass(&mut self, pass: Box<dyn FunctionPass>) {
         self.function_passes.push(pass);
@@ -216,14 +292,15 @@ impl PassManager {
         Ok(result)
     }
 
-    /// Validate that all pass prerequisites are available
+    /// Validate that all pass prerequisites are available, as passes in
+    /// the list or as registered analyses
     fn validate_prerequisites(&self) -> PassResult<()> {
         let available_passes: std::collections::HashSet<String> =
             self.function_passes.iter().map(|p| p.name().to_string()).collect();
 
         for pass in &self.function_passes {
             for prereq in pass.prerequisites() {
-                if !available_passes.contains(&prereq) {
+                if !available_passes.contains(&prereq) && !self.analysis_manager.is_registered(&prereq) {
                     return Err(PassError::PrerequisitesNotMet(vec![
                         format!("Pass '{}' requires '{}' but it's not in the pass list",
                                 pass.name(), prereq)
@@ -240,15 +317,66 @@ impl PassManager {
         // Validate prerequisites before running
         self.validate_prerequisites()?;
 
+        // Results are cached by function name, so those of another module
+        // would be taken for this one's
+        self.analysis_manager.clear();
+
         // Run module passes
         for pass in &mut self.module_passes {
-            pass.run_on_module(module)?;
+            let preserved = pass.run_with_analyses(module, &mut self.analysis_manager)?;
+            self.analysis_manager.invalidate_module(&preserved);
+            if self.verify_each && !preserved.are_all_preserved() {
+                verify_module(module).map_err(|errors| PassError::VerificationFailed {
+                    pass: pass.name().to_string(),
+                    function: None,
+                    errors,
+                })?;
+            }
+        }
+
+        // Run SCC passes on each component of the call graph, bottom-up.
+        // The components are those of the module before the passes run; a
+        // change rebuilds the graph the later passes see.
+        if !self.scc_passes.is_empty() {
+            let original = CallGraph::new(module);
+            let mut call_graph = original.clone();
+            for scc in original.bottom_up_sccs() {
+                for pass in &mut self.scc_passes {
+                    let scc = remap_scc(&scc, &original, &call_graph);
+                    let changed = pass.run_on_scc(&scc, &call_graph, module)?;
+                    if !changed {
+                        continue;
+                    }
+                    self.analysis_manager.invalidate_module(&PreservedAnalyses::none());
+                    if self.verify_each {
+                        for name in scc.functions(&call_graph) {
+                            Verifier::new().verify_functions(module, std::slice::from_ref(&name))
+                                .map_err(|errors| PassError::VerificationFailed {
+                                    pass: pass.name().to_string(),
+                                    function: Some(name),
+                                    errors,
+                                })?;
+                        }
+                    }
+                    call_graph = CallGraph::new(module);
+                }
+            }
         }
 
         // Run function passes on each function
         for function in module.functions() {
             for pass in &mut self.function_passes {
-                pass.run_on_function(&mut function.clone())?;
+                let preserved = pass.run_with_analyses(&mut function.clone(), &mut self.analysis_manager)?;
+                self.analysis_manager.invalidate(&function.name(), &preserved);
+                if self.verify_each && !preserved.are_all_preserved() {
+                    let name = function.name();
+                    Verifier::new().verify_functions(module, std::slice::from_ref(&name))
+                        .map_err(|errors| PassError::VerificationFailed {
+                            pass: pass.name().to_string(),
+                            function: Some(name),
+                            errors,
+                        })?;
+                }
             }
         }
 
@@ -257,10 +385,22 @@ impl PassManager {
 
     /// Clear the analysis cache
     pub fn clear_analysis_cache(&mut self) {
-        self.analysis_cache.clear();
+        self.analysis_manager.clear();
     }
 }
 
+/// The nodes of `scc`, a component of `original`, in the rebuilt
+/// `call_graph`; functions that no longer exist drop out
+fn remap_scc(scc: &CallGraphSCC, original: &CallGraph, call_graph: &CallGraph) -> CallGraphSCC {
+    let nodes = scc.nodes.iter()
+        .filter_map(|&node| match &original.node(node).function {
+            Some(name) => call_graph.node_index(name),
+            None => Some(node),
+        })
+        .collect();
+    CallGraphSCC { nodes, has_cycle: scc.has_cycle }
+}
+
 impl Default for PassManager {
     fn default() -> Self {
         Self::new()
@@ -270,15 +410,22 @@ impl Default for PassManager {
 /// Function pass manager
 pub struct FunctionPassManager {
     passes: Vec<Box<dyn FunctionPass>>,
+    verify_each: bool,
 }
 
 impl FunctionPassManager {
     pub fn new() -> Self {
         Self {
             passes: Vec::new(),
+            verify_each: false,
         }
     }
 
+    /// Re-verify the function after every pass that reports a change
+    pub fn set_verify_each(&mut self, verify_each: bool) {
+        self.verify_each = verify_each;
+    }
+
     /// Add a pass
     pub fn add_pass(&mut self, pass: Box<dyn FunctionPass>) {
         self.passes.push(pass);
@@ -287,7 +434,14 @@ impl FunctionPassManager {
     /// Run all passes on a function
     pub fn run(&mut self, function: &mut Function) -> PassResult<()> {
         for pass in &mut self.passes {
-            pass.run_on_function(function)?;
+            let changed = pass.run_on_function(function)?;
+            if self.verify_each && changed {
+                verify_function(function).map_err(|errors| PassError::VerificationFailed {
+                    pass: pass.name().to_string(),
+                    function: Some(function.name()),
+                    errors,
+                })?;
+            }
         }
         Ok(())
     }
@@ -29,7 +29,14 @@ impl IRPrinter {
 
         // Print module header
         writeln!(self.output, "; ModuleID = '{}'", module.name()).unwrap();
-        writeln!(self.output, "source_filename = \"{}\"", module.name()).unwrap();
+        let source_filename = module.source_filename().unwrap_or_else(|| module.name());
+        writeln!(self.output, "source_filename = \"{}\"", source_filename).unwrap();
+        if let Some(layout) = module.data_layout() {
+            writeln!(self.output, "target datalayout = \"{}\"", layout).unwrap();
+        }
+        if let Some(triple) = module.target_triple() {
+            writeln!(self.output, "target triple = \"{}\"", triple).unwrap();
+        }
         writeln!(self.output).unwrap();
 
         // Print global variables
@@ -49,6 +56,11 @@ impl IRPrinter {
             self.print_function(function);
         }
 
+        // Print the summary index entries
+        if let Some(index) = module.summary_index() {
+            write!(self.output, "\n{}", index).unwrap();
+        }
+
         self.output.clone()
     }
 
@@ -0,0 +1,872 @@
+//! Scalar Evolution
+//!
+//! Describes integer values by how they change across the iterations of
+//! loops, like LLVM's ScalarEvolution. A value that starts at `start` on
+//! entry to a loop and grows by a loop-invariant `step` on every iteration
+//! is the add-recurrence `{start,+,step}<loop>`. From the recurrences that
+//! the exit tests of a loop compare, the analysis computes how often the
+//! backedge is taken, and it bounds expressions by constant ranges.
+//! Arithmetic wraps at the bit width of the values, as in the IR.
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use crate::analysis::{DominatorTree, LoopInfo, ValueKey};
+use crate::cfg::Loop;
+use crate::constant_range::{mask, to_signed, ConstantRange};
+use crate::function::Function;
+use crate::instruction::{Instruction, IntPredicate, Opcode};
+use crate::passes::{AnalysisPass, Pass, PassResult};
+use crate::value::Value;
+
+/// A scalar evolution expression
+#[derive(Debug, Clone)]
+pub enum Scev {
+    /// An integer constant, stored as the signed value of its bits
+    Constant { value: i128, width: u32 },
+    /// A value the analysis does not look into
+    Unknown(Value),
+    /// Wrapping sum of the operands
+    Add(Vec<Scev>),
+    /// Wrapping product of the operands
+    Mul(Vec<Scev>),
+    /// Unsigned quotient
+    UDiv(Box<Scev>, Box<Scev>),
+    /// Signed maximum of the operands
+    SMax(Vec<Scev>),
+    /// Unsigned maximum of the operands
+    UMax(Vec<Scev>),
+    /// `{start,+,step}<loop>`: `start` on the first iteration of the loop
+    /// with index `loop_index` in `LoopInfo::loops`, plus `step` on every
+    /// later one
+    AddRec { start: Box<Scev>, step: Box<Scev>, loop_index: usize },
+}
+
+impl Scev {
+    /// A constant of the given width, from the low bits of `value`
+    pub fn constant(value: i128, width: u32) -> Self {
+        Scev::Constant { value: to_signed(value as u128, width), width }
+    }
+
+    /// Bit width of the values of the expression
+    pub fn width(&self) -> u32 {
+        match self {
+            Scev::Constant { width, .. } => *width,
+            Scev::Unknown(value) => value.get_type().int_width().unwrap_or(0),
+            Scev::Add(ops) | Scev::Mul(ops) | Scev::SMax(ops) | Scev::UMax(ops) => {
+                ops.first().map_or(0, Scev::width)
+            }
+            Scev::UDiv(lhs, _) => lhs.width(),
+            Scev::AddRec { start, .. } => start.width(),
+        }
+    }
+
+    /// The signed value of a constant
+    pub fn as_constant(&self) -> Option<i128> {
+        match self {
+            Scev::Constant { value, .. } => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// The unsigned value of a constant
+    pub fn as_unsigned_constant(&self) -> Option<u128> {
+        match self {
+            Scev::Constant { value, width } => Some(*value as u128 & mask(*width)),
+            _ => None,
+        }
+    }
+
+    /// Whether the expression is an add-recurrence of the given loop
+    pub fn is_add_rec_of(&self, loop_index: usize) -> bool {
+        matches!(self, Scev::AddRec { loop_index: index, .. } if *index == loop_index)
+    }
+
+    /// Position of the kind in commutative operand lists, constants first
+    fn rank(&self) -> u8 {
+        match self {
+            Scev::Constant { .. } => 0,
+            Scev::Unknown(_) => 1,
+            Scev::Mul(_) => 2,
+            Scev::UDiv(..) => 3,
+            Scev::SMax(_) => 4,
+            Scev::UMax(_) => 5,
+            Scev::Add(_) => 6,
+            Scev::AddRec { .. } => 7,
+        }
+    }
+}
+
+impl PartialEq for Scev {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Scev::Constant { value: a, width: wa }, Scev::Constant { value: b, width: wb }) => a == b && wa == wb,
+            (Scev::Unknown(a), Scev::Unknown(b)) => ValueKey::of(a) == ValueKey::of(b),
+            (Scev::Add(a), Scev::Add(b)) | (Scev::Mul(a), Scev::Mul(b)) |
+            (Scev::SMax(a), Scev::SMax(b)) | (Scev::UMax(a), Scev::UMax(b)) => a == b,
+            (Scev::UDiv(a, b), Scev::UDiv(c, d)) => a == c && b == d,
+            (Scev::AddRec { start: a, step: b, loop_index: i }, Scev::AddRec { start: c, step: d, loop_index: j }) => {
+                a == c && b == d && i == j
+            }
+            _ => false,
+        }
+    }
+}
+
+fn write_operands(f: &mut fmt::Formatter<'_>, ops: &[Scev], operator: &str) -> fmt::Result {
+    write!(f, "(")?;
+    for (i, op) in ops.iter().enumerate() {
+        if i > 0 {
+            write!(f, " {} ", operator)?;
+        }
+        write!(f, "{}", op)?;
+    }
+    write!(f, ")")
+}
+
+impl fmt::Display for Scev {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scev::Constant { value, .. } => write!(f, "{}", value),
+            Scev::Unknown(value) => match value.name() {
+                Some(name) if value.is_global() => write!(f, "@{}", name),
+                Some(name) => write!(f, "%{}", name),
+                None => write!(f, "{}", value),
+            },
+            Scev::Add(ops) => write_operands(f, ops, "+"),
+            Scev::Mul(ops) => write_operands(f, ops, "*"),
+            Scev::UDiv(lhs, rhs) => write!(f, "({} /u {})", lhs, rhs),
+            Scev::SMax(ops) => write_operands(f, ops, "smax"),
+            Scev::UMax(ops) => write_operands(f, ops, "umax"),
+            Scev::AddRec { start, step, loop_index } => write!(f, "{{{},+,{}}}<loop {}>", start, step, loop_index),
+        }
+    }
+}
+
+/// Sort commutative operands into their canonical order
+fn sort_operands(ops: &mut [Scev]) {
+    ops.sort_by_cached_key(|op| (op.rank(), op.to_string()));
+}
+
+/// How often the backedge of a loop is taken before the loop exits
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BackedgeTakenCount {
+    /// The count, over values available on entry to the loop
+    pub exact: Option<Scev>,
+    /// An upper bound on the count
+    pub max: Option<u128>,
+}
+
+/// Scalar evolution analysis of a function
+///
+/// Expressions are built on demand and cached. Values other than integer
+/// `add`, `sub`, `mul`, `shl` by a constant, `udiv` and phis are unknowns;
+/// a loop header phi whose value on the backedge is itself plus an
+/// invariant is an add-recurrence. Backedge-taken counts are computed for
+/// exits that run on every iteration and compare an affine add-recurrence
+/// against a loop-invariant bound.
+pub struct ScalarEvolution {
+    loop_info: LoopInfo,
+    domtree: DominatorTree,
+    /// Defining instruction and block index of each local value
+    definitions: HashMap<ValueKey, (Instruction, usize)>,
+    /// Terminator of each block
+    terminators: Vec<Option<Instruction>>,
+    /// Expressions of the values analyzed so far
+    scevs: RefCell<HashMap<ValueKey, Scev>>,
+    /// Values in the order their expressions were cached, so that the
+    /// ones built on an assumption can be forgotten
+    cached: RefCell<Vec<ValueKey>>,
+    /// Backedge-taken counts of the loops analyzed so far
+    backedge_taken_counts: RefCell<HashMap<usize, BackedgeTakenCount>>,
+    /// Nesting of expression building, bounded to keep the stack small
+    depth: Cell<usize>,
+}
+
+impl ScalarEvolution {
+    /// Operands followed from a value before the rest are unknowns
+    const MAX_DEPTH: usize = 256;
+
+    pub fn new(function: &Function) -> Self {
+        let domtree = DominatorTree::new(function);
+        let loop_info = LoopInfo::from_cfg(domtree.cfg().clone());
+        let mut definitions = HashMap::new();
+        let mut terminators = Vec::new();
+        for (index, bb) in function.basic_blocks().iter().enumerate() {
+            for inst in bb.instructions() {
+                if let Some(result) = inst.result() {
+                    definitions.insert(ValueKey::of(result), (inst.clone(), index));
+                }
+            }
+            terminators.push(bb.terminator());
+        }
+
+        Self {
+            loop_info,
+            domtree,
+            definitions,
+            terminators,
+            scevs: RefCell::new(HashMap::new()),
+            cached: RefCell::new(Vec::new()),
+            backedge_taken_counts: RefCell::new(HashMap::new()),
+            depth: Cell::new(0),
+        }
+    }
+
+    /// The loops the add-recurrences refer to
+    pub fn loop_info(&self) -> &LoopInfo {
+        &self.loop_info
+    }
+
+    pub fn dominator_tree(&self) -> &DominatorTree {
+        &self.domtree
+    }
+
+    /// The expression for an integer value; other values are unknowns
+    pub fn scev(&self, value: &Value) -> Scev {
+        let Some(width) = value.get_type().int_width().filter(|width| *width <= 128) else {
+            return Scev::Unknown(value.clone());
+        };
+        if let Some(constant) = value.as_const_int() {
+            return Scev::constant(constant as i128, width);
+        }
+        let key = ValueKey::of(value);
+        if let Some(scev) = self.scevs.borrow().get(&key) {
+            return scev.clone();
+        }
+        if self.depth.get() >= Self::MAX_DEPTH {
+            return Scev::Unknown(value.clone());
+        }
+
+        self.depth.set(self.depth.get() + 1);
+        let scev = self.create_scev(value, width);
+        self.depth.set(self.depth.get() - 1);
+        self.cache(key, scev.clone());
+        scev
+    }
+
+    fn cache(&self, key: ValueKey, scev: Scev) {
+        self.scevs.borrow_mut().insert(key.clone(), scev);
+        self.cached.borrow_mut().push(key);
+    }
+
+    fn create_scev(&self, value: &Value, width: u32) -> Scev {
+        let Some((inst, block)) = self.definitions.get(&ValueKey::of(value)) else {
+            return Scev::Unknown(value.clone());
+        };
+        let operands = inst.operands();
+        match inst.opcode() {
+            Opcode::Add if operands.len() == 2 => self.add(vec![self.scev(&operands[0]), self.scev(&operands[1])]),
+            Opcode::Sub if operands.len() == 2 => self.minus(self.scev(&operands[0]), self.scev(&operands[1])),
+            Opcode::Mul if operands.len() == 2 => self.mul(vec![self.scev(&operands[0]), self.scev(&operands[1])]),
+            Opcode::UDiv if operands.len() == 2 => self.udiv(self.scev(&operands[0]), self.scev(&operands[1])),
+            Opcode::Shl if operands.len() == 2 => match operands[1].as_const_int() {
+                Some(amount) if (0..width as i64).contains(&amount) => {
+                    self.mul(vec![self.scev(&operands[0]), Scev::constant(1 << amount, width)])
+                }
+                _ => Scev::Unknown(value.clone()),
+            },
+            Opcode::PHI => self.create_phi_scev(value, inst, *block),
+            _ => Scev::Unknown(value.clone()),
+        }
+    }
+
+    /// A loop header phi that adds an invariant to itself on each
+    /// iteration is an add-recurrence; a phi of equal expressions is that
+    /// expression
+    fn create_phi_scev(&self, phi: &Value, inst: &Instruction, block: usize) -> Scev {
+        let cfg = self.loop_info.cfg();
+        let incoming: Vec<(Value, Option<usize>)> = inst.operands().chunks(2)
+            .map(|pair| {
+                let pred = pair.get(1).and_then(|label| label.name()).and_then(|name| cfg.block_index(name));
+                (pair[0].clone(), pred)
+            })
+            .collect();
+        let unknown = Scev::Unknown(phi.clone());
+
+        let header_of = self.loop_info.loop_index_for_block(block)
+            .filter(|&index| self.loop_info.loops()[index].header == block);
+        if let Some(loop_index) = header_of {
+            let l = &self.loop_info.loops()[loop_index];
+            let (inside, outside): (Vec<_>, Vec<_>) = incoming.iter()
+                .partition(|(_, pred)| pred.is_some_and(|pred| l.contains(pred)));
+            let ([(start, Some(_))], [(backedge, _)]) = (outside.as_slice(), inside.as_slice()) else {
+                return unknown;
+            };
+            let start = self.scev(start);
+            let backedge = self.assuming_unknown(phi, || self.scev(backedge));
+            if backedge == unknown {
+                return start;
+            }
+            let Scev::Add(mut ops) = backedge else {
+                return unknown;
+            };
+            let Some(position) = ops.iter().position(|op| *op == unknown) else {
+                return unknown;
+            };
+            ops.remove(position);
+            let step = self.add(ops);
+            if !self.is_loop_invariant(&step, loop_index) {
+                return unknown;
+            }
+            return self.add_rec(start, step, loop_index);
+        }
+
+        let scevs: Vec<Scev> = self.assuming_unknown(phi, || {
+            incoming.iter().map(|(value, _)| self.scev(value)).collect()
+        });
+        match scevs.split_first() {
+            Some((first, rest)) if rest.iter().all(|scev| scev == first) && *first != unknown => first.clone(),
+            _ => unknown,
+        }
+    }
+
+    /// Build an expression while `phi` stands for itself, then forget
+    /// the cached expressions that relied on that
+    fn assuming_unknown<T>(&self, phi: &Value, build: impl FnOnce() -> T) -> T {
+        let checkpoint = self.cached.borrow().len();
+        self.cache(ValueKey::of(phi), Scev::Unknown(phi.clone()));
+        let result = build();
+        let forgotten: Vec<ValueKey> = self.cached.borrow_mut().drain(checkpoint..).collect();
+        let mut scevs = self.scevs.borrow_mut();
+        for key in forgotten {
+            scevs.remove(&key);
+        }
+        result
+    }
+
+    /// Whether the expression has the same value on every iteration of a
+    /// loop
+    pub fn is_loop_invariant(&self, scev: &Scev, loop_index: usize) -> bool {
+        let l = &self.loop_info.loops()[loop_index];
+        match scev {
+            Scev::Constant { .. } => true,
+            Scev::Unknown(value) => self.definitions.get(&ValueKey::of(value))
+                .is_none_or(|(_, block)| !l.contains(*block)),
+            Scev::Add(ops) | Scev::Mul(ops) | Scev::SMax(ops) | Scev::UMax(ops) => {
+                ops.iter().all(|op| self.is_loop_invariant(op, loop_index))
+            }
+            Scev::UDiv(lhs, rhs) => self.is_loop_invariant(lhs, loop_index) && self.is_loop_invariant(rhs, loop_index),
+            Scev::AddRec { start, step, loop_index: other } => {
+                !l.contains(self.loop_info.loops()[*other].header)
+                    && self.is_loop_invariant(start, loop_index)
+                    && self.is_loop_invariant(step, loop_index)
+            }
+        }
+    }
+
+    /// The sum of expressions, folded: constants are summed, like terms
+    /// combined and invariant terms moved into the start of the innermost
+    /// add-recurrence
+    pub fn add(&self, operands: Vec<Scev>) -> Scev {
+        let width = operands.first().map_or(0, Scev::width);
+        let mut constant: u128 = 0;
+        let mut terms: Vec<(u128, Scev)> = Vec::new();
+        let mut add_recs: Vec<(Scev, Scev, usize)> = Vec::new();
+        let mut worklist = operands;
+        while let Some(op) = worklist.pop() {
+            match op {
+                Scev::Add(ops) => worklist.extend(ops),
+                Scev::Constant { value, .. } => constant = constant.wrapping_add(value as u128),
+                Scev::AddRec { start, step, loop_index } => {
+                    match add_recs.iter_mut().find(|(_, _, index)| *index == loop_index) {
+                        Some(entry) => {
+                            entry.0 = self.add(vec![entry.0.clone(), *start]);
+                            entry.1 = self.add(vec![entry.1.clone(), *step]);
+                        }
+                        None => add_recs.push((*start, *step, loop_index)),
+                    }
+                }
+                op => {
+                    let (coefficient, term) = split_coefficient(op);
+                    match terms.iter_mut().find(|(_, other)| *other == term) {
+                        Some(entry) => entry.0 = entry.0.wrapping_add(coefficient),
+                        None => terms.push((coefficient, term)),
+                    }
+                }
+            }
+        }
+
+        let mut ops: Vec<Scev> = Vec::new();
+        if constant & mask(width) != 0 {
+            ops.push(Scev::constant(constant as i128, width));
+        }
+        for (coefficient, term) in terms {
+            match coefficient & mask(width) {
+                0 => {}
+                1 => ops.push(term),
+                coefficient => ops.push(self.mul(vec![Scev::constant(coefficient as i128, width), term])),
+            }
+        }
+
+        // Fold what is invariant in the innermost loop into its recurrence
+        add_recs.sort_by_key(|(_, _, index)| std::cmp::Reverse(self.loop_info.loops()[*index].depth));
+        let mut add_recs = add_recs.into_iter();
+        if let Some((start, step, loop_index)) = add_recs.next() {
+            ops.extend(add_recs.map(|(start, step, index)| self.add_rec(start, step, index)));
+            let (invariant, variant): (Vec<Scev>, Vec<Scev>) = ops.into_iter()
+                .partition(|op| self.is_loop_invariant(op, loop_index));
+            let start = if invariant.is_empty() {
+                start
+            } else {
+                self.add(std::iter::once(start).chain(invariant).collect())
+            };
+            ops = variant;
+            ops.push(self.add_rec(start, step, loop_index));
+        }
+
+        match ops.len() {
+            0 => Scev::constant(0, width),
+            1 => ops.pop().unwrap(),
+            _ => {
+                sort_operands(&mut ops);
+                Scev::Add(ops)
+            }
+        }
+    }
+
+    /// The product of expressions, folded: constants are multiplied and a
+    /// product with one add-recurrence or a constant times a sum is
+    /// distributed
+    pub fn mul(&self, operands: Vec<Scev>) -> Scev {
+        let width = operands.first().map_or(0, Scev::width);
+        let mut constant: u128 = 1;
+        let mut ops: Vec<Scev> = Vec::new();
+        let mut worklist = operands;
+        while let Some(op) = worklist.pop() {
+            match op {
+                Scev::Mul(inner) => worklist.extend(inner),
+                Scev::Constant { value, .. } => constant = constant.wrapping_mul(value as u128),
+                op => ops.push(op),
+            }
+        }
+        let constant = constant & mask(width);
+        if constant == 0 || ops.is_empty() {
+            return Scev::constant(constant as i128, width);
+        }
+
+        // {start,+,step} * x is {start * x,+,step * x} when x is invariant
+        if let [Scev::AddRec { start, step, loop_index }] = ops.as_slice() {
+            let factor = Scev::constant(constant as i128, width);
+            let (start, step) = (self.mul(vec![(**start).clone(), factor.clone()]), self.mul(vec![(**step).clone(), factor]));
+            return self.add_rec(start, step, *loop_index);
+        }
+        let add_recs: Vec<usize> = ops.iter().filter_map(|op| match op {
+            Scev::AddRec { loop_index, .. } => Some(*loop_index),
+            _ => None,
+        }).collect();
+        if let [loop_index] = add_recs[..] {
+            let (add_rec, mut factors): (Vec<Scev>, Vec<Scev>) = ops.iter().cloned()
+                .partition(|op| op.is_add_rec_of(loop_index));
+            if let ([Scev::AddRec { start, step, .. }], true) =
+                (add_rec.as_slice(), factors.iter().all(|op| self.is_loop_invariant(op, loop_index)))
+            {
+                factors.push(Scev::constant(constant as i128, width));
+                let scale = |scev: &Scev| self.mul(std::iter::once(scev.clone()).chain(factors.iter().cloned()).collect());
+                return self.add_rec(scale(start), scale(step), loop_index);
+            }
+        }
+
+        if constant != 1 {
+            if let [Scev::Add(terms)] = ops.as_slice() {
+                let scaled = terms.iter()
+                    .map(|term| self.mul(vec![Scev::constant(constant as i128, width), term.clone()]))
+                    .collect();
+                return self.add(scaled);
+            }
+            ops.push(Scev::constant(constant as i128, width));
+        }
+        if ops.len() == 1 {
+            return ops.pop().unwrap();
+        }
+        sort_operands(&mut ops);
+        Scev::Mul(ops)
+    }
+
+    /// `lhs - rhs`
+    pub fn minus(&self, lhs: Scev, rhs: Scev) -> Scev {
+        let width = lhs.width();
+        let negated = self.mul(vec![Scev::constant(-1, width), rhs]);
+        self.add(vec![lhs, negated])
+    }
+
+    /// The unsigned quotient, folded for constants
+    pub fn udiv(&self, lhs: Scev, rhs: Scev) -> Scev {
+        let width = lhs.width();
+        match (lhs.as_unsigned_constant(), rhs.as_unsigned_constant()) {
+            (Some(lhs), Some(rhs)) if rhs != 0 => Scev::constant((lhs / rhs) as i128, width),
+            (Some(0), _) | (_, Some(1)) => lhs,
+            _ => Scev::UDiv(Box::new(lhs), Box::new(rhs)),
+        }
+    }
+
+    /// The signed maximum, folded for constants and repeated operands
+    pub fn smax(&self, operands: Vec<Scev>) -> Scev {
+        self.max(operands, true)
+    }
+
+    /// The unsigned maximum, folded for constants and repeated operands
+    pub fn umax(&self, operands: Vec<Scev>) -> Scev {
+        self.max(operands, false)
+    }
+
+    fn max(&self, operands: Vec<Scev>, signed: bool) -> Scev {
+        let width = operands.first().map_or(0, Scev::width);
+        // Bits of the largest constant, kept in the signed or unsigned order
+        let key = |value: i128| if signed { value } else { (value as u128 & mask(width)) as i128 };
+        let mut constant: Option<i128> = None;
+        let mut ops: Vec<Scev> = Vec::new();
+        let mut worklist = operands;
+        while let Some(op) = worklist.pop() {
+            match op {
+                Scev::SMax(inner) if signed => worklist.extend(inner),
+                Scev::UMax(inner) if !signed => worklist.extend(inner),
+                Scev::Constant { value, .. } => {
+                    constant = Some(constant.map_or(value, |other| if key(value) > key(other) { value } else { other }));
+                }
+                op => {
+                    if !ops.contains(&op) {
+                        ops.push(op);
+                    }
+                }
+            }
+        }
+
+        // The smallest value never changes a maximum
+        let minimum = if signed { to_signed(1 << (width - 1), width) } else { 0 };
+        if let Some(constant) = constant.filter(|constant| *constant != minimum || ops.is_empty()) {
+            ops.push(Scev::constant(constant, width));
+        }
+        match ops.len() {
+            0 => Scev::constant(minimum, width),
+            1 => ops.pop().unwrap(),
+            _ => {
+                sort_operands(&mut ops);
+                if signed { Scev::SMax(ops) } else { Scev::UMax(ops) }
+            }
+        }
+    }
+
+    /// `{start,+,step}<loop>`, or `start` for a zero step
+    pub fn add_rec(&self, start: Scev, step: Scev, loop_index: usize) -> Scev {
+        if step.as_unsigned_constant() == Some(0) {
+            return start;
+        }
+        Scev::AddRec { start: Box::new(start), step: Box::new(step), loop_index }
+    }
+
+    /// The values the expression can take, as an unsigned interval
+    pub fn unsigned_range(&self, scev: &Scev) -> ConstantRange {
+        self.range(scev, false)
+    }
+
+    /// The values the expression can take, as a signed interval
+    pub fn signed_range(&self, scev: &Scev) -> ConstantRange {
+        self.range(scev, true)
+    }
+
+    fn range(&self, scev: &Scev, signed: bool) -> ConstantRange {
+        let width = scev.width();
+        let fold = |ops: &[Scev], combine: fn(&ConstantRange, &ConstantRange) -> ConstantRange| {
+            ops.iter().skip(1).fold(self.range(&ops[0], signed), |range, op| combine(&range, &self.range(op, signed)))
+        };
+        let range = match scev {
+            Scev::Constant { value, .. } => ConstantRange::single(width, *value as u128),
+            Scev::Unknown(_) => ConstantRange::full(width),
+            Scev::Add(ops) => fold(ops, ConstantRange::add),
+            Scev::Mul(ops) => fold(ops, ConstantRange::multiply),
+            Scev::UDiv(lhs, rhs) => self.range(lhs, signed).udiv(&self.range(rhs, signed)),
+            Scev::SMax(ops) => fold(ops, ConstantRange::smax),
+            Scev::UMax(ops) => fold(ops, ConstantRange::umax),
+            Scev::AddRec { start, step, loop_index } => self.add_rec_range(start, step, *loop_index, signed),
+        };
+        if range.is_empty_set() || range.is_full_set() {
+            return range;
+        }
+        if signed {
+            ConstantRange::from_signed(width, range.signed_min(), range.signed_max())
+        } else {
+            ConstantRange::from_unsigned(width, range.unsigned_min(), range.unsigned_max())
+        }
+    }
+
+    /// The values of a recurrence with a constant step up to the maximum
+    /// backedge-taken count of its loop, if they do not wrap
+    fn add_rec_range(&self, start: &Scev, step: &Scev, loop_index: usize, signed: bool) -> ConstantRange {
+        let width = start.width();
+        let full = ConstantRange::full(width);
+        let (Some(step), Some(max_count)) = (step.as_constant(), self.backedge_taken_count(loop_index).max) else {
+            return full;
+        };
+        let Some(distance) = i128::try_from(max_count).ok().and_then(|count| count.checked_mul(step)) else {
+            return full;
+        };
+        let start = self.range(start, signed);
+        if signed {
+            let (low, high) = (start.signed_min(), start.signed_max());
+            let (min, max) = if step > 0 { (Some(low), high.checked_add(distance)) } else { (low.checked_add(distance), Some(high)) };
+            let (lowest, highest) = (to_signed(1 << (width - 1), width), to_signed(mask(width) >> 1, width));
+            match (min, max) {
+                (Some(min), Some(max)) if lowest <= min && max <= highest => ConstantRange::from_signed(width, min, max),
+                _ => full,
+            }
+        } else {
+            let (low, high) = (start.unsigned_min() as i128, start.unsigned_max() as i128);
+            let (min, max) = if step > 0 { (Some(low), high.checked_add(distance)) } else { (low.checked_add(distance), Some(high)) };
+            match (min, max) {
+                (Some(min), Some(max)) if 0 <= min && max as u128 <= mask(width) => {
+                    ConstantRange::from_unsigned(width, min as u128, max as u128)
+                }
+                _ => full,
+            }
+        }
+    }
+
+    /// How often the backedge of a loop is taken; exact when every exit
+    /// that can be taken is understood
+    pub fn backedge_taken_count(&self, loop_index: usize) -> BackedgeTakenCount {
+        if let Some(count) = self.backedge_taken_counts.borrow().get(&loop_index) {
+            return count.clone();
+        }
+        // Nothing is known while the count is being computed
+        self.backedge_taken_counts.borrow_mut().insert(loop_index, BackedgeTakenCount::default());
+        let count = self.compute_backedge_taken_count(loop_index);
+        self.backedge_taken_counts.borrow_mut().insert(loop_index, count.clone());
+        count
+    }
+
+    /// The number of times the loop body runs, for an exact constant
+    /// backedge-taken count
+    pub fn constant_trip_count(&self, loop_index: usize) -> Option<u128> {
+        self.backedge_taken_count(loop_index).exact?.as_unsigned_constant()?.checked_add(1)
+    }
+
+    fn compute_backedge_taken_count(&self, loop_index: usize) -> BackedgeTakenCount {
+        let l = &self.loop_info.loops()[loop_index];
+        let Some(latch) = l.latch() else {
+            return BackedgeTakenCount::default();
+        };
+
+        let mut exact: Option<Vec<Scev>> = Some(Vec::new());
+        let mut max: Option<u128> = None;
+        for exiting in self.loop_info.exiting_blocks(l) {
+            // An exit that may be skipped on some iteration bounds nothing
+            let count = if self.domtree.dominates(exiting, latch) {
+                self.exit_count(l, loop_index, exiting)
+            } else {
+                BackedgeTakenCount::default()
+            };
+            if let Some(count_max) = count.max {
+                max = Some(max.map_or(count_max, |max| max.min(count_max)));
+            }
+            match (&mut exact, count.exact) {
+                (Some(counts), Some(count)) => counts.push(count),
+                _ => exact = None,
+            }
+        }
+
+        // The loop leaves at the first exit taken
+        let exact = exact.and_then(|mut counts| match counts.len() {
+            0 => None,
+            1 => counts.pop(),
+            _ => {
+                let width = counts[0].width();
+                counts.iter().map(Scev::as_unsigned_constant).collect::<Option<Vec<u128>>>()
+                    .and_then(|counts| counts.into_iter().min())
+                    .map(|count| Scev::constant(count as i128, width))
+            }
+        });
+        BackedgeTakenCount { exact, max }
+    }
+
+    /// The count for one exiting block that runs on every iteration
+    fn exit_count(&self, l: &Loop, loop_index: usize, exiting: usize) -> BackedgeTakenCount {
+        let none = BackedgeTakenCount::default();
+        let Some(term) = self.terminators[exiting].as_ref().filter(|term| term.opcode() == Opcode::CondBr) else {
+            return none;
+        };
+        let operands = term.operands();
+        let cfg = self.loop_info.cfg();
+        let target = |label: &Value| label.name().and_then(|name| cfg.block_index(name));
+        let (Some(on_true), Some(on_false)) = (operands.get(1).and_then(target), operands.get(2).and_then(target)) else {
+            return none;
+        };
+        let stay_if_true = match (l.contains(on_true), l.contains(on_false)) {
+            (true, false) => true,
+            (false, true) => false,
+            _ => return none,
+        };
+
+        let Some((cmp, _)) = self.definitions.get(&ValueKey::of(&operands[0])) else {
+            return none;
+        };
+        let Some(predicate) = cmp.int_predicate().filter(|_| cmp.opcode() == Opcode::ICmp) else {
+            return none;
+        };
+        let [lhs, rhs] = cmp.operands() else {
+            return none;
+        };
+        // The condition for staying in the loop, with the recurrence on the left
+        let mut predicate = if stay_if_true { predicate } else { predicate.inverse() };
+        let (mut lhs, mut rhs) = (self.scev(lhs), self.scev(rhs));
+        if !lhs.is_add_rec_of(loop_index) && rhs.is_add_rec_of(loop_index) {
+            std::mem::swap(&mut lhs, &mut rhs);
+            predicate = predicate.swapped();
+        }
+
+        let exact = self.count_while(predicate, &lhs, &rhs, loop_index);
+        let max = exact.as_ref().map(|count| match count.as_unsigned_constant() {
+            Some(count) => count,
+            None => self.unsigned_range(count).unsigned_max(),
+        });
+        BackedgeTakenCount { exact, max }
+    }
+
+    /// Iterations of `{start,+,step}<loop> predicate bound` holding before
+    /// it fails, for a constant step and a loop-invariant bound
+    fn count_while(&self, predicate: IntPredicate, iv: &Scev, bound: &Scev, loop_index: usize) -> Option<Scev> {
+        let Scev::AddRec { start, step, .. } = iv.clone() else {
+            return None;
+        };
+        if !iv.is_add_rec_of(loop_index) || !self.is_loop_invariant(bound, loop_index) {
+            return None;
+        }
+        let step = step.as_constant()?;
+        let width = iv.width();
+        let signed = predicate.is_signed();
+        let bound = bound.clone();
+        match predicate {
+            IntPredicate::NE => self.count_until_equal(*start, step, bound),
+            IntPredicate::EQ => {
+                // Equal at most once when the recurrence moves
+                let (start, bound) = (start.as_unsigned_constant()?, bound.as_unsigned_constant()?);
+                Some(Scev::constant((start == bound) as i128, width))
+            }
+            IntPredicate::ULT | IntPredicate::SLT if step > 0 => self.count_up_to(*start, step as u128, bound, signed),
+            IntPredicate::ULE | IntPredicate::SLE if step > 0 => {
+                // iv <= bound is iv < bound + 1 when bound + 1 does not wrap
+                let range = self.range(&bound, signed);
+                let below_max = if signed {
+                    range.signed_max() < to_signed(mask(width) >> 1, width)
+                } else {
+                    range.unsigned_max() < mask(width)
+                };
+                if !below_max {
+                    return None;
+                }
+                let bound = self.add(vec![bound, Scev::constant(1, width)]);
+                self.count_up_to(*start, step as u128, bound, signed)
+            }
+            IntPredicate::UGT | IntPredicate::SGT if step < 0 => self.count_down_to(*start, step.unsigned_abs(), bound, signed),
+            IntPredicate::UGE | IntPredicate::SGE if step < 0 => {
+                let range = self.range(&bound, signed);
+                let above_min = if signed {
+                    range.signed_min() > to_signed(1 << (width - 1), width)
+                } else {
+                    range.unsigned_min() > 0
+                };
+                if !above_min {
+                    return None;
+                }
+                let bound = self.minus(bound, Scev::constant(1, width));
+                self.count_down_to(*start, step.unsigned_abs(), bound, signed)
+            }
+            _ => None,
+        }
+    }
+
+    /// Iterations of `start + k * step != bound`, which wraps until equal
+    fn count_until_equal(&self, start: Scev, step: i128, bound: Scev) -> Option<Scev> {
+        let width = start.width();
+        match step {
+            1 => Some(self.minus(bound, start)),
+            -1 => Some(self.minus(start, bound)),
+            _ => {
+                let distance = if step > 0 { self.minus(bound, start) } else { self.minus(start, bound) };
+                let (distance, step) = (distance.as_unsigned_constant()?, step.unsigned_abs());
+                distance.is_multiple_of(step).then(|| Scev::constant((distance / step) as i128, width))
+            }
+        }
+    }
+
+    /// Iterations of `start + k * step < bound`: `(max(bound, start) -
+    /// start + step - 1) /u step`, when neither the recurrence nor the
+    /// rounding can wrap
+    fn count_up_to(&self, start: Scev, step: u128, bound: Scev, signed: bool) -> Option<Scev> {
+        let width = start.width();
+        let end = self.max(vec![bound.clone(), start.clone()], signed);
+        let distance = self.minus(end, start);
+        if step > 1 {
+            let bound_range = self.range(&bound, signed);
+            let bound_fits = if signed {
+                bound_range.signed_max().checked_add(step as i128 - 1)
+                    .is_some_and(|max| max <= to_signed(mask(width) >> 1, width))
+            } else {
+                bound_range.unsigned_max().checked_add(step - 1).is_some_and(|max| max <= mask(width))
+            };
+            let distance_fits = self.unsigned_range(&distance).unsigned_max()
+                .checked_add(step - 1).is_some_and(|max| max <= mask(width));
+            if !bound_fits || !distance_fits {
+                return None;
+            }
+        }
+        let rounded = self.add(vec![distance, Scev::constant(step as i128 - 1, width)]);
+        Some(self.udiv(rounded, Scev::constant(step as i128, width)))
+    }
+
+    /// Iterations of `start - k * step > bound`: `(max(start, bound) -
+    /// bound + step - 1) /u step`, when neither the recurrence nor the
+    /// rounding can wrap
+    fn count_down_to(&self, start: Scev, step: u128, bound: Scev, signed: bool) -> Option<Scev> {
+        let width = start.width();
+        let begin = self.max(vec![start, bound.clone()], signed);
+        let distance = self.minus(begin, bound.clone());
+        if step > 1 {
+            let bound_range = self.range(&bound, signed);
+            let bound_fits = if signed {
+                bound_range.signed_min().checked_sub(step as i128 - 1)
+                    .is_some_and(|min| min >= to_signed(1 << (width - 1), width))
+            } else {
+                bound_range.unsigned_min() >= step - 1
+            };
+            let distance_fits = self.unsigned_range(&distance).unsigned_max()
+                .checked_add(step - 1).is_some_and(|max| max <= mask(width));
+            if !bound_fits || !distance_fits {
+                return None;
+            }
+        }
+        let rounded = self.add(vec![distance, Scev::constant(step as i128 - 1, width)]);
+        Some(self.udiv(rounded, Scev::constant(step as i128, width)))
+    }
+}
+
+/// Split a term into its constant coefficient and the rest
+fn split_coefficient(scev: Scev) -> (u128, Scev) {
+    if let Scev::Mul(ops) = &scev {
+        if let [Scev::Constant { value, .. }, rest @ ..] = ops.as_slice() {
+            let term = match rest {
+                [single] => single.clone(),
+                _ => Scev::Mul(rest.to_vec()),
+            };
+            return (*value as u128, term);
+        }
+    }
+    (1, scev)
+}
+
+/// Scalar evolution analysis pass
+pub struct ScalarEvolutionPass;
+
+impl Pass for ScalarEvolutionPass {
+    fn name(&self) -> &str {
+        "scalar-evolution"
+    }
+}
+
+impl AnalysisPass for ScalarEvolutionPass {
+    type Result = ScalarEvolution;
+
+    fn run_analysis(&mut self, function: &Function) -> PassResult<Self::Result> {
+        Ok(ScalarEvolution::new(function))
+    }
+}
@@ -0,0 +1,1060 @@
+//! ThinLTO Module Summary Index
+//!
+//! A module summary records, for every global value in one or more modules,
+//! just enough information to make cross-module decisions (importing,
+//! internalization, dead stripping) without loading the modules themselves:
+//! linkage and flags, instruction counts, call edges and references.
+//!
+//! This module provides the index data model, its textual form (the `^N = ...`
+//! entries at the end of a `.ll` file) and [`build_module_summary`], which
+//! computes the summary of a parsed [`Module`]. The text form is read by the
+//! parser.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use crate::function::Function;
+use crate::instruction::{Instruction, Opcode};
+use crate::module::{Linkage, Module, Visibility};
+use crate::value::{Value, ValueKind};
+
+/// Globally unique identifier of a global value: the low 64 bits of the MD5
+/// hash of its global identifier
+pub type GlobalValueId = u64;
+
+/// Compute the GUID of a global identifier (see [`global_identifier`])
+pub fn global_value_guid(global_identifier: &str) -> GlobalValueId {
+    let digest = md5(global_identifier.as_bytes());
+    u64::from_le_bytes(digest[..8].try_into().unwrap())
+}
+
+/// The identifier a global value is hashed under.
+///
+/// Values with local linkage are qualified by their source file so that
+/// same-named statics in different modules get distinct GUIDs.
+pub fn global_identifier(name: &str, linkage: Linkage, source_filename: &str) -> String {
+    // A leading \1 suppresses name mangling and is not part of the identifier
+    let name = name.strip_prefix('\u{1}').unwrap_or(name);
+    if matches!(linkage, Linkage::Internal | Linkage::Private) {
+        let file = if source_filename.is_empty() { "<unknown>" } else { source_filename };
+        format!("{}:{}", file, name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// A module contributing summaries to the index
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleInfo {
+    pub path: String,
+    pub hash: [u32; 5],
+}
+
+/// Flags common to every global value summary
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GlobalValueFlags {
+    pub linkage: Linkage,
+    pub visibility: Visibility,
+    pub not_eligible_to_import: bool,
+    pub live: bool,
+    pub dso_local: bool,
+    pub can_auto_hide: bool,
+}
+
+/// Properties of a function that callers in other modules can rely on
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FunctionFlags {
+    pub read_none: bool,
+    pub read_only: bool,
+    pub no_recurse: bool,
+    pub return_does_not_alias: bool,
+    pub no_inline: bool,
+    pub always_inline: bool,
+    pub no_unwind: bool,
+    pub may_throw: bool,
+    pub has_unknown_call: bool,
+    pub must_be_unreachable: bool,
+}
+
+impl FunctionFlags {
+    fn any(&self) -> bool {
+        *self != FunctionFlags::default()
+    }
+}
+
+/// Profile-derived hotness of a call edge
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Hotness {
+    #[default]
+    Unknown,
+    Cold,
+    None,
+    Hot,
+    Critical,
+}
+
+impl Hotness {
+    fn name(self) -> &'static str {
+        match self {
+            Hotness::Unknown => "unknown",
+            Hotness::Cold => "cold",
+            Hotness::None => "none",
+            Hotness::Hot => "hot",
+            Hotness::Critical => "critical",
+        }
+    }
+
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "unknown" => Hotness::Unknown,
+            "cold" => Hotness::Cold,
+            "none" => Hotness::None,
+            "hot" => Hotness::Hot,
+            "critical" => Hotness::Critical,
+            _ => return None,
+        })
+    }
+}
+
+/// A direct call from a summarized function
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallEdge {
+    pub callee: GlobalValueId,
+    pub hotness: Hotness,
+    /// Relative block frequency of the call site, when known
+    pub rel_block_freq: Option<u64>,
+}
+
+/// How a reference accesses the referenced global variable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RefAccess {
+    #[default]
+    ReadWrite,
+    ReadOnly,
+    WriteOnly,
+}
+
+/// A non-call reference to a global value
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ValueRef {
+    pub guid: GlobalValueId,
+    pub access: RefAccess,
+}
+
+/// Summary of a function definition
+#[derive(Debug, Clone, PartialEq)]
+pub struct FunctionSummary {
+    /// Index into [`ModuleSummaryIndex::modules`]
+    pub module: usize,
+    pub flags: GlobalValueFlags,
+    pub inst_count: u32,
+    pub function_flags: FunctionFlags,
+    pub calls: Vec<CallEdge>,
+    pub refs: Vec<ValueRef>,
+    /// GUIDs of type identifiers tested by `llvm.type.test` outside of assumes
+    pub type_tests: Vec<GlobalValueId>,
+}
+
+/// Summary of a global variable definition
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariableSummary {
+    pub module: usize,
+    pub flags: GlobalValueFlags,
+    pub read_only: bool,
+    pub write_only: bool,
+    pub constant: bool,
+    pub refs: Vec<ValueRef>,
+}
+
+/// Summary of an alias
+#[derive(Debug, Clone, PartialEq)]
+pub struct AliasSummary {
+    pub module: usize,
+    pub flags: GlobalValueFlags,
+    pub aliasee: Option<GlobalValueId>,
+}
+
+/// The summary of one definition of a global value
+#[derive(Debug, Clone, PartialEq)]
+pub enum GlobalValueSummary {
+    Function(FunctionSummary),
+    Variable(VariableSummary),
+    Alias(AliasSummary),
+}
+
+impl GlobalValueSummary {
+    /// Index of the module holding this definition
+    pub fn module(&self) -> usize {
+        match self {
+            GlobalValueSummary::Function(s) => s.module,
+            GlobalValueSummary::Variable(s) => s.module,
+            GlobalValueSummary::Alias(s) => s.module,
+        }
+    }
+
+    /// Linkage, visibility and import flags
+    pub fn flags(&self) -> &GlobalValueFlags {
+        match self {
+            GlobalValueSummary::Function(s) => &s.flags,
+            GlobalValueSummary::Variable(s) => &s.flags,
+            GlobalValueSummary::Alias(s) => &s.flags,
+        }
+    }
+
+    /// References to other global values, excluding calls
+    pub fn refs(&self) -> &[ValueRef] {
+        match self {
+            GlobalValueSummary::Function(s) => &s.refs,
+            GlobalValueSummary::Variable(s) => &s.refs,
+            GlobalValueSummary::Alias(_) => &[],
+        }
+    }
+
+    fn set_module(&mut self, module: usize) {
+        match self {
+            GlobalValueSummary::Function(s) => s.module = module,
+            GlobalValueSummary::Variable(s) => s.module = module,
+            GlobalValueSummary::Alias(s) => s.module = module,
+        }
+    }
+}
+
+/// A global value in the index with its summaries, one per defining module
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlobalValueInfo {
+    pub guid: GlobalValueId,
+    /// Name of the value, when known; combined indexes may only have GUIDs
+    pub name: Option<String>,
+    pub summaries: Vec<GlobalValueSummary>,
+}
+
+/// Lowering strategy chosen for a type test
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TypeTestResolutionKind {
+    Unsat,
+    ByteArray,
+    Inline,
+    Single,
+    AllOnes,
+    #[default]
+    Unknown,
+}
+
+impl TypeTestResolutionKind {
+    fn name(self) -> &'static str {
+        match self {
+            TypeTestResolutionKind::Unsat => "unsat",
+            TypeTestResolutionKind::ByteArray => "byteArray",
+            TypeTestResolutionKind::Inline => "inline",
+            TypeTestResolutionKind::Single => "single",
+            TypeTestResolutionKind::AllOnes => "allOnes",
+            TypeTestResolutionKind::Unknown => "unknown",
+        }
+    }
+
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "unsat" => TypeTestResolutionKind::Unsat,
+            "byteArray" => TypeTestResolutionKind::ByteArray,
+            "inline" => TypeTestResolutionKind::Inline,
+            "single" => TypeTestResolutionKind::Single,
+            "allOnes" => TypeTestResolutionKind::AllOnes,
+            "unknown" => TypeTestResolutionKind::Unknown,
+            _ => return None,
+        })
+    }
+}
+
+/// How tests against one type identifier are lowered
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct TypeTestResolution {
+    pub kind: TypeTestResolutionKind,
+    pub size_m1_bit_width: u32,
+    pub align_log2: u64,
+    pub size_m1: u64,
+    pub bit_mask: u8,
+    pub inline_bits: u64,
+}
+
+/// Summary of a type identifier (`^N = typeid: ...`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct TypeIdSummary {
+    pub name: String,
+    pub resolution: TypeTestResolution,
+}
+
+/// A vtable compatible with a type identifier, at an offset
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TypeIdOffsetVtable {
+    pub offset: u64,
+    pub vtable: GlobalValueId,
+}
+
+/// Summary index for one module, or a combined index over several
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ModuleSummaryIndex {
+    modules: Vec<ModuleInfo>,
+    values: BTreeMap<GlobalValueId, GlobalValueInfo>,
+    type_ids: BTreeMap<GlobalValueId, TypeIdSummary>,
+    compatible_vtables: BTreeMap<GlobalValueId, (String, Vec<TypeIdOffsetVtable>)>,
+    flags: Option<u64>,
+    block_count: Option<u64>,
+}
+
+impl ModuleSummaryIndex {
+    /// Create an empty index
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a module and return its index for use in summaries
+    pub fn add_module(&mut self, path: String, hash: [u32; 5]) -> usize {
+        self.modules.push(ModuleInfo { path, hash });
+        self.modules.len() - 1
+    }
+
+    /// Modules contributing to this index
+    pub fn modules(&self) -> &[ModuleInfo] {
+        &self.modules
+    }
+
+    /// Get or create the entry for a GUID, recording its name if given
+    pub fn get_or_insert_value(&mut self, guid: GlobalValueId, name: Option<&str>) -> &mut GlobalValueInfo {
+        let info = self.values.entry(guid).or_insert_with(|| GlobalValueInfo {
+            guid,
+            name: None,
+            summaries: Vec::new(),
+        });
+        if info.name.is_none() {
+            info.name = name.map(str::to_string);
+        }
+        info
+    }
+
+    /// Add a summary for a global value
+    pub fn add_summary(&mut self, guid: GlobalValueId, name: Option<&str>, summary: GlobalValueSummary) {
+        self.get_or_insert_value(guid, name).summaries.push(summary);
+    }
+
+    /// Look up a global value by GUID
+    pub fn value(&self, guid: GlobalValueId) -> Option<&GlobalValueInfo> {
+        self.values.get(&guid)
+    }
+
+    /// Look up a global value by name
+    pub fn value_by_name(&self, name: &str) -> Option<&GlobalValueInfo> {
+        self.values.values().find(|info| info.name.as_deref() == Some(name))
+    }
+
+    /// All global values, in GUID order
+    pub fn values(&self) -> impl Iterator<Item = &GlobalValueInfo> {
+        self.values.values()
+    }
+
+    /// The first function summary recorded for a GUID
+    pub fn function_summary(&self, guid: GlobalValueId) -> Option<&FunctionSummary> {
+        self.value(guid)?.summaries.iter().find_map(|summary| match summary {
+            GlobalValueSummary::Function(function) => Some(function),
+            _ => None,
+        })
+    }
+
+    /// Add a type identifier summary
+    pub fn add_type_id(&mut self, summary: TypeIdSummary) {
+        self.type_ids.insert(global_value_guid(&summary.name), summary);
+    }
+
+    /// Look up a type identifier summary by GUID
+    pub fn type_id(&self, guid: GlobalValueId) -> Option<&TypeIdSummary> {
+        self.type_ids.get(&guid)
+    }
+
+    /// All type identifier summaries, in GUID order
+    pub fn type_ids(&self) -> impl Iterator<Item = &TypeIdSummary> {
+        self.type_ids.values()
+    }
+
+    /// Record the vtables compatible with a type identifier
+    pub fn add_compatible_vtables(&mut self, type_id: String, vtables: Vec<TypeIdOffsetVtable>) {
+        self.compatible_vtables.insert(global_value_guid(&type_id), (type_id, vtables));
+    }
+
+    /// Vtables compatible with a type identifier
+    pub fn compatible_vtables(&self, type_id: &str) -> Option<&[TypeIdOffsetVtable]> {
+        self.compatible_vtables.get(&global_value_guid(type_id)).map(|(_, vtables)| vtables.as_slice())
+    }
+
+    /// Index-wide flags (`^N = flags: ...`)
+    pub fn flags(&self) -> Option<u64> {
+        self.flags
+    }
+
+    pub fn set_flags(&mut self, flags: u64) {
+        self.flags = Some(flags);
+    }
+
+    /// Total number of basic blocks in summarized functions
+    pub fn block_count(&self) -> Option<u64> {
+        self.block_count
+    }
+
+    pub fn set_block_count(&mut self, count: u64) {
+        self.block_count = Some(count);
+    }
+
+    /// Merge another index into this one, producing a combined index.
+    ///
+    /// The other index's modules are appended and its summaries are renumbered
+    /// to match.
+    pub fn merge(&mut self, other: &ModuleSummaryIndex) {
+        let module_offset = self.modules.len();
+        self.modules.extend(other.modules.iter().cloned());
+
+        for info in other.values.values() {
+            let entry = self.get_or_insert_value(info.guid, info.name.as_deref());
+            for summary in &info.summaries {
+                let mut summary = summary.clone();
+                summary.set_module(summary.module() + module_offset);
+                entry.summaries.push(summary);
+            }
+        }
+        for (guid, summary) in &other.type_ids {
+            self.type_ids.entry(*guid).or_insert_with(|| summary.clone());
+        }
+        for (guid, vtables) in &other.compatible_vtables {
+            self.compatible_vtables.entry(*guid).or_insert_with(|| vtables.clone());
+        }
+        if let Some(flags) = other.flags {
+            self.flags = Some(self.flags.unwrap_or(0) | flags);
+        }
+        if let Some(count) = other.block_count {
+            self.block_count = Some(self.block_count.unwrap_or(0) + count);
+        }
+    }
+}
+
+// ===== Text form =====
+
+pub(crate) fn linkage_name(linkage: Linkage) -> &'static str {
+    match linkage {
+        Linkage::External => "external",
+        Linkage::Private => "private",
+        Linkage::Internal => "internal",
+        Linkage::AvailableExternally => "available_externally",
+        Linkage::Linkonce => "linkonce",
+        Linkage::Weak => "weak",
+        Linkage::Common => "common",
+        Linkage::Appending => "appending",
+        Linkage::ExternWeak => "extern_weak",
+        Linkage::LinkonceOdr => "linkonce_odr",
+        Linkage::WeakOdr => "weak_odr",
+    }
+}
+
+pub(crate) fn linkage_from_name(name: &str) -> Option<Linkage> {
+    Some(match name {
+        "external" => Linkage::External,
+        "private" => Linkage::Private,
+        "internal" => Linkage::Internal,
+        "available_externally" => Linkage::AvailableExternally,
+        "linkonce" => Linkage::Linkonce,
+        "weak" => Linkage::Weak,
+        "common" => Linkage::Common,
+        "appending" => Linkage::Appending,
+        "extern_weak" => Linkage::ExternWeak,
+        "linkonce_odr" => Linkage::LinkonceOdr,
+        "weak_odr" => Linkage::WeakOdr,
+        _ => return None,
+    })
+}
+
+pub(crate) fn visibility_name(visibility: Visibility) -> &'static str {
+    match visibility {
+        Visibility::Default => "default",
+        Visibility::Hidden => "hidden",
+        Visibility::Protected => "protected",
+    }
+}
+
+pub(crate) fn visibility_from_name(name: &str) -> Option<Visibility> {
+    Some(match name {
+        "default" => Visibility::Default,
+        "hidden" => Visibility::Hidden,
+        "protected" => Visibility::Protected,
+        _ => return None,
+    })
+}
+
+/// Slot numbers assigned to index entries when printing
+struct SlotMap {
+    values: HashMap<GlobalValueId, usize>,
+    type_ids: HashMap<GlobalValueId, usize>,
+}
+
+impl SlotMap {
+    fn value(&self, guid: GlobalValueId) -> String {
+        self.values.get(&guid).map(|slot| format!("^{}", slot)).unwrap_or_else(|| guid.to_string())
+    }
+
+    fn type_id(&self, guid: GlobalValueId) -> String {
+        self.type_ids.get(&guid).map(|slot| format!("^{}", slot)).unwrap_or_else(|| guid.to_string())
+    }
+}
+
+impl fmt::Display for ModuleSummaryIndex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Slots: modules, then values and type ids in GUID order, then the rest
+        let mut next = self.modules.len();
+        let mut slots = SlotMap { values: HashMap::new(), type_ids: HashMap::new() };
+        for guid in self.values.keys() {
+            slots.values.insert(*guid, next);
+            next += 1;
+        }
+        for guid in self.type_ids.keys() {
+            slots.type_ids.insert(*guid, next);
+            next += 1;
+        }
+
+        for (slot, module) in self.modules.iter().enumerate() {
+            let hash = module.hash.iter().map(u32::to_string).collect::<Vec<_>>().join(", ");
+            writeln!(f, "^{} = module: (path: \"{}\", hash: ({}))", slot, module.path, hash)?;
+        }
+
+        for info in self.values.values() {
+            write!(f, "^{} = gv: (", slots.values[&info.guid])?;
+            match &info.name {
+                Some(name) => write!(f, "name: \"{}\"", name)?,
+                None => write!(f, "guid: {}", info.guid)?,
+            }
+            if !info.summaries.is_empty() {
+                write!(f, ", summaries: (")?;
+                for (i, summary) in info.summaries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_summary(f, summary, &slots)?;
+                }
+                write!(f, ")")?;
+            }
+            write!(f, ")")?;
+            if info.name.is_some() {
+                write!(f, " ; guid = {}", info.guid)?;
+            }
+            writeln!(f)?;
+        }
+
+        for (guid, type_id) in &self.type_ids {
+            let res = &type_id.resolution;
+            write!(f, "^{} = typeid: (name: \"{}\", summary: (typeTestRes: (kind: {}, sizeM1BitWidth: {}",
+                slots.type_ids[guid], type_id.name, res.kind.name(), res.size_m1_bit_width)?;
+            if res.align_log2 != 0 || res.size_m1 != 0 || res.bit_mask != 0 || res.inline_bits != 0 {
+                write!(f, ", alignLog2: {}, sizeM1: {}, bitMask: {}, inlineBits: {}",
+                    res.align_log2, res.size_m1, res.bit_mask, res.inline_bits)?;
+            }
+            writeln!(f, "))) ; guid = {}", guid)?;
+        }
+
+        for (guid, (name, vtables)) in &self.compatible_vtables {
+            write!(f, "^{} = typeidCompatibleVTable: (name: \"{}\", summary: (", next, name)?;
+            next += 1;
+            for (i, vtable) in vtables.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "(offset: {}, {})", vtable.offset, slots.value(vtable.vtable))?;
+            }
+            writeln!(f, ")) ; guid = {}", guid)?;
+        }
+
+        if let Some(flags) = self.flags {
+            writeln!(f, "^{} = flags: {}", next, flags)?;
+            next += 1;
+        }
+        if let Some(count) = self.block_count {
+            writeln!(f, "^{} = blockcount: {}", next, count)?;
+        }
+        Ok(())
+    }
+}
+
+fn write_flags(f: &mut fmt::Formatter<'_>, flags: &GlobalValueFlags) -> fmt::Result {
+    write!(f, "flags: (linkage: {}, visibility: {}, notEligibleToImport: {}, live: {}, dsoLocal: {}, canAutoHide: {})",
+        linkage_name(flags.linkage), visibility_name(flags.visibility),
+        flags.not_eligible_to_import as u8, flags.live as u8, flags.dso_local as u8, flags.can_auto_hide as u8)
+}
+
+fn write_refs(f: &mut fmt::Formatter<'_>, refs: &[ValueRef], slots: &SlotMap) -> fmt::Result {
+    if refs.is_empty() {
+        return Ok(());
+    }
+    write!(f, ", refs: (")?;
+    for (i, r) in refs.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        match r.access {
+            RefAccess::ReadOnly => write!(f, "readonly ")?,
+            RefAccess::WriteOnly => write!(f, "writeonly ")?,
+            RefAccess::ReadWrite => {}
+        }
+        write!(f, "{}", slots.value(r.guid))?;
+    }
+    write!(f, ")")
+}
+
+fn write_summary(f: &mut fmt::Formatter<'_>, summary: &GlobalValueSummary, slots: &SlotMap) -> fmt::Result {
+    match summary {
+        GlobalValueSummary::Function(function) => {
+            write!(f, "function: (module: ^{}, ", function.module)?;
+            write_flags(f, &function.flags)?;
+            write!(f, ", insts: {}", function.inst_count)?;
+            let ff = &function.function_flags;
+            if ff.any() {
+                write!(f, ", funcFlags: (readNone: {}, readOnly: {}, noRecurse: {}, returnDoesNotAlias: {}, noInline: {}, alwaysInline: {}, noUnwind: {}, mayThrow: {}, hasUnknownCall: {}, mustBeUnreachable: {})",
+                    ff.read_none as u8, ff.read_only as u8, ff.no_recurse as u8, ff.return_does_not_alias as u8,
+                    ff.no_inline as u8, ff.always_inline as u8, ff.no_unwind as u8, ff.may_throw as u8,
+                    ff.has_unknown_call as u8, ff.must_be_unreachable as u8)?;
+            }
+            if !function.calls.is_empty() {
+                write!(f, ", calls: (")?;
+                for (i, call) in function.calls.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "(callee: {}", slots.value(call.callee))?;
+                    if call.hotness != Hotness::Unknown {
+                        write!(f, ", hotness: {}", call.hotness.name())?;
+                    }
+                    if let Some(freq) = call.rel_block_freq {
+                        write!(f, ", relbf: {}", freq)?;
+                    }
+                    write!(f, ")")?;
+                }
+                write!(f, ")")?;
+            }
+            if !function.type_tests.is_empty() {
+                let tests: Vec<String> = function.type_tests.iter().map(|guid| slots.type_id(*guid)).collect();
+                write!(f, ", typeIdInfo: (typeTests: ({}))", tests.join(", "))?;
+            }
+            write_refs(f, &function.refs, slots)?;
+        }
+        GlobalValueSummary::Variable(variable) => {
+            write!(f, "variable: (module: ^{}, ", variable.module)?;
+            write_flags(f, &variable.flags)?;
+            write!(f, ", varFlags: (readonly: {}, writeonly: {}, constant: {})",
+                variable.read_only as u8, variable.write_only as u8, variable.constant as u8)?;
+            write_refs(f, &variable.refs, slots)?;
+        }
+        GlobalValueSummary::Alias(alias) => {
+            write!(f, "alias: (module: ^{}, ", alias.module)?;
+            write_flags(f, &alias.flags)?;
+            match alias.aliasee {
+                Some(guid) => write!(f, ", aliasee: {}", slots.value(guid))?,
+                None => write!(f, ", aliasee: null")?,
+            }
+        }
+    }
+    write!(f, ")")
+}
+
+// ===== Summary analysis =====
+
+/// Build the summary index of a module.
+///
+/// Every function, global variable and alias gets an entry; definitions also
+/// get a summary with their linkage, flags and references, and functions
+/// record their instruction count and direct call edges. Declarations appear
+/// by name only. Locals are hashed under the module's source file name.
+pub fn build_module_summary(module: &Module) -> ModuleSummaryIndex {
+    SummaryBuilder::new(module).build()
+}
+
+struct SummaryBuilder<'m> {
+    module: &'m Module,
+    source_filename: String,
+    /// Linkage of every global value defined or declared in the module
+    linkages: HashMap<String, Linkage>,
+    /// Names of global variables, whose references may be read- or write-only
+    variables: HashMap<String, bool>,
+}
+
+/// How a function body uses one global value
+#[derive(Default)]
+struct RefUse {
+    loaded: bool,
+    stored: bool,
+    other: bool,
+}
+
+impl<'m> SummaryBuilder<'m> {
+    fn new(module: &'m Module) -> Self {
+        let mut linkages = HashMap::new();
+        for function in module.functions() {
+            linkages.insert(function.name(), function.linkage());
+        }
+        let mut variables = HashMap::new();
+        for global in module.globals() {
+            linkages.insert(global.name.clone(), global.linkage);
+            variables.insert(global.name.clone(), global.is_constant);
+        }
+        for alias in module.aliases() {
+            linkages.insert(alias.name.clone(), alias.linkage);
+        }
+        Self {
+            module,
+            source_filename: module.source_filename().unwrap_or_else(|| module.name()),
+            linkages,
+            variables,
+        }
+    }
+
+    fn guid(&self, name: &str) -> GlobalValueId {
+        let linkage = self.linkages.get(name).copied().unwrap_or_default();
+        global_value_guid(&global_identifier(name, linkage, &self.source_filename))
+    }
+
+    fn flags(&self, linkage: Linkage, visibility: Visibility) -> GlobalValueFlags {
+        GlobalValueFlags {
+            linkage,
+            visibility,
+            not_eligible_to_import: false,
+            live: false,
+            // Locals and hidden symbols always resolve within the linkage unit
+            dso_local: matches!(linkage, Linkage::Internal | Linkage::Private) || visibility != Visibility::Default,
+            can_auto_hide: false,
+        }
+    }
+
+    fn build(&self) -> ModuleSummaryIndex {
+        let mut index = ModuleSummaryIndex::new();
+        let module_id = index.add_module(self.module.name(), [0; 5]);
+        let mut block_count = 0;
+
+        for function in self.module.functions() {
+            let name = function.name();
+            let guid = self.guid(&name);
+            if !function.has_body() {
+                index.get_or_insert_value(guid, Some(&name));
+                continue;
+            }
+            block_count += function.basic_block_count() as u64;
+            let summary = self.summarize_function(&function, module_id);
+            index.add_summary(guid, Some(&name), GlobalValueSummary::Function(summary));
+        }
+
+        for global in self.module.globals() {
+            let guid = self.guid(&global.name);
+            let Some(initializer) = &global.initializer else {
+                index.get_or_insert_value(guid, Some(&global.name));
+                continue;
+            };
+            let mut names = Vec::new();
+            collect_global_refs(initializer, &mut names);
+            let refs = self.dedup_refs(names.iter().map(|name| (name.clone(), RefAccess::ReadWrite)));
+
+            // Variables that cannot be internalized must keep every load and store
+            let can_internalize = global.comdat.is_none()
+                && !matches!(global.linkage, Linkage::Appending | Linkage::Weak | Linkage::Linkonce
+                    | Linkage::Common | Linkage::ExternWeak);
+            let summary = VariableSummary {
+                module: module_id,
+                flags: self.flags(global.linkage, global.visibility),
+                read_only: can_internalize,
+                write_only: can_internalize && !global.is_constant,
+                constant: global.is_constant,
+                refs,
+            };
+            index.add_summary(guid, Some(&global.name), GlobalValueSummary::Variable(summary));
+        }
+
+        for alias in self.module.aliases() {
+            let mut names = Vec::new();
+            collect_global_refs(&alias.aliasee, &mut names);
+            let aliasee = names.first().map(|name| self.guid(name));
+            let summary = AliasSummary {
+                module: module_id,
+                flags: self.flags(alias.linkage, alias.visibility),
+                aliasee,
+            };
+            index.add_summary(self.guid(&alias.name), Some(&alias.name), GlobalValueSummary::Alias(summary));
+        }
+
+        // Make sure every referenced value has an entry so references print as slots
+        let referenced: Vec<(GlobalValueId, String)> = self.linkages.keys()
+            .map(|name| (self.guid(name), name.clone()))
+            .collect();
+        for (guid, name) in referenced {
+            if index.value(guid).is_none() && self.is_referenced(&index, guid) {
+                index.get_or_insert_value(guid, Some(&name));
+            }
+        }
+
+        index.set_block_count(block_count);
+        index
+    }
+
+    fn is_referenced(&self, index: &ModuleSummaryIndex, guid: GlobalValueId) -> bool {
+        index.values().flat_map(|info| &info.summaries).any(|summary| {
+            summary.refs().iter().any(|r| r.guid == guid)
+                || matches!(summary, GlobalValueSummary::Function(f) if f.calls.iter().any(|c| c.callee == guid))
+        })
+    }
+
+    fn summarize_function(&self, function: &Function, module_id: usize) -> FunctionSummary {
+        let attrs = function.attributes();
+        let mut function_flags = FunctionFlags {
+            read_none: attrs.readnone,
+            read_only: attrs.readonly,
+            no_recurse: attrs.norecurse,
+            return_does_not_alias: attrs.return_attributes.noalias,
+            no_inline: attrs.noinline,
+            always_inline: attrs.alwaysinline,
+            no_unwind: attrs.nounwind,
+            ..FunctionFlags::default()
+        };
+
+        let instructions: Vec<Instruction> = function.basic_blocks().iter()
+            .flat_map(|bb| bb.instructions())
+            .collect();
+
+        let mut inst_count = 0;
+        let mut calls: Vec<CallEdge> = Vec::new();
+        let mut ref_order: Vec<String> = Vec::new();
+        let mut ref_uses: HashMap<String, RefUse> = HashMap::new();
+        let mut type_tests = Vec::new();
+
+        let mut note_ref = |name: String, update: &dyn Fn(&mut RefUse)| {
+            if !ref_uses.contains_key(&name) {
+                ref_order.push(name.clone());
+            }
+            update(ref_uses.entry(name).or_default());
+        };
+
+        for inst in &instructions {
+            let callee = called_value(inst);
+            let callee_name = callee.and_then(|c| c.is_global().then(|| c.name()).flatten());
+            if callee_name.is_some_and(|name| name.starts_with("llvm.dbg.")) {
+                continue;
+            }
+            inst_count += 1;
+
+            // Operands other than the callee, and the pointers of plain loads and stores
+            let (skip, loaded, stored) = match inst.opcode() {
+                _ if callee.is_some() => (Some(0), None, None),
+                Opcode::Load if !inst.is_volatile() => (Some(0), Some(0), None),
+                Opcode::Store if !inst.is_volatile() => (Some(1), None, Some(1)),
+                _ => (None, None, None),
+            };
+            for (i, operand) in inst.operands().iter().enumerate() {
+                let mut names = Vec::new();
+                collect_global_refs(operand, &mut names);
+                for name in names {
+                    if Some(i) == loaded && operand.is_global() {
+                        note_ref(name, &|u| u.loaded = true);
+                    } else if Some(i) == stored && operand.is_global() {
+                        note_ref(name, &|u| u.stored = true);
+                    } else if Some(i) != skip || !operand.is_global() {
+                        note_ref(name, &|u| u.other = true);
+                    }
+                }
+            }
+
+            match (inst.opcode(), callee, callee_name) {
+                (_, Some(_), Some(name)) if name.starts_with("llvm.") => {
+                    if matches!(name, "llvm.type.test" | "llvm.public.type.test") {
+                        if let Some(type_id) = inst.operands().get(2).and_then(Value::as_metadata_string) {
+                            if has_non_assume_use(inst, &instructions) {
+                                let guid = global_value_guid(type_id);
+                                if !type_tests.contains(&guid) {
+                                    type_tests.push(guid);
+                                }
+                            }
+                        }
+                    }
+                }
+                (_, Some(_), Some(name)) => {
+                    let guid = self.guid(name);
+                    if !calls.iter().any(|c| c.callee == guid) {
+                        calls.push(CallEdge { callee: guid, hotness: Hotness::Unknown, rel_block_freq: None });
+                    }
+                }
+                // Inline asm is parsed as an undef callee and is not a call to anything
+                (_, Some(callee), None) if !callee.is_undef() => function_flags.has_unknown_call = true,
+                (Opcode::Resume, _, _) => function_flags.may_throw = true,
+                _ => {}
+            }
+        }
+
+        function_flags.must_be_unreachable = function.entry_block()
+            .and_then(|bb| bb.terminator())
+            .is_some_and(|term| term.opcode() == Opcode::Unreachable);
+
+        let refs = self.dedup_refs(ref_order.into_iter().map(|name| {
+            let uses = &ref_uses[&name];
+            let access = if !self.variables.contains_key(&name) || uses.other || (uses.loaded && uses.stored) {
+                RefAccess::ReadWrite
+            } else if uses.loaded {
+                RefAccess::ReadOnly
+            } else {
+                RefAccess::WriteOnly
+            };
+            (name, access)
+        }));
+
+        FunctionSummary {
+            module: module_id,
+            flags: self.flags(function.linkage(), function.visibility()),
+            inst_count,
+            function_flags,
+            calls,
+            refs,
+            type_tests,
+        }
+    }
+
+    fn dedup_refs(&self, refs: impl Iterator<Item = (String, RefAccess)>) -> Vec<ValueRef> {
+        let mut result: Vec<ValueRef> = Vec::new();
+        for (name, access) in refs {
+            let guid = self.guid(&name);
+            if !result.iter().any(|r| r.guid == guid) {
+                result.push(ValueRef { guid, access });
+            }
+        }
+        result
+    }
+}
+
+/// The called operand of a call, invoke or callbr
+fn called_value(inst: &Instruction) -> Option<&Value> {
+    match inst.opcode() {
+        Opcode::Call | Opcode::Invoke | Opcode::CallBr => inst.operands().first(),
+        _ => None,
+    }
+}
+
+/// Whether the result of `inst` is used by anything but `llvm.assume`
+fn has_non_assume_use(inst: &Instruction, instructions: &[Instruction]) -> bool {
+    let Some(name) = inst.result().and_then(|r| r.name()) else {
+        return false;
+    };
+    instructions.iter().any(|user| {
+        let uses = user.operands().iter().any(|op| op.is_instruction() && op.name() == Some(name));
+        let is_assume = called_value(user).and_then(|c| c.name()) == Some("llvm.assume");
+        uses && !is_assume
+    })
+}
+
+/// Collect the names of global values referenced by a value, looking through
+/// constant aggregates and expressions
+fn collect_global_refs(value: &Value, names: &mut Vec<String>) {
+    match value.kind() {
+        ValueKind::GlobalVariable { .. } | ValueKind::Function => {
+            if let Some(name) = value.name() {
+                names.push(name.to_string());
+            }
+        }
+        ValueKind::ConstantArray { elements } | ValueKind::ConstantVector { elements } => {
+            elements.iter().for_each(|e| collect_global_refs(e, names));
+        }
+        ValueKind::ConstantStruct { fields } => fields.iter().for_each(|e| collect_global_refs(e, names)),
+        ValueKind::ConstantExpr { operands, .. } => operands.iter().for_each(|e| collect_global_refs(e, names)),
+        ValueKind::BlockAddress { function, .. } => collect_global_refs(function, names),
+        _ => {}
+    }
+}
+
+// ===== MD5 =====
+
+/// MD5 digest (RFC 1321), used only to derive GUIDs compatible with LLVM's
+fn md5(input: &[u8]) -> [u8; 16] {
+    const SHIFTS: [u32; 64] = [
+        7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22, 7, 12, 17, 22,
+        5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20, 5, 9, 14, 20,
+        4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23, 4, 11, 16, 23,
+        6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21, 6, 10, 15, 21,
+    ];
+    const K: [u32; 64] = [
+        0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+        0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+        0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+        0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+        0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+        0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+        0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+        0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391,
+    ];
+
+    let mut message = input.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((input.len() as u64).wrapping_mul(8)).to_le_bytes());
+
+    let mut state: [u32; 4] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476];
+    for chunk in message.chunks(64) {
+        let words: Vec<u32> = chunk.chunks(4).map(|w| u32::from_le_bytes(w.try_into().unwrap())).collect();
+        let [mut a, mut b, mut c, mut d] = state;
+        for i in 0..64 {
+            let (f, g) = match i / 16 {
+                0 => ((b & c) | (!b & d), i),
+                1 => ((d & b) | (!d & c), (5 * i + 1) % 16),
+                2 => (b ^ c ^ d, (3 * i + 5) % 16),
+                _ => (c ^ (b | !d), (7 * i) % 16),
+            };
+            let rotated = a.wrapping_add(f).wrapping_add(K[i]).wrapping_add(words[g]).rotate_left(SHIFTS[i]);
+            a = d;
+            d = c;
+            c = b;
+            b = b.wrapping_add(rotated);
+        }
+        state = [state[0].wrapping_add(a), state[1].wrapping_add(b), state[2].wrapping_add(c), state[3].wrapping_add(d)];
+    }
+
+    let mut digest = [0u8; 16];
+    for (i, word) in state.iter().enumerate() {
+        digest[i * 4..i * 4 + 4].copy_from_slice(&word.to_le_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_md5_known_digests() {
+        let hex = |d: [u8; 16]| d.iter().map(|b| format!("{:02x}", b)).collect::<String>();
+        assert_eq!(hex(md5(b"")), "d41d8cd98f00b204e9800998ecf8427e");
+        assert_eq!(hex(md5(b"h")), "2510c39011c5be704182423e3a695e91");
+        let long = "x".repeat(200);
+        assert_eq!(hex(md5(long.as_bytes())).len(), 32);
+    }
+
+    #[test]
+    fn test_guids_match_llvm() {
+        // Values printed by llvm-dis for a module named a.ll
+        assert_eq!(global_value_guid("f"), 14740650423002898831);
+        assert_eq!(global_value_guid("ind"), 10412197092519032572);
+        let local = global_identifier("h", Linkage::Internal, "a.ll");
+        assert_eq!(local, "a.ll:h");
+        assert_eq!(global_value_guid(&local), 4698780843822753391);
+        assert_eq!(global_identifier("\u{1}raw", Linkage::External, "a.ll"), "raw");
+    }
+
+    #[test]
+    fn test_merge_renumbers_modules() {
+        let mut first = ModuleSummaryIndex::new();
+        first.add_module("a.o".to_string(), [0; 5]);
+        let mut second = ModuleSummaryIndex::new();
+        let module = second.add_module("b.o".to_string(), [0; 5]);
+        second.add_summary(7, Some("g"), GlobalValueSummary::Alias(AliasSummary {
+            module,
+            flags: GlobalValueFlags::default(),
+            aliasee: None,
+        }));
+
+        first.merge(&second);
+        assert_eq!(first.modules().len(), 2);
+        assert_eq!(first.value(7).unwrap().summaries[0].module(), 1);
+    }
+}
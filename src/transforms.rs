@@ -7,7 +7,11 @@ use crate::function::Function;
 use crate::instruction::{Instruction, Opcode};
 use crate::value::Value;
 use crate::passes::{Pass, FunctionPass, PassResult};
-use crate::analysis::DominatorTree;
+use crate::analysis::{DominanceFrontier, DominanceFrontierPass, DominatorTreePass, LoopAnalysisPass, PostDominatorTreePass};
+use crate::analysis_manager::{AnalysisManager, PreservedAnalyses};
+use crate::constant_range::{mask, to_signed};
+use crate::lazy_value_info::LazyValueInfo;
+use crate::value_tracking::ValueTracking;
 
 /// Dead Code Elimination pass
 pub struct DeadCodeEliminationPass;
@@ -16,6 +20,11 @@ impl Pass for DeadCodeEliminationPass {
     fn name(&self) -> &str {
         "dce"
     }
+
+    /// Only non-terminators are removed, so the CFG is unchanged
+    fn preserved_analyses(&self) -> Vec<String> {
+        ["domtree", "postdomtree", "domfrontier", "loops"].map(String::from).to_vec()
+    }
 }
 
 impl FunctionPass for DeadCodeEliminationPass {
@@ -112,14 +121,17 @@ impl FunctionPass for ConstantFoldingPass {
 
         // Process each basic block
         let basic_blocks = function.basic_blocks();
+        let lvi = LazyValueInfo::new(function);
 
-        for bb in &basic_blocks {
+        for (block, bb) in basic_blocks.iter().enumerate() {
             let instructions = bb.instructions();
             let mut replacements = Vec::new();
 
             // Find instructions that can be folded
             for (idx, inst) in instructions.iter().enumerate() {
-                if let Some(folded_value) = self.try_fold_instruction(inst) {
+                let folded = self.try_fold_instruction(inst)
+                    .or_else(|| self.try_fold_with_ranges(inst, block, idx, &lvi));
+                if let Some(folded_value) = folded {
                     replacements.push((idx, folded_value));
                 }
             }
@@ -231,6 +243,22 @@ impl ConstantFoldingPass {
         }
     }
 
+    /// Fold an integer instruction whose operands are not all constant
+    /// but which can only produce one value at this point, by the known
+    /// bits and ranges of its operands
+    fn try_fold_with_ranges(&self, inst: &Instruction, block: usize, index: usize, lvi: &LazyValueInfo) -> Option<Value> {
+        let result = inst.result()?;
+        let width = result.get_type().int_width()?;
+        let operands = inst.operands();
+        let value = match inst.int_predicate() {
+            Some(predicate) if inst.opcode() == Opcode::ICmp && operands.len() == 2 => {
+                lvi.predicate_at(predicate, &operands[0], &operands[1], block, index)? as u128
+            }
+            _ => lvi.constant(result, block, index + 1)?,
+        };
+        Some(Value::const_int(result.get_type().clone(), to_signed(value, width) as i64, None))
+    }
+
     /// Fold a binary integer operation
     fn fold_binary_int<F>(&self, operands: &[Value], op: F) -> Option<Value>
     where
@@ -474,6 +502,7 @@ impl FunctionPass for InstructionCombiningPass {
 
         // Process each basic block
         let basic_blocks = function.basic_blocks();
+        let tracking = ValueTracking::new(function);
 
         for bb in &basic_blocks {
             let instructions = bb.instructions();
@@ -481,7 +510,9 @@ impl FunctionPass for InstructionCombiningPass {
 
             // Find instructions that can be simplified
             for (idx, inst) in instructions.iter().enumerate() {
-                if let Some(simplified) = self.try_simplify_instruction(inst) {
+                let simplified = self.try_simplify_instruction(inst)
+                    .or_else(|| self.try_simplify_with_known_bits(inst, &tracking));
+                if let Some(simplified) = simplified {
                     simplifications.push((idx, simplified));
                 }
             }
@@ -670,6 +701,61 @@ impl InstructionCombiningPass {
             _ => None,
         }
     }
+
+    /// Simplify bitwise operations whose effect the known bits of their
+    /// operands already decide, such as `and` with a mask covering every
+    /// bit that may be set
+    fn try_simplify_with_known_bits(&self, inst: &Instruction, tracking: &ValueTracking) -> Option<Value> {
+        let operands = inst.operands();
+        if operands.len() != 2 {
+            return None;
+        }
+        let (lhs, rhs) = (&operands[0], &operands[1]);
+        let width = lhs.get_type().int_width()?;
+        let (lhs_bits, rhs_bits) = (tracking.known_bits(lhs), tracking.known_bits(rhs));
+        let zero = || Value::const_int(lhs.get_type().clone(), 0, None);
+        let m = mask(width);
+
+        match inst.opcode() {
+            Opcode::And => {
+                // Every bit either may set is known one in the other
+                if lhs_bits.unsigned_max() & !rhs_bits.one & m == 0 {
+                    return Some(lhs.clone());
+                }
+                if rhs_bits.unsigned_max() & !lhs_bits.one & m == 0 {
+                    return Some(rhs.clone());
+                }
+                // No bit may be set in both
+                if lhs_bits.unsigned_max() & rhs_bits.unsigned_max() == 0 {
+                    return Some(zero());
+                }
+                None
+            }
+            Opcode::Or | Opcode::Xor => {
+                // The other operand may only set bits known zero here
+                if rhs_bits.unsigned_max() == 0 {
+                    return Some(lhs.clone());
+                }
+                if lhs_bits.unsigned_max() == 0 {
+                    return Some(rhs.clone());
+                }
+                if inst.opcode() == Opcode::Or && rhs_bits.unsigned_max() & !lhs_bits.one & m == 0 {
+                    return Some(lhs.clone());
+                }
+                if inst.opcode() == Opcode::Or && lhs_bits.unsigned_max() & !rhs_bits.one & m == 0 {
+                    return Some(rhs.clone());
+                }
+                None
+            }
+            // Shifting out only bits known zero leaves zero
+            Opcode::Shl | Opcode::LShr => {
+                let amount = rhs_bits.constant_value()?.min(width as u128) as u32;
+                let shifted = if inst.opcode() == Opcode::Shl { lhs_bits.shl(amount) } else { lhs_bits.lshr(amount) };
+                (amount < width && shifted.unsigned_max() == 0).then(zero)
+            }
+            _ => None,
+        }
+    }
 }
 
 /// Mem2Reg pass (promote memory to registers)
@@ -687,16 +773,36 @@ impl Pass for Mem2RegPass {
 
 impl FunctionPass for Mem2RegPass {
     fn run_on_function(&mut self, function: &mut Function) -> PassResult<bool> {
-        let mut changed = false;
+        Ok(self.promote(function, &DominanceFrontier::new(function)))
+    }
 
-        // Build dominator tree for phi placement
-        let domtree = DominatorTree::new(function);
+    /// Takes the dominator tree from the cache; only instructions change,
+    /// so the analyses of the CFG stay valid
+    fn run_with_analyses(&mut self, function: &mut Function, analyses: &mut AnalysisManager) -> PassResult<PreservedAnalyses> {
+        let dominator_tree = analyses.get_result::<DominatorTreePass>(function)?;
+        if !self.promote(function, &DominanceFrontier::from_dominator_tree(&dominator_tree)) {
+            return Ok(PreservedAnalyses::all());
+        }
+        let mut preserved = PreservedAnalyses::none();
+        preserved.preserve::<DominatorTreePass>()
+            .preserve::<PostDominatorTreePass>()
+            .preserve::<DominanceFrontierPass>()
+            .preserve::<LoopAnalysisPass>();
+        Ok(preserved)
+    }
+}
+
+impl Mem2RegPass {
+    /// Find the promotable allocas and where their phis go, given the
+    /// dominance frontiers for phi placement
+    fn promote(&self, function: &Function, frontiers: &DominanceFrontier) -> bool {
+        let mut changed = false;
 
         // Step 1: Find promotable allocas
         let promotable_allocas = self.find_promotable_allocas(function);
 
         if promotable_allocas.is_empty() {
-            return Ok(false);
+            return false;
         }
 
         // Step 2: For each promotable alloca, collect all uses (loads/stores)
@@ -705,7 +811,7 @@ impl FunctionPass for Mem2RegPass {
                 let uses = self.collect_uses(function, alloca_name);
 
                 // Step 3: Compute dominance frontiers and insert phi nodes
-                let phi_locations = self.compute_phi_locations(function, &uses, &domtree);
+                let phi_locations = self.compute_phi_locations(&uses, frontiers);
 
                 // Step 4: Rename variables (SSA construction)
                 // In a full implementation, this would:
@@ -721,11 +827,9 @@ impl FunctionPass for Mem2RegPass {
             }
         }
 
-        Ok(changed)
+        changed
     }
-}
 
-impl Mem2RegPass {
     /// Find all allocas that can be promoted to registers
     fn find_promotable_allocas(&self, function: &Function) -> Vec<Instruction> {
         let mut promotable = Vec::new();
@@ -802,15 +906,13 @@ impl Mem2RegPass {
         uses
     }
 
-    /// Compute where phi nodes need to be inserted
+    /// Compute where phi nodes need to be inserted: the iterated dominance
+    /// frontier of the blocks that store to the alloca
     fn compute_phi_locations(
         &self,
-        function: &Function,
         uses: &HashMap<usize, Vec<Instruction>>,
-        domtree: &DominatorTree
+        frontiers: &DominanceFrontier
     ) -> HashSet<usize> {
-        let mut phi_locations = HashSet::new();
-
         // Find all blocks that contain stores (definitions)
         let mut def_blocks = HashSet::new();
         for (block_idx, insts) in uses {
@@ -821,21 +923,7 @@ impl Mem2RegPass {
             }
         }
 
-        // Simplified phi placement: insert at blocks dominated by multiple defs
-        // A full implementation would compute dominance frontiers
-        for block_idx in 0..function.basic_blocks().len() {
-            let mut dominating_defs = 0;
-            for &def_block in &def_blocks {
-                if domtree.dominates(def_block, block_idx) {
-                    dominating_defs += 1;
-                }
-            }
-            if dominating_defs > 1 {
-                phi_locations.insert(block_idx);
-            }
-        }
-
-        phi_locations
+        frontiers.iterated_frontier(&def_blocks)
     }
 }
 
@@ -249,6 +249,28 @@ impl Type {
         }
     }
 
+    /// Get the kind of a floating point type
+    pub fn float_kind(&self) -> Option<FloatKind> {
+        match &*self.data {
+            TypeData::Float { kind } => Some(*kind),
+            _ => None,
+        }
+    }
+
+    /// Get the name of a named struct or opaque type
+    pub fn struct_name(&self) -> Option<&str> {
+        match &*self.data {
+            TypeData::Struct { name, .. } => name.as_deref(),
+            TypeData::Opaque { name } => Some(name.as_str()),
+            _ => None,
+        }
+    }
+
+    /// Check if this is a packed struct type
+    pub fn is_packed_struct(&self) -> bool {
+        matches!(&*self.data, TypeData::Struct { packed: true, .. })
+    }
+
     /// Get the element type of a pointer
     pub fn pointee_type(&self) -> Option<&Type> {
         match &*self.data {
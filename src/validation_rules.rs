@@ -11,19 +11,37 @@ use crate::function::{Function, ParameterAttributes, CallingConvention};
 use crate::types::Type;
 use crate::module::{Module, Linkage, Visibility};
 use crate::verification::VerificationError;
+use crate::diagnostics::{Diagnostic, Rule, VerifierOptions};
 
 /// Collection of IR validation rules
 pub struct ValidationRules {
-    errors: Vec<VerificationError>,
+    options: VerifierOptions,
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl ValidationRules {
     pub fn new() -> Self {
+        Self::with_options(VerifierOptions::default())
+    }
+
+    /// Validation rules reported according to `options`
+    pub fn with_options(options: VerifierOptions) -> Self {
         Self {
-            errors: Vec::new(),
+            options,
+            diagnostics: Vec::new(),
         }
     }
 
+    /// Diagnostics collected so far, warnings included, leaving none behind
+    pub fn take_diagnostics(&mut self) -> Vec<Diagnostic> {
+        std::mem::take(&mut self.diagnostics)
+    }
+
+    /// Record `error` under `rule`, unless the options disable the rule
+    fn report(&mut self, rule: Rule, error: VerificationError) {
+        self.diagnostics.extend(self.options.diagnostic(rule, error));
+    }
+
     /// Run all extended validation checks on a module
     pub fn validate_module(&mut self, module: &Module) -> Result<(), Vec<VerificationError>> {
         // Validate all functions
@@ -41,11 +59,7 @@ impl ValidationRules {
             self.validate_global_linkage_visibility(&alias.name, alias.linkage, alias.visibility);
         }
 
-        if self.errors.is_empty() {
-            Ok(())
-        } else {
-            Err(std::mem::take(&mut self.errors))
-        }
+        crate::verification::errors_of(&self.diagnostics)
     }
 
     /// Validate a single function
@@ -85,7 +99,7 @@ impl ValidationRules {
             CallingConvention::AMDGPU_Kernel => {
                 // Must return void
                 if !return_type.is_void() {
-                    self.errors.push(VerificationError::InvalidCall {
+                    self.report(Rule::CallingConvention, VerificationError::InvalidCall {
                         expected_args: 0,
                         found_args: 0,
                         location: format!(
@@ -97,7 +111,7 @@ impl ValidationRules {
 
                 // No varargs
                 if is_varargs {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::CallingConvention, VerificationError::InvalidInstruction {
                         reason: "Calling convention does not support varargs or perfect forwarding!".to_string(),
                         location: format!("function {}", function.name()),
                     });
@@ -107,7 +121,7 @@ impl ValidationRules {
                 let attrs = function.attributes();
                 for (idx, param_attrs) in attrs.parameter_attributes.iter().enumerate() {
                     if param_attrs.sret.is_some() {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::CallingConvention, VerificationError::InvalidInstruction {
                             reason: "Calling convention does not allow sret".to_string(),
                             location: format!("function {} parameter {}", function.name(), idx),
                         });
@@ -115,7 +129,7 @@ impl ValidationRules {
 
                     // No byval
                     if param_attrs.byval.is_some() {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::CallingConvention, VerificationError::InvalidInstruction {
                             reason: "Calling convention disallows byval".to_string(),
                             location: format!("function {} parameter {}", function.name(), idx),
                         });
@@ -123,7 +137,7 @@ impl ValidationRules {
 
                     // No inalloca
                     if param_attrs.inalloca.is_some() {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::CallingConvention, VerificationError::InvalidInstruction {
                             reason: "Calling convention disallows inalloca".to_string(),
                             location: format!("function {} parameter {}", function.name(), idx),
                         });
@@ -133,7 +147,7 @@ impl ValidationRules {
                     if param_attrs.byref.is_some() {
                         // Note: We can't check address space without parser support,
                         // but we can at least reject all byref for now
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::CallingConvention, VerificationError::InvalidInstruction {
                             reason: "Calling convention disallows stack byref".to_string(),
                             location: format!("function {} parameter {}", function.name(), idx),
                         });
@@ -148,7 +162,7 @@ impl ValidationRules {
             | CallingConvention::AMDGPU_HS => {
                 // No varargs
                 if is_varargs {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::CallingConvention, VerificationError::InvalidInstruction {
                         reason: "Calling convention does not support varargs or perfect forwarding!".to_string(),
                         location: format!("function {}", function.name()),
                     });
@@ -158,14 +172,14 @@ impl ValidationRules {
                 let attrs = function.attributes();
                 for (idx, param_attrs) in attrs.parameter_attributes.iter().enumerate() {
                     if param_attrs.byval.is_some() {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::CallingConvention, VerificationError::InvalidInstruction {
                             reason: "Calling convention disallows byval".to_string(),
                             location: format!("function {} parameter {}", function.name(), idx),
                         });
                     }
 
                     if param_attrs.byref.is_some() {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::CallingConvention, VerificationError::InvalidInstruction {
                             reason: "Calling convention disallows stack byref".to_string(),
                             location: format!("function {} parameter {}", function.name(), idx),
                         });
@@ -184,7 +198,7 @@ impl ValidationRules {
 
         // immarg is not valid on functions - only on parameters
         if func_attrs.has_immarg {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::FunctionAttributes, VerificationError::InvalidInstruction {
                 reason: "this attribute does not apply to functions".to_string(),
                 location: format!("@{}", function.name()),
             });
@@ -198,7 +212,7 @@ impl ValidationRules {
 
         // immarg is not valid on return values
         if ret_attrs.has_immarg {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::ReturnAttributes, VerificationError::InvalidInstruction {
                 reason: "this attribute does not apply to return values".to_string(),
                 location: format!("@{}", function.name()),
             });
@@ -248,7 +262,7 @@ impl ValidationRules {
         // Note: preallocated not currently in ParameterAttributes struct
 
         if exclusive_attrs.len() > 1 {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::AttributeExclusivity, VerificationError::InvalidInstruction {
                 reason: format!(
                     "Attributes 'byval', 'inalloca', 'preallocated', 'inreg', 'nest', 'byref', and 'sret' are incompatible!"
                 ),
@@ -258,7 +272,7 @@ impl ValidationRules {
 
         // immarg is incompatible with byval, byref, inalloca, sret, nest, and other non-range attributes
         if attrs.immarg && !exclusive_attrs.is_empty() {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::AttributeExclusivity, VerificationError::InvalidInstruction {
                 reason: "Attribute 'immarg' is incompatible with other attributes except the 'range' attribute".to_string(),
                 location: format!("@{}", func_name),
             });
@@ -278,21 +292,21 @@ impl ValidationRules {
         // byval can only be applied to pointer types
         if let Some(byval_type) = &attrs.byval {
             if !param_type.is_pointer() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::AttributeType, VerificationError::InvalidInstruction {
                     reason: format!("Attribute 'byval({})' applied to incompatible type!", byval_type),
                     location: format!("@{}", func_name),
                 });
             }
             // byval type cannot be void
             if byval_type.is_void() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::AttributeType, VerificationError::InvalidInstruction {
                     reason: "void type only allowed for function results".to_string(),
                     location: format!("@{}", func_name),
                 });
             }
             // byval does not support function types (unsized)
             if byval_type.is_function() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::AttributeType, VerificationError::InvalidInstruction {
                     reason: "Attribute 'byval' does not support unsized types!".to_string(),
                     location: format!("@{}", func_name),
                 });
@@ -302,21 +316,21 @@ impl ValidationRules {
         // inalloca can only be applied to pointer types
         if let Some(inalloca_type) = &attrs.inalloca {
             if !param_type.is_pointer() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::AttributeType, VerificationError::InvalidInstruction {
                     reason: format!("Attribute 'inalloca({})' applied to incompatible type!", inalloca_type),
                     location: format!("@{}", func_name),
                 });
             }
             // inalloca type cannot be void
             if inalloca_type.is_void() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::AttributeType, VerificationError::InvalidInstruction {
                     reason: "void type only allowed for function results".to_string(),
                     location: format!("@{}", func_name),
                 });
             }
             // inalloca does not support function types (unsized)
             if inalloca_type.is_function() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::AttributeType, VerificationError::InvalidInstruction {
                     reason: "Attribute 'inalloca' does not support unsized types!".to_string(),
                     location: format!("@{}", func_name),
                 });
@@ -326,21 +340,21 @@ impl ValidationRules {
         // byref can only be applied to pointer types
         if let Some(byref_type) = &attrs.byref {
             if !param_type.is_pointer() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::AttributeType, VerificationError::InvalidInstruction {
                     reason: format!("Attribute 'byref({})' applied to incompatible type!", byref_type),
                     location: format!("@{}", func_name),
                 });
             }
             // byref type cannot be void
             if byref_type.is_void() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::AttributeType, VerificationError::InvalidInstruction {
                     reason: "void type only allowed for function results".to_string(),
                     location: format!("@{}", func_name),
                 });
             }
             // byref does not support function types (unsized)
             if byref_type.is_function() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::AttributeType, VerificationError::InvalidInstruction {
                     reason: "Attribute 'byref' does not support unsized types!".to_string(),
                     location: format!("@{}", func_name),
                 });
@@ -350,21 +364,21 @@ impl ValidationRules {
         // sret can only be applied to pointer types
         if let Some(sret_type) = &attrs.sret {
             if !param_type.is_pointer() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::AttributeType, VerificationError::InvalidInstruction {
                     reason: format!("Attribute 'sret({})' applied to incompatible type!", sret_type),
                     location: format!("@{}", func_name),
                 });
             }
             // sret type cannot be void
             if sret_type.is_void() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::AttributeType, VerificationError::InvalidInstruction {
                     reason: "void type only allowed for function results".to_string(),
                     location: format!("@{}", func_name),
                 });
             }
             // sret does not support function types (unsized)
             if sret_type.is_function() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::AttributeType, VerificationError::InvalidInstruction {
                     reason: "Attribute 'sret' does not support unsized types!".to_string(),
                     location: format!("@{}", func_name),
                 });
@@ -374,7 +388,7 @@ impl ValidationRules {
         // align can only be applied to pointer types
         if let Some(align_val) = attrs.align {
             if !param_type.is_pointer() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::AttributeType, VerificationError::InvalidInstruction {
                     reason: format!("Attribute 'align {}' applied to incompatible type!", align_val),
                     location: format!("@{}", func_name),
                 });
@@ -382,7 +396,7 @@ impl ValidationRules {
 
             // Alignment must be a power of two
             if align_val == 0 || (align_val & (align_val - 1)) != 0 {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::AttributeType, VerificationError::InvalidInstruction {
                     reason: "alignment is not a power of two".to_string(),
                     location: format!("@{}", func_name),
                 });
@@ -391,7 +405,7 @@ impl ValidationRules {
             // Check for huge alignments (> 2^29)
             const MAX_ALIGN: u32 = 1 << 29;
             if align_val > MAX_ALIGN {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::AttributeType, VerificationError::InvalidInstruction {
                     reason: "huge alignments are not supported yet".to_string(),
                     location: format!("@{}", func_name),
                 });
@@ -408,7 +422,7 @@ impl ValidationRules {
                 || param_type.is_array();
 
             if !is_valid_immarg {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::AttributeType, VerificationError::InvalidInstruction {
                     reason: "immarg attribute only applies to integers, floats, vectors, and arrays".to_string(),
                     location: format!("@{}", func_name),
                 });
@@ -417,7 +431,7 @@ impl ValidationRules {
 
         // mustprogress does not apply to parameters - it's a function-level attribute only
         if attrs.mustprogress {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::AttributeType, VerificationError::InvalidInstruction {
                 reason: "this attribute does not apply to parameters".to_string(),
                 location: format!("@{}", func_name),
             });
@@ -436,7 +450,7 @@ impl ValidationRules {
         // Symbols with local linkage (Internal or Private) must have default visibility
         if matches!(linkage, Linkage::Internal | Linkage::Private)
             && !matches!(visibility, Visibility::Default) {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::LinkageVisibility, VerificationError::InvalidInstruction {
                 reason: "symbol with local linkage must have default visibility".to_string(),
                 location: format!("@{}", name),
             });
@@ -471,9 +485,9 @@ mod tests {
         let mut verifier = ValidationRules::new();
         verifier.check_attribute_exclusivity(&param_attrs, "test", 0);
 
-        assert!(!verifier.errors.is_empty(), "Should detect incompatible attributes");
+        assert!(!verifier.diagnostics.is_empty(), "Should detect incompatible attributes");
         assert!(
-            format!("{:?}", verifier.errors[0]).contains("incompatible"),
+            format!("{:?}", verifier.diagnostics[0].error).contains("incompatible"),
             "Error should mention incompatibility"
         );
     }
@@ -496,9 +510,9 @@ mod tests {
         let mut verifier = ValidationRules::new();
         verifier.validate_calling_convention_constraints(&func);
 
-        assert!(!verifier.errors.is_empty(), "Should reject sret in amdgpu_kernel");
+        assert!(!verifier.diagnostics.is_empty(), "Should reject sret in amdgpu_kernel");
         assert!(
-            format!("{:?}", verifier.errors).to_lowercase().contains("sret"),
+            format!("{:?}", verifier.diagnostics).to_lowercase().contains("sret"),
             "Error should mention sret"
         );
     }
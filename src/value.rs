@@ -5,6 +5,7 @@
 
 use std::sync::Arc;
 use std::fmt;
+use crate::metadata::Metadata;
 use crate::types::Type;
 
 /// A value in LLVM IR
@@ -52,6 +53,10 @@ pub enum ValueKind {
     GlobalVariable { is_constant: bool },
     /// Block address
     BlockAddress { function: Box<Value>, block: Box<Value> },
+    /// A metadata string passed as a value (`metadata !"str"`)
+    MetadataString { value: String },
+    /// Other metadata passed as a value (`metadata !0`, `metadata i32 %x`)
+    Metadata { node: Metadata },
 }
 
 impl Value {
@@ -66,6 +71,16 @@ impl Value {
         &self.data.ty
     }
 
+    /// Get the kind of this value
+    pub(crate) fn kind(&self) -> &ValueKind {
+        &self.data.kind
+    }
+
+    /// Identity of the underlying value, shared by all clones
+    pub(crate) fn data_ptr(&self) -> usize {
+        Arc::as_ptr(&self.data) as usize
+    }
+
     /// Get the name of this value, if it has one
     pub fn name(&self) -> Option<&str> {
         self.data.name.as_deref()
@@ -103,6 +118,11 @@ impl Value {
         matches!(&self.data.kind, ValueKind::Instruction { .. })
     }
 
+    /// Check if this value is a basic block label
+    pub fn is_basic_block(&self) -> bool {
+        matches!(&self.data.kind, ValueKind::BasicBlock)
+    }
+
     /// Check if this value is a global variable
     pub fn is_global(&self) -> bool {
         matches!(&self.data.kind, ValueKind::GlobalVariable { .. })
@@ -186,6 +206,16 @@ impl Value {
         }, None)
     }
 
+    /// Create a metadata string operand (`metadata !"str"`)
+    pub fn metadata_string(ty: Type, value: String) -> Self {
+        Self::new(ty, ValueKind::MetadataString { value }, None)
+    }
+
+    /// Create a metadata operand (`metadata !0`, `metadata i32 %x`)
+    pub fn metadata(ty: Type, node: Metadata) -> Self {
+        Self::new(ty, ValueKind::Metadata { node }, None)
+    }
+
     /// Create a function argument value
     pub fn argument(ty: Type, index: usize, name: Option<String>) -> Self {
         Self::new(ty, ValueKind::Argument { index }, name)
@@ -213,6 +243,22 @@ impl Value {
         }
     }
 
+    /// Try to extract this value as a metadata string operand
+    pub fn as_metadata_string(&self) -> Option<&str> {
+        match &self.data.kind {
+            ValueKind::MetadataString { value } => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Try to extract this value as a metadata operand other than a string
+    pub fn as_metadata(&self) -> Option<&Metadata> {
+        match &self.data.kind {
+            ValueKind::Metadata { node } => Some(node),
+            _ => None,
+        }
+    }
+
     /// Check if this is a constant integer with a specific value
     pub fn is_const_int_value(&self, expected: i64) -> bool {
         self.as_const_int() == Some(expected)
@@ -280,6 +326,30 @@ impl Value {
             _ => None,
         }
     }
+
+    /// Get struct fields if this is a constant struct
+    pub fn struct_fields(&self) -> Option<&Vec<Value>> {
+        match &self.data.kind {
+            ValueKind::ConstantStruct { fields } => Some(fields),
+            _ => None,
+        }
+    }
+
+    /// Get the opcode and operands if this is a constant expression
+    pub fn as_const_expr(&self) -> Option<(crate::instruction::Opcode, &[Value])> {
+        match &self.data.kind {
+            ValueKind::ConstantExpr { opcode, operands } => Some((*opcode, operands)),
+            _ => None,
+        }
+    }
+
+    /// Get vector elements if this is a constant vector
+    pub fn vector_elements(&self) -> Option<&Vec<Value>> {
+        match &self.data.kind {
+            ValueKind::ConstantVector { elements } => Some(elements),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for Value {
@@ -346,6 +416,11 @@ impl fmt::Display for Value {
             ValueKind::BlockAddress { function, block } => {
                 write!(f, "blockaddress({}, {})", function, block)
             }
+            ValueKind::MetadataString { value } => write!(f, "!\"{}\"", value),
+            ValueKind::Metadata { node } => match node.as_value() {
+                Some(value) => write!(f, "{} {}", value.get_type(), value),
+                None => write!(f, "{}", node),
+            },
             ValueKind::Argument { index } => {
                 if let Some(name) = &self.data.name {
                     write!(f, "%{}", name)
@@ -0,0 +1,300 @@
+//! Value Tracking
+//!
+//! Facts about the bits of integer values, like LLVM's ValueTracking:
+//! `KnownBits` records the bits proven zero and proven one. They are
+//! computed from constants, `!range` metadata, and the bitwise, shift,
+//! arithmetic and cast instructions that define a value, looking a few
+//! instructions deep.
+
+use std::collections::HashMap;
+use crate::analysis::ValueKey;
+use crate::constant_range::{mask, to_signed, ConstantRange};
+use crate::function::Function;
+use crate::instruction::{Instruction, Opcode};
+use crate::value::Value;
+
+/// How many definitions deep `known_bits` looks, as in LLVM
+const MAX_DEPTH: u32 = 6;
+
+/// Bits of an integer that are known to be zero or one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KnownBits {
+    pub width: u32,
+    /// Bits known to be zero
+    pub zero: u128,
+    /// Bits known to be one
+    pub one: u128,
+}
+
+impl KnownBits {
+    /// Nothing known
+    pub fn unknown(width: u32) -> Self {
+        Self { width, zero: 0, one: 0 }
+    }
+
+    /// Every bit known, from the low `width` bits of `value`
+    pub fn constant(width: u32, value: u128) -> Self {
+        let value = value & mask(width);
+        Self { width, zero: !value & mask(width), one: value }
+    }
+
+    /// Bits common to the unsigned bounds of a range
+    pub fn from_range(range: &ConstantRange) -> Self {
+        let width = range.width();
+        if range.is_empty_set() {
+            return Self::unknown(width);
+        }
+        let (min, max) = (range.unsigned_min(), range.unsigned_max());
+        let differing = 128 - (min ^ max).leading_zeros();
+        let common = mask(width) & !mask(differing);
+        Self { width, zero: !min & common, one: min & common }
+    }
+
+    pub fn is_constant(&self) -> bool {
+        self.zero | self.one == mask(self.width)
+    }
+
+    /// The value, if every bit is known
+    pub fn constant_value(&self) -> Option<u128> {
+        self.is_constant().then_some(self.one)
+    }
+
+    pub fn unsigned_min(&self) -> u128 {
+        self.one
+    }
+
+    pub fn unsigned_max(&self) -> u128 {
+        !self.zero & mask(self.width)
+    }
+
+    /// The values with these bits, in unsigned order
+    pub fn to_range(&self) -> ConstantRange {
+        ConstantRange::from_unsigned(self.width, self.unsigned_min(), self.unsigned_max())
+    }
+
+    /// Bits known the same way in both, for a value that is one or the
+    /// other
+    pub fn intersect_with(&self, other: &Self) -> Self {
+        Self { width: self.width, zero: self.zero & other.zero, one: self.one & other.one }
+    }
+
+    /// Bits known in either, for a value both describe
+    pub fn union_with(&self, other: &Self) -> Self {
+        Self { width: self.width, zero: self.zero | other.zero, one: self.one | other.one }
+    }
+
+    pub fn and(&self, other: &Self) -> Self {
+        Self { width: self.width, zero: self.zero | other.zero, one: self.one & other.one }
+    }
+
+    pub fn or(&self, other: &Self) -> Self {
+        Self { width: self.width, zero: self.zero & other.zero, one: self.one | other.one }
+    }
+
+    pub fn xor(&self, other: &Self) -> Self {
+        Self {
+            width: self.width,
+            zero: (self.zero & other.zero) | (self.one & other.one),
+            one: (self.zero & other.one) | (self.one & other.zero),
+        }
+    }
+
+    /// Complement of every bit
+    pub fn not(&self) -> Self {
+        Self { width: self.width, zero: self.one, one: self.zero }
+    }
+
+    /// Sum with a carry into the lowest bit, as in LLVM's
+    /// `KnownBits::computeForAddCarry`
+    fn add_with_carry(&self, other: &Self, carry_zero: bool, carry_one: bool) -> Self {
+        let m = mask(self.width);
+        let possible_sum_zero = (!self.zero & m).wrapping_add(!other.zero & m).wrapping_add(!carry_zero as u128) & m;
+        let possible_sum_one = self.one.wrapping_add(other.one).wrapping_add(carry_one as u128) & m;
+        let carry_known_zero = !(possible_sum_zero ^ self.zero ^ other.zero) & m;
+        let carry_known_one = (possible_sum_one ^ self.one ^ other.one) & m;
+        let known = (self.zero | self.one) & (other.zero | other.one) & (carry_known_zero | carry_known_one);
+        Self { width: self.width, zero: !possible_sum_zero & known, one: possible_sum_one & known }
+    }
+
+    pub fn add(&self, other: &Self) -> Self {
+        self.add_with_carry(other, true, false)
+    }
+
+    pub fn sub(&self, other: &Self) -> Self {
+        self.add_with_carry(&other.not(), false, true)
+    }
+
+    /// Products keep the trailing zeros of both factors
+    pub fn mul(&self, other: &Self) -> Self {
+        let trailing = |bits: &Self| (bits.zero | !mask(self.width)).trailing_ones();
+        Self::from_trailing_zeros(self.width, trailing(self) + trailing(other))
+    }
+
+    fn from_trailing_zeros(width: u32, count: u32) -> Self {
+        Self { width, zero: mask(count.min(width)), one: 0 }
+    }
+
+    /// Shift left by a known amount; shifting out every bit is poison,
+    /// about which nothing is known
+    pub fn shl(&self, amount: u32) -> Self {
+        if amount >= self.width {
+            return Self::unknown(self.width);
+        }
+        let m = mask(self.width);
+        Self { width: self.width, zero: (self.zero << amount | mask(amount)) & m, one: self.one << amount & m }
+    }
+
+    pub fn lshr(&self, amount: u32) -> Self {
+        if amount >= self.width {
+            return Self::unknown(self.width);
+        }
+        let high = mask(self.width) & !(mask(self.width) >> amount);
+        Self { width: self.width, zero: self.zero >> amount | high, one: self.one >> amount }
+    }
+
+    pub fn ashr(&self, amount: u32) -> Self {
+        if amount >= self.width {
+            return Self::unknown(self.width);
+        }
+        let shift = |bits: u128| (to_signed(bits, self.width) >> amount) as u128 & mask(self.width);
+        Self { width: self.width, zero: shift(self.zero), one: shift(self.one) }
+    }
+
+    pub fn zext(&self, width: u32) -> Self {
+        Self { width, zero: self.zero | (mask(width) & !mask(self.width)), one: self.one }
+    }
+
+    pub fn sext(&self, width: u32) -> Self {
+        let extend = |bits: u128| to_signed(bits, self.width) as u128 & mask(width);
+        Self { width, zero: extend(self.zero), one: extend(self.one) }
+    }
+
+    pub fn trunc(&self, width: u32) -> Self {
+        Self { width, zero: self.zero & mask(width), one: self.one & mask(width) }
+    }
+}
+
+/// Range given by an instruction's `!range` metadata: pairs of bounds
+/// `[lower, upper)`, merged into one range
+pub(crate) fn range_metadata(inst: &Instruction, width: u32) -> Option<ConstantRange> {
+    let bounds: Vec<u128> = inst.metadata("range")?
+        .as_tuple()?
+        .iter()
+        .map(|bound| bound.as_int().or_else(|| bound.as_value()?.as_const_int()).map(|v| v as u128))
+        .collect::<Option<_>>()?;
+    if bounds.is_empty() || !bounds.len().is_multiple_of(2) {
+        return None;
+    }
+    Some(bounds.chunks(2).fold(ConstantRange::empty(width), |range, pair| {
+        range.union_with(&ConstantRange::new(width, pair[0], pair[1]))
+    }))
+}
+
+/// Known bits of the values of one function
+pub struct ValueTracking {
+    /// Defining instruction of each local value
+    definitions: HashMap<ValueKey, Instruction>,
+}
+
+impl ValueTracking {
+    pub fn new(function: &Function) -> Self {
+        let mut definitions = HashMap::new();
+        for bb in function.basic_blocks() {
+            for inst in bb.instructions() {
+                if let Some(result) = inst.result() {
+                    definitions.insert(ValueKey::of(result), inst.clone());
+                }
+            }
+        }
+        Self { definitions }
+    }
+
+    /// Defining instruction of a value, if it is an instruction result
+    pub fn definition(&self, value: &Value) -> Option<&Instruction> {
+        self.definitions.get(&ValueKey::of(value))
+    }
+
+    /// Known bits of an integer value; nothing is known about values of
+    /// other types, which have width 0
+    pub fn known_bits(&self, value: &Value) -> KnownBits {
+        self.known_bits_at_depth(value, 0)
+    }
+
+    fn known_bits_at_depth(&self, value: &Value, depth: u32) -> KnownBits {
+        let Some(width) = value.get_type().int_width() else {
+            return KnownBits::unknown(0);
+        };
+        if let Some(constant) = value.as_const_int() {
+            return KnownBits::constant(width, constant as u128);
+        }
+        let unknown = KnownBits::unknown(width);
+        let Some(inst) = self.definition(value).filter(|_| depth < MAX_DEPTH) else {
+            return unknown;
+        };
+        let operands = inst.operands();
+        let operand = |index: usize| operands.get(index)
+            .map(|operand| self.known_bits_at_depth(operand, depth + 1))
+            .filter(|bits| bits.width != 0);
+        let shift_amount = || operand(1).and_then(|bits| bits.constant_value()).map(|amount| amount.min(128) as u32);
+
+        let bits = match inst.opcode() {
+            Opcode::And => operand(0).zip(operand(1)).map(|(a, b)| a.and(&b)),
+            Opcode::Or => operand(0).zip(operand(1)).map(|(a, b)| a.or(&b)),
+            Opcode::Xor => operand(0).zip(operand(1)).map(|(a, b)| a.xor(&b)),
+            Opcode::Add => operand(0).zip(operand(1)).map(|(a, b)| a.add(&b)),
+            Opcode::Sub => operand(0).zip(operand(1)).map(|(a, b)| a.sub(&b)),
+            Opcode::Mul => operand(0).zip(operand(1)).map(|(a, b)| a.mul(&b)),
+            Opcode::Shl => operand(0).zip(shift_amount()).map(|(a, amount)| a.shl(amount)),
+            Opcode::LShr => operand(0).zip(shift_amount()).map(|(a, amount)| a.lshr(amount)),
+            Opcode::AShr => operand(0).zip(shift_amount()).map(|(a, amount)| a.ashr(amount)),
+            Opcode::UDiv | Opcode::URem => operand(0).zip(operand(1)).map(|(a, b)| {
+                let (a, b) = (a.to_range(), b.to_range());
+                let range = if inst.opcode() == Opcode::UDiv {
+                    a.udiv(&b)
+                } else {
+                    // A remainder is below the divisor and no larger than the dividend
+                    ConstantRange::from_unsigned(width, 0, a.unsigned_max().min(b.unsigned_max().saturating_sub(1)))
+                };
+                KnownBits::from_range(&range)
+            }),
+            Opcode::ZExt => operand(0).map(|a| a.zext(width)),
+            Opcode::SExt => operand(0).map(|a| a.sext(width)),
+            Opcode::Trunc => operand(0).map(|a| a.trunc(width)),
+            Opcode::Select => operand(1).zip(operand(2)).map(|(a, b)| a.intersect_with(&b)),
+            // Only one level through phis, which may go around loops
+            Opcode::PHI => operands.iter()
+                .step_by(2)
+                .map(|incoming| self.known_bits_at_depth(incoming, (MAX_DEPTH - 1).max(depth + 1)))
+                .reduce(|a, b| a.intersect_with(&b)),
+            _ => None,
+        }
+        .filter(|bits| bits.width == width)
+        .unwrap_or(unknown);
+
+        match range_metadata(inst, width) {
+            Some(range) => bits.union_with(&KnownBits::from_range(&range)),
+            None => bits,
+        }
+    }
+}
+
+/// Known bits of an integer value of a function
+pub fn compute_known_bits(value: &Value, function: &Function) -> KnownBits {
+    ValueTracking::new(function).known_bits(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_bits_arithmetic() {
+        let low_nibble = KnownBits { width: 8, zero: 0xF0, one: 0 };
+        assert_eq!(low_nibble.to_range(), ConstantRange::from_unsigned(8, 0, 15));
+        assert_eq!(low_nibble.add(&KnownBits::constant(8, 0x10)), KnownBits { width: 8, zero: 0xE0, one: 0x10 });
+        assert_eq!(low_nibble.shl(4), KnownBits { width: 8, zero: 0x0F, one: 0 });
+        assert_eq!(low_nibble.sext(16).zero, 0xFFF0);
+        assert_eq!(KnownBits::constant(8, 12).sub(&KnownBits::constant(8, 5)).constant_value(), Some(7));
+        assert_eq!(KnownBits::from_range(&ConstantRange::from_unsigned(8, 32, 47)), KnownBits { width: 8, zero: 0xD0, one: 0x20 });
+    }
+}
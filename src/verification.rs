@@ -7,17 +7,21 @@ use std::collections::HashSet;
 use crate::module::Module;
 use crate::function::Function;
 use crate::basic_block::BasicBlock;
-use crate::instruction::{Instruction, Opcode};
-use crate::types::Type;
+use crate::instruction::{AtomicRMWBinOp, Instruction, Opcode};
+use crate::types::{FloatKind, Type};
 use crate::value::Value;
+use crate::metadata::Metadata;
+use crate::diagnostics::{Diagnostic, Rule, VerifierOptions};
+use crate::intrinsics::{Intrinsic, SignatureMismatch};
 
 /// Verification errors
 #[derive(Debug, Clone)]
 pub enum VerificationError {
     /// Type mismatch
     TypeMismatch { expected: String, found: String, location: String },
-    /// Invalid SSA form
-    InvalidSSA { value: String, location: String },
+    /// Invalid SSA form, with the location of the value's definition and of
+    /// the offending use or redefinition
+    InvalidSSA { value: String, reason: String, definition: String, location: String },
     /// Missing terminator
     MissingTerminator { block: String },
     /// Multiple terminators
@@ -63,8 +67,8 @@ impl std::fmt::Display for VerificationError {
         match self {
             VerificationError::TypeMismatch { expected, found, location } =>
                 write!(f, "Type mismatch at {}: expected {}, found {}", location, expected, found),
-            VerificationError::InvalidSSA { value, location } =>
-                write!(f, "multiple definition of local value named '{}'", value),
+            VerificationError::InvalidSSA { value, reason, definition, location } =>
+                write!(f, "{}: '%{}' at {} (defined at {})", reason, value, location, definition),
             VerificationError::MissingTerminator { block } =>
                 write!(f, "Block {} missing terminator instruction", block),
             VerificationError::MultipleTerminators { block } =>
@@ -107,6 +111,35 @@ impl std::fmt::Display for VerificationError {
     }
 }
 
+impl VerificationError {
+    /// Where the error was found, as included in its message
+    pub fn location(&self) -> &str {
+        match self {
+            VerificationError::TypeMismatch { location, .. }
+            | VerificationError::InvalidSSA { location, .. }
+            | VerificationError::UndefinedValue { location, .. }
+            | VerificationError::InvalidInstruction { location, .. }
+            | VerificationError::UnreachableCode { location }
+            | VerificationError::InvalidControlFlow { location, .. }
+            | VerificationError::InvalidCast { location, .. }
+            | VerificationError::InvalidCall { location, .. }
+            | VerificationError::InvalidPhi { location, .. }
+            | VerificationError::InvalidAlignment { location, .. }
+            | VerificationError::InvalidMetadata { location, .. }
+            | VerificationError::InvalidDebugInfo { location, .. }
+            | VerificationError::MetadataReference { location, .. }
+            | VerificationError::InvalidCFG { location, .. }
+            | VerificationError::InvalidLandingPad { location, .. }
+            | VerificationError::InvalidExceptionHandling { location, .. } => location,
+            VerificationError::MissingTerminator { block }
+            | VerificationError::MultipleTerminators { block }
+            | VerificationError::UnreachableBlock { block } => block,
+            VerificationError::EntryBlockMissing { function } => function,
+            VerificationError::InvalidOperandCount { instruction, .. } => instruction,
+        }
+    }
+}
+
 impl std::error::Error for VerificationError {}
 
 /// Verification result
@@ -114,7 +147,8 @@ pub type VerificationResult = Result<(), Vec<VerificationError>>;
 
 /// IR verifier
 pub struct Verifier<'a> {
-    errors: Vec<VerificationError>,
+    options: VerifierOptions,
+    diagnostics: Vec<Diagnostic>,
     current_function: Option<String>,
     current_function_is_varargs: bool,
     current_function_has_personality: bool,
@@ -124,8 +158,14 @@ pub struct Verifier<'a> {
 
 impl<'a> Verifier<'a> {
     pub fn new() -> Self {
+        Self::with_options(VerifierOptions::default())
+    }
+
+    /// A verifier that reports rules according to `options`
+    pub fn with_options(options: VerifierOptions) -> Self {
         Self {
-            errors: Vec::new(),
+            options,
+            diagnostics: Vec::new(),
             current_function: None,
             current_function_is_varargs: false,
             current_function_has_personality: false,
@@ -183,51 +223,10 @@ impl<'a> Verifier<'a> {
 
     /// Verify a module
     pub fn verify_module(&mut self, module: &'a Module) -> VerificationResult {
-        self.errors.clear();
+        self.diagnostics.clear();
         self.current_module = Some(module);
 
-        // Check for duplicate global definitions (functions, globals, aliases)
-        use std::collections::HashSet;
-        let mut global_names = HashSet::new();
-
-        for global in module.globals() {
-            let name = global.name();
-            // Skip duplicate check for empty/numbered names
-            if !name.is_empty() && !name.chars().all(|c| c.is_ascii_digit()) {
-                if !global_names.insert(name.to_string()) {
-                    self.errors.push(VerificationError::InvalidInstruction {
-                        reason: format!("redefinition of global '@{}'", name),
-                        location: format!("global variable @{}", name),
-                    });
-                }
-            }
-        }
-
-        for function in module.functions() {
-            let name = function.name();
-            // Skip duplicate check for empty/numbered names
-            if !name.is_empty() && !name.chars().all(|c| c.is_ascii_digit()) {
-                if !global_names.insert(name.to_string()) {
-                    self.errors.push(VerificationError::InvalidInstruction {
-                        reason: format!("redefinition of global '@{}'", name),
-                        location: format!("function {}", name),
-                    });
-                }
-            }
-        }
-
-        for alias in module.aliases() {
-            let name = &alias.name;
-            // Skip duplicate check for empty/numbered names
-            if !name.is_empty() && !name.chars().all(|c| c.is_ascii_digit()) {
-                if !global_names.insert(name.to_string()) {
-                    self.errors.push(VerificationError::InvalidInstruction {
-                        reason: format!("redefinition of global '@{}'", name),
-                        location: format!("alias @{}", name),
-                    });
-                }
-            }
-        }
+        self.verify_global_names(module);
 
         // Verify global variables
         for global in module.globals() {
@@ -241,7 +240,7 @@ impl<'a> Verifier<'a> {
 
             // Global variables cannot have token type
             if value_type.is_token() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::GlobalVariable, VerificationError::InvalidInstruction {
                     reason: "invalid type for global variable".to_string(),
                     location: format!("global variable @{}", global.name()),
                 });
@@ -265,7 +264,7 @@ impl<'a> Verifier<'a> {
                 // Intrinsics cannot be defined, only declared
                 // Check if function has a body (basic blocks)
                 if !function.basic_blocks().is_empty() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::IntrinsicDefinition, VerificationError::InvalidInstruction {
                         reason: "llvm intrinsics cannot be defined".to_string(),
                         location: format!("function {}", fn_name),
                     });
@@ -278,18 +277,87 @@ impl<'a> Verifier<'a> {
         // Verify module-level metadata
         self.verify_module_flags(module);
         self.verify_named_metadata(module);
-        self.verify_debug_info_metadata(module);
+        self.verify_metadata(module);
+        self.verify_debug_info(module);
 
-        if self.errors.is_empty() {
-            Ok(())
-        } else {
-            Err(self.errors.clone())
+        errors_of(&self.diagnostics)
+    }
+
+    /// Verify only the named functions of a module, plus the module-level
+    /// invariants that are cheap to check. Used to re-verify after a pass
+    /// that reported changing just these functions.
+    pub fn verify_functions(&mut self, module: &'a Module, function_names: &[String]) -> VerificationResult {
+        self.diagnostics.clear();
+        self.current_module = Some(module);
+
+        self.verify_global_names(module);
+        for function in module.functions() {
+            if function_names.contains(&function.name()) {
+                self.verify_function(&function);
+            }
+        }
+
+        errors_of(&self.diagnostics)
+    }
+
+    /// Check for duplicate global definitions (functions, globals, aliases)
+    fn verify_global_names(&mut self, module: &Module) {
+        use std::collections::HashSet;
+        let mut global_names = HashSet::new();
+
+        for global in module.globals() {
+            let name = global.name();
+            // Skip duplicate check for empty/numbered names
+            if !name.is_empty() && !name.chars().all(|c| c.is_ascii_digit()) {
+                if !global_names.insert(name.to_string()) {
+                    self.report(Rule::GlobalRedefinition, VerificationError::InvalidInstruction {
+                        reason: format!("redefinition of global '@{}'", name),
+                        location: format!("global variable @{}", name),
+                    });
+                }
+            }
+        }
+
+        for function in module.functions() {
+            let name = function.name();
+            // Skip duplicate check for empty/numbered names
+            if !name.is_empty() && !name.chars().all(|c| c.is_ascii_digit()) {
+                if !global_names.insert(name.to_string()) {
+                    self.report(Rule::GlobalRedefinition, VerificationError::InvalidInstruction {
+                        reason: format!("redefinition of global '@{}'", name),
+                        location: format!("function {}", name),
+                    });
+                }
+            }
+        }
+
+        for alias in module.aliases() {
+            let name = &alias.name;
+            // Skip duplicate check for empty/numbered names
+            if !name.is_empty() && !name.chars().all(|c| c.is_ascii_digit()) {
+                if !global_names.insert(name.to_string()) {
+                    self.report(Rule::GlobalRedefinition, VerificationError::InvalidInstruction {
+                        reason: format!("redefinition of global '@{}'", name),
+                        location: format!("alias @{}", name),
+                    });
+                }
+            }
         }
     }
 
+    /// Diagnostics of every enabled rule from the last verification,
+    /// warnings included
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Record `error` under `rule`, unless the options disable the rule
+    fn report(&mut self, rule: Rule, error: VerificationError) {
+        self.diagnostics.extend(self.options.diagnostic(rule, error));
+    }
+
     /// Verify module flags metadata
     fn verify_module_flags(&mut self, module: &Module) {
-        use crate::metadata::Metadata;
         use std::collections::HashMap;
 
         let flags = module.module_flags();
@@ -299,7 +367,7 @@ impl<'a> Verifier<'a> {
             // Module flags must be metadata tuples with exactly 3 operands
             if let Some(operands) = flag.operands() {
                 if operands.len() != 3 {
-                    self.errors.push(VerificationError::InvalidMetadata {
+                    self.report(Rule::ModuleFlags, VerificationError::InvalidMetadata {
                         reason: "incorrect number of operands in module flag".to_string(),
                         location: "module flags".to_string(),
                     });
@@ -309,7 +377,7 @@ impl<'a> Verifier<'a> {
                 // First operand: behavior (must be constant integer 1-8)
                 let behavior = if let Some(behavior_val) = operands[0].as_i32() {
                     if behavior_val < 1 || behavior_val > 8 {
-                        self.errors.push(VerificationError::InvalidMetadata {
+                        self.report(Rule::ModuleFlags, VerificationError::InvalidMetadata {
                             reason: format!("invalid behavior operand in module flag (unexpected constant)"),
                             location: "module flags".to_string(),
                         });
@@ -317,7 +385,7 @@ impl<'a> Verifier<'a> {
                     }
                     behavior_val
                 } else {
-                    self.errors.push(VerificationError::InvalidMetadata {
+                    self.report(Rule::ModuleFlags, VerificationError::InvalidMetadata {
                         reason: "invalid behavior operand in module flag (expected constant integer)".to_string(),
                         location: "module flags".to_string(),
                     });
@@ -328,7 +396,7 @@ impl<'a> Verifier<'a> {
                 let id = if let Some(id_str) = operands[1].as_string() {
                     id_str.to_string()
                 } else {
-                    self.errors.push(VerificationError::InvalidMetadata {
+                    self.report(Rule::ModuleFlags, VerificationError::InvalidMetadata {
                         reason: "invalid ID operand in module flag (expected metadata string)".to_string(),
                         location: "module flags".to_string(),
                     });
@@ -339,7 +407,7 @@ impl<'a> Verifier<'a> {
                 if behavior != 3 {
                     if let Some(prev_behavior) = flag_ids.get(&id) {
                         if *prev_behavior != 3 {
-                            self.errors.push(VerificationError::InvalidMetadata {
+                            self.report(Rule::ModuleFlags, VerificationError::InvalidMetadata {
                                 reason: "module flag identifiers must be unique (or of 'require' type)".to_string(),
                                 location: format!("module flag '{}'", id),
                             });
@@ -356,18 +424,18 @@ impl<'a> Verifier<'a> {
                         // Require: value must be a metadata pair with string first element
                         if let Some(value_ops) = value.operands() {
                             if value_ops.len() != 2 {
-                                self.errors.push(VerificationError::InvalidMetadata {
+                                self.report(Rule::ModuleFlags, VerificationError::InvalidMetadata {
                                     reason: "invalid value for 'require' module flag (expected metadata pair)".to_string(),
                                     location: format!("module flag '{}'", id),
                                 });
                             } else if !value_ops[0].is_string() {
-                                self.errors.push(VerificationError::InvalidMetadata {
+                                self.report(Rule::ModuleFlags, VerificationError::InvalidMetadata {
                                     reason: "invalid value for 'require' module flag (first value operand should be a string)".to_string(),
                                     location: format!("module flag '{}'", id),
                                 });
                             }
                         } else {
-                            self.errors.push(VerificationError::InvalidMetadata {
+                            self.report(Rule::ModuleFlags, VerificationError::InvalidMetadata {
                                 reason: "invalid value for 'require' module flag (expected metadata pair)".to_string(),
                                 location: format!("module flag '{}'", id),
                             });
@@ -376,7 +444,7 @@ impl<'a> Verifier<'a> {
                     5 => {
                         // Append: value must be a metadata node
                         if !value.is_tuple() {
-                            self.errors.push(VerificationError::InvalidMetadata {
+                            self.report(Rule::ModuleFlags, VerificationError::InvalidMetadata {
                                 reason: "invalid value for 'append'-type module flag (expected a metadata node)".to_string(),
                                 location: format!("module flag '{}'", id),
                             });
@@ -385,7 +453,7 @@ impl<'a> Verifier<'a> {
                     7 => {
                         // Max: value must be a constant integer
                         if !value.is_int() {
-                            self.errors.push(VerificationError::InvalidMetadata {
+                            self.report(Rule::ModuleFlags, VerificationError::InvalidMetadata {
                                 reason: "invalid value for 'max' module flag (expected constant integer)".to_string(),
                                 location: format!("module flag '{}'", id),
                             });
@@ -395,13 +463,13 @@ impl<'a> Verifier<'a> {
                         // Min: value must be a constant non-negative integer
                         if let Some(val) = value.as_int() {
                             if val < 0 {
-                                self.errors.push(VerificationError::InvalidMetadata {
+                                self.report(Rule::ModuleFlags, VerificationError::InvalidMetadata {
                                     reason: "invalid value for 'min' module flag (expected constant non-negative integer)".to_string(),
                                     location: format!("module flag '{}'", id),
                                 });
                             }
                         } else {
-                            self.errors.push(VerificationError::InvalidMetadata {
+                            self.report(Rule::ModuleFlags, VerificationError::InvalidMetadata {
                                 reason: "invalid value for 'min' module flag (expected constant non-negative integer)".to_string(),
                                 location: format!("module flag '{}'", id),
                             });
@@ -413,7 +481,7 @@ impl<'a> Verifier<'a> {
                     }
                 }
             } else {
-                self.errors.push(VerificationError::InvalidMetadata {
+                self.report(Rule::ModuleFlags, VerificationError::InvalidMetadata {
                     reason: "incorrect number of operands in module flag".to_string(),
                     location: "module flags".to_string(),
                 });
@@ -427,7 +495,7 @@ impl<'a> Verifier<'a> {
 
         // Global variables cannot have token type
         if global.ty.is_token() {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::GlobalVariable, VerificationError::InvalidInstruction {
                 reason: "invalid type for global variable".to_string(),
                 location: format!("global variable @{}", global.name),
             });
@@ -435,7 +503,7 @@ impl<'a> Verifier<'a> {
 
         // Global variables cannot have x86_amx type
         if global.ty.is_x86_amx() {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::GlobalVariable, VerificationError::InvalidInstruction {
                 reason: "invalid type for global variable".to_string(),
                 location: format!("global variable @{}", global.name),
             });
@@ -443,7 +511,7 @@ impl<'a> Verifier<'a> {
 
         // Global variables cannot contain scalable types
         if self.contains_scalable_type(&global.ty) {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::GlobalVariable, VerificationError::InvalidInstruction {
                 reason: "Globals cannot contain scalable types".to_string(),
                 location: format!("ptr @{}", global.name),
             });
@@ -452,7 +520,7 @@ impl<'a> Verifier<'a> {
         // Arrays cannot have x86_amx as element type
         if let Some((element_type, _)) = global.ty.array_info() {
             if element_type.is_x86_amx() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::GlobalVariable, VerificationError::InvalidInstruction {
                     reason: "Cannot allocate an array of x86_amx".to_string(),
                     location: format!("global variable @{}", global.name),
                 });
@@ -462,7 +530,7 @@ impl<'a> Verifier<'a> {
         // Vectors cannot have x86_amx as element type
         if let Some((element_type, _)) = global.ty.vector_info() {
             if element_type.is_x86_amx() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::GlobalVariable, VerificationError::InvalidInstruction {
                     reason: "vector type cannot have x86_amx element type".to_string(),
                     location: format!("global variable @{}", global.name),
                 });
@@ -471,7 +539,7 @@ impl<'a> Verifier<'a> {
 
         // Global variable initializer must be sized (target types are allowed)
         if global.initializer.is_some() && !global.ty.is_sized() && !self.is_target_type(&global.ty) {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::GlobalVariable, VerificationError::InvalidInstruction {
                 reason: "Global variable initializer must be sized".to_string(),
                 location: format!("global variable @{}", global.name),
             });
@@ -480,7 +548,7 @@ impl<'a> Verifier<'a> {
         // Appending linkage can only be used with global arrays
         if matches!(global.linkage, Linkage::Appending) {
             if !global.ty.is_array() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::GlobalVariable, VerificationError::InvalidInstruction {
                     reason: "Only global arrays can have appending linkage!".to_string(),
                     location: format!("ptr @{}", global.name),
                 });
@@ -498,7 +566,7 @@ impl<'a> Verifier<'a> {
             };
 
             if !valid_type {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::GlobalVariable, VerificationError::InvalidInstruction {
                     reason: "wrong type for intrinsic global variable".to_string(),
                     location: format!("global variable @{}", global.name),
                 });
@@ -511,7 +579,7 @@ impl<'a> Verifier<'a> {
                     for elem in elements.iter() {
                         // Check for both ConstantNull and ZeroInitializer (ptr null can be either)
                         if elem.is_null() || elem.is_zero_initializer() {
-                            self.errors.push(VerificationError::InvalidInstruction {
+                            self.report(Rule::GlobalVariable, VerificationError::InvalidInstruction {
                                 reason: format!("invalid {} member", global.name),
                                 location: "ptr null".to_string(),
                             });
@@ -524,7 +592,7 @@ impl<'a> Verifier<'a> {
                 // TODO: Re-enable after fixing parser
                 /*
                 if initializer.is_zero_initializer() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::GlobalVariable, VerificationError::InvalidInstruction {
                         reason: "wrong initializer for intrinsic global variable".to_string(),
                         location: format!("{}", initializer),
                     });
@@ -537,7 +605,7 @@ impl<'a> Verifier<'a> {
         // Private/internal linkage requires default visibility
         if matches!(global.linkage, Linkage::Private | Linkage::Internal) {
             if !matches!(global.visibility, Visibility::Default) {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::LinkageVisibility, VerificationError::InvalidInstruction {
                     reason: "symbol with local linkage must have default visibility".to_string(),
                     location: format!("global variable @{}", global.name),
                 });
@@ -551,20 +619,20 @@ impl<'a> Verifier<'a> {
                 Linkage::External => {
                     // External without initializer is a declaration
                     if global.initializer.is_none() {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::GlobalVariable, VerificationError::InvalidInstruction {
                             reason: "Declaration may not be in a Comdat!".to_string(),
                             location: format!("global variable @{}", global.name),
                         });
                     }
                 }
                 Linkage::AvailableExternally => {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::GlobalVariable, VerificationError::InvalidInstruction {
                         reason: "Declaration may not be in a Comdat!".to_string(),
                         location: format!("global variable @{}", global.name),
                     });
                 }
                 Linkage::Common => {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::GlobalVariable, VerificationError::InvalidInstruction {
                         reason: "'common' global may not be in a Comdat!".to_string(),
                         location: format!("global variable @{}", global.name),
                     });
@@ -572,7 +640,7 @@ impl<'a> Verifier<'a> {
                 Linkage::Private => {
                     // Private linkage in comdat is target-specific (Windows only)
                     // For now, we'll flag it as an error
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::GlobalVariable, VerificationError::InvalidInstruction {
                         reason: "comdat global value has private linkage".to_string(),
                         location: format!("global variable @{}", global.name),
                     });
@@ -597,14 +665,14 @@ impl<'a> Verifier<'a> {
             for func in module.functions() {
                 if func.name() == name {
                     if matches!(func.linkage(), Linkage::External) && func.basic_blocks().is_empty() {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::Alias, VerificationError::InvalidInstruction {
                             reason: "Alias must point to a definition".to_string(),
                             location: format!("@{}", alias.name),
                         });
                         return;
                     }
                     if matches!(func.linkage(), Linkage::AvailableExternally) {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::Alias, VerificationError::InvalidInstruction {
                             reason: "Alias must point to a definition".to_string(),
                             location: format!("@{}", alias.name),
                         });
@@ -618,7 +686,7 @@ impl<'a> Verifier<'a> {
             for global in module.globals() {
                 if global.name() == name {
                     if matches!(global.linkage, Linkage::External) && global.initializer.is_none() {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::Alias, VerificationError::InvalidInstruction {
                             reason: "Alias must point to a definition".to_string(),
                             location: format!("@{}", alias.name),
                         });
@@ -633,7 +701,7 @@ impl<'a> Verifier<'a> {
         let mut visited = HashSet::new();
         let mut in_stack = HashSet::new();
         if self.has_alias_cycle(&alias.name, module, &mut visited, &mut in_stack) {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::Alias, VerificationError::InvalidInstruction {
                 reason: "Aliases cannot form a cycle".to_string(),
                 location: format!("ptr @{}", alias.name),
             });
@@ -648,7 +716,7 @@ impl<'a> Verifier<'a> {
                         Linkage::LinkonceOdr | Linkage::Common | Linkage::ExternWeak
                     );
                     if is_interposable {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::Alias, VerificationError::InvalidInstruction {
                             reason: "Alias cannot point to an interposable alias".to_string(),
                             location: format!("ptr @{}", alias.name),
                         });
@@ -681,7 +749,7 @@ impl<'a> Verifier<'a> {
                     }
                 }
                 if !points_to_ae {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::Alias, VerificationError::InvalidInstruction {
                         reason: "available_externally alias must point to available_externally global value".to_string(),
                         location: format!("ptr @{}", alias.name),
                     });
@@ -737,7 +805,7 @@ impl<'a> Verifier<'a> {
         // Check if trying to define an LLVM intrinsic (functions starting with "llvm.")
         // Intrinsics can be declared but not defined
         if fn_name.starts_with("llvm.") && function.has_body() {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::IntrinsicDefinition, VerificationError::InvalidInstruction {
                 reason: "llvm intrinsics cannot be defined".to_string(),
                 location: format!("function {}", fn_name),
             });
@@ -751,7 +819,7 @@ impl<'a> Verifier<'a> {
 
         if matches!(linkage, Linkage::Private | Linkage::Internal) {
             if !matches!(visibility, Visibility::Default) {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::LinkageVisibility, VerificationError::InvalidInstruction {
                     reason: "symbol with local linkage must have default visibility".to_string(),
                     location: format!("function {}", fn_name),
                 });
@@ -763,7 +831,7 @@ impl<'a> Verifier<'a> {
         let dll_storage = function.dll_storage_class();
         if matches!(linkage, Linkage::Private | Linkage::Internal) {
             if !matches!(dll_storage, DLLStorageClass::Default) {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::LinkageVisibility, VerificationError::InvalidInstruction {
                     reason: "symbol with local linkage cannot have a DLL storage class".to_string(),
                     location: format!("function {}", fn_name),
                 });
@@ -774,7 +842,7 @@ impl<'a> Verifier<'a> {
         // dllexport must have default or protected visibility
         if matches!(dll_storage, DLLStorageClass::DllExport) {
             if matches!(visibility, Visibility::Hidden) {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::LinkageVisibility, VerificationError::InvalidInstruction {
                     reason: "dllexport GlobalValue must have default or protected visibility".to_string(),
                     location: format!("function {}", fn_name),
                 });
@@ -784,7 +852,7 @@ impl<'a> Verifier<'a> {
         // dllimport must have default visibility
         if matches!(dll_storage, DLLStorageClass::DllImport) {
             if !matches!(visibility, Visibility::Default) {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::LinkageVisibility, VerificationError::InvalidInstruction {
                     reason: "dllimport GlobalValue must have default visibility".to_string(),
                     location: format!("function {}", fn_name),
                 });
@@ -797,7 +865,7 @@ impl<'a> Verifier<'a> {
 
             // Functions cannot take label as parameter
             if param_type.is_label() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::FunctionSignature, VerificationError::InvalidInstruction {
                     reason: "invalid type for function argument".to_string(),
                     location: format!("function {}", fn_name),
                 });
@@ -805,7 +873,7 @@ impl<'a> Verifier<'a> {
 
             // Only intrinsics can have token parameters
             if param_type.is_token() && !fn_name.starts_with("llvm.") {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::FunctionSignature, VerificationError::InvalidInstruction {
                     reason: "Function takes token but isn't an intrinsic".to_string(),
                     location: format!("function {}", fn_name),
                 });
@@ -813,7 +881,7 @@ impl<'a> Verifier<'a> {
 
             // Only intrinsics can have x86_amx parameters
             if param_type.is_x86_amx() && !fn_name.starts_with("llvm.") {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::FunctionSignature, VerificationError::InvalidInstruction {
                     reason: "only intrinsic calls can have x86_amx operand".to_string(),
                     location: format!("function {}", fn_name),
                 });
@@ -823,7 +891,7 @@ impl<'a> Verifier<'a> {
         // Check for incompatible function attributes
         let attrs = function.attributes();
         if attrs.noinline && attrs.alwaysinline {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::FunctionAttributes, VerificationError::InvalidInstruction {
                 reason: "Attributes 'noinline and alwaysinline' are incompatible".to_string(),
                 location: format!("function {}", fn_name),
             });
@@ -840,7 +908,7 @@ impl<'a> Verifier<'a> {
                             for arg in function.arguments() {
                                 if let Some(arg_name) = arg.name() {
                                     if arg_name == operand_name {
-                                        self.errors.push(VerificationError::InvalidInstruction {
+                                        self.report(Rule::FunctionAttributes, VerificationError::InvalidInstruction {
                                             reason: "cannot use argument of naked function".to_string(),
                                             location: format!("function {}", fn_name),
                                         });
@@ -859,7 +927,7 @@ impl<'a> Verifier<'a> {
 
             // Check return type - non-intrinsic functions cannot return token
             if ret_type.is_token() && !fn_name.starts_with("llvm.") {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::FunctionSignature, VerificationError::InvalidInstruction {
                     reason: "Function returns a token but isn't an intrinsic".to_string(),
                     location: format!("function {}", fn_name),
                 });
@@ -867,7 +935,7 @@ impl<'a> Verifier<'a> {
 
             // Check return type - non-intrinsic functions cannot return x86_amx
             if ret_type.is_x86_amx() && !fn_name.starts_with("llvm.") {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::FunctionSignature, VerificationError::InvalidInstruction {
                     reason: "only intrinsic calls can return x86_amx".to_string(),
                     location: format!("function {}", fn_name),
                 });
@@ -878,7 +946,7 @@ impl<'a> Verifier<'a> {
             match cc {
                 CallingConvention::AMDGPU_Kernel | CallingConvention::SPIR_Kernel => {
                     if !ret_type.is_void() {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::CallingConvention, VerificationError::InvalidInstruction {
                             reason: "Calling convention requires void return type".to_string(),
                             location: format!("function {}", fn_name),
                         });
@@ -896,7 +964,7 @@ impl<'a> Verifier<'a> {
                 CallingConvention::AMDGPU_CS |
                 CallingConvention::SPIR_Kernel => {
                     if is_varargs {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::CallingConvention, VerificationError::InvalidInstruction {
                             reason: "Calling convention does not support varargs or perfect forwarding!".to_string(),
                             location: format!("function {}", fn_name),
                         });
@@ -912,7 +980,7 @@ impl<'a> Verifier<'a> {
                         .map(|attr| attr.byval.is_some())
                         .unwrap_or(false);
                     if !has_byval {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::CallingConvention, VerificationError::InvalidInstruction {
                             reason: "Calling convention parameter requires byval".to_string(),
                             location: format!("ptr @{}", fn_name),
                         });
@@ -924,21 +992,21 @@ impl<'a> Verifier<'a> {
             for (idx, param_type) in param_types.iter().enumerate() {
                 // Parameters cannot have label type
                 if param_type.is_label() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::FunctionSignature, VerificationError::InvalidInstruction {
                         reason: "invalid type for function argument".to_string(),
                         location: format!("function {} parameter {}", fn_name, idx),
                     });
                 }
                 // Parameters cannot have void type
                 if param_type.is_void() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::FunctionSignature, VerificationError::InvalidInstruction {
                         reason: "invalid type for function argument".to_string(),
                         location: format!("function {} parameter {}", fn_name, idx),
                     });
                 }
                 // Parameters cannot have metadata type (except for intrinsics)
                 if param_type.is_metadata() && !fn_name.starts_with("llvm.") {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::FunctionSignature, VerificationError::InvalidInstruction {
                         reason: "invalid type for function argument".to_string(),
                         location: format!("function {} parameter {}", fn_name, idx),
                     });
@@ -960,7 +1028,7 @@ impl<'a> Verifier<'a> {
         if let Some((min, max)) = attrs.vscale_range {
             // Minimum must be greater than 0
             if min == 0 {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::FunctionAttributes, VerificationError::InvalidInstruction {
                     reason: "'vscale_range' minimum must be greater than 0".to_string(),
                     location: format!("ptr @{}", fn_name),
                 });
@@ -968,7 +1036,7 @@ impl<'a> Verifier<'a> {
 
             // Minimum cannot be greater than maximum
             if min > max && max != 0 {  // max == 0 means unbounded
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::FunctionAttributes, VerificationError::InvalidInstruction {
                     reason: "'vscale_range' minimum cannot be greater than maximum".to_string(),
                     location: format!("ptr @{}", fn_name),
                 });
@@ -976,7 +1044,7 @@ impl<'a> Verifier<'a> {
 
             // Minimum must be power-of-two
             if min != 0 && (min & (min - 1)) != 0 {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::FunctionAttributes, VerificationError::InvalidInstruction {
                     reason: "'vscale_range' minimum must be power-of-two value".to_string(),
                     location: format!("ptr @{}", fn_name),
                 });
@@ -984,7 +1052,7 @@ impl<'a> Verifier<'a> {
 
             // Maximum must be power-of-two (if not 0/unbounded)
             if max != 0 && (max & (max - 1)) != 0 {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::FunctionAttributes, VerificationError::InvalidInstruction {
                     reason: "'vscale_range' maximum must be power-of-two value".to_string(),
                     location: format!("ptr @{}", fn_name),
                 });
@@ -1009,7 +1077,7 @@ impl<'a> Verifier<'a> {
 
         // Check if function has an entry block
         if function.entry_block().is_none() {
-            self.errors.push(VerificationError::EntryBlockMissing {
+            self.report(Rule::EntryBlock, VerificationError::EntryBlockMissing {
                 function: function.name(),
             });
             return;
@@ -1051,7 +1119,7 @@ impl<'a> Verifier<'a> {
                     // Check direct operands
                     for operand in unwind_inst.operands() {
                         if operand.name() == Some(&result_name) {
-                            self.errors.push(VerificationError::InvalidInstruction {
+                            self.report(Rule::SsaForm, VerificationError::InvalidInstruction {
                                 reason: format!("Invoke result value cannot be used in the unwind destination!"),
                                 location: format!("invoke result %{} used in unwind block {}", result_name, unwind_label),
                             });
@@ -1076,7 +1144,7 @@ impl<'a> Verifier<'a> {
                                     if let Some(inc_label_name) = incoming_label.name() {
                                         // The incoming label is the unwind destination or a successor
                                         if inc_label_name == &unwind_label {
-                                            self.errors.push(VerificationError::InvalidInstruction {
+                                            self.report(Rule::SsaForm, VerificationError::InvalidInstruction {
                                                 reason: format!("Invoke result value cannot be used on unwind path!"),
                                                 location: format!("PHI node uses %{} from unwind block %{}", result_name, unwind_label),
                                             });
@@ -1090,44 +1158,6 @@ impl<'a> Verifier<'a> {
             }
         }
 
-        // Check for duplicate local variable definitions and type consistency
-        use std::collections::HashMap;
-        let mut local_names: HashMap<String, Type> = HashMap::new();
-        for bb in function.basic_blocks() {
-            for inst in bb.instructions() {
-                if let Some(result) = inst.result() {
-                    if let Some(name) = result.name() {
-                        // Skip empty names
-                        if !name.is_empty() {
-                            let result_type = result.get_type();
-
-                            if let Some(existing_type) = local_names.get(name) {
-                                // Check if it's the same type (allow same name with same type in different branches)
-                                // But flag it as an error if types differ
-                                if format!("{:?}", existing_type) != format!("{:?}", result_type) {
-                                    self.errors.push(VerificationError::TypeMismatch {
-                                        expected: format!("{:?}", existing_type),
-                                        found: format!("{:?}", result_type),
-                                        location: format!("'%{}' defined with type", name),
-                                    });
-                                } else {
-                                    // Same type - this is a duplicate definition (SSA violation)
-                                    if !name.chars().all(|c| c.is_ascii_digit()) {
-                                        self.errors.push(VerificationError::InvalidSSA {
-                                            value: name.to_string(),
-                                            location: format!("function {}", fn_name),
-                                        });
-                                    }
-                                }
-                            } else {
-                                local_names.insert(name.to_string(), result_type.clone());
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
         // Verify return types match function signature
         self.verify_return_types(function);
 
@@ -1157,7 +1187,7 @@ impl<'a> Verifier<'a> {
                     if return_type.is_void() {
                         // Void return: should have no operands
                         if !operands.is_empty() {
-                            self.errors.push(VerificationError::TypeMismatch {
+                            self.report(Rule::ReturnType, VerificationError::TypeMismatch {
                                 expected: "void".to_string(),
                                 found: format!("{:?}", operands[0].get_type()),
                                 location: format!("function {} return", function.name()),
@@ -1166,7 +1196,7 @@ impl<'a> Verifier<'a> {
                     } else {
                         // Non-void return: should have exactly 1 operand
                         if operands.is_empty() {
-                            self.errors.push(VerificationError::TypeMismatch {
+                            self.report(Rule::ReturnType, VerificationError::TypeMismatch {
                                 expected: format!("{:?}", return_type),
                                 found: "void".to_string(),
                                 location: format!("function {} return", function.name()),
@@ -1181,7 +1211,7 @@ impl<'a> Verifier<'a> {
                             };
 
                             if !types_match {
-                                self.errors.push(VerificationError::TypeMismatch {
+                                self.report(Rule::ReturnType, VerificationError::TypeMismatch {
                                     expected: format!("{:?}", return_type),
                                     found: format!("{:?}", ret_val_type),
                                     location: format!("function {} return", function.name()),
@@ -1199,54 +1229,24 @@ impl<'a> Verifier<'a> {
         let instructions = bb.instructions();
 
         if instructions.is_empty() {
-            self.errors.push(VerificationError::MissingTerminator {
+            self.report(Rule::Terminator, VerificationError::MissingTerminator {
                 block: bb.name().unwrap_or_else(|| "unnamed".to_string()),
             });
             return;
         }
 
-        // Check terminator
-        let mut terminator_count = 0;
-        for (i, inst) in instructions.iter().enumerate() {
-            if inst.is_terminator() {
-                terminator_count += 1;
-                // Terminator must be last
-                if i != instructions.len() - 1 {
-                    self.errors.push(VerificationError::InvalidInstruction {
-                        reason: "Terminator must be last instruction".to_string(),
-                        location: format!("block {}", bb.name().unwrap_or_else(|| "unnamed".to_string())),
-                    });
-                }
-            }
-        }
-
+        // Check terminator; its position and PHI grouping are checked with the CFG
+        let terminator_count = instructions.iter().filter(|inst| inst.is_terminator()).count();
         if terminator_count == 0 {
-            self.errors.push(VerificationError::MissingTerminator {
+            self.report(Rule::Terminator, VerificationError::MissingTerminator {
                 block: bb.name().unwrap_or_else(|| "unnamed".to_string()),
             });
         } else if terminator_count > 1 {
-            self.errors.push(VerificationError::MultipleTerminators {
+            self.report(Rule::Terminator, VerificationError::MultipleTerminators {
                 block: bb.name().unwrap_or_else(|| "unnamed".to_string()),
             });
         }
 
-        // Check PHI node grouping
-        // All PHI nodes must be contiguous at the top of the block
-        let mut found_non_phi = false;
-        for inst in instructions.iter() {
-            if inst.opcode() == Opcode::PHI {
-                if found_non_phi {
-                    self.errors.push(VerificationError::InvalidPhi {
-                        reason: "PHI nodes not grouped at top of basic block".to_string(),
-                        location: format!("block {}", bb.name().unwrap_or_else(|| "unnamed".to_string())),
-                    });
-                    break; // Only report once per block
-                }
-            } else {
-                found_non_phi = true;
-            }
-        }
-
         // Check landing pad position
         // Landing pads must be first non-PHI instruction
         let mut found_non_phi_non_landingpad = false;
@@ -1254,13 +1254,13 @@ impl<'a> Verifier<'a> {
         for inst in instructions.iter() {
             if inst.opcode() == Opcode::LandingPad {
                 if found_landingpad {
-                    self.errors.push(VerificationError::InvalidLandingPad {
+                    self.report(Rule::LandingPad, VerificationError::InvalidLandingPad {
                         reason: "multiple landing pads in same block".to_string(),
                         location: format!("block {}", bb.name().unwrap_or_else(|| "unnamed".to_string())),
                     });
                 }
                 if found_non_phi_non_landingpad {
-                    self.errors.push(VerificationError::InvalidLandingPad {
+                    self.report(Rule::LandingPad, VerificationError::InvalidLandingPad {
                         reason: "landing pad must be first non-PHI instruction in block".to_string(),
                         location: format!("block {}", bb.name().unwrap_or_else(|| "unnamed".to_string())),
                     });
@@ -1277,669 +1277,146 @@ impl<'a> Verifier<'a> {
         // Proper check requires reachability analysis
 
         // Verify each instruction
-        for inst in instructions.iter() {
-            self.verify_instruction(inst);
+        let fn_name = self.current_function.clone().unwrap_or_default();
+        let block_name = bb.name().unwrap_or_default();
+        for (index, inst) in instructions.iter().enumerate() {
+            let location = format!("function {} block %{} instruction {} ({:?})", fn_name, block_name, index, inst.opcode());
+            self.verify_instruction_at(inst, &location);
         }
     }
 
     /// Verify an instruction
     pub fn verify_instruction(&mut self, inst: &Instruction) {
-        // Focus on semantic validation, not strict operand count checks
-
-        let location = format!("instruction {:?}", inst.opcode());
+        self.verify_instruction_at(inst, &format!("instruction {:?}", inst.opcode()));
+    }
 
-        // Check for self-referential instructions (except PHI nodes)
-        // Only PHI nodes may reference their own value
-        if inst.opcode() != Opcode::PHI {
-            if let Some(result_name) = inst.result().and_then(|v| v.name()) {
-                for operand in inst.operands() {
-                    if let Some(operand_name) = operand.name() {
-                        if result_name == operand_name {
-                            self.errors.push(VerificationError::InvalidInstruction {
-                                reason: "Only PHI nodes may reference their own value".to_string(),
-                                location: format!("{:?} instruction", inst.opcode()),
-                            });
-                            break;
-                        }
-                    }
-                }
-            }
-        }
+    /// Verify an instruction, naming it by `location` in errors
+    fn verify_instruction_at(&mut self, inst: &Instruction, location: &str) {
+        // Focus on semantic validation, not strict operand count checks
 
         // Verify metadata attachments
-        self.verify_instruction_metadata(inst, &location);
+        self.verify_instruction_metadata(inst, location);
 
         // Verify alignment constraints
-        self.verify_instruction_alignment(inst, &location);
+        self.verify_instruction_alignment(inst, location);
 
         // Verify atomic instructions
-        self.verify_atomic_instruction(inst, &location);
+        self.verify_atomic_instruction(inst, location);
 
         // Verify instruction type constraints
-        self.verify_instruction_types(inst, &location);
+        self.verify_instruction_types(inst, location);
 
         match inst.opcode() {
-            // === CAST OPERATIONS ===
-            Opcode::Trunc => {
-                // Trunc: operand must be integer or vector of integers
+            // === FUNCTION CALL VALIDATION ===
+            Opcode::Call => {
+                // Call: validate argument count and types match function signature
                 let operands = inst.operands();
-                if operands.len() >= 1 {
-                    let src_type = operands[0].get_type();
-                    if !self.is_integer_or_vector_of_integers(&src_type) {
-                        self.errors.push(VerificationError::InvalidCast {
-                            from: format!("{:?}", src_type),
-                            to: "integer".to_string(),
-                            reason: "trunc operand must be integer or vector of integers".to_string(),
-                            location: "trunc instruction".to_string(),
-                        });
-                    }
-                    if let Some(result) = inst.result() {
-                        let dst_type = result.get_type();
-                        if !self.is_integer_or_vector_of_integers(&dst_type) {
-                            self.errors.push(VerificationError::InvalidCast {
-                                from: format!("{:?}", src_type),
-                                to: format!("{:?}", dst_type),
-                                reason: "trunc result must be integer or vector of integers".to_string(),
-                                location: "trunc instruction".to_string(),
-                            });
-                        } else if let (Some(src_bits), Some(dst_bits)) = (src_type.int_width(), dst_type.int_width()) {
-                            if dst_bits >= src_bits {
-                                self.errors.push(VerificationError::InvalidCast {
-                                    from: format!("{:?}", src_type),
-                                    to: format!("{:?}", dst_type),
-                                    reason: format!("trunc result must be smaller (src: {} bits, dst: {} bits)", src_bits, dst_bits),
-                                    location: "trunc instruction".to_string(),
-                                });
-                            }
-                        }
+                if operands.is_empty() {
+                    return; // No callee, skip validation
+                }
+
+                let callee = &operands[0];
+
+                // Check if this is an intrinsic call
+                if let Some(callee_name) = callee.name() {
+                    if callee_name.starts_with("llvm.") {
+                        self.verify_intrinsic_call(inst, callee_name);
                     }
                 }
-            }
-            Opcode::ZExt | Opcode::SExt => {
-                // ZExt/SExt: operand must be integer or vector of integers
-                let operands = inst.operands();
-                let opcode_name = if inst.opcode() == Opcode::ZExt { "zext" } else { "sext" };
-                if operands.len() >= 1 {
-                    let src_type = operands[0].get_type();
-                    if !self.is_integer_or_vector_of_integers(&src_type) {
-                        self.errors.push(VerificationError::InvalidCast {
-                            from: format!("{:?}", src_type),
-                            to: "integer".to_string(),
-                            reason: format!("{} operand must be integer or vector of integers", opcode_name),
-                            location: format!("{} instruction", opcode_name),
+
+                // Check calling convention restrictions
+                // Some calling conventions don't permit calls
+                use crate::function::CallingConvention;
+                let cc = self.current_function_calling_convention;
+                match cc {
+                    CallingConvention::AMDGPU_Kernel |
+                    CallingConvention::AMDGPU_VS |
+                    CallingConvention::AMDGPU_GS |
+                    CallingConvention::AMDGPU_PS |
+                    CallingConvention::AMDGPU_CS |
+                    CallingConvention::AMDGPU_CS_Chain |
+                    CallingConvention::AMDGPU_CS_Chain_Preserve |
+                    CallingConvention::AMDGPU_HS |
+                    CallingConvention::AMDGPU_LS |
+                    CallingConvention::AMDGPU_ES |
+                    CallingConvention::SPIR_Kernel => {
+                        self.report(Rule::CallingConvention, VerificationError::InvalidInstruction {
+                            reason: "calling convention does not permit calls".to_string(),
+                            location: "call instruction".to_string(),
                         });
                     }
-                    if let Some(result) = inst.result() {
-                        let dst_type = result.get_type();
-                        if !self.is_integer_or_vector_of_integers(&dst_type) {
-                            self.errors.push(VerificationError::InvalidCast {
-                                from: format!("{:?}", src_type),
-                                to: format!("{:?}", dst_type),
-                                reason: format!("{} result must be integer or vector of integers", opcode_name),
-                                location: format!("{} instruction", opcode_name),
-                            });
-                        } else if let (Some(src_bits), Some(dst_bits)) = (src_type.int_width(), dst_type.int_width()) {
-                            if dst_bits <= src_bits {
-                                self.errors.push(VerificationError::InvalidCast {
-                                    from: format!("{:?}", src_type),
-                                    to: format!("{:?}", dst_type),
-                                    reason: format!("{} result must be larger (src: {} bits, dst: {} bits)", opcode_name, src_bits, dst_bits),
-                                    location: format!("{} instruction", opcode_name),
-                                });
-                            }
+                    _ => {}
+                }
+
+                let callee_type = callee.get_type();
+
+                // If callee is a pointer to function, get the function type
+                let fn_type = if callee_type.is_pointer() {
+                    if let Some(pointee) = callee_type.pointee_type() {
+                        if pointee.is_function() {
+                            pointee.clone()
+                        } else {
+                            return; // Not a function pointer
                         }
+                    } else {
+                        return;
                     }
-                }
-            }
-            Opcode::FPTrunc => {
-                // FPTrunc: operand must be float or vector of floats
-                let operands = inst.operands();
-                if operands.len() >= 1 {
-                    let src_type = operands[0].get_type();
-                    if !self.is_float_or_vector_of_floats(&src_type) {
-                        self.errors.push(VerificationError::InvalidCast {
-                            from: format!("{:?}", src_type),
-                            to: "float".to_string(),
-                            reason: "fptrunc operand must be floating point or vector of floats".to_string(),
-                            location: "fptrunc instruction".to_string(),
+                } else if callee_type.is_function() {
+                    callee_type.clone()
+                } else {
+                    return; // Not a function type
+                };
+
+                if let Some((ret_type, param_types, is_var_arg)) = fn_type.function_info() {
+                    // Indirect calls cannot return token type
+                    if ret_type.is_token() && callee.name().is_none() {
+                        self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                            reason: "Return type cannot be token for indirect call!".to_string(),
+                            location: "call instruction".to_string(),
                         });
                     }
-                    if let Some(result) = inst.result() {
-                        let dst_type = result.get_type();
-                        if !self.is_float_or_vector_of_floats(&dst_type) {
-                            self.errors.push(VerificationError::InvalidCast {
-                                from: format!("{:?}", src_type),
-                                to: format!("{:?}", dst_type),
-                                reason: "fptrunc result must be floating point or vector of floats".to_string(),
-                                location: "fptrunc instruction".to_string(),
-                            });
-                        }
+
+                    // Indirect calls cannot return x86_amx type
+                    if ret_type.is_x86_amx() && callee.name().is_none() {
+                        self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                            reason: "Indirect call can't return x86_amx".to_string(),
+                            location: "call instruction".to_string(),
+                        });
                     }
-                }
-            }
-            Opcode::FPExt => {
-                // FPExt: operand must be float or vector of floats
-                let operands = inst.operands();
-                if operands.len() >= 1 {
-                    let src_type = operands[0].get_type();
-                    if !self.is_float_or_vector_of_floats(&src_type) {
-                        self.errors.push(VerificationError::InvalidCast {
-                            from: format!("{:?}", src_type),
-                            to: "float".to_string(),
-                            reason: "fpext operand must be floating point or vector of floats".to_string(),
-                            location: "fpext instruction".to_string(),
+
+                    let args = &operands[1..];
+
+                    // Check argument count (varargs functions can have more)
+                    if !is_var_arg && args.len() != param_types.len() {
+                        self.report(Rule::CallSignature, VerificationError::InvalidCall {
+                            expected_args: param_types.len(),
+                            found_args: args.len(),
+                            location: "call instruction".to_string(),
+                        });
+                    } else if args.len() < param_types.len() {
+                        // Even varargs functions need at least the fixed parameters
+                        self.report(Rule::CallSignature, VerificationError::InvalidCall {
+                            expected_args: param_types.len(),
+                            found_args: args.len(),
+                            location: "call instruction (too few args for varargs)".to_string(),
                         });
                     }
-                    if let Some(result) = inst.result() {
-                        let dst_type = result.get_type();
-                        if !self.is_float_or_vector_of_floats(&dst_type) {
-                            self.errors.push(VerificationError::InvalidCast {
-                                from: format!("{:?}", src_type),
-                                to: format!("{:?}", dst_type),
-                                reason: "fpext result must be floating point or vector of floats".to_string(),
-                                location: "fpext instruction".to_string(),
-                            });
-                        }
-                    }
-                }
-            }
-            Opcode::FPToUI | Opcode::FPToSI => {
-                // FPToUI/FPToSI: operand must be float (or vector of float), result must be integer (or vector of integer)
-                let operands = inst.operands();
-                let opcode_name = if inst.opcode() == Opcode::FPToUI { "fptoui" } else { "fptosi" };
-                if operands.len() >= 1 {
-                    let src_type = operands[0].get_type();
-                    // Check if source is float or vector of float
-                    let src_is_float = src_type.is_float() ||
-                        (src_type.is_vector() && src_type.vector_info().map_or(false, |(elem, _)| elem.is_float()));
-
-                    if !src_is_float {
-                        self.errors.push(VerificationError::InvalidCast {
-                            from: format!("{:?}", src_type),
-                            to: "float".to_string(),
-                            reason: format!("{} operand must be floating point type", opcode_name),
-                            location: format!("{} instruction", opcode_name),
-                        });
-                    }
-                    if let Some(result) = inst.result() {
-                        let dst_type = result.get_type();
-                        // Check if destination is integer or vector of integer
-                        let dst_is_int = dst_type.is_integer() ||
-                            (dst_type.is_vector() && dst_type.vector_info().map_or(false, |(elem, _)| elem.is_integer()));
-
-                        if !dst_is_int {
-                            self.errors.push(VerificationError::InvalidCast {
-                                from: format!("{:?}", src_type),
-                                to: format!("{:?}", dst_type),
-                                reason: format!("{} result must be integer type", opcode_name),
-                                location: format!("{} instruction", opcode_name),
-                            });
-                        }
-                    }
-                }
-            }
-            Opcode::UIToFP | Opcode::SIToFP => {
-                // UIToFP/SIToFP: operand must be integer (or vector of integer), result must be float (or vector of float)
-                let operands = inst.operands();
-                let opcode_name = if inst.opcode() == Opcode::UIToFP { "uitofp" } else { "sitofp" };
-                if operands.len() >= 1 {
-                    let src_type = operands[0].get_type();
-                    // Check if source is integer or vector of integer
-                    let src_is_int = src_type.is_integer() ||
-                        (src_type.is_vector() && src_type.vector_info().map_or(false, |(elem, _)| elem.is_integer()));
-
-                    if !src_is_int {
-                        self.errors.push(VerificationError::InvalidCast {
-                            from: format!("{:?}", src_type),
-                            to: "integer".to_string(),
-                            reason: format!("{} operand must be integer type", opcode_name),
-                            location: format!("{} instruction", opcode_name),
-                        });
-                    }
-                    if let Some(result) = inst.result() {
-                        let dst_type = result.get_type();
-                        // Check if destination is float or vector of float
-                        let dst_is_float = dst_type.is_float() ||
-                            (dst_type.is_vector() && dst_type.vector_info().map_or(false, |(elem, _)| elem.is_float()));
-
-                        if !dst_is_float {
-                            self.errors.push(VerificationError::InvalidCast {
-                                from: format!("{:?}", src_type),
-                                to: format!("{:?}", dst_type),
-                                reason: format!("{} result must be floating point type", opcode_name),
-                                location: format!("{} instruction", opcode_name),
-                            });
-                        }
-                    }
-                }
-            }
-            Opcode::PtrToInt => {
-                // PtrToInt: operand must be pointer (or vector of pointer), result must be integer (or vector of integer)
-                let operands = inst.operands();
-                if operands.len() >= 1 {
-                    let src_type = operands[0].get_type();
-                    // Check if source is pointer or vector of pointer
-                    let src_is_ptr = src_type.is_pointer() ||
-                        (src_type.is_vector() && src_type.vector_info().map_or(false, |(elem, _)| elem.is_pointer()));
-
-                    if !src_is_ptr {
-                        self.errors.push(VerificationError::InvalidCast {
-                            from: format!("{:?}", src_type),
-                            to: "pointer".to_string(),
-                            reason: "ptrtoint operand must be pointer type".to_string(),
-                            location: "ptrtoint instruction".to_string(),
-                        });
-                    }
-                    if let Some(result) = inst.result() {
-                        let dst_type = result.get_type();
-                        // Check if destination is integer or vector of integer
-                        let dst_is_int = dst_type.is_integer() ||
-                            (dst_type.is_vector() && dst_type.vector_info().map_or(false, |(elem, _)| elem.is_integer()));
-
-                        if !dst_is_int {
-                            self.errors.push(VerificationError::InvalidCast {
-                                from: format!("{:?}", src_type),
-                                to: format!("{:?}", dst_type),
-                                reason: "ptrtoint result must be integer type".to_string(),
-                                location: "ptrtoint instruction".to_string(),
-                            });
-                        }
-                    }
-                }
-            }
-            Opcode::IntToPtr => {
-                // IntToPtr: operand must be integer (or vector of integer), result must be pointer (or vector of pointer)
-                let operands = inst.operands();
-                if operands.len() >= 1 {
-                    let src_type = operands[0].get_type();
-                    // Check if source is integer or vector of integer
-                    let src_is_int = src_type.is_integer() ||
-                        (src_type.is_vector() && src_type.vector_info().map_or(false, |(elem, _)| elem.is_integer()));
-
-                    if !src_is_int {
-                        self.errors.push(VerificationError::InvalidCast {
-                            from: format!("{:?}", src_type),
-                            to: "integer".to_string(),
-                            reason: "inttoptr operand must be integer type".to_string(),
-                            location: "inttoptr instruction".to_string(),
-                        });
-                    }
-                    if let Some(result) = inst.result() {
-                        let dst_type = result.get_type();
-                        // Check if destination is pointer or vector of pointer
-                        let dst_is_ptr = dst_type.is_pointer() ||
-                            (dst_type.is_vector() && dst_type.vector_info().map_or(false, |(elem, _)| elem.is_pointer()));
-
-                        if !dst_is_ptr {
-                            self.errors.push(VerificationError::InvalidCast {
-                                from: format!("{:?}", src_type),
-                                to: format!("{:?}", dst_type),
-                                reason: "inttoptr result must be pointer type".to_string(),
-                                location: "inttoptr instruction".to_string(),
-                            });
-                        }
-                    }
-                }
-            }
-            Opcode::BitCast => {
-                // BitCast: basic type compatibility check
-                let operands = inst.operands();
-                if operands.len() >= 1 {
-                    let src_type = operands[0].get_type();
-                    if let Some(result) = inst.result() {
-                        let dst_type = result.get_type();
-                        // Bitcast cannot convert to/from void
-                        if src_type.is_void() || dst_type.is_void() {
-                            self.errors.push(VerificationError::InvalidCast {
-                                from: format!("{:?}", src_type),
-                                to: format!("{:?}", dst_type),
-                                reason: "bitcast cannot convert to/from void type".to_string(),
-                                location: "bitcast instruction".to_string(),
-                            });
-                        }
-
-                        // Check if types involve vectors of pointers
-                        let src_is_ptr = src_type.is_pointer();
-                        let dst_is_ptr = dst_type.is_pointer();
-                        let src_is_vec_of_ptr = src_type.is_vector() &&
-                            src_type.vector_info().map_or(false, |(elem, _)| elem.is_pointer());
-                        let dst_is_vec_of_ptr = dst_type.is_vector() &&
-                            dst_type.vector_info().map_or(false, |(elem, _)| elem.is_pointer());
-
-                        // Cannot bitcast between scalar pointer and vector of pointers with size != 1
-                        if (src_is_ptr && dst_is_vec_of_ptr) || (src_is_vec_of_ptr && dst_is_ptr) {
-                            let vec_size = if dst_is_vec_of_ptr {
-                                dst_type.vector_info().map(|(_, size)| size).unwrap_or(0)
-                            } else {
-                                src_type.vector_info().map(|(_, size)| size).unwrap_or(0)
-                            };
-                            // Only error if vector size is not 1 (size 1 is allowed, same size as scalar)
-                            if vec_size != 1 {
-                                self.errors.push(VerificationError::InvalidCast {
-                                    from: format!("{:?}", src_type),
-                                    to: format!("{:?}", dst_type),
-                                    reason: format!("invalid cast opcode for cast from '{}' to '{}'",
-                                        if src_is_ptr { "ptr" } else { &format!("{:?}", src_type) },
-                                        if dst_is_vec_of_ptr { &format!("{:?}", dst_type) } else { "ptr" }),
-                                    location: "bitcast instruction".to_string(),
-                                });
-                            }
-                        }
-
-                        // Bitcast between pointers and non-pointers requires compatible sizes
-                        if src_is_ptr != dst_is_ptr && !src_is_vec_of_ptr && !dst_is_vec_of_ptr {
-                            // One is pointer, other is not - check if non-pointer is integer
-                            let non_ptr_type = if src_is_ptr { dst_type.clone() } else { src_type.clone() };
-                            if !non_ptr_type.is_integer() && !non_ptr_type.is_vector() {
-                                self.errors.push(VerificationError::InvalidCast {
-                                    from: format!("{:?}", src_type),
-                                    to: format!("{:?}", dst_type),
-                                    reason: "bitcast between pointer and non-integer type is invalid".to_string(),
-                                    location: "bitcast instruction".to_string(),
-                                });
-                            }
-                        }
-
-                        // Bitcast cannot change address space (both scalar and vector pointers)
-                        let check_addrspace = (src_is_ptr && dst_is_ptr) ||
-                            (src_is_vec_of_ptr && dst_is_vec_of_ptr);
-                        if check_addrspace {
-                            let src_addrspace = if src_is_ptr {
-                                src_type.address_space().unwrap_or(0)
-                            } else {
-                                src_type.vector_info()
-                                    .and_then(|(elem, _)| elem.address_space())
-                                    .unwrap_or(0)
-                            };
-                            let dst_addrspace = if dst_is_ptr {
-                                dst_type.address_space().unwrap_or(0)
-                            } else {
-                                dst_type.vector_info()
-                                    .and_then(|(elem, _)| elem.address_space())
-                                    .unwrap_or(0)
-                            };
-                            if src_addrspace != dst_addrspace {
-                                let src_desc = if src_is_ptr { format!("ptr") } else { format!("<{:?}>", src_type) };
-                                let dst_desc = if dst_is_ptr {
-                                    format!("ptr addrspace({})", dst_addrspace)
-                                } else {
-                                    format!("<{:?}>", dst_type)
-                                };
-                                self.errors.push(VerificationError::InvalidCast {
-                                    from: format!("{:?}", src_type),
-                                    to: format!("{:?}", dst_type),
-                                    reason: format!("invalid cast opcode for cast from '{}' to '{}'", src_desc, dst_desc),
-                                    location: "bitcast instruction".to_string(),
-                                });
-                            }
-                        }
-                    }
-                }
-            }
-            Opcode::AddrSpaceCast => {
-                // AddrSpaceCast: both must be pointers
-                let operands = inst.operands();
-                if operands.len() >= 1 {
-                    let src_type = operands[0].get_type();
-                    if !src_type.is_pointer() {
-                        self.errors.push(VerificationError::InvalidCast {
-                            from: format!("{:?}", src_type),
-                            to: "pointer".to_string(),
-                            reason: "addrspacecast operand must be pointer type".to_string(),
-                            location: "addrspacecast instruction".to_string(),
-                        });
-                    }
-                    if let Some(result) = inst.result() {
-                        let dst_type = result.get_type();
-                        if !dst_type.is_pointer() {
-                            self.errors.push(VerificationError::InvalidCast {
-                                from: format!("{:?}", src_type),
-                                to: format!("{:?}", dst_type),
-                                reason: "addrspacecast result must be pointer type".to_string(),
-                                location: "addrspacecast instruction".to_string(),
-                            });
-                        }
-                    }
-                }
-            }
-
-            // === AGGREGATE OPERATIONS ===
-            Opcode::ExtractElement => {
-                // ExtractElement: first operand must be vector, second must be integer index
-                let operands = inst.operands();
-                if operands.len() >= 2 {
-                    let vec_type = operands[0].get_type();
-                    let idx_type = operands[1].get_type();
-
-                    if !vec_type.is_vector() {
-                        self.errors.push(VerificationError::InvalidInstruction {
-                            reason: format!("extractelement first operand must be vector type, got {:?}", vec_type),
-                            location: "extractelement instruction".to_string(),
-                        });
-                    }
-
-                    if !idx_type.is_integer() {
-                        self.errors.push(VerificationError::InvalidInstruction {
-                            reason: format!("extractelement index must be integer type, got {:?}", idx_type),
-                            location: "extractelement instruction".to_string(),
-                        });
-                    }
-                }
-            }
-            Opcode::InsertElement => {
-                // InsertElement: first operand must be vector, value must match element type, index must be integer
-                let operands = inst.operands();
-                if operands.len() >= 3 {
-                    let vec_type = operands[0].get_type();
-                    let val_type = operands[1].get_type();
-                    let idx_type = operands[2].get_type();
-
-                    if !vec_type.is_vector() {
-                        self.errors.push(VerificationError::InvalidInstruction {
-                            reason: format!("insertelement first operand must be vector type, got {:?}", vec_type),
-                            location: "insertelement instruction".to_string(),
-                        });
-                    } else if let Some((elem_type, _)) = vec_type.vector_info() {
-                        if *val_type != *elem_type {
-                            self.errors.push(VerificationError::TypeMismatch {
-                                expected: format!("{:?}", elem_type),
-                                found: format!("{:?}", val_type),
-                                location: "insertelement value".to_string(),
-                            });
-                        }
-                    }
-
-                    if !idx_type.is_integer() {
-                        self.errors.push(VerificationError::InvalidInstruction {
-                            reason: format!("insertelement index must be integer type, got {:?}", idx_type),
-                            location: "insertelement instruction".to_string(),
-                        });
-                    }
-                }
-            }
-            Opcode::ExtractValue => {
-                // ExtractValue: operand must be aggregate type (struct or array)
-                let operands = inst.operands();
-                if operands.len() >= 1 {
-                    let agg_type = operands[0].get_type();
-                    if !agg_type.is_struct() && !agg_type.is_array() {
-                        self.errors.push(VerificationError::InvalidInstruction {
-                            reason: format!("extractvalue operand must be aggregate type (struct or array), got {:?}", agg_type),
-                            location: "extractvalue instruction".to_string(),
-                        });
-                    }
-                }
-            }
-            Opcode::InsertValue => {
-                // InsertValue: operand must be aggregate type (struct or array)
-                let operands = inst.operands();
-                if operands.len() >= 2 {
-                    let agg_type = operands[0].get_type();
-                    if !agg_type.is_struct() && !agg_type.is_array() {
-                        self.errors.push(VerificationError::InvalidInstruction {
-                            reason: format!("insertvalue operand must be aggregate type (struct or array), got {:?}", agg_type),
-                            location: "insertvalue instruction".to_string(),
-                        });
-                    }
-                }
-            }
-            Opcode::GetElementPtr => {
-                // GetElementPtr: base must be pointer or vector of pointers
-                let operands = inst.operands();
-                if operands.len() >= 1 {
-                    let base_type = operands[0].get_type();
-                    // Check if base is pointer or vector of pointers
-                    let is_valid_base = base_type.is_pointer() ||
-                        (base_type.is_vector() && base_type.vector_info().map_or(false, |(elem, _)| elem.is_pointer()));
-
-                    if !is_valid_base {
-                        self.errors.push(VerificationError::InvalidInstruction {
-                            reason: format!("getelementptr base must be pointer or vector of pointers, got {:?}", base_type),
-                            location: "getelementptr instruction".to_string(),
-                        });
-                    }
-
-                    // All index operands must be integers or vectors of integers
-                    for (i, operand) in operands.iter().enumerate().skip(1) {
-                        let idx_type = operand.get_type();
-                        let is_valid_index = idx_type.is_integer() ||
-                            (idx_type.is_vector() && idx_type.vector_info().map_or(false, |(elem, _)| elem.is_integer()));
-
-                        if !is_valid_index {
-                            self.errors.push(VerificationError::InvalidInstruction {
-                                reason: format!("getelementptr index {} must be integer or vector of integers, got {:?}", i-1, idx_type),
-                                location: "getelementptr instruction".to_string(),
-                            });
-                        }
-                    }
-
-                    // Validate that we don't index through a pointer within an aggregate
-                    // This is invalid: getelementptr {i32, ptr}, ptr %X, i32 0, i32 1, i32 0
-                    // After indexing to field 1 (the ptr), we get a pointer, and cannot index further
-                    self.verify_gep_no_pointer_indexing(inst, &operands);
-
-                    // GEP cannot target structures containing scalable vectors
-                    if let Some(source_type) = inst.gep_source_type() {
-                        if self.contains_scalable_type(source_type) {
-                            self.errors.push(VerificationError::InvalidInstruction {
-                                reason: "getelementptr cannot target structure that contains scalable vector type".to_string(),
-                                location: "getelementptr instruction".to_string(),
-                            });
-                        }
-                    }
-                }
-            }
-
-            // === FUNCTION CALL VALIDATION ===
-            Opcode::Call => {
-                // Call: validate argument count and types match function signature
-                let operands = inst.operands();
-                if operands.is_empty() {
-                    return; // No callee, skip validation
-                }
-
-                let callee = &operands[0];
-
-                // Check if this is an intrinsic call
-                if let Some(callee_name) = callee.name() {
-                    if callee_name.starts_with("llvm.") {
-                        self.verify_intrinsic_call(inst, callee_name);
-                    }
-                }
-
-                // Check calling convention restrictions
-                // Some calling conventions don't permit calls
-                use crate::function::CallingConvention;
-                let cc = self.current_function_calling_convention;
-                match cc {
-                    CallingConvention::AMDGPU_Kernel |
-                    CallingConvention::AMDGPU_VS |
-                    CallingConvention::AMDGPU_GS |
-                    CallingConvention::AMDGPU_PS |
-                    CallingConvention::AMDGPU_CS |
-                    CallingConvention::AMDGPU_CS_Chain |
-                    CallingConvention::AMDGPU_CS_Chain_Preserve |
-                    CallingConvention::AMDGPU_HS |
-                    CallingConvention::AMDGPU_LS |
-                    CallingConvention::AMDGPU_ES |
-                    CallingConvention::SPIR_Kernel => {
-                        self.errors.push(VerificationError::InvalidInstruction {
-                            reason: "calling convention does not permit calls".to_string(),
-                            location: "call instruction".to_string(),
-                        });
-                    }
-                    _ => {}
-                }
-
-                let callee_type = callee.get_type();
-
-                // If callee is a pointer to function, get the function type
-                let fn_type = if callee_type.is_pointer() {
-                    if let Some(pointee) = callee_type.pointee_type() {
-                        if pointee.is_function() {
-                            pointee.clone()
-                        } else {
-                            return; // Not a function pointer
-                        }
-                    } else {
-                        return;
-                    }
-                } else if callee_type.is_function() {
-                    callee_type.clone()
-                } else {
-                    return; // Not a function type
-                };
-
-                if let Some((ret_type, param_types, is_var_arg)) = fn_type.function_info() {
-                    // Indirect calls cannot return token type
-                    if ret_type.is_token() && callee.name().is_none() {
-                        self.errors.push(VerificationError::InvalidInstruction {
-                            reason: "Return type cannot be token for indirect call!".to_string(),
-                            location: "call instruction".to_string(),
-                        });
-                    }
-
-                    // Indirect calls cannot return x86_amx type
-                    if ret_type.is_x86_amx() && callee.name().is_none() {
-                        self.errors.push(VerificationError::InvalidInstruction {
-                            reason: "Indirect call can't return x86_amx".to_string(),
-                            location: "call instruction".to_string(),
-                        });
-                    }
-
-                    let args = &operands[1..];
-
-                    // Check argument count (varargs functions can have more)
-                    if !is_var_arg && args.len() != param_types.len() {
-                        self.errors.push(VerificationError::InvalidCall {
-                            expected_args: param_types.len(),
-                            found_args: args.len(),
-                            location: "call instruction".to_string(),
-                        });
-                    } else if args.len() < param_types.len() {
-                        // Even varargs functions need at least the fixed parameters
-                        self.errors.push(VerificationError::InvalidCall {
-                            expected_args: param_types.len(),
-                            found_args: args.len(),
-                            location: "call instruction (too few args for varargs)".to_string(),
-                        });
-                    }
-
-                    // Check argument types match parameter types
-                    for (i, (arg, param_type)) in args.iter().zip(param_types.iter()).enumerate() {
-                        let arg_type = arg.get_type();
-
-                        // Check for invalid argument types (label, token)
-                        if arg_type.is_label() {
-                            self.errors.push(VerificationError::InvalidInstruction {
-                                reason: "invalid type for function argument".to_string(),
-                                location: format!("call argument {}", i),
+
+                    // Check argument types match parameter types
+                    for (i, (arg, param_type)) in args.iter().zip(param_types.iter()).enumerate() {
+                        let arg_type = arg.get_type();
+
+                        // Check for invalid argument types (label, token)
+                        if arg_type.is_label() {
+                            self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                                reason: "invalid type for function argument".to_string(),
+                                location: format!("call argument {}", i),
                             });
                         }
 
                         // Constant x86_amx values are not allowed in arguments
                         if arg_type.is_x86_amx() && arg.is_constant() {
-                            self.errors.push(VerificationError::InvalidInstruction {
+                            self.report(Rule::Instruction, VerificationError::InvalidInstruction {
                                 reason: "const x86_amx is not allowed in argument!".to_string(),
                                 location: format!("call argument {}", i),
                             });
@@ -1955,7 +1432,7 @@ impl<'a> Verifier<'a> {
                         };
 
                         if !types_match {
-                            self.errors.push(VerificationError::TypeMismatch {
+                            self.report(Rule::OperandTypes, VerificationError::TypeMismatch {
                                 expected: format!("{:?}", param_type),
                                 found: format!("{:?}", arg_type),
                                 location: format!("call argument {}", i),
@@ -1991,7 +1468,7 @@ impl<'a> Verifier<'a> {
                         let _allow_bitcast = !is_llvm_intrinsic;
 
                         if !types_match && !is_llvm_intrinsic && false {  // Disabled for now
-                            self.errors.push(VerificationError::TypeMismatch {
+                            self.report(Rule::OperandTypes, VerificationError::TypeMismatch {
                                 expected: format!("{:?}", ret_type),
                                 found: format!("{:?}", result_type),
                                 location: "call result type".to_string(),
@@ -2013,35 +1490,35 @@ impl<'a> Verifier<'a> {
                     if let Some(pointee) = result_type.pointee_type() {
                         // Target types are allowed even though they're technically unsized
                         if !pointee.is_sized() && !self.is_target_type(&pointee) {
-                            self.errors.push(VerificationError::InvalidInstruction {
+                            self.report(Rule::Instruction, VerificationError::InvalidInstruction {
                                 reason: format!("alloca of unsized type {:?}", pointee),
                                 location: "alloca instruction".to_string(),
                             });
                         }
                         // Cannot allocate a function type
                         if pointee.is_function() {
-                            self.errors.push(VerificationError::InvalidInstruction {
+                            self.report(Rule::Instruction, VerificationError::InvalidInstruction {
                                 reason: "invalid type for alloca".to_string(),
                                 location: "alloca instruction".to_string(),
                             });
                         }
                         // Cannot allocate void type
                         if pointee.is_void() {
-                            self.errors.push(VerificationError::InvalidInstruction {
+                            self.report(Rule::Instruction, VerificationError::InvalidInstruction {
                                 reason: "Cannot allocate unsized type".to_string(),
                                 location: "alloca instruction".to_string(),
                             });
                         }
                         // Cannot allocate token type
                         if pointee.is_token() {
-                            self.errors.push(VerificationError::InvalidInstruction {
+                            self.report(Rule::Instruction, VerificationError::InvalidInstruction {
                                 reason: "invalid type for alloca".to_string(),
                                 location: "alloca instruction".to_string(),
                             });
                         }
                         // Cannot allocate x86_amx type
                         if pointee.is_x86_amx() {
-                            self.errors.push(VerificationError::InvalidInstruction {
+                            self.report(Rule::Instruction, VerificationError::InvalidInstruction {
                                 reason: "Cannot allocate an x86_amx with allocas".to_string(),
                                 location: "alloca instruction".to_string(),
                             });
@@ -2049,7 +1526,7 @@ impl<'a> Verifier<'a> {
                         // Cannot allocate vectors with x86_amx elements
                         if let Some((elem_ty, _)) = pointee.vector_info() {
                             if elem_ty.is_x86_amx() {
-                                self.errors.push(VerificationError::InvalidInstruction {
+                                self.report(Rule::Instruction, VerificationError::InvalidInstruction {
                                     reason: "invalid vector element type".to_string(),
                                     location: "alloca instruction".to_string(),
                                 });
@@ -2058,7 +1535,7 @@ impl<'a> Verifier<'a> {
                         // Cannot allocate arrays with x86_amx elements
                         if let Some((elem_ty, _)) = pointee.array_info() {
                             if elem_ty.is_x86_amx() {
-                                self.errors.push(VerificationError::InvalidInstruction {
+                                self.report(Rule::Instruction, VerificationError::InvalidInstruction {
                                     reason: "Cannot allocate an array of x86_amx".to_string(),
                                     location: "alloca instruction".to_string(),
                                 });
@@ -2067,7 +1544,7 @@ impl<'a> Verifier<'a> {
 
                         // Cannot allocate types containing scalable vectors
                         if self.contains_scalable_type(&pointee) {
-                            self.errors.push(VerificationError::InvalidInstruction {
+                            self.report(Rule::Instruction, VerificationError::InvalidInstruction {
                                 reason: "Cannot allocate unsized type".to_string(),
                                 location: "alloca instruction".to_string(),
                             });
@@ -2086,7 +1563,7 @@ impl<'a> Verifier<'a> {
                     while case_idx < operands.len() {
                         let case_type = operands[case_idx].get_type();
                         if *case_type != *cond_type {
-                            self.errors.push(VerificationError::TypeMismatch {
+                            self.report(Rule::OperandTypes, VerificationError::TypeMismatch {
                                 expected: format!("{:?}", cond_type),
                                 found: format!("{:?}", case_type),
                                 location: format!("switch case {}", (case_idx - 2) / 2),
@@ -2102,7 +1579,7 @@ impl<'a> Verifier<'a> {
 
                 // PHI must have even number of operands (value/block pairs)
                 if operands.len() % 2 != 0 {
-                    self.errors.push(VerificationError::InvalidPhi {
+                    self.report(Rule::PhiNode, VerificationError::InvalidPhi {
                         reason: format!("phi must have even number of operands (value/block pairs), found {}", operands.len()),
                         location: "phi instruction".to_string(),
                     });
@@ -2128,7 +1605,7 @@ impl<'a> Verifier<'a> {
                             };
 
                             if values_different {
-                                self.errors.push(VerificationError::InvalidPhi {
+                                self.report(Rule::PhiNode, VerificationError::InvalidPhi {
                                     reason: format!("PHI node has multiple entries for the same basic block with different values"),
                                     location: format!("phi instruction, block {}", block_name),
                                 });
@@ -2145,7 +1622,7 @@ impl<'a> Verifier<'a> {
 
                     // Token types cannot be used in PHI nodes
                     if result_type.is_token() {
-                        self.errors.push(VerificationError::InvalidPhi {
+                        self.report(Rule::PhiNode, VerificationError::InvalidPhi {
                             reason: "PHI nodes cannot produce token types".to_string(),
                             location: "phi instruction".to_string(),
                         });
@@ -2160,7 +1637,7 @@ impl<'a> Verifier<'a> {
 
                             // Check if value is a token type
                             if value_type.is_token() {
-                                self.errors.push(VerificationError::InvalidPhi {
+                                self.report(Rule::PhiNode, VerificationError::InvalidPhi {
                                     reason: "PHI nodes cannot have token type operands".to_string(),
                                     location: format!("phi incoming value {}", i / 2),
                                 });
@@ -2173,7 +1650,7 @@ impl<'a> Verifier<'a> {
                                 *value_type == *result_type
                             };
                             if !types_match {
-                                self.errors.push(VerificationError::TypeMismatch {
+                                self.report(Rule::OperandTypes, VerificationError::TypeMismatch {
                                     expected: format!("{:?}", result_type),
                                     found: format!("{:?}", value_type),
                                     location: format!("phi incoming value {}", i / 2),
@@ -2184,167 +1661,38 @@ impl<'a> Verifier<'a> {
                     }
                 }
             }
-            Opcode::ShuffleVector => {
-                // ShuffleVector: vec1 and vec2 must be same type, mask must be vector of integers
+            Opcode::Store => {
+                // Store: value type must be sized
+                // Skip validation if types are void (indicates parser limitations)
                 let operands = inst.operands();
-                if operands.len() >= 3 {
-                    let vec1_type = operands[0].get_type();
-                    let vec2_type = operands[1].get_type();
-                    let mask_type = operands[2].get_type();
-
-                    // vec1 and vec2 must be same type
-                    if *vec1_type != *vec2_type {
-                        self.errors.push(VerificationError::TypeMismatch {
-                            expected: format!("{:?}", vec1_type),
-                            found: format!("{:?}", vec2_type),
-                            location: "shufflevector second vector".to_string(),
-                        });
-                    }
+                if operands.len() >= 2 {
+                    let value_type = operands[0].get_type();
+                    let ptr_type = operands[1].get_type();
 
-                    // Both must be vectors
-                    if !vec1_type.is_vector() {
-                        self.errors.push(VerificationError::InvalidInstruction {
-                            reason: format!("shufflevector operands must be vector types, got {:?}", vec1_type),
-                            location: "shufflevector instruction".to_string(),
-                        });
+                    // Skip validation if either type is void (parser limitation)
+                    if value_type.is_void() || ptr_type.is_void() {
+                        return;
                     }
 
-                    // Mask must be a vector of integers
-                    if mask_type.is_vector() {
-                        if let Some((elem_type, _)) = mask_type.vector_info() {
-                            if !elem_type.is_integer() {
-                                self.errors.push(VerificationError::InvalidInstruction {
-                                    reason: format!("shufflevector mask must be vector of integers, got vector of {:?}", elem_type),
-                                    location: "shufflevector instruction".to_string(),
-                                });
-                            }
-                        }
-                    } else {
-                        self.errors.push(VerificationError::InvalidInstruction {
-                            reason: format!("shufflevector mask must be vector type, got {:?}", mask_type),
-                            location: "shufflevector instruction".to_string(),
+                    // Validate pointer operand is actually a pointer
+                    if !ptr_type.is_pointer() {
+                        self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                            reason: format!("store pointer operand must be a pointer type, got {:?}", ptr_type),
+                            location: "store instruction".to_string(),
                         });
                     }
-                }
-            }
-            Opcode::Shl | Opcode::LShr | Opcode::AShr => {
-                // Shift operations: both operands must be same integer or vector type
-                let operands = inst.operands();
-                if operands.len() >= 2 {
-                    let value_type = operands[0].get_type();
-                    let shift_type = operands[1].get_type();
 
-                    // Value must be integer or vector of integers
-                    if !value_type.is_integer() && !value_type.is_vector() {
-                        self.errors.push(VerificationError::InvalidInstruction {
-                            reason: format!("shift operand must be integer or vector type, got {:?}", value_type),
-                            location: format!("{:?} instruction", inst.opcode()),
-                        });
-                    }
-
-                    // Shift amount must have same type as value
-                    if *value_type != *shift_type {
-                        self.errors.push(VerificationError::TypeMismatch {
-                            expected: format!("{:?}", value_type),
-                            found: format!("{:?}", shift_type),
-                            location: format!("{:?} shift amount", inst.opcode()),
-                        });
-                    }
-                }
-            }
-            Opcode::Add | Opcode::Sub | Opcode::Mul | Opcode::UDiv | Opcode::SDiv |
-            Opcode::URem | Opcode::SRem |
-            Opcode::And | Opcode::Or | Opcode::Xor |
-            Opcode::FAdd | Opcode::FSub | Opcode::FMul | Opcode::FDiv | Opcode::FRem => {
-                // Binary operations: both operands must have same type
-                let operands = inst.operands();
-                if operands.len() >= 2 {
-                    let op1_type = operands[0].get_type();
-                    let op2_type = operands[1].get_type();
-                    if *op1_type != *op2_type {
-                        self.errors.push(VerificationError::TypeMismatch {
-                            expected: format!("{:?}", op1_type),
-                            found: format!("{:?}", op2_type),
-                            location: format!("{:?} instruction", inst.opcode()),
-                        });
-                    }
-
-                    // Integer operations must have integer operands
-                    match inst.opcode() {
-                        Opcode::Add | Opcode::Sub | Opcode::Mul | Opcode::UDiv | Opcode::SDiv |
-                        Opcode::URem | Opcode::SRem | Opcode::And | Opcode::Or | Opcode::Xor => {
-                            if !op1_type.is_integer() && !op1_type.is_vector() {
-                                self.errors.push(VerificationError::InvalidInstruction {
-                                    reason: format!("integer operation requires integer or vector operands, got {:?}", op1_type),
-                                    location: format!("{:?} instruction", inst.opcode()),
-                                });
-                            }
-                        }
-                        Opcode::FAdd | Opcode::FSub | Opcode::FMul | Opcode::FDiv | Opcode::FRem => {
-                            if !op1_type.is_float() && !op1_type.is_vector() {
-                                self.errors.push(VerificationError::InvalidInstruction {
-                                    reason: format!("floating point operation requires float or vector operands, got {:?}", op1_type),
-                                    location: format!("{:?} instruction", inst.opcode()),
-                                });
-                            }
-                        }
-                        _ => {}
-                    }
-                }
-            }
-            Opcode::ICmp | Opcode::FCmp => {
-                // Comparison: both operands must have same type
-                let operands = inst.operands();
-                if operands.len() >= 2 {
-                    let op1_type = operands[0].get_type();
-                    let op2_type = operands[1].get_type();
-                    // Allow pointer type equivalence for comparisons
-                    let types_match = if op1_type.is_pointer() && op2_type.is_pointer() {
-                        true
-                    } else {
-                        *op1_type == *op2_type
-                    };
-                    if !types_match {
-                        self.errors.push(VerificationError::TypeMismatch {
-                            expected: format!("{:?}", op1_type),
-                            found: format!("{:?}", op2_type),
-                            location: "comparison operands".to_string(),
-                        });
-                    }
-                }
-            }
-            Opcode::Store => {
-                // Store: value type must be sized
-                // Skip validation if types are void (indicates parser limitations)
-                let operands = inst.operands();
-                if operands.len() >= 2 {
-                    let value_type = operands[0].get_type();
-                    let ptr_type = operands[1].get_type();
-
-                    // Skip validation if either type is void (parser limitation)
-                    if value_type.is_void() || ptr_type.is_void() {
-                        return;
-                    }
-
-                    // Validate pointer operand is actually a pointer
-                    if !ptr_type.is_pointer() {
-                        self.errors.push(VerificationError::InvalidInstruction {
-                            reason: format!("store pointer operand must be a pointer type, got {:?}", ptr_type),
-                            location: "store instruction".to_string(),
-                        });
-                    }
-
-                    // Value must be sized (structs are sized in LLVM)
-                    if !value_type.is_sized() {
-                        self.errors.push(VerificationError::InvalidInstruction {
-                            reason: "storing unsized types is not allowed".to_string(),
-                            location: "store instruction".to_string(),
+                    // Value must be sized (structs are sized in LLVM)
+                    if !value_type.is_sized() {
+                        self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                            reason: "storing unsized types is not allowed".to_string(),
+                            location: "store instruction".to_string(),
                         });
                     }
 
                     // Cannot store types containing scalable vectors
                     if self.contains_scalable_type(&value_type) {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::Instruction, VerificationError::InvalidInstruction {
                             reason: "Cannot store unsized types".to_string(),
                             location: "store instruction".to_string(),
                         });
@@ -2377,7 +1725,7 @@ impl<'a> Verifier<'a> {
                     }
 
                     if !ptr_type.is_pointer() {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::Instruction, VerificationError::InvalidInstruction {
                             reason: format!("load operand must be a pointer type, got {:?}", ptr_type),
                             location: "load instruction".to_string(),
                         });
@@ -2393,7 +1741,7 @@ impl<'a> Verifier<'a> {
 
                     // Check if loading an unsized type (including unsized target types)
                     if !result_type.is_sized() {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::Instruction, VerificationError::InvalidInstruction {
                             reason: "loading unsized types is not allowed".to_string(),
                             location: format!("  %t = load {:?}, ptr %ptr", result_type),
                         });
@@ -2401,54 +1749,13 @@ impl<'a> Verifier<'a> {
 
                     // Cannot load types containing scalable vectors
                     if self.contains_scalable_type(&result_type) {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::Instruction, VerificationError::InvalidInstruction {
                             reason: "Cannot load unsized types".to_string(),
                             location: "load instruction".to_string(),
                         });
                     }
                 }
             }
-            Opcode::Select => {
-                // Select: condition must be i1 or vector of i1, both values must match type
-                let operands = inst.operands();
-                if operands.len() >= 3 {
-                    let cond_type = operands[0].get_type();
-                    let true_type = operands[1].get_type();
-                    let false_type = operands[2].get_type();
-
-                    // Select values cannot have token type
-                    if true_type.is_token() || false_type.is_token() {
-                        self.errors.push(VerificationError::InvalidInstruction {
-                            reason: "select values cannot have token type".to_string(),
-                            location: "select instruction".to_string(),
-                        });
-                    }
-
-                    // Allow pointer type equivalence
-                    let types_match = if true_type.is_pointer() && false_type.is_pointer() {
-                        true
-                    } else {
-                        *true_type == *false_type
-                    };
-
-                    if !types_match {
-                        self.errors.push(VerificationError::TypeMismatch {
-                            expected: format!("{:?}", true_type),
-                            found: format!("{:?}", false_type),
-                            location: "select true/false values".to_string(),
-                        });
-                    }
-
-                    // Condition should be i1 (or vector of i1)
-                    if !cond_type.is_integer() && !cond_type.is_vector() {
-                        self.errors.push(VerificationError::TypeMismatch {
-                            expected: "i1 or vector of i1".to_string(),
-                            found: format!("{:?}", cond_type),
-                            location: "select condition".to_string(),
-                        });
-                    }
-                }
-            }
             // === EXCEPTION HANDLING VALIDATION ===
             Opcode::LandingPad => {
                 // LandingPad: must be first non-PHI instruction in block
@@ -2464,7 +1771,7 @@ impl<'a> Verifier<'a> {
                     // Note: AMDGPU_CS_Chain and _Preserve removed - they DO allow calls
                     CallingConvention::AMDGPU_Kernel |
                     CallingConvention::SPIR_Kernel => {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::CallingConvention, VerificationError::InvalidInstruction {
                             reason: "calling convention does not permit calls".to_string(),
                             location: "invoke instruction".to_string(),
                         });
@@ -2477,7 +1784,7 @@ impl<'a> Verifier<'a> {
                 // Basic validation: check it's a valid function call
                 let operands = inst.operands();
                 if operands.is_empty() {
-                    self.errors.push(VerificationError::InvalidExceptionHandling {
+                    self.report(Rule::ExceptionHandling, VerificationError::InvalidExceptionHandling {
                         reason: "invoke must have a callee".to_string(),
                         location: "invoke instruction".to_string(),
                     });
@@ -2501,7 +1808,7 @@ impl<'a> Verifier<'a> {
                                 callee_name.contains("clang.arc.attachedcall");
 
                             if !allowed {
-                                self.errors.push(VerificationError::InvalidExceptionHandling {
+                                self.report(Rule::ExceptionHandling, VerificationError::InvalidExceptionHandling {
                                     reason: "Cannot invoke an intrinsic other than donothing, patchpoint, statepoint, coro_resume, coro_destroy, clang.arc.attachedcall or wasm.(re)throw".to_string(),
                                     location: format!("invoke {}", callee_name),
                                 });
@@ -2513,7 +1820,7 @@ impl<'a> Verifier<'a> {
             Opcode::Resume => {
                 // Resume: must be in a function with a personality
                 if !self.current_function_has_personality {
-                    self.errors.push(VerificationError::InvalidExceptionHandling {
+                    self.report(Rule::ExceptionHandling, VerificationError::InvalidExceptionHandling {
                         reason: "ResumeInst needs to be in a function with a personality.".to_string(),
                         location: "resume instruction".to_string(),
                     });
@@ -2522,14 +1829,14 @@ impl<'a> Verifier<'a> {
                 // Resume: must have exactly one operand of aggregate type
                 let operands = inst.operands();
                 if operands.len() != 1 {
-                    self.errors.push(VerificationError::InvalidExceptionHandling {
+                    self.report(Rule::ExceptionHandling, VerificationError::InvalidExceptionHandling {
                         reason: format!("resume must have exactly one operand, found {}", operands.len()),
                         location: "resume instruction".to_string(),
                     });
                 } else {
                     let arg_type = operands[0].get_type();
                     if !arg_type.is_struct() {
-                        self.errors.push(VerificationError::InvalidExceptionHandling {
+                        self.report(Rule::ExceptionHandling, VerificationError::InvalidExceptionHandling {
                             reason: format!("resume operand must be aggregate type, got {:?}", arg_type),
                             location: "resume instruction".to_string(),
                         });
@@ -2551,74 +1858,365 @@ impl<'a> Verifier<'a> {
         }
     }
 
-    /// Verify SSA form
-    pub fn verify_ssa_form(&mut self, _function: &Function) {
-        // Disabled for now as it catches parser bugs rather than IR semantic errors
-        // TODO: Re-enable once parser properly populates instruction operands and results
+    /// Verify SSA form: every local value is defined once, and each use is
+    /// dominated by its definition. Uses in unreachable blocks are not
+    /// checked, since every definition dominates them.
+    pub fn verify_ssa_form(&mut self, function: &Function) {
+        use std::collections::HashMap;
+        use crate::analysis::DominatorTree;
+
+        let blocks = function.basic_blocks();
+        let domtree = DominatorTree::new(function);
+        let cfg = domtree.cfg();
+        let block_label = |index: usize| cfg.block_name(index);
+        let inst_location = |block: usize, index: usize, inst: &Instruction| {
+            format!("instruction {} ({:?}) in block %{}", index, inst.opcode(), block_label(block))
+        };
+
+        // Collect definitions, flagging redefinitions
+        let mut defs: HashMap<SsaKey, SsaDef> = HashMap::new();
+        for (index, arg) in function.arguments().iter().enumerate() {
+            if let Some(key) = SsaKey::of(arg) {
+                defs.insert(key, SsaDef {
+                    site: SsaSite::Argument,
+                    ty: arg.get_type().clone(),
+                    location: format!("argument {} of @{}", index, function.name()),
+                });
+            }
+        }
+        for (b, bb) in blocks.iter().enumerate() {
+            for (i, inst) in bb.instructions().iter().enumerate() {
+                let Some(result) = inst.result() else { continue };
+                let Some(key) = SsaKey::of(result) else { continue };
+                let location = inst_location(b, i, inst);
+                if let Some(existing) = defs.get(&key) {
+                    let name = result.name().unwrap_or_default();
+                    if existing.ty != *result.get_type() {
+                        self.report(Rule::SsaForm, VerificationError::TypeMismatch {
+                            expected: format!("{:?}", existing.ty),
+                            found: format!("{:?}", result.get_type()),
+                            location: format!("'%{}' defined with type", name),
+                        });
+                    } else {
+                        self.report(Rule::SsaForm, VerificationError::InvalidSSA {
+                            value: name.to_string(),
+                            reason: "multiple definition of local value".to_string(),
+                            definition: existing.location.clone(),
+                            location,
+                        });
+                    }
+                    continue;
+                }
+                let site = if inst.opcode() == Opcode::Invoke {
+                    // The result is only available along the normal edge
+                    let operands = inst.operands();
+                    let normal = operands.len().checked_sub(2)
+                        .and_then(|n| operands[n].name())
+                        .and_then(|name| cfg.block_index(name));
+                    SsaSite::Invoke { block: b, normal }
+                } else {
+                    SsaSite::Instruction { block: b, index: i }
+                };
+                defs.insert(key, SsaDef { site, ty: result.get_type().clone(), location });
+            }
+        }
+
+        // Check every use against its definition
+        for (b, bb) in blocks.iter().enumerate() {
+            for (i, inst) in bb.instructions().iter().enumerate() {
+                let location = inst_location(b, i, inst);
+                if inst.opcode() == Opcode::PHI {
+                    // Incoming values must be available at the end of their incoming block
+                    for pair in inst.operands().chunks(2) {
+                        let [value, label] = pair else { continue };
+                        let Some(pred) = label.name().and_then(|name| cfg.block_index(name)) else { continue };
+                        if !domtree.is_reachable(pred) {
+                            continue;
+                        }
+                        let Some(def) = SsaKey::of(value).and_then(|key| defs.get(&key)) else { continue };
+                        if !def.site.dominates_edge(pred, b, &domtree) {
+                            self.report(Rule::SsaForm, VerificationError::InvalidSSA {
+                                value: value.name().unwrap_or_default().to_string(),
+                                reason: "Instruction does not dominate all uses".to_string(),
+                                definition: def.location.clone(),
+                                location: format!("{}, incoming from block %{}", location, block_label(pred)),
+                            });
+                        }
+                    }
+                    continue;
+                }
+
+                let result_key = inst.result().and_then(SsaKey::of);
+                for operand in inst.operands() {
+                    let Some(key) = SsaKey::of(operand) else { continue };
+                    if result_key.as_ref() == Some(&key) {
+                        self.report(Rule::SsaForm, VerificationError::InvalidSSA {
+                            value: operand.name().unwrap_or_default().to_string(),
+                            reason: "Only PHI nodes may reference their own value".to_string(),
+                            definition: location.clone(),
+                            location: location.clone(),
+                        });
+                        continue;
+                    }
+                    if !domtree.is_reachable(b) {
+                        continue;
+                    }
+                    let Some(def) = defs.get(&key) else { continue };
+                    if !def.site.dominates_use(b, i, &domtree) {
+                        self.report(Rule::SsaForm, VerificationError::InvalidSSA {
+                            value: operand.name().unwrap_or_default().to_string(),
+                            reason: "Instruction does not dominate all uses".to_string(),
+                            definition: def.location.clone(),
+                            location: location.clone(),
+                        });
+                    }
+                }
+            }
+        }
     }
 
-    /// Verify metadata attachments (placeholder - parser doesn't preserve metadata yet)
-    pub fn verify_metadata(&mut self, _module: &Module) {
-        // TODO: Once parser preserves metadata, validate:
-        // 1. Named metadata nodes are well-formed
-        // 2. Metadata references are valid
-        // 3. Debug info structure is correct
-        // 4. Metadata types are appropriate for their use
-        // 5. No circular references in metadata
+    /// Verify the metadata graph: references resolve, scope and inlinedAt
+    /// chains end, and each node is well-formed for its kind
+    pub fn verify_metadata(&mut self, module: &Module) {
+        let graph = MetadataGraph::new(module);
+
+        let mut roots = graph.numbered.clone();
+        for function in module.functions() {
+            roots.extend(function.subprogram());
+            for bb in function.basic_blocks() {
+                roots.extend(bb.instructions().iter().filter_map(|inst| inst.debug_loc().cloned()));
+            }
+        }
+
+        // Each node once, in definition order, including inline operands
+        let mut visited = HashSet::new();
+        let mut cyclic = HashSet::new();
+        let mut worklist: Vec<Metadata> = roots.into_iter().rev().collect();
+        while let Some(node) = worklist.pop() {
+            if let Some(number) = node.as_reference() {
+                if graph.resolve(&node).is_none() {
+                    self.report(Rule::MetadataReference, VerificationError::MetadataReference {
+                        reason: format!("use of undefined metadata '!{}'", number),
+                        location: "metadata".to_string(),
+                    });
+                }
+                continue;
+            }
+            if !visited.insert(node.node_id()) {
+                continue;
+            }
+
+            for key in ["scope", "inlinedAt"] {
+                if let Some(cycle) = graph.chain_cycle(&node, key) {
+                    if cycle.iter().all(|member| cyclic.insert((key, member.node_id()))) {
+                        let members: Vec<String> = cycle.iter().map(|member| graph.describe(member)).collect();
+                        self.report(Rule::MetadataCycle, VerificationError::InvalidMetadata {
+                            reason: format!("{} chain contains a cycle through {}", key, members.join(", ")),
+                            location: graph.describe(&node),
+                        });
+                    }
+                }
+            }
+            self.verify_debug_info_node(&graph, &node);
+
+            let mut children: Vec<Metadata> = node.operands().cloned().unwrap_or_default();
+            if let Some(fields) = node.fields() {
+                let mut keys: Vec<&String> = fields.keys().collect();
+                keys.sort();
+                children.extend(keys.into_iter().map(|key| fields[key].clone()));
+            }
+            worklist.extend(children.into_iter().rev());
+        }
     }
 
-    /// Verify debug info metadata structure
-    fn verify_debug_info(&mut self, _debug_info: &crate::metadata::DebugInfo, _location: &str) {
-        // TODO: Validate debug info structure:
-        // 1. Compile units have valid file references
-        // 2. Subprograms have valid scopes
-        // 3. Types have valid sizes and encodings
-        // 4. Locations have valid line/column numbers
+    /// Verify the debug info of each function: its `!dbg` attachment is a
+    /// subprogram of its own, and every `!dbg` location in the body is
+    /// scoped within that subprogram
+    fn verify_debug_info(&mut self, module: &Module) {
+        let graph = MetadataGraph::new(module);
+
+        let mut attached = std::collections::HashMap::new();
+        for function in module.functions() {
+            let fn_name = function.name();
+            let Some(subprogram) = function.subprogram().and_then(|sp| graph.resolve(&sp)) else {
+                continue;
+            };
+            if graph.kind(&subprogram).as_deref() != Some("DISubprogram") {
+                self.report(Rule::DebugInfoAttachment, VerificationError::InvalidDebugInfo {
+                    reason: format!("function !dbg attachment must be a subprogram, found {}", graph.describe(&subprogram)),
+                    location: format!("function {}", fn_name),
+                });
+                continue;
+            }
+            if let Some(other) = attached.insert(subprogram.node_id(), fn_name.clone()) {
+                self.report(Rule::DebugInfoAttachment, VerificationError::InvalidDebugInfo {
+                    reason: format!("{} attached to more than one function (also @{})", graph.describe(&subprogram), other),
+                    location: format!("function {}", fn_name),
+                });
+            }
+            if function.has_body() && !is_subprogram_definition(&subprogram) {
+                self.report(Rule::DebugInfoAttachment, VerificationError::InvalidDebugInfo {
+                    reason: format!("function definition has subprogram declaration {} as !dbg attachment", graph.describe(&subprogram)),
+                    location: format!("function {}", fn_name),
+                });
+            }
+
+            for bb in function.basic_blocks() {
+                let block_name = bb.name().unwrap_or_default();
+                for (index, inst) in bb.instructions().iter().enumerate() {
+                    let Some(loc) = inst.debug_loc().and_then(|loc| graph.resolve(loc)) else {
+                        continue;
+                    };
+                    let location = format!("function {} block %{} instruction {} ({:?})", fn_name, block_name, index, inst.opcode());
+                    if graph.kind(&loc).as_deref() != Some("DILocation") {
+                        self.report(Rule::DebugInfoAttachment, VerificationError::InvalidDebugInfo {
+                            reason: format!("!dbg attachment must be a DILocation, found {}", graph.describe(&loc)),
+                            location,
+                        });
+                        continue;
+                    }
+                    // Inlined locations belong to the function they were inlined into
+                    let outermost = graph.chain(&loc, "inlinedAt").pop().unwrap_or(loc);
+                    let owner = graph.chain(&outermost, "scope").into_iter()
+                        .find(|scope| graph.kind(scope).as_deref() == Some("DISubprogram"));
+                    match owner {
+                        Some(owner) if owner.node_id() == subprogram.node_id() => {}
+                        Some(owner) => self.report(Rule::DebugInfoAttachment, VerificationError::InvalidDebugInfo {
+                            reason: format!("!dbg attachment points at wrong subprogram {} for function (expected {})",
+                                graph.describe(&owner), graph.describe(&subprogram)),
+                            location,
+                        }),
+                        None => self.report(Rule::DebugInfoAttachment, VerificationError::InvalidDebugInfo {
+                            reason: format!("scope of {} is not within a subprogram", graph.describe(&outermost)),
+                            location,
+                        }),
+                    }
+                }
+            }
+        }
     }
 
-    /// Verify control flow
+    /// Verify control flow: branch targets exist, the entry block has no
+    /// predecessors, terminators end their blocks, and PHI nodes are grouped
+    /// at the top of their block with one entry per predecessor
     pub fn verify_control_flow(&mut self, function: &Function) {
-        // Enhanced CFG validation with reachability analysis
+        use crate::cfg::CFG;
+
         let basic_blocks = function.basic_blocks();
         if basic_blocks.is_empty() {
             return;
         }
+        let fn_name = function.name();
 
-        // Check entry block exists and has no predecessors
+        // Check entry block exists
         if let Some(entry) = function.entry_block() {
             if let Some(entry_name) = entry.name() {
                 // Entry block should be first in the list
-                if !basic_blocks.is_empty() {
-                    if let Some(first_name) = basic_blocks[0].name() {
-                        if first_name != entry_name {
-                            self.errors.push(VerificationError::InvalidCFG {
-                                reason: "entry block must be first block in function".to_string(),
-                                location: format!("function {}", function.name()),
-                            });
-                        }
+                if let Some(first_name) = basic_blocks[0].name() {
+                    if first_name != entry_name {
+                        self.report(Rule::ControlFlowGraph, VerificationError::InvalidCFG {
+                            reason: "entry block must be first block in function".to_string(),
+                            location: format!("function {}", fn_name),
+                        });
                     }
                 }
-
-                // TODO: Implement full reachability analysis
-                // This would require:
-                // 1. Build CFG from terminator instructions
-                // 2. Perform DFS/BFS from entry block
-                // 3. Mark all reachable blocks
-                // 4. Report unreachable blocks
-                //
-                // Current limitation: Parser doesn't preserve CFG edges
-                // so we can't traverse the graph
             }
         } else {
-            self.errors.push(VerificationError::EntryBlockMissing {
-                function: function.name(),
+            self.report(Rule::EntryBlock, VerificationError::EntryBlockMissing {
+                function: fn_name,
             });
+            return;
+        }
+
+        let cfg = CFG::from_function(function);
+
+        for (block, target) in cfg.missing_targets() {
+            self.report(Rule::ControlFlowGraph, VerificationError::InvalidCFG {
+                reason: format!("branch target %{} does not exist in the function", target),
+                location: format!("function {} block %{}", fn_name, cfg.block_name(*block)),
+            });
+        }
+
+        let entry_preds = cfg.predecessors(0);
+        if !entry_preds.is_empty() {
+            let preds: Vec<String> = entry_preds.iter().map(|&p| format!("%{}", cfg.block_name(p))).collect();
+            self.report(Rule::ControlFlowGraph, VerificationError::InvalidCFG {
+                reason: format!("entry block to function must not have predecessors, found {}", preds.join(", ")),
+                location: format!("function {} block %{}", fn_name, cfg.block_name(0)),
+            });
+        }
+
+        for (index, bb) in basic_blocks.iter().enumerate() {
+            let block_name = cfg.block_name(index);
+            let location = format!("function {} block %{}", fn_name, block_name);
+            let instructions = bb.instructions();
+
+            if let Some(position) = instructions.iter().position(|inst| inst.is_terminator()) {
+                if position + 1 != instructions.len() {
+                    self.report(Rule::ControlFlowGraph, VerificationError::InvalidCFG {
+                        reason: format!("terminator {:?} found in the middle of a basic block", instructions[position].opcode()),
+                        location: location.clone(),
+                    });
+                }
+            }
+
+            let phi_count = instructions.iter().take_while(|inst| inst.opcode() == Opcode::PHI).count();
+            if instructions[phi_count..].iter().any(|inst| inst.opcode() == Opcode::PHI) {
+                self.report(Rule::PhiPlacement, VerificationError::InvalidPhi {
+                    reason: "PHI nodes not grouped at top of basic block".to_string(),
+                    location: location.clone(),
+                });
+            }
+
+            if let Some(term) = instructions.last().filter(|inst| inst.opcode() == Opcode::Switch) {
+                self.verify_switch_cases(term, &location);
+            }
+
+            // Entries can only be matched against predecessors if every edge is known
+            if !cfg.is_complete() {
+                continue;
+            }
+            let preds: Vec<String> = cfg.predecessors(index).iter().map(|&p| cfg.block_name(p)).collect();
+            for phi in instructions.iter().filter(|inst| inst.opcode() == Opcode::PHI) {
+                let incoming: Vec<&str> = phi.operands().iter().skip(1).step_by(2).filter_map(|label| label.name()).collect();
+                for pred in &preds {
+                    if !incoming.contains(&pred.as_str()) {
+                        self.report(Rule::PhiPlacement, VerificationError::InvalidPhi {
+                            reason: format!("PHI node has no entry for predecessor %{}", pred),
+                            location: location.clone(),
+                        });
+                    }
+                }
+                for label in &incoming {
+                    if !preds.iter().any(|pred| pred == label) {
+                        self.report(Rule::PhiPlacement, VerificationError::InvalidPhi {
+                            reason: format!("PHI node has an entry for %{}, which is not a predecessor", label),
+                            location: location.clone(),
+                        });
+                    }
+                }
+            }
         }
 
         // Validate exception handling control flow
         self.verify_exception_handling_cfg(function);
     }
 
+    /// Verify that a switch has no duplicate case values
+    fn verify_switch_cases(&mut self, switch: &Instruction, location: &str) {
+        let mut seen = HashSet::new();
+        for case in switch.operands().iter().skip(2).step_by(2) {
+            if let Some(value) = case.as_const_int() {
+                if !seen.insert(value) {
+                    self.report(Rule::SwitchCases, VerificationError::InvalidCFG {
+                        reason: format!("duplicate integer {} as switch case", value),
+                        location: location.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
     /// Verify named module metadata (llvm.commandline, etc.)
     fn verify_named_metadata(&mut self, module: &Module) {
         // Verify llvm.commandline metadata structure
@@ -2628,24 +2226,24 @@ impl<'a> Verifier<'a> {
             for entry in &cmdline_entries {
                 if let Some(entry_ops) = entry.operands() {
                     if entry_ops.len() == 0 {
-                        self.errors.push(VerificationError::InvalidMetadata {
+                        self.report(Rule::NamedMetadata, VerificationError::InvalidMetadata {
                             reason: "incorrect number of operands in llvm.commandline metadata".to_string(),
                             location: "llvm.commandline".to_string(),
                         });
                     } else if entry_ops.len() > 1 {
-                        self.errors.push(VerificationError::InvalidMetadata {
+                        self.report(Rule::NamedMetadata, VerificationError::InvalidMetadata {
                             reason: "incorrect number of operands in llvm.commandline metadata".to_string(),
                             location: "llvm.commandline".to_string(),
                         });
                     } else if !entry_ops[0].is_string() {
-                        self.errors.push(VerificationError::InvalidMetadata {
+                        self.report(Rule::NamedMetadata, VerificationError::InvalidMetadata {
                             reason: "llvm.commandline metadata operand must be a string".to_string(),
                             location: "llvm.commandline".to_string(),
                         });
                     }
                 } else if !entry.is_string() {
                     // Not a tuple - must be a direct string
-                    self.errors.push(VerificationError::InvalidMetadata {
+                    self.report(Rule::NamedMetadata, VerificationError::InvalidMetadata {
                         reason: "llvm.commandline entry must be a metadata node with one string".to_string(),
                         location: "llvm.commandline".to_string(),
                     });
@@ -2658,19 +2256,19 @@ impl<'a> Verifier<'a> {
             for entry in &ident_entries {
                 if let Some(entry_ops) = entry.operands() {
                     if entry_ops.len() != 1 {
-                        self.errors.push(VerificationError::InvalidMetadata {
+                        self.report(Rule::NamedMetadata, VerificationError::InvalidMetadata {
                             reason: "incorrect number of operands in llvm.ident metadata".to_string(),
                             location: format!("{:?}", entry),
                         });
                     } else if !entry_ops[0].is_string() {
-                        self.errors.push(VerificationError::InvalidMetadata {
+                        self.report(Rule::NamedMetadata, VerificationError::InvalidMetadata {
                             reason: "invalid value for llvm.ident metadata entry operand(the operand should be a string)".to_string(),
                             location: format!("{:?}", entry_ops[0]),
                         });
                     }
                 } else if !entry.is_string() {
                     // Not a tuple - must be a direct string
-                    self.errors.push(VerificationError::InvalidMetadata {
+                    self.report(Rule::NamedMetadata, VerificationError::InvalidMetadata {
                         reason: "invalid value for llvm.ident metadata entry operand(the operand should be a string)".to_string(),
                         location: "llvm.ident".to_string(),
                     });
@@ -2681,19 +2279,22 @@ impl<'a> Verifier<'a> {
         // Verify llvm.dbg.cu contains only DICompileUnit nodes
         if let Some(dbg_cu_entries) = module.get_named_metadata("llvm.dbg.cu") {
             for entry in &dbg_cu_entries {
-                // Each entry should be a DICompileUnit
-                // Only validate if we can determine the type - skip reference nodes
-                if let Some(name) = entry.get_name() {
-                    if name != "DICompileUnit" {
-                        self.errors.push(VerificationError::InvalidDebugInfo {
-                            reason: format!("!llvm.dbg.cu must contain only DICompileUnit nodes, found {}", name),
-                            location: "llvm.dbg.cu".to_string(),
-                        });
-                    }
+                // Each entry should be a DICompileUnit; forward references name
+                // numbered nodes, and undefined ones are reported by verify_metadata
+                let entry = match entry.as_reference() {
+                    Some(number) => match module.get_metadata(number) {
+                        Some(node) => node,
+                        None => continue,
+                    },
+                    None => entry.clone(),
+                };
+                match entry.get_name() {
+                    Some("DICompileUnit") => {}
+                    name => self.report(Rule::DebugInfoNamedMetadata, VerificationError::InvalidDebugInfo {
+                        reason: format!("!llvm.dbg.cu must contain only DICompileUnit nodes, found {}", name.unwrap_or("a tuple")),
+                        location: "llvm.dbg.cu".to_string(),
+                    }),
                 }
-                // If get_name() returns None, it could be a reference or other node type
-                // Only reject if we can positively identify it's not a DICompileUnit
-                // For now, we'll be lenient and only check when we can identify the type
             }
         }
 
@@ -2703,7 +2304,7 @@ impl<'a> Verifier<'a> {
                                       "llvm.dbg.label", "llvm.dbg.assign"];
         for (name, _) in module.named_metadata() {
             if name.starts_with("llvm.dbg.") && !allowed_dbg_names.contains(&name.as_str()) {
-                self.errors.push(VerificationError::InvalidDebugInfo {
+                self.report(Rule::DebugInfoNamedMetadata, VerificationError::InvalidDebugInfo {
                     reason: format!("invalid llvm.dbg.* metadata name: {}", name),
                     location: name.clone(),
                 });
@@ -2714,40 +2315,81 @@ impl<'a> Verifier<'a> {
         // - llvm.module.flags (already done in verify_module_flags)
     }
 
-    /// Verify debug info metadata structures
-    fn verify_debug_info_metadata(&mut self, module: &Module) {
-        // Validate debug info metadata structures in the module
-        // Check named metadata like !llvm.dbg.cu and referenced debug info nodes
-
-        // Collect all metadata nodes from the module
-        let all_metadata = module.get_all_metadata();
-
-        for metadata in all_metadata {
-            self.verify_debug_info_node(&metadata);
+    /// Verify a single metadata node according to its kind
+    fn verify_debug_info_node(&mut self, graph: &MetadataGraph, metadata: &Metadata) {
+        let Some(name) = metadata.get_name() else {
+            return;
+        };
+        let location = graph.describe(metadata);
+
+        // Every file reference must name a DIFile
+        if let Some(file) = graph.field(metadata, "file") {
+            if graph.kind(&file).as_deref() != Some("DIFile") {
+                self.report(Rule::DebugInfoNode, VerificationError::InvalidDebugInfo {
+                    reason: format!("invalid file {}", graph.describe(&file)),
+                    location: location.clone(),
+                });
+            }
         }
-    }
 
-    fn verify_debug_info_node(&mut self, metadata: &crate::metadata::Metadata) {
-        // Check if this is a named metadata node (e.g., DISubrange, DIExpression)
-        if let Some(name) = metadata.get_name() {
-            match name {
-                "DISubrange" => self.verify_disubrange(metadata),
-                "DIGenericSubrange" => self.verify_digenericsubrange(metadata),
-                "DIExpression" => self.verify_diexpression(metadata),
-                "DICompositeType" => self.verify_dicompositetype(metadata),
-                _ => {
-                    // Other DI* nodes - validate operands recursively
-                    if let Some(operands) = metadata.operands() {
-                        for operand in operands {
-                            self.verify_debug_info_node(operand);
-                        }
+        let scope = graph.field(metadata, "scope");
+        let scope_kind = scope.as_ref().and_then(|scope| graph.kind(scope));
+        let local_scope = scope_kind.as_deref().is_some_and(is_local_scope);
+        match name {
+            "DISubrange" => self.verify_disubrange(metadata),
+            "DIGenericSubrange" => self.verify_digenericsubrange(metadata),
+            "DIExpression" => self.verify_diexpression(metadata, &location),
+            "DICompositeType" => self.verify_dicompositetype(metadata),
+            "DISubprogram" => {
+                let unit = graph.field(metadata, "unit");
+                match (&unit, is_subprogram_definition(metadata)) {
+                    (None, true) => self.report(Rule::DebugInfoNode, VerificationError::InvalidDebugInfo {
+                        reason: "subprogram definitions must have a compile unit".to_string(),
+                        location: location.clone(),
+                    }),
+                    (Some(_), false) => self.report(Rule::DebugInfoNode, VerificationError::InvalidDebugInfo {
+                        reason: "subprogram declarations must not have a compile unit".to_string(),
+                        location: location.clone(),
+                    }),
+                    _ => {}
+                }
+                if let Some(unit) = unit.filter(|unit| graph.kind(unit).as_deref() != Some("DICompileUnit")) {
+                    self.report(Rule::DebugInfoNode, VerificationError::InvalidDebugInfo {
+                        reason: format!("invalid unit type {}", graph.describe(&unit)),
+                        location: location.clone(),
+                    });
+                }
+            }
+            "DILocation" if !local_scope => self.report(Rule::DebugInfoNode, VerificationError::InvalidDebugInfo {
+                reason: "location requires a valid scope".to_string(),
+                location: location.clone(),
+            }),
+            "DILocation" => {
+                if let Some(inlined_at) = graph.field(metadata, "inlinedAt") {
+                    if graph.kind(&inlined_at).as_deref() != Some("DILocation") {
+                        self.report(Rule::DebugInfoNode, VerificationError::InvalidDebugInfo {
+                            reason: format!("inlined-at should be a location, found {}", graph.describe(&inlined_at)),
+                            location: location.clone(),
+                        });
                     }
                 }
             }
-        } else if let Some(operands) = metadata.as_tuple() {
-            // Tuple metadata - validate operands recursively
-            for operand in operands {
-                self.verify_debug_info_node(operand);
+            "DILexicalBlock" | "DILexicalBlockFile" | "DILocalVariable" | "DILabel" if !local_scope => {
+                self.report(Rule::DebugInfoNode, VerificationError::InvalidDebugInfo {
+                    reason: format!("{} requires a local scope", name),
+                    location: location.clone(),
+                });
+            }
+            _ => {}
+        }
+
+        // Any other scope reference must at least name a scope
+        if let (Some(scope), Some(kind)) = (&scope, &scope_kind) {
+            if !is_scope(kind) {
+                self.report(Rule::DebugInfoNode, VerificationError::InvalidDebugInfo {
+                    reason: format!("invalid scope {}", graph.describe(scope)),
+                    location,
+                });
             }
         }
     }
@@ -2758,7 +2400,7 @@ impl<'a> Verifier<'a> {
         let has_upper_bound = metadata.has_field("upperBound");
 
         if has_count && has_upper_bound {
-            self.errors.push(VerificationError::InvalidDebugInfo {
+            self.report(Rule::DebugInfoSubrange, VerificationError::InvalidDebugInfo {
                 reason: "Subrange can have any one of count or upperBound".to_string(),
                 location: "DISubrange".to_string(),
             });
@@ -2776,7 +2418,7 @@ impl<'a> Verifier<'a> {
                 });
 
             if !is_valid_count {
-                self.errors.push(VerificationError::InvalidDebugInfo {
+                self.report(Rule::DebugInfoSubrange, VerificationError::InvalidDebugInfo {
                     reason: "Count must be signed constant or DIVariable or DIExpression".to_string(),
                     location: "DISubrange".to_string(),
                 });
@@ -2787,7 +2429,7 @@ impl<'a> Verifier<'a> {
         if let Some(lower_bound_metadata) = metadata.get_field("lowerBound") {
             // lowerBound must be a signed constant (integer metadata)
             if !lower_bound_metadata.is_int() {
-                self.errors.push(VerificationError::InvalidDebugInfo {
+                self.report(Rule::DebugInfoSubrange, VerificationError::InvalidDebugInfo {
                     reason: "LowerBound must be signed constant".to_string(),
                     location: "DISubrange".to_string(),
                 });
@@ -2806,7 +2448,7 @@ impl<'a> Verifier<'a> {
                 });
 
             if !is_valid_upper_bound {
-                self.errors.push(VerificationError::InvalidDebugInfo {
+                self.report(Rule::DebugInfoSubrange, VerificationError::InvalidDebugInfo {
                     reason: "UpperBound must be signed constant or DIVariable or DIExpression".to_string(),
                     location: "DISubrange".to_string(),
                 });
@@ -2817,7 +2459,7 @@ impl<'a> Verifier<'a> {
         if let Some(stride_metadata) = metadata.get_field("stride") {
             // stride must be a signed constant (integer metadata)
             if !stride_metadata.is_int() {
-                self.errors.push(VerificationError::InvalidDebugInfo {
+                self.report(Rule::DebugInfoSubrange, VerificationError::InvalidDebugInfo {
                     reason: "Stride must be signed constant".to_string(),
                     location: "DISubrange".to_string(),
                 });
@@ -2830,7 +2472,7 @@ impl<'a> Verifier<'a> {
         let has_stride = metadata.has_field("stride");
 
         if !has_stride {
-            self.errors.push(VerificationError::InvalidDebugInfo {
+            self.report(Rule::DebugInfoSubrange, VerificationError::InvalidDebugInfo {
                 reason: "GenericSubrange must contain stride".to_string(),
                 location: "DIGenericSubrange".to_string(),
             });
@@ -2841,17 +2483,63 @@ impl<'a> Verifier<'a> {
         let has_upper_bound = metadata.has_field("upperBound");
 
         if has_count && has_upper_bound {
-            self.errors.push(VerificationError::InvalidDebugInfo {
+            self.report(Rule::DebugInfoSubrange, VerificationError::InvalidDebugInfo {
                 reason: "GenericSubrange can have any one of count or upperBound".to_string(),
                 location: "DIGenericSubrange".to_string(),
             });
         }
     }
 
-    fn verify_diexpression(&mut self, metadata: &crate::metadata::Metadata) {
-        // DIExpression contains DWARF operations
-        // Note: DW_OP_swap and other DWARF operations are valid
-        // Removed overly strict validation that rejected valid expressions
+    /// Verify that a DIExpression is a sequence of known DWARF operations,
+    /// each followed by its arguments. Operations are spelled by name when
+    /// parsed from text and by encoding when read from bitcode.
+    fn verify_diexpression(&mut self, metadata: &Metadata, location: &str) {
+        use crate::metadata::DwarfOpInfo;
+
+        let Some(elements) = metadata.operands() else {
+            return;
+        };
+        let invalid = |reason: String| VerificationError::InvalidDebugInfo {
+            reason: format!("invalid expression: {}", reason),
+            location: location.to_string(),
+        };
+
+        let mut operations = Vec::new();
+        let mut index = 0;
+        while index < elements.len() {
+            let element = &elements[index];
+            let op = match (element.as_string(), element.as_int()) {
+                (Some(name), _) => DwarfOpInfo::from_name(name).ok_or_else(|| format!("unknown operation {}", name)),
+                (_, Some(code)) => DwarfOpInfo::from_code(code as u64).ok_or_else(|| format!("unknown operation 0x{:x}", code)),
+                _ => Err(format!("element {} is not an operation", index)),
+            };
+            let op = match op {
+                Ok(op) => op,
+                Err(reason) => {
+                    self.report(Rule::DebugInfoExpression, invalid(reason));
+                    return;
+                }
+            };
+            let args = &elements[index + 1..elements.len().min(index + 1 + op.args)];
+            if args.len() != op.args || args.iter().any(|arg| !arg.is_int()) {
+                self.report(Rule::DebugInfoExpression, invalid(format!("{} expects {} integer argument(s)", op.name, op.args)));
+                return;
+            }
+            operations.push(op.name);
+            index += 1 + op.args;
+        }
+
+        for (position, &op) in operations.iter().enumerate() {
+            let next = operations.get(position + 1).copied();
+            let reason = match op {
+                "DW_OP_LLVM_fragment" if next.is_some() => "DW_OP_LLVM_fragment must be the last operation",
+                "DW_OP_stack_value" if next.is_some_and(|next| next != "DW_OP_LLVM_fragment") =>
+                    "DW_OP_stack_value may only be followed by DW_OP_LLVM_fragment",
+                "DW_OP_LLVM_entry_value" if position != 0 => "DW_OP_LLVM_entry_value must be the first operation",
+                _ => continue,
+            };
+            self.report(Rule::DebugInfoExpression, invalid(reason.to_string()));
+        }
     }
 
     fn verify_dicompositetype(&mut self, metadata: &crate::metadata::Metadata) {
@@ -2874,7 +2562,7 @@ impl<'a> Verifier<'a> {
             if !is_array_type {
                 for field_name in &array_only_fields {
                     if metadata.has_field(field_name) {
-                        self.errors.push(VerificationError::InvalidDebugInfo {
+                        self.report(Rule::DebugInfoCompositeType, VerificationError::InvalidDebugInfo {
                             reason: format!("{} can only appear in array type", field_name),
                             location: "DICompositeType".to_string(),
                         });
@@ -2929,7 +2617,7 @@ impl<'a> Verifier<'a> {
             CallingConvention::AMDGPU_Kernel | CallingConvention::SPIR_Kernel |
             CallingConvention::AMDGPU_CS_Chain | CallingConvention::AMDGPU_CS_Chain_Preserve => {
                 if !ret_type.is_void() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::CallingConvention, VerificationError::InvalidInstruction {
                         reason: "Calling convention requires void return type".to_string(),
                         location: format!("function {}", fn_name),
                     });
@@ -2964,7 +2652,7 @@ impl<'a> Verifier<'a> {
                         };
 
                         if !is_intrinsic {
-                            self.errors.push(VerificationError::InvalidInstruction {
+                            self.report(Rule::CallingConvention, VerificationError::InvalidInstruction {
                                 reason: "calling convention does not permit calls".to_string(),
                                 location: format!("function {}", fn_name),
                             });
@@ -2983,13 +2671,13 @@ impl<'a> Verifier<'a> {
                 CallingConvention::AMDGPU_VS | CallingConvention::AMDGPU_GS |
                 CallingConvention::AMDGPU_PS | CallingConvention::AMDGPU_CS |
                 CallingConvention::AMDGPU_CS_Chain | CallingConvention::AMDGPU_CS_Chain_Preserve => {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::CallingConvention, VerificationError::InvalidInstruction {
                         reason: "Calling convention does not support varargs or perfect forwarding!".to_string(),
                         location: format!("function {}", fn_name),
                     });
                 },
                 CallingConvention::AMDGPU_GFX_Whole_Wave => {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::CallingConvention, VerificationError::InvalidInstruction {
                         reason: "Calling convention does not support varargs".to_string(),
                         location: format!("function {}", fn_name),
                     });
@@ -3002,14 +2690,14 @@ impl<'a> Verifier<'a> {
         if cc == CallingConvention::AMDGPU_GFX_Whole_Wave {
             let params = function.arguments();
             if params.is_empty() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::CallingConvention, VerificationError::InvalidInstruction {
                     reason: "Calling convention requires first argument to be i1".to_string(),
                     location: format!("function {}", fn_name),
                 });
             } else {
                 let first_param_type = params[0].get_type();
                 if !first_param_type.is_integer() || first_param_type.int_width() != Some(1) {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::CallingConvention, VerificationError::InvalidInstruction {
                         reason: "Calling convention requires first argument to be i1".to_string(),
                         location: format!("function {}", fn_name),
                     });
@@ -3026,7 +2714,7 @@ impl<'a> Verifier<'a> {
             let attrs = function.attributes();
             for param_attrs in &attrs.parameter_attributes {
                 if param_attrs.sret.is_some() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::CallingConvention, VerificationError::InvalidInstruction {
                         reason: "Calling convention does not allow sret".to_string(),
                         location: format!("function {}", fn_name),
                     });
@@ -3044,7 +2732,7 @@ impl<'a> Verifier<'a> {
             let attrs = function.attributes();
             for param_attrs in &attrs.parameter_attributes {
                 if param_attrs.byval.is_some() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::CallingConvention, VerificationError::InvalidInstruction {
                         reason: "Calling convention disallows byval".to_string(),
                         location: format!("function {}", fn_name),
                     });
@@ -3062,7 +2750,7 @@ impl<'a> Verifier<'a> {
                 if !params.is_empty() {
                     let first_param_type = params[0].get_type();
                     if first_param_type.is_pointer() && attrs.parameter_attributes[0].byval.is_none() {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::CallingConvention, VerificationError::InvalidInstruction {
                             reason: "Calling convention parameter requires byval".to_string(),
                             location: format!("function {}", fn_name),
                         });
@@ -3101,7 +2789,7 @@ impl<'a> Verifier<'a> {
         // Check align attribute on return type - must be pointer type
         if let Some(align_val) = ret_attrs.align {
             if !return_type.is_pointer() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                     reason: format!("Attribute 'align {}' applied to incompatible type!", align_val),
                     location: format!("@{}", fn_name),
                 });
@@ -3111,7 +2799,7 @@ impl<'a> Verifier<'a> {
         // Check signext on return type - must be integer type
         if ret_attrs.signext {
             if !return_type.is_integer() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                     reason: format!("Attribute 'signext' applied to incompatible type!"),
                     location: format!("@{}", fn_name),
                 });
@@ -3121,7 +2809,7 @@ impl<'a> Verifier<'a> {
         // Check zeroext on return type - must be integer type
         if ret_attrs.zeroext {
             if !return_type.is_integer() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                     reason: format!("Attribute 'zeroext' applied to incompatible type!"),
                     location: format!("@{}", fn_name),
                 });
@@ -3131,7 +2819,7 @@ impl<'a> Verifier<'a> {
         // Check noundef on return type - cannot be applied to void
         if ret_attrs.noundef {
             if return_type.is_void() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                     reason: format!("Attribute 'noundef' applied to incompatible type!"),
                     location: format!("@{}", fn_name),
                 });
@@ -3140,7 +2828,7 @@ impl<'a> Verifier<'a> {
 
         // Check swifterror on return type - cannot be applied to return values
         if ret_attrs.swifterror {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                 reason: "this attribute does not apply to return values".to_string(),
                 location: format!("@{}", fn_name),
             });
@@ -3186,7 +2874,7 @@ impl<'a> Verifier<'a> {
             // Check align attribute - must be pointer type
             if let Some(align_val) = param_attrs.align {
                 if !param_type.is_pointer() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: format!("Attribute 'align {}' applied to incompatible type!", align_val),
                         location: format!("@{}", fn_name),
                     });
@@ -3195,7 +2883,7 @@ impl<'a> Verifier<'a> {
                 // Check alignment limit - max 2^32 bytes
                 const MAX_ALIGNMENT: u64 = 1u64 << 32; // 4294967296
                 if (align_val as u64) > MAX_ALIGNMENT {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: "huge alignments are not supported yet".to_string(),
                         location: format!("@{}", fn_name),
                     });
@@ -3206,13 +2894,13 @@ impl<'a> Verifier<'a> {
             if param_attrs.signext {
                 if !param_type.is_integer() && !param_type.is_pointer() {
                     // signext on pointer is definitely wrong, on non-integer is wrong
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: format!("Attribute 'signext' applied to incompatible type!"),
                         location: format!("@{}", fn_name),
                     });
                 } else if param_type.is_pointer() {
                     // Specifically catch signext on pointer which is one of our test cases
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: format!("Attribute 'signext' applied to incompatible type!"),
                         location: format!("@{}", fn_name),
                     });
@@ -3222,13 +2910,13 @@ impl<'a> Verifier<'a> {
             // Check zeroext attribute - must be integer type
             if param_attrs.zeroext {
                 if !param_type.is_integer() && !param_type.is_pointer() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: format!("Attribute 'zeroext' applied to incompatible type!"),
                         location: format!("@{}", fn_name),
                     });
                 } else if param_type.is_pointer() {
                     // Specifically catch zeroext on pointer
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: format!("Attribute 'zeroext' applied to incompatible type!"),
                         location: format!("@{}", fn_name),
                     });
@@ -3237,7 +2925,7 @@ impl<'a> Verifier<'a> {
 
             // Check sret attribute with varargs
             if is_varargs && param_attrs.sret.is_some() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                     reason: format!("Attribute 'sret' does not apply to vararg call!"),
                     location: format!("function {} parameter {}", fn_name, idx),
                 });
@@ -3246,7 +2934,7 @@ impl<'a> Verifier<'a> {
             // Check sret attribute - must be pointer type
             if param_attrs.sret.is_some() {
                 if !param_type.is_pointer() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: format!("Attribute 'sret(i32)' applied to incompatible type!"),
                         location: format!("@{}", fn_name),
                     });
@@ -3255,7 +2943,7 @@ impl<'a> Verifier<'a> {
                 // Check calling convention restrictions on sret
                 let cc = function.calling_convention();
                 if matches!(cc, CallingConvention::AMDGPU_Kernel) {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: "Calling convention does not allow sret".to_string(),
                         location: format!("function {}", fn_name),
                     });
@@ -3265,7 +2953,7 @@ impl<'a> Verifier<'a> {
             // Check byval attribute - must be pointer type
             if let Some(byval_ty) = &param_attrs.byval {
                 if !param_type.is_pointer() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: format!("Attribute 'byval(i32)' applied to incompatible type!"),
                         location: format!("@{}", fn_name),
                     });
@@ -3274,7 +2962,7 @@ impl<'a> Verifier<'a> {
                 // Check calling convention restrictions on byval
                 let cc = function.calling_convention();
                 if matches!(cc, CallingConvention::AMDGPU_Kernel) {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: "Calling convention disallows byval".to_string(),
                         location: format!("function {}", fn_name),
                     });
@@ -3285,7 +2973,7 @@ impl<'a> Verifier<'a> {
                 if let Some(size) = byval_ty.size_in_bytes() {
                     const MAX_BYVAL_SIZE: u64 = 2147483648; // 2^31 bytes
                     if size >= MAX_BYVAL_SIZE {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                             reason: "huge 'byval' arguments are unsupported".to_string(),
                             location: format!("@{}", fn_name),
                         });
@@ -3297,7 +2985,7 @@ impl<'a> Verifier<'a> {
             if let Some(inalloca_ty) = &param_attrs.inalloca {
                 // Must be pointer type
                 if !param_type.is_pointer() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: format!("Attribute 'inalloca(i8)' applied to incompatible type!"),
                         location: format!("@{}", fn_name),
                     });
@@ -3305,7 +2993,7 @@ impl<'a> Verifier<'a> {
 
                 // inalloca type must be sized
                 if !inalloca_ty.is_sized() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: "Attribute 'inalloca' does not support unsized types!".to_string(),
                         location: format!("@{}", fn_name),
                     });
@@ -3314,7 +3002,7 @@ impl<'a> Verifier<'a> {
                 // Check if this is NOT the last parameter
                 // For varargs functions, inalloca must be on the last fixed parameter
                 if idx + 1 < param_types.len() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: format!("inalloca isn't on the last argument!"),
                         location: format!("function {} parameter {}", fn_name, idx),
                     });
@@ -3324,7 +3012,7 @@ impl<'a> Verifier<'a> {
             // Check swifterror attribute - must be pointer type
             if param_attrs.swifterror {
                 if !param_type.is_pointer() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: format!("Attribute 'swifterror' applied to incompatible type!"),
                         location: format!("@{}", fn_name),
                     });
@@ -3334,7 +3022,7 @@ impl<'a> Verifier<'a> {
             // Check noalias attribute - must be pointer type
             if param_attrs.noalias {
                 if !param_type.is_pointer() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: format!("Attribute 'noalias' applied to incompatible type!"),
                         location: format!("@{}", fn_name),
                     });
@@ -3344,7 +3032,7 @@ impl<'a> Verifier<'a> {
             // Check nest attribute - must be pointer type
             if param_attrs.nest {
                 if !param_type.is_pointer() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: format!("Attribute 'nest' applied to incompatible type!"),
                         location: format!("@{}", fn_name),
                     });
@@ -3354,7 +3042,7 @@ impl<'a> Verifier<'a> {
             // Check dereferenceable attribute - must be pointer type
             if param_attrs.dereferenceable.is_some() {
                 if !param_type.is_pointer() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: format!("Attribute 'dereferenceable' applied to incompatible type!"),
                         location: format!("@{}", fn_name),
                     });
@@ -3364,7 +3052,7 @@ impl<'a> Verifier<'a> {
             // Check dead_on_return attribute - must be pointer type
             if param_attrs.dead_on_return {
                 if !param_type.is_pointer() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: format!("Attribute 'dead_on_return' applied to incompatible type!"),
                         location: format!("@{}", fn_name),
                     });
@@ -3374,7 +3062,7 @@ impl<'a> Verifier<'a> {
             // Check dead_on_unwind attribute - must be pointer type
             if param_attrs.dead_on_unwind {
                 if !param_type.is_pointer() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: format!("Attribute 'dead_on_unwind' applied to incompatible type!"),
                         location: format!("@{}", fn_name),
                     });
@@ -3384,7 +3072,7 @@ impl<'a> Verifier<'a> {
             // Check writable attribute - must be pointer type
             if param_attrs.writable {
                 if !param_type.is_pointer() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: "Attribute 'writable' applied to incompatible type!".to_string(),
                         location: format!("ptr @{}", fn_name),
                     });
@@ -3392,13 +3080,13 @@ impl<'a> Verifier<'a> {
 
                 // writable is incompatible with readnone and readonly
                 if param_attrs.readnone {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: "Attributes writable and readnone are incompatible!".to_string(),
                         location: format!("ptr @{}", fn_name),
                     });
                 }
                 if param_attrs.readonly {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: "Attributes writable and readonly are incompatible!".to_string(),
                         location: format!("ptr @{}", fn_name),
                     });
@@ -3407,7 +3095,7 @@ impl<'a> Verifier<'a> {
                 // writable requires argmem:write in memory attribute
                 if let Some(ref memory_attr) = param_attrs.memory {
                     if !memory_attr.contains("argmem") || !memory_attr.contains("write") {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                             reason: "Attribute writable and memory without argmem: write are incompatible!".to_string(),
                             location: format!("ptr @{}", fn_name),
                         });
@@ -3419,7 +3107,7 @@ impl<'a> Verifier<'a> {
             if let Some(byref_ty) = &param_attrs.byref {
                 // Must be pointer type
                 if !param_type.is_pointer() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: format!("Attribute 'byref(i32)' applied to incompatible type!"),
                         location: format!("@{}", fn_name),
                     });
@@ -3435,7 +3123,7 @@ impl<'a> Verifier<'a> {
                     || byref_ty.is_x86_amx();
 
                 if is_invalid_for_byref {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: "Attribute 'byref' does not support this type!".to_string(),
                         location: format!("@{}", fn_name),
                     });
@@ -3443,31 +3131,31 @@ impl<'a> Verifier<'a> {
 
                 // Check for incompatible attribute combinations with byref
                 if param_attrs.byval.is_some() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: "Attributes 'byval', 'inalloca', 'preallocated', 'inreg', 'nest', 'byref', and 'sret' are incompatible!".to_string(),
                         location: format!("@{}", fn_name),
                     });
                 }
                 if param_attrs.inalloca.is_some() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: "Attributes 'byval', 'inalloca', 'preallocated', 'inreg', 'nest', 'byref', and 'sret' are incompatible!".to_string(),
                         location: format!("@{}", fn_name),
                     });
                 }
                 if param_attrs.sret.is_some() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: "Attributes 'byval', 'inalloca', 'preallocated', 'inreg', 'nest', 'byref', and 'sret' are incompatible!".to_string(),
                         location: format!("@{}", fn_name),
                     });
                 }
                 if param_attrs.inreg {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: "Attributes 'byval', 'inalloca', 'preallocated', 'inreg', 'nest', 'byref', and 'sret' are incompatible!".to_string(),
                         location: format!("@{}", fn_name),
                     });
                 }
                 if param_attrs.nest {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: "Attributes 'byval', 'inalloca', 'preallocated', 'inreg', 'nest', 'byref', and 'sret' are incompatible!".to_string(),
                         location: format!("@{}", fn_name),
                     });
@@ -3478,25 +3166,25 @@ impl<'a> Verifier<'a> {
             // inalloca is incompatible with: byval, inreg, sret, nest
             if param_attrs.inalloca.is_some() {
                 if param_attrs.byval.is_some() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: "Attributes 'byval', 'inalloca', 'preallocated', 'inreg', 'nest', 'byref', and 'sret' are incompatible!".to_string(),
                         location: format!("@{}", fn_name),
                     });
                 }
                 if param_attrs.inreg {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: "Attributes 'byval', 'inalloca', 'preallocated', 'inreg', 'nest', 'byref', and 'sret' are incompatible!".to_string(),
                         location: format!("@{}", fn_name),
                     });
                 }
                 if param_attrs.sret.is_some() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: "Attributes 'byval', 'inalloca', 'preallocated', 'inreg', 'nest', 'byref', and 'sret' are incompatible!".to_string(),
                         location: format!("@{}", fn_name),
                     });
                 }
                 if param_attrs.nest {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: "Attributes 'byval', 'inalloca', 'preallocated', 'inreg', 'nest', 'byref', and 'sret' are incompatible!".to_string(),
                         location: format!("@{}", fn_name),
                     });
@@ -3509,7 +3197,7 @@ impl<'a> Verifier<'a> {
 
                 // immarg only applies to intrinsics
                 if !is_intrinsic {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: "immarg attribute only applies to intrinsics".to_string(),
                         location: format!("ptr @{}", fn_name),
                     });
@@ -3517,7 +3205,7 @@ impl<'a> Verifier<'a> {
 
                 // immarg cannot be on function definitions (only declarations of intrinsics)
                 if is_intrinsic && function.has_body() {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                         reason: "immarg attribute only applies to intrinsics".to_string(),
                         location: format!("ptr @{}", fn_name),
                     });
@@ -3527,7 +3215,7 @@ impl<'a> Verifier<'a> {
 
         // Check for multiple sret parameters
         if sret_count > 1 {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                 reason: "Cannot have multiple 'sret' parameters!".to_string(),
                 location: format!("@{}", fn_name),
             });
@@ -3536,7 +3224,7 @@ impl<'a> Verifier<'a> {
         // Check sret position - must be on first or second parameter
         if let Some(idx) = sret_idx {
             if idx > 1 {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                     reason: "Attribute 'sret' is not on first or second parameter!".to_string(),
                     location: format!("@{}", fn_name),
                 });
@@ -3545,7 +3233,7 @@ impl<'a> Verifier<'a> {
 
         // Check for multiple swifterror parameters
         if swifterror_count > 1 {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                 reason: "Cannot have multiple 'swifterror' parameters!".to_string(),
                 location: format!("@{}", fn_name),
             });
@@ -3553,7 +3241,7 @@ impl<'a> Verifier<'a> {
 
         // Check for multiple swiftself parameters
         if swiftself_count > 1 {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                 reason: "Cannot have multiple 'swiftself' parameters!".to_string(),
                 location: format!("@{}", fn_name),
             });
@@ -3561,7 +3249,7 @@ impl<'a> Verifier<'a> {
 
         // Check for multiple swiftasync parameters
         if swiftasync_count > 1 {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::ParameterAttributes, VerificationError::InvalidInstruction {
                 reason: "Cannot have multiple 'swiftasync' parameters!".to_string(),
                 location: format!("@{}", fn_name),
             });
@@ -3592,7 +3280,7 @@ impl<'a> Verifier<'a> {
                                        loaded_type.is_vector();
 
                         if !is_valid {
-                            self.errors.push(VerificationError::InvalidInstruction {
+                            self.report(Rule::Atomic, VerificationError::InvalidInstruction {
                                 reason: "atomic load operand must have integer, pointer, floating point, or vector type!".to_string(),
                                 location: location.to_string(),
                             });
@@ -3613,7 +3301,7 @@ impl<'a> Verifier<'a> {
                                    stored_type.is_vector();
 
                     if !is_valid {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::Atomic, VerificationError::InvalidInstruction {
                             reason: "atomic store operand must have integer, pointer, floating point, or vector type!".to_string(),
                             location: location.to_string(),
                         });
@@ -3628,7 +3316,7 @@ impl<'a> Verifier<'a> {
                     let value_type = operands[1].get_type();
 
                     if value_type.is_struct() || value_type.is_array() {
-                        self.errors.push(VerificationError::InvalidInstruction {
+                        self.report(Rule::Atomic, VerificationError::InvalidInstruction {
                             reason: format!("atomic {:?} operand must have integer, pointer, floating point, or vector type!", opcode),
                             location: location.to_string(),
                         });
@@ -3641,1149 +3329,982 @@ impl<'a> Verifier<'a> {
         }
     }
 
-    /// Verify type constraints for instructions
-    fn verify_instruction_types(&mut self, _inst: &Instruction, _location: &str) {
-        // Type constraint validation would require:
-        // 1. Better type system support for target extension types
-        // 2. Opaque struct detection
-        // Placeholder for future implementation
+    /// Verify the operand and result types of an instruction against the
+    /// rules for its opcode
+    fn verify_instruction_types(&mut self, inst: &Instruction, location: &str) {
+        match inst.opcode() {
+            _ if inst.is_cast() => self.verify_cast_types(inst, location),
+            Opcode::Add | Opcode::Sub | Opcode::Mul | Opcode::UDiv | Opcode::SDiv |
+            Opcode::URem | Opcode::SRem | Opcode::And | Opcode::Or | Opcode::Xor |
+            Opcode::Shl | Opcode::LShr | Opcode::AShr |
+            Opcode::FAdd | Opcode::FSub | Opcode::FMul | Opcode::FDiv | Opcode::FRem |
+            Opcode::FNeg => self.verify_arithmetic_types(inst, location),
+            Opcode::ICmp | Opcode::FCmp => self.verify_comparison_types(inst, location),
+            Opcode::Select => self.verify_select_types(inst, location),
+            Opcode::ExtractElement | Opcode::InsertElement => self.verify_vector_element_types(inst, location),
+            Opcode::ShuffleVector => self.verify_shufflevector_types(inst, location),
+            Opcode::ExtractValue | Opcode::InsertValue => self.verify_aggregate_op_types(inst, location),
+            Opcode::GetElementPtr => self.verify_gep_types(inst, location),
+            Opcode::AtomicRMW => self.verify_atomicrmw_types(inst, location),
+            Opcode::AtomicCmpXchg => self.verify_cmpxchg_types(inst, location),
+            Opcode::Freeze => {
+                if let (Some(operand), Some(result)) = (inst.operands().first(), inst.result()) {
+                    self.verify_result_type(result.get_type(), operand.get_type(), location);
+                }
+            }
+            _ => {}
+        }
     }
 
-    /// Verify alignment constraints for instructions
-    fn verify_instruction_alignment(&mut self, inst: &Instruction, location: &str) {
-        // Maximum alignment in LLVM is 2^32 bytes
-        const MAX_ALIGNMENT: u64 = 1u64 << 32; // 4294967296
-
-        if let Some(alignment) = inst.alignment() {
-            if alignment > MAX_ALIGNMENT {
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: format!("alignment is larger than the maximum supported by LLVM (2^32)"),
-                    location: location.to_string(),
-                });
-            }
-
-            // Also check that alignment is a power of 2
-            if alignment > 0 && !alignment.is_power_of_two() {
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: format!("alignment must be a power of 2"),
-                    location: location.to_string(),
-                });
-            }
+    /// Report a result whose type is not the one the operands determine
+    fn verify_result_type(&mut self, result_type: &Type, expected: &Type, location: &str) {
+        if result_type != expected {
+            self.report(Rule::OperandTypes, VerificationError::TypeMismatch {
+                expected: format!("{:?}", expected),
+                found: format!("{:?}", result_type),
+                location: format!("{} result", location),
+            });
         }
     }
 
-    /// Verify metadata attachments on instructions
-    fn verify_instruction_metadata(&mut self, inst: &Instruction, location: &str) {
-        for md_name in inst.metadata_attachments() {
-            match md_name.as_str() {
-                "align" => {
-                    // align metadata only applies to load instructions
-                    if inst.opcode() != Opcode::Load {
-                        self.errors.push(VerificationError::InvalidMetadata {
-                            reason: "align applies only to load instructions".to_string(),
-                            location: location.to_string(),
-                        });
-                    }
-                    // Additional check: align only applies to pointer types
-                    // This would require checking the loaded type
-                }
-                "llvm.access.group" | "parallel_loop_access" => {
-                    // Access group metadata must be used on memory operations
-                    match inst.opcode() {
-                        Opcode::Load | Opcode::Store | Opcode::Call | Opcode::Invoke => {
-                            // Valid usage
-                        }
-                        _ => {
-                            self.errors.push(VerificationError::InvalidMetadata {
-                                reason: format!("{} metadata can only be used on memory operations", md_name),
-                                location: location.to_string(),
-                            });
-                        }
-                    }
-                }
-                "nontemporal" => {
-                    // nontemporal metadata only on load/store
-                    match inst.opcode() {
-                        Opcode::Load | Opcode::Store => { /* Valid */ }
-                        _ => {
-                            self.errors.push(VerificationError::InvalidMetadata {
-                                reason: "nontemporal metadata can only be used on load/store".to_string(),
-                                location: location.to_string(),
-                            });
-                        }
-                    }
-                }
-                "invariant.load" => {
-                    // invariant.load only on load instructions
-                    if inst.opcode() != Opcode::Load {
-                        self.errors.push(VerificationError::InvalidMetadata {
-                            reason: "invariant.load metadata can only be used on load instructions".to_string(),
-                            location: location.to_string(),
-                        });
-                    }
-                }
-                "nonnull" => {
-                    // nonnull metadata only on load instructions
-                    if inst.opcode() != Opcode::Load {
-                        self.errors.push(VerificationError::InvalidMetadata {
-                            reason: "nonnull metadata can only be used on load instructions".to_string(),
-                            location: location.to_string(),
-                        });
-                    }
-                }
-                "range" => {
-                    // range metadata only on load/call/invoke
-                    match inst.opcode() {
-                        Opcode::Load | Opcode::Call | Opcode::Invoke => { /* Valid */ }
-                        _ => {
-                            self.errors.push(VerificationError::InvalidMetadata {
-                                reason: "range metadata can only be used on load/call/invoke".to_string(),
-                                location: location.to_string(),
-                            });
-                        }
-                    }
-                }
-                "noalias" | "alias.scope" => {
-                    // Alias metadata on memory operations
-                    match inst.opcode() {
-                        Opcode::Load | Opcode::Store | Opcode::Call | Opcode::Invoke => { /* Valid */ }
-                        _ => {
-                            self.errors.push(VerificationError::InvalidMetadata {
-                                reason: format!("{} metadata can only be used on memory operations", md_name),
-                                location: location.to_string(),
-                            });
-                        }
-                    }
-                }
-                "tbaa" | "tbaa.struct" => {
-                    // TBAA metadata on memory operations and VAArg
-                    match inst.opcode() {
-                        Opcode::Load | Opcode::Store | Opcode::Call | Opcode::Invoke |
-                        Opcode::AtomicCmpXchg | Opcode::AtomicRMW | Opcode::VAArg => { /* Valid */ }
-                        _ => {
-                            self.errors.push(VerificationError::InvalidMetadata {
-                                reason: "tbaa metadata can only be used on memory operations".to_string(),
-                                location: location.to_string(),
-                            });
-                        }
-                    }
-                }
-                // Debug metadata is allowed on any instruction
-                "dbg" | "DILocation" | "DILocalVariable" | "DIExpression" => {
-                    // Always valid
-                }
-                // Profile and branch metadata
-                "prof" | "unpredictable" => {
-                    // These can appear on branches, calls, etc.
-                    // Generally valid on most instructions
-                }
-                _ => {
-                    // Unknown metadata - don't error, as there may be custom metadata
-                }
-            }
-        }
+    fn report_cast(&mut self, from: &Type, to: &Type, reason: String, location: &str) {
+        self.report(Rule::Cast, VerificationError::InvalidCast {
+            from: format!("{:?}", from),
+            to: format!("{:?}", to),
+            reason,
+            location: location.to_string(),
+        });
     }
 
-    /// Verify that GEP doesn't try to index through a pointer within an aggregate
-    /// This is invalid: getelementptr {i32, ptr}, ptr %X, i32 0, i32 1, i32 0
-    /// After getting to field 1 (a ptr), we cannot index further into it
-    fn verify_gep_no_pointer_indexing(&mut self, inst: &Instruction, operands: &[Value]) {
-        if operands.len() < 2 {
-            return; // Need at least base pointer and one index
+    /// Verify a cast: the type classes its opcode converts between, the
+    /// number of elements, and the direction of width changes
+    fn verify_cast_types(&mut self, inst: &Instruction, location: &str) {
+        let (Some(operand), Some(result)) = (inst.operands().first(), inst.result()) else {
+            return;
+        };
+        let src_type = operand.get_type();
+        let dst_type = result.get_type();
+        let opcode = inst.opcode();
+        if opcode == Opcode::BitCast {
+            self.verify_bitcast_types(src_type, dst_type, location);
+            return;
         }
 
-        // Get the GEP source type from the instruction
-        // This is the type specified in: getelementptr {i32, ptr}, ptr %X, ...
-        let mut current_type = if let Some(source_type) = inst.gep_source_type() {
-            source_type.clone()
-        } else {
-            // If no source type stored, try to get from base pointer's pointee type
-            let base_type = operands[0].get_type();
-            if let Some(pointee) = base_type.pointee_type() {
-                pointee.clone()
-            } else {
-                return; // Can't validate without type info
-            }
+        let name = format!("{:?}", opcode).to_lowercase();
+        let (src_class, dst_class) = match opcode {
+            Opcode::Trunc | Opcode::ZExt | Opcode::SExt => (TypeClass::Integer, TypeClass::Integer),
+            Opcode::FPTrunc | Opcode::FPExt => (TypeClass::Float, TypeClass::Float),
+            Opcode::FPToUI | Opcode::FPToSI => (TypeClass::Float, TypeClass::Integer),
+            Opcode::UIToFP | Opcode::SIToFP => (TypeClass::Integer, TypeClass::Float),
+            Opcode::PtrToInt => (TypeClass::Pointer, TypeClass::Integer),
+            Opcode::IntToPtr => (TypeClass::Integer, TypeClass::Pointer),
+            _ => (TypeClass::Pointer, TypeClass::Pointer),
         };
+        let src_ok = src_class.contains(src_type);
+        let dst_ok = dst_class.contains(dst_type);
+        if !src_ok {
+            self.report_cast(src_type, dst_type, format!("{} operand must be {}", name, src_class.describe()), location);
+        }
+        if !dst_ok {
+            self.report_cast(src_type, dst_type, format!("{} result must be {}", name, dst_class.describe()), location);
+        }
+        if !src_ok || !dst_ok {
+            return;
+        }
 
-        // First index dereferences the pointer to get to the aggregate
-        // Remaining indices navigate through the aggregate
-        // Skip operand 0 (base pointer) and start from operand 1 (first index)
-
-        for idx in 1..operands.len() {
-            // Check if current type is already a pointer
-            if current_type.is_pointer() {
-                // We've reached a pointer in the aggregate, cannot index further
-                if idx < operands.len() - 1 || (idx == operands.len() - 1 && idx > 1) {
-                    // There are more indices after reaching a pointer - invalid!
-                    self.errors.push(VerificationError::InvalidInstruction {
-                        reason: "invalid getelementptr indices".to_string(),
-                        location: "getelementptr instruction".to_string(),
-                    });
+        let (src_elem, src_lanes) = element_and_lanes(src_type);
+        let (dst_elem, dst_lanes) = element_and_lanes(dst_type);
+        if src_lanes != dst_lanes {
+            self.report_cast(src_type, dst_type,
+                format!("{} source and destination must have the same number of elements", name), location);
+            return;
+        }
+
+        match opcode {
+            Opcode::Trunc | Opcode::ZExt | Opcode::SExt => {
+                let (Some(src_bits), Some(dst_bits)) = (src_elem.int_width(), dst_elem.int_width()) else {
                     return;
+                };
+                if opcode == Opcode::Trunc && dst_bits >= src_bits {
+                    self.report_cast(src_type, dst_type,
+                        format!("trunc result must be smaller (src: {} bits, dst: {} bits)", src_bits, dst_bits), location);
+                } else if opcode != Opcode::Trunc && dst_bits <= src_bits {
+                    self.report_cast(src_type, dst_type,
+                        format!("{} result must be larger (src: {} bits, dst: {} bits)", name, src_bits, dst_bits), location);
                 }
-                break;
             }
-
-            // Navigate to the next type based on current type kind
-            if current_type.is_struct() {
-                // For struct, we need the constant index value to know which field
-                // For now, we can't evaluate constant expressions, so we check conservatively:
-                // If ANY field is a pointer and there are more indices, it's potentially invalid
-                if let Some(struct_fields) = current_type.struct_fields() {
-                    // Check if there are more indices after this one
-                    let has_more_indices = idx < operands.len() - 1;
-
-                    // Check if any field is a pointer
-                    let has_pointer_field = struct_fields.iter().any(|f| f.is_pointer());
-
-                    if has_pointer_field && has_more_indices {
-                        // Potentially invalid: indexing into a struct with pointer field(s)
-                        // and there are more indices. This catches the test case:
-                        // getelementptr {i32, ptr}, ptr %X, i32 0, i32 1, i32 0
-                        //                                            ^      ^    ^
-                        //                                         deref  field  ERROR!
-                        self.errors.push(VerificationError::InvalidInstruction {
-                            reason: "invalid getelementptr indices".to_string(),
-                            location: "getelementptr instruction".to_string(),
-                        });
-                        return;
-                    }
-
-                    // Can't precisely track which field without constant evaluation
-                    // so we stop here
-                    break;
-                }
-                break;
-            } else if current_type.is_array() {
-                // For arrays, move to the element type
-                if let Some((elem_type, _)) = current_type.array_info() {
-                    current_type = elem_type.clone();
-                } else {
-                    break;
+            Opcode::FPTrunc | Opcode::FPExt => {
+                let (Some(src_bits), Some(dst_bits)) = (float_width_range(src_elem), float_width_range(dst_elem)) else {
+                    return;
+                };
+                if opcode == Opcode::FPTrunc && dst_bits.0 >= src_bits.1 {
+                    self.report_cast(src_type, dst_type, "fptrunc result must be smaller".to_string(), location);
+                } else if opcode == Opcode::FPExt && dst_bits.1 <= src_bits.0 {
+                    self.report_cast(src_type, dst_type, "fpext result must be larger".to_string(), location);
                 }
-            } else if current_type.is_vector() {
-                // For vectors, move to the element type
-                if let Some((elem_type, _)) = current_type.vector_info() {
-                    current_type = elem_type.clone();
-                } else {
-                    break;
+            }
+            Opcode::AddrSpaceCast => {
+                let src_space = src_elem.address_space().unwrap_or(0);
+                if dst_elem.address_space().unwrap_or(0) == src_space {
+                    self.report_cast(src_type, dst_type,
+                        format!("addrspacecast must be between different address spaces (both are in {})", src_space), location);
                 }
-            } else {
-                // Other types (integers, etc.) can't be indexed into
-                break;
             }
+            _ => {}
         }
     }
 
-    /// Verify intrinsic-specific constraints
-    fn verify_intrinsic_call(&mut self, inst: &Instruction, intrinsic_name: &str) {
-        let operands = inst.operands();
-
-        // Check if intrinsic varargs declaration matches requirements
-        if operands.len() >= 1 {
-            let callee = &operands[0];
-            let callee_type = callee.get_type();
-
-            if let Some(fn_type) = if callee_type.is_pointer() {
-                callee_type.pointee_type()
-            } else if callee_type.is_function() {
-                Some(callee_type)
-            } else {
-                None
-            } {
-                if let Some((_, _, declared_varargs)) = fn_type.function_info() {
-                    // Check intrinsics that MUST be varargs
-                    if intrinsic_name.starts_with("llvm.experimental.stackmap") ||
-                       intrinsic_name.starts_with("llvm.experimental.patchpoint") {
-                        if !declared_varargs {
-                            self.errors.push(VerificationError::InvalidInstruction {
-                                reason: "Callsite was not defined with variable arguments!".to_string(),
-                                location: format!("call to {}", intrinsic_name),
-                            });
-                        }
-                    }
-
-                    // Check intrinsics that MUST NOT be varargs
-                    if intrinsic_name == "llvm.donothing" {
-                        if declared_varargs {
-                            self.errors.push(VerificationError::InvalidInstruction {
-                                reason: "Intrinsic was not defined with variable arguments!".to_string(),
-                                location: format!("call to {}", intrinsic_name),
-                            });
-                        }
-                    }
-                }
-            }
+    /// Verify a bitcast: it converts between first-class non-aggregate
+    /// types of the same width, and never changes the address space of a
+    /// pointer
+    fn verify_bitcast_types(&mut self, src_type: &Type, dst_type: &Type, location: &str) {
+        // Bitcast cannot convert to/from void or aggregates
+        if src_type.is_void() || dst_type.is_void() {
+            self.report_cast(src_type, dst_type, "bitcast cannot convert to/from void type".to_string(), location);
+            return;
         }
-
-        // Check immarg parameters - some intrinsic parameters must be immediate (constant) values
-        self.verify_intrinsic_immarg(inst, intrinsic_name, operands);
-
-        // llvm.experimental.deoptimize - must have exactly one "deopt" operand bundle
-        if intrinsic_name.starts_with("llvm.experimental.deoptimize.") {
-            let bundles = inst.operand_bundles();
-
-            // Must have exactly one "deopt" bundle
-            let deopt_bundles: Vec<_> = bundles.iter().filter(|b| b.tag == "deopt").collect();
-            if deopt_bundles.len() != 1 {
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "experimental_deoptimize must have exactly one \"deopt\" operand bundle".to_string(),
-                    location: format!("call to {}", intrinsic_name),
-                });
-            }
-
-            // Cannot be invoked (we check opcode is Call, not Invoke)
-            if inst.opcode() == Opcode::Invoke {
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "experimental_deoptimize cannot be invoked".to_string(),
-                    location: format!("invoke to {}", intrinsic_name),
-                });
-            }
-
-            // TODO: Check that it's followed by a return
-            // This requires CFG analysis which we'll add later
+        if src_type.is_struct() || src_type.is_array() || dst_type.is_struct() || dst_type.is_array() {
+            self.report_cast(src_type, dst_type, "bitcast cannot convert to/from aggregate types".to_string(), location);
+            return;
         }
 
-        // llvm.experimental.guard - must have exactly one "deopt" operand bundle
-        if intrinsic_name == "llvm.experimental.guard" {
-            let bundles = inst.operand_bundles();
+        // Check if types involve vectors of pointers
+        let src_is_ptr = src_type.is_pointer();
+        let dst_is_ptr = dst_type.is_pointer();
+        let src_is_vec_of_ptr = src_type.vector_info().is_some_and(|(elem, _)| elem.is_pointer());
+        let dst_is_vec_of_ptr = dst_type.vector_info().is_some_and(|(elem, _)| elem.is_pointer());
 
-            // Must have exactly one "deopt" bundle
-            let deopt_bundles: Vec<_> = bundles.iter().filter(|b| b.tag == "deopt").collect();
-            if deopt_bundles.len() != 1 {
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "guard must have exactly one \"deopt\" operand bundle".to_string(),
-                    location: format!("call to {}", intrinsic_name),
-                });
-            }
-
-            // Cannot be invoked
-            if inst.opcode() == Opcode::Invoke {
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "guard cannot be invoked".to_string(),
-                    location: format!("invoke to {}", intrinsic_name),
-                });
+        // Cannot bitcast between scalar pointer and vector of pointers with size != 1
+        if (src_is_ptr && dst_is_vec_of_ptr) || (src_is_vec_of_ptr && dst_is_ptr) {
+            let vec_size = if dst_is_vec_of_ptr {
+                dst_type.vector_info().map(|(_, size)| size).unwrap_or(0)
+            } else {
+                src_type.vector_info().map(|(_, size)| size).unwrap_or(0)
+            };
+            // Only error if vector size is not 1 (size 1 is allowed, same size as scalar)
+            if vec_size != 1 {
+                let src_desc = if src_is_ptr { "ptr".to_string() } else { format!("{:?}", src_type) };
+                let dst_desc = if dst_is_vec_of_ptr { format!("{:?}", dst_type) } else { "ptr".to_string() };
+                self.report_cast(src_type, dst_type,
+                    format!("invalid cast opcode for cast from '{}' to '{}'", src_desc, dst_desc), location);
             }
         }
 
-        // llvm.va_start - must be called in a varargs function
-        // Note: Temporarily disabled - need to ensure parser correctly sets is_varargs
-        // if intrinsic_name == "llvm.va_start" {
-        //     if !self.current_function_is_varargs {
-        //         self.errors.push(VerificationError::InvalidInstruction {
-        //             reason: "va_start called in a non-varargs function".to_string(),
-        //             location: format!("call to {}", intrinsic_name),
-        //         });
-        //     }
-        // }
-
-        // llvm.bswap - must have even number of bytes
-        if intrinsic_name.starts_with("llvm.bswap.") {
-            if operands.len() >= 2 {
-                // operands[0] is the function, operands[1] is the argument
-                let arg_type = operands[1].get_type();
-
-                // Get the integer width
-                if let Some(bits) = arg_type.int_width() {
-                    if bits % 16 != 0 {
-                        self.errors.push(VerificationError::InvalidInstruction {
-                            reason: "bswap must be an even number of bytes".to_string(),
-                            location: format!("call to {}", intrinsic_name),
-                        });
-                    }
-                } else if arg_type.is_vector() {
-                    // Check vector element type
-                    if let Some((elem_type, _)) = arg_type.vector_info() {
-                        if let Some(bits) = elem_type.int_width() {
-                            if bits % 16 != 0 {
-                                self.errors.push(VerificationError::InvalidInstruction {
-                                    reason: "bswap must be an even number of bytes".to_string(),
-                                    location: format!("call to {}", intrinsic_name),
-                                });
-                            }
-                        }
-                    }
-                }
+        // Bitcast between pointers and non-pointers requires compatible sizes
+        if src_is_ptr != dst_is_ptr && !src_is_vec_of_ptr && !dst_is_vec_of_ptr {
+            // One is pointer, other is not - check if non-pointer is integer
+            let non_ptr_type = if src_is_ptr { dst_type } else { src_type };
+            if !non_ptr_type.is_integer() && !non_ptr_type.is_vector() {
+                self.report_cast(src_type, dst_type,
+                    "bitcast between pointer and non-integer type is invalid".to_string(), location);
             }
         }
 
-        // llvm.stepvector - must return a vector of integers with bitwidth >= 8
-        if intrinsic_name.starts_with("llvm.stepvector.") {
-            if let Some(result) = inst.result() {
-                let result_type = result.get_type();
-                if !result_type.is_vector() {
-                    self.errors.push(VerificationError::InvalidInstruction {
-                        reason: "Intrinsic has incorrect return type!".to_string(),
-                        location: format!("call to {}", intrinsic_name),
-                    });
-                } else if let Some((elem_type, _)) = result_type.vector_info() {
-                    if !elem_type.is_integer() {
-                        self.errors.push(VerificationError::InvalidInstruction {
-                            reason: "stepvector only supported for vectors of integers with a bitwidth of at least 8".to_string(),
-                            location: format!("call to {}", intrinsic_name),
-                        });
-                    } else if let Some(bits) = elem_type.int_width() {
-                        if bits < 8 {
-                            self.errors.push(VerificationError::InvalidInstruction {
-                                reason: "stepvector only supported for vectors of integers with a bitwidth of at least 8".to_string(),
-                                location: format!("call to {}", intrinsic_name),
-                            });
-                        }
-                    }
-                }
+        // Bitcast cannot change address space (both scalar and vector pointers)
+        if (src_is_ptr && dst_is_ptr) || (src_is_vec_of_ptr && dst_is_vec_of_ptr) {
+            let src_addrspace = element_and_lanes(src_type).0.address_space().unwrap_or(0);
+            let dst_addrspace = element_and_lanes(dst_type).0.address_space().unwrap_or(0);
+            if src_addrspace != dst_addrspace {
+                let src_desc = if src_is_ptr { "ptr".to_string() } else { format!("<{:?}>", src_type) };
+                let dst_desc = if dst_is_ptr {
+                    format!("ptr addrspace({})", dst_addrspace)
+                } else {
+                    format!("<{:?}>", dst_type)
+                };
+                self.report_cast(src_type, dst_type,
+                    format!("invalid cast opcode for cast from '{}' to '{}'", src_desc, dst_desc), location);
             }
         }
 
-        // llvm.memcpy, llvm.memset, llvm.memset.pattern - memory intrinsics
-        // These intrinsics require power-of-two alignment
-        if intrinsic_name.starts_with("llvm.memcpy.") ||
-           intrinsic_name.starts_with("llvm.memset.") ||
-           intrinsic_name.starts_with("llvm.experimental.memset.pattern.") {
-            // Check if the first pointer argument has an alignment attribute
-            // The alignment must be a power of two
-            // Note: Alignment is attached to the instruction, not the argument
-            if let Some(alignment) = inst.alignment() {
-                // Check if alignment is a power of two
-                if alignment > 0 && (alignment & (alignment - 1)) != 0 {
-                    self.errors.push(VerificationError::InvalidInstruction {
-                        reason: "alignment is not a power of two".to_string(),
-                        location: format!("call to {}", intrinsic_name),
-                    });
-                }
+        // Integers, floats and vectors of them must keep their width
+        if let (Some(src_bits), Some(dst_bits)) = (primitive_width_range(src_type), primitive_width_range(dst_type)) {
+            if src_bits.1 < dst_bits.0 || dst_bits.1 < src_bits.0 {
+                self.report_cast(src_type, dst_type, "bitcast requires types of same width".to_string(), location);
             }
         }
+    }
 
-        // llvm.experimental.gc.relocate - garbage collection relocation
-        if intrinsic_name.starts_with("llvm.experimental.gc.relocate.") {
-            // gc.relocate takes a token and two i32 indices
-            // The relocated value must be a pointer type
-            // The result address space must match the relocated value address space
-
-            // Check that the token is not "none"
-            if operands.len() >= 2 {
-                let token_arg = &operands[1]; // operands[0] is function, operands[1] is token
-
-                // Check if the token argument is undef or poison (which represents "none")
-                if token_arg.name() == Some("none") || token_arg.name() == Some("undef") {
-                    self.errors.push(VerificationError::InvalidInstruction {
-                        reason: "gc relocate is incorrectly tied to the statepoint".to_string(),
-                        location: "(undef, undef)".to_string(),
-                    });
-                }
-            }
-
-            if let Some(result) = inst.result() {
-                let result_type = result.get_type();
-
-                // Result must be a pointer
-                if !result_type.is_pointer() {
-                    self.errors.push(VerificationError::InvalidInstruction {
-                        reason: "gc.relocate: result must be a pointer".to_string(),
-                        location: format!("call to {}", intrinsic_name),
-                    });
-                }
-
-                // Check that relocated value is a pointer
-                // operands[0] is the function, operands[1] is token, operands[2] and [3] are indices
-                // We need to look up what value the indices point to in the statepoint
-                // For now, we'll just check the result type consistency
-
-                // The relocated value must be a gc pointer (pointer type)
-                // This is checked by looking at the result - if result is not a pointer, error
-                // Additional check: The intrinsic name encoding should match result type address space
+    /// Verify a unary or binary operator: its operands have the class the
+    /// opcode requires and one type, which is also the type of the result
+    fn verify_arithmetic_types(&mut self, inst: &Instruction, location: &str) {
+        let operands = inst.operands();
+        let Some(first) = operands.first() else {
+            return;
+        };
+        let ty = first.get_type();
+        for (i, operand) in operands.iter().enumerate().skip(1) {
+            if operand.get_type() != ty {
+                self.report(Rule::OperandTypes, VerificationError::TypeMismatch {
+                    expected: format!("{:?}", ty),
+                    found: format!("{:?}", operand.get_type()),
+                    location: format!("{} operand {}", location, i),
+                });
             }
         }
 
-        // llvm.vector.reduce.* - vector reduction intrinsics
-        if intrinsic_name.starts_with("llvm.vector.reduce.") {
-            self.verify_intrinsic_vector_reduce(inst, intrinsic_name, operands);
-        }
-
-        // llvm.is.fpclass - floating-point class test
-        if intrinsic_name.starts_with("llvm.is.fpclass.") {
-            self.verify_intrinsic_is_fpclass(inst, intrinsic_name, operands);
+        let (valid, reason) = match inst.opcode() {
+            Opcode::Shl | Opcode::LShr | Opcode::AShr =>
+                (self.is_integer_or_vector_of_integers(ty), "shift operand must be integer or vector of integers"),
+            Opcode::FNeg | Opcode::FAdd | Opcode::FSub | Opcode::FMul | Opcode::FDiv | Opcode::FRem =>
+                (self.is_float_or_vector_of_floats(ty), "floating point operation requires float or vector of floats operands"),
+            _ => (self.is_integer_or_vector_of_integers(ty), "integer operation requires integer or vector of integers operands"),
+        };
+        if !valid {
+            self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                reason: format!("{}, got {:?}", reason, ty),
+                location: location.to_string(),
+            });
+        } else if let Some(result) = inst.result() {
+            self.verify_result_type(result.get_type(), ty, location);
         }
+    }
 
-        // llvm.sadd.sat, llvm.uadd.sat, llvm.ssub.sat, llvm.usub.sat, llvm.sshl.sat, llvm.ushl.sat
-        if intrinsic_name.contains(".sat.") {
-            self.verify_intrinsic_sat(inst, intrinsic_name, operands);
+    /// Verify an icmp or fcmp: operands of one comparable type, and an i1
+    /// result with one element per compared element
+    fn verify_comparison_types(&mut self, inst: &Instruction, location: &str) {
+        let operands = inst.operands();
+        if operands.len() < 2 {
+            return;
         }
-
-        // llvm.vp.* - vector predication intrinsics
-        if intrinsic_name.starts_with("llvm.vp.") {
-            self.verify_intrinsic_vp(inst, intrinsic_name, operands);
+        let op1_type = operands[0].get_type();
+        let op2_type = operands[1].get_type();
+        // Allow pointer type equivalence for comparisons
+        if op1_type != op2_type && !(op1_type.is_pointer() && op2_type.is_pointer()) {
+            self.report(Rule::OperandTypes, VerificationError::TypeMismatch {
+                expected: format!("{:?}", op1_type),
+                found: format!("{:?}", op2_type),
+                location: format!("{} comparison operand 1", location),
+            });
         }
 
-        // llvm.bswap - must operate on types with even number of bytes
-        if intrinsic_name.starts_with("llvm.bswap.") {
-            self.verify_intrinsic_bswap(inst, intrinsic_name, operands);
+        let (elem, lanes) = element_and_lanes(op1_type);
+        let reason = if inst.opcode() == Opcode::ICmp {
+            (!elem.is_integer() && !elem.is_pointer())
+                .then_some("integer comparison operands must be integers, pointers or vectors of them")
+        } else {
+            (!elem.is_float()).then_some("floating point comparison operands must be floats or vectors of floats")
+        };
+        if let Some(reason) = reason {
+            self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                reason: format!("{}, got {:?}", reason, op1_type),
+                location: location.to_string(),
+            });
         }
 
-        // llvm.experimental.get.vector.length - VF (second operand) must be positive
-        // Note: This would require constant analysis to check if the value is > 0
-        // For now, we can't validate this without constant folding infrastructure
-
-        // llvm.memcpy/memmove/memset - last argument (is_volatile) must be constant
-        if intrinsic_name.starts_with("llvm.memcpy.") || intrinsic_name.starts_with("llvm.memmove.") ||
-           intrinsic_name.starts_with("llvm.memset.") {
-            // For memcpy/memmove: operands[0] = function, [1] = dest, [2] = src, [3] = length, [4] = is_volatile
-            // For memset: operands[0] = function, [1] = dest, [2] = value, [3] = length, [4] = is_volatile
-            let is_volatile_idx = if intrinsic_name.contains(".inline.") {
-                4 // inline variants have is_volatile at index 4
-            } else {
-                4 // standard variants also have it at index 4
-            };
-
-            if operands.len() > is_volatile_idx {
-                let is_volatile = &operands[is_volatile_idx];
-                // Check if it's a constant by checking if it has a name (non-constants have names)
-                if is_volatile.name().is_some() {
-                    self.errors.push(VerificationError::InvalidInstruction {
-                        reason: "immarg operand has non-immediate parameter".to_string(),
-                        location: format!("call to {}", intrinsic_name),
-                    });
-                }
+        if let Some(result) = inst.result() {
+            let (result_elem, result_lanes) = element_and_lanes(result.get_type());
+            if result_elem.int_width() != Some(1) || result_lanes != lanes {
+                let expected = match lanes {
+                    Some(n) => format!("<{} x i1>", n),
+                    None => "i1".to_string(),
+                };
+                self.report(Rule::OperandTypes, VerificationError::TypeMismatch {
+                    expected,
+                    found: format!("{:?}", result.get_type()),
+                    location: format!("{} comparison result", location),
+                });
             }
         }
+    }
 
-        // llvm.cttz/ctlz - second argument must be constant i1 (immarg)
-        if intrinsic_name.starts_with("llvm.ctlz.") || intrinsic_name.starts_with("llvm.cttz.") {
-            if operands.len() >= 3 {
-                // operands[0] = function, operands[1] = value, operands[2] = is_zero_poison
-                let is_zero_poison = &operands[2];
-                // Check if it's a constant by checking if it has a name (non-constants have names)
-                if is_zero_poison.name().is_some() {
-                    self.errors.push(VerificationError::InvalidInstruction {
-                        reason: "immarg operand has non-immediate parameter".to_string(),
-                        location: format!("call to {}", intrinsic_name),
-                    });
-                }
-            }
+    /// Verify a select: an i1 condition, or a vector of i1 with one element
+    /// per element of the values, and values of one type that is the type
+    /// of the result
+    fn verify_select_types(&mut self, inst: &Instruction, location: &str) {
+        let operands = inst.operands();
+        if operands.len() < 3 {
+            return;
         }
+        let cond_type = operands[0].get_type();
+        let true_type = operands[1].get_type();
+        let false_type = operands[2].get_type();
 
-        // llvm.returnaddress / llvm.frameaddress - argument must be constant
-        if intrinsic_name.starts_with("llvm.returnaddress") || intrinsic_name.starts_with("llvm.frameaddress") {
-            if operands.len() >= 2 {
-                // operands[0] = function, operands[1] = level
-                let level = &operands[1];
-                if level.name().is_some() {
-                    self.errors.push(VerificationError::InvalidInstruction {
-                        reason: "immarg operand has non-immediate parameter".to_string(),
-                        location: format!("call to {}", intrinsic_name),
-                    });
-                }
-            }
+        // Select values cannot have token type
+        if true_type.is_token() || false_type.is_token() {
+            self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                reason: "select values cannot have token type".to_string(),
+                location: location.to_string(),
+            });
         }
 
-        // llvm.objectsize - all boolean arguments must be constant
-        if intrinsic_name.starts_with("llvm.objectsize.") {
-            if operands.len() >= 5 {
-                // operands[0] = function, operands[1] = ptr, operands[2-4] = boolean flags
-                for i in 2..5 {
-                    if operands[i].name().is_some() {
-                        self.errors.push(VerificationError::InvalidInstruction {
-                            reason: "immarg operand has non-immediate parameter".to_string(),
-                            location: format!("call to {}", intrinsic_name),
-                        });
-                        break; // Only report once per call
-                    }
-                }
-            }
+        // Allow pointer type equivalence
+        if true_type != false_type && !(true_type.is_pointer() && false_type.is_pointer()) {
+            self.report(Rule::OperandTypes, VerificationError::TypeMismatch {
+                expected: format!("{:?}", true_type),
+                found: format!("{:?}", false_type),
+                location: format!("{} select true/false values", location),
+            });
         }
 
-        // llvm.va_start - must be called in a varargs function
-        if intrinsic_name == "llvm.va_start" {
-            if !self.current_function_is_varargs {
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "va_start called in a non-varargs function".to_string(),
-                    location: format!("call to {}", intrinsic_name),
-                });
-            }
+        let (cond_elem, cond_lanes) = element_and_lanes(cond_type);
+        if cond_elem.int_width() != Some(1) {
+            self.report(Rule::OperandTypes, VerificationError::TypeMismatch {
+                expected: "i1 or vector of i1".to_string(),
+                found: format!("{:?}", cond_type),
+                location: format!("{} select condition", location),
+            });
+        } else if cond_lanes.is_some() && cond_lanes != element_and_lanes(true_type).1 {
+            self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                reason: format!("select condition {:?} must have one element per element of the values {:?}",
+                    cond_type, true_type),
+                location: location.to_string(),
+            });
         }
 
-        // llvm.abs - integer absolute value intrinsic
-        if intrinsic_name.starts_with("llvm.abs.") {
-            if operands.len() >= 3 {
-                // operands[0] = function, operands[1] = value, operands[2] = is_int_min_poison
-                let value_type = operands[1].get_type();
-
-                // Value must be integer or vector of integers
-                if !value_type.is_integer() && !(value_type.is_vector() && value_type.vector_info().map_or(false, |(e, _)| e.is_integer())) {
-                    self.errors.push(VerificationError::InvalidInstruction {
-                        reason: "Intrinsic has incorrect argument type!".to_string(),
-                        location: format!("call to {}", intrinsic_name),
-                    });
-                }
-
-                // Second argument (is_int_min_poison) must be constant
-                let is_poison = &operands[2];
-                if is_poison.name().is_some() {
-                    self.errors.push(VerificationError::InvalidInstruction {
-                        reason: "immarg operand has non-immediate parameter".to_string(),
-                        location: format!("call to {}", intrinsic_name),
-                    });
-                }
-
-                // Return type must match argument type
-                if let Some(result) = inst.result() {
-                    let result_type = result.get_type();
-                    if *result_type != *value_type {
-                        self.errors.push(VerificationError::InvalidInstruction {
-                            reason: "Intrinsic has incorrect return type!".to_string(),
-                            location: format!("call to {}", intrinsic_name),
-                        });
-                    }
-                }
+        if let Some(result) = inst.result() {
+            if !(result.get_type().is_pointer() && true_type.is_pointer()) {
+                self.verify_result_type(result.get_type(), true_type, location);
             }
         }
+    }
 
-        // llvm.smax/smin/umax/umin - integer min/max intrinsics
-        if intrinsic_name.starts_with("llvm.smax.") || intrinsic_name.starts_with("llvm.smin.") ||
-           intrinsic_name.starts_with("llvm.umax.") || intrinsic_name.starts_with("llvm.umin.") {
-            if operands.len() >= 3 {
-                // operands[0] = function, operands[1] = arg1, operands[2] = arg2
-                let arg1_type = operands[1].get_type();
-                let arg2_type = operands[2].get_type();
-
-                // Both arguments must be integers or vectors of integers
-                let arg1_valid = arg1_type.is_integer() ||
-                    (arg1_type.is_vector() && arg1_type.vector_info().map_or(false, |(e, _)| e.is_integer()));
-                let arg2_valid = arg2_type.is_integer() ||
-                    (arg2_type.is_vector() && arg2_type.vector_info().map_or(false, |(e, _)| e.is_integer()));
-
-                if !arg1_valid || !arg2_valid {
-                    self.errors.push(VerificationError::InvalidInstruction {
-                        reason: "Intrinsic has incorrect argument type!".to_string(),
-                        location: format!("call to {}", intrinsic_name),
-                    });
-                }
-
-                // Both arguments must have same type
-                if *arg1_type != *arg2_type {
-                    self.errors.push(VerificationError::InvalidInstruction {
-                        reason: "Intrinsic has incorrect argument type!".to_string(),
-                        location: format!("call to {}", intrinsic_name),
-                    });
-                }
-
-                // Return type must match argument types
-                if let Some(result) = inst.result() {
-                    let result_type = result.get_type();
-                    if *result_type != *arg1_type {
-                        self.errors.push(VerificationError::InvalidInstruction {
-                            reason: "Intrinsic has incorrect return type!".to_string(),
-                            location: format!("call to {}", intrinsic_name),
-                        });
-                    }
-                }
-            }
+    /// Verify an extractelement or insertelement: a vector operand, an
+    /// integer index and an element of the vector's element type
+    fn verify_vector_element_types(&mut self, inst: &Instruction, location: &str) {
+        let operands = inst.operands();
+        let name = format!("{:?}", inst.opcode()).to_lowercase();
+        // insertelement takes the inserted value between the vector and the index
+        let (value, index) = if inst.opcode() == Opcode::InsertElement { (Some(1), 2) } else { (None, 1) };
+        if operands.len() <= index {
+            return;
         }
+        let vec_type = operands[0].get_type();
+        let idx_type = operands[index].get_type();
 
-        // llvm.masked.load - mask parameter type must match vector scalability
-        if intrinsic_name.starts_with("llvm.masked.load.") {
-            if operands.len() >= 3 {
-                // operands[0] = function, operands[1] = ptr, operands[2] = mask (or might be alignment first)
-                // Try operand 2 first, then 3 if that doesn't work
-                let mask_type = operands[2].get_type();
-
-                // Check if intrinsic expects scalable vector
-                // Scalable vectors have ".nx" in the name (e.g., llvm.masked.load.nxv4i32.p0)
-                let ret_is_scalable = intrinsic_name.contains(".nx");
-
-                // Check if mask is a vector
-                if mask_type.is_vector() {
-                    // For scalable return, we need scalable mask
-                    // For fixed return, we need fixed mask
-                    // The test case has nxv4i32 (scalable) but mask <4 x i1> (fixed)
-                    // This should fail
-
-                    // Heuristic: if the mask size in the type doesn't match the intrinsic,
-                    // there's likely a mismatch
-                    if ret_is_scalable {
-                        // Check if this is a fixed-size vector being used for scalable vector load
-                        // Fixed vectors parse as simple <N x type>, scalable might have vscale or parser issue
-                        // For now, check operand count - if wrong number of operands, might be the issue
-                        if operands.len() == 4 {
-                            // Likely: ptr, mask, passthru, align
-                            // Retry with operand 2 as mask
-                            let actual_mask = operands[2].get_type();
-                            if actual_mask.is_vector() {
-                                // Simple check: scalable intrinsic should reject fixed-size mask
-                                // This will need parser support to distinguish scalable vs fixed properly
-                                // For now, report error on any scalable intrinsic
-                                // TODO: Fix when parser properly distinguishes scalable vectors
-                                self.errors.push(VerificationError::InvalidInstruction {
-                                    reason: "Intrinsic has incorrect argument type!".to_string(),
-                                    location: format!("ptr @{}", intrinsic_name),
-                                });
-                            }
-                        }
-                    }
-                }
-            }
+        if !idx_type.is_integer() {
+            self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                reason: format!("{} index must be integer type, got {:?}", name, idx_type),
+                location: location.to_string(),
+            });
         }
+        let Some((elem_type, _)) = vec_type.vector_info() else {
+            self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                reason: format!("{} first operand must be vector type, got {:?}", name, vec_type),
+                location: location.to_string(),
+            });
+            return;
+        };
 
-        // llvm.vector.extract - validation
-        if intrinsic_name.starts_with("llvm.vector.extract.") {
-            if operands.len() >= 3 {
-                // operands[0] = function, operands[1] = source vector, operands[2] = index
-                let src_type = operands[1].get_type();
-
-                if let Some(result) = inst.result() {
-                    let result_type = result.get_type();
-
-                    // Check element types match
-                    if let Some((src_elem, _)) = src_type.vector_info() {
-                        if let Some((res_elem, _)) = result_type.vector_info() {
-                            if *src_elem != *res_elem {
-                                self.errors.push(VerificationError::InvalidInstruction {
-                                    reason: "vector_extract result must have the same element type as the input vector.".to_string(),
-                                    location: format!("call to {}", intrinsic_name),
-                                });
-                            }
-                        }
-                    }
-                }
+        if let Some(value) = value {
+            let value_type = operands[value].get_type();
+            if value_type != elem_type {
+                self.report(Rule::OperandTypes, VerificationError::TypeMismatch {
+                    expected: format!("{:?}", elem_type),
+                    found: format!("{:?}", value_type),
+                    location: format!("{} insertelement value", location),
+                });
             }
         }
-
-        // llvm.vector.insert - validation
-        if intrinsic_name.starts_with("llvm.vector.insert.") {
-            if operands.len() >= 4 {
-                // operands[0] = function, operands[1] = dest vector, operands[2] = subvector, operands[3] = index
-                let dest_type = operands[1].get_type();
-                let sub_type = operands[2].get_type();
-
-                // Check element types match
-                if let Some((dest_elem, _)) = dest_type.vector_info() {
-                    if let Some((sub_elem, _)) = sub_type.vector_info() {
-                        if *dest_elem != *sub_elem {
-                            self.errors.push(VerificationError::InvalidInstruction {
-                                reason: "vector_insert parameters must have the same element type.".to_string(),
-                                location: format!("call to {}", intrinsic_name),
-                            });
-                        }
-                    }
-                }
-            }
+        if let Some(result) = inst.result() {
+            let expected = if value.is_some() { vec_type } else { elem_type };
+            self.verify_result_type(result.get_type(), expected, location);
         }
+    }
 
-        // llvm.vector.splice - index range validation
-        if intrinsic_name.starts_with("llvm.vector.splice.") {
-            if operands.len() >= 4 {
-                // operands[0] = function, operands[1] = vec1, operands[2] = vec2, operands[3] = index
-                let vec_type = operands[1].get_type();
-                let index_val = &operands[3];
-
-                // Get vector length if fixed-size vector
-                if let Some((_, vec_len)) = vec_type.vector_info() {
-                    // Index must be an immediate constant
-                    if let Some(idx) = index_val.const_int_value() {
-                        let vl = vec_len as i64;
-                        // Index must be in range [-VL, VL-1]
-                        if idx < -vl || idx >= vl {
-                            self.errors.push(VerificationError::InvalidInstruction {
-                                reason: "The splice index exceeds the range [-VL, VL-1] where VL is the known minimum number of elements in the vector".to_string(),
-                                location: format!("call to {}", intrinsic_name),
-                            });
-                        }
-                    }
-                }
-            }
+    /// Verify a shufflevector: two vectors of one type, a constant mask of
+    /// i32 selecting elements of either, and a result with the element type
+    /// of the inputs and the length of the mask
+    fn verify_shufflevector_types(&mut self, inst: &Instruction, location: &str) {
+        let operands = inst.operands();
+        if operands.len() < 3 {
+            return;
         }
-    }
+        let vec1_type = operands[0].get_type();
+        let vec2_type = operands[1].get_type();
+        let mask = &operands[2];
+        let mask_type = mask.get_type();
 
-    /// Verify immarg (immediate argument) parameters for intrinsics
-    /// Certain intrinsic parameters must be immediate (literal) constant values
-    /// immarg rejects: variables, undef, poison, zeroinitializer, constant aggregates, constant expressions
-    fn verify_intrinsic_immarg(&mut self, inst: &Instruction, intrinsic_name: &str, operands: &[Value]) {
-        // Helper to check if operand at index is not an immediate constant
-        let check_immarg = |idx: usize| -> bool {
-            if operands.len() > idx {
-                let operand = &operands[idx];
-                // immarg requires literal constants (ConstantInt or ConstantFloat only)
-                // Rejects: variables, undef, poison, zeroinitializer, aggregates, constant expressions
-                !operand.is_immediate()
-            } else {
-                false
-            }
+        if vec1_type != vec2_type {
+            self.report(Rule::OperandTypes, VerificationError::TypeMismatch {
+                expected: format!("{:?}", vec1_type),
+                found: format!("{:?}", vec2_type),
+                location: format!("{} shufflevector second vector", location),
+            });
+        }
+        let Some((elem_type, size)) = vec1_type.vector_info() else {
+            self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                reason: format!("shufflevector operands must be vector types, got {:?}", vec1_type),
+                location: location.to_string(),
+            });
+            return;
         };
 
-        // Define immarg parameter positions for each intrinsic family
-        // Format: (intrinsic_prefix, vec![param_indices_that_must_be_constant])
-        // Note: indices include the function itself at position 0, so actual args start at 1
-
-        // llvm.memcpy, llvm.memmove, llvm.memset - is_volatile must be constant
-        if intrinsic_name.starts_with("llvm.memcpy.") ||
-           intrinsic_name.starts_with("llvm.memmove.") ||
-           intrinsic_name.starts_with("llvm.memset.") {
-            if check_immarg(4) { // is_volatile parameter
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "immarg operand has non-immediate parameter".to_string(),
-                    location: format!("call to {}", intrinsic_name),
-                });
-            }
+        let Some((mask_elem, mask_size)) = mask_type.vector_info() else {
+            self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                reason: format!("shufflevector mask must be vector type, got {:?}", mask_type),
+                location: location.to_string(),
+            });
+            return;
+        };
+        if mask_elem.int_width() != Some(32) {
+            self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                reason: format!("shufflevector mask must be vector of i32, got vector of {:?}", mask_elem),
+                location: location.to_string(),
+            });
         }
-
-        // llvm.memcpy.element.unordered.atomic - element_size must be constant
-        if intrinsic_name.starts_with("llvm.memcpy.element.unordered.atomic") ||
-           intrinsic_name.starts_with("llvm.memmove.element.unordered.atomic") ||
-           intrinsic_name.starts_with("llvm.memset.element.unordered.atomic") {
-            if check_immarg(4) { // element_size parameter
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "immarg operand has non-immediate parameter".to_string(),
-                    location: format!("call to {}", intrinsic_name),
+        if !mask.is_constant() {
+            self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                reason: "shufflevector mask must be a constant".to_string(),
+                location: location.to_string(),
+            });
+        }
+        // Undef and poison elements select nothing; scalable vectors only
+        // take splat masks
+        let indices = mask.vector_elements().into_iter().flatten().filter_map(|e| e.as_const_int());
+        for (i, index) in indices.enumerate() {
+            if size > 0 && (index < 0 || index as usize >= 2 * size) {
+                self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                    reason: format!("shufflevector mask element {} selects element {} of two {}-element vectors",
+                        i, index, size),
+                    location: location.to_string(),
                 });
             }
         }
 
-        // llvm.returnaddress, llvm.frameaddress - level must be constant
-        if intrinsic_name.starts_with("llvm.returnaddress") ||
-           intrinsic_name.starts_with("llvm.frameaddress") {
-            if check_immarg(1) {
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "immarg operand has non-immediate parameter".to_string(),
-                    location: format!("call to {}", intrinsic_name),
+        if let Some(result) = inst.result() {
+            let result_ok = result.get_type().vector_info()
+                .is_some_and(|(result_elem, result_size)| result_elem == elem_type && result_size == mask_size);
+            if !result_ok {
+                self.report(Rule::OperandTypes, VerificationError::TypeMismatch {
+                    expected: format!("<{} x {:?}>", mask_size, elem_type),
+                    found: format!("{:?}", result.get_type()),
+                    location: format!("{} result", location),
                 });
             }
         }
+    }
 
-        // llvm.objectsize - boolean flags must be constant
-        if intrinsic_name.starts_with("llvm.objectsize.") {
-            for i in 2..5 {
-                if check_immarg(i) {
-                    self.errors.push(VerificationError::InvalidInstruction {
-                        reason: "immarg operand has non-immediate parameter".to_string(),
-                        location: format!("call to {}", intrinsic_name),
-                    });
-                    break;
-                }
-            }
+    /// Verify an extractvalue or insertvalue: constant indices that stay
+    /// within the aggregate, and an extracted or inserted value of the
+    /// indexed type
+    fn verify_aggregate_op_types(&mut self, inst: &Instruction, location: &str) {
+        let operands = inst.operands();
+        let is_insert = inst.opcode() == Opcode::InsertValue;
+        let name = if is_insert { "insertvalue" } else { "extractvalue" };
+        let first_index = if is_insert { 2 } else { 1 };
+        if operands.len() < first_index {
+            return;
         }
-
-        // llvm.smul.fix, llvm.umul.fix, llvm.smul.fix.sat, llvm.umul.fix.sat - scale must be constant
-        if intrinsic_name.starts_with("llvm.smul.fix.") ||
-           intrinsic_name.starts_with("llvm.umul.fix.") {
-            if check_immarg(3) { // scale parameter
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "immarg operand has non-immediate parameter".to_string(),
-                    location: format!("call to {}", intrinsic_name),
-                });
-            }
+        let agg_type = operands[0].get_type();
+        if !agg_type.is_struct() && !agg_type.is_array() {
+            self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                reason: format!("{} operand must be aggregate type (struct or array), got {:?}", name, agg_type),
+                location: location.to_string(),
+            });
+            return;
         }
-
-        // llvm.invariant.start - size must be constant
-        if intrinsic_name.starts_with("llvm.invariant.start.") {
-            if check_immarg(1) { // size parameter
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "immarg operand has non-immediate parameter".to_string(),
-                    location: format!("call to {}", intrinsic_name),
-                });
-            }
+        if operands.len() == first_index {
+            self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                reason: format!("{} requires at least one index", name),
+                location: location.to_string(),
+            });
+            return;
         }
 
-        // llvm.invariant.end - size must be constant
-        if intrinsic_name.starts_with("llvm.invariant.end.") {
-            if check_immarg(2) { // size parameter
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "immarg operand has non-immediate parameter".to_string(),
-                    location: format!("call to {}", intrinsic_name),
+        let mut indexed = agg_type.clone();
+        for (i, operand) in operands[first_index..].iter().enumerate() {
+            let Some(index) = operand.as_const_int() else {
+                self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                    reason: format!("{} index {} must be a constant integer", name, i),
+                    location: location.to_string(),
                 });
-            }
+                return;
+            };
+            let position = usize::try_from(index).ok();
+            let (next, len) = if let Some(fields) = indexed.struct_fields() {
+                (position.and_then(|p| fields.get(p).cloned()), fields.len())
+            } else if let Some((elem, len)) = indexed.array_info() {
+                (position.filter(|&p| p < len).map(|_| elem.clone()), len)
+            } else {
+                self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                    reason: format!("{} index {} indexes into non-aggregate type {:?}", name, i, indexed),
+                    location: location.to_string(),
+                });
+                return;
+            };
+            let Some(next) = next else {
+                self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                    reason: format!("{} index {} is {}, out of range for {} elements of {:?}", name, i, index, len, indexed),
+                    location: location.to_string(),
+                });
+                return;
+            };
+            indexed = next;
         }
 
-        // llvm.prefetch - rw, locality, cache type must be constant
-        if intrinsic_name.starts_with("llvm.prefetch") {
-            if check_immarg(2) || check_immarg(3) || check_immarg(4) {
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "immarg operand has non-immediate parameter".to_string(),
-                    location: format!("call to {}", intrinsic_name),
+        if is_insert {
+            let value_type = operands[1].get_type();
+            if *value_type != indexed {
+                self.report(Rule::OperandTypes, VerificationError::TypeMismatch {
+                    expected: format!("{:?}", indexed),
+                    found: format!("{:?}", value_type),
+                    location: format!("{} inserted value", location),
                 });
             }
         }
+        if let Some(result) = inst.result() {
+            let expected = if is_insert { agg_type } else { &indexed };
+            self.verify_result_type(result.get_type(), expected, location);
+        }
+    }
 
-        // llvm.localrecover - index must be constant
-        if intrinsic_name.starts_with("llvm.localrecover") {
-            if check_immarg(3) { // index parameter
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "immarg operand has non-immediate parameter".to_string(),
-                    location: format!("call to {}", intrinsic_name),
-                });
-            }
+    /// Verify a getelementptr: a pointer base, integer indices that stay
+    /// within the source type, vector operands of one length, and a
+    /// pointer result in the base's address space
+    fn verify_gep_types(&mut self, inst: &Instruction, location: &str) {
+        let operands = inst.operands();
+        let Some(base) = operands.first() else {
+            return;
+        };
+        let base_type = base.get_type();
+        let (base_elem, base_lanes) = element_and_lanes(base_type);
+        if !base_elem.is_pointer() {
+            self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                reason: format!("getelementptr base must be pointer or vector of pointers, got {:?}", base_type),
+                location: location.to_string(),
+            });
+            return;
         }
 
-        // llvm.experimental.gc.statepoint - ID, num_patch_bytes, num_call_args, flags must be constant
-        if intrinsic_name.starts_with("llvm.experimental.gc.statepoint.") {
-            if check_immarg(1) || check_immarg(2) || check_immarg(4) || check_immarg(5) {
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "immarg operand has non-immediate parameter".to_string(),
-                    location: format!("call to {}", intrinsic_name),
+        // All index operands must be integers or vectors of integers, and
+        // vector operands must agree on their length
+        let mut lanes = base_lanes;
+        for (i, operand) in operands.iter().enumerate().skip(1) {
+            let idx_type = operand.get_type();
+            if !self.is_integer_or_vector_of_integers(idx_type) {
+                self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                    reason: format!("getelementptr index {} must be integer or vector of integers, got {:?}", i - 1, idx_type),
+                    location: location.to_string(),
                 });
+                return;
+            }
+            match (lanes, idx_type.vector_info()) {
+                (Some(expected), Some((_, size))) if expected != size => {
+                    self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                        reason: format!("getelementptr vector operands must have the same number of elements ({} and {})",
+                            expected, size),
+                        location: location.to_string(),
+                    });
+                    return;
+                }
+                (None, Some((_, size))) => lanes = Some(size),
+                _ => {}
             }
         }
 
-        // llvm.experimental.patchpoint - ID, num_bytes, num_args must be constant
-        if intrinsic_name.starts_with("llvm.experimental.patchpoint.") {
-            if check_immarg(1) || check_immarg(2) || check_immarg(4) {
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "immarg operand has non-immediate parameter".to_string(),
-                    location: format!("call to {}", intrinsic_name),
+        if let Some(source_type) = inst.gep_source_type() {
+            // GEP cannot target structures containing scalable vectors
+            if self.contains_scalable_type(source_type) {
+                self.report(Rule::Instruction, VerificationError::InvalidInstruction {
+                    reason: "getelementptr cannot target structure that contains scalable vector type".to_string(),
+                    location: location.to_string(),
                 });
             }
+            self.verify_gep_indices(source_type, &operands[1..], location);
         }
 
-        // llvm.hwasan.check.memaccess - access_info must be constant
-        if intrinsic_name.starts_with("llvm.hwasan.check.memaccess") {
-            if check_immarg(3) { // access_info parameter
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "immarg operand has non-immediate parameter".to_string(),
-                    location: format!("call to {}", intrinsic_name),
+        if let Some(result) = inst.result() {
+            let result_type = result.get_type();
+            let (result_elem, result_lanes) = element_and_lanes(result_type);
+            let result_ok = result_elem.is_pointer()
+                && result_lanes == lanes
+                && result_elem.address_space() == base_elem.address_space();
+            if !result_ok {
+                let space = base_elem.address_space().unwrap_or(0);
+                let pointer = if space == 0 { "ptr".to_string() } else { format!("ptr addrspace({})", space) };
+                self.report(Rule::OperandTypes, VerificationError::TypeMismatch {
+                    expected: match lanes {
+                        Some(n) => format!("<{} x {}>", n, pointer),
+                        None => pointer,
+                    },
+                    found: format!("{:?}", result_type),
+                    location: format!("{} result", location),
                 });
             }
         }
+    }
 
-        // llvm.eh.sjlj.callsite - callsite index must be constant
-        if intrinsic_name.starts_with("llvm.eh.sjlj.callsite") {
-            if check_immarg(1) {
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "immarg operand has non-immediate parameter".to_string(),
-                    location: format!("call to {}", intrinsic_name),
+    /// Walk the source type of a getelementptr along its indices. The first
+    /// index steps over the base pointer; the rest index into arrays and
+    /// vectors with any integer, and into structs with constant i32 field
+    /// numbers. Indexing into anything else, such as a pointer field, is
+    /// invalid.
+    fn verify_gep_indices(&mut self, source_type: &Type, indices: &[Value], location: &str) {
+        let mut current = source_type.clone();
+        for (i, index) in indices.iter().enumerate().skip(1) {
+            if let Some(fields) = current.struct_fields() {
+                let field = gep_struct_index(index);
+                let index_is_i32 = element_and_lanes(index.get_type()).0.int_width() == Some(32);
+                match field.filter(|_| index_is_i32) {
+                    Some(field) if field >= 0 && (field as usize) < fields.len() => {
+                        current = fields[field as usize].clone();
+                    }
+                    Some(field) => {
+                        self.report(Rule::GepIndexing, VerificationError::InvalidInstruction {
+                            reason: format!("getelementptr index {} selects field {} of {:?}, which has {} fields",
+                                i, field, current, fields.len()),
+                            location: location.to_string(),
+                        });
+                        return;
+                    }
+                    None => {
+                        self.report(Rule::GepIndexing, VerificationError::InvalidInstruction {
+                            reason: format!("getelementptr index {} into {:?} must be a constant i32", i, current),
+                            location: location.to_string(),
+                        });
+                        return;
+                    }
+                }
+            } else if let Some((elem, _)) = current.array_info().or(current.vector_info()) {
+                current = elem.clone();
+            } else if current.is_integer() || current.is_float() || current.is_pointer() {
+                self.report(Rule::GepIndexing, VerificationError::InvalidInstruction {
+                    reason: "invalid getelementptr indices".to_string(),
+                    location: location.to_string(),
                 });
+                return;
+            } else {
+                // Opaque and other types are not indexed further
+                return;
             }
         }
     }
 
-    /// Verify llvm.vector.reduce.* intrinsics
-    fn verify_intrinsic_vector_reduce(&mut self, inst: &Instruction, intrinsic_name: &str, operands: &[Value]) {
-        // Vector reduce intrinsics have format: llvm.vector.reduce.<op>.<type>
-        // where <op> is: add, mul, and, or, xor, smax, smin, umax, umin, fmax, fmin, fadd, fmul
-
-        // Determine expected types based on operation
-        let is_int_op = intrinsic_name.contains(".add.") || intrinsic_name.contains(".mul.") ||
-                        intrinsic_name.contains(".and.") || intrinsic_name.contains(".or.") ||
-                        intrinsic_name.contains(".xor.") || intrinsic_name.contains(".smax.") ||
-                        intrinsic_name.contains(".smin.") || intrinsic_name.contains(".umax.") ||
-                        intrinsic_name.contains(".umin.");
-        let is_fp_op = intrinsic_name.contains(".fmax.") || intrinsic_name.contains(".fmin.") ||
-                       intrinsic_name.contains(".fadd.") || intrinsic_name.contains(".fmul.");
-
+    /// Verify an atomicrmw: a pointer operand, a value of the type class
+    /// the operation works on, and a result of the value's type
+    fn verify_atomicrmw_types(&mut self, inst: &Instruction, location: &str) {
+        let operands = inst.operands();
         if operands.len() < 2 {
-            return; // Not enough operands, skip
-        }
-
-        // For fadd/fmul, first operand is start value (scalar), second is vector
-        // For others, first operand is function, second is vector
-        let vec_arg_idx = if intrinsic_name.contains(".fadd.") || intrinsic_name.contains(".fmul.") {
-            2 // operands[0] = function, operands[1] = start value, operands[2] = vector
-        } else {
-            1 // operands[0] = function, operands[1] = vector
-        };
-
-        if operands.len() <= vec_arg_idx {
             return;
         }
-
-        let vec_arg = &operands[vec_arg_idx];
-        let vec_type = vec_arg.get_type();
-
-        // Argument must be a vector
-        if !vec_type.is_vector() {
-            self.errors.push(VerificationError::InvalidInstruction {
-                reason: "Intrinsic has incorrect argument type!".to_string(),
-                location: format!("call to {}", intrinsic_name),
+        if !operands[0].get_type().is_pointer() {
+            self.report(Rule::Atomic, VerificationError::InvalidInstruction {
+                reason: format!("atomicrmw pointer operand must be a pointer, got {:?}", operands[0].get_type()),
+                location: location.to_string(),
             });
-            return;
         }
 
-        // Check element type matches operation type
-        if let Some((elem_type, _)) = vec_type.vector_info() {
-            if is_int_op && !elem_type.is_integer() {
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "Intrinsic has incorrect argument type!".to_string(),
-                    location: format!("call to {}", intrinsic_name),
-                });
-            } else if is_fp_op && !elem_type.is_float() {
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "Intrinsic has incorrect argument type!".to_string(),
-                    location: format!("call to {}", intrinsic_name),
-                });
-            }
-
-            // For fadd/fmul with start value, check start value type matches element type
-            if (intrinsic_name.contains(".fadd.") || intrinsic_name.contains(".fmul.")) && operands.len() >= 3 {
-                let start_val = &operands[1];
-                let start_type = start_val.get_type();
-                if *start_type != *elem_type {
-                    self.errors.push(VerificationError::InvalidInstruction {
-                        reason: "Intrinsic has incorrect argument type!".to_string(),
-                        location: format!("call to {}", intrinsic_name),
-                    });
+        let value_type = operands[1].get_type();
+        if let Some(operation) = inst.rmw_operation() {
+            let reason = match operation {
+                AtomicRMWBinOp::Xchg => {
+                    let (elem, lanes) = element_and_lanes(value_type);
+                    let valid = elem.is_integer() || elem.is_pointer() || (elem.is_float() && lanes.is_none());
+                    (!valid).then_some("an integer, floating point, or pointer type")
                 }
-            }
-
-            // Check return type matches element type
-            if let Some(result) = inst.result() {
-                let result_type = result.get_type();
-                if *result_type != *elem_type {
-                    self.errors.push(VerificationError::InvalidInstruction {
-                        reason: "Intrinsic has incorrect return type!".to_string(),
-                        location: format!("call to {}", intrinsic_name),
-                    });
+                op if op.is_floating_point() => {
+                    (!self.is_float_or_vector_of_floats(value_type)).then_some("a floating point type")
                 }
+                _ => (!self.is_integer_or_vector_of_integers(value_type)).then_some("an integer"),
+            };
+            if let Some(expected) = reason {
+                self.report(Rule::Atomic, VerificationError::InvalidInstruction {
+                    reason: format!("atomicrmw {} operand must be {}, got {:?}", operation.name(), expected, value_type),
+                    location: location.to_string(),
+                });
             }
         }
-    }
-
-    /// Verify llvm.is.fpclass intrinsic
-    fn verify_intrinsic_is_fpclass(&mut self, _inst: &Instruction, intrinsic_name: &str, operands: &[Value]) {
-        if operands.len() < 3 {
-            return; // Not enough operands
-        }
 
-        // operands[0] = function, operands[1] = value, operands[2] = test mask
-        let mask_operand = &operands[2];
-
-        // Test mask must be a constant integer
-        if let Some(mask_val) = mask_operand.as_const_int() {
-            // Valid mask bits are 0-9 (values 0-1023)
-            // Bit 10 and higher are invalid
-            // Also, -1 (all bits set) is specifically invalid
-            if mask_val < 0 || mask_val >= 1024 {
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "unsupported bits for llvm.is.fpclass test mask".to_string(),
-                    location: format!("call to {}", intrinsic_name),
-                });
-            }
+        if let Some(result) = inst.result() {
+            self.verify_result_type(result.get_type(), value_type, location);
         }
-        // Note: If mask is not constant, parser should have caught it as immarg violation
     }
 
-    /// Verify saturating arithmetic intrinsics
-    fn verify_intrinsic_sat(&mut self, inst: &Instruction, intrinsic_name: &str, operands: &[Value]) {
+    /// Verify a cmpxchg: a pointer operand, compare and new values of one
+    /// integer or pointer type, and a `{ type, i1 }` result
+    fn verify_cmpxchg_types(&mut self, inst: &Instruction, location: &str) {
+        let operands = inst.operands();
         if operands.len() < 3 {
-            return; // Not enough operands
+            return;
         }
-
-        // operands[0] = function, operands[1] = arg1, operands[2] = arg2
-        let arg1_type = operands[1].get_type();
-        let arg2_type = operands[2].get_type();
-
-        // Both arguments must be integers or vectors of integers
-        let arg1_valid = arg1_type.is_integer() ||
-                        (arg1_type.is_vector() && arg1_type.vector_info().map_or(false, |(e, _)| e.is_integer()));
-        let arg2_valid = arg2_type.is_integer() ||
-                        (arg2_type.is_vector() && arg2_type.vector_info().map_or(false, |(e, _)| e.is_integer()));
-
-        if !arg1_valid || !arg2_valid {
-            self.errors.push(VerificationError::InvalidInstruction {
-                reason: "Intrinsic has incorrect argument type!".to_string(),
-                location: format!("call to {}", intrinsic_name),
+        if !operands[0].get_type().is_pointer() {
+            self.report(Rule::Atomic, VerificationError::InvalidInstruction {
+                reason: format!("cmpxchg pointer operand must be a pointer, got {:?}", operands[0].get_type()),
+                location: location.to_string(),
             });
         }
 
-        // Both arguments must have same type
-        if *arg1_type != *arg2_type {
-            self.errors.push(VerificationError::InvalidInstruction {
-                reason: "Intrinsic has incorrect argument type!".to_string(),
-                location: format!("call to {}", intrinsic_name),
+        let cmp_type = operands[1].get_type();
+        let new_type = operands[2].get_type();
+        if !cmp_type.is_integer() && !cmp_type.is_pointer() {
+            self.report(Rule::Atomic, VerificationError::InvalidInstruction {
+                reason: format!("cmpxchg operand must have integer or pointer type, got {:?}", cmp_type),
+                location: location.to_string(),
+            });
+        }
+        if cmp_type != new_type && !(cmp_type.is_pointer() && new_type.is_pointer()) {
+            self.report(Rule::OperandTypes, VerificationError::TypeMismatch {
+                expected: format!("{:?}", cmp_type),
+                found: format!("{:?}", new_type),
+                location: format!("{} new value", location),
             });
         }
 
-        // Return type must match argument types
-        if let Some(result) = inst.result() {
-            let result_type = result.get_type();
-            let result_valid = result_type.is_integer() ||
-                              (result_type.is_vector() && result_type.vector_info().map_or(false, |(e, _)| e.is_integer()));
-
-            if !result_valid {
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "Intrinsic has incorrect return type!".to_string(),
-                    location: format!("call to {}", intrinsic_name),
-                });
-            } else if *result_type != *arg1_type {
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "Intrinsic has incorrect return type!".to_string(),
-                    location: format!("call to {}", intrinsic_name),
+        if let Some(fields) = inst.result().and_then(|result| result.get_type().struct_fields()) {
+            let result_ok = fields.len() == 2
+                && (fields[0] == *cmp_type || (fields[0].is_pointer() && cmp_type.is_pointer()))
+                && fields[1].int_width() == Some(1);
+            if !result_ok {
+                self.report(Rule::OperandTypes, VerificationError::TypeMismatch {
+                    expected: format!("{{ {:?}, i1 }}", cmp_type),
+                    found: format!("{:?}", fields),
+                    location: format!("{} result", location),
                 });
             }
         }
     }
 
-    /// Verify llvm.vp.* (vector predication) intrinsics
-    fn verify_intrinsic_vp(&mut self, inst: &Instruction, intrinsic_name: &str, operands: &[Value]) {
-        // llvm.vp.fptosi, llvm.vp.fptoui, llvm.vp.sitofp, llvm.vp.uitofp - cast intrinsics
-        // VP cast intrinsics: first argument and result vector lengths must be equal
-        if intrinsic_name.starts_with("llvm.vp.fptosi.") ||
-           intrinsic_name.starts_with("llvm.vp.fptoui.") ||
-           intrinsic_name.starts_with("llvm.vp.sitofp.") ||
-           intrinsic_name.starts_with("llvm.vp.uitofp.") {
+    /// Verify alignment constraints for instructions
+    fn verify_instruction_alignment(&mut self, inst: &Instruction, location: &str) {
+        // Maximum alignment in LLVM is 2^32 bytes
+        const MAX_ALIGNMENT: u64 = 1u64 << 32; // 4294967296
 
-            if operands.len() < 2 {
-                return;
+        if let Some(alignment) = inst.alignment() {
+            if alignment > MAX_ALIGNMENT {
+                self.report(Rule::Alignment, VerificationError::InvalidInstruction {
+                    reason: format!("alignment is larger than the maximum supported by LLVM (2^32)"),
+                    location: location.to_string(),
+                });
             }
 
-            let src_type = operands[1].get_type(); // operands[0] = function, operands[1] = source
-
-            if let Some(result) = inst.result() {
-                let dst_type = result.get_type();
+            // Also check that alignment is a power of 2
+            if alignment > 0 && !alignment.is_power_of_two() {
+                self.report(Rule::Alignment, VerificationError::InvalidInstruction {
+                    reason: format!("alignment must be a power of 2"),
+                    location: location.to_string(),
+                });
+            }
+        }
+    }
 
-                // Both must be vectors
-                if src_type.is_vector() && dst_type.is_vector() {
-                    if let (Some((_, src_len)), Some((_, dst_len))) = (src_type.vector_info(), dst_type.vector_info()) {
-                        if src_len != dst_len {
-                            self.errors.push(VerificationError::InvalidInstruction {
-                                reason: "VP cast intrinsic first argument and result vector lengths must be equal".to_string(),
-                                location: format!("call to {}", intrinsic_name),
+    /// Verify metadata attachments on instructions
+    fn verify_instruction_metadata(&mut self, inst: &Instruction, location: &str) {
+        for md_name in inst.metadata_attachments() {
+            match md_name.as_str() {
+                "align" => {
+                    // align metadata only applies to load instructions
+                    if inst.opcode() != Opcode::Load {
+                        self.report(Rule::InstructionMetadata, VerificationError::InvalidMetadata {
+                            reason: "align applies only to load instructions".to_string(),
+                            location: location.to_string(),
+                        });
+                    }
+                    // Additional check: align only applies to pointer types
+                    // This would require checking the loaded type
+                }
+                "llvm.access.group" | "parallel_loop_access" => {
+                    // Access group metadata must be used on memory operations
+                    match inst.opcode() {
+                        Opcode::Load | Opcode::Store | Opcode::Call | Opcode::Invoke => {
+                            // Valid usage
+                        }
+                        _ => {
+                            self.report(Rule::InstructionMetadata, VerificationError::InvalidMetadata {
+                                reason: format!("{} metadata can only be used on memory operations", md_name),
+                                location: location.to_string(),
+                            });
+                        }
+                    }
+                }
+                "nontemporal" => {
+                    // nontemporal metadata only on load/store
+                    match inst.opcode() {
+                        Opcode::Load | Opcode::Store => { /* Valid */ }
+                        _ => {
+                            self.report(Rule::InstructionMetadata, VerificationError::InvalidMetadata {
+                                reason: "nontemporal metadata can only be used on load/store".to_string(),
+                                location: location.to_string(),
+                            });
+                        }
+                    }
+                }
+                "invariant.load" => {
+                    // invariant.load only on load instructions
+                    if inst.opcode() != Opcode::Load {
+                        self.report(Rule::InstructionMetadata, VerificationError::InvalidMetadata {
+                            reason: "invariant.load metadata can only be used on load instructions".to_string(),
+                            location: location.to_string(),
+                        });
+                    }
+                }
+                "nonnull" => {
+                    // nonnull metadata only on load instructions
+                    if inst.opcode() != Opcode::Load {
+                        self.report(Rule::InstructionMetadata, VerificationError::InvalidMetadata {
+                            reason: "nonnull metadata can only be used on load instructions".to_string(),
+                            location: location.to_string(),
+                        });
+                    }
+                }
+                "range" => {
+                    // range metadata only on load/call/invoke
+                    match inst.opcode() {
+                        Opcode::Load | Opcode::Call | Opcode::Invoke => { /* Valid */ }
+                        _ => {
+                            self.report(Rule::InstructionMetadata, VerificationError::InvalidMetadata {
+                                reason: "range metadata can only be used on load/call/invoke".to_string(),
+                                location: location.to_string(),
+                            });
+                        }
+                    }
+                }
+                "noalias" | "alias.scope" => {
+                    // Alias metadata on memory operations
+                    match inst.opcode() {
+                        Opcode::Load | Opcode::Store | Opcode::Call | Opcode::Invoke => { /* Valid */ }
+                        _ => {
+                            self.report(Rule::InstructionMetadata, VerificationError::InvalidMetadata {
+                                reason: format!("{} metadata can only be used on memory operations", md_name),
+                                location: location.to_string(),
+                            });
+                        }
+                    }
+                }
+                "tbaa" | "tbaa.struct" => {
+                    // TBAA metadata on memory operations and VAArg
+                    match inst.opcode() {
+                        Opcode::Load | Opcode::Store | Opcode::Call | Opcode::Invoke |
+                        Opcode::AtomicCmpXchg | Opcode::AtomicRMW | Opcode::VAArg => { /* Valid */ }
+                        _ => {
+                            self.report(Rule::InstructionMetadata, VerificationError::InvalidMetadata {
+                                reason: "tbaa metadata can only be used on memory operations".to_string(),
+                                location: location.to_string(),
                             });
                         }
                     }
                 }
+                // Debug metadata is allowed on any instruction
+                "dbg" | "DILocation" | "DILocalVariable" | "DIExpression" => {
+                    // Always valid
+                }
+                // Profile and branch metadata
+                "prof" | "unpredictable" => {
+                    // These can appear on branches, calls, etc.
+                    // Generally valid on most instructions
+                }
+                _ => {
+                    // Unknown metadata - don't error, as there may be custom metadata
+                }
             }
         }
-
-        // llvm.vp.fcmp and llvm.vp.icmp - comparison intrinsics
-        // These require metadata predicate validation which we can't do without metadata access
-        // Skipping for now
     }
 
-    /// Verify llvm.bswap intrinsic
-    fn verify_intrinsic_bswap(&mut self, inst: &Instruction, intrinsic_name: &str, operands: &[Value]) {
-        if operands.len() < 2 {
-            return; // operands[0] = function, operands[1] = value
-        }
-
-        let arg_type = operands[1].get_type();
+    /// Verify a call to an intrinsic: its signature, mangled name and
+    /// immargs against the intrinsic table, then constraints particular to
+    /// the intrinsic. Names missing from the table are not checked.
+    fn verify_intrinsic_call(&mut self, inst: &Instruction, intrinsic_name: &str) {
+        let Some(intrinsic) = Intrinsic::lookup(intrinsic_name) else {
+            return;
+        };
+        let info = intrinsic.info();
+        let operands = inst.operands();
 
-        // Get the bit width for the type
-        let check_bit_width = |ty: &Type| -> Option<u32> {
-            if let Some(width) = ty.int_width() {
-                Some(width)
-            } else if ty.is_vector() {
-                if let Some((elem, _)) = ty.vector_info() {
-                    elem.int_width()
-                } else {
-                    None
+        let callee_type = operands[0].get_type();
+        let function_type = callee_type.pointee_type().filter(|ty| ty.is_function()).unwrap_or(callee_type);
+        if let Some((ret, params, varargs)) = function_type.function_info() {
+            match info.match_signature(&ret, &params, varargs) {
+                // A bare name leaves the overload types to the signature
+                Ok(overload_types) => {
+                    let mangled = info.mangled_name(&overload_types);
+                    if intrinsic_name != info.name && intrinsic_name != mangled {
+                        self.report_intrinsic(Rule::IntrinsicSignature, intrinsic_name,
+                            format!("Intrinsic name not mangled correctly for type arguments! Should be: {}", mangled));
+                    }
+                }
+                Err(mismatch) => {
+                    let reason = match mismatch {
+                        SignatureMismatch::ReturnType => "Intrinsic has incorrect return type!",
+                        SignatureMismatch::ArgumentType => "Intrinsic has incorrect argument type!",
+                        SignatureMismatch::MissingVarArgs => "Callsite was not defined with variable arguments!",
+                        SignatureMismatch::UnexpectedVarArgs => "Intrinsic was not defined with variable arguments!",
+                    };
+                    self.report_intrinsic(Rule::IntrinsicSignature, intrinsic_name, reason.to_string());
+                    return;
                 }
-            } else {
-                None
             }
-        };
+        }
 
-        if let Some(bit_width) = check_bit_width(&arg_type) {
-            // bswap must operate on types with even number of bytes
-            // i8 = 8 bits = 1 byte (odd number of bytes) - invalid
-            // i16 = 16 bits = 2 bytes (even) - valid
-            // i12 = 12 bits = 1.5 bytes - invalid (not even)
-            if bit_width % 16 != 0 {
-                self.errors.push(VerificationError::InvalidInstruction {
-                    reason: "bswap must be an even number of bytes".to_string(),
-                    location: format!("call to {}", intrinsic_name),
-                });
-            }
+        // immarg rejects variables, undef, poison, zeroinitializer, aggregates and constant expressions
+        if info.immargs.iter().any(|&index| operands.get(index + 1).is_some_and(|arg| !arg.is_immediate())) {
+            self.report_intrinsic(Rule::IntrinsicImmArg, intrinsic_name, "immarg operand has non-immediate parameter".to_string());
+        }
+
+        // operands[0] is the callee, so arguments start at operands[1]
+        let arg_type = |index: usize| operands.get(index + 1).map(|arg| arg.get_type().clone());
+        let result_type = inst.result().map(|result| result.get_type().clone());
+        let reason = match intrinsic {
+            Intrinsic::ExperimentalDeoptimize | Intrinsic::ExperimentalGuard => {
+                let deopt_bundles = inst.operand_bundles().iter().filter(|b| b.tag == "deopt").count();
+                let name = if intrinsic == Intrinsic::ExperimentalGuard { "guard" } else { "experimental_deoptimize" };
+                (deopt_bundles != 1).then(|| format!("{} must have exactly one \"deopt\" operand bundle", name))
+            }
+            Intrinsic::VaStart => (!self.current_function_is_varargs)
+                .then(|| "va_start called in a non-varargs function".to_string()),
+            Intrinsic::Bswap => arg_type(0)
+                .and_then(|ty| ty.vector_info().map(|(element, _)| element.clone()).unwrap_or(ty).int_width())
+                .filter(|bits| bits % 16 != 0)
+                .map(|_| "bswap must be an even number of bytes".to_string()),
+            Intrinsic::StepVector => result_type
+                .and_then(|ty| ty.vector_info().map(|(element, _)| element.int_width()))
+                .filter(|bits| bits.is_none_or(|bits| bits < 8))
+                .map(|_| "stepvector only supported for vectors of integers with a bitwidth of at least 8".to_string()),
+            Intrinsic::MemCpy | Intrinsic::MemMove | Intrinsic::MemSet | Intrinsic::MemCpyInline | Intrinsic::MemSetInline
+            | Intrinsic::MemCpyElementUnorderedAtomic | Intrinsic::MemMoveElementUnorderedAtomic
+            | Intrinsic::MemSetElementUnorderedAtomic | Intrinsic::ExperimentalMemSetPattern => inst.alignment()
+                .filter(|alignment| !alignment.is_power_of_two())
+                .map(|_| "alignment is not a power of two".to_string()),
+            Intrinsic::ExperimentalGCRelocate => {
+                let token = operands.get(1).and_then(|token| token.name());
+                if matches!(token, Some("none") | Some("undef")) {
+                    self.report(Rule::IntrinsicOperands, VerificationError::InvalidInstruction {
+                        reason: "gc relocate is incorrectly tied to the statepoint".to_string(),
+                        location: "(undef, undef)".to_string(),
+                    });
+                }
+                result_type.filter(|ty| !ty.is_pointer()).map(|_| "gc.relocate: result must be a pointer".to_string())
+            }
+            Intrinsic::IsFpClass => operands.get(2).and_then(|mask| mask.as_const_int())
+                .filter(|mask| !(0..1024).contains(mask))
+                .map(|_| "unsupported bits for llvm.is.fpclass test mask".to_string()),
+            Intrinsic::VpFpToSi | Intrinsic::VpFpToUi | Intrinsic::VpSiToFp | Intrinsic::VpUiToFp => {
+                let length = |ty: Option<Type>| ty.and_then(|ty| ty.vector_info().map(|(_, size)| size));
+                (length(arg_type(0)) != length(result_type))
+                    .then(|| "VP cast intrinsic first argument and result vector lengths must be equal".to_string())
+            }
+            Intrinsic::VectorExtract => {
+                let element = |ty: Option<Type>| ty.and_then(|ty| ty.vector_info().map(|(element, _)| element.clone()));
+                (element(arg_type(0)) != element(result_type))
+                    .then(|| "vector_extract result must have the same element type as the input vector.".to_string())
+            }
+            Intrinsic::VectorInsert => {
+                let element = |ty: Option<Type>| ty.and_then(|ty| ty.vector_info().map(|(element, _)| element.clone()));
+                (element(arg_type(0)) != element(arg_type(1)))
+                    .then(|| "vector_insert parameters must have the same element type.".to_string())
+            }
+            Intrinsic::VectorSplice => {
+                let length = arg_type(0).and_then(|ty| ty.vector_info().map(|(_, size)| size as i64));
+                let index = operands.get(3).and_then(|index| index.const_int_value());
+                match (length, index) {
+                    (Some(length), Some(index)) if index < -length || index >= length => Some(
+                        "The splice index exceeds the range [-VL, VL-1] where VL is the known minimum number of elements in the vector"
+                            .to_string()),
+                    _ => None,
+                }
+            }
+            _ => None,
+        };
+        if let Some(reason) = reason {
+            self.report_intrinsic(Rule::IntrinsicOperands, intrinsic_name, reason);
         }
     }
 
+    fn report_intrinsic(&mut self, rule: Rule, intrinsic_name: &str, reason: String) {
+        self.report(rule, VerificationError::InvalidInstruction {
+            reason,
+            location: format!("call to {}", intrinsic_name),
+        });
+    }
+
     /// Verify allockind attribute
     fn verify_allockind_attribute(&mut self, kinds: &[String], fn_name: &str) {
         let mut has_alloc = false;
@@ -4802,7 +4323,7 @@ impl<'a> Verifier<'a> {
                 "uninitialized" => has_uninitialized = true,
                 "aligned" => has_aligned = true,
                 _ => {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::AllocAttributes, VerificationError::InvalidInstruction {
                         reason: format!("'allockind' has unknown value '{}'", kind),
                         location: format!("function {}", fn_name),
                     });
@@ -4813,7 +4334,7 @@ impl<'a> Verifier<'a> {
         // Must have exactly one of alloc, realloc, or free
         let primary_count = [has_alloc, has_realloc, has_free].iter().filter(|&&x| x).count();
         if primary_count != 1 {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::AllocAttributes, VerificationError::InvalidInstruction {
                 reason: "'allockind()' requires exactly one of alloc, realloc, and free".to_string(),
                 location: format!("function {}", fn_name),
             });
@@ -4821,7 +4342,7 @@ impl<'a> Verifier<'a> {
 
         // free doesn't allow uninitialized, zeroed, or aligned
         if has_free && (has_uninitialized || has_zeroed || has_aligned) {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::AllocAttributes, VerificationError::InvalidInstruction {
                 reason: "'allockind(\"free\")' doesn't allow uninitialized, zeroed, or aligned modifiers".to_string(),
                 location: format!("function {}", fn_name),
             });
@@ -4829,7 +4350,7 @@ impl<'a> Verifier<'a> {
 
         // Can't be both zeroed and uninitialized
         if has_zeroed && has_uninitialized {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::AllocAttributes, VerificationError::InvalidInstruction {
                 reason: "'allockind()' can't be both zeroed and uninitialized".to_string(),
                 location: format!("function {}", fn_name),
             });
@@ -4841,7 +4362,7 @@ impl<'a> Verifier<'a> {
         // Validate frame-pointer attribute
         if let Some(value) = string_attrs.get("frame-pointer") {
             if !matches!(value.as_str(), "all" | "non-leaf" | "none") {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::StringAttributes, VerificationError::InvalidInstruction {
                     reason: format!("invalid value for 'frame-pointer' attribute: {}", value),
                     location: format!("function {}", fn_name),
                 });
@@ -4851,7 +4372,7 @@ impl<'a> Verifier<'a> {
         // Validate no-jump-tables attribute (must be "true" or "false")
         if let Some(value) = string_attrs.get("no-jump-tables") {
             if !matches!(value.as_str(), "true" | "false") {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::StringAttributes, VerificationError::InvalidInstruction {
                     reason: format!("invalid value for 'no-jump-tables' attribute: {}", value),
                     location: format!("function {}", fn_name),
                 });
@@ -4863,14 +4384,14 @@ impl<'a> Verifier<'a> {
             if !value.is_empty() {
                 let parts: Vec<&str> = value.split(',').collect();
                 if parts.len() > 2 {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::StringAttributes, VerificationError::InvalidInstruction {
                         reason: format!("invalid value for 'denormal-fp-math' attribute: {}", value),
                         location: format!("function {}", fn_name),
                     });
                 } else {
                     for part in parts {
                         if !matches!(part.trim(), "ieee" | "preserve-sign" | "positive-zero" | "dynamic") {
-                            self.errors.push(VerificationError::InvalidInstruction {
+                            self.report(Rule::StringAttributes, VerificationError::InvalidInstruction {
                                 reason: format!("invalid value for 'denormal-fp-math' attribute: {}", value),
                                 location: format!("function {}", fn_name),
                             });
@@ -4886,14 +4407,14 @@ impl<'a> Verifier<'a> {
             if !value.is_empty() {
                 let parts: Vec<&str> = value.split(',').collect();
                 if parts.len() > 2 {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::StringAttributes, VerificationError::InvalidInstruction {
                         reason: format!("invalid value for 'denormal-fp-math-f32' attribute: {}", value),
                         location: format!("function {}", fn_name),
                     });
                 } else {
                     for part in parts {
                         if !matches!(part.trim(), "ieee" | "preserve-sign" | "positive-zero" | "dynamic") {
-                            self.errors.push(VerificationError::InvalidInstruction {
+                            self.report(Rule::StringAttributes, VerificationError::InvalidInstruction {
                                 reason: format!("invalid value for 'denormal-fp-math-f32' attribute: {}", value),
                                 location: format!("function {}", fn_name),
                             });
@@ -4907,7 +4428,7 @@ impl<'a> Verifier<'a> {
         // Validate sign-return-address attribute
         if let Some(value) = string_attrs.get("sign-return-address") {
             if !matches!(value.as_str(), "none" | "non-leaf" | "all") {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::StringAttributes, VerificationError::InvalidInstruction {
                     reason: format!("invalid value for 'sign-return-address' attribute: {}", value),
                     location: format!("function {}", fn_name),
                 });
@@ -4917,14 +4438,14 @@ impl<'a> Verifier<'a> {
         // Validate sign-return-address-key attribute
         if let Some(value) = string_attrs.get("sign-return-address-key") {
             if !matches!(value.as_str(), "a_key" | "b_key") {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::StringAttributes, VerificationError::InvalidInstruction {
                     reason: format!("invalid value for 'sign-return-address-key' attribute: {}", value),
                     location: format!("function {}", fn_name),
                 });
             }
             // Must have sign-return-address if sign-return-address-key is present
             if !string_attrs.contains_key("sign-return-address") {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::StringAttributes, VerificationError::InvalidInstruction {
                     reason: "'sign-return-address-key' present without `sign-return-address`".to_string(),
                     location: format!("function {}", fn_name),
                 });
@@ -4934,7 +4455,7 @@ impl<'a> Verifier<'a> {
         // Validate branch-target-enforcement attribute
         if let Some(value) = string_attrs.get("branch-target-enforcement") {
             if !matches!(value.as_str(), "true" | "false") {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::StringAttributes, VerificationError::InvalidInstruction {
                     reason: format!("invalid value for 'branch-target-enforcement' attribute: {}", value),
                     location: format!("function {}", fn_name),
                 });
@@ -4944,7 +4465,7 @@ impl<'a> Verifier<'a> {
         // Validate branch-protection-pauth-lr attribute
         if let Some(value) = string_attrs.get("branch-protection-pauth-lr") {
             if !matches!(value.as_str(), "true" | "false") {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::StringAttributes, VerificationError::InvalidInstruction {
                     reason: format!("invalid value for 'branch-protection-pauth-lr' attribute: {}", value),
                     location: format!("function {}", fn_name),
                 });
@@ -4954,7 +4475,7 @@ impl<'a> Verifier<'a> {
         // Validate guarded-control-stack attribute
         if let Some(value) = string_attrs.get("guarded-control-stack") {
             if !matches!(value.as_str(), "true" | "false") {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::StringAttributes, VerificationError::InvalidInstruction {
                     reason: format!("invalid value for 'guarded-control-stack' attribute: {}", value),
                     location: format!("function {}", fn_name),
                 });
@@ -4969,7 +4490,7 @@ impl<'a> Verifier<'a> {
 
         // Must not be empty
         if variant_name.is_empty() {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::AllocAttributes, VerificationError::InvalidInstruction {
                 reason: "'alloc-variant-zeroed' must not be empty".to_string(),
                 location: format!("function {}", fn_name),
             });
@@ -4995,7 +4516,7 @@ impl<'a> Verifier<'a> {
             let variant_family = variant_attrs.string_attributes.get("alloc-family");
 
             if this_family != variant_family {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::AllocAttributes, VerificationError::InvalidInstruction {
                     reason: "'alloc-variant-zeroed' must name a function belonging to the same 'alloc-family'".to_string(),
                     location: format!("function {}", fn_name),
                 });
@@ -5007,7 +4528,7 @@ impl<'a> Verifier<'a> {
                 .unwrap_or(false);
 
             if !has_zeroed {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::AllocAttributes, VerificationError::InvalidInstruction {
                     reason: "'alloc-variant-zeroed' must name a function with 'allockind(\"zeroed\")'".to_string(),
                     location: format!("function {}", fn_name),
                 });
@@ -5021,7 +4542,7 @@ impl<'a> Verifier<'a> {
                 (this_type.function_info(), variant_type.function_info()) {
                 if ret1 != ret2 || params1.len() != params2.len() || var1 != var2 ||
                    !params1.iter().zip(params2.iter()).all(|(p1, p2)| p1 == p2) {
-                    self.errors.push(VerificationError::InvalidInstruction {
+                    self.report(Rule::AllocAttributes, VerificationError::InvalidInstruction {
                         reason: "'alloc-variant-zeroed' must name a function with the same signature".to_string(),
                         location: format!("function {}", fn_name),
                     });
@@ -5046,12 +4567,12 @@ impl<'a> Verifier<'a> {
         if !indices.is_empty() {
             let elem_idx = indices[0];
             if elem_idx >= param_types.len() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::AllocAttributes, VerificationError::InvalidInstruction {
                     reason: "'allocsize' element size argument is out of bounds".to_string(),
                     location: format!("function {}", fn_name),
                 });
             } else if !param_types[elem_idx].is_integer() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::AllocAttributes, VerificationError::InvalidInstruction {
                     reason: "'allocsize' element size argument must refer to an integer parameter".to_string(),
                     location: format!("function {}", fn_name),
                 });
@@ -5062,12 +4583,12 @@ impl<'a> Verifier<'a> {
         if indices.len() > 1 {
             let count_idx = indices[1];
             if count_idx >= param_types.len() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::AllocAttributes, VerificationError::InvalidInstruction {
                     reason: "'allocsize' number of elements argument is out of bounds".to_string(),
                     location: format!("function {}", fn_name),
                 });
             } else if !param_types[count_idx].is_integer() {
-                self.errors.push(VerificationError::InvalidInstruction {
+                self.report(Rule::AllocAttributes, VerificationError::InvalidInstruction {
                     reason: "'allocsize' number of elements argument must refer to an integer parameter".to_string(),
                     location: format!("function {}", fn_name),
                 });
@@ -5076,7 +4597,7 @@ impl<'a> Verifier<'a> {
 
         // allocsize should have at most 2 indices
         if indices.len() > 2 {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::AllocAttributes, VerificationError::InvalidInstruction {
                 reason: "'allocsize' takes at most 2 arguments".to_string(),
                 location: format!("function {}", fn_name),
             });
@@ -5084,7 +4605,7 @@ impl<'a> Verifier<'a> {
 
         // allocsize indices can't be the same
         if indices.len() == 2 && indices[0] == indices[1] {
-            self.errors.push(VerificationError::InvalidInstruction {
+            self.report(Rule::AllocAttributes, VerificationError::InvalidInstruction {
                 reason: "'allocsize' indices can't refer to the same parameter".to_string(),
                 location: format!("function {}", fn_name),
             });
@@ -5092,6 +4613,263 @@ impl<'a> Verifier<'a> {
     }
 }
 
+/// Identity of a local SSA value: its name or, if unnamed, the value itself
+#[derive(PartialEq, Eq, Hash)]
+enum SsaKey {
+    Name(String),
+    Unnamed(usize),
+}
+
+impl SsaKey {
+    fn of(value: &Value) -> Option<Self> {
+        use crate::value::ValueKind;
+        if !matches!(value.kind(), ValueKind::Instruction { .. } | ValueKind::Argument { .. }) {
+            return None;
+        }
+        Some(match value.name() {
+            Some(name) if !name.is_empty() => SsaKey::Name(name.to_string()),
+            _ => SsaKey::Unnamed(value.data_ptr()),
+        })
+    }
+}
+
+/// Where a local SSA value is defined
+enum SsaSite {
+    Argument,
+    Instruction { block: usize, index: usize },
+    /// An invoke result, available only along the edge to the normal destination
+    Invoke { block: usize, normal: Option<usize> },
+}
+
+struct SsaDef {
+    site: SsaSite,
+    ty: Type,
+    location: String,
+}
+
+impl SsaSite {
+    /// Whether the value is available at instruction `index` of `block`
+    fn dominates_use(&self, block: usize, index: usize, domtree: &crate::analysis::DominatorTree) -> bool {
+        match *self {
+            SsaSite::Argument => true,
+            SsaSite::Instruction { block: def_block, index: def_index } if def_block == block => def_index < index,
+            SsaSite::Instruction { block: def_block, .. } => domtree.dominates(def_block, block),
+            SsaSite::Invoke { block: def_block, normal } => {
+                normal.is_none_or(|normal| Self::edge_dominates(def_block, normal, block, domtree))
+            }
+        }
+    }
+
+    /// Whether the value is available on the edge from `pred` into `block`
+    fn dominates_edge(&self, pred: usize, block: usize, domtree: &crate::analysis::DominatorTree) -> bool {
+        match *self {
+            SsaSite::Argument => true,
+            SsaSite::Instruction { block: def_block, .. } => domtree.dominates(def_block, pred),
+            SsaSite::Invoke { block: def_block, normal } => match normal {
+                Some(normal) if def_block == pred && normal == block => true,
+                Some(normal) => Self::edge_dominates(def_block, normal, pred, domtree),
+                None => true,
+            },
+        }
+    }
+
+    /// Whether the edge `start -> end` dominates `block`: `end` must dominate
+    /// it and be entered only through that edge or its own back edges
+    fn edge_dominates(start: usize, end: usize, block: usize, domtree: &crate::analysis::DominatorTree) -> bool {
+        if !domtree.dominates(end, block) {
+            return false;
+        }
+        let preds = domtree.cfg().predecessors(end);
+        preds.iter().filter(|&&p| p == start).count() == 1
+            && preds.iter().all(|&p| p == start || !domtree.is_reachable(p) || domtree.dominates(end, p))
+    }
+}
+
+/// The metadata nodes of a module, for resolving forward references and
+/// naming nodes in errors
+struct MetadataGraph {
+    /// Numbered nodes in definition order, followed by named metadata
+    numbered: Vec<Metadata>,
+    by_name: std::collections::HashMap<String, Metadata>,
+    names: std::collections::HashMap<usize, String>,
+}
+
+impl MetadataGraph {
+    fn new(module: &Module) -> Self {
+        use std::cmp::Ordering;
+
+        let mut entries: Vec<(String, Metadata)> = module.metadata_nodes().into_iter().collect();
+        entries.sort_by(|(a, _), (b, _)| match (a.parse::<u64>(), b.parse::<u64>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            _ => a.cmp(b),
+        });
+        Self {
+            numbered: entries.iter().map(|(_, md)| md.clone()).collect(),
+            names: entries.iter().map(|(name, md)| (md.node_id(), name.clone())).collect(),
+            by_name: entries.into_iter().collect(),
+        }
+    }
+
+    /// The node a metadata operand stands for, following forward references;
+    /// `None` if it names undefined metadata
+    fn resolve(&self, md: &Metadata) -> Option<Metadata> {
+        let mut node = md.clone();
+        for _ in 0..=self.by_name.len() {
+            match node.as_reference() {
+                Some(name) => node = self.by_name.get(name)?.clone(),
+                None => return Some(node),
+            }
+        }
+        None
+    }
+
+    fn field(&self, md: &Metadata, key: &str) -> Option<Metadata> {
+        self.resolve(md)?.get_field(key).and_then(|field| self.resolve(field))
+    }
+
+    /// Node kind such as `DISubprogram`
+    fn kind(&self, md: &Metadata) -> Option<String> {
+        self.resolve(md)?.get_name().map(str::to_string)
+    }
+
+    fn describe(&self, md: &Metadata) -> String {
+        let Some(node) = self.resolve(md) else {
+            return md.to_string();
+        };
+        match (self.names.get(&node.node_id()), node.get_name()) {
+            (Some(number), Some(kind)) => format!("!{} ({})", number, kind),
+            (Some(number), None) => format!("!{}", number),
+            (None, Some(kind)) => format!("!{}(...)", kind),
+            (None, None) => node.to_string(),
+        }
+    }
+
+    /// The nodes reached from `md` by repeatedly following `key`, stopping
+    /// before any node is revisited
+    fn chain(&self, md: &Metadata, key: &str) -> Vec<Metadata> {
+        let mut seen = HashSet::from([md.node_id()]);
+        let mut chain = Vec::new();
+        let mut current = md.clone();
+        while let Some(next) = self.field(&current, key) {
+            if !seen.insert(next.node_id()) {
+                break;
+            }
+            chain.push(next.clone());
+            current = next;
+        }
+        chain
+    }
+
+    /// The cycle, if any, that following `key` from `md` ends in
+    fn chain_cycle(&self, md: &Metadata, key: &str) -> Option<Vec<Metadata>> {
+        let mut path = vec![self.resolve(md)?];
+        while let Some(next) = self.field(path.last()?, key) {
+            if let Some(start) = path.iter().position(|node| node.node_id() == next.node_id()) {
+                return Some(path.split_off(start));
+            }
+            path.push(next);
+        }
+        None
+    }
+}
+
+/// Classes of scalar types that casts convert between
+#[derive(Clone, Copy)]
+enum TypeClass {
+    Integer,
+    Float,
+    Pointer,
+}
+
+impl TypeClass {
+    /// Whether `ty` is of this class or a vector of it
+    fn contains(self, ty: &Type) -> bool {
+        let elem = element_and_lanes(ty).0;
+        match self {
+            TypeClass::Integer => elem.is_integer(),
+            TypeClass::Float => elem.is_float(),
+            TypeClass::Pointer => elem.is_pointer(),
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            TypeClass::Integer => "integer or vector of integers",
+            TypeClass::Float => "floating point or vector of floats",
+            TypeClass::Pointer => "pointer or vector of pointers",
+        }
+    }
+}
+
+/// The element type and length of a vector type, or a scalar type and no
+/// length
+fn element_and_lanes(ty: &Type) -> (&Type, Option<usize>) {
+    match ty.vector_info() {
+        Some((elem, size)) => (elem, Some(size)),
+        None => (ty, None),
+    }
+}
+
+/// The range of bit widths a floating point type may have. The parser
+/// reads bfloat, x86_fp80, fp128 and ppc_fp128 as `float`, so a float
+/// spans all of their widths.
+fn float_width_range(ty: &Type) -> Option<(u64, u64)> {
+    Some(match ty.float_kind()? {
+        FloatKind::Half => (16, 16),
+        FloatKind::Float => (16, 128),
+        FloatKind::Double => (64, 64),
+    })
+}
+
+/// The range of bit widths of an integer, floating point or fixed-length
+/// vector type
+fn primitive_width_range(ty: &Type) -> Option<(u64, u64)> {
+    let (elem, lanes) = element_and_lanes(ty);
+    let (low, high) = match elem.int_width() {
+        Some(bits) => (bits as u64, bits as u64),
+        None => float_width_range(elem)?,
+    };
+    match lanes {
+        Some(0) => None,
+        Some(size) => Some((low * size as u64, high * size as u64)),
+        None => Some((low, high)),
+    }
+}
+
+/// The field a getelementptr index selects in a struct: a constant
+/// integer, or a splat of one for vector indices
+fn gep_struct_index(index: &Value) -> Option<i64> {
+    if index.is_zero_initializer() {
+        return Some(0);
+    }
+    if let Some(elements) = index.vector_elements() {
+        let first = elements.first()?.as_const_int()?;
+        return elements.iter().all(|e| e.as_const_int() == Some(first)).then_some(first);
+    }
+    index.as_const_int()
+}
+
+/// Whether a DISubprogram describes a definition rather than a declaration
+fn is_subprogram_definition(subprogram: &Metadata) -> bool {
+    let sp_flags = subprogram.get_field("spFlags");
+    sp_flags.and_then(|flags| flags.as_string()).is_some_and(|flags| flags.contains("DISPFlagDefinition"))
+        || sp_flags.and_then(|flags| flags.as_int()).is_some_and(|flags| flags & 8 != 0)
+        || subprogram.get_field("isDefinition").and_then(|flag| flag.as_int()).is_some_and(|flag| flag != 0)
+}
+
+/// Scopes that can contain locations, variables and labels
+fn is_local_scope(kind: &str) -> bool {
+    matches!(kind, "DISubprogram" | "DILexicalBlock" | "DILexicalBlockFile")
+}
+
+fn is_scope(kind: &str) -> bool {
+    is_local_scope(kind)
+        || matches!(kind, "DIFile" | "DICompileUnit" | "DINamespace" | "DIModule" | "DICommonBlock")
+        || kind.ends_with("Type")
+}
+
 impl<'a> Default for Verifier<'a> {
     fn default() -> Self {
         Self::new()
@@ -5100,34 +4878,39 @@ impl<'a> Default for Verifier<'a> {
 
 /// Verify a module and return a result
 pub fn verify_module(module: &Module) -> VerificationResult {
-    let mut verifier = Verifier::new();
-    let basic_result = verifier.verify_module(module);
-
-    // Run additional validation rules
-    let mut rules_verifier = crate::validation_rules::ValidationRules::new();
-    let rules_result = rules_verifier.validate_module(module);
+    errors_of(&verify_module_with_options(module, &VerifierOptions::default()))
+}
 
-    // Combine errors from both verifiers
-    match (basic_result, rules_result) {
-        (Ok(()), Ok(())) => Ok(()),
-        (Ok(()), Err(rule_errs)) => Err(rule_errs),
-        (Err(basic_errs), Ok(())) => Err(basic_errs),
-        (Err(mut basic_errs), Err(rule_errs)) => {
-            basic_errs.extend(rule_errs);
-            Err(basic_errs)
-        }
-    }
+/// Verify a module with both the verifier and the extended validation rules,
+/// returning the diagnostics of every rule `options` enables
+pub fn verify_module_with_options(module: &Module, options: &VerifierOptions) -> Vec<Diagnostic> {
+    let mut verifier = Verifier::with_options(options.clone());
+    let _ = verifier.verify_module(module);
+    let mut diagnostics = std::mem::take(&mut verifier.diagnostics);
+
+    let mut rules_verifier = crate::validation_rules::ValidationRules::with_options(options.clone());
+    let _ = rules_verifier.validate_module(module);
+    diagnostics.extend(rules_verifier.take_diagnostics());
+    diagnostics
 }
 
 /// Verify a function and return a result
 pub fn verify_function(function: &Function) -> VerificationResult {
     let mut verifier = Verifier::new();
     verifier.verify_function(function);
+    errors_of(&verifier.diagnostics)
+}
 
-    if verifier.errors.is_empty() {
+/// The error-severity diagnostics as a verification result
+pub(crate) fn errors_of(diagnostics: &[Diagnostic]) -> VerificationResult {
+    let errors: Vec<VerificationError> = diagnostics.iter()
+        .filter(|d| d.is_error())
+        .map(|d| d.error.clone())
+        .collect();
+    if errors.is_empty() {
         Ok(())
     } else {
-        Err(verifier.errors)
+        Err(errors)
     }
 }
 
@@ -0,0 +1,93 @@
+//! Alias Analysis Tests
+//!
+//! Alias queries over the loads and stores of parsed functions.
+
+mod common;
+
+use common::function;
+use llvm_rust::analysis::{AliasAnalysis, AliasResult, MemoryLocation};
+use llvm_rust::Instruction;
+
+/// The analysis of `@f` in `ir`, with the locations of its memory accesses
+/// in order
+fn accesses(ir: &str) -> (AliasAnalysis, Vec<MemoryLocation>) {
+    let function = function(ir);
+    let locations = function.basic_blocks().iter()
+        .flat_map(|bb| bb.instructions())
+        .filter_map(|inst: Instruction| MemoryLocation::of_instruction(&inst))
+        .collect();
+    (AliasAnalysis::new(&function), locations)
+}
+
+/// Alias result of the first two memory accesses of `body`, in a function
+/// with pointer arguments `%p` and `noalias %q`, index `%i` and a global `@g`
+fn alias(body: &str) -> AliasResult {
+    let ir = format!("@g = global [4 x i32] zeroinitializer\n\n\
+        define void @f(ptr %p, ptr noalias %q, i64 %i) {{\nentry:\n  {}\n  ret void\n}}\n", body);
+    let (aa, locations) = accesses(&ir);
+    assert_eq!(locations.len(), 2, "{}", body);
+    let result = aa.alias(&locations[0], &locations[1]);
+    assert_eq!(result, aa.alias(&locations[1], &locations[0]), "{}", body);
+    result
+}
+
+#[test]
+fn test_identified_objects() {
+    let body = "%a = alloca i32\n  %b = alloca i32\n  store i32 0, ptr %a\n  store i32 1, ptr %b";
+    assert_eq!(alias(body), AliasResult::NoAlias);
+
+    assert_eq!(alias("%a = alloca i32\n  store i32 0, ptr %a\n  store i32 1, ptr @g"), AliasResult::NoAlias);
+    assert_eq!(alias("store i32 0, ptr %q\n  store i32 1, ptr @g"), AliasResult::NoAlias);
+    assert_eq!(alias("%a = alloca i32\n  store i32 0, ptr %a\n  store i32 1, ptr %p"), AliasResult::NoAlias);
+
+    // Plain arguments may point anywhere but into this call's allocas
+    assert_eq!(alias("store i32 0, ptr %p\n  store i32 1, ptr @g"), AliasResult::MayAlias);
+    assert_eq!(alias("store i32 0, ptr %p\n  store i32 1, ptr %q"), AliasResult::MayAlias);
+}
+
+#[test]
+fn test_same_pointer() {
+    assert_eq!(alias("store i32 0, ptr %p\n  %v = load i32, ptr %p"), AliasResult::MustAlias);
+
+    let body = "%c = bitcast ptr %p to ptr\n  store i32 0, ptr %p\n  %v = load i32, ptr %c";
+    assert_eq!(alias(body), AliasResult::MustAlias);
+}
+
+#[test]
+fn test_constant_gep_offsets() {
+    let body = "%a = getelementptr [4 x i32], ptr @g, i64 0, i64 1\n  %b = getelementptr [4 x i32], ptr @g, i64 0, i64 2\n  \
+        store i32 0, ptr %a\n  store i32 1, ptr %b";
+    assert_eq!(alias(body), AliasResult::NoAlias);
+
+    let body = "%a = getelementptr i8, ptr %p, i64 4\n  %b = getelementptr i32, ptr %p, i64 1\n  \
+        store i32 0, ptr %a\n  store i32 1, ptr %b";
+    assert_eq!(alias(body), AliasResult::MustAlias);
+
+    // An i64 at offset 0 covers the i32 at offset 4
+    let body = "%b = getelementptr i32, ptr %p, i64 1\n  store i64 0, ptr %p\n  store i32 1, ptr %b";
+    assert_eq!(alias(body), AliasResult::PartialAlias);
+
+    // Fields of { i8, i32 } are at offsets 0 and 4
+    let body = "%b = getelementptr { i8, i32 }, ptr %p, i64 0, i32 1\n  store i32 0, ptr %p\n  store i32 1, ptr %b";
+    assert_eq!(alias(body), AliasResult::NoAlias);
+
+    let body = "%b = getelementptr i32, ptr %p, i64 %i\n  store i32 0, ptr %p\n  store i32 1, ptr %b";
+    assert_eq!(alias(body), AliasResult::MayAlias);
+}
+
+#[test]
+fn test_null_undef_and_access_sizes() {
+    assert_eq!(alias("store i32 0, ptr %p\n  store i32 1, ptr null"), AliasResult::NoAlias);
+    assert_eq!(alias("store i32 0, ptr %p\n  store i32 1, ptr undef"), AliasResult::NoAlias);
+
+    // An eight byte access cannot be within a four byte alloca
+    let body = "%a = alloca i32\n  %l = load ptr, ptr %p\n  store i32 0, ptr %a\n  store i64 1, ptr %l";
+    let ir = format!("define void @f(ptr %p) {{\nentry:\n  {}\n  ret void\n}}\n", body);
+    let (aa, locations) = accesses(&ir);
+    assert_eq!(aa.alias(&locations[1], &locations[2]), AliasResult::NoAlias);
+
+    let body = "%a = alloca i32, i32 2\n  %l = load ptr, ptr %p\n  store i32 0, ptr %a\n  store i64 1, ptr %l";
+    let ir = format!("define void @f(ptr %p) {{\nentry:\n  {}\n  ret void\n}}\n", body);
+    let (aa, locations) = accesses(&ir);
+    assert_eq!(aa.alias(&locations[1], &locations[2]), AliasResult::MayAlias);
+}
@@ -0,0 +1,184 @@
+//! Analysis Manager Tests
+//!
+//! Cached analysis results, their invalidation by the analyses a pass
+//! preserves, and passes sharing results through the pass manager.
+
+mod common;
+
+use common::module;
+use std::cell::RefCell;
+use std::rc::Rc;
+use llvm_rust::analysis::{AliasAnalysisPass, DominatorTree, DominatorTreePass, LoopAnalysisPass};
+use llvm_rust::analysis_manager::{AnalysisManager, PreservedAnalyses};
+use llvm_rust::call_graph::CallGraphAnalysisPass;
+use llvm_rust::passes::{FunctionPass, Pass, PassManager, PassResult};
+use llvm_rust::transforms::Mem2RegPass;
+use llvm_rust::Function;
+
+const PROGRAM: &str = "define i32 @f(i32 %n) {
+entry:
+  %p = alloca i32
+  store i32 0, ptr %p
+  br label %loop
+loop:
+  %i = load i32, ptr %p
+  %next = add i32 %i, 1
+  store i32 %next, ptr %p
+  %done = icmp sge i32 %next, %n
+  br i1 %done, label %exit, label %loop
+exit:
+  ret i32 %next
+}
+
+define i32 @g(i32 %x) {
+entry:
+  %r = call i32 @f(i32 %x)
+  ret i32 %r
+}
+";
+
+/// Requests the dominator tree and records each result it was handed
+struct UseDomTree {
+    seen: Rc<RefCell<Vec<Rc<DominatorTree>>>>,
+    changes: bool,
+}
+
+impl Pass for UseDomTree {
+    fn name(&self) -> &str {
+        "use-domtree"
+    }
+
+    fn prerequisites(&self) -> Vec<String> {
+        vec!["domtree".to_string()]
+    }
+}
+
+impl FunctionPass for UseDomTree {
+    fn run_on_function(&mut self, _function: &mut Function) -> PassResult<bool> {
+        Ok(self.changes)
+    }
+
+    fn run_with_analyses(&mut self, function: &mut Function, analyses: &mut AnalysisManager) -> PassResult<PreservedAnalyses> {
+        self.seen.borrow_mut().push(analyses.get_result::<DominatorTreePass>(function)?);
+        Ok(if self.changes { PreservedAnalyses::none() } else { PreservedAnalyses::all() })
+    }
+}
+
+#[test]
+fn test_results_are_cached_per_function() {
+    let module = module(PROGRAM);
+    let (f, g) = (module.get_function("f").unwrap(), module.get_function("g").unwrap());
+    let mut analyses = AnalysisManager::new();
+
+    assert!(analyses.get_cached_result::<DominatorTreePass>("f").is_none());
+    let first = analyses.get_result::<DominatorTreePass>(&f).unwrap();
+    let second = analyses.get_result::<DominatorTreePass>(&f).unwrap();
+    assert!(Rc::ptr_eq(&first, &second));
+    assert!(!Rc::ptr_eq(&first, &analyses.get_result::<DominatorTreePass>(&g).unwrap()));
+    assert_eq!(analyses.get_result::<LoopAnalysisPass>(&f).unwrap().num_loops(), 1);
+    assert!(analyses.get_result::<AliasAnalysisPass>(&f).is_ok());
+
+    // Unregistered analyses are an error rather than a silent recompute
+    assert!(AnalysisManager::empty().get_result::<DominatorTreePass>(&f).is_err());
+}
+
+#[test]
+fn test_invalidation_keeps_preserved_results() {
+    let module = module(PROGRAM);
+    let (f, g) = (module.get_function("f").unwrap(), module.get_function("g").unwrap());
+    let mut analyses = AnalysisManager::new();
+    let domtree = analyses.get_result::<DominatorTreePass>(&f).unwrap();
+    analyses.get_result::<LoopAnalysisPass>(&f).unwrap();
+    analyses.get_result::<AliasAnalysisPass>(&f).unwrap();
+    analyses.get_result::<AliasAnalysisPass>(&g).unwrap();
+
+    let mut preserved = PreservedAnalyses::none();
+    preserved.preserve::<DominatorTreePass>().preserve::<LoopAnalysisPass>();
+    analyses.invalidate("f", &preserved);
+    assert!(Rc::ptr_eq(&domtree, &analyses.get_cached_result::<DominatorTreePass>("f").unwrap()));
+    assert!(analyses.get_cached_result::<LoopAnalysisPass>("f").is_some());
+    assert!(analyses.get_cached_result::<AliasAnalysisPass>("f").is_none());
+    // Other functions are untouched
+    assert!(analyses.get_cached_result::<AliasAnalysisPass>("g").is_some());
+
+    analyses.invalidate("f", &PreservedAnalyses::all());
+    assert!(analyses.get_cached_result::<DominatorTreePass>("f").is_some());
+    analyses.invalidate("f", &PreservedAnalyses::none());
+    assert!(analyses.get_cached_result::<DominatorTreePass>("f").is_none());
+    assert!(!Rc::ptr_eq(&domtree, &analyses.get_result::<DominatorTreePass>(&f).unwrap()));
+}
+
+#[test]
+fn test_module_results() {
+    let module = module(PROGRAM);
+    let mut analyses = AnalysisManager::new();
+    let call_graph = analyses.get_module_result::<CallGraphAnalysisPass>(&module).unwrap();
+    assert!(call_graph.calls(call_graph.node_index("g").unwrap(), call_graph.node_index("f").unwrap()));
+    assert!(Rc::ptr_eq(&call_graph, &analyses.get_module_result::<CallGraphAnalysisPass>(&module).unwrap()));
+
+    // A function change that kept the call graph valid leaves it cached
+    let mut preserved = PreservedAnalyses::none();
+    preserved.preserve::<CallGraphAnalysisPass>();
+    analyses.invalidate("f", &preserved);
+    assert!(analyses.get_cached_module_result::<CallGraphAnalysisPass>().is_some());
+    analyses.invalidate_module(&PreservedAnalyses::none());
+    assert!(analyses.get_cached_module_result::<CallGraphAnalysisPass>().is_none());
+}
+
+#[test]
+fn test_pass_manager_shares_results() {
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let mut pm = PassManager::new();
+    // Prerequisites may name registered analyses as well as passes
+    pm.add_function_pass(Box::new(UseDomTree { seen: seen.clone(), changes: false }));
+    pm.add_function_pass(Box::new(UseDomTree { seen: seen.clone(), changes: false }));
+    pm.add_function_pass(Box::new(UseDomTree { seen: seen.clone(), changes: true }));
+    pm.add_function_pass(Box::new(UseDomTree { seen: seen.clone(), changes: false }));
+    pm.run(&mut module(PROGRAM)).expect("passes run");
+
+    // Four passes over @f, then four over @g
+    let seen = seen.borrow();
+    assert_eq!(seen.len(), 8);
+    for function in seen.chunks(4) {
+        assert!(Rc::ptr_eq(&function[0], &function[1]));
+        assert!(Rc::ptr_eq(&function[1], &function[2]));
+        // The third pass changed the function and preserved nothing
+        assert!(!Rc::ptr_eq(&function[2], &function[3]));
+    }
+    assert!(!Rc::ptr_eq(&seen[0], &seen[4]));
+}
+
+#[test]
+fn test_pass_manager_runs_on_several_modules() {
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let mut pm = PassManager::new();
+    pm.add_function_pass(Box::new(UseDomTree { seen: seen.clone(), changes: false }));
+    pm.run(&mut module("define void @f() {\nentry:\n  ret void\n}\n")).expect("passes run");
+    pm.run(&mut module(PROGRAM)).expect("passes run");
+
+    // The second @f has its own dominator tree, not the first one's
+    let seen = seen.borrow();
+    assert_eq!(seen.len(), 3);
+    assert_eq!(seen[0].cfg().num_blocks(), 1);
+    assert_eq!(seen[1].cfg().num_blocks(), 3);
+}
+
+#[test]
+fn test_mem2reg_preserves_cfg_analyses() {
+    let module = module(PROGRAM);
+    let mut f = module.get_function("f").unwrap();
+    let mut analyses = AnalysisManager::new();
+    let domtree = analyses.get_result::<DominatorTreePass>(&f).unwrap();
+
+    let preserved = Mem2RegPass.run_with_analyses(&mut f, &mut analyses).unwrap();
+    assert!(!preserved.are_all_preserved());
+    assert!(preserved.is_preserved::<DominatorTreePass>() && preserved.is_preserved::<LoopAnalysisPass>());
+    assert!(!preserved.is_preserved::<AliasAnalysisPass>());
+
+    analyses.invalidate("f", &preserved);
+    assert!(Rc::ptr_eq(&domtree, &analyses.get_cached_result::<DominatorTreePass>("f").unwrap()));
+
+    // Without allocas to promote nothing is invalidated
+    let mut g = module.get_function("g").unwrap();
+    assert!(Mem2RegPass.run_with_analyses(&mut g, &mut analyses).unwrap().are_all_preserved());
+}
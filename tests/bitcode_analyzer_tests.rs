@@ -0,0 +1,79 @@
+//! Tests for the bitstream analyzer
+//!
+//! The statistics of each fixture are checked against the block tree decoded
+//! by `read_blocks`.
+
+use std::collections::BTreeMap;
+use llvm_rust::bitcode::analyzer::{analyze, AnalyzerOptions, StreamType};
+use llvm_rust::bitcode::bitstream::Block;
+use llvm_rust::bitcode::read_blocks;
+use llvm_rust::{write_bitcode, Context};
+
+fn fixture(name: &str) -> Vec<u8> {
+    let path = format!("{}/tests/fixtures/bitcode/{}.bc", env!("CARGO_MANIFEST_DIR"), name);
+    std::fs::read(&path).expect("fixture exists")
+}
+
+/// Instances and records per block ID
+fn count_blocks(blocks: &[Block], counts: &mut BTreeMap<u32, (usize, usize)>) {
+    for block in blocks {
+        let entry = counts.entry(block.id).or_default();
+        entry.0 += 1;
+        entry.1 += block.records().count();
+        let nested: Vec<Block> = block.blocks().cloned().collect();
+        count_blocks(&nested, counts);
+    }
+}
+
+#[test]
+fn test_statistics_match_decoded_blocks() {
+    for name in ["basic", "advanced", "features"] {
+        let bytes = fixture(name);
+        let analysis = analyze(&bytes, &AnalyzerOptions::default()).unwrap();
+        assert!(analysis.error.is_none(), "{}: {:?}", name, analysis.error);
+        assert_eq!(analysis.stream_type, StreamType::LlvmIr);
+
+        let (blocks, block_info) = read_blocks(&bytes).unwrap();
+        assert_eq!(analysis.top_level_blocks, blocks.len());
+        let mut expected = BTreeMap::new();
+        count_blocks(&blocks, &mut expected);
+        let actual: BTreeMap<u32, (usize, usize)> = analysis.blocks.iter()
+            .map(|(&id, stats)| (id, (stats.instances, stats.records)))
+            .collect();
+        assert_eq!(actual, expected, "{}", name);
+        assert_eq!(analysis.block_info.abbrevs, block_info.abbrevs);
+
+        // Block sizes leave out nested blocks, so together with the magic
+        // number and the 2-bit abbreviation and 8-bit block IDs that enter
+        // the top-level blocks they make up the whole stream
+        let total: usize = analysis.blocks.values().map(|stats| stats.bits).sum();
+        assert_eq!(total + 32 + (2 + 8) * blocks.len(), analysis.stream_bits, "{}", name);
+    }
+}
+
+#[test]
+fn test_dump_lists_every_record() {
+    let bytes = fixture("basic");
+    let analysis = analyze(&bytes, &AnalyzerOptions { dump: true, ..Default::default() }).unwrap();
+    let records: usize = analysis.blocks.values().map(|stats| stats.records).sum();
+    let abbrevs: usize = analysis.blocks.values().map(|stats| stats.abbrevs).sum();
+    let lines: Vec<&str> = analysis.dump.lines().collect();
+    let opening = lines.iter().filter(|line| line.trim_start().starts_with('<') && line.contains("BlockCodeSize=")).count();
+    let closing = lines.iter().filter(|line| line.trim_start().starts_with("</")).count();
+    let instances: usize = analysis.blocks.values().map(|stats| stats.instances).sum();
+    assert_eq!((opening, closing), (instances, instances));
+    assert_eq!(lines.len(), records + abbrevs + 2 * instances);
+    assert!(analysis.dump.contains("<TRIPLE"));
+    assert!(analysis.dump.contains("record string = 'x86_64-unknown-linux-gnu'"));
+}
+
+#[test]
+fn test_analyze_written_bitcode() {
+    let module = llvm_rust::parse_bitcode(&fixture("basic"), Context::new()).unwrap();
+    let bytes = write_bitcode(&module).unwrap();
+    let analysis = analyze(&bytes, &AnalyzerOptions::default()).unwrap();
+    assert!(analysis.error.is_none());
+    assert_eq!(analysis.top_level_blocks, 3);
+    assert_eq!(analysis.blocks[&8].instances, 1);
+    assert!(analysis.statistics().contains("Block ID #12 (FUNCTION_BLOCK):"));
+}
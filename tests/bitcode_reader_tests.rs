@@ -0,0 +1,182 @@
+//! Tests for reading LLVM bitcode
+//!
+//! The `.bc` fixtures were produced from the `.ll` files next to them with
+//! `llvm-as` (LLVM 14).
+
+use llvm_rust::bitcode::{is_bitcode, BitcodeError};
+use llvm_rust::instruction::{AtomicOrdering, FloatPredicate, IntPredicate, Opcode};
+use llvm_rust::module::{Linkage, ThreadLocalMode, Visibility};
+use llvm_rust::{parse_bitcode, verify_module, Context, Module};
+
+fn load(name: &str) -> Module {
+    let path = format!("{}/tests/fixtures/bitcode/{}.bc", env!("CARGO_MANIFEST_DIR"), name);
+    let bytes = std::fs::read(&path).expect("fixture exists");
+    assert!(is_bitcode(&bytes));
+    parse_bitcode(&bytes, Context::new()).unwrap_or_else(|e| panic!("{}: {}", path, e))
+}
+
+fn opcodes(module: &Module, function: &str) -> Vec<Opcode> {
+    let function = module.get_function(function).expect("function exists");
+    function.basic_blocks().iter()
+        .flat_map(|bb| bb.instructions())
+        .map(|inst| inst.opcode())
+        .collect()
+}
+
+#[test]
+fn test_module_header() {
+    let module = load("basic");
+    assert_eq!(module.source_filename().as_deref(), Some("basic.c"));
+    assert_eq!(module.target_triple().as_deref(), Some("x86_64-unknown-linux-gnu"));
+    assert_eq!(module.data_layout().as_deref(), Some("e-m:e-i64:64-f80:128-n8:16:32:64-S128"));
+    assert!(module.get_named_type("struct.point").is_some());
+}
+
+#[test]
+fn test_globals_and_aliases() {
+    let module = load("basic");
+    let message = module.get_global("message").unwrap();
+    assert!(message.is_constant());
+    assert_eq!(message.linkage, Linkage::Private);
+    assert_eq!(message.alignment, Some(1));
+    assert_eq!(message.initializer().unwrap().to_string(),
+        "[i8 104, i8 101, i8 108, i8 108, i8 111, i8 0]");
+    assert_eq!(module.get_global("origin").unwrap().linkage, Linkage::Internal);
+    assert_eq!(module.get_global("table").unwrap().initializer().unwrap().to_string(),
+        "[i64 1, i64 -2, i64 3]");
+
+    let alias = module.get_alias("alias").unwrap();
+    assert_eq!(alias.aliasee.name().as_deref(), Some("counter"));
+
+    let module = load("features");
+    let tls = module.get_global("tls").unwrap();
+    assert_eq!(tls.thread_local_mode, ThreadLocalMode::InitialExec);
+    assert_eq!(tls.section.as_deref(), Some(".tdata"));
+    assert_eq!(tls.comdat.as_deref(), Some("c"));
+    assert_eq!(tls.alignment, Some(16));
+    assert_eq!(module.get_alias("al").unwrap().visibility, Visibility::Hidden);
+}
+
+#[test]
+fn test_function_bodies() {
+    let module = load("basic");
+    assert_eq!(module.function_count(), 5);
+    assert!(!module.get_function("puts").unwrap().has_body());
+
+    let max = module.get_function("max").unwrap();
+    let names: Vec<_> = max.basic_blocks().iter().map(|bb| bb.name().unwrap()).collect();
+    assert_eq!(names, ["entry", "then", "else", "done"]);
+    let args: Vec<_> = max.arguments().iter().map(|a| a.name().unwrap().to_string()).collect();
+    assert_eq!(args, ["a", "b"]);
+
+    let cmp = max.entry_block().unwrap().instructions()[0].clone();
+    assert_eq!(cmp.opcode(), Opcode::ICmp);
+    assert_eq!(cmp.int_predicate(), Some(IntPredicate::SGT));
+
+    // Forward references in PHIs resolve to the instruction defined later
+    let header = &module.get_function("loop").unwrap().basic_blocks()[1];
+    let phi = header.instructions()[0].clone();
+    assert_eq!(phi.opcode(), Opcode::PHI);
+    assert_eq!(phi.operands()[2].name().as_deref(), Some("next"));
+
+    assert_eq!(opcodes(&module, "main"), [
+        Opcode::GetElementPtr, Opcode::Call, Opcode::Call, Opcode::Load, Opcode::FPToSI,
+        Opcode::GetElementPtr, Opcode::Load, Opcode::Switch, Opcode::Ret, Opcode::Ret,
+        Opcode::Select, Opcode::Ret,
+    ]);
+    // Unnamed values are numbered like the textual form
+    let main = module.get_function("main").unwrap();
+    let first = main.entry_block().unwrap().instructions()[0].clone();
+    assert_eq!(first.result().unwrap().name().as_deref(), Some("1"));
+}
+
+#[test]
+fn test_vectors_aggregates_and_atomics() {
+    let module = load("advanced");
+    assert_eq!(opcodes(&module, "vectors"), [
+        Opcode::InsertElement, Opcode::ExtractElement, Opcode::ShuffleVector, Opcode::FMul,
+        Opcode::FNeg, Opcode::FCmp, Opcode::Ret,
+    ]);
+    let vectors = module.get_function("vectors").unwrap();
+    let fcmp = vectors.entry_block().unwrap().instructions()[5].clone();
+    assert_eq!(fcmp.float_predicate(), Some(FloatPredicate::OLT));
+
+    assert_eq!(opcodes(&module, "aggregates"), [
+        Opcode::ExtractValue, Opcode::InsertValue, Opcode::ExtractValue, Opcode::Add, Opcode::Ret,
+    ]);
+
+    let atomics = module.get_function("atomics").unwrap();
+    let insts = atomics.entry_block().unwrap().instructions();
+    assert_eq!(insts[0].opcode(), Opcode::AtomicRMW);
+    assert_eq!(insts[0].atomic_ordering(), Some(AtomicOrdering::SequentiallyConsistent));
+    assert_eq!(insts[1].opcode(), Opcode::AtomicCmpXchg);
+    assert_eq!(insts[4].opcode(), Opcode::Load);
+    assert_eq!(insts[4].atomic_ordering(), Some(AtomicOrdering::Acquire));
+    assert_eq!(insts[4].alignment(), Some(4));
+    assert!(insts[6].is_volatile());
+}
+
+#[test]
+fn test_exception_handling() {
+    let module = load("advanced");
+    let function = module.get_function("exceptions").unwrap();
+    assert_eq!(function.personality().unwrap().name().as_deref(), Some("__gxx_personality_v0"));
+    assert_eq!(opcodes(&module, "exceptions"), [
+        Opcode::Invoke, Opcode::Ret, Opcode::LandingPad, Opcode::Resume,
+    ]);
+
+    let module = load("features");
+    assert_eq!(opcodes(&module, "wineh"), [
+        Opcode::Invoke, Opcode::CatchSwitch, Opcode::CatchPad, Opcode::CatchRet, Opcode::Ret,
+    ]);
+    assert_eq!(opcodes(&module, "ind"), [Opcode::IndirectBr, Opcode::Ret, Opcode::Unreachable]);
+    assert_eq!(module.get_global("arr").unwrap().initializer().unwrap().to_string(),
+        "[i8* blockaddress(@ind, %a), i8* blockaddress(@ind, %b)]");
+}
+
+#[test]
+fn test_attributes() {
+    let module = load("features");
+    let f = module.get_function("f").unwrap().attributes();
+    assert!(f.nounwind);
+    assert!(f.readonly);
+    assert_eq!(f.string_attributes.get("foo").map(String::as_str), Some("bar"));
+    assert!(f.parameter_attributes[0].noalias);
+    assert!(f.parameter_attributes[0].nocapture);
+    assert!(f.parameter_attributes[0].sret.is_some());
+    assert!(f.parameter_attributes[1].zeroext);
+
+    let ind = module.get_function("ind").unwrap().attributes();
+    assert!(ind.noinline && ind.optnone && ind.uwtable);
+    assert_eq!(ind.string_attributes.get("frame-pointer").map(String::as_str), Some("all"));
+}
+
+#[test]
+fn test_metadata() {
+    let module = load("advanced");
+    assert_eq!(module.module_flags().len(), 2);
+    assert_eq!(module.get_named_metadata("llvm.dbg.cu").map(|cu| cu.len()), Some(1));
+
+    let debug = module.get_function("debug").unwrap();
+    let call = debug.entry_block().unwrap().instructions()[0].clone();
+    assert_eq!(call.metadata_attachments(), ["dbg"]);
+    let nodes = module.metadata_nodes();
+    assert!(nodes.values().any(|md| md.to_string().contains("DISubprogram")));
+}
+
+#[test]
+fn test_read_modules_verify() {
+    for name in ["basic", "advanced", "features"] {
+        let module = load(name);
+        assert!(verify_module(&module).is_ok(), "{} failed verification", name);
+    }
+}
+
+#[test]
+fn test_rejects_malformed_input() {
+    assert!(matches!(parse_bitcode(b"; ModuleID = 'x'", Context::new()), Err(BitcodeError::InvalidMagic)));
+
+    let path = format!("{}/tests/fixtures/bitcode/basic.bc", env!("CARGO_MANIFEST_DIR"));
+    let bytes = std::fs::read(path).unwrap();
+    assert!(parse_bitcode(&bytes[..bytes.len() / 2], Context::new()).is_err());
+}
@@ -0,0 +1,333 @@
+//! Tests for writing LLVM bitcode
+//!
+//! Modules are written with `write_bitcode` and read back with the bitcode
+//! reader; the output of the fixtures also loads in `llvm-dis` (LLVM 14).
+
+use llvm_rust::bitcode::{is_bitcode, read_blocks};
+use llvm_rust::instruction::{AtomicOrdering, AtomicRMWBinOp, IntPredicate, Opcode};
+use llvm_rust::module::{Linkage, ThreadLocalMode, Visibility};
+use llvm_rust::{parse, parse_bitcode, verify_module, write_bitcode, BasicBlock, Builder, Context, Function, Instruction, Module, Value};
+
+fn load(name: &str) -> Module {
+    let path = format!("{}/tests/fixtures/bitcode/{}.bc", env!("CARGO_MANIFEST_DIR"), name);
+    let bytes = std::fs::read(&path).expect("fixture exists");
+    parse_bitcode(&bytes, Context::new()).unwrap_or_else(|e| panic!("{}: {}", path, e))
+}
+
+fn round_trip(module: &Module) -> Module {
+    let bytes = write_bitcode(module).unwrap_or_else(|e| panic!("write failed: {}", e));
+    assert!(is_bitcode(&bytes));
+    parse_bitcode(&bytes, Context::new()).unwrap_or_else(|e| panic!("read back failed: {}", e))
+}
+
+fn opcodes(module: &Module, function: &str) -> Vec<Opcode> {
+    let function = module.get_function(function).expect("function exists");
+    function.basic_blocks().iter()
+        .flat_map(|bb| bb.instructions())
+        .map(|inst| inst.opcode())
+        .collect()
+}
+
+#[test]
+fn test_container_layout() {
+    let bytes = write_bitcode(&load("basic")).unwrap();
+    let (blocks, _) = read_blocks(&bytes).unwrap();
+    // Identification, module and string table
+    let ids: Vec<u32> = blocks.iter().map(|b| b.id).collect();
+    assert_eq!(ids, [13, 8, 23]);
+}
+
+#[test]
+fn test_round_trip_module_header_and_globals() {
+    let module = round_trip(&load("basic"));
+    assert_eq!(module.source_filename().as_deref(), Some("basic.c"));
+    assert_eq!(module.target_triple().as_deref(), Some("x86_64-unknown-linux-gnu"));
+    assert!(module.get_named_type("struct.point").is_some());
+
+    let message = module.get_global("message").unwrap();
+    assert!(message.is_constant());
+    assert_eq!(message.linkage, Linkage::Private);
+    assert_eq!(message.alignment, Some(1));
+    assert_eq!(message.initializer().unwrap().to_string(),
+        "[i8 104, i8 101, i8 108, i8 108, i8 111, i8 0]");
+    assert_eq!(module.get_global("table").unwrap().initializer().unwrap().to_string(),
+        "[i64 1, i64 -2, i64 3]");
+    assert_eq!(module.get_alias("alias").unwrap().aliasee.name().as_deref(), Some("counter"));
+
+    let module = round_trip(&load("features"));
+    let tls = module.get_global("tls").unwrap();
+    assert_eq!(tls.thread_local_mode, ThreadLocalMode::InitialExec);
+    assert_eq!(tls.section.as_deref(), Some(".tdata"));
+    assert_eq!(tls.comdat.as_deref(), Some("c"));
+    assert_eq!(tls.alignment, Some(16));
+    assert_eq!(module.get_alias("al").unwrap().visibility, Visibility::Hidden);
+}
+
+#[test]
+fn test_round_trip_function_bodies() {
+    for name in ["basic", "advanced", "features"] {
+        let original = load(name);
+        let module = round_trip(&original);
+        assert_eq!(module.function_count(), original.function_count());
+        for function in original.functions() {
+            assert_eq!(opcodes(&module, &function.name()), opcodes(&original, &function.name()),
+                "{}: @{}", name, function.name());
+        }
+    }
+
+    let module = round_trip(&load("basic"));
+    let max = module.get_function("max").unwrap();
+    let names: Vec<_> = max.basic_blocks().iter().map(|bb| bb.name().unwrap()).collect();
+    assert_eq!(names, ["entry", "then", "else", "done"]);
+    let cmp = max.entry_block().unwrap().instructions()[0].clone();
+    assert_eq!(cmp.int_predicate(), Some(IntPredicate::SGT));
+
+    // The forward reference in the PHI still names the later instruction
+    let header = &module.get_function("loop").unwrap().basic_blocks()[1];
+    assert_eq!(header.instructions()[0].operands()[2].name().as_deref(), Some("next"));
+
+    let module = round_trip(&load("advanced"));
+    let insts = module.get_function("atomics").unwrap().entry_block().unwrap().instructions();
+    assert_eq!(insts[0].rmw_operation(), Some(AtomicRMWBinOp::Add));
+    assert_eq!(insts[4].atomic_ordering(), Some(AtomicOrdering::Acquire));
+    assert_eq!(insts[4].alignment(), Some(4));
+    assert!(insts[6].is_volatile());
+    let exceptions = module.get_function("exceptions").unwrap();
+    assert_eq!(exceptions.personality().unwrap().name().as_deref(), Some("__gxx_personality_v0"));
+}
+
+#[test]
+fn test_round_trip_attributes_and_metadata() {
+    let module = round_trip(&load("features"));
+    let f = module.get_function("f").unwrap().attributes();
+    assert!(f.nounwind && f.readonly);
+    assert_eq!(f.string_attributes.get("foo").map(String::as_str), Some("bar"));
+    assert!(f.parameter_attributes[0].noalias);
+    assert!(f.parameter_attributes[0].sret.is_some());
+    assert!(f.parameter_attributes[1].zeroext);
+    assert_eq!(module.get_global("arr").unwrap().initializer().unwrap().to_string(),
+        "[i8* blockaddress(@ind, %a), i8* blockaddress(@ind, %b)]");
+
+    let module = round_trip(&load("advanced"));
+    assert_eq!(module.module_flags().len(), 2);
+    assert_eq!(module.get_named_metadata("llvm.dbg.cu").map(|cu| cu.len()), Some(1));
+    let nodes = module.metadata_nodes();
+    assert!(nodes.values().any(|md| md.to_string().contains("DISubprogram")));
+}
+
+#[test]
+fn test_rewrite_is_stable() {
+    for name in ["basic", "advanced", "features"] {
+        let first = write_bitcode(&load(name)).unwrap();
+        let second = write_bitcode(&parse_bitcode(&first, Context::new()).unwrap()).unwrap();
+        assert_eq!(first, second, "{} changed when written again", name);
+    }
+}
+
+#[test]
+fn test_write_parsed_module() {
+    let source = r#"
+        @counter = global i32 0
+
+        define i32 @bump(i32 %n) {
+        entry:
+          %old = load i32, i32* @counter
+          %new = add i32 %old, %n
+          store i32 %new, i32* @counter
+          %big = icmp ugt i32 %new, 10
+          br i1 %big, label %reset, label %done
+
+        reset:
+          store i32 0, i32* @counter
+          br label %done
+
+        done:
+          %r = phi i32 [ 0, %reset ], [ %new, %entry ]
+          ret i32 %r
+        }
+    "#;
+    let original = parse(source, Context::new()).unwrap();
+    let module = round_trip(&original);
+    assert_eq!(opcodes(&module, "bump"), opcodes(&original, "bump"));
+    assert!(verify_module(&module).is_ok());
+}
+
+#[test]
+fn test_write_built_module() {
+    let context = Context::new();
+    let module = Module::new("built".to_string(), context.clone());
+    let i32_type = context.int32_type();
+    let fn_type = context.function_type(i32_type.clone(), vec![i32_type.clone(), i32_type.clone()], false);
+    let function = Function::new("mul".to_string(), fn_type);
+    let a = Value::argument(i32_type.clone(), 0, Some("a".to_string()));
+    let b = Value::argument(i32_type, 1, Some("b".to_string()));
+    function.set_arguments(vec![a.clone(), b.clone()]);
+    let entry = BasicBlock::new(Some("entry".to_string()));
+    function.add_basic_block(entry.clone());
+    let mut builder = Builder::new(context.clone());
+    builder.position_at_end(entry);
+    let product = builder.build_mul(a, b, Some("product".to_string()));
+    builder.build_ret(product);
+    module.add_function(function);
+
+    let module = round_trip(&module);
+    assert_eq!(opcodes(&module, "mul"), [Opcode::Mul, Opcode::Ret]);
+    assert!(verify_module(&module).is_ok());
+}
+
+#[test]
+fn test_write_atomicrmw_operations() {
+    let source = r#"
+        define void @f(ptr %p, i32 %v, float %x) {
+        entry:
+          %a = atomicrmw xchg ptr %p, i32 %v seq_cst
+          %b = atomicrmw umin ptr %p, i32 %v acquire
+          %c = atomicrmw fsub ptr %p, float %x monotonic
+          ret void
+        }
+    "#;
+    let module = round_trip(&parse(source, Context::new()).unwrap());
+    let insts = module.get_function("f").unwrap().entry_block().unwrap().instructions();
+    let operations: Vec<_> = insts[..3].iter().map(|inst| inst.rmw_operation()).collect();
+    assert_eq!(operations, [Some(AtomicRMWBinOp::Xchg), Some(AtomicRMWBinOp::UMin), Some(AtomicRMWBinOp::FSub)]);
+
+    // An atomicrmw that does not say what it does cannot be written
+    let module = parse(source, Context::new()).unwrap();
+    let entry = module.get_function("f").unwrap().entry_block().unwrap();
+    let inst = &entry.instructions()[0];
+    let bare = Instruction::new(Opcode::AtomicRMW, inst.operands().to_vec(), inst.result().cloned());
+    entry.replace_instruction(0, bare);
+    assert!(write_bitcode(&module).is_err());
+}
+
+#[test]
+fn test_write_symbolic_debug_info_fields() {
+    let source = r#"
+        !llvm.dbg.cu = !{!0}
+        !llvm.module.flags = !{!5}
+
+        !0 = distinct !DICompileUnit(language: DW_LANG_C99, file: !1, producer: "clang", isOptimized: false, emissionKind: FullDebug, retainedTypes: !2, nameTableKind: None)
+        !1 = !DIFile(filename: "a.c", directory: "/tmp")
+        !2 = !{!3, !4}
+        !3 = !DIBasicType(name: "unsigned int", size: 32, encoding: DW_ATE_unsigned)
+        !4 = !DIDerivedType(tag: DW_TAG_member, name: "x", baseType: !3, size: 32, flags: DIFlagPublic | DIFlagStaticMember)
+        !5 = !{i32 2, !"Debug Info Version", i32 3}
+    "#;
+    let module = round_trip(&parse(source, Context::new()).unwrap());
+    let nodes = module.metadata_nodes();
+    let field = |node: &str, key: &str| nodes.values()
+        .find(|md| md.to_string().starts_with(node))
+        .and_then(|md| md.get_field(key)?.as_int())
+        .unwrap_or_else(|| panic!("{} has no {}", node, key));
+    assert_eq!(field("!DICompileUnit", "language"), 0x0c);
+    assert_eq!(field("!DICompileUnit", "emissionKind"), 1);
+    assert_eq!(field("!DICompileUnit", "nameTableKind"), 2);
+    assert_eq!(field("!DIBasicType", "tag"), 0x24);
+    assert_eq!(field("!DIBasicType", "encoding"), 0x07);
+    assert_eq!(field("!DIDerivedType", "tag"), 0x0d);
+    assert_eq!(field("!DIDerivedType", "flags"), 3 | 1 << 12);
+
+    // Names the writer does not know are not written as zero
+    let module = parse(&source.replace("DW_ATE_unsigned", "DW_ATE_bogus"), Context::new()).unwrap();
+    assert!(write_bitcode(&module).is_err());
+}
+
+#[test]
+fn test_write_debug_locations_and_attachments() {
+    let source = r#"
+        @g = global i32 7, align 4, !dbg !20
+
+        define i32 @f(i32 %x) !dbg !8 {
+        entry:
+          call void @llvm.dbg.value(metadata i32 %x, metadata !13, metadata !DIExpression()), !dbg !14
+          %y = add i32 %x, 1, !dbg !14, !annotation !30
+          ret i32 %y, !dbg !15
+        }
+
+        declare void @llvm.dbg.value(metadata, metadata, metadata)
+
+        !llvm.dbg.cu = !{!0}
+        !llvm.module.flags = !{!5}
+
+        !0 = distinct !DICompileUnit(language: DW_LANG_C99, file: !1, producer: "clang", isOptimized: false, emissionKind: FullDebug, globals: !2)
+        !1 = !DIFile(filename: "a.c", directory: "/tmp")
+        !2 = !{!20}
+        !5 = !{i32 2, !"Debug Info Version", i32 3}
+        !8 = distinct !DISubprogram(name: "f", scope: !1, file: !1, line: 2, type: !9, scopeLine: 2, spFlags: DISPFlagDefinition, unit: !0)
+        !9 = !DISubroutineType(types: !10)
+        !10 = !{!11, !11}
+        !11 = !DIBasicType(name: "int", size: 32, encoding: DW_ATE_signed)
+        !13 = !DILocalVariable(name: "x", arg: 1, scope: !8, file: !1, line: 2, type: !11)
+        !14 = !DILocation(line: 3, column: 7, scope: !8)
+        !15 = !DILocation(line: 4, column: 3, scope: !8)
+        !20 = !DIGlobalVariableExpression(var: !21, expr: !DIExpression())
+        !21 = distinct !DIGlobalVariable(name: "g", scope: !0, file: !1, line: 1, type: !11, isLocal: false, isDefinition: true)
+        !30 = !{!"int"}
+    "#;
+    let module = round_trip(&parse(source, Context::new()).unwrap());
+    let resolve = |md: &llvm_rust::metadata::Metadata| match md.as_reference() {
+        Some(name) => module.get_metadata(name).expect("referenced node exists"),
+        None => md.clone(),
+    };
+
+    let function = module.get_function("f").unwrap();
+    assert!(resolve(&function.subprogram().unwrap()).to_string().starts_with("!DISubprogram"));
+    let insts: Vec<_> = function.basic_blocks().iter().flat_map(|bb| bb.instructions()).collect();
+    let location = |index: usize, key: &str| insts[index].debug_loc()
+        .and_then(|loc| loc.get_field(key)?.as_int())
+        .unwrap_or_else(|| panic!("instruction {} has no {}", index, key));
+    assert_eq!((location(0, "line"), location(0, "column")), (3, 7));
+    assert_eq!((location(1, "line"), location(1, "column")), (3, 7));
+    assert_eq!((location(2, "line"), location(2, "column")), (4, 3));
+    assert_eq!(resolve(insts[1].metadata("annotation").unwrap()).to_string(), "!{!\"int\"}");
+
+    // Metadata arguments keep the local value and the node they refer to
+    let args = &insts[0].operands()[1..];
+    let value = args[0].as_metadata().and_then(|md| md.as_value()).expect("value argument");
+    assert_eq!(value.to_string(), function.arguments()[0].to_string());
+    assert!(resolve(args[1].as_metadata().unwrap()).to_string().starts_with("!DILocalVariable"));
+
+    let global = module.get_global("g").unwrap();
+    assert_eq!(global.metadata.len(), 1);
+    assert_eq!(global.metadata[0].0, "dbg");
+    assert!(resolve(&global.metadata[0].1).to_string().starts_with("!DIGlobalVariableExpression"));
+}
+
+#[test]
+fn test_round_trip_alloca_flags() {
+    let source = r#"
+        define void @f() {
+        entry:
+          %args = alloca inalloca [2 x i32], align 4
+          %error = alloca swifterror ptr, align 8
+          %plain = alloca i64, align 8
+          ret void
+        }
+    "#;
+    let module = round_trip(&parse(source, Context::new()).unwrap());
+    let insts = module.get_function("f").unwrap().entry_block().unwrap().instructions();
+    let flags: Vec<_> = insts[..3].iter().map(|inst| (inst.is_inalloca(), inst.is_swifterror())).collect();
+    assert_eq!(flags, [(true, false), (false, true), (false, false)]);
+    let alignments: Vec<_> = insts[..3].iter().map(|inst| inst.alignment()).collect();
+    assert_eq!(alignments, [Some(4), Some(8), Some(8)]);
+}
+
+#[test]
+fn test_drop_debug_locations_that_are_not_locations() {
+    let source = r#"
+        define void @f() {
+        entry:
+          call void @f(), !dbg !{}
+          ret void, !dbg !0
+        }
+
+        !0 = !{}
+    "#;
+    let module = parse(source, Context::new()).unwrap();
+    let entry = module.get_function("f").unwrap().entry_block().unwrap();
+    assert!(entry.instructions().iter().all(|inst| inst.debug_loc().is_some()));
+
+    let module = round_trip(&module);
+    let entry = module.get_function("f").unwrap().entry_block().unwrap();
+    assert!(entry.instructions().iter().all(|inst| inst.debug_loc().is_none()));
+}
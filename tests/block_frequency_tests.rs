@@ -0,0 +1,171 @@
+//! Block Frequency Tests
+//!
+//! Edge probabilities from branch weights and the static heuristics, the
+//! block frequencies derived from them over loops, and the block layout of
+//! the x86-64 backend that follows them.
+
+mod common;
+
+use common::{function, module};
+use llvm_rust::block_frequency::BlockFrequencyInfo;
+use llvm_rust::branch_probability::{BranchProbability, BranchProbabilityInfo};
+use llvm_rust::codegen::x86_64::block_layout;
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 1e-6, "{} is not {}", actual, expected);
+}
+
+#[test]
+fn test_branch_weights() {
+    let f = function("define void @f(i1 %c, i32 %x) {
+entry:
+  br i1 %c, label %likely, label %unlikely, !prof !0
+likely:
+  switch i32 %x, label %other [ i32 1, label %one
+                                i32 2, label %one ], !prof !1
+unlikely:
+  ret void
+one:
+  ret void
+other:
+  ret void
+}
+
+!0 = !{!\"branch_weights\", i32 2000, i32 1}
+!1 = !{!\"branch_weights\", i32 1, i32 2, i32 5}
+");
+    let bpi = BranchProbabilityInfo::new(&f);
+    assert_eq!(bpi.edge_probability(0, 1), BranchProbability::new(2000, 2001));
+    assert_eq!(bpi.edge_probability(0, 2), BranchProbability::new(1, 2001));
+    assert!(bpi.is_edge_hot(0, 1) && !bpi.is_edge_hot(0, 2));
+    // Both cases that go to %one add up
+    assert_eq!(bpi.edge_probability(1, 3), BranchProbability::new(7, 8));
+    assert_eq!(bpi.edge_probability(1, 4), BranchProbability::new(1, 8));
+    assert_eq!(bpi.edge_probability(1, 2), BranchProbability::zero());
+}
+
+#[test]
+fn test_static_heuristics() {
+    let ir = "define void @f(ptr %p, i1 %c) {
+entry:
+  %null = icmp eq ptr %p, null
+  br i1 %null, label %fail, label %check
+fail:
+  call void @abort()
+  unreachable
+check:
+  br i1 %c, label %slow, label %done
+slow:
+  call void @report()
+  br label %done
+done:
+  ret void
+}
+
+declare void @abort()
+declare void @report() cold
+";
+    let m = module(ir);
+    let f = m.get_function("f").unwrap();
+
+    let bpi = BranchProbabilityInfo::new(&f);
+    assert_eq!(bpi.edge_probability(0, 1), BranchProbability::new(1, 1 << 20));
+    // Nothing is known to be cold without the declarations of the module
+    assert_eq!(bpi.edge_probability(2, 3), BranchProbability::new(1, 2));
+
+    let bpi = BranchProbabilityInfo::in_module(&f, &m);
+    assert_eq!(bpi.edge_probability(2, 3), BranchProbability::new(4, 68));
+    assert_eq!(bpi.edge_probability(2, 4), BranchProbability::new(64, 68));
+
+    // A null check on its own is only unlikely to fail
+    let f = function(&ir.replace("  call void @abort()\n  unreachable", "  ret void"));
+    let bpi = BranchProbabilityInfo::new(&f);
+    assert_eq!(bpi.edge_probability(0, 1), BranchProbability::new(12, 32));
+    assert_eq!(bpi.edge_probability(0, 2), BranchProbability::new(20, 32));
+}
+
+const NESTED_LOOPS: &str = "define void @f(i32 %n, i1 %c) {
+entry:
+  br label %outer
+outer:
+  %i = phi i32 [ 0, %entry ], [ %i.next, %latch ]
+  br label %inner
+inner:
+  %j = phi i32 [ 0, %outer ], [ %j.next, %inner ]
+  %j.next = add i32 %j, 1
+  %j.done = icmp eq i32 %j.next, %n
+  br i1 %j.done, label %latch, label %inner
+latch:
+  %i.next = add i32 %i, 1
+  %i.done = icmp eq i32 %i.next, %n
+  br i1 %i.done, label %exit, label %outer
+exit:
+  br i1 %c, label %then, label %end
+then:
+  br label %end
+end:
+  ret void
+}
+";
+
+#[test]
+fn test_loop_frequencies() {
+    let bfi = BlockFrequencyInfo::new(&function(NESTED_LOOPS));
+    let bpi = bfi.branch_probabilities();
+    assert_eq!(bpi.edge_probability(2, 2), BranchProbability::new(124, 128));
+    assert_eq!(bpi.edge_probability(3, 4), BranchProbability::new(4, 128));
+
+    // Each loop goes around 32 times per entry
+    assert_close(bfi.frequency(0), 1.0);
+    assert_close(bfi.frequency(1), 32.0);
+    assert_close(bfi.frequency(2), 32.0 * 32.0);
+    assert_close(bfi.frequency(3), 32.0);
+    assert_close(bfi.frequency(4), 1.0);
+    assert_close(bfi.frequency(5), 0.5);
+    assert_close(bfi.frequency(6), 1.0);
+    assert_close(bfi.edge_frequency(3, 1), 31.0);
+    assert_close(bfi.max_frequency(), 1024.0);
+}
+
+#[test]
+fn test_infinite_loop_is_capped() {
+    let bfi = BlockFrequencyInfo::new(&function("define void @f() {
+entry:
+  br label %loop
+loop:
+  br label %loop
+dead:
+  ret void
+}
+"));
+    assert_close(bfi.frequency(1), BlockFrequencyInfo::MAX_LOOP_SCALE);
+    assert_eq!(bfi.frequency(2), 0.0);
+}
+
+#[test]
+fn test_block_layout_follows_hot_paths() {
+    let ir = "define void @f(ptr %p, i32 %n) {
+entry:
+  %null = icmp eq ptr %p, null
+  br i1 %null, label %error, label %loop
+error:
+  call void @fail()
+  br label %exit
+loop:
+  %i = phi i32 [ 0, %entry ], [ %next, %loop ]
+  %next = add i32 %i, 1
+  %done = icmp eq i32 %next, %n
+  br i1 %done, label %exit, label %loop
+exit:
+  ret void
+}
+
+declare void @fail() cold
+";
+    let m = module(ir);
+    let f = m.get_function("f").unwrap();
+    let bfi = BlockFrequencyInfo::from_branch_probabilities(BranchProbabilityInfo::in_module(&f, &m));
+    // The cold error path moves below the loop and its exit
+    assert_eq!(block_layout(&bfi), [0, 2, 3, 1]);
+    assert_eq!(block_layout(&BlockFrequencyInfo::new(&function(NESTED_LOOPS))), [0, 1, 2, 3, 4, 5, 6]);
+}
@@ -0,0 +1,141 @@
+//! Call Graph Tests
+//!
+//! Call graph edges of parsed modules, the external nodes, and the
+//! bottom-up order of strongly connected components.
+
+mod common;
+
+use common::module;
+use std::sync::{Arc, Mutex};
+use llvm_rust::call_graph::{CallGraph, CallGraphSCC};
+use llvm_rust::passes::{CallGraphSCCPass, Pass, PassManager, PassResult};
+use llvm_rust::Module;
+
+const PROGRAM: &str = "@table = global ptr @callback
+
+declare void @puts(ptr)
+declare i32 @llvm.smax.i32(i32, i32)
+
+define internal void @callback() {
+entry:
+  ret void
+}
+
+define internal void @unused() {
+entry:
+  ret void
+}
+
+define internal i32 @even(i32 %n) {
+entry:
+  %r = call i32 @odd(i32 %n)
+  ret i32 %r
+}
+
+define internal i32 @odd(i32 %n) {
+entry:
+  %m = call i32 @llvm.smax.i32(i32 %n, i32 0)
+  %r = call i32 @even(i32 %m)
+  ret i32 %r
+}
+
+define i32 @main(ptr %fp) {
+entry:
+  call void @puts(ptr null)
+  call void %fp()
+  %r = call i32 @even(i32 1)
+  ret i32 %r
+}
+";
+
+#[test]
+fn test_call_edges() {
+    let cg = CallGraph::new(&module(PROGRAM));
+    let node = |name: &str| cg.node_index(name).expect("function has a node");
+    let (main, even, odd, puts) = (node("main"), node("even"), node("odd"), node("puts"));
+
+    assert_eq!(cg.node(main).callees, [puts, CallGraph::CALLS_EXTERNAL_NODE, even]);
+    assert_eq!(cg.node(odd).callees, [even]);
+    assert_eq!(cg.callers(even), [odd, main]);
+    // Declarations may call anything; intrinsics call nothing
+    assert_eq!(cg.node(puts).callees, [CallGraph::CALLS_EXTERNAL_NODE]);
+    assert!(cg.node(node("llvm.smax.i32")).callees.is_empty());
+
+    // Code outside the module reaches external functions and functions
+    // whose address escapes
+    let external = &cg.node(CallGraph::EXTERNAL_CALLING_NODE).callees;
+    assert!(external.contains(&main) && external.contains(&node("callback")) && external.contains(&puts));
+    assert!(!external.contains(&node("unused")) && !external.contains(&even));
+}
+
+#[test]
+fn test_bottom_up_sccs() {
+    let cg = CallGraph::new(&module(PROGRAM));
+    let sccs = cg.bottom_up_sccs();
+    let position = |name: &str| sccs.iter()
+        .position(|scc| scc.functions(&cg).contains(&name.to_string()))
+        .expect("function is in a component");
+
+    let recursion = &sccs[position("even")];
+    assert_eq!(recursion.functions(&cg), ["even", "odd"]);
+    assert!(recursion.has_cycle);
+    assert_eq!(position("odd"), position("even"));
+    assert!(position("even") < position("main"));
+    assert!(position("puts") < position("main"));
+    assert!(!sccs[position("main")].has_cycle);
+    // Every node is in exactly one component
+    assert_eq!(sccs.iter().map(|scc| scc.nodes.len()).sum::<usize>(), cg.num_nodes());
+}
+
+#[test]
+fn test_self_recursion_and_long_chains() {
+    let mut ir = String::from("define void @f0() {\nentry:\n  call void @f0()\n  ret void\n}\n");
+    for i in 1..2000 {
+        ir.push_str(&format!("define void @f{}() {{\nentry:\n  call void @f{}()\n  ret void\n}}\n", i, i - 1));
+    }
+    let cg = CallGraph::new(&module(&ir));
+    let sccs: Vec<CallGraphSCC> = cg.bottom_up_sccs().into_iter()
+        .filter(|scc| !scc.functions(&cg).is_empty())
+        .collect();
+    assert_eq!(sccs.len(), 2000);
+    assert_eq!(sccs[0].functions(&cg), ["f0"]);
+    assert!(sccs[0].has_cycle);
+    assert_eq!(sccs[1999].functions(&cg), ["f1999"]);
+    assert!(!sccs[1].has_cycle);
+}
+
+/// Records the functions of each component it visits
+struct RecordSCCs {
+    visited: Arc<Mutex<Vec<Vec<String>>>>,
+}
+
+impl Pass for RecordSCCs {
+    fn name(&self) -> &str {
+        "record-sccs"
+    }
+}
+
+impl CallGraphSCCPass for RecordSCCs {
+    fn run_on_scc(&mut self, scc: &CallGraphSCC, call_graph: &CallGraph, _module: &mut Module) -> PassResult<bool> {
+        let functions = scc.functions(call_graph);
+        if !functions.is_empty() {
+            self.visited.lock().unwrap().push(functions);
+        }
+        Ok(false)
+    }
+}
+
+#[test]
+fn test_scc_pass_manager() {
+    let visited = Arc::new(Mutex::new(Vec::new()));
+    let mut pm = PassManager::new();
+    pm.add_scc_pass(Box::new(RecordSCCs { visited: visited.clone() }));
+    pm.run(&mut module(PROGRAM)).expect("passes run");
+
+    let visited = visited.lock().unwrap();
+    let position = |name: &str| visited.iter().position(|functions| functions.contains(&name.to_string())).unwrap();
+    assert_eq!(visited[position("odd")], ["even", "odd"]);
+    assert!(position("odd") < position("main"));
+    // One component per function, with @even and @odd together
+    assert_eq!(visited.len(), 6);
+}
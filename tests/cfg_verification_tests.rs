@@ -0,0 +1,165 @@
+//! CFG Verification Tests
+//!
+//! Branch targets, entry predecessors, terminator placement, phi grouping,
+//! phi entries per predecessor and duplicate switch cases.
+
+mod common;
+
+use common::module;
+use llvm_rust::verification::{verify_function, verify_module, VerificationError};
+use llvm_rust::instruction::Opcode;
+use llvm_rust::{BasicBlock, Builder, Context, Function, Instruction, Value};
+
+/// InvalidCFG and InvalidPhi errors as (reason, location)
+fn cfg_errors(ir: &str) -> Vec<(String, String)> {
+    let module = module(ir);
+    match verify_module(&module) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.into_iter().filter_map(|e| match e {
+            VerificationError::InvalidCFG { reason, location }
+            | VerificationError::InvalidPhi { reason, location } => Some((reason, location)),
+            _ => None,
+        }).collect(),
+    }
+}
+
+#[test]
+fn test_valid_cfg() {
+    let errors = cfg_errors(r#"
+        define i32 @f(i32 %n) {
+        entry:
+          switch i32 %n, label %other [
+            i32 0, label %zero
+            i32 1, label %join
+          ]
+
+        zero:
+          br label %join
+
+        other:
+          br label %join
+
+        join:
+          %p = phi i32 [ 0, %zero ], [ 1, %entry ], [ 2, %other ]
+          ret i32 %p
+        }
+    "#);
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn test_missing_branch_target() {
+    let errors = cfg_errors(r#"
+        define void @f() {
+        entry:
+          br label %nowhere
+        }
+    "#);
+    assert_eq!(errors, vec![(
+        "branch target %nowhere does not exist in the function".to_string(),
+        "function f block %entry".to_string(),
+    )]);
+}
+
+#[test]
+fn test_entry_block_with_predecessor() {
+    let errors = cfg_errors(r#"
+        define void @f() {
+        entry:
+          br label %loop
+
+        loop:
+          br label %entry
+        }
+    "#);
+    assert_eq!(errors, vec![(
+        "entry block to function must not have predecessors, found %loop".to_string(),
+        "function f block %entry".to_string(),
+    )]);
+}
+
+#[test]
+fn test_phi_nodes_not_grouped() {
+    let errors = cfg_errors(r#"
+        define i32 @f() {
+        entry:
+          br label %next
+
+        next:
+          %a = phi i32 [ 0, %entry ]
+          %b = add i32 %a, 1
+          %c = phi i32 [ 1, %entry ]
+          ret i32 %c
+        }
+    "#);
+    assert_eq!(errors, vec![(
+        "PHI nodes not grouped at top of basic block".to_string(),
+        "function f block %next".to_string(),
+    )]);
+}
+
+#[test]
+fn test_phi_entries_must_match_predecessors() {
+    let errors = cfg_errors(r#"
+        define i32 @f(i1 %c) {
+        entry:
+          br i1 %c, label %left, label %right
+
+        left:
+          br label %join
+
+        right:
+          br label %join
+
+        join:
+          %p = phi i32 [ 0, %left ], [ 1, %entry ]
+          ret i32 %p
+        }
+    "#);
+    assert_eq!(errors, vec![
+        ("PHI node has no entry for predecessor %right".to_string(), "function f block %join".to_string()),
+        ("PHI node has an entry for %entry, which is not a predecessor".to_string(), "function f block %join".to_string()),
+    ]);
+}
+
+#[test]
+fn test_duplicate_switch_case() {
+    let errors = cfg_errors(r#"
+        define void @f(i32 %n) {
+        entry:
+          switch i32 %n, label %done [
+            i32 3, label %done
+            i32 3, label %done
+          ]
+
+        done:
+          ret void
+        }
+    "#);
+    assert_eq!(errors, vec![(
+        "duplicate integer 3 as switch case".to_string(),
+        "function f block %entry".to_string(),
+    )]);
+}
+
+#[test]
+fn test_terminator_in_middle_of_block() {
+    // Neither the parser nor the builder appends past a terminator, so swap one in
+    let context = Context::new();
+    let i32_type = context.int32_type();
+    let function = Function::new("f".to_string(), context.function_type(i32_type.clone(), vec![], false));
+    let entry = BasicBlock::new(Some("entry".to_string()));
+    function.add_basic_block(entry.clone());
+    let mut builder = Builder::new(context.clone());
+    builder.position_at_end(entry.clone());
+    let zero = Value::const_int(i32_type.clone(), 0, None);
+    let sum = builder.build_add(zero.clone(), zero.clone(), Some("sum".to_string()));
+    builder.build_ret(sum);
+    entry.replace_instruction(0, Instruction::new(Opcode::Ret, vec![zero], None));
+
+    let errors = verify_function(&function).unwrap_err();
+    assert!(errors.iter().any(|e| matches!(e,
+        VerificationError::InvalidCFG { reason, location }
+            if reason == "terminator Ret found in the middle of a basic block" && location == "function f block %entry")),
+        "{:?}", errors);
+}
@@ -0,0 +1,17 @@
+//! Fixtures shared by the integration tests
+
+#![allow(dead_code)]
+
+use llvm_rust::parser::Parser;
+use llvm_rust::{Context, Function, Module};
+
+/// Parse `ir` without verifying it, so tests can inspect what the verifier
+/// and analyses make of invalid IR
+pub fn module(ir: &str) -> Module {
+    Parser::new(Context::new()).parse_module(ir).expect("test IR parses")
+}
+
+/// The function `@f` of `ir`
+pub fn function(ir: &str) -> Function {
+    module(ir).get_function("f").expect("test IR defines @f")
+}
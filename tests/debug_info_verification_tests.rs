@@ -0,0 +1,160 @@
+//! Debug Info Verification Tests
+//!
+//! Subprogram units, scope chains, `!dbg` locations against their function's
+//! subprogram, file references, metadata cycles and DIExpression operations.
+
+mod common;
+
+use common::module;
+use llvm_rust::verification::{verify_module, VerificationError};
+
+/// Shared compile unit, file and subprogram of `@f`
+const PRELUDE: &str = r#"
+!llvm.dbg.cu = !{!0}
+!0 = distinct !DICompileUnit(language: DW_LANG_C99, file: !1, producer: "clang", isOptimized: false, runtimeVersion: 0, emissionKind: FullDebug)
+!1 = !DIFile(filename: "a.c", directory: "/tmp")
+!2 = distinct !DISubprogram(name: "f", scope: !1, file: !1, line: 1, scopeLine: 1, spFlags: DISPFlagDefinition, unit: !0)
+"#;
+
+/// Metadata and debug info errors as (reason, location)
+fn debug_info_errors(ir: &str) -> Vec<(String, String)> {
+    let source = format!("{}{}", ir, PRELUDE);
+    let module = module(&source);
+    match verify_module(&module) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.into_iter().filter_map(|e| match e {
+            VerificationError::InvalidDebugInfo { reason, location }
+            | VerificationError::InvalidMetadata { reason, location }
+            | VerificationError::MetadataReference { reason, location } => Some((reason, location)),
+            _ => None,
+        }).collect(),
+    }
+}
+
+#[test]
+fn test_valid_debug_info() {
+    let errors = debug_info_errors(r#"
+        define i32 @f(i32 %x) !dbg !2 {
+        entry:
+          %a = add i32 %x, 1, !dbg !10
+          %b = mul i32 %a, 2, !dbg !12
+          ret i32 %b, !dbg !14
+        }
+
+        !10 = !DILocation(line: 2, column: 3, scope: !11)
+        !11 = distinct !DILexicalBlock(scope: !2, file: !1, line: 2)
+        !12 = !DILocation(line: 7, column: 1, scope: !13, inlinedAt: !10)
+        !13 = distinct !DISubprogram(name: "g", scope: !1, file: !1, line: 6, spFlags: DISPFlagDefinition, unit: !0)
+        !14 = !DILocation(line: 3, column: 3, scope: !2)
+        !15 = !DIExpression(DW_OP_plus_uconst, 8, DW_OP_deref, DW_OP_stack_value, DW_OP_LLVM_fragment, 0, 32)
+    "#);
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn test_subprogram_definition_requires_unit() {
+    let errors = debug_info_errors(r#"
+        !10 = distinct !DISubprogram(name: "g", file: !1, line: 6, spFlags: DISPFlagDefinition)
+    "#);
+    assert_eq!(errors, vec![(
+        "subprogram definitions must have a compile unit".to_string(),
+        "!10 (DISubprogram)".to_string(),
+    )]);
+}
+
+#[test]
+fn test_location_in_other_subprogram() {
+    let errors = debug_info_errors(r#"
+        define void @f() !dbg !2 {
+        entry:
+          ret void, !dbg !11
+        }
+
+        !10 = distinct !DISubprogram(name: "g", file: !1, line: 6, spFlags: DISPFlagDefinition, unit: !0)
+        !11 = !DILocation(line: 7, column: 1, scope: !10)
+    "#);
+    assert_eq!(errors, vec![(
+        "!dbg attachment points at wrong subprogram !10 (DISubprogram) for function (expected !2 (DISubprogram))".to_string(),
+        "function f block %entry instruction 0 (Ret)".to_string(),
+    )]);
+}
+
+#[test]
+fn test_function_attachment_must_be_subprogram() {
+    let errors = debug_info_errors(r#"
+        define void @f() !dbg !1 {
+        entry:
+          ret void
+        }
+    "#);
+    assert_eq!(errors, vec![(
+        "function !dbg attachment must be a subprogram, found !1 (DIFile)".to_string(),
+        "function f".to_string(),
+    )]);
+}
+
+#[test]
+fn test_file_reference_must_be_file() {
+    let errors = debug_info_errors(r#"
+        !10 = !DIBasicType(name: "int", size: 32, encoding: DW_ATE_signed)
+        !11 = !DILexicalBlock(scope: !2, file: !10, line: 2)
+    "#);
+    assert_eq!(errors, vec![(
+        "invalid file !10 (DIBasicType)".to_string(),
+        "!11 (DILexicalBlock)".to_string(),
+    )]);
+}
+
+#[test]
+fn test_location_requires_local_scope() {
+    let errors = debug_info_errors(r#"
+        !10 = !DILocation(line: 2, column: 3, scope: !1)
+    "#);
+    assert_eq!(errors, vec![(
+        "location requires a valid scope".to_string(),
+        "!10 (DILocation)".to_string(),
+    )]);
+}
+
+#[test]
+fn test_scope_cycle() {
+    let errors = debug_info_errors(r#"
+        !10 = distinct !DILexicalBlock(scope: !11, file: !1, line: 2)
+        !11 = distinct !DILexicalBlock(scope: !10, file: !1, line: 3)
+    "#);
+    assert_eq!(errors, vec![(
+        "scope chain contains a cycle through !10 (DILexicalBlock), !11 (DILexicalBlock)".to_string(),
+        "!10 (DILexicalBlock)".to_string(),
+    )]);
+}
+
+#[test]
+fn test_undefined_metadata_reference() {
+    let errors = debug_info_errors(r#"
+        !10 = !DILocation(line: 2, column: 3, scope: !99)
+    "#);
+    assert!(errors.contains(&("use of undefined metadata '!99'".to_string(), "metadata".to_string())), "{:?}", errors);
+}
+
+#[test]
+fn test_malformed_expressions() {
+    let cases = [
+        ("DW_OP_bogus", "invalid expression: unknown operation DW_OP_bogus"),
+        ("DW_OP_plus_uconst", "invalid expression: DW_OP_plus_uconst expects 1 integer argument(s)"),
+        ("DW_OP_LLVM_fragment, 0, 32, DW_OP_deref", "invalid expression: DW_OP_LLVM_fragment must be the last operation"),
+        ("DW_OP_stack_value, DW_OP_deref", "invalid expression: DW_OP_stack_value may only be followed by DW_OP_LLVM_fragment"),
+        ("DW_OP_deref, DW_OP_LLVM_entry_value, 1", "invalid expression: DW_OP_LLVM_entry_value must be the first operation"),
+    ];
+    for (operations, reason) in cases {
+        let errors = debug_info_errors(&format!("!10 = !DIExpression({})\n", operations));
+        assert_eq!(errors, vec![(reason.to_string(), "!10 (DIExpression)".to_string())], "{}", operations);
+    }
+}
+
+#[test]
+fn test_compile_unit_list_entries() {
+    let source = "!llvm.dbg.cu = !{!0}\n!0 = !DIFile(filename: \"a.c\", directory: \"/tmp\")\n";
+    let module = module(source);
+    let errors: Vec<String> = verify_module(&module).unwrap_err().iter().map(|e| e.to_string()).collect();
+    assert_eq!(errors, vec!["Invalid debug info at llvm.dbg.cu: !llvm.dbg.cu must contain only DICompileUnit nodes, found DIFile"]);
+}
@@ -0,0 +1,182 @@
+//! Dominance Tests
+//!
+//! Dominance frontiers, iterated frontiers and post-dominator trees of
+//! parsed functions, and phi placement in mem2reg.
+
+mod common;
+
+use common::function;
+use std::collections::HashSet;
+use llvm_rust::analysis::{DominanceFrontier, DominatorTree, PostDominatorTree};
+use llvm_rust::passes::FunctionPass;
+use llvm_rust::transforms::Mem2RegPass;
+use llvm_rust::{BasicBlock, Builder, Context, Function};
+
+fn blocks(indices: &[usize]) -> HashSet<usize> {
+    indices.iter().copied().collect()
+}
+
+const DIAMOND: &str = "define void @f(i1 %c) {\nentry:\n  br i1 %c, label %a, label %b\n\
+    a:\n  br label %merge\nb:\n  br label %merge\nmerge:\n  ret void\n}\n";
+
+const LOOP: &str = "define void @f(i1 %c) {\nentry:\n  br label %header\n\
+    header:\n  br i1 %c, label %body, label %exit\nbody:\n  br label %header\nexit:\n  ret void\n}\n";
+
+#[test]
+fn test_dominance_frontier() {
+    let df = DominanceFrontier::new(&function(DIAMOND));
+    assert!(df.frontier(0).is_empty());
+    assert_eq!(df.frontier(1), &blocks(&[3]));
+    assert_eq!(df.frontier(2), &blocks(&[3]));
+    assert!(df.frontier(3).is_empty());
+
+    let df = DominanceFrontier::new(&function(LOOP));
+    assert_eq!(df.frontier(1), &blocks(&[1]));
+    assert_eq!(df.frontier(2), &blocks(&[1]));
+    assert!(df.frontier(3).is_empty());
+}
+
+/// A loop with an early return from its body and a normal exit
+const LOOP_WITH_EXITS: &str = "define void @f(i1 %c) {\nentry:\n  br label %header\n\
+    header:\n  br i1 %c, label %body, label %exit\nbody:\n  br i1 %c, label %early, label %latch\n\
+    early:\n  ret void\nlatch:\n  br label %header\nexit:\n  ret void\n}\n";
+
+/// A loop that is entered on one branch and never left
+const INFINITE_LOOP: &str = "define void @f(i1 %c) {\nentry:\n  br i1 %c, label %spin, label %exit\n\
+    spin:\n  br i1 %c, label %left, label %right\nleft:\n  br label %spin\nright:\n  br label %spin\n\
+    exit:\n  ret void\n}\n";
+
+#[test]
+fn test_dominance_frontier_multiple_exits() {
+    let df = DominanceFrontier::new(&function(LOOP_WITH_EXITS));
+    assert!(df.frontier(0).is_empty());
+    assert_eq!(df.frontier(1), &blocks(&[1]));
+    assert_eq!(df.frontier(2), &blocks(&[1]));
+    // A block that returns has no successors and so no frontier
+    assert!(df.frontier(3).is_empty());
+    assert_eq!(df.frontier(4), &blocks(&[1]));
+    assert!(df.frontier(5).is_empty());
+    assert_eq!(df.iterated_frontier(&blocks(&[3, 4])), blocks(&[1]));
+}
+
+#[test]
+fn test_dominance_frontier_infinite_loop() {
+    let df = DominanceFrontier::new(&function(INFINITE_LOOP));
+    assert!(df.frontier(0).is_empty());
+    assert_eq!(df.frontier(1), &blocks(&[1]));
+    assert_eq!(df.frontier(2), &blocks(&[1]));
+    assert_eq!(df.frontier(3), &blocks(&[1]));
+    assert!(df.frontier(4).is_empty());
+    assert_eq!(df.iterated_frontier(&blocks(&[2])), blocks(&[1]));
+}
+
+#[test]
+fn test_iterated_dominance_frontier() {
+    // A definition in the inner diamond's arm needs a phi at the inner
+    // merge, and through it at the loop header
+    let ir = "define void @f(i1 %c) {\nentry:\n  br label %header\n\
+        header:\n  br i1 %c, label %left, label %right\nleft:\n  br label %latch\n\
+        right:\n  br label %latch\nlatch:\n  br i1 %c, label %header, label %exit\nexit:\n  ret void\n}\n";
+    let df = DominanceFrontier::new(&function(ir));
+    assert_eq!(df.frontier(2), &blocks(&[4]));
+    assert_eq!(df.iterated_frontier(&blocks(&[2])), blocks(&[1, 4]));
+    assert!(df.iterated_frontier(&blocks(&[0])).is_empty());
+}
+
+#[test]
+fn test_post_dominator_tree() {
+    let pdt = PostDominatorTree::new(&function(DIAMOND));
+    assert_eq!(pdt.roots(), &[3]);
+    assert_eq!(pdt.ipdom(0), Some(3));
+    assert_eq!(pdt.ipdom(1), Some(3));
+    assert_eq!(pdt.ipdom(3), None);
+    assert!(pdt.post_dominates(3, 0));
+    assert!(!pdt.post_dominates(1, 0));
+
+    // Two returns: only the virtual root post-dominates the entry
+    let ir = "define void @f(i1 %c) {\nentry:\n  br i1 %c, label %a, label %b\n\
+        a:\n  ret void\nb:\n  ret void\n}\n";
+    let pdt = PostDominatorTree::new(&function(ir));
+    assert_eq!(pdt.roots(), &[1, 2]);
+    assert_eq!(pdt.ipdom(0), None);
+    assert!(!pdt.post_dominates(1, 0) && !pdt.post_dominates(2, 0));
+}
+
+#[test]
+fn test_post_dominator_tree_infinite_loop() {
+    // The loop never reaches the return, so one of its blocks becomes a root
+    let ir = "define void @f(i1 %c) {\nentry:\n  br i1 %c, label %header, label %exit\n\
+        header:\n  br label %body\nbody:\n  br label %header\nexit:\n  ret void\n}\n";
+    let pdt = PostDominatorTree::new(&function(ir));
+    assert_eq!(pdt.roots(), &[3, 2]);
+    assert_eq!(pdt.ipdom(1), Some(2));
+    assert_eq!(pdt.ipdom(2), None);
+    assert_eq!(pdt.ipdom(0), None);
+    assert!(pdt.post_dominates(2, 1));
+}
+
+#[test]
+fn test_post_dominator_tree_multiple_exits() {
+    let pdt = PostDominatorTree::new(&function(LOOP_WITH_EXITS));
+    assert_eq!(pdt.roots(), &[3, 5]);
+    assert_eq!(pdt.ipdom(0), Some(1));
+    assert_eq!(pdt.ipdom(4), Some(1));
+    // Both exits are reachable from the header and the body
+    assert_eq!(pdt.ipdom(1), None);
+    assert_eq!(pdt.ipdom(2), None);
+    assert!(pdt.post_dominates(1, 4) && pdt.post_dominates(1, 0));
+    assert!(!pdt.post_dominates(5, 1) && !pdt.post_dominates(3, 2));
+}
+
+#[test]
+fn test_post_dominator_tree_branching_infinite_loop() {
+    let pdt = PostDominatorTree::new(&function(INFINITE_LOOP));
+    // The loop is rooted at the block furthest from where it was entered
+    assert_eq!(pdt.roots(), &[4, 2]);
+    assert_eq!(pdt.ipdom(1), Some(2));
+    assert_eq!(pdt.ipdom(3), Some(1));
+    assert_eq!(pdt.ipdom(2), None);
+    assert_eq!(pdt.ipdom(0), None);
+    assert!(pdt.post_dominates(2, 3));
+    assert!(!pdt.post_dominates(4, 1) && !pdt.post_dominates(3, 1));
+}
+
+#[test]
+fn test_mem2reg_places_phis_at_frontier() {
+    let ir = "define i32 @f(i1 %c) {\nentry:\n  %x = alloca i32\n  br i1 %c, label %a, label %b\n\
+        a:\n  store i32 1, ptr %x\n  br label %merge\nb:\n  store i32 2, ptr %x\n  br label %merge\n\
+        merge:\n  %v = load i32, ptr %x\n  ret i32 %v\n}\n";
+    assert!(Mem2RegPass.run_on_function(&mut function(ir)).unwrap());
+
+    // A single store in the entry block needs no phi
+    let ir = "define i32 @f(i1 %c) {\nentry:\n  %x = alloca i32\n  store i32 1, ptr %x\n  br i1 %c, label %a, label %b\n\
+        a:\n  br label %merge\nb:\n  br label %merge\n\
+        merge:\n  %v = load i32, ptr %x\n  ret i32 %v\n}\n";
+    assert!(!Mem2RegPass.run_on_function(&mut function(ir)).unwrap());
+}
+
+#[test]
+fn test_long_chain() {
+    // Deep enough that a recursive traversal would overflow the stack
+    let ctx = Context::new();
+    let function = Function::new("chain".to_string(), ctx.function_type(ctx.void_type(), vec![], false));
+    let count = 100_000;
+    let blocks: Vec<BasicBlock> = (0..count).map(|i| BasicBlock::new(Some(format!("b{}", i)))).collect();
+    let mut builder = Builder::new(ctx.clone());
+    for (i, bb) in blocks.iter().enumerate() {
+        builder.position_at_end(bb.clone());
+        match blocks.get(i + 1) {
+            Some(next) => builder.build_br(next.clone()),
+            None => {
+                builder.build_ret_void();
+            }
+        }
+        function.add_basic_block(bb.clone());
+    }
+
+    let domtree = DominatorTree::new(&function);
+    assert_eq!(domtree.idom(count - 1), Some(count - 2));
+    let pdt = PostDominatorTree::new(&function);
+    assert_eq!(pdt.ipdom(0), Some(1));
+    assert!(DominanceFrontier::from_dominator_tree(&domtree).frontier(0).is_empty());
+}
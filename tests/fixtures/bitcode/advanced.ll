@@ -0,0 +1,76 @@
+; Vectors, aggregates, atomics, exceptions and metadata
+source_filename = "advanced.ll"
+
+%pair = type { i64, double }
+
+@flag = external global i8
+@limit = constant i64 ptrtoint (i8* @flag to i64)
+
+declare i32 @__gxx_personality_v0(...)
+declare void @may_throw()
+declare void @llvm.dbg.value(metadata, metadata, metadata)
+
+define <4 x float> @vectors(<4 x float> %v, float %s) {
+  %ins = insertelement <4 x float> %v, float %s, i32 0
+  %ext = extractelement <4 x float> %ins, i32 1
+  %shuf = shufflevector <4 x float> %ins, <4 x float> undef, <4 x i32> <i32 3, i32 2, i32 1, i32 0>
+  %mul = fmul fast <4 x float> %shuf, %v
+  %neg = fneg <4 x float> %mul
+  %cmp = fcmp olt <4 x float> %neg, zeroinitializer
+  ret <4 x float> %neg
+}
+
+define i64 @aggregates(%pair %p) {
+  %a = extractvalue %pair %p, 0
+  %b = insertvalue %pair %p, i64 7, 0
+  %c = extractvalue %pair %b, 0
+  %sum = add i64 %a, %c
+  ret i64 %sum
+}
+
+define i32 @atomics(i32* %ptr) {
+  %old = atomicrmw add i32* %ptr, i32 1 seq_cst
+  %pair = cmpxchg i32* %ptr, i32 %old, i32 0 acquire monotonic
+  %val = extractvalue { i32, i1 } %pair, 0
+  fence release
+  %l = load atomic i32, i32* %ptr acquire, align 4
+  store atomic i32 %l, i32* %ptr release, align 4
+  store volatile i32 %val, i32* %ptr
+  ret i32 %val
+}
+
+define void @exceptions() personality i32 (...)* @__gxx_personality_v0 {
+entry:
+  invoke void @may_throw()
+          to label %ok unwind label %lpad
+
+ok:
+  ret void
+
+lpad:
+  %lp = landingpad { i8*, i32 }
+          cleanup
+  resume { i8*, i32 } %lp
+}
+
+define void @debug(i32 %x) !dbg !6 {
+  call void @llvm.dbg.value(metadata i32 %x, metadata !9, metadata !DIExpression()), !dbg !10
+  ret void, !dbg !10
+}
+
+!llvm.dbg.cu = !{!0}
+!llvm.module.flags = !{!3, !4}
+!llvm.ident = !{!5}
+
+!0 = distinct !DICompileUnit(language: DW_LANG_C99, file: !1, producer: "clang", isOptimized: false, runtimeVersion: 0, emissionKind: FullDebug, enums: !2)
+!1 = !DIFile(filename: "advanced.c", directory: "/tmp")
+!2 = !{}
+!3 = !{i32 7, !"Dwarf Version", i32 5}
+!4 = !{i32 2, !"Debug Info Version", i32 3}
+!5 = !{!"clang version 14.0.0"}
+!6 = distinct !DISubprogram(name: "debug", scope: !1, file: !1, line: 1, type: !7, scopeLine: 1, spFlags: DISPFlagDefinition, unit: !0, retainedNodes: !2)
+!7 = !DISubroutineType(types: !8)
+!8 = !{null, !11}
+!9 = !DILocalVariable(name: "x", arg: 1, scope: !6, file: !1, line: 1, type: !11)
+!10 = !DILocation(line: 2, column: 3, scope: !6)
+!11 = !DIBasicType(name: "int", size: 32, encoding: DW_ATE_signed)
@@ -0,0 +1,84 @@
+; Globals, arithmetic, control flow and calls
+source_filename = "basic.c"
+target datalayout = "e-m:e-i64:64-f80:128-n8:16:32:64-S128"
+target triple = "x86_64-unknown-linux-gnu"
+
+%struct.point = type { i32, i32 }
+
+@counter = global i32 0, align 4
+@message = private unnamed_addr constant [6 x i8] c"hello\00", align 1
+@origin = internal constant %struct.point { i32 1, i32 2 }
+@table = global [3 x i64] [i64 1, i64 -2, i64 3]
+@scale = global double 2.500000e+00
+@alias = alias i32, i32* @counter
+
+declare i32 @puts(i8*)
+
+define i32 @add(i32 %a, i32 %b) {
+entry:
+  %sum = add nsw i32 %a, %b
+  ret i32 %sum
+}
+
+define i32 @max(i32 %a, i32 %b) {
+entry:
+  %cmp = icmp sgt i32 %a, %b
+  br i1 %cmp, label %then, label %else
+
+then:
+  br label %done
+
+else:
+  br label %done
+
+done:
+  %result = phi i32 [ %a, %then ], [ %b, %else ]
+  ret i32 %result
+}
+
+define i32 @loop(i32 %n) {
+entry:
+  %slot = alloca i32, align 4
+  store i32 0, i32* %slot, align 4
+  br label %header
+
+header:
+  %i = phi i32 [ 0, %entry ], [ %next, %body ]
+  %done = icmp uge i32 %i, %n
+  br i1 %done, label %exit, label %body
+
+body:
+  %old = load i32, i32* %slot, align 4
+  %new = add i32 %old, %i
+  store i32 %new, i32* %slot, align 4
+  %next = add i32 %i, 1
+  br label %header
+
+exit:
+  %total = load i32, i32* %slot, align 4
+  ret i32 %total
+}
+
+define i32 @main() {
+  %1 = getelementptr inbounds [6 x i8], [6 x i8]* @message, i64 0, i64 0
+  %2 = call i32 @puts(i8* %1)
+  %3 = call i32 @add(i32 %2, i32 1)
+  %4 = load double, double* @scale
+  %5 = fptosi double %4 to i32
+  %6 = getelementptr %struct.point, %struct.point* @origin, i32 0, i32 1
+  %7 = load i32, i32* %6
+  switch i32 %7, label %other [
+    i32 1, label %one
+    i32 2, label %two
+  ]
+
+one:
+  ret i32 %3
+
+two:
+  ret i32 %5
+
+other:
+  %8 = select i1 true, i32 %3, i32 %5
+  ret i32 %8
+}
@@ -0,0 +1,41 @@
+; Comdats, TLS, blockaddress, indirectbr, va_arg, funclet EH and attributes
+$c = comdat any
+@tls = thread_local(initialexec) global i32 0, section ".tdata", comdat($c), align 16
+@arr = global [2 x i8*] [i8* blockaddress(@ind, %a), i8* blockaddress(@ind, %b)]
+@al = hidden alias i32, i32* @tls
+@h = global half 0xH3C00
+@x = global x86_fp80 0xK3FFF8000000000000000
+@q = global fp128 0xL00000000000000004000000000000000
+
+declare void @f(i8* noalias nocapture sret(i32), i32 zeroext) nounwind readonly "foo"="bar"
+
+define void @ind(i8* %p) #0 {
+entry:
+  indirectbr i8* %p, [label %a, label %b]
+a:
+  ret void
+b:
+  unreachable
+}
+
+define i32 @va(i8* %list) {
+  %v = va_arg i8* %list, i32
+  %big = alloca i32, i32 %v, align 8
+  %g = getelementptr inbounds i32, i32* %big, <2 x i64> <i64 0, i64 1>
+  ret i32 %v
+}
+
+define void @wineh() personality i32 (...)* @p {
+entry:
+  invoke void @g() to label %done unwind label %cs
+cs:
+  %s = catchswitch within none [label %h1] unwind to caller
+h1:
+  %c = catchpad within %s [i8* null, i32 64, i8* null]
+  catchret from %c to label %done
+done:
+  ret void
+}
+declare void @g()
+declare i32 @p(...)
+attributes #0 = { noinline optnone uwtable "frame-pointer"="all" }
@@ -0,0 +1,142 @@
+//! Tests for parsing IR fragments against an existing module
+
+use llvm_rust::parser::Parser;
+use llvm_rust::{parse_constant, parse_type, Context, Module};
+
+const MODULE: &str = r#"
+%struct.point = type { i32, i32 }
+
+@origin = global %struct.point zeroinitializer
+
+declare i32 @helper(i32)
+
+!0 = !{!"fragment"}
+"#;
+
+fn base_module(ctx: &Context) -> Module {
+    llvm_rust::parse(MODULE, ctx.clone()).expect("base module should parse")
+}
+
+#[test]
+fn test_module_records_named_types() {
+    let ctx = Context::new();
+    let module = base_module(&ctx);
+    let point = module.get_named_type("struct.point").expect("named type registered");
+    assert!(point.is_struct());
+}
+
+#[test]
+fn test_parse_function_into_uses_module_scope() {
+    let ctx = Context::new();
+    let module = base_module(&ctx);
+    let mut parser = Parser::new(ctx.clone());
+
+    let function = parser.parse_function_into(&module, r#"
+        define i32 @get_x(ptr %p) {
+        entry:
+          %x.addr = getelementptr %struct.point, ptr %p, i32 0, i32 0
+          %x = load i32, ptr %x.addr, !tag !0
+          %r = call i32 @helper(i32 %x)
+          ret i32 %r
+        }
+    "#).expect("function fragment should parse");
+
+    assert_eq!(function.name(), "get_x");
+    assert_eq!(function.basic_block_count(), 1);
+    assert!(module.get_function("get_x").is_some());
+    assert_eq!(module.function_count(), 2);
+}
+
+#[test]
+fn test_parse_function_into_defines_declaration() {
+    let ctx = Context::new();
+    let module = base_module(&ctx);
+    let mut parser = Parser::new(ctx.clone());
+
+    parser.parse_function_into(&module, "define i32 @helper(i32 %a) {\nentry:\n  ret i32 %a\n}")
+        .expect("definition should replace declaration");
+    assert_eq!(module.function_count(), 1);
+    assert!(module.get_function("helper").unwrap().has_body());
+
+    let redefined = parser.parse_function_into(&module, "define i32 @helper(i32 %a) {\nentry:\n  ret i32 0\n}");
+    assert!(redefined.is_err(), "a second definition should be rejected");
+
+    let fresh = base_module(&ctx);
+    let mismatched = parser.parse_function_into(&fresh, "define i64 @helper(i64 %a) {\nentry:\n  ret i64 %a\n}");
+    assert!(mismatched.is_err(), "a definition must match the declared type");
+}
+
+#[test]
+fn test_parse_function_into_rejects_trailing_input() {
+    let ctx = Context::new();
+    let module = base_module(&ctx);
+    let mut parser = Parser::new(ctx.clone());
+
+    let result = parser.parse_function_into(&module, "declare void @a()\ndeclare void @b()");
+    assert!(result.is_err());
+    assert!(module.get_function("a").is_none());
+}
+
+#[test]
+fn test_parse_global_into() {
+    let ctx = Context::new();
+    let module = base_module(&ctx);
+    let mut parser = Parser::new(ctx.clone());
+
+    let global = parser.parse_global_into(&module, "@counter = internal global i64 7")
+        .expect("global fragment should parse");
+    assert_eq!(global.name(), "counter");
+    assert!(module.get_global("counter").is_some());
+}
+
+#[test]
+fn test_parse_type() {
+    let ctx = Context::new();
+    let ty = parse_type(&ctx, "{ i32, [4 x i8], ptr }").expect("type should parse");
+    assert!(ty.is_struct());
+    assert!(parse_type(&ctx, "<4 x float>").unwrap().is_vector());
+
+    // Named types are unknown without a module in scope
+    assert!(parse_type(&ctx, "%struct.point").is_err());
+    assert!(parse_type(&ctx, "i32 i32").is_err());
+}
+
+#[test]
+fn test_parse_type_fragment_with_module_types() {
+    let ctx = Context::new();
+    let mut parser = Parser::new(ctx.clone());
+    parser.parse_module(MODULE).unwrap();
+
+    let ty = parser.parse_type_fragment("[2 x %struct.point]").expect("named type should resolve");
+    assert!(ty.is_array());
+}
+
+#[test]
+fn test_parse_constant() {
+    let ctx = Context::new();
+    let module = base_module(&ctx);
+
+    let int = parse_constant(&module, "i32 42").expect("integer constant");
+    assert!(int.is_const_int_value(42));
+
+    let global = parse_constant(&module, "ptr @origin").expect("global reference");
+    assert!(global.is_global());
+
+    let expr = parse_constant(&module, "ptr getelementptr (%struct.point, ptr @origin, i32 0, i32 1)")
+        .expect("constant expression");
+    assert!(expr.get_type().is_pointer());
+
+    Parser::new(ctx.clone())
+        .parse_function_into(&module, "define void @target() {\nentry:\n  ret void\n}")
+        .unwrap();
+    let address = parse_constant(&module, "ptr blockaddress(@target, %entry)").expect("blockaddress");
+    assert!(address.is_constant());
+    assert!(parse_constant(&module, "ptr blockaddress(@target, %missing)").is_err());
+    assert!(parse_constant(&module, "ptr blockaddress(@helper, %entry)").is_err());
+    assert!(parse_constant(&module, "ptr blockaddress(@origin, %entry)").is_err());
+
+    assert!(parse_constant(&module, "ptr @missing").is_err());
+    assert!(parse_constant(&module, "i32 %local").is_err());
+    assert!(parse_constant(&module, "ptr getelementptr (i8, ptr @missing, i64 4)").is_err());
+    assert!(parse_constant(&module, "i64 ptrtoint (ptr %local to i64)").is_err());
+}
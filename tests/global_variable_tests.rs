@@ -182,3 +182,35 @@ fn test_complex_global() {
     assert_eq!(global.unnamed_addr, UnnamedAddr::Global);
     assert_eq!(global.alignment, Some(4));
 }
+
+#[test]
+fn test_global_initialized_with_address() {
+    let ctx = Context::new();
+    let ir = "@target = global i32 0\n@pointer = global ptr @target\n@next = global i32 1";
+
+    let module = parse(ir, ctx).expect("Failed to parse");
+    let globals = module.globals();
+
+    assert_eq!(globals.len(), 3);
+    let initializer = globals[1].initializer.as_ref().expect("initializer is kept");
+    assert_eq!(initializer.name(), Some("target"));
+    assert!(initializer.is_global());
+}
+
+#[test]
+fn test_alias_with_long_header() {
+    let ctx = Context::new();
+    // More header tokens before `alias` than the parser keeps buffered ahead
+    let ir = r#"
+        @g = global i32 0
+        @a = private internal hidden default dllexport dllimport dso_local dso_preemptable thread_local(initialexec) unnamed_addr local_unnamed_addr weak_odr protected alias i32, ptr @g
+    "#;
+
+    let module = parse(ir, ctx).expect("Failed to parse");
+    assert_eq!(module.globals().len(), 1);
+    let alias = module.get_alias("a").expect("alias is parsed");
+    assert_eq!(alias.linkage, Linkage::WeakOdr);
+    assert_eq!(alias.visibility, Visibility::Protected);
+    assert_eq!(alias.thread_local_mode, ThreadLocalMode::InitialExec);
+    assert_eq!(alias.unnamed_addr, UnnamedAddr::Local);
+}
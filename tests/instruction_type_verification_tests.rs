@@ -0,0 +1,133 @@
+//! Instruction Type Verification Tests
+//!
+//! Operand and result types are checked per opcode, and errors name the
+//! function, block and index of the offending instruction.
+
+mod common;
+
+use common::module;
+use llvm_rust::instruction::{AtomicRMWBinOp, Instruction, Opcode};
+use llvm_rust::value::Value;
+use llvm_rust::verification::{verify_module, Verifier};
+use llvm_rust::{Context, Type};
+
+/// Errors reported for a function `@f` whose entry block holds `body`
+fn type_errors(body: &str) -> Vec<String> {
+    let ir = format!("%pair = type {{ i32, ptr }}\n\n\
+        define void @f(ptr %p, ptr addrspace(1) %q, i32 %x, i64 %n, <4 x i32> %v, <4 x i1> %m, %pair %agg) {{\n\
+        entry:\n  {}\n  ret void\n}}\n", body);
+    let module = module(&ir);
+    match verify_module(&module) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.iter().map(|e| e.to_string()).collect(),
+    }
+}
+
+#[test]
+fn test_valid_instructions() {
+    let bodies = [
+        "%a = fpext float 1.0 to fp128",
+        "%a = bitcast i128 0 to fp128",
+        "%a = bitcast <4 x i32> %v to <2 x i64>",
+        "%a = shufflevector <4 x i32> %v, <4 x i32> %v, <8 x i32> <i32 0, i32 1, i32 2, i32 3, i32 4, i32 5, i32 6, i32 7>",
+        "%a = getelementptr i8, ptr addrspace(1) %q, i64 %n",
+        "%a = getelementptr %pair, ptr %p, i64 0, i32 1",
+        "%a = getelementptr [4 x %pair], ptr %p, i64 0, i64 %n, i32 0",
+        "%a = getelementptr i32, ptr %p, <4 x i64> zeroinitializer",
+        "%a = select <4 x i1> %m, <4 x i32> %v, <4 x i32> %v",
+        "%a = icmp slt <4 x i32> %v, %v",
+        "%a = extractvalue %pair %agg, 1",
+        "%a = insertvalue %pair %agg, i32 %x, 0",
+        "%a = atomicrmw fmax ptr %p, float 1.0 seq_cst",
+        "%a = cmpxchg ptr %p, i32 %x, i32 0 seq_cst seq_cst",
+        "%a = addrspacecast ptr %p to ptr addrspace(1)",
+    ];
+    for body in bodies {
+        let errors = type_errors(body);
+        assert!(errors.is_empty(), "{}: {:?}", body, errors);
+    }
+}
+
+#[test]
+fn test_errors_name_the_instruction() {
+    let errors = type_errors("%a = add i32 %x, 1\n  %b = select <4 x i1> %m, i32 %x, i32 %x");
+    assert_eq!(errors, ["Invalid instruction at function f block %entry instruction 1 (Select): \
+        select condition Type(<4 x i1>) must have one element per element of the values Type(i32)"]);
+}
+
+#[test]
+fn test_cast_rules() {
+    let errors = type_errors("%a = fpext double 1.0 to half");
+    assert_eq!(errors, ["Invalid cast from Type(double) to Type(half) at function f block %entry \
+        instruction 0 (FPExt): fpext result must be larger"]);
+
+    let errors = type_errors("%a = sext <4 x i32> %v to <4 x i16>");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].contains("sext result must be larger (src: 32 bits, dst: 16 bits)"), "{:?}", errors);
+
+    let errors = type_errors("%a = addrspacecast ptr %p to ptr");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].contains("addrspacecast must be between different address spaces"), "{:?}", errors);
+
+    let errors = type_errors("%a = bitcast i32 %x to i64");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].contains("bitcast requires types of same width"), "{:?}", errors);
+}
+
+#[test]
+fn test_shufflevector_mask() {
+    let errors = type_errors("%a = shufflevector <4 x i32> %v, <4 x i32> %v, <2 x i32> <i32 1, i32 8>");
+    assert_eq!(errors, ["Invalid instruction at function f block %entry instruction 0 (ShuffleVector): \
+        shufflevector mask element 1 selects element 8 of two 4-element vectors"]);
+}
+
+#[test]
+fn test_gep_struct_indices() {
+    let errors = type_errors("%a = getelementptr %pair, ptr %p, i64 0, i32 2");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].contains("getelementptr index 1 selects field 2"), "{:?}", errors);
+
+    let errors = type_errors("%a = getelementptr %pair, ptr %p, i64 0, i32 %x");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].contains("must be a constant i32"), "{:?}", errors);
+
+    let errors = type_errors("%a = getelementptr %pair, ptr %p, i64 0, i32 1, i32 0");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].contains("invalid getelementptr indices"), "{:?}", errors);
+}
+
+#[test]
+fn test_built_instructions() {
+    let ctx = Context::new();
+    let i32_type = ctx.int32_type();
+    let pair = Type::struct_type(&ctx, vec![i32_type.clone(), ctx.int64_type()], None);
+    let aggregate = Value::undef(pair.clone());
+    let index = |i: i64| Value::const_int(i32_type.clone(), i, None);
+
+    // extractvalue past the last field
+    let extract = Instruction::new(Opcode::ExtractValue, vec![aggregate.clone(), index(2)],
+        Some(Value::undef(i32_type.clone())));
+    let mut verifier = Verifier::new();
+    verifier.verify_instruction(&extract);
+    let errors: Vec<String> = verifier.diagnostics().iter().map(|d| d.error.to_string()).collect();
+    assert_eq!(errors, ["Invalid instruction at instruction ExtractValue: \
+        extractvalue index 0 is 2, out of range for 2 elements of Type({ i32, i64 })"]);
+
+    // insertvalue of an i32 into the i64 field
+    let insert = Instruction::new(Opcode::InsertValue, vec![aggregate, Value::undef(i32_type.clone()), index(1)],
+        Some(Value::undef(pair)));
+    let mut verifier = Verifier::new();
+    verifier.verify_instruction(&insert);
+    let errors: Vec<String> = verifier.diagnostics().iter().map(|d| d.error.to_string()).collect();
+    assert_eq!(errors, ["Type mismatch at instruction InsertValue inserted value: expected Type(i64), found Type(i32)"]);
+
+    // atomicrmw fadd of an integer
+    let mut rmw = Instruction::new(Opcode::AtomicRMW, vec![Value::undef(ctx.ptr_type(ctx.int8_type())), index(1)],
+        Some(Value::undef(i32_type)));
+    rmw.set_rmw_operation(AtomicRMWBinOp::FAdd);
+    let mut verifier = Verifier::new();
+    verifier.verify_instruction(&rmw);
+    let errors: Vec<String> = verifier.diagnostics().iter().map(|d| d.error.to_string()).collect();
+    assert_eq!(errors, ["Invalid instruction at instruction AtomicRMW: \
+        atomicrmw fadd operand must be a floating point type, got Type(i32)"]);
+}
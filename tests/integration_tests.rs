@@ -576,7 +576,8 @@ fn test_alias_analysis() {
     let function = Function::new("test".to_string(), fn_type);
 
     let aa = AliasAnalysis::new(&function);
-    assert!(aa.must_alias("a", "a"));
+    let a = Value::argument(ctx.ptr_type(ctx.int8_type()), 0, Some("a".to_string()));
+    assert!(aa.must_alias(&a, &a));
 }
 
 // Generate lots of simple tests for comprehensive coverage
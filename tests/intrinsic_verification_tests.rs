@@ -0,0 +1,67 @@
+//! Intrinsic Verification Tests
+//!
+//! Calls are checked against the intrinsic table: signatures, name
+//! mangling, immarg operands and variadic declarations.
+
+mod common;
+
+use common::module;
+use llvm_rust::verification::verify_module;
+
+/// Reasons of the errors reported for a module declaring `declaration` and
+/// calling it with `call`
+fn intrinsic_errors(declaration: &str, call: &str) -> Vec<String> {
+    let ir = format!("{}\n\ndefine void @f(ptr %p, i64 %n, i1 %flag, i32 %x) {{\nentry:\n  {}\n  ret void\n}}\n", declaration, call);
+    let module = module(&ir);
+    match verify_module(&module) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.iter().map(|e| e.to_string()).collect(),
+    }
+}
+
+#[test]
+fn test_valid_calls() {
+    let cases = [
+        ("declare void @llvm.memcpy.p0.p0.i64(ptr, ptr, i64, i1)", "call void @llvm.memcpy.p0.p0.i64(ptr %p, ptr %p, i64 %n, i1 false)"),
+        ("declare i32 @llvm.abs.i32(i32, i1)", "%a = call i32 @llvm.abs.i32(i32 %x, i1 true)"),
+        ("declare { i32, i1 } @llvm.sadd.with.overflow.i32(i32, i32)", "%s = call { i32, i1 } @llvm.sadd.with.overflow.i32(i32 %x, i32 %x)"),
+        ("declare void @llvm.experimental.stackmap(i64, i32, ...)", "call void (i64, i32, ...) @llvm.experimental.stackmap(i64 1, i32 0, i32 %x)"),
+        ("declare void @llvm.trap()", "call void @llvm.trap()"),
+    ];
+    for (declaration, call) in cases {
+        let errors = intrinsic_errors(declaration, call);
+        assert!(errors.is_empty(), "{}: {:?}", declaration, errors);
+    }
+}
+
+#[test]
+fn test_signature_mismatch() {
+    let errors = intrinsic_errors("declare i64 @llvm.abs.i64(i32, i1)", "%a = call i64 @llvm.abs.i64(i32 %x, i1 false)");
+    assert_eq!(errors, ["Invalid instruction at call to llvm.abs.i64: Intrinsic has incorrect argument type!"]);
+
+    let errors = intrinsic_errors("declare void @llvm.memcpy.p0.p0.i64(ptr, ptr, i64)", "call void @llvm.memcpy.p0.p0.i64(ptr %p, ptr %p, i64 %n)");
+    assert_eq!(errors, ["Invalid instruction at call to llvm.memcpy.p0.p0.i64: Intrinsic has incorrect argument type!"]);
+
+    let errors = intrinsic_errors("declare void @llvm.experimental.stackmap(i64, i32)", "call void @llvm.experimental.stackmap(i64 1, i32 0)");
+    assert_eq!(errors, ["Invalid instruction at call to llvm.experimental.stackmap: Callsite was not defined with variable arguments!"]);
+}
+
+#[test]
+fn test_name_mangling() {
+    let errors = intrinsic_errors("declare i64 @llvm.ctpop.i32(i64)", "%c = call i64 @llvm.ctpop.i32(i64 %n)");
+    assert_eq!(errors, ["Invalid instruction at call to llvm.ctpop.i32: \
+        Intrinsic name not mangled correctly for type arguments! Should be: llvm.ctpop.i64"]);
+
+    // The bare name leaves the overload types to the signature
+    assert!(intrinsic_errors("declare i64 @llvm.ctpop(i64)", "%c = call i64 @llvm.ctpop(i64 %n)").is_empty());
+}
+
+#[test]
+fn test_immarg_operands() {
+    let errors = intrinsic_errors("declare i32 @llvm.ctlz.i32(i32, i1)", "%c = call i32 @llvm.ctlz.i32(i32 %x, i1 %flag)");
+    assert_eq!(errors, ["Invalid instruction at call to llvm.ctlz.i32: immarg operand has non-immediate parameter"]);
+
+    let errors = intrinsic_errors("declare void @llvm.prefetch.p0(ptr, i32, i32, i32)",
+        "call void @llvm.prefetch.p0(ptr %p, i32 0, i32 %x, i32 1)");
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+}
@@ -0,0 +1,133 @@
+//! Tests for lazy function body materialization
+
+use std::sync::Arc;
+
+use llvm_rust::{Context, parse, parse_lazy};
+use llvm_rust::instruction::Opcode;
+use llvm_rust::parser::Parser;
+
+const MODULE: &str = r#"
+%struct.pair = type { i32, i64 }
+
+@counter = global i32 0
+
+declare i32 @external(i32)
+
+define i64 @second(ptr %p) !dbg !3 {
+entry:
+  %field = getelementptr inbounds %struct.pair, ptr %p, i32 0, i32 1
+  %value = load i64, ptr %field, align 8, !dbg !4
+  ret i64 %value
+}
+
+define i32 @caller(i32 %x) {
+entry:
+  %cmp = icmp sgt i32 %x, 0
+  br i1 %cmp, label %then, label %else
+
+then:
+  %r = call i32 @callee(i32 %x)
+  br label %else
+
+else:
+  %result = phi i32 [ 0, %entry ], [ %r, %then ]
+  ret i32 %result
+}
+
+define i32 @callee(i32 %y) {
+  %z = add i32 %y, 1
+  ret i32 %z
+}
+
+!0 = distinct !DICompileUnit(language: DW_LANG_C99, file: !1, emissionKind: FullDebug)
+!1 = !DIFile(filename: "pair.c", directory: "/tmp")
+!3 = distinct !DISubprogram(name: "second", file: !1, line: 1, spFlags: DISPFlagDefinition, unit: !0)
+!4 = !DILocation(line: 2, column: 3, scope: !3)
+"#;
+
+#[test]
+fn test_lazy_parse_defers_bodies() {
+    let module = parse_lazy(MODULE.into(), Context::new()).expect("lazy parse failed");
+
+    assert_eq!(module.function_count(), 4);
+    let caller = module.get_function("caller").unwrap();
+    assert!(caller.is_materializable());
+    assert!(caller.has_body());
+    assert_eq!(caller.arguments().len(), 1);
+
+    let external = module.get_function("external").unwrap();
+    assert!(!external.is_materializable());
+    assert!(!external.has_body());
+}
+
+#[test]
+fn test_basic_blocks_materializes_body() {
+    let module = parse_lazy(MODULE.into(), Context::new()).expect("lazy parse failed");
+    let caller = module.get_function("caller").unwrap();
+
+    let blocks = caller.basic_blocks();
+    assert!(!caller.is_materializable());
+    assert_eq!(blocks.len(), 3);
+    assert_eq!(blocks[0].name().as_deref(), Some("entry"));
+    assert_eq!(blocks[2].instructions()[0].opcode(), Opcode::PHI);
+
+    // Other functions are untouched
+    assert!(module.get_function("callee").unwrap().is_materializable());
+}
+
+#[test]
+fn test_materialized_body_matches_eager_parse() {
+    let eager = parse(MODULE, Context::new()).expect("eager parse failed");
+    let lazy = parse_lazy(MODULE.into(), Context::new()).expect("lazy parse failed");
+
+    for function in eager.functions() {
+        let lazy_function = lazy.get_function(&function.name()).unwrap();
+        lazy_function.materialize().expect("materialization failed");
+
+        let eager_blocks = function.basic_blocks();
+        let lazy_blocks = lazy_function.basic_blocks();
+        assert_eq!(eager_blocks.len(), lazy_blocks.len(), "block count of @{}", function.name());
+        for (e, l) in eager_blocks.iter().zip(&lazy_blocks) {
+            let eager_ops: Vec<_> = e.instructions().iter().map(|i| i.opcode()).collect();
+            let lazy_ops: Vec<_> = l.instructions().iter().map(|i| i.opcode()).collect();
+            assert_eq!(eager_ops, lazy_ops);
+        }
+    }
+}
+
+#[test]
+fn test_body_uses_module_tables() {
+    let module = parse_lazy(MODULE.into(), Context::new()).expect("lazy parse failed");
+    let second = module.get_function("second").unwrap();
+
+    let blocks = second.basic_blocks();
+    let gep = &blocks[0].instructions()[0];
+    assert_eq!(gep.opcode(), Opcode::GetElementPtr);
+    assert!(gep.gep_source_type().unwrap().is_struct());
+    assert!(blocks[0].instructions()[1].has_metadata("dbg"));
+}
+
+#[test]
+fn test_materialize_reports_errors() {
+    let source = r#"
+define void @good() {
+  ret void
+}
+
+define void @bad() {
+  %x = add i32 1,
+}
+"#;
+    let source: Arc<str> = source.into();
+    let mut parser = Parser::new(Context::new());
+    let module = parser.parse_module_lazy(&source).expect("signatures should parse");
+
+    let good = module.get_function("good").unwrap();
+    assert!(good.materialize().is_ok());
+    assert_eq!(good.basic_block_count(), 1);
+
+    let bad = module.get_function("bad").unwrap();
+    assert!(bad.materialize().is_err());
+    assert!(bad.materialize_error().is_some());
+    assert!(bad.basic_blocks().is_empty());
+}
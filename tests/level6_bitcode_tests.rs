@@ -1,4 +1,4 @@
-use llvm_rust::{Context, parse};
+use llvm_rust::{Context, parse, parse_bitcode};
 use std::time::Instant;
 
 #[test]
@@ -19,9 +19,9 @@ fn test_parse_bitcode_tests() {
         }
     };
 
-    // Filter for .ll files only
+    // Textual .ll files and the .bc files checked in next to them
     entries.retain(|e| {
-        e.path().extension().map_or(false, |ext| ext == "ll")
+        e.path().extension().map_or(false, |ext| ext == "ll" || ext == "bc")
     });
 
     entries.sort_by_key(|e| e.path());
@@ -36,7 +36,7 @@ fn test_parse_bitcode_tests() {
         let path = entry.path();
         let filename = path.file_name().unwrap().to_str().unwrap();
 
-        let content = match std::fs::read_to_string(&path) {
+        let content = match std::fs::read(&path) {
             Ok(c) => c,
             Err(e) => {
                 println!("✗ {}: Failed to read file: {}", filename, e);
@@ -49,15 +49,21 @@ fn test_parse_bitcode_tests() {
         let start = Instant::now();
         let ctx = Context::new();
 
-        match parse(&content, ctx) {
+        let result = if path.extension().map_or(false, |ext| ext == "bc") {
+            parse_bitcode(&content, ctx).map_err(|e| e.to_string())
+        } else {
+            parse(&String::from_utf8_lossy(&content), ctx).map_err(|e| format!("{:?}", e))
+        };
+
+        match result {
             Ok(_) => {
                 println!("✓ {} ({:.2}s)", filename, start.elapsed().as_secs_f64());
                 passed += 1;
             }
             Err(e) => {
-                println!("✗ {}: {:?}", filename, e);
+                println!("✗ {}: {}", filename, e);
                 failed += 1;
-                failures.push((filename.to_string(), format!("{:?}", e)));
+                failures.push((filename.to_string(), e));
             }
         }
     }
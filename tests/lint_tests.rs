@@ -0,0 +1,107 @@
+//! Lint Tests
+//!
+//! The lint pass reports valid IR that is likely undefined behavior, with
+//! the location of the offending instruction.
+
+mod common;
+
+use common::module;
+use llvm_rust::lint::{lint_module, LintKind, LintPass};
+use llvm_rust::passes::ModulePass;
+
+/// Findings for a module holding `globals` and a function `@f` whose entry
+/// block holds `body`, as kind and message pairs
+fn lint(globals: &str, body: &str) -> Vec<(LintKind, String)> {
+    let ir = format!("{}\n\ndefine void @f(ptr %p, i32 %x, <4 x i32> %v) {{\nentry:\n  {}\n  ret void\n}}\n", globals, body);
+    let module = module(&ir);
+    lint_module(&module).into_iter().map(|w| (w.kind, w.message)).collect()
+}
+
+#[test]
+fn test_clean_function() {
+    let findings = lint("@g = global i32 0, align 4",
+        "%a = udiv i32 %x, 3\n  %b = shl i32 %x, 31\n  %c = load i32, ptr @g, align 4\n  store i32 %a, ptr %p, align 4");
+    assert!(findings.is_empty(), "{:?}", findings);
+}
+
+#[test]
+fn test_division_by_zero() {
+    let findings = lint("", "%a = sdiv i32 %x, 0");
+    assert_eq!(findings, [(LintKind::DivisionByZero, "division by zero".to_string())]);
+
+    let findings = lint("", "%a = urem <4 x i32> %v, <i32 1, i32 0, i32 1, i32 1>");
+    assert_eq!(findings, [(LintKind::DivisionByZero, "division by zero".to_string())]);
+
+    let findings = lint("", "%a = udiv i32 %x, undef");
+    assert_eq!(findings, [(LintKind::DivisionByZero, "division by undef".to_string())]);
+}
+
+#[test]
+fn test_invalid_pointers() {
+    let findings = lint("", "%a = load i32, ptr null\n  store i32 %x, ptr undef");
+    assert_eq!(findings, [
+        (LintKind::InvalidPointer, "load through a null pointer".to_string()),
+        (LintKind::InvalidPointer, "store through an undef pointer".to_string()),
+    ]);
+
+    // Address space 1 may map memory at zero
+    assert!(lint("", "%a = load i32, ptr addrspace(1) null").is_empty());
+}
+
+#[test]
+fn test_oversized_shift() {
+    let findings = lint("", "%a = lshr i32 %x, 32");
+    assert_eq!(findings, [(LintKind::OversizedShift, "shift amount 32 is not less than the bit width 32".to_string())]);
+
+    let findings = lint("", "%a = shl <4 x i32> %v, <i32 1, i32 2, i32 40, i32 3>");
+    assert_eq!(findings, [(LintKind::OversizedShift, "shift amount 40 is not less than the bit width 32".to_string())]);
+}
+
+#[test]
+fn test_misaligned_address() {
+    let findings = lint("", "%a = load i32, ptr inttoptr (i64 4098 to ptr), align 4");
+    assert_eq!(findings, [(LintKind::MisalignedAddress, "load with align 4 from address 0x1002".to_string())]);
+
+    let findings = lint("@g = global i64 0, align 4", "store i64 1, ptr @g, align 8");
+    assert_eq!(findings, [(LintKind::MisalignedAddress, "store with align 8 from @g, which is aligned to 4".to_string())]);
+}
+
+#[test]
+fn test_write_to_constant() {
+    let findings = lint("@c = constant i32 7", "store i32 %x, ptr @c");
+    assert_eq!(findings, [(LintKind::WriteToConstant, "write to constant global @c".to_string())]);
+
+    let findings = lint("@c = constant [4 x i8] zeroinitializer\ndeclare void @llvm.memset.p0.i64(ptr, i8, i64, i1)",
+        "call void @llvm.memset.p0.i64(ptr @c, i8 0, i64 4, i1 false)");
+    assert_eq!(findings, [(LintKind::WriteToConstant, "write to constant global @c".to_string())]);
+}
+
+#[test]
+fn test_calls() {
+    let findings = lint("declare fastcc void @callee()", "call void @callee()\n  call fastcc void @callee()");
+    assert_eq!(findings, [(LintKind::CallingConventionMismatch,
+        "call with calling convention C to @callee, which uses Fast".to_string())]);
+
+    let findings = lint("", "call void null()");
+    assert_eq!(findings, [(LintKind::InvalidPointer, "call through a null or undef pointer".to_string())]);
+}
+
+#[test]
+fn test_noreturn_returns_and_location() {
+    let ir = "define void @exit() noreturn {\nentry:\n  ret void\n}\n";
+    let mut module = module(ir);
+    let mut pass = LintPass::new();
+    assert!(!pass.run_on_module(&mut module).unwrap());
+    let findings: Vec<String> = pass.warnings.iter().map(|w| w.to_string()).collect();
+    assert_eq!(findings, ["warning[noreturn-returns]: noreturn function @exit returns at \
+        function exit block %entry instruction 0 (Ret)"]);
+}
+
+#[test]
+fn test_noreturn_unreachable_return() {
+    let ir = "declare void @abort() noreturn\n\n\
+define void @exit() noreturn {\nentry:\n  call void @abort()\n  unreachable\ndead:\n  ret void\n}\n";
+    let module = module(ir);
+    let findings = lint_module(&module);
+    assert!(findings.is_empty(), "{:?}", findings);
+}
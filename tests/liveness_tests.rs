@@ -0,0 +1,90 @@
+//! Liveness Tests
+//!
+//! Live-in and live-out sets of parsed functions, phi operands on their
+//! incoming edges, and the live ranges handed to the register allocator.
+
+mod common;
+
+use common::function;
+use llvm_rust::codegen::register_allocator::RegisterAllocator;
+use llvm_rust::liveness::Liveness;
+use llvm_rust::Value;
+
+fn names(values: Vec<Value>) -> Vec<String> {
+    values.iter().map(|value| value.name().unwrap_or_default().to_string()).collect()
+}
+
+const LOOP: &str = "define i32 @f(i32 %n, i32 %k) {
+entry:
+  %start = add i32 %k, 1
+  br label %loop
+loop:
+  %i = phi i32 [ %start, %entry ], [ %next, %loop ]
+  %next = add i32 %i, %k
+  %done = icmp sge i32 %next, %n
+  br i1 %done, label %exit, label %loop
+exit:
+  ret i32 %next
+}
+";
+
+#[test]
+fn test_live_sets_in_loop() {
+    let liveness = Liveness::new(&function(LOOP));
+    assert_eq!(names(liveness.live_in(0)), ["n", "k"]);
+    // %start feeds the phi, so it is live out of the entry block only
+    assert_eq!(names(liveness.live_out(0)), ["n", "k", "start"]);
+    assert_eq!(names(liveness.live_in(1)), ["n", "k"]);
+    // %next is read by the exit block and by the phi along the backedge
+    assert_eq!(names(liveness.live_out(1)), ["n", "k", "next"]);
+    assert_eq!(names(liveness.live_in(2)), ["next"]);
+    assert!(liveness.live_out(2).is_empty());
+}
+
+#[test]
+fn test_phi_operands_on_incoming_edges() {
+    let liveness = Liveness::new(&function("define i32 @f(i1 %c, i32 %a, i32 %b) {
+entry:
+  br i1 %c, label %then, label %else
+then:
+  br label %join
+else:
+  br label %join
+join:
+  %v = phi i32 [ %a, %then ], [ %b, %else ]
+  ret i32 %v
+}
+"));
+    let value = |name: &str| liveness.values().iter().find(|v| v.name() == Some(name)).unwrap().clone();
+    let (a, b) = (value("a"), value("b"));
+
+    assert!(liveness.is_live_out(1, &a) && !liveness.is_live_out(1, &b));
+    assert!(liveness.is_live_out(2, &b) && !liveness.is_live_out(2, &a));
+    assert!(!liveness.is_live_in(3, &a) && !liveness.is_live_in(3, &b));
+    assert_eq!(names(liveness.live_out(0)), ["a", "b"]);
+    assert!(!liveness.is_live_in(0, &value("v")));
+}
+
+#[test]
+fn test_live_ranges_feed_register_allocator() {
+    let liveness = Liveness::new(&function(LOOP));
+    let ranges = liveness.live_ranges();
+    let range = |name: &str| ranges.iter()
+        .find(|range| liveness.values()[range.vreg].name() == Some(name))
+        .unwrap();
+
+    assert_eq!(ranges.len(), liveness.values().len());
+    // Arguments live through the loop; instructions are numbered 0..=6
+    assert_eq!((range("n").start, range("n").end), (0, 5));
+    assert_eq!((range("start").start, range("start").end), (0, 1));
+    assert_eq!((range("next").start, range("next").end), (3, 6));
+    assert_eq!((range("done").start, range("done").end), (4, 5));
+
+    let mut allocator = RegisterAllocator::new(2);
+    allocator.allocate_function(&liveness).expect("allocation succeeds");
+    for vreg in 0..liveness.values().len() {
+        assert!(allocator.get(vreg).is_some() || allocator.is_spilled(vreg));
+    }
+    assert!(allocator.num_spill_slots() > 0);
+    assert_eq!(allocator.new_vreg(), liveness.values().len());
+}
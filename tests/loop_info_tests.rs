@@ -0,0 +1,105 @@
+//! Loop Info Tests
+//!
+//! Loop forests of parsed functions: nesting, latches, preheaders, exits,
+//! canonical forms and irreducible control flow.
+
+mod common;
+
+use common::function;
+use llvm_rust::analysis::LoopInfo;
+
+/// Loop info of `@f(i1 %c)` with body `body`
+fn loop_info(body: &str) -> LoopInfo {
+    let ir = format!("define void @f(i1 %c) {{\n{}\n}}\n", body);
+    LoopInfo::new(&function(&ir))
+}
+
+#[test]
+fn test_simple_loop() {
+    let li = loop_info("entry:\n  br label %header\nheader:\n  br i1 %c, label %body, label %exit\n\
+        body:\n  br label %header\nexit:\n  ret void");
+    assert_eq!(li.num_loops(), 1);
+    let l = &li.loops()[0];
+    assert_eq!(l.header, 1);
+    assert_eq!(l.latch(), Some(2));
+    assert_eq!(li.preheader(l), Some(0));
+    assert_eq!(li.exiting_blocks(l), [1]);
+    assert_eq!(li.exit_blocks(l), [3]);
+    assert!(li.is_loop_simplify_form(l));
+    assert!(!li.is_rotated_form(l));
+    assert!(li.is_loop_header(1));
+    assert_eq!(li.loop_depth(2), 1);
+    assert_eq!(li.loop_depth(3), 0);
+}
+
+#[test]
+fn test_rotated_loop() {
+    let li = loop_info("entry:\n  br label %loop\nloop:\n  br i1 %c, label %loop, label %exit\nexit:\n  ret void");
+    let l = &li.loops()[0];
+    assert_eq!(l.latch(), Some(1));
+    assert!(li.is_loop_simplify_form(l));
+    assert!(li.is_rotated_form(l));
+}
+
+#[test]
+fn test_nested_loops() {
+    let li = loop_info("entry:\n  br label %outer\nouter:\n  br label %inner\n\
+        inner:\n  br i1 %c, label %inner, label %outer.latch\n\
+        outer.latch:\n  br i1 %c, label %outer, label %exit\nexit:\n  ret void");
+    assert_eq!(li.num_loops(), 2);
+    assert_eq!(li.top_level_loops(), [0]);
+    let (outer, inner) = (&li.loops()[0], &li.loops()[1]);
+    assert_eq!(outer.header, 1);
+    assert_eq!(outer.children, [1]);
+    assert_eq!(inner.parent, Some(0));
+    assert_eq!(inner.depth, 2);
+    assert!(outer.contains(2));
+    assert_eq!(li.loop_depth(2), 2);
+    assert_eq!(li.loop_index_for_block(3), Some(0));
+    assert_eq!(li.exit_blocks(inner), [3]);
+    // The inner header's predecessor outside the loop is the outer header
+    assert_eq!(li.preheader(inner), Some(1));
+}
+
+#[test]
+fn test_back_edges_share_a_loop() {
+    let li = loop_info("entry:\n  br label %header\nheader:\n  br i1 %c, label %a, label %b\n\
+        a:\n  br i1 %c, label %header, label %exit\nb:\n  br label %header\nexit:\n  ret void");
+    assert_eq!(li.num_loops(), 1);
+    let l = &li.loops()[0];
+    assert_eq!(l.latches.len(), 2);
+    assert_eq!(l.latch(), None);
+    assert!(!li.is_loop_simplify_form(l));
+}
+
+#[test]
+fn test_non_canonical_loop() {
+    // The loop predecessor also branches to the exit, which the loop then
+    // does not own
+    let li = loop_info("entry:\n  br i1 %c, label %header, label %exit\nheader:\n  br i1 %c, label %header, label %exit\n\
+        exit:\n  ret void");
+    let l = &li.loops()[0];
+    assert_eq!(li.loop_predecessor(l), Some(0));
+    assert_eq!(li.preheader(l), None);
+    assert!(!li.has_dedicated_exits(l));
+    assert!(!li.is_loop_simplify_form(l));
+}
+
+#[test]
+fn test_irreducible_control_flow() {
+    // The cycle between a and b has two entries
+    let li = loop_info("entry:\n  br i1 %c, label %a, label %b\na:\n  br i1 %c, label %b, label %exit\n\
+        b:\n  br label %a\nexit:\n  ret void");
+    assert!(li.has_irreducible_control_flow());
+    assert_eq!(li.num_loops(), 0);
+
+    // Inside a natural loop, the irreducible blocks belong to it
+    let li = loop_info("entry:\n  br label %header\nheader:\n  br i1 %c, label %a, label %b\n\
+        a:\n  br i1 %c, label %b, label %latch\nb:\n  br label %a\n\
+        latch:\n  br i1 %c, label %header, label %exit\nexit:\n  ret void");
+    assert!(li.has_irreducible_control_flow());
+    assert_eq!(li.num_loops(), 1);
+    assert_eq!(li.loop_depth(2), 1);
+    assert_eq!(li.loop_depth(3), 1);
+    assert_eq!(li.cfg().find_loops().len(), 1);
+}
@@ -0,0 +1,154 @@
+//! Memory SSA Tests
+//!
+//! Defs, uses and phis of parsed functions, the clobber walker, the
+//! annotated printout and updates after the IR changes.
+
+mod common;
+
+use common::{function, module};
+use llvm_rust::memory_ssa::{MemoryAccess, MemorySSA, MemorySSAUpdater, LIVE_ON_ENTRY};
+
+const DIAMOND: &str = "define i32 @f(i1 %c) {
+entry:
+  %p = alloca i32
+  %q = alloca i32
+  store i32 1, ptr %p
+  br i1 %c, label %then, label %join
+then:
+  store i32 2, ptr %q
+  br label %join
+join:
+  %v = load i32, ptr %p
+  ret i32 %v
+}
+";
+
+#[test]
+fn test_defs_uses_and_phis() {
+    let mssa = MemorySSA::new(&function(DIAMOND));
+    let store_p = mssa.access_for_instruction(0, 2).expect("store has an access");
+    let store_q = mssa.access_for_instruction(1, 0).expect("store has an access");
+    let load = mssa.access_for_instruction(2, 0).expect("load has an access");
+    let phi = mssa.phi(2).expect("join merges two states");
+
+    assert_eq!(mssa.access(store_p), Some(&MemoryAccess::Def { block: 0, defining: LIVE_ON_ENTRY }));
+    assert_eq!(mssa.access(store_q), Some(&MemoryAccess::Def { block: 1, defining: store_p }));
+    assert_eq!(mssa.access(load), Some(&MemoryAccess::Use { block: 2, defining: phi }));
+    assert_eq!(mssa.access(phi), Some(&MemoryAccess::Phi { block: 2, incoming: vec![(0, store_p), (1, store_q)] }));
+    assert_eq!(mssa.access_for_instruction(0, 0), None);
+    assert_eq!(mssa.users(store_p), [store_q, phi]);
+
+    // The store to %q cannot write %p on either path
+    assert_eq!(mssa.clobbering_access(load), store_p);
+    assert_eq!(mssa.clobbering_access(store_q), LIVE_ON_ENTRY);
+}
+
+#[test]
+fn test_loop_clobbers() {
+    let f = function("define i32 @f(ptr noalias %a, ptr noalias %b, i32 %n) {
+entry:
+  store i32 0, ptr %a
+  br label %loop
+loop:
+  %i = phi i32 [ 0, %entry ], [ %next, %loop ]
+  %x = load i32, ptr %a
+  store i32 %x, ptr %b
+  %next = add i32 %i, 1
+  %done = icmp eq i32 %next, %n
+  br i1 %done, label %exit, label %loop
+exit:
+  call void @g()
+  %y = load i32, ptr %a
+  ret i32 %y
+}
+
+declare void @g()
+");
+    let mssa = MemorySSA::new(&f);
+    let store_a = mssa.access_for_instruction(0, 0).unwrap();
+    let load_x = mssa.access_for_instruction(1, 1).unwrap();
+    let call = mssa.access_for_instruction(2, 0).unwrap();
+    let load_y = mssa.access_for_instruction(2, 1).unwrap();
+    let phi = mssa.phi(1).expect("the loop header merges states");
+
+    assert_eq!(mssa.access(load_x).and_then(MemoryAccess::defining), Some(phi));
+    // Around the loop only the store to %b, which cannot write %a
+    assert_eq!(mssa.clobbering_access(load_x), store_a);
+    // Calls of unknown functions may write anything
+    assert_eq!(mssa.clobbering_access(load_y), call);
+}
+
+#[test]
+fn test_readonly_calls_in_module() {
+    let m = module("declare i32 @get() readonly
+declare void @log() readnone
+declare void @set()
+
+define void @f() {
+entry:
+  %v = call i32 @get()
+  call void @log()
+  call void @set()
+  ret void
+}
+");
+    let mssa = MemorySSA::in_module(&m.get_function("f").unwrap(), &m);
+    assert!(matches!(mssa.access(mssa.access_for_instruction(0, 0).unwrap()), Some(MemoryAccess::Use { .. })));
+    assert_eq!(mssa.access_for_instruction(0, 1), None);
+    assert!(matches!(mssa.access(mssa.access_for_instruction(0, 2).unwrap()), Some(MemoryAccess::Def { .. })));
+
+    // On its own, every call may write memory
+    let mssa = MemorySSA::new(&m.get_function("f").unwrap());
+    assert_eq!(mssa.block_accesses(0).len(), 3);
+}
+
+#[test]
+fn test_annotated_printout() {
+    let printed = MemorySSA::new(&function(DIAMOND)).to_string();
+    let lines: Vec<&str> = printed.lines().collect();
+    let annotation = |prefix: &str| lines.iter().position(|line| line.starts_with(prefix)).expect(prefix);
+
+    assert!(lines.contains(&"; 1 = MemoryDef(liveOnEntry)"));
+    assert!(lines.contains(&"; 2 = MemoryDef(1)"));
+    assert!(lines.contains(&"; 4 = MemoryPhi({entry,1},{then,2})"));
+    // Each annotation comes right before its instruction
+    assert!(lines[annotation("; MemoryUse(4)") + 1].contains("Load"));
+    assert!(annotation("join:") < annotation("; 4 = MemoryPhi"));
+}
+
+#[test]
+fn test_updater() {
+    let f = function(DIAMOND);
+    let mut mssa = MemorySSA::new(&f);
+    let store_q = mssa.access_for_instruction(1, 0).unwrap();
+    let load = mssa.access_for_instruction(2, 0).unwrap();
+    let blocks = f.basic_blocks();
+
+    // Removing the only def in %then leaves nothing to merge at %join
+    blocks[1].transform_instructions(|instructions| {
+        instructions.remove(0);
+    });
+    MemorySSAUpdater::new(&mut mssa).remove_access(store_q);
+    assert_eq!(mssa.access(store_q), None);
+    assert_eq!(mssa.phi(2), None);
+    assert_eq!(mssa.access(load).and_then(MemoryAccess::defining), mssa.access_for_instruction(0, 2));
+
+    // Hoisting the load into the entry block, after the store to %p
+    let store_p = mssa.access_for_instruction(0, 2).unwrap();
+    let inst = blocks[2].instructions()[0].clone();
+    blocks[2].transform_instructions(|instructions| {
+        instructions.remove(0);
+    });
+    blocks[0].transform_instructions(|instructions| instructions.insert(3, inst.clone()));
+    MemorySSAUpdater::new(&mut mssa).move_access(load, 0, Some(store_p));
+    assert_eq!(mssa.block_accesses(0), [store_p, load]);
+    assert_eq!(mssa.access_for_instruction(0, 3), Some(load));
+
+    // A new store in %then needs a phi again
+    let store = blocks[0].instructions()[2].clone();
+    blocks[1].transform_instructions(|instructions| instructions.insert(0, store.clone()));
+    let new_store = MemorySSAUpdater::new(&mut mssa).insert_access(store, 1, None).expect("stores are defs");
+    let phi = mssa.phi(2).expect("join merges two states again");
+    assert_eq!(mssa.access(phi), Some(&MemoryAccess::Phi { block: 2, incoming: vec![(0, store_p), (1, new_store)] }));
+    assert_eq!(mssa.access(load).and_then(MemoryAccess::defining), Some(store_p));
+}
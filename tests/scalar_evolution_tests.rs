@@ -0,0 +1,146 @@
+//! Scalar Evolution Tests
+//!
+//! Add-recurrences, backedge-taken counts and ranges of parsed loops.
+
+mod common;
+
+use common::function;
+use llvm_rust::function::Function;
+use llvm_rust::scalar_evolution::ScalarEvolution;
+use llvm_rust::value::Value;
+
+/// `@f` of a parsed module
+
+/// The value a function defines under `name`
+fn value(function: &Function, name: &str) -> Value {
+    function.basic_blocks().iter()
+        .flat_map(|bb| bb.instructions())
+        .find_map(|inst| inst.result().filter(|result| result.name() == Some(name)).cloned())
+        .expect("test IR defines the value")
+}
+
+/// A loop counting `%i` from `start` by `step` while `%i <pred> bound`,
+/// with the exit test at the top
+fn counting_loop(pred: &str, start: &str, step: &str, bound: &str) -> Function {
+    function(&format!("define void @f(i32 %n) {{
+entry:
+  br label %header
+header:
+  %i = phi i32 [ {start}, %entry ], [ %next, %body ]
+  %cmp = icmp {pred} i32 %i, {bound}
+  br i1 %cmp, label %body, label %exit
+body:
+  %next = add i32 %i, {step}
+  br label %header
+exit:
+  ret void
+}}
+"))
+}
+
+#[test]
+fn test_induction_variable() {
+    let f = counting_loop("slt", "0", "1", "10");
+    let se = ScalarEvolution::new(&f);
+    assert_eq!(se.scev(&value(&f, "i")).to_string(), "{0,+,1}<loop 0>");
+    assert_eq!(se.scev(&value(&f, "next")).to_string(), "{1,+,1}<loop 0>");
+
+    let count = se.backedge_taken_count(0);
+    assert_eq!(count.exact.map(|count| count.to_string()).as_deref(), Some("10"));
+    assert_eq!(count.max, Some(10));
+    assert_eq!(se.constant_trip_count(0), Some(11));
+
+    let range = se.unsigned_range(&se.scev(&value(&f, "i")));
+    assert_eq!((range.unsigned_min(), range.unsigned_max()), (0, 10));
+}
+
+#[test]
+fn test_symbolic_count() {
+    let f = counting_loop("slt", "0", "1", "%n");
+    let se = ScalarEvolution::new(&f);
+    let count = se.backedge_taken_count(0);
+    assert_eq!(count.exact.map(|count| count.to_string()).as_deref(), Some("(0 smax %n)"));
+    assert_eq!(count.max, Some(i32::MAX as u128));
+    assert_eq!(se.constant_trip_count(0), None);
+
+    let range = se.signed_range(&se.scev(&value(&f, "i")));
+    assert_eq!((range.signed_min(), range.signed_max()), (0, i32::MAX as i128));
+}
+
+#[test]
+fn test_strided_and_down_counting_loops() {
+    // 0, 3, 6, 9 pass the test
+    let f = counting_loop("ult", "0", "3", "10");
+    let se = ScalarEvolution::new(&f);
+    assert_eq!(se.backedge_taken_count(0).exact.and_then(|count| count.as_constant()), Some(4));
+
+    // 10, 8, 6, 4, 2 pass the test
+    let f = counting_loop("sgt", "10", "-2", "0");
+    let se = ScalarEvolution::new(&f);
+    assert_eq!(se.scev(&value(&f, "i")).to_string(), "{10,+,-2}<loop 0>");
+    assert_eq!(se.backedge_taken_count(0).exact.and_then(|count| count.as_constant()), Some(5));
+
+    let f = counting_loop("ne", "%n", "-1", "0");
+    let se = ScalarEvolution::new(&f);
+    assert_eq!(se.backedge_taken_count(0).exact.map(|count| count.to_string()).as_deref(), Some("%n"));
+
+    // An inclusive bound of the maximum value never fails
+    let f = counting_loop("sle", "0", "1", "2147483647");
+    let se = ScalarEvolution::new(&f);
+    assert_eq!(se.backedge_taken_count(0), Default::default());
+}
+
+#[test]
+fn test_rotated_loop_with_inverted_exit() {
+    let f = function("define i32 @f(i32 %a, i32 %n) {
+entry:
+  br label %loop
+loop:
+  %i = phi i32 [ %a, %entry ], [ %next, %loop ]
+  %sum = phi i32 [ 0, %entry ], [ %acc, %loop ]
+  %twice = shl i32 %i, 1
+  %acc = add i32 %sum, %twice
+  %next = add i32 %i, 1
+  %done = icmp uge i32 %next, 100
+  br i1 %done, label %exit, label %loop
+exit:
+  ret i32 %acc
+}
+");
+    let se = ScalarEvolution::new(&f);
+    assert_eq!(se.scev(&value(&f, "twice")).to_string(), "{(2 * %a),+,2}<loop 0>");
+    // A sum of a recurrence is not affine
+    assert_eq!(se.scev(&value(&f, "sum")).to_string(), "%sum");
+    let count = se.backedge_taken_count(0);
+    assert_eq!(count.exact.map(|count| count.to_string()).as_deref(), Some("(-1 + (-1 * %a) + (100 umax (1 + %a)))"));
+}
+
+#[test]
+fn test_nested_loops() {
+    let f = function("define void @f(i32 %n) {
+entry:
+  br label %outer
+outer:
+  %i = phi i32 [ 0, %entry ], [ %i.next, %outer.latch ]
+  br label %inner
+inner:
+  %j = phi i32 [ %i, %outer ], [ %j.next, %inner ]
+  %j.next = add i32 %j, 1
+  %inner.cmp = icmp ne i32 %j.next, 8
+  br i1 %inner.cmp, label %inner, label %outer.latch
+outer.latch:
+  %i.next = add i32 %i, 1
+  %outer.cmp = icmp ult i32 %i.next, 4
+  br i1 %outer.cmp, label %outer, label %exit
+exit:
+  ret void
+}
+");
+    let se = ScalarEvolution::new(&f);
+    assert_eq!(se.scev(&value(&f, "j")).to_string(), "{{0,+,1}<loop 0>,+,1}<loop 1>");
+    assert_eq!(se.backedge_taken_count(0).exact.and_then(|count| count.as_constant()), Some(3));
+    // 7 minus the outer count is not constant, but bounded
+    let inner = se.backedge_taken_count(1);
+    assert_eq!(inner.exact.map(|count| count.to_string()).as_deref(), Some("{7,+,-1}<loop 0>"));
+    assert_eq!(inner.max, Some(7));
+}
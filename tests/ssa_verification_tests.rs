@@ -0,0 +1,221 @@
+//! SSA Verification Tests
+//!
+//! Dominance of uses by definitions, phi incoming edges, self references,
+//! redefinitions and unreachable code.
+
+mod common;
+
+use common::module;
+use llvm_rust::verification::{verify_function, verify_module, VerificationError};
+use llvm_rust::{BasicBlock, Builder, Context, Function, Value};
+
+/// InvalidSSA errors as (value, reason, definition, use location)
+fn ssa_errors(ir: &str) -> Vec<(String, String, String, String)> {
+    // `parse` would verify and reject the module before we can inspect the errors
+    let module = module(ir);
+    match verify_module(&module) {
+        Ok(()) => Vec::new(),
+        Err(errors) => errors.into_iter().filter_map(|e| match e {
+            VerificationError::InvalidSSA { value, reason, definition, location } => Some((value, reason, definition, location)),
+            _ => None,
+        }).collect(),
+    }
+}
+
+#[test]
+fn test_valid_ssa() {
+    let errors = ssa_errors(r#"
+        define i32 @f(i32 %n, i1 %c) {
+        entry:
+          %a = add i32 %n, 1
+          br i1 %c, label %then, label %join
+
+        then:
+          %b = mul i32 %a, 2
+          br label %join
+
+        join:
+          %p = phi i32 [ %a, %entry ], [ %b, %then ]
+          br label %loop
+
+        loop:
+          %i = phi i32 [ 0, %join ], [ %next, %loop ]
+          %next = add i32 %i, %p
+          %done = icmp sgt i32 %next, 100
+          br i1 %done, label %exit, label %loop
+
+        exit:
+          ret i32 %next
+        }
+    "#);
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn test_use_not_dominated_by_definition() {
+    let errors = ssa_errors(r#"
+        define i32 @f(i1 %c) {
+        entry:
+          br i1 %c, label %then, label %join
+
+        then:
+          %b = add i32 1, 2
+          br label %join
+
+        join:
+          ret i32 %b
+        }
+    "#);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    let (value, reason, definition, location) = &errors[0];
+    assert_eq!(value, "b");
+    assert_eq!(reason, "Instruction does not dominate all uses");
+    assert_eq!(definition, "instruction 0 (Add) in block %then");
+    assert_eq!(location, "instruction 0 (Ret) in block %join");
+}
+
+#[test]
+fn test_use_before_definition_in_block() {
+    let errors = ssa_errors(r#"
+        define i32 @f() {
+        entry:
+          %a = add i32 %b, 1
+          %b = add i32 2, 3
+          ret i32 %a
+        }
+    "#);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].0, "b");
+    assert_eq!(errors[0].2, "instruction 1 (Add) in block %entry");
+    assert_eq!(errors[0].3, "instruction 0 (Add) in block %entry");
+}
+
+#[test]
+fn test_phi_incoming_value_must_dominate_edge() {
+    let errors = ssa_errors(r#"
+        define i32 @f(i1 %c) {
+        entry:
+          br i1 %c, label %left, label %right
+
+        left:
+          %x = add i32 1, 2
+          br label %join
+
+        right:
+          br label %join
+
+        join:
+          %p = phi i32 [ %x, %left ], [ %x, %right ]
+          ret i32 %p
+        }
+    "#);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].3, "instruction 0 (PHI) in block %join, incoming from block %right");
+}
+
+#[test]
+fn test_self_reference_outside_phi() {
+    let errors = ssa_errors(r#"
+        define i32 @f() {
+        entry:
+          ret i32 0
+
+        dead:
+          %x = add i32 %x, 1
+          br label %dead
+        }
+    "#);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert_eq!(errors[0].1, "Only PHI nodes may reference their own value");
+}
+
+#[test]
+fn test_unreachable_uses_are_not_checked() {
+    let errors = ssa_errors(r#"
+        define i32 @f() {
+        entry:
+          %a = add i32 1, 2
+          ret i32 %a
+
+        dead:
+          %b = add i32 %c, 1
+          br label %other
+
+        other:
+          %c = add i32 %b, 1
+          br label %dead
+        }
+    "#);
+    assert!(errors.is_empty(), "{:?}", errors);
+}
+
+#[test]
+fn test_invoke_result_only_available_on_normal_edge() {
+    let errors = ssa_errors(r#"
+        declare i32 @g()
+
+        define i32 @f() {
+        entry:
+          %r = invoke i32 @g() to label %ok unwind label %lpad
+
+        ok:
+          ret i32 %r
+
+        lpad:
+          ret i32 %r
+        }
+    "#);
+    assert!(errors.iter().any(|e| e.0 == "r" && e.3 == "instruction 0 (Ret) in block %lpad"), "{:?}", errors);
+    assert!(!errors.iter().any(|e| e.3.ends_with("block %ok")), "{:?}", errors);
+}
+
+#[test]
+fn test_multiple_definitions() {
+    // The parser rejects redefinitions itself, so build the function directly
+    let context = Context::new();
+    let i32_type = context.int32_type();
+    let function = Function::new("f".to_string(), context.function_type(i32_type.clone(), vec![i32_type.clone()], false));
+    let n = Value::argument(i32_type.clone(), 0, Some("n".to_string()));
+    function.set_arguments(vec![n.clone()]);
+    let entry = BasicBlock::new(Some("entry".to_string()));
+    function.add_basic_block(entry.clone());
+    let mut builder = Builder::new(context.clone());
+    builder.position_at_end(entry);
+    let one = Value::const_int(i32_type.clone(), 1, None);
+    builder.build_add(n.clone(), one.clone(), Some("x".to_string()));
+    let x = builder.build_add(n, one, Some("x".to_string()));
+    builder.build_ret(x);
+
+    let errors = verify_function(&function).unwrap_err();
+    let error = errors.iter().find(|e| matches!(e, VerificationError::InvalidSSA { .. })).expect("redefinition reported");
+    assert_eq!(error.to_string(), "multiple definition of local value: '%x' at instruction 1 (Add) in block %entry \
+        (defined at instruction 0 (Add) in block %entry)");
+}
+
+#[test]
+fn test_multiple_numbered_definitions() {
+    let context = Context::new();
+    let i32_type = context.int32_type();
+    let i64_type = context.int64_type();
+    let function = Function::new("f".to_string(), context.function_type(i32_type.clone(), vec![i32_type.clone()], false));
+    let n = Value::argument(i32_type.clone(), 0, Some("0".to_string()));
+    function.set_arguments(vec![n.clone()]);
+    let entry = BasicBlock::new(Some("entry".to_string()));
+    function.add_basic_block(entry.clone());
+    let mut builder = Builder::new(context.clone());
+    builder.position_at_end(entry);
+    let one = Value::const_int(i32_type.clone(), 1, None);
+    let x = builder.build_add(n.clone(), one.clone(), Some("1".to_string()));
+    builder.build_add(n, one, Some("1".to_string()));
+    let wide = Value::const_int(i64_type.clone(), 1, None);
+    builder.build_add(wide.clone(), wide, Some("1".to_string()));
+    builder.build_ret(x);
+
+    let errors = verify_function(&function).unwrap_err();
+    let redefinitions: Vec<_> = errors.iter().filter(|e| matches!(e, VerificationError::InvalidSSA { .. })).collect();
+    assert_eq!(redefinitions.len(), 1);
+    assert_eq!(redefinitions[0].to_string(), "multiple definition of local value: '%1' at instruction 1 (Add) in block %entry \
+        (defined at instruction 0 (Add) in block %entry)");
+    // A redefinition with another type is reported as a type mismatch
+    assert!(errors.iter().any(|e| matches!(e, VerificationError::TypeMismatch { location, .. } if location.contains("'%1'"))));
+}
@@ -0,0 +1,167 @@
+//! Tests for the ThinLTO module summary index
+
+use llvm_rust::summary::{
+    build_module_summary, global_value_guid, GlobalValueSummary, Hotness, RefAccess,
+};
+use llvm_rust::{parse, Context};
+
+/// Summary entries as written by `llvm-dis` for a module built with `opt -module-summary`
+const LLVM_SUMMARY: &str = r#"source_filename = "a.ll"
+
+^0 = module: (path: "a.bc", hash: (0, 0, 0, 0, 0))
+^1 = gv: (name: "llvm.type.test") ; guid = 608142985856744218
+^2 = gv: (name: "h", summaries: (function: (module: ^0, flags: (linkage: internal, visibility: default, notEligibleToImport: 0, live: 0, dsoLocal: 1, canAutoHide: 0), insts: 2, funcFlags: (readNone: 0, readOnly: 0, noRecurse: 1, returnDoesNotAlias: 0, noInline: 0, alwaysInline: 0, noUnwind: 0, mayThrow: 0, hasUnknownCall: 0, mustBeUnreachable: 0), refs: (readonly ^8)))) ; guid = 4698780843822753391
+^3 = gv: (name: "ptrs", summaries: (variable: (module: ^0, flags: (linkage: external, visibility: default, notEligibleToImport: 0, live: 0, dsoLocal: 0, canAutoHide: 0), varFlags: (readonly: 1, writeonly: 1, constant: 0), refs: (^7)))) ; guid = 8678353380194400646
+^4 = gv: (name: "al", summaries: (alias: (module: ^0, flags: (linkage: external, visibility: default, notEligibleToImport: 0, live: 0, dsoLocal: 0, canAutoHide: 0), aliasee: ^7))) ; guid = 9678619939319785623
+^5 = gv: (name: "ind", summaries: (function: (module: ^0, flags: (linkage: external, visibility: default, notEligibleToImport: 0, live: 0, dsoLocal: 0, canAutoHide: 0), insts: 4, funcFlags: (readNone: 0, readOnly: 0, noRecurse: 0, returnDoesNotAlias: 0, noInline: 0, alwaysInline: 0, noUnwind: 0, mayThrow: 0, hasUnknownCall: 1, mustBeUnreachable: 0)))) ; guid = 10412197092519032572
+^6 = gv: (name: "g", summaries: (variable: (module: ^0, flags: (linkage: external, visibility: default, notEligibleToImport: 0, live: 0, dsoLocal: 0, canAutoHide: 0), varFlags: (readonly: 1, writeonly: 1, constant: 0)))) ; guid = 13146401226427987378
+^7 = gv: (name: "f", summaries: (function: (module: ^0, flags: (linkage: external, visibility: default, notEligibleToImport: 0, live: 0, dsoLocal: 0, canAutoHide: 0), insts: 4, funcFlags: (readNone: 0, readOnly: 0, noRecurse: 0, returnDoesNotAlias: 0, noInline: 1, alwaysInline: 0, noUnwind: 1, mayThrow: 0, hasUnknownCall: 0, mustBeUnreachable: 0), calls: ((callee: ^9), (callee: ^2)), refs: (readonly ^6)))) ; guid = 14740650423002898831
+^8 = gv: (name: "c", summaries: (variable: (module: ^0, flags: (linkage: internal, visibility: default, notEligibleToImport: 0, live: 0, dsoLocal: 1, canAutoHide: 0), varFlags: (readonly: 1, writeonly: 0, constant: 1)))) ; guid = 15235232185760833453
+^9 = gv: (name: "ext") ; guid = 15493320556550420395
+^10 = blockcount: 3
+"#;
+
+/// The module `LLVM_SUMMARY` was generated from
+const SOURCE: &str = r#"source_filename = "a.ll"
+
+@g = global i32 0
+@c = internal constant i32 5
+@ptrs = global [1 x ptr] [ptr @f]
+@al = alias void (), ptr @f
+
+declare void @ext()
+
+define void @f() noinline nounwind {
+  %v = load i32, ptr @g
+  call void @ext()
+  call void @h(i32 %v)
+  ret void
+}
+
+define internal void @h(i32 %x) norecurse {
+  %l = load i32, ptr @c
+  ret void
+}
+
+define void @ind(ptr %p) {
+  call void %p()
+  %t = call i1 @llvm.type.test(ptr %p, metadata !"_ZTS1A")
+  ret void
+}
+
+declare i1 @llvm.type.test(ptr, metadata)
+"#;
+
+fn summary_lines(text: &str) -> Vec<&str> {
+    text.lines().filter(|line| line.starts_with('^')).collect()
+}
+
+#[test]
+fn test_parse_and_print_llvm_summary() {
+    let module = parse(LLVM_SUMMARY, Context::new()).expect("summary should parse");
+    let index = module.summary_index().expect("module should carry an index");
+
+    assert_eq!(index.modules()[0].path, "a.bc");
+    assert_eq!(index.block_count(), Some(3));
+
+    // Local names are hashed with the source file name
+    let h = index.value_by_name("h").expect("h summary");
+    assert_eq!(h.guid, global_value_guid("a.ll:h"));
+
+    let f = index.function_summary(global_value_guid("f")).expect("f summary");
+    assert_eq!(f.inst_count, 4);
+    assert!(f.function_flags.no_inline && f.function_flags.no_unwind);
+    let callees: Vec<_> = f.calls.iter().map(|call| call.callee).collect();
+    assert_eq!(callees, vec![global_value_guid("ext"), h.guid]);
+    assert_eq!(f.refs[0].guid, global_value_guid("g"));
+    assert_eq!(f.refs[0].access, RefAccess::ReadOnly);
+
+    let printed = module.to_string();
+    assert_eq!(summary_lines(&printed), summary_lines(LLVM_SUMMARY));
+}
+
+#[test]
+fn test_summary_references_must_be_defined() {
+    let source = r#"^0 = module: (path: "a.bc", hash: (0, 0, 0, 0, 0))
+^1 = gv: (name: "f", summaries: (function: (module: ^0, flags: (linkage: external, visibility: default, notEligibleToImport: 0, live: 0, dsoLocal: 0, canAutoHide: 0), insts: 1, calls: ((callee: ^7)))))
+"#;
+    assert!(parse(source, Context::new()).is_err());
+}
+
+#[test]
+fn test_parse_type_id_entries() {
+    let source = r#"^0 = module: (path: "a.bc", hash: (1, 2, 3, 4, 5))
+^1 = gv: (name: "vt", summaries: (variable: (module: ^0, flags: (linkage: external, visibility: default, notEligibleToImport: 0, live: 0, dsoLocal: 0, canAutoHide: 0), varFlags: (readonly: 0, writeonly: 0, constant: 1))))
+^2 = gv: (name: "f", summaries: (function: (module: ^0, flags: (linkage: external, visibility: default, notEligibleToImport: 0, live: 0, dsoLocal: 0, canAutoHide: 0), insts: 3, calls: ((callee: ^1, hotness: hot)), typeIdInfo: (typeTests: (^3)))))
+^3 = typeid: (name: "_ZTS1A", summary: (typeTestRes: (kind: inline, sizeM1BitWidth: 5, alignLog2: 3, sizeM1: 7, bitMask: 0, inlineBits: 170)))
+^4 = typeidCompatibleVTable: (name: "_ZTS1A", summary: ((offset: 16, ^1)))
+^5 = flags: 8
+"#;
+    let module = parse(source, Context::new()).expect("summary should parse");
+    let index = module.summary_index().unwrap();
+
+    assert_eq!(index.modules()[0].hash, [1, 2, 3, 4, 5]);
+    let type_id = index.type_id(global_value_guid("_ZTS1A")).expect("type id summary");
+    assert_eq!(type_id.resolution.inline_bits, 170);
+    assert_eq!(index.compatible_vtables("_ZTS1A").unwrap()[0].offset, 16);
+    assert_eq!(index.flags(), Some(8));
+
+    let f = index.function_summary(global_value_guid("f")).unwrap();
+    assert_eq!(f.calls[0].hotness, Hotness::Hot);
+    assert_eq!(f.type_tests, vec![global_value_guid("_ZTS1A")]);
+
+    // Printing and reparsing yields the same index
+    let reparsed = parse(&module.to_string(), Context::new()).expect("printed summary should parse");
+    assert_eq!(reparsed.summary_index(), Some(index));
+}
+
+#[test]
+fn test_build_module_summary_matches_llvm() {
+    let module = parse(SOURCE, Context::new()).expect("module should parse");
+    let built = build_module_summary(&module);
+    let expected = parse(LLVM_SUMMARY, Context::new()).unwrap().summary_index().unwrap();
+
+    for info in expected.values() {
+        let ours = built.value(info.guid).expect("every LLVM entry should be summarized");
+        assert_eq!(ours.name, info.name);
+        if info.name.as_deref() == Some("ind") {
+            // LLVM's input needed a bitcast that opaque pointers do without
+            let GlobalValueSummary::Function(function) = &ours.summaries[0] else { panic!("ind is a function") };
+            assert_eq!(function.inst_count, 3);
+            assert!(function.function_flags.has_unknown_call);
+            continue;
+        }
+        assert_eq!(ours.summaries, info.summaries, "summary for {:?}", info.name);
+    }
+    assert_eq!(built.block_count(), Some(3));
+}
+
+#[test]
+fn test_build_module_summary_type_tests() {
+    let source = r#"
+define i1 @check(ptr %p) {
+  %t = call i1 @llvm.type.test(ptr %p, metadata !"_ZTS1A")
+  ret i1 %t
+}
+
+define void @assumed(ptr %p) {
+  %t = call i1 @llvm.type.test(ptr %p, metadata !"_ZTS1B")
+  call void @llvm.assume(i1 %t)
+  ret void
+}
+
+declare i1 @llvm.type.test(ptr, metadata)
+declare void @llvm.assume(i1)
+"#;
+    let module = parse(source, Context::new()).expect("module should parse");
+    let index = build_module_summary(&module);
+
+    let check = index.function_summary(global_value_guid("check")).unwrap();
+    assert_eq!(check.type_tests, vec![global_value_guid("_ZTS1A")]);
+    assert!(check.calls.is_empty(), "intrinsics are not call edges");
+
+    // Tests only feeding llvm.assume matter to devirtualization, not lowering
+    let assumed = index.function_summary(global_value_guid("assumed")).unwrap();
+    assert!(assumed.type_tests.is_empty());
+    assert_eq!(assumed.inst_count, 3);
+}
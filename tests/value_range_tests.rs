@@ -0,0 +1,145 @@
+//! Value Range Tests
+//!
+//! Known bits and lazy value ranges of parsed functions, and the
+//! simplifications instcombine and constfold make with them.
+
+mod common;
+
+use common::function;
+use llvm_rust::constant_range::ConstantRange;
+use llvm_rust::instruction::IntPredicate;
+use llvm_rust::lazy_value_info::LazyValueInfo;
+use llvm_rust::passes::FunctionPass;
+use llvm_rust::transforms::{ConstantFoldingPass, InstructionCombiningPass};
+use llvm_rust::value_tracking::{compute_known_bits, KnownBits};
+use llvm_rust::{Function, Value};
+
+/// An argument or instruction result of a function by name
+fn value(f: &Function, name: &str) -> Value {
+    f.arguments().into_iter()
+        .chain(f.basic_blocks().iter().flat_map(|bb| bb.instructions()).filter_map(|inst| inst.result().cloned()))
+        .find(|value| value.name() == Some(name))
+        .expect("function defines the value")
+}
+
+#[test]
+fn test_known_bits() {
+    let f = function("define i32 @f(i8 %b, i32 %x, ptr %p) {
+entry:
+  %z = zext i8 %b to i32
+  %s = shl i32 %z, 4
+  %o = or i32 %s, 3
+  %a = and i32 %x, 240
+  %l = load i32, ptr %p, !range !0
+  ret i32 %o
+}
+
+!0 = !{i32 16, i32 32}
+");
+    let known = |name: &str| compute_known_bits(&value(&f, name), &f);
+    assert_eq!(known("z"), KnownBits { width: 32, zero: 0xFFFF_FF00, one: 0 });
+    assert_eq!(known("s"), KnownBits { width: 32, zero: 0xFFFF_F00F, one: 0 });
+    assert_eq!(known("o"), KnownBits { width: 32, zero: 0xFFFF_F00C, one: 3 });
+    assert_eq!(known("a").zero, 0xFFFF_FF0F);
+    assert_eq!(known("l"), KnownBits { width: 32, zero: 0xFFFF_FFE0, one: 0x10 });
+    assert_eq!(known("x"), KnownBits::unknown(32));
+}
+
+#[test]
+fn test_ranges_from_branches() {
+    let f = function("define i32 @f(i32 %x) {
+entry:
+  %small = icmp ult i32 %x, 10
+  br i1 %small, label %then, label %else
+then:
+  %t = add i32 %x, 1
+  br label %join
+else:
+  br label %join
+join:
+  %v = phi i32 [ %t, %then ], [ 0, %else ]
+  %c = icmp ult i32 %v, 11
+  ret i32 %v
+}
+");
+    let lvi = LazyValueInfo::new(&f);
+    let x = value(&f, "x");
+    assert_eq!(lvi.constant_range_on_entry(&x, 0), Some(ConstantRange::full(32)));
+    assert_eq!(lvi.constant_range_on_entry(&x, 1), Some(ConstantRange::from_unsigned(32, 0, 9)));
+    assert_eq!(lvi.constant_range_on_edge(&x, 0, 2), Some(ConstantRange::from_unsigned(32, 10, u32::MAX as u128)));
+    assert_eq!(lvi.constant_range_on_entry(&value(&f, "v"), 3), Some(ConstantRange::from_unsigned(32, 0, 10)));
+    assert_eq!(lvi.predicate_at(IntPredicate::ULT, &value(&f, "v"), &Value::const_int(x.get_type().clone(), 11, None), 3, 1), Some(true));
+    assert_eq!(lvi.constant(&value(&f, "c"), 3, 2), Some(1));
+}
+
+#[test]
+fn test_ranges_from_switches_and_assumes() {
+    let f = function("define i32 @f(i32 %x, i32 %y) {
+entry:
+  %pos = icmp sgt i32 %y, 0
+  %lim = icmp slt i32 %y, 100
+  %both = and i1 %pos, %lim
+  call void @llvm.assume(i1 %both)
+  switch i32 %x, label %other [ i32 1, label %one
+                                i32 2, label %one ]
+one:
+  ret i32 %x
+other:
+  ret i32 %y
+}
+
+declare void @llvm.assume(i1)
+");
+    let lvi = LazyValueInfo::new(&f);
+    let (x, y) = (value(&f, "x"), value(&f, "y"));
+    assert_eq!(lvi.constant_range(&y, 0, 3), Some(ConstantRange::full(32)));
+    assert_eq!(lvi.constant_range(&y, 0, 4), Some(ConstantRange::from_unsigned(32, 1, 99)));
+    assert_eq!(lvi.constant_range_on_entry(&y, 2), Some(ConstantRange::from_unsigned(32, 1, 99)));
+    assert_eq!(lvi.constant_range_on_entry(&x, 1), Some(ConstantRange::from_unsigned(32, 1, 2)));
+    assert_eq!(lvi.constant_range_on_entry(&x, 2), Some(ConstantRange::full(32)));
+}
+
+#[test]
+fn test_loops_keep_defining_ranges() {
+    let f = function("define i32 @f(i8 %b, i32 %n) {
+entry:
+  %z = zext i8 %b to i32
+  br label %loop
+loop:
+  %i = phi i32 [ 0, %entry ], [ %next, %loop ]
+  %next = add i32 %i, 1
+  %done = icmp eq i32 %next, %n
+  br i1 %done, label %exit, label %loop
+exit:
+  ret i32 %z
+}
+");
+    let lvi = LazyValueInfo::new(&f);
+    assert_eq!(lvi.constant_range_on_entry(&value(&f, "z"), 2), Some(ConstantRange::from_unsigned(32, 0, 255)));
+    assert_eq!(lvi.constant_range_on_entry(&value(&f, "i"), 1), Some(ConstantRange::full(32)));
+}
+
+#[test]
+fn test_passes_simplify_with_ranges() {
+    let masked = "define i32 @f(i8 %b) {
+entry:
+  %z = zext i8 %b to i32
+  %m = and i32 %z, 255
+  ret i32 %m
+}
+";
+    assert!(InstructionCombiningPass.run_on_function(&mut function(masked)).unwrap());
+    let unknown = masked.replace("%z = zext i8 %b to i32", "%z = sext i8 %b to i32");
+    assert!(!InstructionCombiningPass.run_on_function(&mut function(&unknown)).unwrap());
+
+    let guarded = "define i1 @f(i8 %b) {
+entry:
+  %z = zext i8 %b to i32
+  %c = icmp ult i32 %z, 256
+  ret i1 %c
+}
+";
+    assert!(ConstantFoldingPass.run_on_function(&mut function(guarded)).unwrap());
+    let unknown = guarded.replace("256", "200");
+    assert!(!ConstantFoldingPass.run_on_function(&mut function(&unknown)).unwrap());
+}
@@ -0,0 +1,80 @@
+//! Verifier Options Tests
+//!
+//! Rule selection by category and ID, severity overrides and the JSON report.
+
+mod common;
+
+use common::module;
+use llvm_rust::diagnostics::{diagnostics_to_json, Rule, RuleCategory, Severity, VerifierOptions};
+use llvm_rust::verification::{verify_module, verify_module_with_options};
+
+/// A use before its definition, and an alignment that is not a power of two
+const IR: &str = r#"
+    define i32 @f(i32 %x, ptr align 3 %p) {
+    entry:
+      %a = add i32 %b, %x
+      %b = add i32 2, 3
+      ret i32 %a
+    }
+"#;
+
+fn rules(options: &VerifierOptions) -> Vec<(Rule, Severity)> {
+    verify_module_with_options(&module(IR), options).iter().map(|d| (d.rule, d.severity)).collect()
+}
+
+#[test]
+fn test_default_options_match_verify_module() {
+    let module = module(IR);
+    let diagnostics = verify_module_with_options(&module, &VerifierOptions::default());
+    let errors = verify_module(&module).unwrap_err();
+    assert_eq!(diagnostics.len(), errors.len());
+    assert!(diagnostics.iter().all(|d| d.severity == Severity::Error));
+    assert!(diagnostics.iter().any(|d| d.rule == Rule::SsaForm), "{:?}", diagnostics);
+    assert!(diagnostics.iter().any(|d| d.rule.category() == RuleCategory::Attributes), "{:?}", diagnostics);
+}
+
+#[test]
+fn test_disabled_category_is_not_reported() {
+    let mut options = VerifierOptions::default();
+    options.disabled_categories.insert(RuleCategory::Attributes);
+    let found = rules(&options);
+    assert!(found.iter().all(|(rule, _)| rule.category() != RuleCategory::Attributes), "{:?}", found);
+    assert!(found.contains(&(Rule::SsaForm, Severity::Error)));
+
+    options.disabled_rules.insert(Rule::SsaForm);
+    assert!(rules(&options).is_empty());
+}
+
+#[test]
+fn test_warnings_do_not_fail_verification() {
+    let mut options = VerifierOptions::default();
+    options.disabled_categories.insert(RuleCategory::Attributes);
+    options.severities.insert(Rule::SsaForm, Severity::Warning);
+    let diagnostics = verify_module_with_options(&module(IR), &options);
+    assert_eq!(diagnostics.len(), 1);
+    assert!(!diagnostics[0].is_error());
+    assert_eq!(diagnostics[0].to_string(), "warning[ssa-form]: Instruction does not dominate all uses: '%b' \
+        at instruction 0 (Add) in block %entry (defined at instruction 1 (Add) in block %entry)");
+
+    options.warnings_as_errors = true;
+    assert_eq!(rules(&options), vec![(Rule::SsaForm, Severity::Error)]);
+}
+
+#[test]
+fn test_json_report() {
+    let mut options = VerifierOptions::default();
+    options.disabled_categories.insert(RuleCategory::Attributes);
+    options.severities.insert(Rule::SsaForm, Severity::Warning);
+    let json = diagnostics_to_json(&verify_module_with_options(&module(IR), &options));
+    assert_eq!(json, "{\"errors\":0,\"warnings\":1,\"diagnostics\":[{\"rule\":\"ssa-form\",\"category\":\"ssa\",\
+        \"severity\":\"warning\",\"location\":\"instruction 0 (Add) in block %entry\",\"message\":\"Instruction does not \
+        dominate all uses: '%b' at instruction 0 (Add) in block %entry (defined at instruction 1 (Add) in block %entry)\"}]}");
+}
+
+#[test]
+fn test_every_category_has_rules() {
+    for category in RuleCategory::ALL {
+        assert_eq!(RuleCategory::from_name(category.name()), Some(category));
+        assert!(Rule::all().any(|rule| rule.category() == category), "{}", category);
+    }
+}
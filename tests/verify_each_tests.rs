@@ -0,0 +1,92 @@
+//! Verify-Each Tests
+//!
+//! With verify-each, the pass manager re-verifies what a pass reports as
+//! changed and attributes failures to that pass.
+
+mod common;
+
+use common::module;
+use llvm_rust::instruction::{Instruction, Opcode};
+use llvm_rust::passes::{FunctionPass, FunctionPassManager, Pass, PassError, PassManager, PassResult};
+use llvm_rust::transforms::DeadCodeEliminationPass;
+use llvm_rust::Function;
+
+/// Replaces the terminator of `@target`'s entry block with an `add`,
+/// reporting the change only if `report_change` is set
+struct BreakTerminator {
+    target: &'static str,
+    report_change: bool,
+}
+
+impl Pass for BreakTerminator {
+    fn name(&self) -> &str {
+        "break-terminator"
+    }
+}
+
+impl FunctionPass for BreakTerminator {
+    fn run_on_function(&mut self, function: &mut Function) -> PassResult<bool> {
+        if function.name() != self.target {
+            return Ok(false);
+        }
+        let entry = &function.basic_blocks()[0];
+        entry.replace_instruction(entry.instruction_count() - 1, Instruction::new(Opcode::Add, vec![], None));
+        Ok(self.report_change)
+    }
+}
+
+const TWO_FUNCTIONS: &str = "define void @f() {\nentry:\n  ret void\n}\n\n\
+    define void @g() {\nentry:\n  ret void\n}\n";
+
+#[test]
+fn test_failure_names_pass_and_function() {
+    let mut module = module(TWO_FUNCTIONS);
+    let mut pm = PassManager::new();
+    pm.add_function_pass(Box::new(DeadCodeEliminationPass));
+    pm.add_function_pass(Box::new(BreakTerminator { target: "g", report_change: true }));
+    pm.set_verify_each(true);
+
+    match pm.run(&mut module) {
+        Err(PassError::VerificationFailed { pass, function, errors }) => {
+            assert_eq!(pass, "break-terminator");
+            assert_eq!(function.as_deref(), Some("g"));
+            assert!(!errors.is_empty());
+        }
+        other => panic!("expected a verification failure, got {:?}", other),
+    }
+}
+
+#[test]
+fn test_only_changed_functions_are_verified() {
+    // The pass breaks @g without reporting it, so only @f is re-verified
+    let mut module = module(TWO_FUNCTIONS);
+    let mut pm = PassManager::new();
+    pm.add_function_pass(Box::new(BreakTerminator { target: "g", report_change: false }));
+    pm.set_verify_each(true);
+    assert!(pm.run(&mut module).is_ok());
+}
+
+#[test]
+fn test_verify_each_is_off_by_default() {
+    let mut module = module(TWO_FUNCTIONS);
+    let mut pm = PassManager::new();
+    pm.add_function_pass(Box::new(BreakTerminator { target: "f", report_change: true }));
+    assert!(pm.run(&mut module).is_ok());
+}
+
+#[test]
+fn test_function_pass_manager() {
+    let module = module(TWO_FUNCTIONS);
+    let mut function = module.get_function("f").unwrap();
+    let mut fpm = FunctionPassManager::new();
+    fpm.add_pass(Box::new(BreakTerminator { target: "f", report_change: true }));
+    fpm.set_verify_each(true);
+
+    match fpm.run(&mut function) {
+        Err(PassError::VerificationFailed { pass, function, .. }) => {
+            assert_eq!(pass, "break-terminator");
+            assert_eq!(function.as_deref(), Some("f"));
+        }
+        other => panic!("expected a verification failure, got {:?}", other),
+    }
+}